@@ -0,0 +1,204 @@
+//! Per-user audio processing for transcodes.
+//!
+//! Loudness normalization, dialogue boost and night mode are stored as user
+//! preferences and can be overridden per request. Normalization is accurate
+//! only with a measured loudness profile, which is expensive to compute, so
+//! the first request for an unmeasured stream falls back to single-pass
+//! `loudnorm` and queues an analysis whose result is cached per media item.
+
+use crate::state::AppState;
+use dashmap::{DashMap, DashSet};
+use ferrite_db::media_repo::MediaItemRow;
+use ferrite_db::{loudness_repo, preference_repo, stream_repo};
+use ferrite_transcode::audio::{self, AudioProcessing, LoudnessStats};
+use std::path::PathBuf;
+use std::sync::LazyLock;
+use tokio::sync::Semaphore;
+use tracing::{info, warn};
+
+/// User preference key enabling loudness normalization.
+pub const NORMALIZE_PREFERENCE: &str = "audio_normalize";
+/// User preference key enabling the dialogue-boost downmix.
+pub const DIALOGUE_BOOST_PREFERENCE: &str = "audio_dialogue_boost";
+/// User preference key enabling dynamic range compression.
+pub const NIGHT_MODE_PREFERENCE: &str = "audio_night_mode";
+
+/// Streams with an analysis queued or running, keyed `media_id:audio_index`.
+static ANALYSES_IN_FLIGHT: LazyLock<DashSet<String>> = LazyLock::new(DashSet::new);
+
+/// Streams whose analysis failed or timed out, keyed like
+/// [`ANALYSES_IN_FLIGHT`], with the file size they failed for. They aren't
+/// retried until the file changes or the server restarts.
+static ANALYSES_FAILED: LazyLock<DashMap<String, i64>> = LazyLock::new(DashMap::new);
+
+/// Analyses decode the whole audio stream; run one at a time.
+static ANALYSIS_SLOTS: LazyLock<Semaphore> = LazyLock::new(|| Semaphore::new(1));
+
+/// Per-request overrides; `None` falls back to the user's preference.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ProcessingOverrides {
+    pub normalize: Option<bool>,
+    pub dialogue_boost: Option<bool>,
+    pub night_mode: Option<bool>,
+}
+
+/// The audio processing to apply for `user_id`, with request overrides
+/// taking precedence over stored preferences.
+pub(crate) async fn resolve_processing(
+    state: &AppState,
+    user_id: Option<&str>,
+    overrides: ProcessingOverrides,
+) -> AudioProcessing {
+    let pref = |key: &'static str| async move {
+        let user_id = user_id?;
+        match preference_repo::get_preference(&state.db.read, user_id, key).await {
+            Ok(value) => value.map(|v| parse_flag(&v)),
+            Err(e) => {
+                warn!("Failed to read preference {} for {}: {}", key, user_id, e);
+                None
+            }
+        }
+    };
+    let normalize = match overrides.normalize {
+        Some(v) => v,
+        None => pref(NORMALIZE_PREFERENCE).await.unwrap_or(false),
+    };
+    let dialogue_boost = match overrides.dialogue_boost {
+        Some(v) => v,
+        None => pref(DIALOGUE_BOOST_PREFERENCE).await.unwrap_or(false),
+    };
+    let night_mode = match overrides.night_mode {
+        Some(v) => v,
+        None => pref(NIGHT_MODE_PREFERENCE).await.unwrap_or(false),
+    };
+    AudioProcessing {
+        normalize,
+        dialogue_boost,
+        night_mode,
+    }
+}
+
+fn parse_flag(value: &str) -> bool {
+    matches!(
+        value.trim().to_ascii_lowercase().as_str(),
+        "1" | "true" | "yes" | "on"
+    )
+}
+
+/// The FFmpeg `-af` chain for `processing` on one audio stream of `item`, or
+/// `None` when no processing is requested. `input` is what FFmpeg reads; the
+/// loudness of stacked (concat) inputs isn't analyzed.
+pub(crate) async fn audio_filter(
+    state: &AppState,
+    item: &MediaItemRow,
+    input: &std::path::Path,
+    stacked: bool,
+    audio_stream: Option<u32>,
+    processing: AudioProcessing,
+) -> Option<String> {
+    if !processing.is_active() {
+        return None;
+    }
+    let index = audio_stream.unwrap_or(0);
+    let channels = stream_repo::get_audio_channels(&state.db.read, &item.id, index)
+        .await
+        .unwrap_or(None);
+    let measured = if processing.normalize && !stacked {
+        cached_loudness(state, item, input, index).await
+    } else {
+        None
+    };
+    audio::filter_chain(&processing, channels, measured.as_ref())
+}
+
+/// The cached analysis of an audio stream. On a miss, queues one in the
+/// background and returns `None` so this request uses dynamic normalization.
+async fn cached_loudness(
+    state: &AppState,
+    item: &MediaItemRow,
+    input: &std::path::Path,
+    audio_index: u32,
+) -> Option<LoudnessStats> {
+    match loudness_repo::get_loudness(&state.db.read, &item.id, audio_index as i64, item.file_size)
+        .await
+    {
+        Ok(Some(row)) => {
+            return Some(LoudnessStats {
+                input_i: row.input_i,
+                input_tp: row.input_tp,
+                input_lra: row.input_lra,
+                input_thresh: row.input_thresh,
+                target_offset: row.target_offset,
+            })
+        }
+        Ok(None) => {}
+        Err(e) => {
+            warn!("Failed to load loudness for {}: {}", item.id, e);
+            return None;
+        }
+    }
+
+    let key = format!("{}:{}", item.id, audio_index);
+    if ANALYSES_FAILED
+        .get(&key)
+        .is_some_and(|size| *size == item.file_size)
+    {
+        return None;
+    }
+    if !ANALYSES_IN_FLIGHT.insert(key.clone()) {
+        return None;
+    }
+    let db = state.db.clone();
+    let ffmpeg_path = state.config.transcode.ffmpeg_path.clone();
+    let media_id = item.id.clone();
+    let file_size = item.file_size;
+    let input: PathBuf = input.to_path_buf();
+    let timeout = audio::analysis_timeout(item.duration_ms);
+    tokio::spawn(async move {
+        let _slot = ANALYSIS_SLOTS.acquire().await;
+        match audio::analyze_loudness(&ffmpeg_path, &input, Some(audio_index), timeout).await {
+            Ok(stats) => {
+                info!(
+                    "Measured loudness of {} audio stream {}: {:.1} LUFS",
+                    media_id, audio_index, stats.input_i
+                );
+                if let Err(e) = loudness_repo::save_loudness(
+                    &db.write,
+                    &media_id,
+                    audio_index as i64,
+                    stats.input_i,
+                    stats.input_tp,
+                    stats.input_lra,
+                    stats.input_thresh,
+                    stats.target_offset,
+                    file_size,
+                )
+                .await
+                {
+                    warn!("Failed to save loudness for {}: {}", media_id, e);
+                }
+            }
+            Err(e) => {
+                warn!("Loudness analysis of {} failed: {}", media_id, e);
+                ANALYSES_FAILED.insert(key.clone(), file_size);
+            }
+        }
+        ANALYSES_IN_FLIGHT.remove(&key);
+    });
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn preference_flags_accept_common_truthy_values() {
+        for v in ["1", "true", "TRUE", " yes ", "on"] {
+            assert!(parse_flag(v), "{v}");
+        }
+        for v in ["0", "false", "", "off", "nope"] {
+            assert!(!parse_flag(v), "{v}");
+        }
+    }
+}
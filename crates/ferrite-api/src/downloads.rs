@@ -0,0 +1,350 @@
+use dashmap::DashMap;
+use ferrite_core::config::AppConfig;
+use ferrite_db::download_repo::{self, DownloadRow};
+use ferrite_db::{media_repo, stream_repo, subtitle_repo, Database};
+use ferrite_stream::download::{self, DownloadOutcome, DownloadSource, DownloadSubtitle};
+use ferrite_stream::stack;
+use ferrite_transcode::hwaccel::EncoderProfile;
+use ferrite_transcode::variants::select_variants_for;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::{Notify, Semaphore};
+use tracing::{debug, info, warn};
+
+/// How often expired downloads are purged and the cache quota enforced.
+const MAINTENANCE_INTERVAL: Duration = Duration::from_secs(300);
+
+/// Minimum interval between progress writes to the database.
+const PROGRESS_WRITE_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Background worker that turns queued download rows into finished MP4 files
+/// in the download cache, and keeps that cache within its quota and expiry.
+pub struct DownloadManager {
+    db: Database,
+    config: Arc<AppConfig>,
+    encoder: Arc<EncoderProfile>,
+    /// Wakes the worker loop when a download is queued or a transcode finishes.
+    wake: Notify,
+    /// Cancellation handles for transcodes currently running, keyed by download ID.
+    active: DashMap<String, Arc<Notify>>,
+}
+
+impl DownloadManager {
+    pub fn new(db: Database, config: Arc<AppConfig>, encoder: Arc<EncoderProfile>) -> Self {
+        Self {
+            db,
+            config,
+            encoder,
+            wake: Notify::new(),
+            active: DashMap::new(),
+        }
+    }
+
+    /// Final on-disk location of a finished download.
+    pub fn output_path(&self, id: &str) -> PathBuf {
+        self.config.downloads.cache_dir.join(format!("{id}.mp4"))
+    }
+
+    fn partial_path(&self, id: &str) -> PathBuf {
+        self.config
+            .downloads
+            .cache_dir
+            .join(format!("{id}.mp4.part"))
+    }
+
+    /// Signal the worker that new work may be available.
+    pub fn notify_queued(&self) {
+        self.wake.notify_one();
+    }
+
+    /// Make room for a new download of `estimated_bytes`, evicting the least
+    /// recently accessed finished downloads if necessary.
+    /// Returns `false` if the request can't fit in the quota even after eviction.
+    pub async fn reserve_space(&self, estimated_bytes: u64) -> anyhow::Result<bool> {
+        let quota = self.config.downloads.quota_bytes();
+        if estimated_bytes > quota {
+            return Ok(false);
+        }
+        let committed = download_repo::committed_bytes(&self.db.read).await?;
+        if committed + estimated_bytes <= quota {
+            return Ok(true);
+        }
+        self.evict_until(quota - estimated_bytes).await?;
+        let committed = download_repo::committed_bytes(&self.db.read).await?;
+        Ok(committed + estimated_bytes <= quota)
+    }
+
+    /// Cancel a running transcode (if any) and delete the download's file and row.
+    pub async fn remove(&self, row: &DownloadRow) -> anyhow::Result<()> {
+        if let Some((_, cancel)) = self.active.remove(&row.id) {
+            cancel.notify_one();
+        }
+        download_repo::delete_download(&self.db.write, &row.id).await?;
+        self.remove_files(&row.id).await;
+        Ok(())
+    }
+
+    async fn remove_files(&self, id: &str) {
+        for path in [self.output_path(id), self.partial_path(id)] {
+            match tokio::fs::remove_file(&path).await {
+                Ok(()) => debug!("Removed download file {}", path.display()),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => warn!("Failed to remove download file {}: {}", path.display(), e),
+            }
+        }
+    }
+
+    /// Worker loop: runs up to `max_concurrent` transcodes at a time and
+    /// periodically purges expired downloads. Never returns.
+    pub async fn run(self: Arc<Self>) {
+        match download_repo::requeue_interrupted(&self.db.write).await {
+            Ok(0) => {}
+            Ok(n) => info!("Re-queued {} interrupted download(s)", n),
+            Err(e) => warn!("Failed to re-queue interrupted downloads: {}", e),
+        }
+        self.remove_stale_partials().await;
+
+        let slots = Arc::new(Semaphore::new(self.config.downloads.max_concurrent.max(1)));
+        let mut last_maintenance: Option<Instant> = None;
+
+        loop {
+            if last_maintenance.is_none_or(|t| t.elapsed() >= MAINTENANCE_INTERVAL) {
+                self.maintenance().await;
+                last_maintenance = Some(Instant::now());
+            }
+
+            while let Ok(permit) = slots.clone().try_acquire_owned() {
+                match download_repo::claim_next_queued(&self.db.write).await {
+                    Ok(Some(row)) => {
+                        let manager = self.clone();
+                        tokio::spawn(async move {
+                            manager.process(row).await;
+                            drop(permit);
+                            manager.wake.notify_one();
+                        });
+                    }
+                    Ok(None) => break,
+                    Err(e) => {
+                        warn!("Failed to claim queued download: {}", e);
+                        break;
+                    }
+                }
+            }
+
+            tokio::select! {
+                _ = self.wake.notified() => {}
+                _ = tokio::time::sleep(MAINTENANCE_INTERVAL) => {}
+            }
+        }
+    }
+
+    async fn process(&self, row: DownloadRow) {
+        let cancel = Arc::new(Notify::new());
+        self.active.insert(row.id.clone(), cancel.clone());
+
+        let result = self.transcode(&row, &cancel).await;
+        self.active.remove(&row.id);
+
+        match result {
+            Ok(DownloadOutcome::Completed) => {
+                // The row may have been deleted while the final rename was in flight.
+                if matches!(
+                    download_repo::get_download(&self.db.read, &row.id).await,
+                    Ok(None)
+                ) {
+                    self.remove_files(&row.id).await;
+                }
+            }
+            Ok(DownloadOutcome::Cancelled) => {
+                self.remove_files(&row.id).await;
+            }
+            Err(e) => {
+                warn!("Download {} failed: {:#}", row.id, e);
+                self.remove_files(&row.id).await;
+                if let Err(e) =
+                    download_repo::mark_failed(&self.db.write, &row.id, &format!("{e:#}")).await
+                {
+                    warn!("Failed to mark download {} as failed: {}", row.id, e);
+                }
+            }
+        }
+    }
+
+    async fn transcode(
+        &self,
+        row: &DownloadRow,
+        cancel: &Notify,
+    ) -> anyhow::Result<DownloadOutcome> {
+        let item = media_repo::get_media_item(&self.db.read, &row.media_id)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("media item {} no longer exists", row.media_id))?;
+
+        let encoding =
+            crate::encoding::for_library(&self.db.read, &self.config.transcode, &item.library_id)
+                .await;
+        let variant = select_variants_for(
+            &encoding,
+            item.width.map(|w| w as u32),
+            item.height.map(|h| h as u32),
+        )
+        .into_iter()
+        .find(|v| v.label == row.variant_label)
+        .ok_or_else(|| anyhow::anyhow!("variant {} is not available", row.variant_label))?;
+
+        // Multi-part movies download as one file spanning every part.
+        let parts = if item.part_number.is_some() {
+            media_repo::list_stack_parts(&self.db.read, &item.id).await?
+        } else {
+            Vec::new()
+        };
+        let (file_path, duration_ms) = if parts.len() > 1 {
+            let paths: Vec<PathBuf> = parts.iter().map(|p| PathBuf::from(&p.file_path)).collect();
+            let list = stack::write_concat_list(&self.config.transcode.cache_dir, &item.id, &paths)
+                .await?;
+            (list, parts.iter().map(|p| p.duration_ms).sum())
+        } else {
+            (PathBuf::from(&item.file_path), item.duration_ms)
+        };
+
+        let video_meta = stream_repo::get_video_meta(&self.db.read, &row.media_id).await?;
+        let audio_stream_index = row.audio_stream.max(0) as u32;
+        // Passthrough depends on the track being mapped, not the primary one
+        let audio_codec =
+            stream_repo::get_audio_codec(&self.db.read, &row.media_id, audio_stream_index).await?;
+        let (color_transfer, color_primaries) =
+            crate::handlers::stream::tonemap_color(video_meta.as_ref());
+        let source = DownloadSource {
+            file_path,
+            source_height: item.height.map(|h| h as u32),
+            video_codec: item.video_codec.clone(),
+            audio_codec,
+            pixel_format: video_meta.as_ref().and_then(|m| m.pixel_format.clone()),
+            color_transfer,
+            color_primaries,
+            audio_stream_index,
+        };
+
+        let mut subtitles = Vec::new();
+        for sub_id in row.subtitle_id_list() {
+            if let Some(sub) = subtitle_repo::get_subtitle_by_id(&self.db.read, sub_id).await? {
+                subtitles.push(DownloadSubtitle {
+                    path: PathBuf::from(sub.file_path),
+                    language: sub.language,
+                    title: sub.title,
+                });
+            }
+        }
+
+        let partial = self.partial_path(&row.id);
+        let encoder = self.encoder.with_quality(&encoding.preset, encoding.crf);
+        let args = download::build_download_args(&source, &variant, &subtitles, &encoder, &partial);
+
+        info!(
+            "Download {}: transcoding {} at {}",
+            row.id,
+            item.title.as_deref().unwrap_or(&item.file_path),
+            variant.label
+        );
+
+        // Progress is reported synchronously from the ffmpeg reader; a side
+        // task persists the latest value at a bounded rate.
+        let (progress_tx, mut progress_rx) = tokio::sync::watch::channel(0u8);
+        let progress_db = self.db.write.clone();
+        let progress_id = row.id.clone();
+        let progress_task = tokio::spawn(async move {
+            while progress_rx.changed().await.is_ok() {
+                let pct = *progress_rx.borrow_and_update();
+                if let Err(e) =
+                    download_repo::update_progress(&progress_db, &progress_id, pct).await
+                {
+                    debug!("Failed to update download progress: {}", e);
+                }
+                tokio::time::sleep(PROGRESS_WRITE_INTERVAL).await;
+            }
+        });
+
+        let duration_secs = duration_ms.map(|ms| ms as f64 / 1000.0).unwrap_or(0.0);
+        let outcome = download::run_download(
+            &self.config.transcode.ffmpeg_path,
+            &args,
+            duration_secs,
+            cancel,
+            |pct| {
+                let _ = progress_tx.send(pct);
+            },
+        )
+        .await;
+        drop(progress_tx);
+        let _ = progress_task.await;
+
+        if outcome? == DownloadOutcome::Cancelled {
+            return Ok(DownloadOutcome::Cancelled);
+        }
+
+        let output = self.output_path(&row.id);
+        tokio::fs::rename(&partial, &output).await?;
+        let size = tokio::fs::metadata(&output).await?.len();
+        download_repo::mark_ready(
+            &self.db.write,
+            &row.id,
+            &output.to_string_lossy(),
+            size,
+            self.config.downloads.expiry_hours,
+        )
+        .await?;
+        info!("Download {} ready ({} bytes)", row.id, size);
+        Ok(DownloadOutcome::Completed)
+    }
+
+    /// Purge expired downloads and evict the least recently used ones while
+    /// the cache is over quota.
+    async fn maintenance(&self) {
+        match download_repo::list_expired(&self.db.read).await {
+            Ok(expired) => {
+                for row in expired {
+                    info!("Download {} expired, removing", row.id);
+                    if let Err(e) = self.remove(&row).await {
+                        warn!("Failed to remove expired download {}: {}", row.id, e);
+                    }
+                }
+            }
+            Err(e) => warn!("Failed to list expired downloads: {}", e),
+        }
+
+        if let Err(e) = self.evict_until(self.config.downloads.quota_bytes()).await {
+            warn!("Download cache eviction failed: {}", e);
+        }
+    }
+
+    /// Evict finished downloads in LRU order until committed bytes ≤ `target`.
+    async fn evict_until(&self, target: u64) -> anyhow::Result<()> {
+        let mut committed = download_repo::committed_bytes(&self.db.read).await?;
+        if committed <= target {
+            return Ok(());
+        }
+        for row in download_repo::list_ready_lru(&self.db.read).await? {
+            if committed <= target {
+                break;
+            }
+            info!("Evicting download {} to stay within cache quota", row.id);
+            self.remove(&row).await?;
+            committed = committed.saturating_sub(row.file_size.unwrap_or(0).max(0) as u64);
+        }
+        Ok(())
+    }
+
+    /// Remove `.part` files left behind by transcodes interrupted by a restart.
+    async fn remove_stale_partials(&self) {
+        let dir: &Path = &self.config.downloads.cache_dir;
+        let Ok(mut entries) = tokio::fs::read_dir(dir).await else {
+            return;
+        };
+        while let Ok(Some(entry)) = entries.next_entry().await {
+            let path = entry.path();
+            if path.extension().is_some_and(|ext| ext == "part") {
+                let _ = tokio::fs::remove_file(&path).await;
+            }
+        }
+    }
+}
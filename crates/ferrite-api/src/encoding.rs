@@ -0,0 +1,28 @@
+//! Resolution of per-library encoding settings.
+//!
+//! `[transcode.library_encoding]` overrides are keyed by library ID or name;
+//! libraries live in the database, so resolving one needs a lookup.
+
+use ferrite_core::config::{EncodingConfig, TranscodeConfig};
+use ferrite_db::library_repo;
+use sqlx::SqlitePool;
+use tracing::warn;
+
+/// Effective encoding settings for media in `library_id`.
+pub async fn for_library(
+    pool: &SqlitePool,
+    transcode: &TranscodeConfig,
+    library_id: &str,
+) -> EncodingConfig {
+    if transcode.library_encoding.is_empty() {
+        return transcode.encoding.clone();
+    }
+    let name = match library_repo::get_library(pool, library_id).await {
+        Ok(library) => library.name,
+        Err(e) => {
+            warn!("Failed to load library {}: {}", library_id, e);
+            String::new()
+        }
+    };
+    transcode.encoding_for(library_id, &name)
+}
@@ -52,6 +52,13 @@ impl ApiError {
             message: msg.into(),
         }
     }
+
+    pub fn too_many_requests(msg: impl Into<String>) -> Self {
+        Self {
+            status: StatusCode::TOO_MANY_REQUESTS,
+            message: msg.into(),
+        }
+    }
 }
 
 impl IntoResponse for ApiError {
@@ -0,0 +1,278 @@
+use crate::state::AppState;
+use ferrite_db::download_repo::{self, DownloadRow};
+use ferrite_scanner::{LibraryChange, ScanProgress};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+use tokio::sync::broadcast;
+use tracing::warn;
+
+/// How often scans, streams and jobs are sampled while clients are connected.
+const SAMPLE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Events buffered per client before a slow one starts missing them.
+const EVENT_BUFFER: usize = 256;
+
+/// A live update pushed to admin clients on `/api/events`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ServerEvent {
+    /// Progress of a running scan; the last one for a scan carries its final status.
+    ScanProgress {
+        library_id: String,
+        progress: ScanProgress,
+    },
+    /// Items added, moved or removed by the filesystem watcher.
+    LibraryChanged(LibraryChange),
+    /// Active streams and transcodes, in the shape of `GET /api/admin/streams`.
+    Sessions(serde_json::Value),
+    /// A download transcode was queued, advanced or finished.
+    Job(JobProgress),
+}
+
+/// State of a background job. Downloads are the only kind so far.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct JobProgress {
+    pub kind: &'static str,
+    pub id: String,
+    pub user_id: Option<String>,
+    pub media_id: String,
+    /// `queued`, `transcoding`, `ready`, `failed` or `removed`.
+    pub status: String,
+    pub progress_pct: i64,
+    pub error: Option<String>,
+}
+
+impl JobProgress {
+    fn from_download(row: &DownloadRow) -> Self {
+        Self {
+            kind: "download",
+            id: row.id.clone(),
+            user_id: row.user_id.clone(),
+            media_id: row.media_id.clone(),
+            status: row.status.clone(),
+            progress_pct: row.progress_pct,
+            error: row.error.clone(),
+        }
+    }
+}
+
+/// Fan-out point for live server events.
+///
+/// Events come from two places: the filesystem watcher publishes library
+/// changes as they happen, and `run` samples scans, streams and download jobs
+/// once a second and publishes whatever changed. Sampling stops while no
+/// client is connected.
+pub struct EventHub {
+    events: broadcast::Sender<ServerEvent>,
+    library_changes: broadcast::Sender<LibraryChange>,
+}
+
+impl Default for EventHub {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EventHub {
+    pub fn new() -> Self {
+        Self {
+            events: broadcast::channel(EVENT_BUFFER).0,
+            library_changes: broadcast::channel(EVENT_BUFFER).0,
+        }
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<ServerEvent> {
+        self.events.subscribe()
+    }
+
+    pub fn publish(&self, event: ServerEvent) {
+        // Nobody listening is not an error.
+        let _ = self.events.send(event);
+    }
+
+    /// Sender handed to the filesystem watcher.
+    pub fn library_changes(&self) -> broadcast::Sender<LibraryChange> {
+        self.library_changes.clone()
+    }
+
+    /// Current state of everything the stream reports on, sent to a client
+    /// when it connects so it doesn't wait for the next change.
+    pub async fn snapshot(state: &AppState) -> Vec<ServerEvent> {
+        let mut events = Vec::new();
+        for scan in state.scan_registry.list() {
+            let progress = scan.to_progress().await;
+            if progress.scanning {
+                events.push(ServerEvent::ScanProgress {
+                    library_id: scan.library_id.clone(),
+                    progress,
+                });
+            }
+        }
+        events.push(ServerEvent::Sessions(
+            crate::handlers::system::active_streams(state).await,
+        ));
+        match download_repo::list_active_downloads(&state.db.read).await {
+            Ok(rows) => events.extend(
+                rows.iter()
+                    .map(|row| ServerEvent::Job(JobProgress::from_download(row))),
+            ),
+            Err(e) => warn!("Failed to list active downloads for event stream: {}", e),
+        }
+        events
+    }
+
+    /// Sampler loop publishing changes to connected clients. Never returns.
+    pub async fn run(state: AppState) {
+        let hub = state.events.clone();
+        let mut library_changes = hub.library_changes.subscribe();
+        let mut sampler = Sampler::default();
+        let mut interval = tokio::time::interval(SAMPLE_INTERVAL);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {
+                    if hub.events.receiver_count() == 0 {
+                        // Clients get a fresh snapshot when they connect.
+                        sampler = Sampler::default();
+                        continue;
+                    }
+                    for event in sampler.sample(&state).await {
+                        hub.publish(event);
+                    }
+                }
+                change = library_changes.recv() => match change {
+                    Ok(change) => hub.publish(ServerEvent::LibraryChanged(change)),
+                    Err(broadcast::error::RecvError::Lagged(n)) => {
+                        warn!("Event stream dropped {} library change(s)", n);
+                    }
+                    Err(broadcast::error::RecvError::Closed) => {}
+                },
+            }
+        }
+    }
+}
+
+/// What was last published, so only changes go out.
+#[derive(Default)]
+struct Sampler {
+    running_scans: HashSet<String>,
+    sessions: Option<serde_json::Value>,
+    jobs: HashMap<String, JobProgress>,
+}
+
+impl Sampler {
+    async fn sample(&mut self, state: &AppState) -> Vec<ServerEvent> {
+        let mut events = Vec::new();
+
+        // Running scans report every tick; a scan that just ended reports once more.
+        for scan in state.scan_registry.list() {
+            let progress = scan.to_progress().await;
+            let was_running = self.running_scans.remove(&scan.library_id);
+            if progress.scanning {
+                self.running_scans.insert(scan.library_id.clone());
+            }
+            if progress.scanning || was_running {
+                events.push(ServerEvent::ScanProgress {
+                    library_id: scan.library_id.clone(),
+                    progress,
+                });
+            }
+        }
+
+        // Session timers tick every second; only publish when something else moved.
+        let sessions = crate::handlers::system::active_streams(state).await;
+        let key = without_timers(&sessions);
+        if self.sessions.as_ref() != Some(&key) {
+            self.sessions = Some(key);
+            events.push(ServerEvent::Sessions(sessions));
+        }
+
+        match download_repo::list_active_downloads(&state.db.read).await {
+            Ok(rows) => {
+                let mut gone: HashSet<String> = self.jobs.keys().cloned().collect();
+                for row in &rows {
+                    gone.remove(&row.id);
+                    let job = JobProgress::from_download(row);
+                    if self.jobs.get(&row.id) != Some(&job) {
+                        self.jobs.insert(row.id.clone(), job.clone());
+                        events.push(ServerEvent::Job(job));
+                    }
+                }
+                for id in gone {
+                    let Some(last) = self.jobs.remove(&id) else {
+                        continue;
+                    };
+                    let finished = match download_repo::get_download(&state.db.read, &id).await {
+                        Ok(Some(row)) => JobProgress::from_download(&row),
+                        Ok(None) => JobProgress {
+                            status: "removed".into(),
+                            ..last
+                        },
+                        Err(e) => {
+                            warn!("Failed to load download {} for event stream: {}", id, e);
+                            continue;
+                        }
+                    };
+                    events.push(ServerEvent::Job(finished));
+                }
+            }
+            Err(e) => warn!("Failed to list active downloads for event stream: {}", e),
+        }
+
+        events
+    }
+}
+
+/// Copy of a streams payload without the per-second timers.
+fn without_timers(value: &serde_json::Value) -> serde_json::Value {
+    match value {
+        serde_json::Value::Object(map) => map
+            .iter()
+            .filter(|(k, _)| !matches!(k.as_str(), "idle_secs" | "age_secs" | "waiting_secs"))
+            .map(|(k, v)| (k.clone(), without_timers(v)))
+            .collect(),
+        serde_json::Value::Array(items) => items.iter().map(without_timers).collect(),
+        other => other.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn session_timers_do_not_count_as_changes() {
+        let a = json!({
+            "sessions": [{"session_id": "s1", "idle_secs": 1, "age_secs": 10}],
+            "transcodes": {"slots": [{"id": "t", "age_secs": 10}], "queue": []},
+        });
+        let b = json!({
+            "sessions": [{"session_id": "s1", "idle_secs": 2, "age_secs": 11}],
+            "transcodes": {"slots": [{"id": "t", "age_secs": 11}], "queue": []},
+        });
+        let c = json!({
+            "sessions": [{"session_id": "s2", "idle_secs": 2, "age_secs": 11}],
+            "transcodes": {"slots": [{"id": "t", "age_secs": 11}], "queue": []},
+        });
+        assert_eq!(without_timers(&a), without_timers(&b));
+        assert_ne!(without_timers(&b), without_timers(&c));
+    }
+
+    #[test]
+    fn events_are_tagged_by_type() {
+        let event = ServerEvent::LibraryChanged(LibraryChange {
+            library_id: "lib".into(),
+            indexed: 2,
+            relocated: 0,
+            removed: 1,
+            full_rescan: false,
+        });
+        let value = serde_json::to_value(&event).unwrap();
+        assert_eq!(value["type"], "library_changed");
+        assert_eq!(value["library_id"], "lib");
+        assert_eq!(value["removed"], 1);
+    }
+}
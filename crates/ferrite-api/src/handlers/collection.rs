@@ -1,10 +1,12 @@
+use crate::auth::AuthUser;
 use crate::error::ApiError;
 use crate::state::AppState;
 use axum::extract::{Path, Query, State};
 use axum::http::StatusCode;
 use axum::response::IntoResponse;
-use axum::Json;
-use ferrite_db::collection_repo;
+use axum::{Extension, Json};
+use ferrite_db::collection_repo::{self, CollectionRow, SmartRules};
+use ferrite_db::{franchise_repo, movie_repo, user_repo};
 use serde::Deserialize;
 
 #[derive(Deserialize)]
@@ -12,9 +14,12 @@ pub struct CreateCollectionRequest {
     pub name: String,
     #[serde(default)]
     pub description: String,
-    /// "collection" (default) or "playlist"
+    /// "collection" (default), "playlist" or "smart"
     #[serde(default = "default_kind")]
     pub kind: String,
+    /// Filter rules; required for (and only allowed on) smart collections
+    #[serde(default)]
+    pub rules: Option<SmartRules>,
 }
 
 fn default_kind() -> String {
@@ -23,10 +28,17 @@ fn default_kind() -> String {
 
 #[derive(Deserialize, Default)]
 pub struct ListCollectionsQuery {
-    /// Filter by kind: "collection" or "playlist"
+    /// Filter by kind: "collection", "playlist", "smart" or "franchise"
     pub kind: Option<String>,
 }
 
+#[derive(Deserialize, Default)]
+pub struct CollectionItemsQuery {
+    /// Page of a smart collection's matches (manual collections return every item)
+    pub page: Option<u32>,
+    pub per_page: Option<u32>,
+}
+
 #[derive(Deserialize)]
 pub struct AddItemRequest {
     pub media_id: String,
@@ -38,50 +50,85 @@ pub struct ReorderRequest {
     pub position: i64,
 }
 
+#[derive(Deserialize)]
+pub struct ShareRequest {
+    /// Allow adding, removing and reordering items (default read-only)
+    #[serde(default)]
+    pub can_edit: bool,
+}
+
 #[derive(Deserialize)]
 pub struct UpdateCollectionRequest {
     pub name: String,
     #[serde(default)]
     pub description: String,
+    /// Replacement rules for a smart collection
+    #[serde(default)]
+    pub rules: Option<SmartRules>,
 }
 
 /// POST /api/collections — Create a new collection or playlist.
 pub async fn create_collection(
     State(state): State<AppState>,
+    auth_user: Option<Extension<AuthUser>>,
     Json(body): Json<CreateCollectionRequest>,
 ) -> Result<impl IntoResponse, ApiError> {
     if body.name.trim().is_empty() {
         return Err(ApiError::bad_request("Collection name cannot be empty"));
     }
 
-    if body.kind != "collection" && body.kind != "playlist" {
+    if !matches!(body.kind.as_str(), "collection" | "playlist" | "smart") {
         return Err(ApiError::bad_request(
-            "Kind must be 'collection' or 'playlist'",
+            "Kind must be 'collection', 'playlist' or 'smart'",
         ));
     }
 
-    // Use a default user ID for now (multi-user support uses auth middleware to extract user)
-    let user_id = extract_user_id(&state).await;
-
-    let collection = collection_repo::create_collection(
-        &state.db.write,
-        &user_id,
-        body.name.trim(),
-        body.description.trim(),
-        &body.kind,
-    )
-    .await
+    let user_id = extract_user_id(&state, auth_user.as_deref()).await;
+
+    let collection = match (body.kind.as_str(), &body.rules) {
+        ("smart", Some(rules)) => {
+            rules.validate().map_err(ApiError::bad_request)?;
+            collection_repo::create_smart_collection(
+                &state.db.write,
+                &user_id,
+                body.name.trim(),
+                body.description.trim(),
+                rules,
+            )
+            .await
+        }
+        ("smart", None) => {
+            return Err(ApiError::bad_request("Smart collections require rules"));
+        }
+        (_, Some(_)) => {
+            return Err(ApiError::bad_request(
+                "Rules are only supported for smart collections",
+            ));
+        }
+        (kind, None) => {
+            collection_repo::create_collection(
+                &state.db.write,
+                &user_id,
+                body.name.trim(),
+                body.description.trim(),
+                kind,
+            )
+            .await
+        }
+    }
     .map_err(|e| ApiError::internal(format!("Failed to create collection: {}", e)))?;
 
     Ok((StatusCode::CREATED, Json(collection)))
 }
 
-/// GET /api/collections — List all collections for the current user.
+/// GET /api/collections — List all collections for the current user, followed
+/// by the server-wide franchise collections.
 pub async fn list_collections(
     State(state): State<AppState>,
+    auth_user: Option<Extension<AuthUser>>,
     Query(query): Query<ListCollectionsQuery>,
 ) -> Result<impl IntoResponse, ApiError> {
-    let user_id = extract_user_id(&state).await;
+    let user_id = extract_user_id(&state, auth_user.as_deref()).await;
 
     let collections =
         collection_repo::list_collections(&state.db.read, &user_id, query.kind.as_deref())
@@ -91,32 +138,103 @@ pub async fn list_collections(
     // Enrich with item counts
     let mut result = Vec::with_capacity(collections.len());
     for c in collections {
-        let count = collection_repo::count_items(&state.db.read, &c.id)
-            .await
-            .unwrap_or(0);
+        let count = match c.smart_rules() {
+            Ok(Some(rules)) => collection_repo::count_smart_items(&state.db.read, &c, &rules).await,
+            Ok(None) => collection_repo::count_items(&state.db.read, &c.id).await,
+            Err(e) => Err(e),
+        }
+        .unwrap_or(0);
+        let access = access_level(&state, &c, &user_id).await?;
         result.push(serde_json::json!({
             "id": c.id,
             "user_id": c.user_id,
             "name": c.name,
             "description": c.description,
             "kind": c.kind,
+            "access": access.map(Access::as_str),
+            "rules": c.smart_rules().ok().flatten(),
             "item_count": count,
             "created_at": c.created_at,
             "updated_at": c.updated_at,
         }));
     }
 
+    if matches!(query.kind.as_deref(), None | Some("franchise")) {
+        let franchises = franchise_repo::list_franchises(&state.db.read)
+            .await
+            .map_err(|e| ApiError::internal(format!("Failed to list franchises: {}", e)))?;
+        result.extend(franchises.iter().map(franchise_json));
+    }
+
     Ok(Json(result))
 }
 
 /// GET /api/collections/{id} — Get a collection with its items.
+/// Smart collections evaluate their rules and return a page of media items.
 pub async fn get_collection(
     State(state): State<AppState>,
+    auth_user: Option<Extension<AuthUser>>,
     Path(id): Path<String>,
+    Query(query): Query<CollectionItemsQuery>,
 ) -> Result<impl IntoResponse, ApiError> {
-    let collection = collection_repo::get_collection(&state.db.read, &id)
-        .await?
-        .ok_or_else(|| ApiError::not_found(format!("Collection '{id}' not found")))?;
+    let page = query.page.unwrap_or(1).max(1);
+    let per_page = query.per_page.unwrap_or(50).clamp(1, 200);
+    let user_id = extract_user_id(&state, auth_user.as_deref()).await;
+
+    let Some(collection) = collection_repo::get_collection(&state.db.read, &id).await? else {
+        // Franchise collections share the id space and this endpoint
+        let franchise = franchise_repo::get_franchise(&state.db.read, &id)
+            .await?
+            .ok_or_else(|| ApiError::not_found(format!("Collection '{id}' not found")))?;
+        let mq = movie_repo::MediaQuery {
+            franchise_id: Some(&franchise.id),
+            // Release order
+            sort_by: Some("year"),
+            page: page as i64,
+            per_page: per_page as i64,
+            ..Default::default()
+        };
+        let items = movie_repo::list_movies_with_media(&state.db.read, &mq, Some(&user_id))
+            .await
+            .map_err(|e| ApiError::internal(format!("Failed to list items: {}", e)))?;
+        let mut body = franchise_json(&franchise);
+        body["items"] = serde_json::json!(items);
+        body["page"] = serde_json::json!(page);
+        body["per_page"] = serde_json::json!(per_page);
+        return Ok(Json(body));
+    };
+
+    let access = require_access(&state, &collection, &user_id, Access::Read).await?;
+
+    if let Some(rules) = collection.smart_rules()? {
+        let items = collection_repo::list_smart_items(
+            &state.db.read,
+            &collection,
+            &rules,
+            page as i64,
+            per_page as i64,
+        )
+        .await
+        .map_err(|e| ApiError::internal(format!("Failed to evaluate rules: {}", e)))?;
+        let total = collection_repo::count_smart_items(&state.db.read, &collection, &rules)
+            .await
+            .map_err(|e| ApiError::internal(format!("Failed to evaluate rules: {}", e)))?;
+        return Ok(Json(serde_json::json!({
+            "id": collection.id,
+            "user_id": collection.user_id,
+            "name": collection.name,
+            "description": collection.description,
+            "kind": collection.kind,
+            "access": access.as_str(),
+            "rules": rules,
+            "item_count": total,
+            "items": items,
+            "page": page,
+            "per_page": per_page,
+            "created_at": collection.created_at,
+            "updated_at": collection.updated_at,
+        })));
+    }
 
     let items = collection_repo::list_items(&state.db.read, &id)
         .await
@@ -128,6 +246,7 @@ pub async fn get_collection(
         "name": collection.name,
         "description": collection.description,
         "kind": collection.kind,
+        "access": access.as_str(),
         "item_count": items.len(),
         "items": items,
         "created_at": collection.created_at,
@@ -138,6 +257,7 @@ pub async fn get_collection(
 /// PUT /api/collections/{id} — Update a collection's name and description.
 pub async fn update_collection(
     State(state): State<AppState>,
+    auth_user: Option<Extension<AuthUser>>,
     Path(id): Path<String>,
     Json(body): Json<UpdateCollectionRequest>,
 ) -> Result<impl IntoResponse, ApiError> {
@@ -145,6 +265,21 @@ pub async fn update_collection(
         return Err(ApiError::bad_request("Collection name cannot be empty"));
     }
 
+    let user_id = extract_user_id(&state, auth_user.as_deref()).await;
+    let collection = find_collection(&state, &id, &user_id, Access::Owner).await?;
+
+    if let Some(rules) = &body.rules {
+        rules.validate().map_err(ApiError::bad_request)?;
+        if collection.kind != "smart" {
+            return Err(ApiError::bad_request(
+                "Rules are only supported for smart collections",
+            ));
+        }
+        collection_repo::update_rules(&state.db.write, &id, rules)
+            .await
+            .map_err(|e| ApiError::internal(format!("Failed to update rules: {}", e)))?;
+    }
+
     let updated = collection_repo::update_collection(
         &state.db.write,
         &id,
@@ -161,8 +296,11 @@ pub async fn update_collection(
 /// DELETE /api/collections/{id} — Delete a collection.
 pub async fn delete_collection(
     State(state): State<AppState>,
+    auth_user: Option<Extension<AuthUser>>,
     Path(id): Path<String>,
 ) -> Result<impl IntoResponse, ApiError> {
+    let user_id = extract_user_id(&state, auth_user.as_deref()).await;
+    find_collection(&state, &id, &user_id, Access::Owner).await?;
     let deleted = collection_repo::delete_collection(&state.db.write, &id)
         .await
         .map_err(|e| ApiError::internal(format!("Failed to delete collection: {}", e)))?;
@@ -177,13 +315,13 @@ pub async fn delete_collection(
 /// POST /api/collections/{id}/items — Add a media item to a collection.
 pub async fn add_item(
     State(state): State<AppState>,
+    auth_user: Option<Extension<AuthUser>>,
     Path(id): Path<String>,
     Json(body): Json<AddItemRequest>,
 ) -> Result<impl IntoResponse, ApiError> {
-    // Verify collection exists
-    collection_repo::get_collection(&state.db.read, &id)
-        .await?
-        .ok_or_else(|| ApiError::not_found(format!("Collection '{id}' not found")))?;
+    let user_id = extract_user_id(&state, auth_user.as_deref()).await;
+    let collection = find_collection(&state, &id, &user_id, Access::Edit).await?;
+    reject_smart(&collection)?;
 
     let item = collection_repo::add_item(&state.db.write, &id, &body.media_id)
         .await
@@ -201,8 +339,11 @@ pub async fn add_item(
 /// DELETE /api/collections/{collection_id}/items/{media_id} — Remove an item.
 pub async fn remove_item(
     State(state): State<AppState>,
+    auth_user: Option<Extension<AuthUser>>,
     Path((collection_id, media_id)): Path<(String, String)>,
 ) -> Result<impl IntoResponse, ApiError> {
+    let user_id = extract_user_id(&state, auth_user.as_deref()).await;
+    reject_smart(&find_collection(&state, &collection_id, &user_id, Access::Edit).await?)?;
     let removed = collection_repo::remove_item(&state.db.write, &collection_id, &media_id)
         .await
         .map_err(|e| ApiError::internal(format!("Failed to remove item: {}", e)))?;
@@ -217,12 +358,12 @@ pub async fn remove_item(
 /// PUT /api/collections/{id}/reorder — Reorder an item in a playlist.
 pub async fn reorder_item(
     State(state): State<AppState>,
+    auth_user: Option<Extension<AuthUser>>,
     Path(id): Path<String>,
     Json(body): Json<ReorderRequest>,
 ) -> Result<impl IntoResponse, ApiError> {
-    let collection = collection_repo::get_collection(&state.db.read, &id)
-        .await?
-        .ok_or_else(|| ApiError::not_found(format!("Collection '{id}' not found")))?;
+    let user_id = extract_user_id(&state, auth_user.as_deref()).await;
+    let collection = find_collection(&state, &id, &user_id, Access::Edit).await?;
 
     if collection.kind != "playlist" {
         return Err(ApiError::bad_request(
@@ -247,9 +388,168 @@ pub async fn reorder_item(
     Ok(Json(items))
 }
 
-/// Extract user ID from the first user in the database (simplified).
-/// In a full implementation, this would come from the auth middleware.
-async fn extract_user_id(state: &AppState) -> String {
+/// GET /api/collections/{id}/shares — List who a collection is shared with (owner only).
+pub async fn list_shares(
+    State(state): State<AppState>,
+    auth_user: Option<Extension<AuthUser>>,
+    Path(id): Path<String>,
+) -> Result<impl IntoResponse, ApiError> {
+    let user_id = extract_user_id(&state, auth_user.as_deref()).await;
+    find_collection(&state, &id, &user_id, Access::Owner).await?;
+    let shares = collection_repo::list_shares(&state.db.read, &id).await?;
+    Ok(Json(shares))
+}
+
+/// PUT /api/collections/{id}/shares/{user_id} — Share a collection with a user,
+/// read-only or editable (owner only).
+pub async fn share_collection(
+    State(state): State<AppState>,
+    auth_user: Option<Extension<AuthUser>>,
+    Path((id, target_user_id)): Path<(String, String)>,
+    Json(body): Json<ShareRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    let user_id = extract_user_id(&state, auth_user.as_deref()).await;
+    find_collection(&state, &id, &user_id, Access::Owner).await?;
+    if target_user_id == user_id {
+        return Err(ApiError::bad_request(
+            "A collection cannot be shared with its owner",
+        ));
+    }
+    user_repo::get_user_by_id(&state.db.read, &target_user_id)
+        .await?
+        .ok_or_else(|| ApiError::not_found(format!("User '{target_user_id}' not found")))?;
+
+    collection_repo::share_collection(&state.db.write, &id, &target_user_id, body.can_edit)
+        .await
+        .map_err(|e| ApiError::internal(format!("Failed to share collection: {}", e)))?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// DELETE /api/collections/{id}/shares/{user_id} — Stop sharing a collection.
+/// The owner can remove anyone; a user can remove their own share.
+pub async fn unshare_collection(
+    State(state): State<AppState>,
+    auth_user: Option<Extension<AuthUser>>,
+    Path((id, target_user_id)): Path<(String, String)>,
+) -> Result<impl IntoResponse, ApiError> {
+    let user_id = extract_user_id(&state, auth_user.as_deref()).await;
+    let needed = if target_user_id == user_id {
+        Access::Read
+    } else {
+        Access::Owner
+    };
+    find_collection(&state, &id, &user_id, needed).await?;
+
+    let removed = collection_repo::unshare_collection(&state.db.write, &id, &target_user_id)
+        .await
+        .map_err(|e| ApiError::internal(format!("Failed to unshare collection: {}", e)))?;
+    if !removed {
+        return Err(ApiError::not_found(
+            "Collection is not shared with this user",
+        ));
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// What the requesting user may do with a collection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum Access {
+    Read,
+    Edit,
+    Owner,
+}
+
+impl Access {
+    fn as_str(self) -> &'static str {
+        match self {
+            Access::Read => "read",
+            Access::Edit => "edit",
+            Access::Owner => "owner",
+        }
+    }
+}
+
+async fn access_level(
+    state: &AppState,
+    collection: &CollectionRow,
+    user_id: &str,
+) -> Result<Option<Access>, ApiError> {
+    if collection.user_id == user_id {
+        return Ok(Some(Access::Owner));
+    }
+    let share =
+        collection_repo::get_share_permission(&state.db.read, &collection.id, user_id).await?;
+    Ok(share.map(|can_edit| if can_edit { Access::Edit } else { Access::Read }))
+}
+
+/// Check that `user_id` has at least `needed` access. Collections the user
+/// cannot see at all are reported as missing.
+async fn require_access(
+    state: &AppState,
+    collection: &CollectionRow,
+    user_id: &str,
+    needed: Access,
+) -> Result<Access, ApiError> {
+    match access_level(state, collection, user_id).await? {
+        None => Err(ApiError::not_found(format!(
+            "Collection '{}' not found",
+            collection.id
+        ))),
+        Some(access) if access < needed => Err(ApiError::forbidden(format!(
+            "Collection '{}' is shared with you read-only",
+            collection.id
+        ))),
+        Some(access) => Ok(access),
+    }
+}
+
+/// A franchise rendered in the same shape as a collection listing entry.
+fn franchise_json(f: &franchise_repo::FranchiseRow) -> serde_json::Value {
+    serde_json::json!({
+        "id": f.id,
+        "user_id": null,
+        "name": f.name,
+        "description": "",
+        "kind": "franchise",
+        "tmdb_id": f.tmdb_id,
+        "poster_path": f.poster_path,
+        "backdrop_path": f.backdrop_path,
+        "item_count": f.item_count,
+        "created_at": f.updated_at,
+        "updated_at": f.updated_at,
+    })
+}
+
+/// Load a collection the user has at least `needed` access to.
+pub(crate) async fn find_collection(
+    state: &AppState,
+    id: &str,
+    user_id: &str,
+    needed: Access,
+) -> Result<CollectionRow, ApiError> {
+    let collection = collection_repo::get_collection(&state.db.read, id)
+        .await?
+        .ok_or_else(|| ApiError::not_found(format!("Collection '{id}' not found")))?;
+    require_access(state, &collection, user_id, needed).await?;
+    Ok(collection)
+}
+
+/// Smart collections are populated by their rules, never by hand.
+fn reject_smart(collection: &CollectionRow) -> Result<(), ApiError> {
+    if collection.kind == "smart" {
+        return Err(ApiError::bad_request(
+            "Items of a smart collection come from its rules",
+        ));
+    }
+    Ok(())
+}
+
+/// The requesting user's ID. Without an authenticated user (auth disabled)
+/// this falls back to the first user in the database.
+pub(crate) async fn extract_user_id(state: &AppState, auth_user: Option<&AuthUser>) -> String {
+    if let Some(user) = auth_user {
+        return user.user_id.clone();
+    }
     // Try to get the first user; fall back to a default ID
     let result: Option<(String,)> = sqlx::query_as("SELECT id FROM users LIMIT 1")
         .fetch_optional(&state.db.read)
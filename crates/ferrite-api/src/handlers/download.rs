@@ -0,0 +1,232 @@
+use crate::auth::AuthUser;
+use crate::encoding;
+use crate::error::ApiError;
+use crate::state::AppState;
+use axum::extract::{Path, State};
+use axum::http::{header, HeaderMap, HeaderValue, StatusCode};
+use axum::response::IntoResponse;
+use axum::{Extension, Json};
+use ferrite_db::download_repo::{self, DownloadRow};
+use ferrite_db::{media_repo, stream_repo, subtitle_repo};
+use ferrite_stream::{direct, download};
+use ferrite_transcode::variants::select_variants_for;
+use serde::Deserialize;
+
+#[derive(Deserialize)]
+pub struct CreateDownloadRequest {
+    /// Quality variant label (e.g. "720p"). Defaults to the highest available.
+    pub variant: Option<String>,
+    /// Audio stream index (0-based within audio streams). Defaults to the first.
+    #[serde(default)]
+    pub audio_stream: u32,
+    /// External subtitle IDs to include as soft tracks.
+    #[serde(default)]
+    pub subtitle_ids: Vec<i64>,
+}
+
+fn extract_user_id(auth_user: &Option<AuthUser>) -> Option<&str> {
+    auth_user.as_ref().map(|u| u.user_id.as_str())
+}
+
+/// Load a download and check it belongs to the caller (404 otherwise, so IDs
+/// of other users' downloads aren't disclosed).
+async fn load_owned(
+    state: &AppState,
+    id: &str,
+    user_id: Option<&str>,
+) -> Result<DownloadRow, ApiError> {
+    download_repo::get_download(&state.db.read, id)
+        .await?
+        .filter(|row| row.user_id.as_deref() == user_id)
+        .ok_or_else(|| ApiError::not_found(format!("Download '{id}' not found")))
+}
+
+/// POST /api/media/{id}/download — queue an offline download of a media item.
+pub async fn create_download(
+    State(state): State<AppState>,
+    auth_user: Option<Extension<AuthUser>>,
+    Path(media_id): Path<String>,
+    Json(body): Json<CreateDownloadRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    let user = auth_user.map(|e| e.0);
+    let user_id = extract_user_id(&user);
+
+    let item = media_repo::get_media_item(&state.db.read, &media_id)
+        .await?
+        .ok_or_else(|| ApiError::not_found(format!("Media item '{media_id}' not found")))?;
+
+    let encoding =
+        encoding::for_library(&state.db.read, &state.config.transcode, &item.library_id).await;
+    let variants = select_variants_for(
+        &encoding,
+        item.width.map(|w| w as u32),
+        item.height.map(|h| h as u32),
+    );
+    let variant = match body.variant.as_deref() {
+        Some(label) => variants.iter().find(|v| v.label == label).ok_or_else(|| {
+            let available: Vec<&str> = variants.iter().map(|v| v.label.as_str()).collect();
+            ApiError::bad_request(format!(
+                "Variant '{label}' is not available (available: {})",
+                available.join(", ")
+            ))
+        })?,
+        None => variants
+            .first()
+            .ok_or_else(|| ApiError::internal("No quality variants available"))?,
+    };
+
+    // A missing track would only fail once FFmpeg runs, after quota was
+    // reserved and the job queued.
+    let audio_streams = stream_repo::get_streams(&state.db.read, &media_id)
+        .await?
+        .into_iter()
+        .filter(|s| s.stream_type == "audio")
+        .count();
+    if body.audio_stream as usize >= audio_streams {
+        return Err(ApiError::bad_request(format!(
+            "Audio stream {} does not exist (the item has {audio_streams})",
+            body.audio_stream
+        )));
+    }
+
+    let mut subtitle_ids = body.subtitle_ids.clone();
+    subtitle_ids.sort_unstable();
+    subtitle_ids.dedup();
+    for sub_id in &subtitle_ids {
+        let sub = subtitle_repo::get_subtitle_by_id(&state.db.read, *sub_id)
+            .await?
+            .filter(|s| s.media_item_id == media_id)
+            .ok_or_else(|| ApiError::bad_request(format!("Subtitle {sub_id} not found")))?;
+        if !download::is_soft_subtitle_format(&sub.format) {
+            return Err(ApiError::bad_request(format!(
+                "Subtitle {sub_id} is a bitmap format ({}) and cannot be included in a download",
+                sub.format
+            )));
+        }
+    }
+
+    // Reuse an identical pending or finished download instead of transcoding twice.
+    if let Some(existing) = download_repo::find_matching(
+        &state.db.read,
+        user_id,
+        &media_id,
+        &variant.label,
+        body.audio_stream,
+        &subtitle_ids,
+    )
+    .await?
+    {
+        return Ok((StatusCode::OK, Json(existing)));
+    }
+
+    let duration_secs = item.duration_ms.map(|ms| ms as f64 / 1000.0).unwrap_or(0.0);
+    let estimated = download::estimate_output_bytes(variant, duration_secs);
+    if !state.downloads.reserve_space(estimated).await? {
+        return Err(ApiError::service_unavailable(
+            "Download cache quota exceeded, try again later",
+        ));
+    }
+
+    let row = download_repo::create_download(
+        &state.db.write,
+        user_id,
+        &media_id,
+        &variant.label,
+        body.audio_stream,
+        &subtitle_ids,
+        estimated,
+    )
+    .await?;
+    state.downloads.notify_queued();
+
+    Ok((StatusCode::ACCEPTED, Json(row)))
+}
+
+/// GET /api/downloads — list the current user's downloads.
+pub async fn list_downloads(
+    State(state): State<AppState>,
+    auth_user: Option<Extension<AuthUser>>,
+) -> Result<impl IntoResponse, ApiError> {
+    let user = auth_user.map(|e| e.0);
+    let rows = download_repo::list_downloads(&state.db.read, extract_user_id(&user)).await?;
+    Ok(Json(rows))
+}
+
+/// GET /api/downloads/{id} — status and progress of a single download.
+pub async fn get_download(
+    State(state): State<AppState>,
+    auth_user: Option<Extension<AuthUser>>,
+    Path(id): Path<String>,
+) -> Result<impl IntoResponse, ApiError> {
+    let user = auth_user.map(|e| e.0);
+    let row = load_owned(&state, &id, extract_user_id(&user)).await?;
+    Ok(Json(row))
+}
+
+/// GET /api/downloads/{id}/file — fetch a finished download (supports Range requests).
+pub async fn serve_download(
+    State(state): State<AppState>,
+    auth_user: Option<Extension<AuthUser>>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, ApiError> {
+    let user = auth_user.map(|e| e.0);
+    let row = load_owned(&state, &id, extract_user_id(&user)).await?;
+    let file_path = match (row.status.as_str(), row.file_path.as_deref()) {
+        ("ready", Some(path)) => std::path::PathBuf::from(path),
+        _ => {
+            return Err(ApiError::bad_request(format!(
+                "Download is not ready (status: {})",
+                row.status
+            )))
+        }
+    };
+
+    download_repo::touch(&state.db.write, &id).await?;
+
+    let mut response =
+        direct::serve_file(&file_path, &headers)
+            .await
+            .map_err(|status| match status {
+                StatusCode::NOT_FOUND => ApiError::not_found("Download file is missing"),
+                other => ApiError::internal(format!("Failed to serve download: {other}")),
+            })?;
+
+    let title = media_repo::get_media_item(&state.db.read, &row.media_id)
+        .await?
+        .and_then(|item| item.title)
+        .unwrap_or_else(|| row.media_id.clone());
+    let filename: String = format!("{title} ({}).mp4", row.variant_label)
+        .chars()
+        .map(|c| {
+            if c.is_ascii_graphic() || c == ' ' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .filter(|c| *c != '"' && *c != '\\')
+        .collect();
+    if let Ok(value) = HeaderValue::from_str(&format!("attachment; filename=\"{filename}\"")) {
+        response
+            .headers_mut()
+            .insert(header::CONTENT_DISPOSITION, value);
+    }
+    response
+        .headers_mut()
+        .insert(header::CONTENT_TYPE, HeaderValue::from_static("video/mp4"));
+
+    Ok(response)
+}
+
+/// DELETE /api/downloads/{id} — cancel a pending download or delete a finished one.
+pub async fn delete_download(
+    State(state): State<AppState>,
+    auth_user: Option<Extension<AuthUser>>,
+    Path(id): Path<String>,
+) -> Result<impl IntoResponse, ApiError> {
+    let user = auth_user.map(|e| e.0);
+    let row = load_owned(&state, &id, extract_user_id(&user)).await?;
+    state.downloads.remove(&row).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,144 @@
+use crate::auth::AuthUser;
+use crate::error::ApiError;
+use crate::handlers::system::ensure_admin_if_present;
+use crate::state::AppState;
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use axum::{Extension, Json};
+use ferrite_core::config::IntegrityMode;
+use ferrite_db::integrity_repo::{self, IntegrityTarget};
+use ferrite_db::media_repo;
+use serde::Deserialize;
+
+fn parse_mode(state: &AppState, mode: Option<&str>) -> Result<IntegrityMode, ApiError> {
+    match mode {
+        None => Ok(state.config.integrity.mode),
+        Some(value) => IntegrityMode::parse(value).ok_or_else(|| {
+            ApiError::bad_request(format!(
+                "Unknown integrity check mode '{value}' (expected 'sample' or 'full')"
+            ))
+        }),
+    }
+}
+
+/// GET /api/admin/integrity — item counts per check status, the schedule and
+/// the pass in progress, if any (admin only).
+pub async fn integrity_status(
+    State(state): State<AppState>,
+    auth_user: Option<Extension<AuthUser>>,
+) -> Result<impl IntoResponse, ApiError> {
+    ensure_admin_if_present(&state, auth_user.as_ref()).await?;
+    let counts: serde_json::Map<String, serde_json::Value> =
+        integrity_repo::count_by_status(&state.db.read)
+            .await?
+            .into_iter()
+            .map(|(status, count)| (status, count.into()))
+            .collect();
+    let cfg = &state.config.integrity;
+    Ok(Json(serde_json::json!({
+        "counts": counts,
+        "scheduled": cfg.enabled,
+        "mode": cfg.mode,
+        "interval_hours": cfg.interval_hours,
+        "pass": state.integrity.pass_progress(),
+    })))
+}
+
+#[derive(Deserialize)]
+pub struct BrokenMediaQuery {
+    pub library_id: Option<String>,
+}
+
+/// GET /api/admin/integrity/broken?library_id={id} — items whose latest check
+/// found them damaged or broken, broken first (admin only).
+pub async fn list_broken_media(
+    State(state): State<AppState>,
+    auth_user: Option<Extension<AuthUser>>,
+    Query(params): Query<BrokenMediaQuery>,
+) -> Result<impl IntoResponse, ApiError> {
+    ensure_admin_if_present(&state, auth_user.as_ref()).await?;
+    let items =
+        integrity_repo::list_broken_media(&state.db.read, params.library_id.as_deref()).await?;
+    Ok(Json(items))
+}
+
+#[derive(Deserialize)]
+pub struct StartCheckQuery {
+    pub library_id: Option<String>,
+    /// `sample` or `full`; defaults to the configured mode
+    pub mode: Option<String>,
+    /// Check every item, not only those never checked, changed or due a recheck
+    #[serde(default)]
+    pub all: bool,
+}
+
+/// POST /api/admin/integrity/check?library_id={id}&mode={mode}&all=true —
+/// start a background pass over one library or all of them (admin only).
+pub async fn start_check(
+    State(state): State<AppState>,
+    auth_user: Option<Extension<AuthUser>>,
+    Query(params): Query<StartCheckQuery>,
+) -> Result<impl IntoResponse, ApiError> {
+    ensure_admin_if_present(&state, auth_user.as_ref()).await?;
+    let mode = parse_mode(&state, params.mode.as_deref())?;
+    let recheck_days = if params.all {
+        0
+    } else {
+        state.config.integrity.recheck_days
+    };
+    let queued = state
+        .integrity
+        .start_pass(params.library_id, mode, recheck_days, None)
+        .await?
+        .ok_or_else(|| ApiError::bad_request("An integrity check is already running"))?;
+    Ok((
+        StatusCode::ACCEPTED,
+        Json(serde_json::json!({ "queued": queued, "mode": mode })),
+    ))
+}
+
+/// GET /api/media/{id}/integrity — latest check of a media item (admin only).
+pub async fn get_media_integrity(
+    State(state): State<AppState>,
+    auth_user: Option<Extension<AuthUser>>,
+    Path(id): Path<String>,
+) -> Result<impl IntoResponse, ApiError> {
+    ensure_admin_if_present(&state, auth_user.as_ref()).await?;
+    let row = integrity_repo::get_integrity(&state.db.read, &id)
+        .await?
+        .ok_or_else(|| ApiError::not_found(format!("Media item '{id}' has not been checked")))?;
+    Ok(Json(row))
+}
+
+#[derive(Deserialize)]
+pub struct CheckMediaQuery {
+    /// `sample` or `full`; defaults to the configured mode
+    pub mode: Option<String>,
+}
+
+/// POST /api/media/{id}/integrity?mode={mode} — check a media item now and
+/// return the stored result. Waits for a running check to finish first (admin only).
+pub async fn check_media_integrity(
+    State(state): State<AppState>,
+    auth_user: Option<Extension<AuthUser>>,
+    Path(id): Path<String>,
+    Query(params): Query<CheckMediaQuery>,
+) -> Result<impl IntoResponse, ApiError> {
+    ensure_admin_if_present(&state, auth_user.as_ref()).await?;
+    let mode = parse_mode(&state, params.mode.as_deref())?;
+    let item = media_repo::get_media_item(&state.db.read, &id)
+        .await?
+        .ok_or_else(|| ApiError::not_found(format!("Media item '{id}' not found")))?;
+    let target = IntegrityTarget {
+        id: item.id,
+        file_path: item.file_path,
+        file_size: item.file_size,
+        duration_ms: item.duration_ms,
+    };
+    state.integrity.check(&target, mode).await?;
+    let row = integrity_repo::get_integrity(&state.db.read, &id)
+        .await?
+        .ok_or_else(|| ApiError::internal("Integrity check result was not stored"))?;
+    Ok(Json(row))
+}
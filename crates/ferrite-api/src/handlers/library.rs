@@ -1,18 +1,34 @@
+use crate::auth::AuthUser;
 use crate::error::ApiError;
+use crate::handlers::system::ensure_admin_if_present;
 use crate::state::AppState;
-use axum::extract::{Path, State};
+use axum::extract::{Path, Query, State};
 use axum::http::StatusCode;
 use axum::response::IntoResponse;
-use axum::Json;
-use ferrite_db::{library_repo, media_repo};
+use axum::{Extension, Json};
+use ferrite_db::{library_repo, media_repo, stats_repo, unmatched_repo};
 use serde::Deserialize;
 use std::sync::Arc;
 
 #[derive(Deserialize)]
 pub struct CreateLibraryRequest {
     pub name: String,
-    pub path: String,
+    /// Single root folder (kept for older clients); `paths` takes precedence
+    pub path: Option<String>,
+    /// Root folders, primary first
+    pub paths: Option<Vec<String>>,
     pub library_type: String,
+    /// Glob patterns excluded from scans, e.g. `*sample*` or `Extras/`
+    #[serde(default)]
+    pub ignore_patterns: Vec<String>,
+}
+
+#[derive(Deserialize)]
+pub struct UpdateLibraryRequest {
+    pub name: Option<String>,
+    pub paths: Option<Vec<String>>,
+    pub ignore_patterns: Option<Vec<String>>,
+    pub scan_interval_minutes: Option<u32>,
 }
 
 pub async fn list_libraries(State(state): State<AppState>) -> Result<impl IntoResponse, ApiError> {
@@ -29,14 +45,24 @@ pub async fn create_library(
         "music" => ferrite_core::media::LibraryType::Music,
         _ => ferrite_core::media::LibraryType::Movie,
     };
+    let paths = clean_paths(req.paths.or(req.path.map(|p| vec![p])).unwrap_or_default())?;
+    ensure_paths_free(&state, &paths, None).await?;
+    let ignore_patterns = clean_patterns(req.ignore_patterns);
 
-    let lib = library_repo::create_library(&state.db.write, &req.name, &req.path, lib_type).await?;
+    let lib = library_repo::create_library(
+        &state.db.write,
+        &req.name,
+        &paths,
+        lib_type,
+        &ignore_patterns,
+    )
+    .await?;
 
-    // Register the new library path with the filesystem watcher so future
+    // Register the new library folders with the filesystem watcher so future
     // file changes are detected automatically.
     if let Some(ref handle) = state.watcher_handle {
         handle
-            .watch_library(lib.id.to_string(), std::path::PathBuf::from(&lib.path))
+            .watch_library(lib.id.to_string(), ferrite_scanner::library_roots(&lib))
             .await;
     }
 
@@ -44,59 +70,174 @@ pub async fn create_library(
     // discovered immediately without requiring a separate manual scan.
     let lib_id = lib.id.to_string();
     if let Some(scan_state) = state.scan_registry.try_start(lib_id.clone()) {
-        let db = state.db.clone();
-        let config = state.config.clone();
-        tokio::spawn(async move {
-            let ffprobe_path = config.transcode.ffprobe_path.clone();
-            let ffmpeg_path = config.transcode.ffmpeg_path.clone();
-            let concurrent_probes = config.scanner.concurrent_probes;
-            let subtitle_cache_dir = config.scanner.subtitle_cache_dir.clone();
-
-            let (tmdb_provider, image_cache) =
-                if let Some(ref api_key) = config.metadata.tmdb_api_key {
-                    let provider: Arc<dyn ferrite_metadata::provider::MetadataProvider> =
-                        Arc::new(ferrite_metadata::tmdb::TmdbProvider::new(
-                            api_key.clone(),
-                            config.metadata.rate_limit_per_second,
-                        ));
-                    let cache = Arc::new(ferrite_metadata::image_cache::ImageCache::new(
-                        config.metadata.image_cache_dir.clone(),
-                    ));
-                    (Some(provider), Some(cache))
-                } else {
-                    (None, None)
-                };
-
-            match ferrite_scanner::scan_library(
-                &db.write, // scanner needs write access
-                &lib_id,
-                &ffprobe_path,
-                &ffmpeg_path,
-                concurrent_probes,
-                &subtitle_cache_dir,
-                scan_state,
-                tmdb_provider,
-                image_cache,
-            )
-            .await
-            {
-                Ok(count) => {
-                    tracing::info!(
-                        "Initial scan complete for new library {}: {} items",
-                        lib_id,
-                        count
-                    );
-                }
-                Err(e) => {
-                    tracing::error!("Initial scan failed for new library {}: {}", lib_id, e);
-                }
-            }
-        });
+        spawn_scan(&state, lib_id, scan_state);
     }
 
     Ok((StatusCode::CREATED, Json(lib)))
 }
 
+/// PUT /api/libraries/{id} — Rename a library or change its folders, ignore
+/// patterns and scan interval. Changing folders or patterns triggers a rescan,
+/// which drops media that is no longer included.
+pub async fn update_library(
+    State(state): State<AppState>,
+    auth_user: Option<Extension<AuthUser>>,
+    Path(id): Path<String>,
+    Json(req): Json<UpdateLibraryRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    ensure_admin_if_present(&state, auth_user.as_ref()).await?;
+    let current = library_repo::get_library(&state.db.read, &id)
+        .await
+        .map_err(|_| ApiError::not_found(format!("Library '{id}' not found")))?;
+
+    let name = req.name.map(|n| n.trim().to_string());
+    if name.as_deref().is_some_and(str::is_empty) {
+        return Err(ApiError::bad_request("Library name cannot be empty"));
+    }
+    let paths = req.paths.map(clean_paths).transpose()?;
+    if let Some(paths) = &paths {
+        ensure_paths_free(&state, paths, Some(&id)).await?;
+    }
+    let ignore_patterns = req.ignore_patterns.map(clean_patterns);
+    if req.scan_interval_minutes == Some(0) {
+        return Err(ApiError::bad_request(
+            "scan_interval_minutes must be at least 1",
+        ));
+    }
+
+    let lib = library_repo::update_library(
+        &state.db.write,
+        &id,
+        &library_repo::LibraryUpdate {
+            name: name.as_deref(),
+            paths: paths.as_deref(),
+            ignore_patterns: ignore_patterns.as_deref(),
+            scan_interval_minutes: req.scan_interval_minutes,
+        },
+    )
+    .await?;
+
+    let paths_changed = lib.paths != current.paths;
+    if paths_changed {
+        if let Some(ref handle) = state.watcher_handle {
+            handle.unwatch_library(id.clone()).await;
+            handle
+                .watch_library(id.clone(), ferrite_scanner::library_roots(&lib))
+                .await;
+        }
+    }
+    if paths_changed || lib.ignore_patterns != current.ignore_patterns {
+        match state.scan_registry.try_start(id.clone()) {
+            Some(scan_state) => spawn_scan(&state, id, scan_state),
+            None => tracing::info!(
+                "Library {} changed during a scan; the next scan applies the new settings",
+                id
+            ),
+        }
+    }
+
+    Ok(Json(lib))
+}
+
+/// Trim and validate root folders: at least one, no duplicates and none
+/// nested inside another.
+fn clean_paths(paths: Vec<String>) -> Result<Vec<String>, ApiError> {
+    let paths: Vec<String> = paths
+        .into_iter()
+        .map(|p| p.trim().to_string())
+        .filter(|p| !p.is_empty())
+        .collect();
+    if paths.is_empty() {
+        return Err(ApiError::bad_request("A library needs at least one folder"));
+    }
+    for (i, a) in paths.iter().enumerate() {
+        for b in &paths[i + 1..] {
+            let (a_path, b_path) = (std::path::Path::new(a), std::path::Path::new(b));
+            if a_path.starts_with(b_path) || b_path.starts_with(a_path) {
+                return Err(ApiError::bad_request(format!(
+                    "Folders '{a}' and '{b}' overlap"
+                )));
+            }
+        }
+    }
+    Ok(paths)
+}
+
+fn clean_patterns(patterns: Vec<String>) -> Vec<String> {
+    patterns
+        .into_iter()
+        .map(|p| p.trim().to_string())
+        .filter(|p| !p.is_empty())
+        .collect()
+}
+
+async fn ensure_paths_free(
+    state: &AppState,
+    paths: &[String],
+    library_id: Option<&str>,
+) -> Result<(), ApiError> {
+    match library_repo::find_taken_path(&state.db.read, paths, library_id).await? {
+        Some(path) => Err(ApiError::bad_request(format!(
+            "'{path}' overlaps a folder of another library"
+        ))),
+        None => Ok(()),
+    }
+}
+
+/// Run a full scan of a library in the background.
+fn spawn_scan(
+    state: &AppState,
+    lib_id: String,
+    scan_state: Arc<ferrite_scanner::progress::ScanState>,
+) {
+    let db = state.db.clone();
+    let config = state.config.clone();
+    tokio::spawn(async move {
+        let ffprobe_path = config.transcode.ffprobe_path.clone();
+        let ffmpeg_path = config.transcode.ffmpeg_path.clone();
+        let concurrent_probes = config.scanner.concurrent_probes;
+        let subtitle_cache_dir = config.scanner.subtitle_cache_dir.clone();
+
+        // Build optional TMDB provider for inline enrichment
+        let (tmdb_provider, image_cache) = if let Some(ref api_key) = config.metadata.tmdb_api_key {
+            let provider: Arc<dyn ferrite_metadata::provider::MetadataProvider> = Arc::new(
+                ferrite_metadata::tmdb::TmdbProvider::new(
+                    api_key.clone(),
+                    config.metadata.rate_limit_per_second,
+                )
+                .with_languages(&config.metadata.languages),
+            );
+            let cache = Arc::new(ferrite_metadata::image_cache::ImageCache::new(
+                config.metadata.image_cache_dir.clone(),
+            ));
+            (Some(provider), Some(cache))
+        } else {
+            (None, None)
+        };
+
+        match ferrite_scanner::scan_library(
+            &db.write, // scanner needs write access
+            &lib_id,
+            &ffprobe_path,
+            &ffmpeg_path,
+            concurrent_probes,
+            &subtitle_cache_dir,
+            scan_state,
+            tmdb_provider,
+            image_cache,
+        )
+        .await
+        {
+            Ok(count) => {
+                tracing::info!("Scan complete for library {}: {} new items", lib_id, count);
+            }
+            Err(e) => {
+                tracing::error!("Scan failed for library {}: {}", lib_id, e);
+            }
+        }
+    });
+}
+
 pub async fn delete_library(
     State(state): State<AppState>,
     Path(id): Path<String>,
@@ -106,7 +247,10 @@ pub async fn delete_library(
         handle.unwatch_library(id.clone()).await;
     }
 
-    // 2. Clear any in-progress or completed scan state for this library.
+    // 2. Stop a running scan and clear the library's scan state.
+    if let Some(scan_state) = state.scan_registry.get(&id) {
+        scan_state.cancel();
+    }
     state.scan_registry.remove(&id);
 
     // 3. Collect media item IDs *before* deleting rows — needed for cache cleanup.
@@ -163,57 +307,112 @@ pub async fn scan_library(
         .try_start(id.clone())
         .ok_or_else(|| ApiError::bad_request("Scan already in progress for this library"))?;
 
-    let db = state.db.clone();
-    let config = state.config.clone();
-    let lib_id = id.clone();
+    spawn_scan(&state, id, scan_state);
 
-    tokio::spawn(async move {
-        let ffprobe_path = config.transcode.ffprobe_path.clone();
-        let ffmpeg_path = config.transcode.ffmpeg_path.clone();
-        let concurrent_probes = config.scanner.concurrent_probes;
-        let subtitle_cache_dir = config.scanner.subtitle_cache_dir.clone();
+    Ok((
+        StatusCode::ACCEPTED,
+        Json(serde_json::json!({ "status": "scanning" })),
+    ))
+}
 
-        // Build optional TMDB provider for inline enrichment
-        let (tmdb_provider, image_cache) = if let Some(ref api_key) = config.metadata.tmdb_api_key {
-            let provider: Arc<dyn ferrite_metadata::provider::MetadataProvider> =
-                Arc::new(ferrite_metadata::tmdb::TmdbProvider::new(
-                    api_key.clone(),
-                    config.metadata.rate_limit_per_second,
-                ));
-            let cache = Arc::new(ferrite_metadata::image_cache::ImageCache::new(
-                config.metadata.image_cache_dir.clone(),
-            ));
-            (Some(provider), Some(cache))
-        } else {
-            (None, None)
-        };
+#[derive(Deserialize)]
+pub struct DryRunQuery {
+    /// `all` also probes files that are already indexed and unchanged
+    pub probe: Option<String>,
+}
 
-        match ferrite_scanner::scan_library(
-            &db.write, // scanner needs write access
-            &lib_id,
-            &ffprobe_path,
-            &ffmpeg_path,
-            concurrent_probes,
-            &subtitle_cache_dir,
-            scan_state,
-            tmdb_provider,
-            image_cache,
-        )
+/// POST /api/admin/libraries/{id}/scan/dry-run — walk and parse a library
+/// without indexing anything, reporting per file how it parses, probe
+/// failures and the TMDB candidate enrichment would pick (admin only). Probes
+/// and searches are capped so large libraries still answer in one request;
+/// `truncated` says when the cap was hit.
+pub async fn dry_run_scan(
+    State(state): State<AppState>,
+    auth_user: Option<Extension<AuthUser>>,
+    Path(id): Path<String>,
+    Query(params): Query<DryRunQuery>,
+) -> Result<impl IntoResponse, ApiError> {
+    ensure_admin_if_present(&state, auth_user.as_ref()).await?;
+    library_repo::get_library(&state.db.read, &id)
         .await
-        {
-            Ok(count) => {
-                tracing::info!("Scan complete for library {}: {} new items", lib_id, count);
-            }
-            Err(e) => {
-                tracing::error!("Scan failed for library {}: {}", lib_id, e);
-            }
-        }
+        .map_err(|_| ApiError::not_found(format!("Library '{id}' not found")))?;
+
+    let config = &state.config;
+    let provider = config.metadata.tmdb_api_key.as_ref().map(|api_key| {
+        ferrite_metadata::tmdb::TmdbProvider::new(
+            api_key.clone(),
+            config.metadata.rate_limit_per_second,
+        )
+        .with_languages(&config.metadata.languages)
     });
+    let report = ferrite_scanner::dry_run::dry_run_library(
+        &state.db.read,
+        &id,
+        &config.transcode.ffprobe_path,
+        config.scanner.concurrent_probes,
+        params.probe.as_deref() == Some("all"),
+        provider
+            .as_ref()
+            .map(|p| p as &dyn ferrite_metadata::provider::MetadataProvider),
+        ferrite_scanner::dry_run::DryRunLimits::default(),
+    )
+    .await?;
+    Ok(Json(report))
+}
 
-    Ok((
-        StatusCode::ACCEPTED,
-        Json(serde_json::json!({ "status": "scanning" })),
-    ))
+#[derive(Deserialize)]
+pub struct UnmatchedQuery {
+    pub library_id: Option<String>,
+}
+
+/// GET /api/admin/unmatched?library_id={id} — files and shows a scan indexed
+/// but couldn't identify: unparsed episodes, titles without a TMDB match and
+/// episodes still waiting for provider numbering (admin only).
+pub async fn list_unmatched(
+    State(state): State<AppState>,
+    auth_user: Option<Extension<AuthUser>>,
+    Query(params): Query<UnmatchedQuery>,
+) -> Result<impl IntoResponse, ApiError> {
+    ensure_admin_if_present(&state, auth_user.as_ref()).await?;
+    let items =
+        unmatched_repo::list_unmatched_items(&state.db.read, params.library_id.as_deref()).await?;
+    Ok(Json(items))
+}
+
+#[derive(Deserialize)]
+pub struct LibraryStatsQuery {
+    pub library_id: Option<String>,
+    /// Length of the largest-files list (default 20, at most 200)
+    pub largest: Option<u32>,
+}
+
+/// GET /api/admin/stats?library_id={id}&largest={n} — size and runtime per
+/// library, format breakdowns (resolution, codecs, HDR, container, bitrate),
+/// the largest files and how many files each client profile would need
+/// transcoded, over one library or all of them (admin only).
+pub async fn library_stats(
+    State(state): State<AppState>,
+    auth_user: Option<Extension<AuthUser>>,
+    Query(params): Query<LibraryStatsQuery>,
+) -> Result<impl IntoResponse, ApiError> {
+    ensure_admin_if_present(&state, auth_user.as_ref()).await?;
+    let library_id = params.library_id.as_deref();
+    let mut libraries = stats_repo::library_totals(&state.db.read).await?;
+    if let Some(id) = library_id {
+        libraries.retain(|l| l.library_id == id);
+        if libraries.is_empty() {
+            return Err(ApiError::not_found(format!("Library '{id}' not found")));
+        }
+    }
+    let rows = stats_repo::list_media_formats(&state.db.read, library_id).await?;
+    let formats = crate::library_stats::aggregate(&rows);
+    let largest = params.largest.unwrap_or(20).min(200);
+    let largest_files = stats_repo::largest_files(&state.db.read, library_id, largest).await?;
+    Ok(Json(serde_json::json!({
+        "libraries": libraries,
+        "formats": formats,
+        "largest_files": largest_files,
+    })))
 }
 
 pub async fn scan_status(
@@ -232,6 +431,8 @@ pub async fn scan_status(
             items_enriched: 0,
             errors: 0,
             current_item: String::new(),
+            paused: false,
+            cancelling: false,
             elapsed_seconds: 0,
             phase_elapsed_seconds: 0,
             estimated_remaining_seconds: None,
@@ -239,3 +440,60 @@ pub async fn scan_status(
         })),
     }
 }
+
+/// The running scan of a library, for the scan control endpoints.
+async fn running_scan(
+    state: &AppState,
+    id: &str,
+) -> Result<Arc<ferrite_scanner::progress::ScanState>, ApiError> {
+    match state.scan_registry.get(id) {
+        Some(scan_state) if scan_state.is_running().await => Ok(scan_state),
+        _ => Err(ApiError::bad_request(
+            "No scan in progress for this library",
+        )),
+    }
+}
+
+/// POST /api/libraries/{id}/scan/cancel — stop a running or paused scan at
+/// its next checkpoint (admin only). Items committed so far stay indexed;
+/// files still waiting for subtitles are picked up by the next scan.
+pub async fn cancel_scan(
+    State(state): State<AppState>,
+    auth_user: Option<Extension<AuthUser>>,
+    Path(id): Path<String>,
+) -> Result<impl IntoResponse, ApiError> {
+    ensure_admin_if_present(&state, auth_user.as_ref()).await?;
+    let scan_state = running_scan(&state, &id).await?;
+    scan_state.cancel();
+    tracing::info!("Cancelling scan of library {}", id);
+    Ok((StatusCode::ACCEPTED, Json(scan_state.to_progress().await)))
+}
+
+/// POST /api/libraries/{id}/scan/pause — hold a running scan at its next
+/// checkpoint until it is resumed (admin only).
+pub async fn pause_scan(
+    State(state): State<AppState>,
+    auth_user: Option<Extension<AuthUser>>,
+    Path(id): Path<String>,
+) -> Result<impl IntoResponse, ApiError> {
+    ensure_admin_if_present(&state, auth_user.as_ref()).await?;
+    let scan_state = running_scan(&state, &id).await?;
+    if !scan_state.pause() {
+        return Err(ApiError::bad_request("Scan is being cancelled"));
+    }
+    Ok(Json(scan_state.to_progress().await))
+}
+
+/// POST /api/libraries/{id}/scan/resume — continue a paused scan (admin only).
+pub async fn resume_scan(
+    State(state): State<AppState>,
+    auth_user: Option<Extension<AuthUser>>,
+    Path(id): Path<String>,
+) -> Result<impl IntoResponse, ApiError> {
+    ensure_admin_if_present(&state, auth_user.as_ref()).await?;
+    let scan_state = running_scan(&state, &id).await?;
+    if !scan_state.resume() {
+        return Err(ApiError::bad_request("Scan is being cancelled"));
+    }
+    Ok(Json(scan_state.to_progress().await))
+}
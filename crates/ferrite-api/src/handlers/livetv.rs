@@ -0,0 +1,380 @@
+use crate::auth::AuthUser;
+use crate::error::ApiError;
+use crate::handlers::stream::{
+    admit_transcode, claim_user_stream_slot, resolve_hls_token, resolve_remote_bitrate_cap,
+    transcode_admission, HlsQuery,
+};
+use crate::state::AppState;
+use axum::extract::{ConnectInfo, Path, Query, State};
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::response::IntoResponse;
+use axum::{Extension, Json};
+use chrono::{Duration, NaiveDateTime, Utc};
+use ferrite_db::livetv_repo::{self, ChannelRow, ProgramRow, RecordingRow};
+use ferrite_db::user_repo;
+use ferrite_livetv::xmltv::TIME_FORMAT;
+use ferrite_stream::hls::HlsSessionManager;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use tracing::warn;
+
+/// Longest guide window a single request may ask for.
+const MAX_GUIDE_HOURS: i64 = 7 * 24;
+
+#[derive(Deserialize)]
+pub struct GuideQuery {
+    /// Window start, UTC `YYYY-MM-DD HH:MM:SS`. Defaults to now.
+    pub start: Option<String>,
+    /// Window length in hours. Defaults to 6.
+    pub hours: Option<i64>,
+}
+
+#[derive(Serialize)]
+pub struct GuideChannel {
+    #[serde(flatten)]
+    pub channel: ChannelRow,
+    pub programs: Vec<ProgramRow>,
+}
+
+#[derive(Deserialize)]
+pub struct CreateRecordingRequest {
+    /// Record a guide programme. Channel, title and times are taken from it.
+    pub program_id: Option<i64>,
+    /// Manual recording: channel plus explicit window (UTC `YYYY-MM-DD HH:MM:SS`).
+    pub channel_id: Option<String>,
+    pub title: Option<String>,
+    pub start_time: Option<String>,
+    pub end_time: Option<String>,
+}
+
+fn require_enabled(state: &AppState) -> Result<(), ApiError> {
+    if state.livetv.enabled() {
+        Ok(())
+    } else {
+        Err(ApiError::service_unavailable("Live TV is not enabled"))
+    }
+}
+
+fn parse_time(value: &str, field: &str) -> Result<NaiveDateTime, ApiError> {
+    NaiveDateTime::parse_from_str(value.trim(), TIME_FORMAT).map_err(|_| {
+        ApiError::bad_request(format!(
+            "Invalid {field} '{value}' (expected YYYY-MM-DD HH:MM:SS UTC)"
+        ))
+    })
+}
+
+/// Resolve a guide window from the query, clamped to [`MAX_GUIDE_HOURS`].
+fn guide_window(query: &GuideQuery) -> Result<(String, String), ApiError> {
+    let start = match query.start.as_deref() {
+        Some(s) => parse_time(s, "start")?,
+        None => Utc::now().naive_utc(),
+    };
+    let hours = query.hours.unwrap_or(6).clamp(1, MAX_GUIDE_HOURS);
+    let end = start + Duration::hours(hours);
+    Ok((
+        start.format(TIME_FORMAT).to_string(),
+        end.format(TIME_FORMAT).to_string(),
+    ))
+}
+
+async fn load_channel(state: &AppState, id: &str) -> Result<ChannelRow, ApiError> {
+    livetv_repo::get_channel(&state.db.read, id)
+        .await?
+        .ok_or_else(|| ApiError::not_found(format!("Channel '{id}' not found")))
+}
+
+/// GET /api/livetv/channels — list imported channels.
+pub async fn list_channels(State(state): State<AppState>) -> Result<impl IntoResponse, ApiError> {
+    require_enabled(&state)?;
+    Ok(Json(livetv_repo::list_channels(&state.db.read).await?))
+}
+
+/// GET /api/livetv/channels/{id}/guide — programmes on one channel.
+pub async fn channel_guide(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Query(query): Query<GuideQuery>,
+) -> Result<impl IntoResponse, ApiError> {
+    require_enabled(&state)?;
+    let channel = load_channel(&state, &id).await?;
+    let (from, to) = guide_window(&query)?;
+    let programs = match channel.xmltv_id.as_deref() {
+        Some(xmltv_id) => {
+            livetv_repo::list_channel_programs(&state.db.read, xmltv_id, &from, &to).await?
+        }
+        None => Vec::new(),
+    };
+    Ok(Json(GuideChannel { channel, programs }))
+}
+
+/// GET /api/livetv/guide — programme grid for all channels.
+pub async fn guide(
+    State(state): State<AppState>,
+    Query(query): Query<GuideQuery>,
+) -> Result<impl IntoResponse, ApiError> {
+    require_enabled(&state)?;
+    let (from, to) = guide_window(&query)?;
+    let channels = livetv_repo::list_channels(&state.db.read).await?;
+
+    let mut by_xmltv: HashMap<String, Vec<ProgramRow>> = HashMap::new();
+    for program in livetv_repo::list_programs_in_window(&state.db.read, &from, &to).await? {
+        by_xmltv
+            .entry(program.xmltv_id.clone())
+            .or_default()
+            .push(program);
+    }
+
+    let grid: Vec<GuideChannel> = channels
+        .into_iter()
+        .map(|channel| {
+            let programs = channel
+                .xmltv_id
+                .as_deref()
+                .and_then(|id| by_xmltv.get(id).cloned())
+                .unwrap_or_default();
+            GuideChannel { channel, programs }
+        })
+        .collect();
+    Ok(Json(grid))
+}
+
+/// POST /api/livetv/refresh — re-import channels and guide now (admin only).
+pub async fn refresh(
+    State(state): State<AppState>,
+    auth_user: Option<Extension<AuthUser>>,
+) -> Result<impl IntoResponse, ApiError> {
+    require_enabled(&state)?;
+    if let Some(caller) = auth_user.as_ref() {
+        let caller_user = user_repo::get_user_by_id(&state.db.read, &caller.user_id)
+            .await?
+            .ok_or_else(|| ApiError::unauthorized("User not found"))?;
+        if caller_user.is_admin == 0 {
+            return Err(ApiError::forbidden("Only admins can refresh live TV"));
+        }
+    }
+
+    let summary = state.livetv.refresh().await.map_err(|e| {
+        warn!("Live TV refresh failed: {:#}", e);
+        ApiError::internal(format!("Live TV refresh failed: {e:#}"))
+    })?;
+    Ok(Json(summary))
+}
+
+/// GET /api/livetv/channels/{id}/hls/master.m3u8 — watch a channel live.
+///
+/// The tuner stream is relayed through the HLS session manager under the
+/// media id `livetv-{id}`, so variant playlists, segments and
+/// `DELETE /api/stream/livetv-{id}/hls` work exactly as for library items.
+/// Only a single variant is produced, since each variant would hold its own
+/// tuner connection.
+pub async fn channel_master_playlist(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Query(query): Query<HlsQuery>,
+    auth_user: Option<Extension<AuthUser>>,
+    connect_info: Option<Extension<ConnectInfo<SocketAddr>>>,
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, ApiError> {
+    require_enabled(&state)?;
+    let channel = load_channel(&state, &id).await?;
+
+    let media_id = format!("livetv-{}", channel.id);
+    let owner_key = HlsSessionManager::owner_key(&media_id, query.playback_session_id.as_deref());
+    let token = resolve_hls_token(query.token.as_deref(), &headers);
+
+    let auth_user = auth_user.map(|e| e.0);
+    if let Some(user) = auth_user.as_ref() {
+        claim_user_stream_slot(&state, user, &owner_key).await?;
+    }
+
+    let existing = state.hls_sessions.get_variant_sessions_owned(&owner_key);
+    let sessions = if existing.is_empty() {
+        let max_bitrate_kbps = resolve_remote_bitrate_cap(
+            &state,
+            auth_user.as_ref(),
+            connect_info.map(|c| c.0 .0),
+            &headers,
+        )
+        .await;
+        let admission = transcode_admission(
+            &state,
+            auth_user.as_ref(),
+            owner_key.clone(),
+            &media_id,
+            "livetv",
+            1,
+            1,
+        )
+        .await;
+        let permit = admit_transcode(&state, admission, true).await?;
+        let sessions = state
+            .hls_sessions
+            .create_single_variant_session_owned(
+                &owner_key,
+                &media_id,
+                std::path::Path::new(&channel.stream_url),
+                None,
+                None,
+                None,
+                None,
+                0.0,
+                0.0,
+                None,
+                None,
+                query.audio_stream,
+                None,
+                None,
+                None,
+                None,
+                None,
+                max_bitrate_kbps,
+                None,
+                &state.config.transcode.encoding,
+                false,
+            )
+            .await
+            .map_err(|e| {
+                warn!("Failed to start live TV relay for {}: {}", channel.name, e);
+                ApiError::internal(e.to_string())
+            })?;
+        permit.retain();
+        sessions
+    } else {
+        for s in &existing {
+            s.touch();
+        }
+        existing
+    };
+
+    let playlist =
+        state
+            .hls_sessions
+            .generate_master_playlist(&sessions, &media_id, token.as_deref());
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/vnd.apple.mpegurl"),
+            (header::CACHE_CONTROL, "no-store"),
+        ],
+        playlist,
+    ))
+}
+
+/// GET /api/livetv/recordings — list the current user's recordings.
+pub async fn list_recordings(
+    State(state): State<AppState>,
+    auth_user: Option<Extension<AuthUser>>,
+) -> Result<impl IntoResponse, ApiError> {
+    require_enabled(&state)?;
+    let user_id = auth_user.as_ref().map(|u| u.user_id.as_str());
+    Ok(Json(
+        livetv_repo::list_recordings(&state.db.read, user_id).await?,
+    ))
+}
+
+/// POST /api/livetv/recordings — schedule a recording of a guide programme
+/// or of a channel for an explicit time window (admin only).
+pub async fn create_recording(
+    State(state): State<AppState>,
+    auth_user: Option<Extension<AuthUser>>,
+    Json(body): Json<CreateRecordingRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    require_enabled(&state)?;
+    if let Some(caller) = auth_user.as_ref() {
+        let caller_user = user_repo::get_user_by_id(&state.db.read, &caller.user_id)
+            .await?
+            .ok_or_else(|| ApiError::unauthorized("User not found"))?;
+        if caller_user.is_admin == 0 {
+            return Err(ApiError::forbidden("Only admins can schedule recordings"));
+        }
+    }
+    let user_id = auth_user.as_ref().map(|u| u.user_id.as_str());
+
+    let (channel, title, start, end) = match body.program_id {
+        Some(program_id) => {
+            let program = livetv_repo::get_program(&state.db.read, program_id)
+                .await?
+                .ok_or_else(|| ApiError::not_found(format!("Program {program_id} not found")))?;
+            let channel = livetv_repo::list_channels(&state.db.read)
+                .await?
+                .into_iter()
+                .find(|c| c.xmltv_id.as_deref() == Some(program.xmltv_id.as_str()))
+                .ok_or_else(|| ApiError::not_found("Program's channel is no longer available"))?;
+            let title = match program.subtitle.as_deref() {
+                Some(sub) => format!("{} - {}", program.title, sub),
+                None => program.title.clone(),
+            };
+            (
+                channel,
+                title,
+                parse_time(&program.start_time, "start_time")?,
+                parse_time(&program.end_time, "end_time")?,
+            )
+        }
+        None => {
+            let channel_id = body.channel_id.as_deref().ok_or_else(|| {
+                ApiError::bad_request("Either program_id or channel_id is required")
+            })?;
+            let channel = load_channel(&state, channel_id).await?;
+            let start = parse_time(
+                body.start_time
+                    .as_deref()
+                    .ok_or_else(|| ApiError::bad_request("start_time is required"))?,
+                "start_time",
+            )?;
+            let end = parse_time(
+                body.end_time
+                    .as_deref()
+                    .ok_or_else(|| ApiError::bad_request("end_time is required"))?,
+                "end_time",
+            )?;
+            let title = body
+                .title
+                .as_deref()
+                .map(str::trim)
+                .filter(|t| !t.is_empty())
+                .map(str::to_string)
+                .unwrap_or_else(|| channel.name.clone());
+            (channel, title, start, end)
+        }
+    };
+
+    if end <= start {
+        return Err(ApiError::bad_request("end_time must be after start_time"));
+    }
+    if end <= Utc::now().naive_utc() {
+        return Err(ApiError::bad_request("Recording window has already ended"));
+    }
+
+    let row: RecordingRow = livetv_repo::create_recording(
+        &state.db.write,
+        user_id,
+        &channel.id,
+        &channel.name,
+        &channel.stream_url,
+        &title,
+        &start.format(TIME_FORMAT).to_string(),
+        &end.format(TIME_FORMAT).to_string(),
+    )
+    .await?;
+    Ok((StatusCode::CREATED, Json(row)))
+}
+
+/// DELETE /api/livetv/recordings/{id} — cancel a scheduled recording or stop
+/// one in progress. Files already written to the recordings directory are kept.
+pub async fn delete_recording(
+    State(state): State<AppState>,
+    auth_user: Option<Extension<AuthUser>>,
+    Path(id): Path<String>,
+) -> Result<impl IntoResponse, ApiError> {
+    require_enabled(&state)?;
+    let user_id = auth_user.as_ref().map(|u| u.user_id.as_str());
+    let row = livetv_repo::get_recording(&state.db.read, &id)
+        .await?
+        .filter(|r| r.user_id.as_deref() == user_id)
+        .ok_or_else(|| ApiError::not_found(format!("Recording '{id}' not found")))?;
+
+    state.livetv.stop_recording(&row.id);
+    livetv_repo::delete_recording(&state.db.write, &row.id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
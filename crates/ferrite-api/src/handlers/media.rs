@@ -1,10 +1,11 @@
 use crate::auth::AuthUser;
 use crate::error::ApiError;
+use crate::locale;
 use crate::state::AppState;
 use axum::extract::{Path, Query, State};
 use axum::response::IntoResponse;
 use axum::{Extension, Json};
-use ferrite_db::{chapter_repo, movie_repo, stream_repo};
+use ferrite_db::{chapter_repo, extra_repo, media_repo, movie_repo, stream_repo, tv_repo};
 use serde::Deserialize;
 
 fn extract_user_id(auth_user: &Option<AuthUser>) -> Option<&str> {
@@ -40,9 +41,13 @@ pub async fn list_media(
         sort_dir: query.dir.as_deref(),
         page: page as i64,
         per_page: per_page as i64,
+        ..Default::default()
     };
 
-    let items = movie_repo::list_movies_with_media(&state.db.read, &mq, user_id).await?;
+    let mut items = movie_repo::list_movies_with_media(&state.db.read, &mq, user_id).await?;
+    if let Some(lang) = locale::user_translation_language(&state, user_id).await? {
+        locale::localize_movies(&state, &lang, &mut items).await?;
+    }
     let total = movie_repo::count_movies_with_media(&state.db.read, &mq).await?;
 
     Ok(Json(serde_json::json!({
@@ -61,9 +66,12 @@ pub async fn get_media(
     let user = auth_user.map(|e| e.0);
     let user_id = extract_user_id(&user);
     // Use enriched query with movie metadata
-    let item = movie_repo::get_movie_with_media(&state.db.read, &id, user_id)
+    let mut item = movie_repo::get_movie_with_media(&state.db.read, &id, user_id)
         .await?
         .ok_or_else(|| ApiError::not_found(format!("Media item '{id}' not found")))?;
+    if let Some(lang) = locale::user_translation_language(&state, user_id).await? {
+        locale::localize_movies(&state, &lang, std::slice::from_mut(&mut item)).await?;
+    }
     Ok(Json(item))
 }
 
@@ -84,3 +92,72 @@ pub async fn get_media_chapters(
     let chapters = chapter_repo::get_chapters(&state.db.read, &id).await?;
     Ok(Json(chapters))
 }
+
+/// GET /api/media/{id}/versions — list every version of a movie with its stacked parts
+pub async fn get_media_versions(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<impl IntoResponse, ApiError> {
+    let item = media_repo::get_media_item(&state.db.read, &id)
+        .await?
+        .ok_or_else(|| ApiError::not_found(format!("Media item '{id}' not found")))?;
+
+    let group_id = item
+        .movie_group_id
+        .clone()
+        .unwrap_or_else(|| item.id.clone());
+    let mut versions = media_repo::list_movie_versions(&state.db.read, &group_id).await?;
+    if versions.is_empty() {
+        versions.push(item);
+    }
+
+    let mut out = Vec::with_capacity(versions.len());
+    for version in versions {
+        let parts = media_repo::list_stack_parts(&state.db.read, &version.id).await?;
+        let duration_ms: Option<i64> = parts.iter().map(|p| p.duration_ms).sum();
+        out.push(serde_json::json!({
+            "id": version.id,
+            "is_primary": version.id == group_id,
+            "version_label": version.version_label,
+            "container_format": version.container_format,
+            "video_codec": version.video_codec,
+            "audio_codec": version.audio_codec,
+            "width": version.width,
+            "height": version.height,
+            "bitrate_kbps": version.bitrate_kbps,
+            "duration_ms": duration_ms,
+            "parts": parts.iter().map(|p| serde_json::json!({
+                "id": p.id,
+                "part_number": p.part_number,
+                "file_path": p.file_path,
+                "file_size": p.file_size,
+                "duration_ms": p.duration_ms,
+            })).collect::<Vec<_>>(),
+        }));
+    }
+    Ok(Json(out))
+}
+
+/// GET /api/media/{id}/extras — trailers, featurettes and other extras of a
+/// movie or show. `id` may be any version of a movie, an episode, or a show.
+pub async fn get_media_extras(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<impl IntoResponse, ApiError> {
+    let extras = match media_repo::get_media_item(&state.db.read, &id).await? {
+        Some(item) if item.episode_number.is_some() => {
+            match extra_repo::show_id_for_episode(&state.db.read, &item.id).await? {
+                Some(show_id) => extra_repo::list_extras_for_show(&state.db.read, &show_id).await?,
+                None => Vec::new(),
+            }
+        }
+        Some(item) => extra_repo::list_extras_for_media(&state.db.read, &item.id).await?,
+        None => {
+            tv_repo::get_show(&state.db.read, &id)
+                .await?
+                .ok_or_else(|| ApiError::not_found(format!("Media item '{id}' not found")))?;
+            extra_repo::list_extras_for_show(&state.db.read, &id).await?
+        }
+    };
+    Ok(Json(extras))
+}
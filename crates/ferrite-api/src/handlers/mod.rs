@@ -1,8 +1,13 @@
 pub mod collection;
+pub mod download;
 pub mod image;
+pub mod integrity;
 pub mod library;
+pub mod livetv;
 pub mod media;
+pub mod people;
 pub mod progress;
+pub mod queue;
 pub mod stream;
 pub mod subtitle;
 pub mod system;
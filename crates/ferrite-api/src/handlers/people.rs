@@ -0,0 +1,48 @@
+use crate::error::ApiError;
+use crate::state::AppState;
+use axum::extract::{Path, State};
+use axum::response::IntoResponse;
+use axum::Json;
+use ferrite_db::people_repo::{self, CreditTarget};
+use ferrite_db::{media_repo, tv_repo};
+
+/// GET /api/media/{id}/credits — cast and crew for a movie
+pub async fn media_credits(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<impl IntoResponse, ApiError> {
+    let item = media_repo::get_media_item(&state.db.read, &id)
+        .await?
+        .ok_or_else(|| ApiError::not_found(format!("Media item '{id}' not found")))?;
+    // Metadata (and credits) live on the primary version of a movie group.
+    let movie_id = item.movie_group_id.as_deref().unwrap_or(&item.id);
+    let credits = people_repo::list_credits(&state.db.read, CreditTarget::Movie(movie_id)).await?;
+    Ok(Json(credits))
+}
+
+/// GET /api/shows/{id}/credits — cast and crew for a TV show
+pub async fn show_credits(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<impl IntoResponse, ApiError> {
+    tv_repo::get_show(&state.db.read, &id)
+        .await?
+        .ok_or_else(|| ApiError::not_found(format!("TV show '{id}' not found")))?;
+    let credits = people_repo::list_credits(&state.db.read, CreditTarget::Show(&id)).await?;
+    Ok(Json(credits))
+}
+
+/// GET /api/people/{id} — a person and their filmography within the library
+pub async fn get_person(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<impl IntoResponse, ApiError> {
+    let person = people_repo::get_person(&state.db.read, &id)
+        .await?
+        .ok_or_else(|| ApiError::not_found(format!("Person '{id}' not found")))?;
+    let credits = people_repo::list_person_credits(&state.db.read, &id).await?;
+    Ok(Json(serde_json::json!({
+        "person": person,
+        "credits": credits,
+    })))
+}
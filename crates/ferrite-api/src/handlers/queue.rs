@@ -0,0 +1,337 @@
+use crate::auth::AuthUser;
+use crate::error::ApiError;
+use crate::handlers::collection::{extract_user_id, find_collection, Access};
+use crate::play_queue::{self, PlayQueue, RepeatMode};
+use crate::state::AppState;
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use axum::{Extension, Json};
+use ferrite_db::{collection_repo, media_repo, queue_repo};
+use serde::Deserialize;
+use uuid::Uuid;
+
+/// Upper bound on queue length, so a smart collection over a huge library
+/// doesn't produce an unbounded queue.
+const MAX_QUEUE_ITEMS: usize = 5000;
+
+#[derive(Deserialize)]
+pub struct ReplaceQueueRequest {
+    /// Explicit media items, in order
+    pub media_ids: Option<Vec<String>>,
+    /// Queue every item of a collection or playlist the user can read
+    pub collection_id: Option<String>,
+    /// Index to start at (default 0)
+    pub start_index: Option<usize>,
+    /// Start at this media item instead of an index
+    pub start_media_id: Option<String>,
+    #[serde(default)]
+    pub shuffle: bool,
+    /// "off" (default), "all" or "one"
+    pub repeat: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct AddQueueItemRequest {
+    pub media_id: String,
+    /// Insert right after the current item instead of at the end
+    #[serde(default)]
+    pub play_next: bool,
+}
+
+#[derive(Deserialize)]
+pub struct JumpRequest {
+    pub index: usize,
+}
+
+#[derive(Deserialize)]
+pub struct QueueModeRequest {
+    pub shuffle: Option<bool>,
+    pub repeat: Option<String>,
+}
+
+/// GET /api/queue — The current user's play queue.
+pub async fn get_queue(
+    State(state): State<AppState>,
+    auth_user: Option<Extension<AuthUser>>,
+) -> Result<impl IntoResponse, ApiError> {
+    let user_id = extract_user_id(&state, auth_user.as_deref()).await;
+    let queue = play_queue::load(&state.db, &user_id)
+        .await?
+        .unwrap_or_default();
+    queue_json(&state, &user_id, &queue).await
+}
+
+/// PUT /api/queue — Replace the queue with a list of media items or the
+/// contents of a collection.
+pub async fn replace_queue(
+    State(state): State<AppState>,
+    auth_user: Option<Extension<AuthUser>>,
+    Json(body): Json<ReplaceQueueRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    let user_id = extract_user_id(&state, auth_user.as_deref()).await;
+    let repeat = parse_repeat(body.repeat.as_deref())?.unwrap_or_default();
+
+    let (media_ids, source_collection_id) = match (body.media_ids, body.collection_id) {
+        (Some(ids), None) => {
+            for id in &ids {
+                if media_repo::get_media_item(&state.db.read, id)
+                    .await?
+                    .is_none()
+                {
+                    return Err(ApiError::not_found(format!("Media item '{id}' not found")));
+                }
+            }
+            (ids, None)
+        }
+        (None, Some(collection_id)) => {
+            let ids = collection_media_ids(&state, &user_id, &collection_id).await?;
+            (ids, Some(collection_id))
+        }
+        _ => {
+            return Err(ApiError::bad_request(
+                "Provide exactly one of media_ids or collection_id",
+            ))
+        }
+    };
+    if media_ids.len() > MAX_QUEUE_ITEMS {
+        return Err(ApiError::bad_request(format!(
+            "A queue holds at most {MAX_QUEUE_ITEMS} items"
+        )));
+    }
+
+    let start = match &body.start_media_id {
+        Some(media_id) => media_ids
+            .iter()
+            .position(|id| id == media_id)
+            .ok_or_else(|| ApiError::bad_request("start_media_id is not in the queue"))?,
+        None => body.start_index.unwrap_or(0),
+    };
+    if !media_ids.is_empty() && start >= media_ids.len() {
+        return Err(ApiError::bad_request("start_index is out of range"));
+    }
+
+    let mut queue = PlayQueue::new(media_ids, start);
+    queue.source_collection_id = source_collection_id;
+    queue.repeat = repeat;
+    queue.set_shuffle(body.shuffle, random_index);
+
+    play_queue::save(&state.db, &user_id, &queue).await?;
+    queue_json(&state, &user_id, &queue).await
+}
+
+/// DELETE /api/queue — Clear the queue.
+pub async fn clear_queue(
+    State(state): State<AppState>,
+    auth_user: Option<Extension<AuthUser>>,
+) -> Result<impl IntoResponse, ApiError> {
+    let user_id = extract_user_id(&state, auth_user.as_deref()).await;
+    queue_repo::delete_queue(&state.db.write, &user_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// POST /api/queue/items — Add a media item at the end, or right after the
+/// current item with `play_next`.
+pub async fn add_item(
+    State(state): State<AppState>,
+    auth_user: Option<Extension<AuthUser>>,
+    Json(body): Json<AddQueueItemRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    let user_id = extract_user_id(&state, auth_user.as_deref()).await;
+    media_repo::get_media_item(&state.db.read, &body.media_id)
+        .await?
+        .ok_or_else(|| ApiError::not_found(format!("Media item '{}' not found", body.media_id)))?;
+
+    let queue = play_queue::update(&state.db, &user_id, |queue| {
+        let queue = queue.get_or_insert_with(PlayQueue::default);
+        if queue.items.len() >= MAX_QUEUE_ITEMS {
+            return Err(ApiError::bad_request(format!(
+                "A queue holds at most {MAX_QUEUE_ITEMS} items"
+            )));
+        }
+        if body.play_next {
+            queue.play_next(body.media_id);
+        } else {
+            queue.append(body.media_id);
+        }
+        Ok(queue.clone())
+    })
+    .await?;
+    queue_json(&state, &user_id, &queue).await
+}
+
+/// DELETE /api/queue/items/{index} — Remove the item at a queue index.
+pub async fn remove_item(
+    State(state): State<AppState>,
+    auth_user: Option<Extension<AuthUser>>,
+    Path(index): Path<usize>,
+) -> Result<impl IntoResponse, ApiError> {
+    let user_id = extract_user_id(&state, auth_user.as_deref()).await;
+    let queue = play_queue::update(&state.db, &user_id, |queue| {
+        let queue = require_queue(queue)?;
+        if !queue.remove(index) {
+            return Err(ApiError::not_found(format!(
+                "No queue item at index {index}"
+            )));
+        }
+        Ok(queue.clone())
+    })
+    .await?;
+    queue_json(&state, &user_id, &queue).await
+}
+
+/// POST /api/queue/next — Advance to the next item, honoring repeat.
+/// Returns 404 at the end of a non-repeating queue.
+pub async fn next(
+    State(state): State<AppState>,
+    auth_user: Option<Extension<AuthUser>>,
+) -> Result<impl IntoResponse, ApiError> {
+    let user_id = extract_user_id(&state, auth_user.as_deref()).await;
+    let queue = play_queue::update(&state.db, &user_id, |queue| {
+        let queue = require_queue(queue)?;
+        if !queue.advance() {
+            return Err(ApiError::not_found("End of queue"));
+        }
+        Ok(queue.clone())
+    })
+    .await?;
+    queue_json(&state, &user_id, &queue).await
+}
+
+/// POST /api/queue/previous — Step back to the previous item.
+/// Returns 404 at the start of a non-repeating queue.
+pub async fn previous(
+    State(state): State<AppState>,
+    auth_user: Option<Extension<AuthUser>>,
+) -> Result<impl IntoResponse, ApiError> {
+    let user_id = extract_user_id(&state, auth_user.as_deref()).await;
+    let queue = play_queue::update(&state.db, &user_id, |queue| {
+        let queue = require_queue(queue)?;
+        if !queue.go_back() {
+            return Err(ApiError::not_found("Start of queue"));
+        }
+        Ok(queue.clone())
+    })
+    .await?;
+    queue_json(&state, &user_id, &queue).await
+}
+
+/// POST /api/queue/jump — Make the item at `index` current.
+pub async fn jump(
+    State(state): State<AppState>,
+    auth_user: Option<Extension<AuthUser>>,
+    Json(body): Json<JumpRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    let user_id = extract_user_id(&state, auth_user.as_deref()).await;
+    let queue = play_queue::update(&state.db, &user_id, |queue| {
+        let queue = require_queue(queue)?;
+        if !queue.jump(body.index) {
+            return Err(ApiError::bad_request(format!(
+                "Index {} is out of range",
+                body.index
+            )));
+        }
+        Ok(queue.clone())
+    })
+    .await?;
+    queue_json(&state, &user_id, &queue).await
+}
+
+/// PUT /api/queue/mode — Change shuffle and/or repeat.
+pub async fn set_mode(
+    State(state): State<AppState>,
+    auth_user: Option<Extension<AuthUser>>,
+    Json(body): Json<QueueModeRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    let user_id = extract_user_id(&state, auth_user.as_deref()).await;
+    let repeat = parse_repeat(body.repeat.as_deref())?;
+    let queue = play_queue::update(&state.db, &user_id, |queue| {
+        let queue = require_queue(queue)?;
+        if let Some(repeat) = repeat {
+            queue.repeat = repeat;
+        }
+        if let Some(shuffle) = body.shuffle {
+            queue.set_shuffle(shuffle, random_index);
+        }
+        Ok::<_, ApiError>(queue.clone())
+    })
+    .await?;
+    queue_json(&state, &user_id, &queue).await
+}
+
+fn require_queue(queue: &mut Option<PlayQueue>) -> Result<&mut PlayQueue, ApiError> {
+    queue
+        .as_mut()
+        .filter(|q| !q.items.is_empty())
+        .ok_or_else(|| ApiError::not_found("Play queue is empty"))
+}
+
+/// Media ids of a collection the user can read, in collection order.
+async fn collection_media_ids(
+    state: &AppState,
+    user_id: &str,
+    collection_id: &str,
+) -> Result<Vec<String>, ApiError> {
+    let collection = find_collection(state, collection_id, user_id, Access::Read).await?;
+    let ids = match collection.smart_rules()? {
+        Some(rules) => collection_repo::list_smart_items(
+            &state.db.read,
+            &collection,
+            &rules,
+            1,
+            MAX_QUEUE_ITEMS as i64,
+        )
+        .await?
+        .into_iter()
+        .map(|m| m.id)
+        .collect(),
+        None => collection_repo::list_items(&state.db.read, collection_id)
+            .await?
+            .into_iter()
+            .map(|i| i.media_id)
+            .collect(),
+    };
+    Ok(ids)
+}
+
+fn parse_repeat(repeat: Option<&str>) -> Result<Option<RepeatMode>, ApiError> {
+    repeat
+        .map(|r| {
+            RepeatMode::parse(r).ok_or_else(|| {
+                ApiError::bad_request(format!(
+                    "Invalid repeat mode '{r}' (expected off, all or one)"
+                ))
+            })
+        })
+        .transpose()
+}
+
+fn random_index(n: usize) -> usize {
+    (Uuid::new_v4().as_u128() % n as u128) as usize
+}
+
+/// Render the queue with display metadata. `index` in the response is the
+/// position to pass to the jump and remove endpoints.
+async fn queue_json(
+    state: &AppState,
+    user_id: &str,
+    queue: &PlayQueue,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let mut entries = queue_repo::list_queue_entries(&state.db.read, user_id).await?;
+    for (i, entry) in entries.iter_mut().enumerate() {
+        // Stored positions can have gaps after media is deleted
+        entry.position = i as i64;
+    }
+    let current = (!entries.is_empty()).then_some(queue.current);
+    let up_next = queue.next_index().and_then(|i| entries.get(i));
+
+    Ok(Json(serde_json::json!({
+        "source_collection_id": queue.source_collection_id,
+        "shuffle": queue.shuffle,
+        "repeat": queue.repeat.as_str(),
+        "current_index": current,
+        "current": current.and_then(|i| entries.get(i)),
+        "up_next": up_next,
+        "items": entries,
+    })))
+}
@@ -1,20 +1,30 @@
+use crate::audio::ProcessingOverrides;
+use crate::auth::AuthUser;
 use crate::error::ApiError;
+use crate::network::effective_remote_cap_kbps;
+use crate::play_queue;
+use crate::scheduler::{Admission, Grant, Priority, TranscodePermit};
 use crate::state::AppState;
 use axum::body::Body;
-use axum::extract::{Path, Query, State};
+use axum::extract::{ConnectInfo, Path, Query, State};
 use axum::http::{header, HeaderMap, StatusCode};
 use axum::response::IntoResponse;
-use axum::Json;
+use axum::{Extension, Json};
 use dashmap::DashMap;
 use ferrite_core::config::HlsSegmentMimeMode;
-use ferrite_db::{keyframe_repo, media_repo, stream_repo, subtitle_repo};
-use ferrite_stream::compat::{self, StreamStrategy};
-use ferrite_stream::{direct, transcode};
+use ferrite_db::{keyframe_repo, media_repo, stream_repo, subtitle_repo, user_repo};
+use ferrite_stream::compat::{self, ClientProfile, StreamStrategy, VersionCandidate};
+use ferrite_stream::hls::TerminationNotice;
+use ferrite_stream::{direct, stack, transcode};
+use ferrite_transcode::hwaccel::EncoderProfile;
+use ferrite_transcode::tonemap::{self, DolbyVision};
+use futures::StreamExt;
 use serde::Deserialize;
 use sqlx::SqlitePool;
+use std::net::SocketAddr;
 use std::sync::Arc;
 use std::time::Instant;
-use tokio::sync::{Mutex, OwnedSemaphorePermit};
+use tokio::sync::Mutex;
 use tokio_util::io::ReaderStream;
 use tracing::{debug, info, warn};
 use uuid::Uuid;
@@ -39,6 +49,22 @@ pub struct StreamQuery {
     /// Seek behavior: fast (default, index-backed) or precise (ffprobe-backed).
     #[serde(default)]
     pub seek_mode: SeekMode,
+    /// Loudness normalization; overrides the user's `audio_normalize` preference.
+    pub normalize: Option<bool>,
+    /// Dialogue-boost downmix; overrides `audio_dialogue_boost`.
+    pub dialogue_boost: Option<bool>,
+    /// Dynamic range compression; overrides `audio_night_mode`.
+    pub night_mode: Option<bool>,
+}
+
+impl StreamQuery {
+    fn audio_overrides(&self) -> ProcessingOverrides {
+        ProcessingOverrides {
+            normalize: self.normalize,
+            dialogue_boost: self.dialogue_boost,
+            night_mode: self.night_mode,
+        }
+    }
 }
 
 #[derive(Deserialize)]
@@ -125,7 +151,7 @@ fn extract_bearer_token(headers: &HeaderMap) -> Option<String> {
         .map(str::to_string)
 }
 
-fn resolve_hls_token(query_token: Option<&str>, headers: &HeaderMap) -> Option<String> {
+pub(crate) fn resolve_hls_token(query_token: Option<&str>, headers: &HeaderMap) -> Option<String> {
     query_token
         .map(str::to_string)
         .or_else(|| extract_bearer_token(headers))
@@ -285,16 +311,55 @@ async fn lazy_probe_keyframes(
     true
 }
 
-async fn acquire_transcode_permit(
+/// Build a scheduler admission for `user`. Admins get queue priority.
+pub(crate) async fn transcode_admission(
     state: &AppState,
+    user: Option<&AuthUser>,
+    key: String,
     media_id: &str,
     operation: &'static str,
-) -> Result<OwnedSemaphorePermit, ApiError> {
+    wanted: usize,
+    min: usize,
+) -> Admission {
+    let is_admin = match user {
+        Some(user) => user_repo::get_user_by_id(&state.db.read, &user.user_id)
+            .await
+            .ok()
+            .flatten()
+            .is_some_and(|u| u.is_admin != 0),
+        None => false,
+    };
+    Admission {
+        key,
+        user_id: user.map(|u| u.user_id.clone()),
+        username: user.map(|u| u.username.clone()),
+        media_id: media_id.to_string(),
+        operation,
+        priority: if is_admin {
+            Priority::High
+        } else {
+            Priority::Normal
+        },
+        wanted,
+        min,
+        cheap_fallback: false,
+    }
+}
+
+/// Admit a transcode through the scheduler, recording how long it queued.
+/// HLS admissions are keyed by owner key and must be `retain`ed once their
+/// sessions exist.
+pub(crate) async fn admit_transcode(
+    state: &AppState,
+    admission: Admission,
+    hls: bool,
+) -> Result<TranscodePermit, ApiError> {
     let wait_started = Instant::now();
-    let timeout =
-        std::time::Duration::from_secs(state.config.transcode.transcode_queue_timeout_secs);
-    match tokio::time::timeout(timeout, state.transcode_semaphore.clone().acquire_owned()).await {
-        Ok(Ok(permit)) => {
+    let operation = admission.operation;
+    let media_id = admission.media_id.clone();
+    let hls_sessions = hls.then_some(state.hls_sessions.as_ref());
+    match state.transcodes.admit(admission, hls_sessions).await {
+        Ok(permit) => {
             let wait_ms = wait_started.elapsed().as_secs_f64() * 1000.0;
             state.playback_metrics.record_timing(
                 "transcode_queue_wait_ms",
@@ -302,37 +367,180 @@ async fn acquire_transcode_permit(
                 wait_ms,
             );
             info!(
-                "Transcode permit acquired: op={} media_id={} wait_ms={:.1}",
-                operation, media_id, wait_ms
+                "Transcode admitted: op={} media_id={} grant={:?} wait_ms={:.1}",
+                operation,
+                media_id,
+                permit.grant(),
+                wait_ms
             );
             Ok(permit)
         }
-        Ok(Err(_)) => Err(ApiError::service_unavailable("Transcode queue unavailable")),
-        Err(_) => {
+        Err(err) => {
             warn!(
                 "Transcode queue timeout: op={} media_id={} timeout_secs={}",
                 operation, media_id, state.config.transcode.transcode_queue_timeout_secs
             );
-            Err(ApiError::service_unavailable(
-                "Transcode queue timeout, try again later",
-            ))
+            Err(err)
         }
     }
 }
 
+/// Admit a progressive transcode, which has no owner key of its own.
+async fn admit_progressive(
+    state: &AppState,
+    user: Option<&AuthUser>,
+    media_id: &str,
+    operation: &'static str,
+    encodes: usize,
+) -> Result<TranscodePermit, ApiError> {
+    let key = format!("stream:{}", Uuid::new_v4());
+    let admission =
+        transcode_admission(state, user, key, media_id, operation, encodes, encodes).await;
+    admit_transcode(state, admission, false).await
+}
+
+/// Tie a progressive response to its permit: the slot is held until the body
+/// finishes or the client goes away, and an admin termination ends the body.
+fn hold_permit(
+    response: axum::response::Response,
+    permit: TranscodePermit,
+) -> axum::response::Response {
+    let cancel = permit.cancellation();
+    let (parts, body) = response.into_parts();
+    let stream = body
+        .into_data_stream()
+        .take_until(cancel.cancelled_owned())
+        .map(move |chunk| {
+            let _held = &permit;
+            chunk
+        });
+    axum::response::Response::from_parts(parts, Body::from_stream(stream))
+}
+
+/// Whether a saturated server may serve this HLS request by copying the
+/// source video instead of encoding it.
+fn passthrough_allowed(
+    item: &media_repo::MediaItemRow,
+    pixel_format: Option<&str>,
+    burns_subtitles: bool,
+    max_bitrate_kbps: Option<u32>,
+) -> bool {
+    let fits_cap = max_bitrate_kbps
+        .is_none_or(|cap| item.bitrate_kbps.is_some_and(|b| b > 0 && b as u32 <= cap));
+    fits_cap
+        && ferrite_stream::hls::can_copy_source_video(
+            item.video_codec.as_deref(),
+            pixel_format,
+            burns_subtitles,
+        )
+}
+
+/// Bitrate cap (kbps) for the requesting client, or `None` when it is on the
+/// local network or no remote cap is configured for the server or user.
+pub(crate) async fn resolve_remote_bitrate_cap(
+    state: &AppState,
+    auth_user: Option<&AuthUser>,
+    peer: Option<SocketAddr>,
+    headers: &HeaderMap,
+) -> Option<u32> {
+    let client_ip = state.network.client_ip(peer, headers);
+    if state.network.is_local(client_ip) {
+        return None;
+    }
+    let per_user = match auth_user {
+        Some(user) => user_repo::get_user_by_id(&state.db.read, &user.user_id)
+            .await
+            .ok()
+            .flatten()
+            .and_then(|u| u.remote_max_bitrate_kbps),
+        None => None,
+    };
+    let cap = effective_remote_cap_kbps(state.config.network.remote_max_bitrate_kbps, per_user);
+    if let Some(kbps) = cap {
+        debug!(
+            "Remote client {:?}: limiting HLS variants to {} kbps",
+            client_ip, kbps
+        );
+    }
+    cap
+}
+
+/// Check the user's concurrent playback limit and register `owner_key` as one
+/// of their streams. An owner key the user already holds doesn't count twice.
+pub(crate) async fn claim_user_stream_slot(
+    state: &AppState,
+    user: &AuthUser,
+    owner_key: &str,
+) -> Result<(), ApiError> {
+    let per_user = user_repo::get_user_by_id(&state.db.read, &user.user_id)
+        .await?
+        .and_then(|u| u.max_concurrent_streams);
+    let limit = match per_user {
+        Some(limit) => limit.max(0) as usize,
+        None => state.config.network.max_streams_per_user as usize,
+    };
+    if limit > 0 {
+        let active = state
+            .hls_sessions
+            .active_owners_for_user(&user.user_id)
+            .into_iter()
+            .filter(|key| key != owner_key)
+            .count();
+        if active >= limit {
+            warn!(
+                "Stream limit reached for user {}: {} active (limit {})",
+                user.username, active, limit
+            );
+            return Err(ApiError::too_many_requests(format!(
+                "Concurrent stream limit reached ({limit}). Stop another stream and try again."
+            )));
+        }
+    }
+    state
+        .hls_sessions
+        .register_owner_user(owner_key, &user.user_id);
+    Ok(())
+}
+
+/// The server's encoder tuned with a library's encoding settings, plus the
+/// AAC bitrate for progressive transcodes.
+async fn library_encoder(state: &AppState, library_id: &str) -> (EncoderProfile, u32) {
+    let encoding =
+        crate::encoding::for_library(&state.db.read, &state.config.transcode, library_id).await;
+    (
+        state
+            .encoder_profile
+            .with_quality(&encoding.preset, encoding.crf),
+        encoding.audio_bitrate_kbps,
+    )
+}
+
+/// Make `media_id` current in the user's play queue when a client starts
+/// playing it, in the background so streaming isn't held up.
+fn sync_play_queue(state: &AppState, auth_user: Option<&AuthUser>, media_id: &str) {
+    let Some(user) = auth_user else {
+        return;
+    };
+    let db = state.db.clone();
+    let user_id = user.user_id.clone();
+    let media_id = media_id.to_string();
+    tokio::spawn(async move {
+        if let Err(e) = play_queue::sync_playback(&db, &user_id, &media_id).await {
+            warn!("Failed to sync play queue for user {}: {}", user_id, e);
+        }
+    });
+}
+
 pub async fn stream_media(
     State(state): State<AppState>,
     Path(id): Path<String>,
     Query(query): Query<StreamQuery>,
+    auth_user: Option<Extension<AuthUser>>,
     headers: HeaderMap,
 ) -> impl IntoResponse {
-    let item = match media_repo::get_media_item(&state.db.read, &id).await {
-        Ok(Some(item)) => item,
-        Ok(None) => return StatusCode::NOT_FOUND.into_response(),
-        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
-    };
-
-    let file_path = std::path::Path::new(&item.file_path);
+    // Only the opening request of a playback moves the queue, not every
+    // range request the player makes while seeking.
+    let opens_playback = header_str(&headers, "range").is_none_or(|r| r.trim() == "bytes=0-");
 
     // Determine streaming strategy per client capability profile.
     let explicit_profile = resolve_profile_override(query.client_profile.as_deref(), &headers);
@@ -341,12 +549,43 @@ pub async fn stream_media(
         header_str(&headers, "user-agent"),
         header_str(&headers, "sec-ch-ua-platform"),
     );
-    let strategy = compat::determine_strategy_for_profile(
+
+    let source = match resolve_playback_source(&state, &id, client_profile).await {
+        Ok(Some(source)) => source,
+        Ok(None) => return StatusCode::NOT_FOUND.into_response(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    };
+    let item = &source.item;
+    let file_path = source.input_path.as_path();
+
+    let video_meta = stream_repo::get_video_meta(&state.db.read, &item.id)
+        .await
+        .unwrap_or(None);
+    let mut strategy = compat::determine_strategy_for_stream(
         client_profile,
         item.container_format.as_deref(),
         item.video_codec.as_deref(),
         item.audio_codec.as_deref(),
+        video_dolby_vision(video_meta.as_ref()),
     );
+    // A concat list isn't a servable file; stacked parts are at least remuxed.
+    if source.stacked && strategy == StreamStrategy::DirectPlay {
+        strategy = StreamStrategy::Remux;
+    }
+    // Audio processing needs the audio re-encoded
+    let audio_filter = playback_audio_filter(
+        &state,
+        auth_user.as_deref(),
+        query.audio_overrides(),
+        &source,
+        query.audio_stream,
+    )
+    .await;
+    if audio_filter.is_some()
+        && matches!(strategy, StreamStrategy::DirectPlay | StreamStrategy::Remux)
+    {
+        strategy = StreamStrategy::AudioTranscode;
+    }
 
     info!(
         "Stream {}: strategy={:?} profile={} (container={:?}, video={:?}, audio={:?})",
@@ -358,18 +597,26 @@ pub async fn stream_media(
         item.audio_codec,
     );
 
-    let duration_secs = item.duration_ms.map(|ms| ms as f64 / 1000.0);
+    let duration_secs = source.duration_ms.map(|ms| ms as f64 / 1000.0);
     let startup_started = Instant::now();
     let strategy_metric = strategy_label(&strategy);
     let requested_start = query.start.unwrap_or(0.0);
-    let pre_resolved_start_secs =
-        if requested_start > 0.5 && !matches!(strategy, StreamStrategy::DirectPlay) {
-            let (resolved_start_secs, _, _) =
-                resolve_seek_start(&state, &id, file_path, requested_start, query.seek_mode).await;
-            Some(resolved_start_secs)
-        } else {
-            None
-        };
+    let pre_resolved_start_secs = if requested_start > 0.5
+        && !source.stacked
+        && !matches!(strategy, StreamStrategy::DirectPlay)
+    {
+        let (resolved_start_secs, _, _) = resolve_seek_start(
+            &state,
+            &item.id,
+            file_path,
+            requested_start,
+            query.seek_mode,
+        )
+        .await;
+        Some(resolved_start_secs)
+    } else {
+        None
+    };
 
     let response = match strategy {
         StreamStrategy::DirectPlay => match direct::serve_file(file_path, &headers).await {
@@ -377,10 +624,12 @@ pub async fn stream_media(
             Err(status) => status.into_response(),
         },
         StreamStrategy::Remux => {
-            let _permit = match acquire_transcode_permit(&state, &id, "remux").await {
-                Ok(permit) => permit,
-                Err(err) => return err.into_response(),
-            };
+            let permit =
+                match admit_progressive(&state, auth_user.as_deref(), &id, "remux", 0).await {
+                    Ok(permit) => permit,
+                    Err(err) => return err.into_response(),
+                };
+            let (encoder, audio_bitrate_kbps) = library_encoder(&state, &item.library_id).await;
             let ffmpeg_path = &state.config.transcode.ffmpeg_path;
             let ffprobe_path = &state.config.transcode.ffprobe_path;
             let sub_path = resolve_subtitle_path(&state.db.read, query.subtitle_id).await;
@@ -392,21 +641,26 @@ pub async fn stream_media(
                 query.start,
                 pre_resolved_start_secs,
                 sub_path.as_deref(),
-                &state.encoder_profile,
+                &encoder,
+                audio_bitrate_kbps,
                 query.audio_stream,
                 item.video_codec.as_deref(),
             )
             .await
             {
-                Ok(response) => response.into_response(),
+                Ok(response) => hold_permit(response.into_response(), permit),
                 Err(status) => status.into_response(),
             }
         }
         StreamStrategy::AudioTranscode => {
-            let _permit = match acquire_transcode_permit(&state, &id, "audio-transcode").await {
-                Ok(permit) => permit,
-                Err(err) => return err.into_response(),
-            };
+            let permit =
+                match admit_progressive(&state, auth_user.as_deref(), &id, "audio-transcode", 0)
+                    .await
+                {
+                    Ok(permit) => permit,
+                    Err(err) => return err.into_response(),
+                };
+            let (encoder, audio_bitrate_kbps) = library_encoder(&state, &item.library_id).await;
             let ffmpeg_path = &state.config.transcode.ffmpeg_path;
             let ffprobe_path = &state.config.transcode.ffprobe_path;
             let sub_path = resolve_subtitle_path(&state.db.read, query.subtitle_id).await;
@@ -418,31 +672,39 @@ pub async fn stream_media(
                 query.start,
                 pre_resolved_start_secs,
                 sub_path.as_deref(),
-                &state.encoder_profile,
+                &encoder,
+                audio_bitrate_kbps,
                 query.audio_stream,
+                audio_filter.as_deref(),
             )
             .await
             {
-                Ok(response) => response.into_response(),
+                Ok(response) => hold_permit(response.into_response(), permit),
                 Err(status) => status.into_response(),
             }
         }
         StreamStrategy::FullTranscode => {
-            let _permit = match acquire_transcode_permit(&state, &id, "full-transcode").await {
-                Ok(permit) => permit,
-                Err(err) => return err.into_response(),
-            };
+            let permit =
+                match admit_progressive(&state, auth_user.as_deref(), &id, "full-transcode", 1)
+                    .await
+                {
+                    Ok(permit) => permit,
+                    Err(err) => return err.into_response(),
+                };
+            let encoding = crate::encoding::for_library(
+                &state.db.read,
+                &state.config.transcode,
+                &item.library_id,
+            )
+            .await;
+            let encoder = state
+                .encoder_profile
+                .with_quality(&encoding.preset, encoding.crf);
             let ffmpeg_path = &state.config.transcode.ffmpeg_path;
             let ffprobe_path = &state.config.transcode.ffprobe_path;
             let sub_path = resolve_subtitle_path(&state.db.read, query.subtitle_id).await;
-            let pixel_format = stream_repo::get_video_pixel_format(&state.db.read, &id)
-                .await
-                .unwrap_or(None);
-            let color_meta = stream_repo::get_video_color_metadata(&state.db.read, &id)
-                .await
-                .unwrap_or(None);
-            let color_transfer = color_meta.as_ref().and_then(|m| m.color_transfer.clone());
-            let color_primaries = color_meta.as_ref().and_then(|m| m.color_primaries.clone());
+            let pixel_format = video_meta.as_ref().and_then(|m| m.pixel_format.clone());
+            let (color_transfer, color_primaries) = tonemap_color(video_meta.as_ref());
             match transcode::serve_full_transcode(
                 ffmpeg_path,
                 ffprobe_path,
@@ -451,20 +713,28 @@ pub async fn stream_media(
                 query.start,
                 pre_resolved_start_secs,
                 sub_path.as_deref(),
-                &state.encoder_profile,
+                &encoder,
+                &encoding,
+                item.height.map(|h| h as u32),
                 pixel_format.as_deref(),
                 query.audio_stream,
                 color_transfer.as_deref(),
                 color_primaries.as_deref(),
+                audio_filter.as_deref(),
             )
             .await
             {
-                Ok(response) => response.into_response(),
+                Ok(response) => hold_permit(response.into_response(), permit),
                 Err(status) => status.into_response(),
             }
         }
     };
 
+    // The queue follows only playbacks that actually started
+    if opens_playback && response.status().is_success() {
+        sync_play_queue(&state, auth_user.as_deref(), &id);
+    }
+
     state.playback_metrics.record_timing(
         "playback_ttff_ms",
         &[
@@ -549,6 +819,44 @@ pub struct HlsQuery {
     /// Seek behavior: fast (default, index-backed) or precise (ffprobe-backed).
     #[serde(default)]
     pub seek_mode: SeekMode,
+    /// Loudness normalization; overrides the user's `audio_normalize` preference.
+    pub normalize: Option<bool>,
+    /// Dialogue-boost downmix; overrides `audio_dialogue_boost`.
+    pub dialogue_boost: Option<bool>,
+    /// Dynamic range compression; overrides `audio_night_mode`.
+    pub night_mode: Option<bool>,
+}
+
+impl HlsQuery {
+    fn audio_overrides(&self) -> ProcessingOverrides {
+        ProcessingOverrides {
+            normalize: self.normalize,
+            dialogue_boost: self.dialogue_boost,
+            night_mode: self.night_mode,
+        }
+    }
+}
+
+/// The `-af` chain for the user's audio processing settings, if any apply.
+async fn playback_audio_filter(
+    state: &AppState,
+    auth_user: Option<&AuthUser>,
+    overrides: ProcessingOverrides,
+    source: &PlaybackSource,
+    audio_stream: Option<u32>,
+) -> Option<String> {
+    let processing =
+        crate::audio::resolve_processing(state, auth_user.map(|u| u.user_id.as_str()), overrides)
+            .await;
+    crate::audio::audio_filter(
+        state,
+        &source.item,
+        &source.input_path,
+        source.stacked,
+        audio_stream,
+        processing,
+    )
+    .await
 }
 
 /// Resolve a subtitle_id to a file path on disk.
@@ -582,23 +890,152 @@ async fn resolve_subtitle_path(
     }
 }
 
+/// Dolby Vision signalling of a media item's first video stream.
+fn video_dolby_vision(video_meta: Option<&stream_repo::VideoMeta>) -> Option<DolbyVision> {
+    video_meta.and_then(|m| DolbyVision::from_columns(m.dv_profile, m.dv_bl_compat_id))
+}
+
+/// Transfer and primaries the transcoders tone-map from
+/// (see [`tonemap::effective_color`]).
+pub(crate) fn tonemap_color(
+    video_meta: Option<&stream_repo::VideoMeta>,
+) -> (Option<String>, Option<String>) {
+    let Some(meta) = video_meta else {
+        return (None, None);
+    };
+    let (transfer, primaries) = tonemap::effective_color(
+        meta.color_transfer.as_deref(),
+        meta.color_primaries.as_deref(),
+        video_dolby_vision(Some(meta)),
+    );
+    (transfer.map(str::to_string), primaries.map(str::to_string))
+}
+
+/// What actually gets played for a media id: the version chosen for the
+/// client and, for multi-part movies, a concat list spanning every part.
+pub(crate) struct PlaybackSource {
+    /// The chosen version (the stack head for multi-part movies).
+    pub item: media_repo::MediaItemRow,
+    /// Input handed to FFmpeg: the file itself or a generated concat list.
+    pub input_path: std::path::PathBuf,
+    pub duration_ms: Option<i64>,
+    pub stacked: bool,
+}
+
+/// Resolve the file(s) to play for `id`. Requesting a movie group's primary
+/// picks the version that is cheapest to stream for `profile`; any other id
+/// plays exactly that version. Returns `None` if the media item doesn't exist.
+pub(crate) async fn resolve_playback_source(
+    state: &AppState,
+    id: &str,
+    profile: ClientProfile,
+) -> anyhow::Result<Option<PlaybackSource>> {
+    let Some(mut item) = media_repo::get_media_item(&state.db.read, id).await? else {
+        return Ok(None);
+    };
+
+    if item.movie_group_id.as_deref() == Some(id) {
+        let versions = media_repo::list_movie_versions(&state.db.read, id).await?;
+        if versions.len() > 1 {
+            let mut stacked = Vec::with_capacity(versions.len());
+            let mut dolby_vision = Vec::with_capacity(versions.len());
+            for version in &versions {
+                let is_stacked = version.part_number.is_some()
+                    && media_repo::list_stack_parts(&state.db.read, &version.id)
+                        .await?
+                        .len()
+                        > 1;
+                stacked.push(is_stacked);
+                let video_meta = stream_repo::get_video_meta(&state.db.read, &version.id).await?;
+                dolby_vision.push(video_dolby_vision(video_meta.as_ref()));
+            }
+            let candidates: Vec<VersionCandidate<'_>> = versions
+                .iter()
+                .zip(stacked.iter().zip(&dolby_vision))
+                .map(|(v, (&stacked, &dolby_vision))| VersionCandidate {
+                    container_format: v.container_format.as_deref(),
+                    video_codec: v.video_codec.as_deref(),
+                    audio_codec: v.audio_codec.as_deref(),
+                    height: v.height,
+                    dolby_vision,
+                    stacked,
+                })
+                .collect();
+            if let Some(idx) = compat::select_version(profile, &candidates) {
+                if versions[idx].id != item.id {
+                    debug!(
+                        "Media {}: playing version {} ({}) for profile {}",
+                        id,
+                        versions[idx].id,
+                        versions[idx]
+                            .version_label
+                            .as_deref()
+                            .unwrap_or("unlabeled"),
+                        profile.as_str()
+                    );
+                    item = versions[idx].clone();
+                }
+            }
+        }
+    }
+
+    if item.part_number.is_some() {
+        let parts = media_repo::list_stack_parts(&state.db.read, &item.id).await?;
+        if parts.len() > 1 {
+            let paths: Vec<std::path::PathBuf> = parts
+                .iter()
+                .map(|p| std::path::PathBuf::from(&p.file_path))
+                .collect();
+            let input_path =
+                stack::write_concat_list(&state.config.transcode.cache_dir, &item.id, &paths)
+                    .await?;
+            let duration_ms = parts.iter().map(|p| p.duration_ms).sum();
+            return Ok(Some(PlaybackSource {
+                item,
+                input_path,
+                duration_ms,
+                stacked: true,
+            }));
+        }
+    }
+
+    Ok(Some(PlaybackSource {
+        input_path: std::path::PathBuf::from(&item.file_path),
+        duration_ms: item.duration_ms,
+        item,
+        stacked: false,
+    }))
+}
+
+/// Client profile for HLS requests, which carry no `client_profile` query.
+fn hls_client_profile(headers: &HeaderMap) -> ClientProfile {
+    compat::resolve_client_profile(
+        resolve_profile_override(None, headers),
+        header_str(headers, "user-agent"),
+        header_str(headers, "sec-ch-ua-platform"),
+    )
+}
+
 /// GET /api/stream/{id}/hls/master.m3u8
 /// Creates an HLS session (or reuses an existing one) and returns the master playlist.
 pub async fn hls_master_playlist(
     State(state): State<AppState>,
     Path(id): Path<String>,
     Query(query): Query<HlsQuery>,
+    auth_user: Option<Extension<AuthUser>>,
+    connect_info: Option<Extension<ConnectInfo<SocketAddr>>>,
     headers: HeaderMap,
 ) -> Result<impl IntoResponse, ApiError> {
     let t0 = Instant::now();
 
-    let item = media_repo::get_media_item(&state.db.read, &id)
+    let source = resolve_playback_source(&state, &id, hls_client_profile(&headers))
         .await?
         .ok_or_else(|| ApiError::not_found(format!("Media item '{id}' not found")))?;
+    let item = &source.item;
     let db_ms = t0.elapsed().as_secs_f64() * 1000.0;
 
-    let file_path = std::path::Path::new(&item.file_path);
-    let duration_secs = item.duration_ms.map(|ms| ms as f64 / 1000.0);
+    let file_path = source.input_path.as_path();
+    let duration_secs = source.duration_ms.map(|ms| ms as f64 / 1000.0);
 
     // Extract auth token from query or Authorization header for playlist URL rewriting
     let token = resolve_hls_token(query.token.as_deref(), &headers);
@@ -608,20 +1045,60 @@ pub async fn hls_master_playlist(
         &id,
         query.playback_session_id.as_deref(),
     );
+    // A stopped playback can't restart under the same playback session id
+    if let Some(notice) = state.hls_sessions.termination_notice(&owner_key) {
+        return Ok(terminated_response(&notice));
+    }
 
-    let (start_secs, seek_source, seek_lookup_ms) =
-        resolve_seek_start(&state, &id, file_path, requested_start, query.seek_mode).await;
+    let (start_secs, seek_source, seek_lookup_ms) = if source.stacked {
+        // Keyframe indexes are per file; the concat demuxer seeks on its own.
+        (requested_start, "stacked", 0.0)
+    } else {
+        resolve_seek_start(
+            &state,
+            &item.id,
+            file_path,
+            requested_start,
+            query.seek_mode,
+        )
+        .await
+    };
 
     let sub_path = resolve_subtitle_path(&state.db.read, query.subtitle_id).await;
 
     // Fetch all video stream metadata in a single DB round-trip
-    let video_meta = stream_repo::get_video_meta(&state.db.read, &id)
+    let video_meta = stream_repo::get_video_meta(&state.db.read, &item.id)
         .await
         .unwrap_or(None);
     let pixel_format = video_meta.as_ref().and_then(|m| m.pixel_format.clone());
     let frame_rate = video_meta.as_ref().and_then(|m| m.frame_rate.clone());
-    let color_transfer = video_meta.as_ref().and_then(|m| m.color_transfer.clone());
-    let color_primaries = video_meta.as_ref().and_then(|m| m.color_primaries.clone());
+    let (color_transfer, color_primaries) = tonemap_color(video_meta.as_ref());
+
+    let auth_user = auth_user.map(|e| e.0);
+    if let (Some(user), Some(_)) = (auth_user.as_ref(), query.playback_session_id.as_deref()) {
+        state
+            .hls_sessions
+            .register_owner_user(&owner_key, &user.user_id);
+    }
+    let max_bitrate_kbps = resolve_remote_bitrate_cap(
+        &state,
+        auth_user.as_ref(),
+        connect_info.map(|c| c.0 .0),
+        &headers,
+    )
+    .await;
+
+    let encoding =
+        crate::encoding::for_library(&state.db.read, &state.config.transcode, &item.library_id)
+            .await;
+    let audio_filter = playback_audio_filter(
+        &state,
+        auth_user.as_ref(),
+        query.audio_overrides(),
+        &source,
+        query.audio_stream,
+    )
+    .await;
 
     // Check if we already have variant sessions for this media/playback owner.
     let t1 = Instant::now();
@@ -635,8 +1112,36 @@ pub async fn hls_master_playlist(
         && existing_variants[0]
             .awaiting_promotion
             .load(std::sync::atomic::Ordering::Acquire);
-    let sessions = if should_promote_ladder {
-        let _permit = acquire_transcode_permit(&state, &id, "hls-master").await?;
+    let promotion_permit = if should_promote_ladder {
+        // Grow to the ladder only with capacity to spare; a saturated server
+        // keeps the single variant rather than queueing a playing viewer.
+        let ladder_len = ferrite_transcode::variants::cap_variants(
+            ferrite_transcode::variants::select_variants_for(
+                &encoding,
+                item.width.map(|w| w as u32),
+                item.height.map(|h| h as u32),
+            ),
+            max_bitrate_kbps,
+        )
+        .len();
+        let admission = transcode_admission(
+            &state,
+            auth_user.as_ref(),
+            owner_key.clone(),
+            &id,
+            "hls-master",
+            ladder_len,
+            2,
+        )
+        .await;
+        state
+            .transcodes
+            .try_admit(admission, &state.hls_sessions)
+            .await
+    } else {
+        None
+    };
+    let sessions = if let Some(permit) = promotion_permit {
         reused = false;
 
         let create_result = state
@@ -659,13 +1164,19 @@ pub async fn hls_master_playlist(
                 item.video_codec.as_deref(),
                 color_transfer.as_deref(),
                 color_primaries.as_deref(),
+                max_bitrate_kbps,
+                Some(permit.encodes()),
+                audio_filter.as_deref(),
+                &encoding,
             )
             .await;
 
-        create_result.map_err(|e| {
+        let sessions = create_result.map_err(|e| {
             warn!("Failed to create HLS session for {}: {}", id, e);
             ApiError::internal(e.to_string())
-        })?
+        })?;
+        permit.retain();
+        sessions
     } else if reused {
         // Reuse existing variant sessions (touch them to keep alive)
         for s in &existing_variants {
@@ -673,39 +1184,87 @@ pub async fn hls_master_playlist(
         }
         existing_variants
     } else {
-        let _permit = acquire_transcode_permit(&state, &id, "hls-master").await?;
+        let mut admission = transcode_admission(
+            &state,
+            auth_user.as_ref(),
+            owner_key.clone(),
+            &id,
+            "hls-master",
+            1,
+            1,
+        )
+        .await;
+        admission.cheap_fallback = passthrough_allowed(
+            item,
+            pixel_format.as_deref(),
+            sub_path.is_some(),
+            max_bitrate_kbps,
+        );
+        let permit = admit_transcode(&state, admission, true).await?;
 
         // Start with a single variant at the highest quality for fastest TTFF.
         // The player re-polls master.m3u8 within a few seconds; the
         // should_promote_ladder check will then spawn the full ABR ladder.
-        let create_result = state
-            .hls_sessions
-            .create_single_variant_session_owned(
-                &owner_key,
-                &id,
-                file_path,
-                duration_secs,
-                item.width.map(|w| w as u32),
-                item.height.map(|h| h as u32),
-                item.bitrate_kbps.map(|b| b as u32),
-                start_secs,
-                requested_start,
-                sub_path.as_deref(),
-                pixel_format.as_deref(),
-                query.audio_stream,
-                frame_rate.as_deref(),
-                item.audio_codec.as_deref(),
-                item.video_codec.as_deref(),
-                color_transfer.as_deref(),
-                color_primaries.as_deref(),
-                true, // awaiting_promotion = true for initial play
-            )
-            .await;
+        // A passthrough session (saturated server) is never promoted.
+        let create_result = if permit.grant() == Grant::Cheap {
+            state
+                .hls_sessions
+                .create_passthrough_session_owned(
+                    &owner_key,
+                    &id,
+                    file_path,
+                    duration_secs,
+                    item.width.map(|w| w as u32),
+                    item.height.map(|h| h as u32),
+                    item.bitrate_kbps.map(|b| b as u32),
+                    start_secs,
+                    requested_start,
+                    pixel_format.as_deref(),
+                    query.audio_stream,
+                    frame_rate.as_deref(),
+                    item.audio_codec.as_deref(),
+                    item.video_codec.as_deref(),
+                    color_transfer.as_deref(),
+                    color_primaries.as_deref(),
+                    audio_filter.as_deref(),
+                    &encoding,
+                )
+                .await
+        } else {
+            state
+                .hls_sessions
+                .create_single_variant_session_owned(
+                    &owner_key,
+                    &id,
+                    file_path,
+                    duration_secs,
+                    item.width.map(|w| w as u32),
+                    item.height.map(|h| h as u32),
+                    item.bitrate_kbps.map(|b| b as u32),
+                    start_secs,
+                    requested_start,
+                    sub_path.as_deref(),
+                    pixel_format.as_deref(),
+                    query.audio_stream,
+                    frame_rate.as_deref(),
+                    item.audio_codec.as_deref(),
+                    item.video_codec.as_deref(),
+                    color_transfer.as_deref(),
+                    color_primaries.as_deref(),
+                    max_bitrate_kbps,
+                    audio_filter.as_deref(),
+                    &encoding,
+                    true, // awaiting_promotion = true for initial play
+                )
+                .await
+        };
 
-        create_result.map_err(|e| {
+        let sessions = create_result.map_err(|e| {
             warn!("Failed to create HLS session for {}: {}", id, e);
             ApiError::internal(e.to_string())
-        })?
+        })?;
+        permit.retain();
+        sessions
     };
     let session_ms = t1.elapsed().as_secs_f64() * 1000.0;
 
@@ -764,10 +1323,15 @@ pub async fn hls_master_playlist(
         "x-hls-video-copied",
         if video_copied { "1" } else { "0" }.parse().unwrap(),
     );
+    let absolute_pts = sessions.first().is_some_and(|s| s.absolute_timestamps());
+    resp_headers.insert(
+        "x-hls-absolute-pts",
+        if absolute_pts { "1" } else { "0" }.parse().unwrap(),
+    );
     resp_headers.insert("x-hls-session-ids", session_ids.join(",").parse().unwrap());
     resp_headers.insert("Server-Timing", timing.parse().unwrap());
 
-    Ok((StatusCode::OK, resp_headers, playlist))
+    Ok((StatusCode::OK, resp_headers, playlist).into_response())
 }
 
 /// POST /api/stream/{id}/hls/session/start
@@ -776,6 +1340,7 @@ pub async fn hls_session_start(
     State(state): State<AppState>,
     Path(id): Path<String>,
     Query(query): Query<HlsQuery>,
+    auth_user: Option<Extension<AuthUser>>,
     headers: HeaderMap,
 ) -> Result<impl IntoResponse, ApiError> {
     media_repo::get_media_item(&state.db.read, &id)
@@ -790,6 +1355,16 @@ pub async fn hls_session_start(
         .map(str::to_string)
         .unwrap_or_else(|| Uuid::new_v4().to_string());
 
+    // Enforce the per-user concurrent playback limit. Restarting an existing
+    // playback session id does not count as an additional stream.
+    if let Some(Extension(user)) = auth_user.as_ref() {
+        let owner_key =
+            ferrite_stream::hls::HlsSessionManager::owner_key(&id, Some(&playback_session_id));
+        claim_user_stream_slot(&state, user, &owner_key).await?;
+    }
+
+    sync_play_queue(&state, auth_user.as_deref(), &id);
+
     let token = resolve_hls_token(query.token.as_deref(), &headers);
     let master_url_suffix = build_seek_master_url_suffix(
         token.as_deref(),
@@ -817,6 +1392,9 @@ pub async fn hls_session_heartbeat(
     let playback_session_id = require_playback_session_id(query.playback_session_id.as_deref())?;
     let owner_key =
         ferrite_stream::hls::HlsSessionManager::owner_key(&id, Some(playback_session_id));
+    if let Some(notice) = state.hls_sessions.termination_notice(&owner_key) {
+        return Ok(terminated_response(&notice));
+    }
 
     let mut touched = 0usize;
     let sessions = state.hls_sessions.get_variant_sessions_owned(&owner_key);
@@ -835,7 +1413,33 @@ pub async fn hls_session_heartbeat(
     Ok(Json(serde_json::json!({
         "playback_session_id": playback_session_id,
         "active_sessions": touched,
-    })))
+    }))
+    .into_response())
+}
+
+/// 410 telling the player an administrator stopped its playback, with the
+/// reason they gave.
+fn terminated_response(notice: &TerminationNotice) -> axum::response::Response {
+    (
+        StatusCode::GONE,
+        Json(serde_json::json!({
+            "error": "Playback was stopped by an administrator",
+            "terminated": true,
+            "reason": notice.reason,
+        })),
+    )
+        .into_response()
+}
+
+/// Response for a session that no longer exists: 410 if an administrator
+/// stopped it, 404 otherwise.
+fn missing_session_response(state: &AppState, session_id: &str) -> axum::response::Response {
+    match state.hls_sessions.termination_notice(session_id) {
+        Some(notice) => terminated_response(&notice),
+        None => {
+            ApiError::not_found(format!("HLS session '{session_id}' not found")).into_response()
+        }
+    }
 }
 
 /// GET /api/stream/{id}/hls/{session_id}/playlist.m3u8
@@ -846,10 +1450,9 @@ pub async fn hls_variant_playlist(
     Query(query): Query<HlsQuery>,
     headers: HeaderMap,
 ) -> Result<impl IntoResponse, ApiError> {
-    let session = state
-        .hls_sessions
-        .get_session(&session_id)
-        .ok_or_else(|| ApiError::not_found(format!("HLS session '{session_id}' not found")))?;
+    let Some(session) = state.hls_sessions.get_session(&session_id) else {
+        return Ok(missing_session_response(&state, &session_id));
+    };
 
     let token = resolve_hls_token(query.token.as_deref(), &headers);
 
@@ -872,7 +1475,7 @@ pub async fn hls_variant_playlist(
     );
     resp_headers.insert(header::CACHE_CONTROL, "no-store".parse().unwrap());
 
-    Ok((StatusCode::OK, resp_headers, playlist))
+    Ok((StatusCode::OK, resp_headers, playlist).into_response())
 }
 
 fn hls_segment_content_type(filename: &str, mode: HlsSegmentMimeMode) -> &'static str {
@@ -901,10 +1504,9 @@ pub async fn hls_segment(
 ) -> Result<impl IntoResponse, ApiError> {
     let t0 = Instant::now();
 
-    let session = state
-        .hls_sessions
-        .get_session(&session_id)
-        .ok_or_else(|| ApiError::not_found(format!("HLS session '{session_id}' not found")))?;
+    let Some(session) = state.hls_sessions.get_session(&session_id) else {
+        return Ok(missing_session_response(&state, &session_id));
+    };
 
     // Wait for the segment to be ready (polls playlist until FFmpeg finalizes it)
     let wait_started = Instant::now();
@@ -947,7 +1549,7 @@ pub async fn hls_segment(
     resp_headers.insert(header::CACHE_CONTROL, "max-age=3600".parse().unwrap());
     resp_headers.insert("Server-Timing", timing.parse().unwrap());
 
-    Ok((StatusCode::OK, resp_headers, body))
+    Ok((StatusCode::OK, resp_headers, body).into_response())
 }
 
 /// POST /api/stream/{id}/hls/seek?start=123.456
@@ -959,6 +1561,8 @@ pub async fn hls_seek(
     State(state): State<AppState>,
     Path(id): Path<String>,
     Query(query): Query<HlsQuery>,
+    auth_user: Option<Extension<AuthUser>>,
+    connect_info: Option<Extension<ConnectInfo<SocketAddr>>>,
     headers: HeaderMap,
 ) -> Result<impl IntoResponse, ApiError> {
     let t0 = Instant::now();
@@ -1018,6 +1622,7 @@ pub async fn hls_seek(
                 "session_id": existing.session_id,
                 "start_secs": existing.start_secs, "requested_start": requested_start,
                 "video_copied": existing.video_copied,
+                "absolute_pts": existing.absolute_timestamps(),
                 "reused": true,
                 "variant_count": 1,
                 "master_url": build_hls_master_url(&id, Some(requested_start), &master_url_suffix),
@@ -1026,59 +1631,136 @@ pub async fn hls_seek(
         }
     }
 
-    let item = media_repo::get_media_item(&state.db.read, &id)
+    let source = resolve_playback_source(&state, &id, hls_client_profile(&headers))
         .await?
         .ok_or_else(|| ApiError::not_found(format!("Media item '{id}' not found")))?;
+    let item = &source.item;
     let db_ms = t0.elapsed().as_secs_f64() * 1000.0;
 
-    let file_path = std::path::Path::new(&item.file_path);
-    let duration_secs = item.duration_ms.map(|ms| ms as f64 / 1000.0);
+    let file_path = source.input_path.as_path();
+    let duration_secs = source.duration_ms.map(|ms| ms as f64 / 1000.0);
 
-    let (start_secs, seek_source, seek_lookup_ms) =
-        resolve_seek_start(&state, &id, file_path, requested_start, query.seek_mode).await;
+    let (start_secs, seek_source, seek_lookup_ms) = if source.stacked {
+        // Keyframe indexes are per file; the concat demuxer seeks on its own.
+        (requested_start, "stacked", 0.0)
+    } else {
+        resolve_seek_start(
+            &state,
+            &item.id,
+            file_path,
+            requested_start,
+            query.seek_mode,
+        )
+        .await
+    };
 
     let sub_path = resolve_subtitle_path(&state.db.read, query.subtitle_id).await;
 
     // Fetch all video stream metadata in a single DB round-trip
-    let video_meta = stream_repo::get_video_meta(&state.db.read, &id)
+    let video_meta = stream_repo::get_video_meta(&state.db.read, &item.id)
         .await
         .unwrap_or(None);
     let pixel_format = video_meta.as_ref().and_then(|m| m.pixel_format.clone());
     let frame_rate = video_meta.as_ref().and_then(|m| m.frame_rate.clone());
-    let color_transfer = video_meta.as_ref().and_then(|m| m.color_transfer.clone());
-    let color_primaries = video_meta.as_ref().and_then(|m| m.color_primaries.clone());
-
-    let _permit = acquire_transcode_permit(&state, &id, "hls-seek").await?;
+    let (color_transfer, color_primaries) = tonemap_color(video_meta.as_ref());
+
+    let max_bitrate_kbps = resolve_remote_bitrate_cap(
+        &state,
+        auth_user.as_ref().map(|e| &e.0),
+        connect_info.map(|c| c.0 .0),
+        &headers,
+    )
+    .await;
+
+    let encoding =
+        crate::encoding::for_library(&state.db.read, &state.config.transcode, &item.library_id)
+            .await;
+    let audio_filter = playback_audio_filter(
+        &state,
+        auth_user.as_ref().map(|e| &e.0),
+        query.audio_overrides(),
+        &source,
+        query.audio_stream,
+    )
+    .await;
+
+    let mut admission = transcode_admission(
+        &state,
+        auth_user.as_ref().map(|e| &e.0),
+        owner_key.clone(),
+        &id,
+        "hls-seek",
+        1,
+        1,
+    )
+    .await;
+    admission.cheap_fallback = passthrough_allowed(
+        item,
+        pixel_format.as_deref(),
+        sub_path.is_some(),
+        max_bitrate_kbps,
+    );
+    let permit = admit_transcode(&state, admission, true).await?;
 
     // Create a single variant session for fast seeking (1 FFmpeg process instead of N).
     let t1 = Instant::now();
-    let sessions = state
-        .hls_sessions
-        .create_single_variant_session_owned(
-            &owner_key,
-            &id,
-            file_path,
-            duration_secs,
-            item.width.map(|w| w as u32),
-            item.height.map(|h| h as u32),
-            item.bitrate_kbps.map(|b| b as u32),
-            start_secs,
-            requested_start,
-            sub_path.as_deref(),
-            pixel_format.as_deref(),
-            query.audio_stream,
-            frame_rate.as_deref(),
-            item.audio_codec.as_deref(),
-            item.video_codec.as_deref(),
-            color_transfer.as_deref(),
-            color_primaries.as_deref(),
-            false, // awaiting_promotion = false for seek
-        )
-        .await
-        .map_err(|e| {
-            warn!("Failed to create HLS seek session for {}: {}", id, e);
-            ApiError::internal(e.to_string())
-        })?;
+    let create_result = if permit.grant() == Grant::Cheap {
+        state
+            .hls_sessions
+            .create_passthrough_session_owned(
+                &owner_key,
+                &id,
+                file_path,
+                duration_secs,
+                item.width.map(|w| w as u32),
+                item.height.map(|h| h as u32),
+                item.bitrate_kbps.map(|b| b as u32),
+                start_secs,
+                requested_start,
+                pixel_format.as_deref(),
+                query.audio_stream,
+                frame_rate.as_deref(),
+                item.audio_codec.as_deref(),
+                item.video_codec.as_deref(),
+                color_transfer.as_deref(),
+                color_primaries.as_deref(),
+                audio_filter.as_deref(),
+                &encoding,
+            )
+            .await
+    } else {
+        state
+            .hls_sessions
+            .create_single_variant_session_owned(
+                &owner_key,
+                &id,
+                file_path,
+                duration_secs,
+                item.width.map(|w| w as u32),
+                item.height.map(|h| h as u32),
+                item.bitrate_kbps.map(|b| b as u32),
+                start_secs,
+                requested_start,
+                sub_path.as_deref(),
+                pixel_format.as_deref(),
+                query.audio_stream,
+                frame_rate.as_deref(),
+                item.audio_codec.as_deref(),
+                item.video_codec.as_deref(),
+                color_transfer.as_deref(),
+                color_primaries.as_deref(),
+                max_bitrate_kbps,
+                audio_filter.as_deref(),
+                &encoding,
+                false, // awaiting_promotion = false for seek
+            )
+            .await
+    };
+    let sessions = create_result.map_err(|e| {
+        warn!("Failed to create HLS seek session for {}: {}", id, e);
+        ApiError::internal(e.to_string())
+    })?;
+    permit.retain();
     let session_ms = t1.elapsed().as_secs_f64() * 1000.0;
     let total_ms = t0.elapsed().as_secs_f64() * 1000.0;
 
@@ -1109,6 +1791,7 @@ pub async fn hls_seek(
         "session_id": first.session_id,
         "start_secs": first.start_secs, "requested_start": requested_start,
         "video_copied": first.video_copied,
+        "absolute_pts": first.absolute_timestamps(),
         "reused": false,
         "variant_count": sessions.len(),
         "master_url": build_hls_master_url(&id, Some(requested_start), &master_url_suffix),
@@ -1131,6 +1814,8 @@ pub async fn hls_stop_media(
         query.playback_session_id.as_deref(),
     );
     state.hls_sessions.destroy_owner_sessions(&owner_key).await;
+    state.hls_sessions.unregister_owner(&owner_key);
+    state.transcodes.release_owner(&owner_key);
     StatusCode::NO_CONTENT
 }
 
@@ -1145,6 +1830,8 @@ pub async fn hls_session_stop(
     let owner_key =
         ferrite_stream::hls::HlsSessionManager::owner_key(&id, Some(playback_session_id));
     state.hls_sessions.destroy_owner_sessions(&owner_key).await;
+    state.hls_sessions.unregister_owner(&owner_key);
+    state.transcodes.release_owner(&owner_key);
     Ok(StatusCode::NO_CONTENT)
 }
 
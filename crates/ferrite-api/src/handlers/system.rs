@@ -1,12 +1,17 @@
 use crate::auth::AuthUser;
 use crate::error::ApiError;
+use crate::events::{EventHub, ServerEvent};
 use crate::state::AppState;
-use axum::extract::{Query, State};
-use axum::response::{Html, IntoResponse, Json};
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::{Path, Query, State};
+use axum::response::{Html, IntoResponse, Json, Response};
 use axum::Extension;
-use ferrite_db::user_repo;
+use ferrite_db::termination_repo::{self, NewTermination};
+use ferrite_db::{library_repo, user_repo};
+use ferrite_stream::hls::HlsSessionManager;
 use serde::Deserialize;
 use serde_json::json;
+use tokio::sync::broadcast::error::RecvError;
 
 pub async fn health() -> impl IntoResponse {
     Json(json!({ "status": "ok" }))
@@ -19,30 +24,64 @@ pub async fn info() -> impl IntoResponse {
     }))
 }
 
-/// GET /api/system/encoder — returns the active video encoder profile and backend.
-pub async fn encoder_info(State(state): State<AppState>) -> impl IntoResponse {
+/// GET /api/system/encoder — returns the active video encoder profile and backend,
+/// the server-wide encoding settings and the effective settings of every library
+/// with an override.
+pub async fn encoder_info(State(state): State<AppState>) -> Result<impl IntoResponse, ApiError> {
     let profile = &state.encoder_profile;
-    Json(json!({
+    let transcode = &state.config.transcode;
+
+    let mut libraries = Vec::new();
+    if !transcode.library_encoding.is_empty() {
+        for library in library_repo::list_libraries(&state.db.read).await? {
+            let id = library.id.to_string();
+            if transcode.library_encoding.contains_key(&id)
+                || transcode.library_encoding.contains_key(&library.name)
+            {
+                libraries.push(json!({
+                    "library_id": id,
+                    "name": library.name,
+                    "encoding": transcode.encoding_for(&id, &library.name),
+                }));
+            }
+        }
+    }
+
+    Ok(Json(json!({
         "backend": format!("{}", profile.backend),
         "encoder_name": profile.encoder_name,
         "is_hardware": profile.is_hardware(),
-    }))
+        "encoding": transcode.encoding,
+        "libraries": libraries,
+    })))
 }
 
-/// GET /api/admin/streams — list all active HLS transcode sessions (admin only).
+/// GET /api/admin/streams — list all active HLS transcode sessions and the
+/// transcode scheduler's slots and queue (admin only).
 pub async fn list_active_streams(
     State(state): State<AppState>,
     auth_user: Option<Extension<AuthUser>>,
 ) -> Result<impl IntoResponse, ApiError> {
     ensure_admin_if_present(&state, auth_user.as_ref()).await?;
+    Ok(Json(active_streams(&state).await))
+}
 
+/// Active HLS sessions and the transcode scheduler state, as returned by
+/// `GET /api/admin/streams` and pushed on the admin event stream.
+pub(crate) async fn active_streams(state: &AppState) -> serde_json::Value {
     let sessions = state.hls_sessions.list_active_sessions();
     let count = sessions.len();
     let items: Vec<serde_json::Value> = sessions
         .into_iter()
         .map(|s| {
+            let playback_session_id = s
+                .owner_key
+                .as_deref()
+                .and_then(|o| HlsSessionManager::split_owner_key(o).1);
             json!({
                 "session_id": s.session_id,
+                "playback_session_id": playback_session_id,
+                "user_id": s.user_id,
                 "media_id": s.media_id,
                 "variant_label": s.variant_label,
                 "start_secs": s.start_secs,
@@ -54,7 +93,322 @@ pub async fn list_active_streams(
             })
         })
         .collect();
-    Ok(Json(json!({ "sessions": items, "count": count })))
+    let transcodes = state.transcodes.snapshot(&state.hls_sessions).await;
+    json!({
+        "sessions": items,
+        "count": count,
+        "transcodes": transcodes,
+    })
+}
+
+/// GET /api/events — WebSocket pushing scan progress, watcher library
+/// changes, stream/transcode state and download job progress to admins as
+/// JSON messages tagged by `type`. The current state is sent on connect.
+/// Browsers authenticate with `?token=`.
+pub async fn event_stream(
+    State(state): State<AppState>,
+    auth_user: Option<Extension<AuthUser>>,
+    ws: WebSocketUpgrade,
+) -> Result<Response, ApiError> {
+    ensure_admin_if_present(&state, auth_user.as_ref()).await?;
+    Ok(ws.on_upgrade(move |socket| forward_events(state, socket)))
+}
+
+async fn forward_events(state: AppState, mut socket: WebSocket) {
+    // Subscribe before the snapshot so nothing between the two is lost.
+    let mut events = state.events.subscribe();
+    if send_events(&mut socket, EventHub::snapshot(&state).await)
+        .await
+        .is_err()
+    {
+        return;
+    }
+    loop {
+        tokio::select! {
+            event = events.recv() => {
+                let sent = match event {
+                    Ok(event) => send_events(&mut socket, vec![event]).await,
+                    // Too slow to keep up: resync from a fresh snapshot.
+                    Err(RecvError::Lagged(_)) => {
+                        send_events(&mut socket, EventHub::snapshot(&state).await).await
+                    }
+                    Err(RecvError::Closed) => return,
+                };
+                if sent.is_err() {
+                    return;
+                }
+            }
+            message = socket.recv() => match message {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => return,
+                // Clients have nothing to say; pings are answered by axum.
+                Some(Ok(_)) => {}
+            },
+        }
+    }
+}
+
+async fn send_events(socket: &mut WebSocket, events: Vec<ServerEvent>) -> Result<(), axum::Error> {
+    for event in events {
+        let text = serde_json::to_string(&event).unwrap_or_default();
+        socket.send(Message::Text(text.into())).await?;
+    }
+    Ok(())
+}
+
+/// Longest termination reason shown to the viewer.
+const MAX_REASON_LEN: usize = 500;
+
+#[derive(Deserialize, Default)]
+pub struct TerminateRequest {
+    /// Shown to the viewer and kept in the audit log
+    #[serde(default)]
+    pub reason: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct TerminationListQuery {
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+fn clean_reason(reason: Option<String>) -> Result<Option<String>, ApiError> {
+    let reason = reason
+        .map(|r| r.trim().to_string())
+        .filter(|r| !r.is_empty());
+    if reason
+        .as_ref()
+        .is_some_and(|r| r.chars().count() > MAX_REASON_LEN)
+    {
+        return Err(ApiError::bad_request(format!(
+            "Reason must be at most {MAX_REASON_LEN} characters"
+        )));
+    }
+    Ok(reason)
+}
+
+/// Write a termination to the audit log. The target's username is looked up
+/// so the entry stays readable after the user is deleted.
+async fn audit_termination(
+    state: &AppState,
+    admin: Option<&AuthUser>,
+    target_user_id: Option<&str>,
+    media_id: Option<&str>,
+    playback_session_id: Option<&str>,
+    scope: &str,
+    reason: Option<&str>,
+) -> Result<(), ApiError> {
+    let target_username = match target_user_id {
+        Some(id) => user_repo::get_user_by_id(&state.db.read, id)
+            .await?
+            .map(|u| u.username),
+        None => None,
+    };
+    termination_repo::record_termination(
+        &state.db.write,
+        &NewTermination {
+            admin_user_id: admin.map(|a| a.user_id.as_str()),
+            admin_username: admin.map(|a| a.username.as_str()),
+            target_user_id,
+            target_username: target_username.as_deref(),
+            media_id,
+            playback_session_id,
+            scope,
+            reason,
+        },
+    )
+    .await?;
+    Ok(())
+}
+
+/// Stop an HLS playback, free its transcode slot and audit it.
+async fn stop_playback(
+    state: &AppState,
+    admin: Option<&AuthUser>,
+    owner_key: &str,
+    scope: &str,
+    reason: Option<&str>,
+) -> Result<(), ApiError> {
+    let target_user_id = state.hls_sessions.owner_user(owner_key);
+    state
+        .hls_sessions
+        .terminate_owner(owner_key, reason.map(str::to_string))
+        .await;
+    state.transcodes.release_owner(owner_key);
+    let (media_id, playback_session_id) = HlsSessionManager::split_owner_key(owner_key);
+    audit_termination(
+        state,
+        admin,
+        target_user_id.as_deref(),
+        Some(media_id),
+        playback_session_id,
+        scope,
+        reason,
+    )
+    .await
+}
+
+/// POST /api/admin/streams/{session_id}/terminate — stop the playback an HLS
+/// session belongs to. The viewer gets `reason` on its next heartbeat or
+/// segment request (admin only).
+pub async fn terminate_stream(
+    State(state): State<AppState>,
+    auth_user: Option<Extension<AuthUser>>,
+    Path(session_id): Path<String>,
+    Json(body): Json<TerminateRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    ensure_admin_if_present(&state, auth_user.as_ref()).await?;
+    let reason = clean_reason(body.reason)?;
+    let owner_key = state
+        .hls_sessions
+        .owner_of_session(&session_id)
+        .ok_or_else(|| ApiError::not_found(format!("Stream '{session_id}' not found")))?;
+    stop_playback(
+        &state,
+        auth_user.as_deref(),
+        &owner_key,
+        "session",
+        reason.as_deref(),
+    )
+    .await?;
+    let (media_id, playback_session_id) = HlsSessionManager::split_owner_key(&owner_key);
+    Ok(Json(json!({
+        "status": "ok",
+        "media_id": media_id,
+        "playback_session_id": playback_session_id,
+    })))
+}
+
+/// POST /api/admin/users/{id}/streams/terminate — stop every playback and
+/// progressive transcode of a user (admin only).
+pub async fn terminate_user_streams(
+    State(state): State<AppState>,
+    auth_user: Option<Extension<AuthUser>>,
+    Path(user_id): Path<String>,
+    Json(body): Json<TerminateRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    ensure_admin_if_present(&state, auth_user.as_ref()).await?;
+    let reason = clean_reason(body.reason)?;
+    user_repo::get_user_by_id(&state.db.read, &user_id)
+        .await?
+        .ok_or_else(|| ApiError::not_found(format!("User '{user_id}' not found")))?;
+
+    let owners = state.hls_sessions.active_owners_for_user(&user_id);
+    for owner_key in &owners {
+        stop_playback(
+            &state,
+            auth_user.as_deref(),
+            owner_key,
+            "user",
+            reason.as_deref(),
+        )
+        .await?;
+    }
+    let progressive = state.transcodes.terminate_user_progressive(&user_id);
+    for stopped in &progressive {
+        audit_termination(
+            &state,
+            auth_user.as_deref(),
+            Some(&user_id),
+            Some(&stopped.media_id),
+            None,
+            "user",
+            reason.as_deref(),
+        )
+        .await?;
+    }
+    Ok(Json(json!({
+        "status": "ok",
+        "stopped": owners.len() + progressive.len(),
+    })))
+}
+
+/// DELETE /api/admin/transcodes/{id} — stop a running transcode by the slot
+/// id from `/api/admin/streams`, with an optional `?reason=` (admin only).
+pub async fn terminate_transcode(
+    State(state): State<AppState>,
+    auth_user: Option<Extension<AuthUser>>,
+    Path(id): Path<String>,
+    Query(query): Query<TerminateRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    ensure_admin_if_present(&state, auth_user.as_ref()).await?;
+    let reason = clean_reason(query.reason)?;
+    let stopped = state
+        .transcodes
+        .terminate(&id)
+        .ok_or_else(|| ApiError::not_found(format!("Transcode '{id}' not found")))?;
+    if stopped.hls {
+        stop_playback(
+            &state,
+            auth_user.as_deref(),
+            &stopped.key,
+            "session",
+            reason.as_deref(),
+        )
+        .await?;
+    } else {
+        audit_termination(
+            &state,
+            auth_user.as_deref(),
+            stopped.user_id.as_deref(),
+            Some(&stopped.media_id),
+            None,
+            "transcode",
+            reason.as_deref(),
+        )
+        .await?;
+    }
+    Ok(Json(json!({ "status": "ok" })))
+}
+
+/// GET /api/admin/streams/terminations — audit log of streams stopped by
+/// administrators, newest first (admin only).
+pub async fn list_stream_terminations(
+    State(state): State<AppState>,
+    auth_user: Option<Extension<AuthUser>>,
+    Query(query): Query<TerminationListQuery>,
+) -> Result<impl IntoResponse, ApiError> {
+    ensure_admin_if_present(&state, auth_user.as_ref()).await?;
+    let limit = query.limit.unwrap_or(50).clamp(1, 500);
+    let offset = query.offset.unwrap_or(0).max(0);
+    let entries = termination_repo::list_terminations(&state.db.read, limit, offset).await?;
+    Ok(Json(json!({ "terminations": entries })))
+}
+
+/// GET /api/system/segment-cache — segment cache usage (admin only).
+pub async fn segment_cache_stats(
+    State(state): State<AppState>,
+    auth_user: Option<Extension<AuthUser>>,
+) -> Result<impl IntoResponse, ApiError> {
+    ensure_admin_if_present(&state, auth_user.as_ref()).await?;
+    let Some(cache) = state.hls_sessions.segment_cache() else {
+        return Ok(Json(json!({ "enabled": false })));
+    };
+    let stats = cache.stats();
+    Ok(Json(json!({
+        "enabled": true,
+        "entries": stats.entries,
+        "segments": stats.segments,
+        "used_bytes": stats.used_bytes,
+        "max_bytes": stats.max_bytes,
+    })))
+}
+
+/// DELETE /api/system/segment-cache — drop every cached segment (admin only).
+/// Sessions already serving cached segments keep their own links.
+pub async fn clear_segment_cache(
+    State(state): State<AppState>,
+    auth_user: Option<Extension<AuthUser>>,
+) -> Result<impl IntoResponse, ApiError> {
+    ensure_admin_if_present(&state, auth_user.as_ref()).await?;
+    let cache = state
+        .hls_sessions
+        .segment_cache()
+        .cloned()
+        .ok_or_else(|| ApiError::bad_request("Segment cache is not enabled"))?;
+    tokio::task::spawn_blocking(move || cache.clear())
+        .await
+        .map_err(|e| ApiError::internal(e.to_string()))??;
+    Ok(Json(json!({ "status": "ok" })))
 }
 
 #[derive(Deserialize)]
@@ -125,7 +479,7 @@ pub async fn track_playback_metric(
     Ok(Json(json!({ "status": "ok" })))
 }
 
-async fn ensure_admin_if_present(
+pub(crate) async fn ensure_admin_if_present(
     state: &AppState,
     auth_user: Option<&Extension<AuthUser>>,
 ) -> Result<(), ApiError> {
@@ -462,11 +816,10 @@ async fn perform_update(
             .await
             .map_err(|e| format!("Failed to write tarball: {e}"))?;
         downloaded += chunk.len() as u64;
-        let pct = if content_length > 0 {
-            ((downloaded * 100) / content_length).min(100) as u8
-        } else {
-            0
-        };
+        let pct = (downloaded * 100)
+            .checked_div(content_length)
+            .map(|p| p.min(100) as u8)
+            .unwrap_or(0);
         let mut progress = update_state.progress.lock().await;
         progress.downloaded_bytes = downloaded;
         progress.progress_pct = pct;
@@ -1,10 +1,16 @@
 use crate::auth::AuthUser;
 use crate::error::ApiError;
+use crate::handlers::collection;
+use crate::handlers::system::ensure_admin_if_present;
+use crate::locale;
+use crate::play_queue;
 use crate::state::AppState;
 use axum::extract::{Path, State};
 use axum::response::IntoResponse;
 use axum::{Extension, Json};
-use ferrite_db::tv_repo;
+use ferrite_db::{queue_repo, tv_repo};
+use ferrite_metadata::provider::MetadataProvider;
+use ferrite_metadata::tmdb::TmdbProvider;
 
 fn extract_user_id(auth_user: &Option<AuthUser>) -> Option<&str> {
     auth_user.as_ref().map(|u| u.user_id.as_str())
@@ -13,14 +19,20 @@ fn extract_user_id(auth_user: &Option<AuthUser>) -> Option<&str> {
 /// GET /api/shows?library_id={id} — list all TV shows in a library
 pub async fn list_shows(
     State(state): State<AppState>,
+    auth_user: Option<Extension<AuthUser>>,
     axum::extract::Query(params): axum::extract::Query<ListShowsQuery>,
 ) -> Result<impl IntoResponse, ApiError> {
+    let user = auth_user.map(|e| e.0);
+    let user_id = extract_user_id(&user);
     let library_id = params
         .library_id
         .as_deref()
         .ok_or_else(|| ApiError::bad_request("library_id query parameter is required"))?;
 
-    let shows = tv_repo::list_shows(&state.db.read, library_id).await?;
+    let mut shows = tv_repo::list_shows(&state.db.read, library_id).await?;
+    if let Some(lang) = locale::user_translation_language(&state, user_id).await? {
+        locale::localize_shows(&state, &lang, &mut shows).await?;
+    }
     Ok(Json(shows))
 }
 
@@ -32,11 +44,17 @@ pub struct ListShowsQuery {
 /// GET /api/shows/{id} — get a single TV show with season/episode counts
 pub async fn get_show(
     State(state): State<AppState>,
+    auth_user: Option<Extension<AuthUser>>,
     Path(id): Path<String>,
 ) -> Result<impl IntoResponse, ApiError> {
-    let show = tv_repo::get_show(&state.db.read, &id)
+    let user = auth_user.map(|e| e.0);
+    let user_id = extract_user_id(&user);
+    let mut show = tv_repo::get_show(&state.db.read, &id)
         .await?
         .ok_or_else(|| ApiError::not_found(format!("TV show '{id}' not found")))?;
+    if let Some(lang) = locale::user_translation_language(&state, user_id).await? {
+        locale::localize_shows(&state, &lang, std::slice::from_mut(&mut show)).await?;
+    }
     Ok(Json(show))
 }
 
@@ -49,6 +67,74 @@ pub async fn list_seasons(
     Ok(Json(seasons))
 }
 
+/// TMDB provider and TMDB id of a show, for calls that need the provider.
+async fn show_on_tmdb(state: &AppState, show_id: &str) -> Result<(TmdbProvider, i64), ApiError> {
+    let show = tv_repo::get_show(&state.db.read, show_id)
+        .await?
+        .ok_or_else(|| ApiError::not_found(format!("TV show '{show_id}' not found")))?;
+    let tmdb_id = show
+        .tmdb_id
+        .ok_or_else(|| ApiError::bad_request("TV show has not been matched on TMDB"))?;
+    let api_key = state
+        .config
+        .metadata
+        .tmdb_api_key
+        .clone()
+        .ok_or_else(|| ApiError::bad_request("TMDB is not configured"))?;
+    let provider = TmdbProvider::new(api_key, state.config.metadata.rate_limit_per_second)
+        .with_languages(&state.config.metadata.languages);
+    Ok((provider, tmdb_id))
+}
+
+/// GET /api/shows/{id}/episode-groups — alternative episode orderings TMDB
+/// lists for a show, to pick one for absolute episode numbering
+pub async fn list_episode_groups(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<impl IntoResponse, ApiError> {
+    let (provider, tmdb_id) = show_on_tmdb(&state, &id).await?;
+    let groups = provider.get_episode_groups(tmdb_id).await?;
+    Ok(Json(groups))
+}
+
+#[derive(serde::Deserialize)]
+pub struct SetEpisodeGroupRequest {
+    pub episode_group_id: Option<String>,
+}
+
+/// PUT /api/shows/{id}/episode-group — map the show's absolutely numbered
+/// episodes through a TMDB episode group (`null` for the regular seasons).
+/// The episodes are remapped on the next metadata pass (admin only).
+pub async fn set_episode_group(
+    State(state): State<AppState>,
+    auth_user: Option<Extension<AuthUser>>,
+    Path(id): Path<String>,
+    Json(req): Json<SetEpisodeGroupRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    ensure_admin_if_present(&state, auth_user.as_ref()).await?;
+    let group_id = req
+        .episode_group_id
+        .map(|g| g.trim().to_string())
+        .filter(|g| !g.is_empty());
+    if let Some(group_id) = &group_id {
+        let (provider, tmdb_id) = show_on_tmdb(&state, &id).await?;
+        let groups = provider.get_episode_groups(tmdb_id).await?;
+        if !groups.iter().any(|g| &g.id == group_id) {
+            return Err(ApiError::bad_request(format!(
+                "Episode group '{group_id}' does not belong to this show"
+            )));
+        }
+    }
+
+    if !tv_repo::set_episode_group(&state.db.write, &id, group_id.as_deref()).await? {
+        return Err(ApiError::not_found(format!("TV show '{id}' not found")));
+    }
+    let show = tv_repo::get_show(&state.db.read, &id)
+        .await?
+        .ok_or_else(|| ApiError::not_found(format!("TV show '{id}' not found")))?;
+    Ok(Json(show))
+}
+
 /// GET /api/seasons/{id}/episodes — list all episodes in a season
 pub async fn list_episodes(
     State(state): State<AppState>,
@@ -61,14 +147,25 @@ pub async fn list_episodes(
     Ok(Json(episodes))
 }
 
-/// GET /api/episodes/{media_item_id}/next — get the next episode after this one
+/// GET /api/episodes/{media_item_id}/next — get what plays after this item.
+/// When the item is in the user's play queue the queue decides ("source":
+/// "queue"); otherwise it is the next episode of the show ("source": "episode").
 pub async fn next_episode(
     State(state): State<AppState>,
+    auth_user: Option<Extension<AuthUser>>,
     Path(media_item_id): Path<String>,
 ) -> Result<impl IntoResponse, ApiError> {
-    let next = tv_repo::get_next_episode(&state.db.read, &media_item_id).await?;
-    match next {
-        Some(ep) => Ok(Json(serde_json::json!({ "next": ep }))),
-        None => Ok(Json(serde_json::json!({ "next": null }))),
+    let user_id = collection::extract_user_id(&state, auth_user.as_deref()).await;
+    if let Some(mut queue) = play_queue::load(&state.db, &user_id).await? {
+        if queue.focus(&media_item_id) {
+            let entries = queue_repo::list_queue_entries(&state.db.read, &user_id).await?;
+            let next = queue.next_index().and_then(|i| entries.get(i));
+            return Ok(Json(serde_json::json!({ "next": next, "source": "queue" })));
+        }
     }
+
+    let next = tv_repo::get_next_episode(&state.db.read, &media_item_id).await?;
+    Ok(Json(
+        serde_json::json!({ "next": next, "source": "episode" }),
+    ))
 }
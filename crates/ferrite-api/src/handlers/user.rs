@@ -181,6 +181,55 @@ pub struct AdminResetPasswordRequest {
     pub new_password: String,
 }
 
+/// PUT /api/users/{id}/limits — set a user's streaming limits (admin only).
+/// Omitted/null fields fall back to the server defaults; 0 means unlimited.
+pub async fn update_stream_limits(
+    State(state): State<AppState>,
+    auth_user: Option<Extension<AuthUser>>,
+    axum::extract::Path(target_id): axum::extract::Path<String>,
+    Json(req): Json<StreamLimitsRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    let caller = auth_user
+        .as_ref()
+        .ok_or_else(|| ApiError::unauthorized("Authentication required"))?;
+
+    let caller_user = user_repo::get_user_by_id(&state.db.read, &caller.user_id)
+        .await?
+        .ok_or_else(|| ApiError::unauthorized("User not found"))?;
+
+    if caller_user.is_admin == 0 {
+        return Err(ApiError::forbidden("Only admins can change stream limits"));
+    }
+
+    if req.remote_max_bitrate_kbps.is_some_and(|v| v < 0)
+        || req.max_concurrent_streams.is_some_and(|v| v < 0)
+    {
+        return Err(ApiError::bad_request("Limits cannot be negative"));
+    }
+
+    let updated = user_repo::update_stream_limits(
+        &state.db.write,
+        &target_id,
+        req.remote_max_bitrate_kbps,
+        req.max_concurrent_streams,
+    )
+    .await?;
+    if !updated {
+        return Err(ApiError::not_found(format!("User '{target_id}' not found")));
+    }
+
+    Ok(Json(serde_json::json!({
+        "remote_max_bitrate_kbps": req.remote_max_bitrate_kbps,
+        "max_concurrent_streams": req.max_concurrent_streams,
+    })))
+}
+
+#[derive(Deserialize)]
+pub struct StreamLimitsRequest {
+    pub remote_max_bitrate_kbps: Option<i64>,
+    pub max_concurrent_streams: Option<i64>,
+}
+
 /// GET /api/users/setup — check if initial setup is needed (no users exist)
 pub async fn setup_status(State(state): State<AppState>) -> Result<impl IntoResponse, ApiError> {
     let count = user_repo::count_users(&state.db.read).await?;
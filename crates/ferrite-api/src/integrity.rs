@@ -0,0 +1,174 @@
+use ferrite_core::config::{AppConfig, IntegrityMode};
+use ferrite_db::integrity_repo::{self, IntegrityInsert, IntegrityTarget};
+use ferrite_db::Database;
+use ferrite_transcode::integrity::{self, HealthStatus, IntegrityReport};
+use serde::Serialize;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Semaphore;
+use tracing::{info, warn};
+
+/// Delay before the first scheduled pass, so it doesn't compete with the
+/// startup scan.
+const SCHEDULE_START_DELAY: Duration = Duration::from_secs(600);
+
+/// Progress of the pass in flight, returned by GET /api/admin/integrity.
+#[derive(Debug, Clone, Serialize)]
+pub struct PassProgress {
+    pub library_id: Option<String>,
+    pub mode: IntegrityMode,
+    pub total: usize,
+    pub checked: usize,
+    pub damaged: usize,
+    pub broken: usize,
+}
+
+/// Decodes media files to find corrupt or truncated ones: single items on
+/// demand, and passes over the library on demand or on the configured schedule.
+pub struct IntegrityChecker {
+    db: Database,
+    config: Arc<AppConfig>,
+    /// Checks decode whole windows of video, so only one runs at a time.
+    slot: Semaphore,
+    pass: std::sync::Mutex<Option<PassProgress>>,
+}
+
+impl IntegrityChecker {
+    pub fn new(db: Database, config: Arc<AppConfig>) -> Self {
+        Self {
+            db,
+            config,
+            slot: Semaphore::new(1),
+            pass: std::sync::Mutex::new(None),
+        }
+    }
+
+    pub fn pass_progress(&self) -> Option<PassProgress> {
+        self.pass.lock().unwrap().clone()
+    }
+
+    /// Check one file and store the result.
+    pub async fn check(
+        &self,
+        target: &IntegrityTarget,
+        mode: IntegrityMode,
+    ) -> anyhow::Result<IntegrityReport> {
+        let _slot = self.slot.acquire().await?;
+        let report = integrity::check_file(
+            &self.config.transcode.ffmpeg_path,
+            Path::new(&target.file_path),
+            target.duration_ms,
+            mode,
+        )
+        .await?;
+        integrity_repo::save_integrity(
+            &self.db.write,
+            &IntegrityInsert {
+                media_item_id: &target.id,
+                status: report.status.as_str(),
+                mode: mode.as_str(),
+                error_count: report.error_count,
+                errors: &report.errors,
+                decoded_ms: report.decoded_ms,
+                file_size: target.file_size,
+            },
+        )
+        .await?;
+        if report.status != HealthStatus::Ok {
+            warn!(
+                "Integrity check: {} is {} ({} error(s))",
+                target.file_path,
+                report.status.as_str(),
+                report.error_count
+            );
+        }
+        Ok(report)
+    }
+
+    /// Start a pass over the items due in `library_id` (all libraries when
+    /// `None`) in the background. Returns the number of items queued, or
+    /// `None` if a pass is already running.
+    pub async fn start_pass(
+        self: &Arc<Self>,
+        library_id: Option<String>,
+        mode: IntegrityMode,
+        recheck_days: u64,
+        limit: Option<u32>,
+    ) -> anyhow::Result<Option<usize>> {
+        let targets = integrity_repo::list_due_for_check(
+            &self.db.read,
+            library_id.as_deref(),
+            recheck_days,
+            limit,
+        )
+        .await?;
+        {
+            let mut pass = self.pass.lock().unwrap();
+            if pass.is_some() {
+                return Ok(None);
+            }
+            *pass = Some(PassProgress {
+                library_id,
+                mode,
+                total: targets.len(),
+                checked: 0,
+                damaged: 0,
+                broken: 0,
+            });
+        }
+        let queued = targets.len();
+        let checker = self.clone();
+        tokio::spawn(async move { checker.run_pass(targets, mode).await });
+        Ok(Some(queued))
+    }
+
+    async fn run_pass(&self, targets: Vec<IntegrityTarget>, mode: IntegrityMode) {
+        info!(
+            "Integrity check of {} file(s) started ({} mode)",
+            targets.len(),
+            mode.as_str()
+        );
+        for target in &targets {
+            let status = match self.check(target, mode).await {
+                Ok(report) => Some(report.status),
+                Err(e) => {
+                    warn!("Integrity check of {} failed: {:#}", target.file_path, e);
+                    None
+                }
+            };
+            if let Some(pass) = self.pass.lock().unwrap().as_mut() {
+                pass.checked += 1;
+                match status {
+                    Some(HealthStatus::Damaged) => pass.damaged += 1,
+                    Some(HealthStatus::Broken) => pass.broken += 1,
+                    _ => {}
+                }
+            }
+        }
+        if let Some(pass) = self.pass.lock().unwrap().take() {
+            info!(
+                "Integrity check finished: {} checked, {} damaged, {} broken",
+                pass.checked, pass.damaged, pass.broken
+            );
+        }
+    }
+
+    /// Background loop: every `interval_hours`, checks up to `items_per_pass`
+    /// files that were never checked, changed, or are due a recheck. Never returns.
+    pub async fn run(self: Arc<Self>) {
+        let cfg = self.config.integrity.clone();
+        let interval = Duration::from_secs(cfg.interval_hours.max(1) * 3600);
+        tokio::time::sleep(SCHEDULE_START_DELAY).await;
+        loop {
+            // A pass still running from an on-demand check is left to finish.
+            if let Err(e) = self
+                .start_pass(None, cfg.mode, cfg.recheck_days, Some(cfg.items_per_pass))
+                .await
+            {
+                warn!("Failed to start scheduled integrity check: {:#}", e);
+            }
+            tokio::time::sleep(interval).await;
+        }
+    }
+}
@@ -1,7 +1,18 @@
+pub mod audio;
 pub mod auth;
+pub mod downloads;
+pub mod encoding;
 pub mod error;
+pub mod events;
 pub mod handlers;
+pub mod integrity;
+pub mod library_stats;
+pub mod livetv;
+pub mod locale;
 pub mod metrics;
+pub mod network;
+pub mod play_queue;
 pub mod router;
+pub mod scheduler;
 pub mod state;
 pub mod webhooks;
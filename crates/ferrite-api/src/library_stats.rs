@@ -0,0 +1,284 @@
+//! Storage and format statistics for the admin dashboard.
+//!
+//! Totals come straight from SQL; the breakdowns are computed here from one
+//! format row per file so they can reuse the playback code's own notion of
+//! resolution classes, HDR formats and client compatibility.
+
+use ferrite_db::stats_repo::MediaFormatRow;
+use ferrite_stream::compat::{determine_strategy_for_stream, ClientProfile, StreamStrategy};
+use ferrite_transcode::tonemap::{hdr_format, DolbyVision};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// Upper bounds (exclusive) of the bitrate histogram buckets, in kbps. A
+/// final open-ended bucket holds everything above the last bound.
+const BITRATE_BUCKETS_KBPS: &[u64] = &[2_000, 5_000, 10_000, 20_000, 40_000];
+
+/// Number of files and their combined size.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Tally {
+    pub count: u64,
+    pub size: u64,
+}
+
+impl Tally {
+    fn add(&mut self, size: u64) {
+        self.count += 1;
+        self.size += size;
+    }
+}
+
+/// One bitrate histogram bucket, `min_kbps` inclusive to `max_kbps` exclusive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct BitrateBucket {
+    pub min_kbps: u64,
+    /// `None` for the open-ended top bucket
+    pub max_kbps: Option<u64>,
+    pub count: u64,
+    pub size: u64,
+}
+
+/// How the files of a library would stream to one client profile.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct StrategyCounts {
+    pub direct_play: u64,
+    pub remux: u64,
+    pub audio_transcode: u64,
+    pub full_transcode: u64,
+    /// Combined size of the files needing a full transcode
+    pub full_transcode_size: u64,
+}
+
+/// Format breakdown of a set of media files.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FormatStats {
+    /// Files, so every version and stack part of a title counts
+    pub file_count: u64,
+    pub total_size: u64,
+    pub total_duration_ms: u64,
+    /// `sd`, `720p`, `1080p`, `2160p` or `unknown`, classed by width like
+    /// the smart collection resolution rule
+    pub resolutions: BTreeMap<&'static str, Tally>,
+    pub video_codecs: BTreeMap<String, Tally>,
+    pub audio_codecs: BTreeMap<String, Tally>,
+    pub containers: BTreeMap<String, Tally>,
+    /// `sdr`, `hdr10`, `hdr10_plus`, `hlg` or `dolby_vision`; files without
+    /// video aren't counted
+    pub hdr: BTreeMap<&'static str, Tally>,
+    pub bitrates: Vec<BitrateBucket>,
+    /// Files with neither a probed bitrate nor a size and duration to derive one
+    pub bitrate_unknown: Tally,
+    /// Playback strategy counts keyed by client profile
+    pub strategies: BTreeMap<&'static str, StrategyCounts>,
+}
+
+impl Default for FormatStats {
+    fn default() -> Self {
+        let mut bitrates = Vec::with_capacity(BITRATE_BUCKETS_KBPS.len() + 1);
+        let mut min_kbps = 0;
+        for &max in BITRATE_BUCKETS_KBPS {
+            bitrates.push(BitrateBucket {
+                min_kbps,
+                max_kbps: Some(max),
+                count: 0,
+                size: 0,
+            });
+            min_kbps = max;
+        }
+        bitrates.push(BitrateBucket {
+            min_kbps,
+            max_kbps: None,
+            count: 0,
+            size: 0,
+        });
+        Self {
+            file_count: 0,
+            total_size: 0,
+            total_duration_ms: 0,
+            resolutions: BTreeMap::new(),
+            video_codecs: BTreeMap::new(),
+            audio_codecs: BTreeMap::new(),
+            containers: BTreeMap::new(),
+            hdr: BTreeMap::new(),
+            bitrates,
+            bitrate_unknown: Tally::default(),
+            strategies: ClientProfile::ALL
+                .iter()
+                .map(|p| (p.as_str(), StrategyCounts::default()))
+                .collect(),
+        }
+    }
+}
+
+/// Resolution class of a video `width`, matching the smart collection rule.
+fn resolution_class(width: Option<i64>) -> &'static str {
+    match width {
+        Some(w) if w >= 3200 => "2160p",
+        Some(w) if w >= 1800 => "1080p",
+        Some(w) if w >= 1200 => "720p",
+        Some(w) if w > 0 => "sd",
+        _ => "unknown",
+    }
+}
+
+/// Probed overall bitrate, or one derived from size and duration.
+fn bitrate_kbps(row: &MediaFormatRow) -> Option<u64> {
+    if let Some(kbps) = row.bitrate_kbps.filter(|b| *b > 0) {
+        return Some(kbps as u64);
+    }
+    let duration_ms = row.duration_ms.filter(|d| *d > 0)?;
+    // bytes * 8 / ms = kbit/s
+    Some((row.file_size.max(0) as u64 * 8) / duration_ms as u64)
+}
+
+fn codec_key(codec: Option<&str>) -> String {
+    codec.map_or_else(|| "none".to_string(), str::to_ascii_lowercase)
+}
+
+/// Aggregate the format rows of a library (or of all libraries).
+///
+/// Strategies use [`determine_strategy_for_stream`], i.e.
+/// `determine_strategy_for_profile` plus the Dolby Vision check the playback
+/// path applies, on each file's container and primary codecs.
+pub fn aggregate(rows: &[MediaFormatRow]) -> FormatStats {
+    let mut stats = FormatStats::default();
+    for row in rows {
+        let size = row.file_size.max(0) as u64;
+        stats.file_count += 1;
+        stats.total_size += size;
+        stats.total_duration_ms += row.duration_ms.unwrap_or(0).max(0) as u64;
+
+        let dolby_vision = DolbyVision::from_columns(row.dv_profile, row.dv_bl_compat_id);
+        let has_video = row.video_codec.is_some();
+        if has_video {
+            stats
+                .resolutions
+                .entry(resolution_class(row.width))
+                .or_default()
+                .add(size);
+            let hdr = hdr_format(row.color_transfer.as_deref(), dolby_vision, row.hdr10_plus)
+                .map_or("sdr", |f| f.as_str());
+            stats.hdr.entry(hdr).or_default().add(size);
+        }
+        stats
+            .video_codecs
+            .entry(codec_key(row.video_codec.as_deref()))
+            .or_default()
+            .add(size);
+        stats
+            .audio_codecs
+            .entry(codec_key(row.audio_codec.as_deref()))
+            .or_default()
+            .add(size);
+        stats
+            .containers
+            .entry(codec_key(row.container_format.as_deref()))
+            .or_default()
+            .add(size);
+
+        match bitrate_kbps(row) {
+            Some(kbps) => {
+                let bucket = BITRATE_BUCKETS_KBPS
+                    .iter()
+                    .position(|max| kbps < *max)
+                    .unwrap_or(BITRATE_BUCKETS_KBPS.len());
+                stats.bitrates[bucket].count += 1;
+                stats.bitrates[bucket].size += size;
+            }
+            None => stats.bitrate_unknown.add(size),
+        }
+
+        for profile in ClientProfile::ALL {
+            let counts = stats.strategies.entry(profile.as_str()).or_default();
+            match determine_strategy_for_stream(
+                profile,
+                row.container_format.as_deref(),
+                row.video_codec.as_deref(),
+                row.audio_codec.as_deref(),
+                dolby_vision,
+            ) {
+                StreamStrategy::DirectPlay => counts.direct_play += 1,
+                StreamStrategy::Remux => counts.remux += 1,
+                StreamStrategy::AudioTranscode => counts.audio_transcode += 1,
+                StreamStrategy::FullTranscode => {
+                    counts.full_transcode += 1;
+                    counts.full_transcode_size += size;
+                }
+            }
+        }
+    }
+    stats
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(container: &str, video: Option<&str>, audio: &str, width: i64) -> MediaFormatRow {
+        MediaFormatRow {
+            file_size: 1_000,
+            duration_ms: Some(1_000),
+            container_format: Some(container.to_string()),
+            video_codec: video.map(str::to_string),
+            audio_codec: Some(audio.to_string()),
+            width: video.map(|_| width),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn aggregates_formats_and_playback_strategies() {
+        let mut dv5 = row("matroska", Some("hevc"), "truehd", 3840);
+        dv5.color_transfer = Some("smpte2084".into());
+        dv5.dv_profile = Some(5);
+        let mut hdr10 = row("mp4", Some("hevc"), "eac3", 3840);
+        hdr10.color_transfer = Some("smpte2084".into());
+        hdr10.bitrate_kbps = Some(45_000);
+        let rows = [
+            row("mp4", Some("h264"), "aac", 1920),
+            row("matroska", Some("h264"), "aac", 1280),
+            row("mp4", Some("h264"), "dts", 720),
+            hdr10,
+            dv5,
+            row("flac", None, "flac", 0),
+        ];
+        let stats = aggregate(&rows);
+
+        assert_eq!(stats.file_count, 6);
+        assert_eq!(stats.total_size, 6_000);
+        assert_eq!(stats.total_duration_ms, 6_000);
+        assert_eq!(stats.resolutions["2160p"].count, 2);
+        assert_eq!(stats.resolutions["1080p"].count, 1);
+        assert_eq!(stats.resolutions["720p"].count, 1);
+        assert_eq!(stats.resolutions["sd"].count, 1);
+        assert_eq!(stats.hdr["sdr"].count, 3);
+        assert_eq!(stats.hdr["hdr10"].count, 1);
+        assert_eq!(stats.hdr["dolby_vision"].count, 1);
+        assert_eq!(stats.video_codecs["h264"].count, 3);
+        assert_eq!(stats.video_codecs["none"].count, 1);
+        assert_eq!(stats.containers["matroska"].size, 2_000);
+        // 1000 bytes over one second is 8 kbps; the HDR10 file reports 45 Mbps
+        assert_eq!(stats.bitrates[0].count, 5);
+        assert_eq!(stats.bitrates.last().unwrap().count, 1);
+        assert_eq!(stats.bitrate_unknown.count, 0);
+
+        let web = stats.strategies["web-chrome"];
+        assert_eq!(web.direct_play, 2);
+        assert_eq!(web.remux, 1);
+        assert_eq!(web.audio_transcode, 1);
+        assert_eq!(web.full_transcode, 2);
+        assert_eq!(web.full_transcode_size, 2_000);
+        // tvOS plays the HDR10 file but not Dolby Vision profile 5
+        let tvos = stats.strategies["tvos"];
+        assert_eq!(tvos.full_transcode, 1);
+    }
+
+    #[test]
+    fn resolution_classes_follow_width() {
+        assert_eq!(resolution_class(Some(3840)), "2160p");
+        assert_eq!(resolution_class(Some(1920)), "1080p");
+        assert_eq!(resolution_class(Some(1280)), "720p");
+        assert_eq!(resolution_class(Some(640)), "sd");
+        assert_eq!(resolution_class(None), "unknown");
+    }
+}
@@ -0,0 +1,255 @@
+use chrono::{NaiveDateTime, Utc};
+use dashmap::DashMap;
+use ferrite_core::config::AppConfig;
+use ferrite_db::livetv_repo::{self, NewChannel, NewProgram, RecordingRow};
+use ferrite_db::Database;
+use ferrite_livetv::recorder::{self, RecordingOutcome};
+use ferrite_livetv::{m3u, xmltv};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::{Mutex, Notify};
+use tracing::{info, warn};
+
+/// How often the scheduler looks for recordings to start.
+const SCHEDULER_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Result of a channel/guide refresh, returned by POST /api/livetv/refresh.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RefreshSummary {
+    pub channels: u64,
+    pub channels_removed: u64,
+    pub programs: u64,
+}
+
+/// Imports channels and the programme guide from the configured tuner
+/// sources and runs scheduled DVR recordings.
+pub struct LiveTvManager {
+    db: Database,
+    config: Arc<AppConfig>,
+    /// Serialises refreshes triggered by the timer and by the API.
+    refresh_lock: Mutex<()>,
+    /// Stop handles for recordings currently running, keyed by recording ID.
+    active: DashMap<String, Arc<Notify>>,
+}
+
+impl LiveTvManager {
+    pub fn new(db: Database, config: Arc<AppConfig>) -> Self {
+        Self {
+            db,
+            config,
+            refresh_lock: Mutex::new(()),
+            active: DashMap::new(),
+        }
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.config.livetv.enabled
+    }
+
+    /// Re-import the M3U channel list and the XMLTV guide.
+    pub async fn refresh(&self) -> anyhow::Result<RefreshSummary> {
+        let _guard = self.refresh_lock.lock().await;
+        let cfg = &self.config.livetv;
+        let mut summary = RefreshSummary::default();
+
+        let guide = match cfg.xmltv_path.as_deref() {
+            Some(location) => Some(xmltv::parse(&ferrite_livetv::read_source(location).await?)?),
+            None => None,
+        };
+
+        if let Some(location) = cfg.m3u_path.as_deref() {
+            let playlist = m3u::parse(&ferrite_livetv::read_source(location).await?);
+            let channels = to_new_channels(&playlist, guide.as_ref());
+            let (upserted, removed) = livetv_repo::sync_channels(&self.db.write, &channels).await?;
+            summary.channels = upserted;
+            summary.channels_removed = removed;
+        }
+
+        if let Some(guide) = guide {
+            let programs: Vec<NewProgram> = guide
+                .programmes
+                .into_iter()
+                .map(|p| NewProgram {
+                    xmltv_id: p.channel,
+                    start_time: p.start,
+                    end_time: p.stop,
+                    title: p.title,
+                    subtitle: p.subtitle,
+                    description: p.description,
+                    category: p.category,
+                    episode_num: p.episode_num,
+                })
+                .collect();
+            summary.programs = livetv_repo::replace_programs(&self.db.write, &programs).await?;
+        }
+
+        info!(
+            "Live TV refresh: {} channel(s) ({} removed), {} programme(s)",
+            summary.channels, summary.channels_removed, summary.programs
+        );
+        Ok(summary)
+    }
+
+    /// Stop a running recording. Returns `false` if it wasn't running.
+    pub fn stop_recording(&self, id: &str) -> bool {
+        match self.active.remove(id) {
+            Some((_, stop)) => {
+                stop.notify_one();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Background loop: refreshes the guide every `guide_refresh_hours` and
+    /// starts due recordings. Never returns.
+    pub async fn run(self: Arc<Self>) {
+        match livetv_repo::requeue_interrupted(&self.db.write).await {
+            Ok(0) => {}
+            Ok(n) => info!("Resuming {} interrupted recording(s)", n),
+            Err(e) => warn!("Failed to resume interrupted recordings: {}", e),
+        }
+
+        let refresh_interval =
+            Duration::from_secs(self.config.livetv.guide_refresh_hours.max(1) * 3600);
+        let mut last_refresh: Option<Instant> = None;
+
+        loop {
+            if last_refresh.is_none_or(|t| t.elapsed() >= refresh_interval) {
+                if let Err(e) = self.refresh().await {
+                    warn!("Live TV refresh failed: {:#}", e);
+                }
+                last_refresh = Some(Instant::now());
+            }
+
+            match livetv_repo::fail_missed_recordings(&self.db.write).await {
+                Ok(0) => {}
+                Ok(n) => warn!("{} scheduled recording(s) were missed", n),
+                Err(e) => warn!("Failed to check for missed recordings: {}", e),
+            }
+
+            match livetv_repo::claim_due_recordings(&self.db.write).await {
+                Ok(due) => {
+                    for row in due {
+                        let manager = self.clone();
+                        tokio::spawn(async move { manager.record(row).await });
+                    }
+                }
+                Err(e) => warn!("Failed to claim due recordings: {}", e),
+            }
+
+            tokio::time::sleep(SCHEDULER_INTERVAL).await;
+        }
+    }
+
+    async fn record(&self, row: RecordingRow) {
+        let stop = Arc::new(Notify::new());
+        self.active.insert(row.id.clone(), stop.clone());
+        let result = self.capture(&row, &stop).await;
+        self.active.remove(&row.id);
+
+        let (status, error) = match result {
+            Ok(RecordingOutcome::Completed) => {
+                info!("Recording {} ('{}') completed", row.id, row.title);
+                ("completed", None)
+            }
+            Ok(RecordingOutcome::Stopped) => ("cancelled", None),
+            Err(e) => {
+                warn!("Recording {} ('{}') failed: {:#}", row.id, row.title, e);
+                ("failed", Some(format!("{e:#}")))
+            }
+        };
+        if let Err(e) =
+            livetv_repo::finish_recording(&self.db.write, &row.id, status, error.as_deref()).await
+        {
+            warn!("Failed to update recording {}: {}", row.id, e);
+        }
+    }
+
+    async fn capture(&self, row: &RecordingRow, stop: &Notify) -> anyhow::Result<RecordingOutcome> {
+        let end = NaiveDateTime::parse_from_str(&row.end_time, xmltv::TIME_FORMAT)?;
+        let remaining = (end - Utc::now().naive_utc()).num_seconds();
+        if remaining <= 0 {
+            anyhow::bail!("recording window has already ended");
+        }
+
+        let dir = &self.config.livetv.recordings_dir;
+        tokio::fs::create_dir_all(dir).await?;
+        let output = unique_output_path(dir, row).await;
+        livetv_repo::set_recording_file(&self.db.write, &row.id, &output.to_string_lossy()).await?;
+
+        info!(
+            "Recording '{}' from {} for {}s to {}",
+            row.title,
+            row.channel_name,
+            remaining,
+            output.display()
+        );
+        let args = recorder::build_record_args(&row.stream_url, remaining as u64, &output);
+        recorder::run_recording(&self.config.transcode.ffmpeg_path, &args, stop).await
+    }
+}
+
+/// Build the channel rows to store. Channels without a `tvg-id` are matched
+/// to the guide by display name so they still get programme data, and guide
+/// icons fill in missing logos.
+fn to_new_channels(
+    playlist: &[m3u::M3uChannel],
+    guide: Option<&xmltv::XmltvGuide>,
+) -> Vec<NewChannel> {
+    let mut by_id: HashMap<&str, &xmltv::XmltvChannel> = HashMap::new();
+    let mut by_name: HashMap<String, &xmltv::XmltvChannel> = HashMap::new();
+    for ch in guide.map(|g| g.channels.as_slice()).unwrap_or_default() {
+        by_id.insert(ch.id.as_str(), ch);
+        if let Some(name) = ch.display_name.as_deref() {
+            by_name.insert(name.to_lowercase(), ch);
+        }
+    }
+
+    playlist
+        .iter()
+        .map(|ch| {
+            let guide_channel = match ch.tvg_id.as_deref() {
+                Some(id) => by_id.get(id).copied(),
+                None => by_name.get(&ch.name.to_lowercase()).copied(),
+            };
+            NewChannel {
+                source_key: ch.source_key().to_string(),
+                name: ch.name.clone(),
+                number: ch.number.clone(),
+                logo_url: ch
+                    .logo
+                    .clone()
+                    .or_else(|| guide_channel.and_then(|g| g.icon.clone())),
+                group_title: ch.group.clone(),
+                stream_url: ch.url.clone(),
+                xmltv_id: ch
+                    .tvg_id
+                    .clone()
+                    .or_else(|| guide_channel.map(|g| g.id.clone())),
+            }
+        })
+        .collect()
+}
+
+/// `<dir>/<Title> (<Channel>) <YYYY-MM-DD HH-MM>.ts`, with a numeric suffix if
+/// the file already exists (e.g. a recording resumed after a restart).
+async fn unique_output_path(dir: &std::path::Path, row: &RecordingRow) -> PathBuf {
+    let stamp = row
+        .start_time
+        .get(..16)
+        .unwrap_or(&row.start_time)
+        .replace(':', "-");
+    let stem =
+        recorder::sanitize_file_stem(&format!("{} ({}) {}", row.title, row.channel_name, stamp));
+    let mut path = dir.join(format!("{stem}.ts"));
+    let mut n = 2;
+    while tokio::fs::try_exists(&path).await.unwrap_or(false) {
+        path = dir.join(format!("{stem} - part {n}.ts"));
+        n += 1;
+    }
+    path
+}
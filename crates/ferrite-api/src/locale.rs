@@ -0,0 +1,160 @@
+//! Per-user metadata language.
+//!
+//! Titles are enriched in the primary language of `[metadata].languages` and
+//! the remaining languages are stored as translations. A user picks one with
+//! the `metadata_language` preference; responses overlay whatever translated
+//! fields exist and keep the primary-language value for the rest.
+
+use crate::state::AppState;
+use anyhow::Result;
+use ferrite_core::config::MetadataConfig;
+use ferrite_db::movie_repo::MovieWithMediaRow;
+use ferrite_db::preference_repo;
+use ferrite_db::translation_repo::{self, TranslationRow};
+use ferrite_db::tv_repo::TvShowRow;
+
+/// User preference key holding the requested metadata language tag.
+pub const LANGUAGE_PREFERENCE: &str = "metadata_language";
+
+/// Resolve a requested language against the configured list. Returns `None`
+/// when the request maps to the primary language or to nothing configured,
+/// i.e. when stored metadata should be served as-is.
+pub fn translation_language(config: &MetadataConfig, requested: &str) -> Option<String> {
+    let matched = config.match_language(requested)?;
+    (matched != config.primary_language()).then(|| matched.to_string())
+}
+
+/// The translation language to serve `user_id`, if any.
+pub(crate) async fn user_translation_language(
+    state: &AppState,
+    user_id: Option<&str>,
+) -> Result<Option<String>> {
+    let Some(user_id) = user_id else {
+        return Ok(None);
+    };
+    let requested =
+        preference_repo::get_preference(&state.db.read, user_id, LANGUAGE_PREFERENCE).await?;
+    Ok(requested.and_then(|r| translation_language(&state.config.metadata, &r)))
+}
+
+/// Overlay `language` translations onto movie rows in place.
+pub(crate) async fn localize_movies(
+    state: &AppState,
+    language: &str,
+    rows: &mut [MovieWithMediaRow],
+) -> Result<()> {
+    let ids: Vec<String> = rows.iter().map(|r| r.id.clone()).collect();
+    let translations =
+        translation_repo::get_movie_translations(&state.db.read, language, &ids).await?;
+    for row in rows {
+        if let Some(t) = translations.get(&row.id) {
+            overlay(&mut row.movie_title, &t.title);
+            overlay(&mut row.overview, &t.overview);
+            overlay(&mut row.tagline, &t.tagline);
+            overlay(&mut row.poster_path, &t.poster_path);
+        }
+    }
+    Ok(())
+}
+
+/// Overlay `language` translations onto TV show rows in place.
+pub(crate) async fn localize_shows(
+    state: &AppState,
+    language: &str,
+    rows: &mut [TvShowRow],
+) -> Result<()> {
+    let ids: Vec<String> = rows.iter().map(|r| r.id.clone()).collect();
+    let translations =
+        translation_repo::get_show_translations(&state.db.read, language, &ids).await?;
+    for row in rows {
+        if let Some(t) = translations.get(&row.id) {
+            apply_show_translation(row, t);
+        }
+    }
+    Ok(())
+}
+
+fn apply_show_translation(row: &mut TvShowRow, t: &TranslationRow) {
+    if let Some(title) = &t.title {
+        row.title = title.clone();
+    }
+    overlay(&mut row.overview, &t.overview);
+    overlay(&mut row.poster_path, &t.poster_path);
+}
+
+fn overlay(field: &mut Option<String>, translated: &Option<String>) {
+    if translated.is_some() {
+        field.clone_from(translated);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(languages: &[&str]) -> MetadataConfig {
+        MetadataConfig {
+            languages: languages.iter().map(|l| l.to_string()).collect(),
+            ..MetadataConfig::default()
+        }
+    }
+
+    #[test]
+    fn requests_resolve_to_configured_translations() {
+        let config = config(&["en-US", "es-MX"]);
+        assert_eq!(
+            translation_language(&config, "es-MX").as_deref(),
+            Some("es-MX")
+        );
+        // Same base language falls back to the configured regional variant
+        assert_eq!(
+            translation_language(&config, "es").as_deref(),
+            Some("es-MX")
+        );
+        assert_eq!(
+            translation_language(&config, "ES-es").as_deref(),
+            Some("es-MX")
+        );
+        // Primary language and unconfigured languages serve stored metadata
+        assert_eq!(translation_language(&config, "en-GB"), None);
+        assert_eq!(translation_language(&config, "fr-FR"), None);
+        assert_eq!(translation_language(&config, ""), None);
+    }
+
+    #[test]
+    fn missing_translated_fields_keep_primary_values() {
+        let mut row = TvShowRow {
+            id: "s".into(),
+            library_id: "l".into(),
+            title: "The Office".into(),
+            sort_title: None,
+            year: Some(2005),
+            overview: Some("A mockumentary.".into()),
+            status: None,
+            tmdb_id: Some(2316),
+            tvdb_id: None,
+            poster_path: Some("2316_poster.jpg".into()),
+            backdrop_path: None,
+            genres: None,
+            fetched_at: None,
+            episode_group_id: None,
+            match_failed_at: None,
+            season_count: 9,
+            episode_count: 201,
+        };
+        apply_show_translation(
+            &mut row,
+            &TranslationRow {
+                id: "s".into(),
+                language: "es-MX".into(),
+                title: Some("La oficina".into()),
+                overview: None,
+                tagline: None,
+                poster_path: Some("2316_es-MX_poster.jpg".into()),
+            },
+        );
+        assert_eq!(row.title, "La oficina");
+        assert_eq!(row.overview.as_deref(), Some("A mockumentary."));
+        assert_eq!(row.poster_path.as_deref(), Some("2316_es-MX_poster.jpg"));
+    }
+}
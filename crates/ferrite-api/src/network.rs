@@ -0,0 +1,200 @@
+use axum::http::HeaderMap;
+use ferrite_core::config::NetworkConfig;
+use std::net::{IpAddr, SocketAddr};
+use tracing::warn;
+
+/// An IPv4 or IPv6 subnet in CIDR notation (e.g. `192.168.0.0/16`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Subnet {
+    addr: IpAddr,
+    prefix: u8,
+}
+
+impl Subnet {
+    /// Parse `addr/prefix`. A bare address is treated as a single host.
+    pub fn parse(s: &str) -> Option<Self> {
+        let s = s.trim();
+        let (addr, prefix) = match s.split_once('/') {
+            Some((addr, prefix)) => (addr.parse::<IpAddr>().ok()?, Some(prefix.parse().ok()?)),
+            None => (s.parse::<IpAddr>().ok()?, None),
+        };
+        let max = if addr.is_ipv4() { 32 } else { 128 };
+        let prefix = prefix.unwrap_or(max);
+        (prefix <= max).then_some(Self { addr, prefix })
+    }
+
+    /// Whether `ip` falls inside this subnet. IPv4-mapped IPv6 addresses are
+    /// compared as IPv4.
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        let ip = match ip {
+            IpAddr::V6(v6) => v6.to_ipv4_mapped().map(IpAddr::V4).unwrap_or(ip),
+            v4 => v4,
+        };
+        match (self.addr, ip) {
+            (IpAddr::V4(net), IpAddr::V4(ip)) => prefix_matches(
+                u32::from(net) as u128,
+                u32::from(ip) as u128,
+                self.prefix,
+                32,
+            ),
+            (IpAddr::V6(net), IpAddr::V6(ip)) => {
+                prefix_matches(u128::from(net), u128::from(ip), self.prefix, 128)
+            }
+            _ => false,
+        }
+    }
+}
+
+fn prefix_matches(net: u128, ip: u128, prefix: u8, bits: u32) -> bool {
+    if prefix == 0 {
+        return true;
+    }
+    let shift = bits - prefix as u32;
+    (net >> shift) == (ip >> shift)
+}
+
+/// Parsed view of the `[network]` config used for local/remote decisions.
+#[derive(Debug, Clone)]
+pub struct NetworkPolicy {
+    local_subnets: Vec<Subnet>,
+    trust_forwarded_for: bool,
+}
+
+impl NetworkPolicy {
+    pub fn from_config(config: &NetworkConfig) -> Self {
+        let local_subnets = config
+            .local_subnets
+            .iter()
+            .filter_map(|s| {
+                let subnet = Subnet::parse(s);
+                if subnet.is_none() {
+                    warn!("Ignoring invalid local subnet '{}'", s);
+                }
+                subnet
+            })
+            .collect();
+        Self {
+            local_subnets,
+            trust_forwarded_for: config.trust_forwarded_for,
+        }
+    }
+
+    /// Determine the client IP from the socket address, or from
+    /// `X-Forwarded-For` when configured to trust a reverse proxy.
+    pub fn client_ip(&self, peer: Option<SocketAddr>, headers: &HeaderMap) -> Option<IpAddr> {
+        if self.trust_forwarded_for {
+            let forwarded = headers
+                .get("x-forwarded-for")
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.split(',').next())
+                .and_then(|v| v.trim().parse::<IpAddr>().ok());
+            if forwarded.is_some() {
+                return forwarded;
+            }
+        }
+        peer.map(|addr| addr.ip())
+    }
+
+    /// Whether a client is on the local network. Unknown addresses are treated
+    /// as local so in-process callers (and tests) are never throttled.
+    pub fn is_local(&self, ip: Option<IpAddr>) -> bool {
+        match ip {
+            Some(ip) => self.local_subnets.iter().any(|s| s.contains(ip)),
+            None => true,
+        }
+    }
+}
+
+/// Resolve the remote bitrate cap (kbps). A per-user value overrides the
+/// server-wide default; `0` at either level means unlimited.
+pub fn effective_remote_cap_kbps(global: Option<u32>, per_user: Option<i64>) -> Option<u32> {
+    match per_user {
+        Some(v) if v > 0 => Some(v.min(u32::MAX as i64) as u32),
+        Some(_) => None,
+        None => global.filter(|v| *v > 0),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(subnets: &[&str], trust: bool) -> NetworkPolicy {
+        NetworkPolicy::from_config(&NetworkConfig {
+            local_subnets: subnets.iter().map(|s| s.to_string()).collect(),
+            trust_forwarded_for: trust,
+            ..NetworkConfig::default()
+        })
+    }
+
+    #[test]
+    fn parses_and_matches_ipv4_subnets() {
+        let net = Subnet::parse("192.168.0.0/16").unwrap();
+        assert!(net.contains("192.168.42.7".parse().unwrap()));
+        assert!(!net.contains("192.169.0.1".parse().unwrap()));
+
+        let net = Subnet::parse("172.16.0.0/12").unwrap();
+        assert!(net.contains("172.31.255.255".parse().unwrap()));
+        assert!(!net.contains("172.32.0.1".parse().unwrap()));
+    }
+
+    #[test]
+    fn parses_and_matches_ipv6_subnets() {
+        let net = Subnet::parse("fc00::/7").unwrap();
+        assert!(net.contains("fd12:3456::1".parse().unwrap()));
+        assert!(!net.contains("2001:db8::1".parse().unwrap()));
+    }
+
+    #[test]
+    fn ipv4_mapped_addresses_match_ipv4_subnets() {
+        let net = Subnet::parse("10.0.0.0/8").unwrap();
+        assert!(net.contains("::ffff:10.1.2.3".parse().unwrap()));
+    }
+
+    #[test]
+    fn bare_address_and_invalid_input() {
+        let host = Subnet::parse("203.0.113.5").unwrap();
+        assert!(host.contains("203.0.113.5".parse().unwrap()));
+        assert!(!host.contains("203.0.113.6".parse().unwrap()));
+        assert!(Subnet::parse("10.0.0.0/33").is_none());
+        assert!(Subnet::parse("not-an-ip/8").is_none());
+        assert!(Subnet::parse("0.0.0.0/0")
+            .unwrap()
+            .contains("8.8.8.8".parse().unwrap()));
+    }
+
+    #[test]
+    fn forwarded_for_is_only_used_when_trusted() {
+        let mut headers = HeaderMap::new();
+        headers.insert("x-forwarded-for", "198.51.100.7, 10.0.0.1".parse().unwrap());
+        let peer: SocketAddr = "127.0.0.1:5000".parse().unwrap();
+
+        let untrusted = policy(&["127.0.0.0/8"], false);
+        let ip = untrusted.client_ip(Some(peer), &headers);
+        assert_eq!(ip, Some("127.0.0.1".parse().unwrap()));
+        assert!(untrusted.is_local(ip));
+
+        let trusted = policy(&["127.0.0.0/8"], true);
+        let ip = trusted.client_ip(Some(peer), &headers);
+        assert_eq!(ip, Some("198.51.100.7".parse().unwrap()));
+        assert!(!trusted.is_local(ip));
+    }
+
+    #[test]
+    fn unknown_client_is_local() {
+        assert!(policy(&[], false).is_local(None));
+    }
+
+    #[test]
+    fn per_user_remote_cap_overrides_server_default() {
+        assert_eq!(effective_remote_cap_kbps(None, None), None);
+        assert_eq!(effective_remote_cap_kbps(Some(8000), None), Some(8000));
+        assert_eq!(effective_remote_cap_kbps(None, Some(3000)), Some(3000));
+        assert_eq!(
+            effective_remote_cap_kbps(Some(2000), Some(6000)),
+            Some(6000)
+        );
+        assert_eq!(effective_remote_cap_kbps(Some(2000), Some(0)), None);
+        assert_eq!(effective_remote_cap_kbps(Some(0), None), None);
+    }
+}
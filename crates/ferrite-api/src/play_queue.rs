@@ -0,0 +1,458 @@
+//! Server-side play queue.
+//!
+//! Each user has one queue, stored in `play_queues` / `play_queue_items`, so a
+//! queue started on one client continues on another. [`PlayQueue`] holds the
+//! queue in memory; handlers apply one operation to it through [`update`],
+//! which loads and saves it in a single transaction.
+
+use anyhow::Result;
+use ferrite_db::queue_repo::{self, QueueItemRow, QueueRow, QueueWrite};
+use ferrite_db::Database;
+use sqlx::SqliteConnection;
+
+/// What happens at the end of the queue (or the end of the current item).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RepeatMode {
+    /// Stop after the last item.
+    #[default]
+    Off,
+    /// Wrap around to the first item.
+    All,
+    /// Play the current item again.
+    One,
+}
+
+impl RepeatMode {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "off" => Some(Self::Off),
+            "all" => Some(Self::All),
+            "one" => Some(Self::One),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Off => "off",
+            Self::All => "all",
+            Self::One => "one",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QueueItem {
+    pub media_id: String,
+    /// Position in unshuffled order
+    pub original_position: i64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PlayQueue {
+    pub source_collection_id: Option<String>,
+    /// Items in play order
+    pub items: Vec<QueueItem>,
+    /// Index into `items` of the item now playing
+    pub current: usize,
+    pub shuffle: bool,
+    pub repeat: RepeatMode,
+}
+
+impl PlayQueue {
+    /// Build a queue from media ids in their natural order, starting at `start`.
+    pub fn new(media_ids: Vec<String>, start: usize) -> Self {
+        let items = media_ids
+            .into_iter()
+            .enumerate()
+            .map(|(i, media_id)| QueueItem {
+                media_id,
+                original_position: i as i64,
+            })
+            .collect::<Vec<_>>();
+        let current = start.min(items.len().saturating_sub(1));
+        Self {
+            items,
+            current,
+            ..Self::default()
+        }
+    }
+
+    /// Rebuild a queue from its stored rows. Rows may have gaps in their
+    /// positions (media deleted since the queue was saved); the current item
+    /// becomes the first one at or after the stored position.
+    pub fn from_rows(queue: &QueueRow, rows: Vec<QueueItemRow>) -> Self {
+        let current = rows
+            .iter()
+            .position(|r| r.position >= queue.current_position)
+            .unwrap_or(rows.len())
+            .min(rows.len().saturating_sub(1));
+        Self {
+            source_collection_id: queue.source_collection_id.clone(),
+            items: rows
+                .into_iter()
+                .map(|r| QueueItem {
+                    media_id: r.media_id,
+                    original_position: r.original_position,
+                })
+                .collect(),
+            current,
+            shuffle: queue.shuffle != 0,
+            repeat: RepeatMode::parse(&queue.repeat_mode).unwrap_or_default(),
+        }
+    }
+
+    /// The queue as rows to persist.
+    pub fn to_write(&self) -> QueueWrite<'_> {
+        QueueWrite {
+            source_collection_id: self.source_collection_id.as_deref(),
+            current_position: self.current as i64,
+            shuffle: self.shuffle,
+            repeat_mode: self.repeat.as_str(),
+            items: self
+                .items
+                .iter()
+                .map(|i| (i.original_position, i.media_id.as_str()))
+                .collect(),
+        }
+    }
+
+    pub fn current_media(&self) -> Option<&str> {
+        self.items.get(self.current).map(|i| i.media_id.as_str())
+    }
+
+    /// Index of the item that plays after the current one, honoring repeat.
+    pub fn next_index(&self) -> Option<usize> {
+        if self.items.is_empty() {
+            return None;
+        }
+        match self.repeat {
+            RepeatMode::One => Some(self.current),
+            _ if self.current + 1 < self.items.len() => Some(self.current + 1),
+            RepeatMode::All => Some(0),
+            RepeatMode::Off => None,
+        }
+    }
+
+    /// Index of the item before the current one. Wraps only with repeat all;
+    /// repeat one still steps back so users can leave a looping item.
+    pub fn previous_index(&self) -> Option<usize> {
+        if self.items.is_empty() {
+            return None;
+        }
+        match self.current.checked_sub(1) {
+            Some(i) => Some(i),
+            None if self.repeat == RepeatMode::All => Some(self.items.len() - 1),
+            None => None,
+        }
+    }
+
+    /// Move to the next item. Returns false (and stays put) at the end.
+    pub fn advance(&mut self) -> bool {
+        match self.next_index() {
+            Some(i) => {
+                self.current = i;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Move to the previous item. Returns false (and stays put) at the start.
+    pub fn go_back(&mut self) -> bool {
+        match self.previous_index() {
+            Some(i) => {
+                self.current = i;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Jump to `index`. Returns false if it is out of range.
+    pub fn jump(&mut self, index: usize) -> bool {
+        if index < self.items.len() {
+            self.current = index;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Make `media_id` current when playback of it starts elsewhere (a client
+    /// opened a stream directly). Prefers the first occurrence at or after the
+    /// current item. Returns false if the media is not queued.
+    pub fn focus(&mut self, media_id: &str) -> bool {
+        if self.current_media() == Some(media_id) {
+            return true;
+        }
+        let found = self.items[self.current..]
+            .iter()
+            .position(|i| i.media_id == media_id)
+            .map(|i| i + self.current)
+            .or_else(|| self.items.iter().position(|i| i.media_id == media_id));
+        match found {
+            Some(i) => {
+                self.current = i;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Queue `media_id` right after the current item ("play next").
+    pub fn play_next(&mut self, media_id: String) {
+        let at = if self.items.is_empty() {
+            0
+        } else {
+            self.current + 1
+        };
+        let item = self.new_item(media_id);
+        self.items.insert(at, item);
+        self.renumber_if_unshuffled();
+    }
+
+    /// Queue `media_id` at the end.
+    pub fn append(&mut self, media_id: String) {
+        let item = self.new_item(media_id);
+        self.items.push(item);
+    }
+
+    /// Remove the item at `index`. The current item stays current; removing
+    /// it makes the following item current.
+    pub fn remove(&mut self, index: usize) -> bool {
+        if index >= self.items.len() {
+            return false;
+        }
+        self.items.remove(index);
+        if index < self.current {
+            self.current -= 1;
+        }
+        self.current = self.current.min(self.items.len().saturating_sub(1));
+        self.renumber_if_unshuffled();
+        true
+    }
+
+    /// Turn shuffle on or off. Turning it on keeps the current item playing
+    /// and shuffles everything else after it; turning it off restores the
+    /// original order. `random(n)` must return a value in `0..n`.
+    pub fn set_shuffle(&mut self, on: bool, mut random: impl FnMut(usize) -> usize) {
+        if on == self.shuffle {
+            return;
+        }
+        self.shuffle = on;
+        if self.items.is_empty() {
+            return;
+        }
+        let current = self.items.remove(self.current);
+        if on {
+            // Fisher–Yates
+            for i in (1..self.items.len()).rev() {
+                let j = random(i + 1);
+                self.items.swap(i, j);
+            }
+            self.items.insert(0, current);
+            self.current = 0;
+        } else {
+            self.items.sort_by_key(|i| i.original_position);
+            let at = self
+                .items
+                .partition_point(|i| i.original_position < current.original_position);
+            self.items.insert(at, current);
+            self.current = at;
+            self.renumber_if_unshuffled();
+        }
+    }
+
+    fn new_item(&self, media_id: String) -> QueueItem {
+        let original_position = self
+            .items
+            .iter()
+            .map(|i| i.original_position + 1)
+            .max()
+            .unwrap_or(0);
+        QueueItem {
+            media_id,
+            original_position,
+        }
+    }
+
+    /// Without shuffle, play order is the original order.
+    fn renumber_if_unshuffled(&mut self) {
+        if !self.shuffle {
+            for (i, item) in self.items.iter_mut().enumerate() {
+                item.original_position = i as i64;
+            }
+        }
+    }
+}
+
+/// Load a user's queue, if they have one.
+pub async fn load(db: &Database, user_id: &str) -> Result<Option<PlayQueue>> {
+    let mut conn = db.read.acquire().await?;
+    load_from(&mut conn, user_id).await
+}
+
+async fn load_from(conn: &mut SqliteConnection, user_id: &str) -> Result<Option<PlayQueue>> {
+    let Some(row) = queue_repo::get_queue(conn, user_id).await? else {
+        return Ok(None);
+    };
+    let items = queue_repo::list_queue_items(conn, user_id).await?;
+    Ok(Some(PlayQueue::from_rows(&row, items)))
+}
+
+/// Persist a user's queue, replacing whatever was stored.
+pub async fn save(db: &Database, user_id: &str, queue: &PlayQueue) -> Result<()> {
+    let mut conn = db.write.acquire().await?;
+    queue_repo::save_queue(&mut conn, user_id, &queue.to_write()).await
+}
+
+/// Load a user's queue, apply `op` and save the queue if `op` changed it,
+/// all in one write transaction so concurrent updates (another client, or
+/// the sync when a stream starts) can't overwrite each other. Nothing is
+/// saved when `op` fails.
+pub async fn update<T, E>(
+    db: &Database,
+    user_id: &str,
+    op: impl FnOnce(&mut Option<PlayQueue>) -> Result<T, E>,
+) -> Result<T, E>
+where
+    E: From<anyhow::Error>,
+{
+    let mut tx = db.write.begin().await.map_err(anyhow::Error::from)?;
+    let before = load_from(&mut tx, user_id).await?;
+    let mut queue = before.clone();
+    let out = op(&mut queue)?;
+    if queue != before {
+        if let Some(queue) = &queue {
+            queue_repo::save_queue(&mut tx, user_id, &queue.to_write()).await?;
+        }
+    }
+    tx.commit().await.map_err(anyhow::Error::from)?;
+    Ok(out)
+}
+
+/// Keep the queue in step when a client starts streaming `media_id`
+/// directly: if it is queued, it becomes the current item.
+pub async fn sync_playback(db: &Database, user_id: &str, media_id: &str) -> Result<()> {
+    update(db, user_id, |queue| {
+        if let Some(queue) = queue {
+            queue.focus(media_id);
+        }
+        Ok(())
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn queue(ids: &[&str]) -> PlayQueue {
+        PlayQueue::new(ids.iter().map(|s| s.to_string()).collect(), 0)
+    }
+
+    fn order(q: &PlayQueue) -> Vec<&str> {
+        q.items.iter().map(|i| i.media_id.as_str()).collect()
+    }
+
+    #[test]
+    fn next_and_previous_honor_repeat() {
+        let mut q = queue(&["a", "b", "c"]);
+        assert!(q.advance());
+        assert!(q.advance());
+        assert_eq!(q.current_media(), Some("c"));
+        assert!(!q.advance());
+        assert_eq!(q.current_media(), Some("c"));
+
+        q.repeat = RepeatMode::All;
+        assert!(q.advance());
+        assert_eq!(q.current_media(), Some("a"));
+        assert!(q.go_back());
+        assert_eq!(q.current_media(), Some("c"));
+
+        q.repeat = RepeatMode::One;
+        assert_eq!(q.next_index(), Some(2));
+        assert_eq!(q.previous_index(), Some(1));
+    }
+
+    #[test]
+    fn play_next_inserts_after_current() {
+        let mut q = queue(&["a", "b", "c"]);
+        q.advance();
+        q.play_next("x".into());
+        q.append("z".into());
+        assert_eq!(order(&q), vec!["a", "b", "x", "c", "z"]);
+        assert_eq!(q.current_media(), Some("b"));
+        assert!(q.advance());
+        assert_eq!(q.current_media(), Some("x"));
+    }
+
+    #[test]
+    fn removing_items_keeps_the_current_one() {
+        let mut q = queue(&["a", "b", "c", "d"]);
+        q.jump(2);
+        assert!(q.remove(0));
+        assert_eq!(q.current_media(), Some("c"));
+        assert!(q.remove(1));
+        assert_eq!(q.current_media(), Some("d"));
+        assert!(!q.remove(5));
+    }
+
+    #[test]
+    fn shuffle_keeps_current_first_and_unshuffle_restores_order() {
+        let mut q = queue(&["a", "b", "c", "d", "e"]);
+        q.jump(2);
+        // Deterministic "random": always pick index 0
+        q.set_shuffle(true, |_| 0);
+        assert_eq!(q.current, 0);
+        assert_eq!(q.current_media(), Some("c"));
+        let mut shuffled = order(&q);
+        shuffled.sort();
+        assert_eq!(shuffled, vec!["a", "b", "c", "d", "e"]);
+
+        q.advance();
+        let playing = q.current_media().unwrap().to_string();
+        q.set_shuffle(false, |_| 0);
+        assert_eq!(order(&q), vec!["a", "b", "c", "d", "e"]);
+        assert_eq!(q.current_media(), Some(playing.as_str()));
+    }
+
+    #[test]
+    fn focus_follows_direct_playback() {
+        let mut q = queue(&["a", "b", "a", "c"]);
+        q.jump(1);
+        assert!(q.focus("a"));
+        assert_eq!(q.current, 2);
+        assert!(!q.focus("zzz"));
+        assert_eq!(q.current, 2);
+    }
+
+    #[test]
+    fn stored_rows_with_gaps_resume_at_the_next_item() {
+        let row = QueueRow {
+            user_id: "u".into(),
+            source_collection_id: None,
+            current_position: 1,
+            shuffle: 0,
+            repeat_mode: "all".into(),
+            updated_at: String::new(),
+        };
+        // Item at position 1 was deleted
+        let rows = vec![
+            QueueItemRow {
+                position: 0,
+                original_position: 0,
+                media_id: "a".into(),
+            },
+            QueueItemRow {
+                position: 2,
+                original_position: 2,
+                media_id: "c".into(),
+            },
+        ];
+        let q = PlayQueue::from_rows(&row, rows);
+        assert_eq!(q.current_media(), Some("c"));
+        assert_eq!(q.repeat, RepeatMode::All);
+    }
+}
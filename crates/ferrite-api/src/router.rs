@@ -1,7 +1,7 @@
 use crate::auth;
 use crate::handlers::{
-    collection, image, library, media, progress, stream, subtitle, system, thumbnail, tv, user,
-    webhook,
+    collection, download, image, integrity, library, livetv, media, people, progress, queue,
+    stream, subtitle, system, thumbnail, tv, user, webhook,
 };
 use crate::state::AppState;
 use axum::http::{header, Method, Request};
@@ -40,17 +40,95 @@ pub fn build_router(state: AppState) -> Router {
             post(system::track_playback_metric),
         )
         .route("/api/admin/streams", get(system::list_active_streams))
+        .route("/api/events", get(system::event_stream))
+        .route(
+            "/api/admin/transcodes/{id}",
+            delete(system::terminate_transcode),
+        )
+        .route(
+            "/api/admin/streams/terminations",
+            get(system::list_stream_terminations),
+        )
+        .route(
+            "/api/admin/streams/{session_id}/terminate",
+            post(system::terminate_stream),
+        )
+        .route(
+            "/api/admin/users/{id}/streams/terminate",
+            post(system::terminate_user_streams),
+        )
+        .route(
+            "/api/system/segment-cache",
+            get(system::segment_cache_stats).delete(system::clear_segment_cache),
+        )
         // Libraries
         .route("/api/libraries", get(library::list_libraries))
         .route("/api/libraries", post(library::create_library))
+        .route("/api/libraries/{id}", put(library::update_library))
         .route("/api/libraries/{id}", delete(library::delete_library))
         .route("/api/libraries/{id}/scan", post(library::scan_library))
         .route("/api/libraries/{id}/scan/status", get(library::scan_status))
+        .route(
+            "/api/libraries/{id}/scan/cancel",
+            post(library::cancel_scan),
+        )
+        .route("/api/libraries/{id}/scan/pause", post(library::pause_scan))
+        .route(
+            "/api/libraries/{id}/scan/resume",
+            post(library::resume_scan),
+        )
+        .route(
+            "/api/admin/libraries/{id}/scan/dry-run",
+            post(library::dry_run_scan),
+        )
+        .route("/api/admin/unmatched", get(library::list_unmatched))
+        .route("/api/admin/stats", get(library::library_stats))
+        .route("/api/admin/integrity", get(integrity::integrity_status))
+        .route(
+            "/api/admin/integrity/broken",
+            get(integrity::list_broken_media),
+        )
+        .route("/api/admin/integrity/check", post(integrity::start_check))
         // Media
         .route("/api/media", get(media::list_media))
         .route("/api/media/{id}", get(media::get_media))
         .route("/api/media/{id}/streams", get(media::get_media_streams))
         .route("/api/media/{id}/chapters", get(media::get_media_chapters))
+        .route("/api/media/{id}/versions", get(media::get_media_versions))
+        .route("/api/media/{id}/extras", get(media::get_media_extras))
+        .route(
+            "/api/media/{id}/integrity",
+            get(integrity::get_media_integrity).post(integrity::check_media_integrity),
+        )
+        .route("/api/media/{id}/credits", get(people::media_credits))
+        // Offline downloads
+        .route("/api/media/{id}/download", post(download::create_download))
+        .route("/api/downloads", get(download::list_downloads))
+        .route(
+            "/api/downloads/{id}",
+            get(download::get_download).delete(download::delete_download),
+        )
+        .route("/api/downloads/{id}/file", get(download::serve_download))
+        // Live TV / DVR
+        .route("/api/livetv/channels", get(livetv::list_channels))
+        .route(
+            "/api/livetv/channels/{id}/guide",
+            get(livetv::channel_guide),
+        )
+        .route(
+            "/api/livetv/channels/{id}/hls/master.m3u8",
+            get(livetv::channel_master_playlist),
+        )
+        .route("/api/livetv/guide", get(livetv::guide))
+        .route("/api/livetv/refresh", post(livetv::refresh))
+        .route(
+            "/api/livetv/recordings",
+            get(livetv::list_recordings).post(livetv::create_recording),
+        )
+        .route(
+            "/api/livetv/recordings/{id}",
+            delete(livetv::delete_recording),
+        )
         // Subtitles
         .route("/api/media/{id}/subtitles", get(subtitle::list_subtitles))
         .route("/api/subtitles/{id}/serve", get(subtitle::serve_subtitle))
@@ -58,6 +136,13 @@ pub fn build_router(state: AppState) -> Router {
         .route("/api/shows", get(tv::list_shows))
         .route("/api/shows/{id}", get(tv::get_show))
         .route("/api/shows/{id}/seasons", get(tv::list_seasons))
+        .route(
+            "/api/shows/{id}/episode-groups",
+            get(tv::list_episode_groups),
+        )
+        .route("/api/shows/{id}/episode-group", put(tv::set_episode_group))
+        .route("/api/shows/{id}/credits", get(people::show_credits))
+        .route("/api/people/{id}", get(people::get_person))
         .route("/api/seasons/{id}/episodes", get(tv::list_episodes))
         .route("/api/episodes/{id}/next", get(tv::next_episode))
         // Images
@@ -82,6 +167,24 @@ pub fn build_router(state: AppState) -> Router {
             "/api/collections/{id}/reorder",
             put(collection::reorder_item),
         )
+        .route("/api/collections/{id}/shares", get(collection::list_shares))
+        .route(
+            "/api/collections/{id}/shares/{user_id}",
+            put(collection::share_collection).delete(collection::unshare_collection),
+        )
+        // Play queue
+        .route(
+            "/api/queue",
+            get(queue::get_queue)
+                .put(queue::replace_queue)
+                .delete(queue::clear_queue),
+        )
+        .route("/api/queue/items", post(queue::add_item))
+        .route("/api/queue/items/{index}", delete(queue::remove_item))
+        .route("/api/queue/next", post(queue::next))
+        .route("/api/queue/previous", post(queue::previous))
+        .route("/api/queue/jump", post(queue::jump))
+        .route("/api/queue/mode", put(queue::set_mode))
         // Webhooks
         .route(
             "/api/webhooks",
@@ -153,6 +256,7 @@ pub fn build_router(state: AppState) -> Router {
         .route("/api/users/me/password", put(user::change_password))
         .route("/api/users/{id}", delete(user::delete_user))
         .route("/api/users/{id}/password", put(user::admin_reset_password))
+        .route("/api/users/{id}/limits", put(user::update_stream_limits))
         // Playback Progress
         .route(
             "/api/progress/{media_id}",
@@ -212,6 +316,7 @@ pub fn build_router(state: AppState) -> Router {
                 "x-hls-start-secs".parse().unwrap(),
                 "x-hls-requested-start".parse().unwrap(),
                 "x-hls-video-copied".parse().unwrap(),
+                "x-hls-absolute-pts".parse().unwrap(),
                 "x-hls-session-ids".parse().unwrap(),
                 "Server-Timing".parse().unwrap(),
             ])
@@ -242,6 +347,7 @@ pub fn build_router(state: AppState) -> Router {
                 "x-hls-start-secs".parse().unwrap(),
                 "x-hls-requested-start".parse().unwrap(),
                 "x-hls-video-copied".parse().unwrap(),
+                "x-hls-absolute-pts".parse().unwrap(),
                 "x-hls-session-ids".parse().unwrap(),
                 "Server-Timing".parse().unwrap(),
             ])
@@ -0,0 +1,781 @@
+//! Transcode admission control.
+//!
+//! Capacity is `max_concurrent_transcodes` counted in video encodes: an ABR
+//! ladder with three re-encoded variants uses three, while remuxes, audio-only
+//! transcodes and video-copy sessions use none. When the server is saturated
+//! a request may take a cheaper strategy, trim another viewer's ladder down to
+//! its lower variants, or wait in a priority queue until the configured
+//! timeout.
+
+use crate::error::ApiError;
+use ferrite_stream::hls::HlsSessionManager;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::Notify;
+use tokio_util::sync::CancellationToken;
+use tracing::info;
+use uuid::Uuid;
+
+/// How often queued requests recheck capacity even without a wakeup, so HLS
+/// sessions that end through idle cleanup free their slots.
+const RECHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Priority {
+    Normal,
+    High,
+}
+
+/// Who is asking for a transcode and how many encodes it wants.
+#[derive(Debug, Clone)]
+pub struct Admission {
+    /// HLS owner key, or a unique key for a progressive stream.
+    pub key: String,
+    pub user_id: Option<String>,
+    pub username: Option<String>,
+    pub media_id: String,
+    pub operation: &'static str,
+    pub priority: Priority,
+    /// Encodes the request would like (ladder size).
+    pub wanted: usize,
+    /// Encodes the request can't do without; 0 admits immediately.
+    pub min: usize,
+    /// Whether a zero-encode alternative exists for this request.
+    pub cheap_fallback: bool,
+}
+
+/// How an admission was granted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Grant {
+    /// Run with this many video encodes.
+    Encodes(usize),
+    /// Capacity is exhausted; use the zero-encode alternative.
+    Cheap,
+}
+
+struct Slot {
+    id: String,
+    user_id: Option<String>,
+    username: Option<String>,
+    media_id: String,
+    operation: &'static str,
+    priority: Priority,
+    cost: usize,
+    hls: bool,
+    /// HLS slots are pending until their sessions exist; reconcile leaves
+    /// them alone so a slow session start isn't mistaken for a stopped one.
+    pending: bool,
+    started: Instant,
+    cancel: CancellationToken,
+}
+
+struct Waiter {
+    ticket: u64,
+    username: Option<String>,
+    media_id: String,
+    operation: &'static str,
+    priority: Priority,
+    min: usize,
+    since: Instant,
+}
+
+#[derive(Default)]
+struct Inner {
+    slots: HashMap<String, Slot>,
+    waiting: Vec<Waiter>,
+    next_ticket: u64,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Decision {
+    Grant(Grant),
+    /// Trim the HLS owner `victim` to `keep` encodes, then decide again.
+    Degrade {
+        victim: String,
+        keep: usize,
+    },
+    Wait,
+}
+
+impl Inner {
+    /// Encodes in use by everyone but `key` (a viewer replacing its own
+    /// sessions doesn't compete with itself).
+    fn used_excluding(&self, key: &str) -> usize {
+        self.slots
+            .iter()
+            .filter(|(k, _)| k.as_str() != key)
+            .map(|(_, s)| s.cost)
+            .sum()
+    }
+
+    /// Whether `ticket` is first among waiters, by priority then arrival.
+    fn is_next(&self, ticket: u64) -> bool {
+        self.waiting
+            .iter()
+            .min_by_key(|w| (std::cmp::Reverse(w.priority), w.ticket))
+            .is_none_or(|w| w.ticket == ticket)
+    }
+
+    fn decide(&self, capacity: usize, req: &Admission, ticket: u64) -> Decision {
+        if req.min == 0 {
+            return Decision::Grant(Grant::Encodes(0));
+        }
+        let free = capacity.saturating_sub(self.used_excluding(&req.key));
+        if free >= req.min {
+            // Capacity exists but an earlier waiter has first claim on it;
+            // settling for less would only be a way to jump the queue.
+            if self.is_next(ticket) {
+                return Decision::Grant(Grant::Encodes(req.wanted.max(req.min).min(free)));
+            }
+            return Decision::Wait;
+        }
+        if req.cheap_fallback {
+            return Decision::Grant(Grant::Cheap);
+        }
+        // Rather than turn a viewer away, shrink the biggest ladder of an
+        // equal-or-lower priority viewer. Every ladder keeps one variant, and
+        // only running ladders qualify: a pending one has no sessions to trim.
+        let short = req.min.saturating_sub(free);
+        let victim = self
+            .slots
+            .iter()
+            .filter(|(k, s)| {
+                k.as_str() != req.key
+                    && s.hls
+                    && !s.pending
+                    && s.cost > 1
+                    && s.priority <= req.priority
+            })
+            .max_by_key(|(_, s)| s.cost);
+        if let Some((key, slot)) = victim {
+            return Decision::Degrade {
+                victim: key.clone(),
+                keep: slot.cost.saturating_sub(short).max(1),
+            };
+        }
+        Decision::Wait
+    }
+}
+
+/// Capacity owned by an admitted request. Dropping it frees the capacity
+/// unless [`TranscodePermit::retain`] handed it to the HLS sessions.
+pub struct TranscodePermit {
+    scheduler: Arc<TranscodeScheduler>,
+    key: String,
+    id: String,
+    grant: Grant,
+    cancel: CancellationToken,
+    retained: bool,
+}
+
+impl TranscodePermit {
+    pub fn grant(&self) -> Grant {
+        self.grant
+    }
+
+    /// Encodes granted; 0 for a cheap grant.
+    pub fn encodes(&self) -> usize {
+        match self.grant {
+            Grant::Encodes(n) => n,
+            Grant::Cheap => 0,
+        }
+    }
+
+    /// Cancelled when an admin terminates the transcode.
+    pub fn cancellation(&self) -> CancellationToken {
+        self.cancel.clone()
+    }
+
+    /// Keep the slot after the permit is dropped. HLS sessions outlive the
+    /// request that created them; the slot is freed by
+    /// [`TranscodeScheduler::release_owner`] or once the owner's sessions are gone.
+    pub fn retain(mut self) {
+        self.retained = true;
+        let mut inner = self.scheduler.inner.lock().unwrap();
+        if let Some(slot) = inner.slots.get_mut(&self.key) {
+            if slot.id == self.id {
+                slot.pending = false;
+            }
+        }
+    }
+}
+
+impl Drop for TranscodePermit {
+    fn drop(&mut self) {
+        if !self.retained {
+            self.scheduler.remove_slot(&self.key, &self.id);
+        }
+    }
+}
+
+/// A running transcode, as listed by the admin streams API.
+#[derive(Debug, Serialize)]
+pub struct SlotInfo {
+    pub id: String,
+    pub user_id: Option<String>,
+    pub username: Option<String>,
+    pub media_id: String,
+    pub operation: &'static str,
+    pub priority: Priority,
+    pub encodes: usize,
+    pub hls: bool,
+    pub age_secs: u64,
+}
+
+/// A transcode an administrator stopped.
+#[derive(Debug)]
+pub struct StoppedTranscode {
+    /// HLS owner key or progressive stream key.
+    pub key: String,
+    pub hls: bool,
+    pub user_id: Option<String>,
+    pub username: Option<String>,
+    pub media_id: String,
+}
+
+/// A request waiting for capacity.
+#[derive(Debug, Serialize)]
+pub struct QueuedInfo {
+    pub username: Option<String>,
+    pub media_id: String,
+    pub operation: &'static str,
+    pub priority: Priority,
+    pub encodes: usize,
+    pub waiting_secs: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SchedulerSnapshot {
+    pub capacity: usize,
+    pub used: usize,
+    pub slots: Vec<SlotInfo>,
+    pub queue: Vec<QueuedInfo>,
+}
+
+pub struct TranscodeScheduler {
+    capacity: usize,
+    queue_timeout: Duration,
+    inner: Mutex<Inner>,
+    notify: Notify,
+}
+
+/// Removes a waiter when its request finishes or is dropped mid-wait.
+struct WaitGuard<'a> {
+    scheduler: &'a TranscodeScheduler,
+    ticket: u64,
+}
+
+impl Drop for WaitGuard<'_> {
+    fn drop(&mut self) {
+        let mut inner = self.scheduler.inner.lock().unwrap();
+        inner.waiting.retain(|w| w.ticket != self.ticket);
+        drop(inner);
+        self.scheduler.notify.notify_waiters();
+    }
+}
+
+impl TranscodeScheduler {
+    pub fn new(capacity: usize, queue_timeout: Duration) -> Self {
+        Self {
+            capacity,
+            queue_timeout,
+            inner: Mutex::new(Inner::default()),
+            notify: Notify::new(),
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Admit a request, waiting up to the queue timeout for capacity.
+    /// `hls` is `Some` for HLS requests, whose slot is keyed by owner key.
+    pub async fn admit(
+        self: &Arc<Self>,
+        req: Admission,
+        hls: Option<&HlsSessionManager>,
+    ) -> Result<TranscodePermit, ApiError> {
+        let ticket = self.enqueue(&req);
+        let _guard = WaitGuard {
+            scheduler: self,
+            ticket,
+        };
+        let deadline = tokio::time::Instant::now() + self.queue_timeout;
+        loop {
+            if let Some(hls) = hls {
+                self.reconcile(hls).await;
+            }
+            // Register for wakeups before deciding so a release between the
+            // decision and the wait isn't missed.
+            let notified = self.notify.notified();
+            match self.try_grant(&req, ticket, hls.is_some()) {
+                Ok(permit) => return Ok(permit),
+                Err(Some((victim, keep))) => {
+                    // Retry straight away only if the trim freed something;
+                    // the reconcile at the top of the loop then recounts the
+                    // victim from its remaining sessions. Otherwise wait as
+                    // usual rather than spin on the same victim.
+                    if let Some(hls) = hls {
+                        info!(
+                            "Transcode capacity full: trimming {} to {} variant(s) for {}",
+                            victim, keep, req.media_id
+                        );
+                        if hls.trim_owner_variants(&victim, keep).await > 0 {
+                            continue;
+                        }
+                    }
+                }
+                Err(None) => {}
+            }
+            if tokio::time::timeout_at(deadline, async {
+                let _ = tokio::time::timeout(RECHECK_INTERVAL, notified).await;
+            })
+            .await
+            .is_err()
+            {
+                return Err(ApiError::service_unavailable(
+                    "Transcode queue timeout, try again later",
+                ));
+            }
+        }
+    }
+
+    /// Admit a request only if capacity is free right now, without queueing
+    /// or degrading anyone. Used to grow an existing ladder.
+    pub async fn try_admit(
+        self: &Arc<Self>,
+        req: Admission,
+        hls: &HlsSessionManager,
+    ) -> Option<TranscodePermit> {
+        self.reconcile(hls).await;
+        let mut inner = self.inner.lock().unwrap();
+        if !inner.waiting.is_empty() {
+            return None;
+        }
+        let free = self.capacity.saturating_sub(inner.used_excluding(&req.key));
+        if free < req.min {
+            return None;
+        }
+        let grant = Grant::Encodes(req.wanted.max(req.min).min(free));
+        Some(self.insert_slot(&mut inner, &req, grant, true))
+    }
+
+    fn enqueue(&self, req: &Admission) -> u64 {
+        let mut inner = self.inner.lock().unwrap();
+        let ticket = inner.next_ticket;
+        inner.next_ticket += 1;
+        inner.waiting.push(Waiter {
+            ticket,
+            username: req.username.clone(),
+            media_id: req.media_id.clone(),
+            operation: req.operation,
+            priority: req.priority,
+            min: req.min,
+            since: Instant::now(),
+        });
+        ticket
+    }
+
+    /// One admission decision. `Err(Some(..))` asks the caller to degrade an
+    /// HLS owner; `Err(None)` means wait.
+    fn try_grant(
+        self: &Arc<Self>,
+        req: &Admission,
+        ticket: u64,
+        hls: bool,
+    ) -> Result<TranscodePermit, Option<(String, usize)>> {
+        let mut inner = self.inner.lock().unwrap();
+        match inner.decide(self.capacity, req, ticket) {
+            Decision::Grant(grant) => Ok(self.insert_slot(&mut inner, req, grant, hls)),
+            // Only HLS admissions hold the session manager needed to trim;
+            // a progressive request waits instead of counting a trim that
+            // never happens.
+            Decision::Degrade { .. } if !hls => Err(None),
+            Decision::Degrade { victim, keep } => {
+                // Count the trim now so a concurrent request doesn't pick
+                // the same victim; reconcile corrects it from the sessions.
+                if let Some(slot) = inner.slots.get_mut(&victim) {
+                    slot.cost = keep;
+                }
+                Err(Some((victim, keep)))
+            }
+            Decision::Wait => Err(None),
+        }
+    }
+
+    fn insert_slot(
+        self: &Arc<Self>,
+        inner: &mut Inner,
+        req: &Admission,
+        grant: Grant,
+        hls: bool,
+    ) -> TranscodePermit {
+        let id = Uuid::new_v4().to_string();
+        let cancel = CancellationToken::new();
+        let cost = match grant {
+            Grant::Encodes(n) => n,
+            Grant::Cheap => 0,
+        };
+        inner.slots.insert(
+            req.key.clone(),
+            Slot {
+                id: id.clone(),
+                user_id: req.user_id.clone(),
+                username: req.username.clone(),
+                media_id: req.media_id.clone(),
+                operation: req.operation,
+                priority: req.priority,
+                cost,
+                hls,
+                pending: hls,
+                started: Instant::now(),
+                cancel: cancel.clone(),
+            },
+        );
+        TranscodePermit {
+            scheduler: Arc::clone(self),
+            key: req.key.clone(),
+            id,
+            grant,
+            cancel,
+            retained: false,
+        }
+    }
+
+    fn remove_slot(&self, key: &str, id: &str) {
+        let mut inner = self.inner.lock().unwrap();
+        // A newer admission for the same owner may have replaced the slot
+        if inner.slots.get(key).is_some_and(|s| s.id == id) {
+            inner.slots.remove(key);
+        }
+        drop(inner);
+        self.notify.notify_waiters();
+    }
+
+    /// Free an HLS owner's slot after its sessions were stopped.
+    pub fn release_owner(&self, owner_key: &str) {
+        let removed = self.inner.lock().unwrap().slots.remove(owner_key);
+        if removed.is_some() {
+            self.notify.notify_waiters();
+        }
+    }
+
+    /// Bring HLS slots in line with the sessions that actually exist: drop
+    /// owners whose sessions are gone and recount encodes for the rest
+    /// (finished or trimmed variants stop counting).
+    async fn reconcile(&self, hls: &HlsSessionManager) {
+        let owners: Vec<(String, String)> = {
+            let inner = self.inner.lock().unwrap();
+            inner
+                .slots
+                .iter()
+                .filter(|(_, s)| s.hls && !s.pending)
+                .map(|(k, s)| (k.clone(), s.id.clone()))
+                .collect()
+        };
+        if owners.is_empty() {
+            return;
+        }
+        let mut loads = Vec::with_capacity(owners.len());
+        for (key, id) in owners {
+            let load = hls.owner_encode_load(&key).await;
+            loads.push((key, id, load));
+        }
+        let mut changed = false;
+        let mut inner = self.inner.lock().unwrap();
+        for (key, id, load) in loads {
+            let Some(slot) = inner.slots.get_mut(&key) else {
+                continue;
+            };
+            if slot.id != id {
+                continue;
+            }
+            match load {
+                Some(load) => {
+                    changed |= slot.cost != load;
+                    slot.cost = load;
+                }
+                None => {
+                    inner.slots.remove(&key);
+                    changed = true;
+                }
+            }
+        }
+        drop(inner);
+        if changed {
+            self.notify.notify_waiters();
+        }
+    }
+
+    /// Running transcodes and the queue, for the admin streams API.
+    pub async fn snapshot(&self, hls: &HlsSessionManager) -> SchedulerSnapshot {
+        self.reconcile(hls).await;
+        let inner = self.inner.lock().unwrap();
+        let mut slots: Vec<SlotInfo> = inner
+            .slots
+            .values()
+            .map(|s| SlotInfo {
+                id: s.id.clone(),
+                user_id: s.user_id.clone(),
+                username: s.username.clone(),
+                media_id: s.media_id.clone(),
+                operation: s.operation,
+                priority: s.priority,
+                encodes: s.cost,
+                hls: s.hls,
+                age_secs: s.started.elapsed().as_secs(),
+            })
+            .collect();
+        slots.sort_by_key(|s| std::cmp::Reverse(s.age_secs));
+        let mut waiting: Vec<&Waiter> = inner.waiting.iter().collect();
+        waiting.sort_by_key(|w| (std::cmp::Reverse(w.priority), w.ticket));
+        let queue = waiting
+            .into_iter()
+            .map(|w| QueuedInfo {
+                username: w.username.clone(),
+                media_id: w.media_id.clone(),
+                operation: w.operation,
+                priority: w.priority,
+                encodes: w.min,
+                waiting_secs: w.since.elapsed().as_secs(),
+            })
+            .collect();
+        SchedulerSnapshot {
+            capacity: self.capacity,
+            used: inner.slots.values().map(|s| s.cost).sum(),
+            slots,
+            queue,
+        }
+    }
+
+    /// Stop a running transcode by slot id and free its capacity. Progressive
+    /// streams end at their next chunk; stopping an HLS owner's sessions is
+    /// left to the caller. Returns `None` if no slot has that id.
+    pub fn terminate(&self, id: &str) -> Option<StoppedTranscode> {
+        let mut inner = self.inner.lock().unwrap();
+        let key = inner
+            .slots
+            .iter()
+            .find(|(_, s)| s.id == id)
+            .map(|(k, _)| k.clone())?;
+        let slot = inner.slots.remove(&key)?;
+        drop(inner);
+        self.notify.notify_waiters();
+        Some(Self::stop(key, slot))
+    }
+
+    /// Stop a user's progressive transcodes. Their HLS playbacks are stopped
+    /// through the session manager instead.
+    pub fn terminate_user_progressive(&self, user_id: &str) -> Vec<StoppedTranscode> {
+        let mut inner = self.inner.lock().unwrap();
+        let keys: Vec<String> = inner
+            .slots
+            .iter()
+            .filter(|(_, s)| !s.hls && s.user_id.as_deref() == Some(user_id))
+            .map(|(k, _)| k.clone())
+            .collect();
+        let stopped: Vec<StoppedTranscode> = keys
+            .into_iter()
+            .filter_map(|key| {
+                let slot = inner.slots.remove(&key)?;
+                Some(Self::stop(key, slot))
+            })
+            .collect();
+        drop(inner);
+        if !stopped.is_empty() {
+            self.notify.notify_waiters();
+        }
+        stopped
+    }
+
+    fn stop(key: String, slot: Slot) -> StoppedTranscode {
+        info!(
+            "Terminating transcode {} ({} of {})",
+            slot.id, slot.operation, slot.media_id
+        );
+        slot.cancel.cancel();
+        StoppedTranscode {
+            key,
+            hls: slot.hls,
+            user_id: slot.user_id,
+            username: slot.username,
+            media_id: slot.media_id,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn req(key: &str, priority: Priority, wanted: usize, min: usize) -> Admission {
+        Admission {
+            key: key.to_string(),
+            user_id: None,
+            username: None,
+            media_id: "m".to_string(),
+            operation: "test",
+            priority,
+            wanted,
+            min,
+            cheap_fallback: false,
+        }
+    }
+
+    fn scheduler(capacity: usize) -> Arc<TranscodeScheduler> {
+        Arc::new(TranscodeScheduler::new(capacity, Duration::from_millis(50)))
+    }
+
+    fn hls_slot(sched: &Arc<TranscodeScheduler>, key: &str, cost: usize, priority: Priority) {
+        let mut inner = sched.inner.lock().unwrap();
+        let permit = sched.insert_slot(
+            &mut inner,
+            &req(key, priority, cost, cost),
+            Grant::Encodes(cost),
+            true,
+        );
+        drop(inner);
+        permit.retain();
+    }
+
+    #[test]
+    fn grants_up_to_free_capacity() {
+        let sched = scheduler(4);
+        hls_slot(&sched, "a", 3, Priority::Normal);
+        let inner = sched.inner.lock().unwrap();
+        assert_eq!(
+            inner.decide(4, &req("b", Priority::Normal, 3, 1), 0),
+            Decision::Grant(Grant::Encodes(1))
+        );
+        // A viewer replacing its own sessions reuses its capacity
+        assert_eq!(
+            inner.decide(4, &req("a", Priority::Normal, 4, 1), 0),
+            Decision::Grant(Grant::Encodes(4))
+        );
+        // Remuxes never wait
+        assert_eq!(
+            inner.decide(0, &req("c", Priority::Normal, 0, 0), 0),
+            Decision::Grant(Grant::Encodes(0))
+        );
+    }
+
+    #[test]
+    fn saturation_prefers_cheap_then_degrades_then_waits() {
+        let sched = scheduler(3);
+        hls_slot(&sched, "a", 3, Priority::High);
+        let inner = sched.inner.lock().unwrap();
+
+        let mut cheap = req("b", Priority::Normal, 1, 1);
+        cheap.cheap_fallback = true;
+        assert_eq!(inner.decide(3, &cheap, 0), Decision::Grant(Grant::Cheap));
+
+        // A normal viewer can't shrink an admin's ladder
+        assert_eq!(
+            inner.decide(3, &req("b", Priority::Normal, 1, 1), 0),
+            Decision::Wait
+        );
+        assert_eq!(
+            inner.decide(3, &req("b", Priority::High, 1, 1), 0),
+            Decision::Degrade {
+                victim: "a".to_string(),
+                keep: 2
+            }
+        );
+    }
+
+    #[test]
+    fn pending_ladders_are_not_degraded() {
+        let sched = scheduler(3);
+        let mut inner = sched.inner.lock().unwrap();
+        // Admitted but its sessions haven't started yet
+        let pending = sched.insert_slot(
+            &mut inner,
+            &req("a", Priority::Normal, 3, 3),
+            Grant::Encodes(3),
+            true,
+        );
+        assert_eq!(
+            inner.decide(3, &req("b", Priority::High, 1, 1), 0),
+            Decision::Wait
+        );
+        drop(inner);
+        drop(pending);
+    }
+
+    #[test]
+    fn later_waiters_do_not_take_free_capacity() {
+        let sched = scheduler(3);
+        hls_slot(&sched, "a", 2, Priority::Normal);
+        sched.enqueue(&req("b", Priority::Normal, 1, 1));
+        let mut second = req("c", Priority::Normal, 1, 1);
+        second.cheap_fallback = true;
+        let ticket = sched.enqueue(&second);
+        let inner = sched.inner.lock().unwrap();
+        // One encode is free, but it belongs to "b" until it has been admitted
+        assert_eq!(inner.decide(3, &second, ticket), Decision::Wait);
+    }
+
+    #[test]
+    fn queue_orders_by_priority_then_arrival() {
+        let sched = scheduler(1);
+        let first = sched.enqueue(&req("a", Priority::Normal, 1, 1));
+        let admin = sched.enqueue(&req("b", Priority::High, 1, 1));
+        let inner = sched.inner.lock().unwrap();
+        assert!(inner.is_next(admin));
+        assert!(!inner.is_next(first));
+        assert_eq!(
+            inner.decide(1, &req("a", Priority::Normal, 1, 1), first),
+            Decision::Wait
+        );
+    }
+
+    #[tokio::test]
+    async fn permits_release_on_drop_and_waiters_time_out() {
+        let sched = scheduler(1);
+        let hls = HlsSessionManager::new(
+            std::env::temp_dir().join(format!("ferrite-sched-{}", Uuid::new_v4())),
+            "ffmpeg".to_string(),
+            6,
+            30,
+            1800,
+            30,
+            ferrite_transcode::hwaccel::EncoderProfile::software(),
+        );
+
+        let permit = sched
+            .admit(req("stream:1", Priority::Normal, 1, 1), None)
+            .await
+            .unwrap();
+        assert_eq!(permit.encodes(), 1);
+
+        let err = sched
+            .admit(req("stream:2", Priority::Normal, 1, 1), None)
+            .await
+            .err()
+            .expect("saturated scheduler should time out");
+        assert_eq!(
+            axum::response::IntoResponse::into_response(err).status(),
+            axum::http::StatusCode::SERVICE_UNAVAILABLE
+        );
+        assert!(sched.inner.lock().unwrap().waiting.is_empty());
+
+        let snapshot = sched.snapshot(&hls).await;
+        assert_eq!(snapshot.used, 1);
+        let id = snapshot.slots[0].id.clone();
+        let cancel = permit.cancellation();
+        let stopped = sched.terminate(&id).expect("slot should exist");
+        assert!(!stopped.hls);
+        assert!(sched.terminate(&id).is_none());
+        assert!(cancel.is_cancelled());
+        drop(permit);
+
+        let permit = sched
+            .admit(req("stream:3", Priority::Normal, 1, 1), None)
+            .await
+            .unwrap();
+        assert_eq!(permit.encodes(), 1);
+    }
+}
@@ -1,4 +1,10 @@
+use crate::downloads::DownloadManager;
+use crate::events::EventHub;
+use crate::integrity::IntegrityChecker;
+use crate::livetv::LiveTvManager;
 use crate::metrics::PlaybackMetrics;
+use crate::network::NetworkPolicy;
+use crate::scheduler::TranscodeScheduler;
 use crate::webhooks::WebhookDispatcher;
 use ferrite_core::config::AppConfig;
 use ferrite_db::Database;
@@ -13,7 +19,7 @@ use std::num::NonZeroU32;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::time::Instant;
-use tokio::sync::{Mutex, Semaphore};
+use tokio::sync::Mutex;
 
 /// Global (not per-IP) login rate limiter type.
 pub type LoginRateLimiter = RateLimiter<NotKeyed, InMemoryState, DefaultClock>;
@@ -24,8 +30,9 @@ pub struct AppState {
     pub db: Database,
     pub config: Arc<AppConfig>,
     pub hls_sessions: Arc<HlsSessionManager>,
-    /// Limits the number of concurrent FFmpeg transcode processes.
-    pub transcode_semaphore: Arc<Semaphore>,
+    /// Admission control for transcodes: capacity, priority queue and the
+    /// list of running transcodes shown to admins.
+    pub transcodes: Arc<TranscodeScheduler>,
     /// Rate limiter for login attempts (brute-force protection).
     /// Allows a burst of 5 attempts, replenishing 1 per second.
     pub login_limiter: Arc<LoginRateLimiter>,
@@ -44,6 +51,16 @@ pub struct AppState {
     pub update_state: Arc<UpdateState>,
     /// In-memory cache of valid user IDs for zero-I/O authentication.
     pub user_cache: Arc<dashmap::DashSet<String>>,
+    /// Offline download queue and managed MP4 cache.
+    pub downloads: Arc<DownloadManager>,
+    /// Parsed local subnets used to tell LAN clients from remote ones.
+    pub network: Arc<NetworkPolicy>,
+    /// Live TV channel/guide import and DVR scheduler.
+    pub livetv: Arc<LiveTvManager>,
+    /// Live events pushed to admin clients on `/api/events`.
+    pub events: Arc<EventHub>,
+    /// On-demand and scheduled media file integrity checks.
+    pub integrity: Arc<IntegrityChecker>,
 }
 
 /// Cached result of a GitHub release version check.
@@ -1,4 +1,5 @@
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::path::PathBuf;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -17,6 +18,18 @@ pub struct AppConfig {
     /// Self-update config. If absent from ferrite.toml, defaults are used.
     #[serde(default)]
     pub update: UpdateConfig,
+    /// Offline download (sync transcode) config.
+    #[serde(default)]
+    pub downloads: DownloadConfig,
+    /// Local network detection and remote bandwidth limits.
+    #[serde(default)]
+    pub network: NetworkConfig,
+    /// Live TV tuner (M3U/XMLTV) and DVR config.
+    #[serde(default)]
+    pub livetv: LiveTvConfig,
+    /// Scheduled media file integrity checks.
+    #[serde(default)]
+    pub integrity: IntegrityConfig,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -108,6 +121,224 @@ pub struct TranscodeConfig {
     /// Hardware acceleration preference: "nvenc", "qsv", "vaapi", "software", or null for auto-detect.
     #[serde(default)]
     pub hw_accel: Option<String>,
+    /// Server-wide ABR ladder and encoder quality (`[transcode.encoding]`).
+    #[serde(default)]
+    pub encoding: EncodingConfig,
+    /// Per-library overrides of `encoding`, keyed by library name or ID
+    /// (`[transcode.library_encoding."Anime"]`). Unset fields inherit the
+    /// server-wide value.
+    #[serde(default)]
+    pub library_encoding: HashMap<String, EncodingOverride>,
+    /// Keep re-encoded HLS segments under `cache_dir/hls-cache` so later
+    /// sessions for the same media, variant and audio track reuse them and
+    /// only transcode what's missing.
+    #[serde(default)]
+    pub segment_cache_enabled: bool,
+    /// Disk quota for the segment cache in GiB. Least recently used titles are
+    /// evicted first.
+    #[serde(default = "default_segment_cache_max_gb")]
+    pub segment_cache_max_gb: f64,
+}
+
+impl TranscodeConfig {
+    /// Effective encoding settings for a library: its override (matched by ID
+    /// first, then name) layered over the server-wide settings.
+    pub fn encoding_for(&self, library_id: &str, library_name: &str) -> EncodingConfig {
+        match self
+            .library_encoding
+            .get(library_id)
+            .or_else(|| self.library_encoding.get(library_name))
+        {
+            Some(o) => o.apply(&self.encoding),
+            None => self.encoding.clone(),
+        }
+    }
+
+    /// Check the server-wide encoding settings and every library override.
+    pub fn validate_encoding(&self) -> Result<(), String> {
+        self.encoding
+            .validate()
+            .map_err(|e| format!("[transcode.encoding]: {e}"))?;
+        for (key, o) in &self.library_encoding {
+            o.apply(&self.encoding)
+                .validate()
+                .map_err(|e| format!("[transcode.library_encoding.\"{key}\"]: {e}"))?;
+        }
+        Ok(())
+    }
+}
+
+/// x264 presets, fastest first. QSV accepts the same names.
+pub const ENCODER_PRESETS: &[&str] = &[
+    "ultrafast",
+    "superfast",
+    "veryfast",
+    "faster",
+    "fast",
+    "medium",
+    "slow",
+    "slower",
+    "veryslow",
+];
+
+/// ABR ladder and encoder quality used when transcoding.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EncodingConfig {
+    /// Encoder speed/quality preset (see [`ENCODER_PRESETS`]). Applied to
+    /// libx264 and QSV; hardware encoders with their own preset scale keep it.
+    #[serde(default = "default_encoder_preset")]
+    pub preset: String,
+    /// Constant quality factor, 0–51 (lower is better). Maps to `-crf`
+    /// (libx264), `-cq` (NVENC), `-global_quality` (QSV) or `-qp` (VAAPI).
+    #[serde(default = "default_encoder_crf")]
+    pub crf: u8,
+    /// Never transcode above this height; taller sources are scaled down.
+    #[serde(default)]
+    pub max_height: Option<u32>,
+    /// AAC bitrate when audio is re-encoded outside the ladder (kbps).
+    #[serde(default = "default_audio_bitrate_kbps")]
+    pub audio_bitrate_kbps: u32,
+    /// Quality rungs offered for adaptive streaming, highest first.
+    #[serde(default = "default_ladder")]
+    pub ladder: Vec<LadderRung>,
+}
+
+/// One rung of the ABR ladder.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LadderRung {
+    /// Label shown to clients (e.g. "1080p")
+    pub label: String,
+    pub width: u32,
+    pub height: u32,
+    pub video_bitrate_kbps: u32,
+    pub audio_bitrate_kbps: u32,
+    /// HLS `BANDWIDTH` in bits/sec. Defaults to the combined bitrate plus
+    /// 6% container overhead.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bandwidth_bps: Option<u64>,
+}
+
+impl LadderRung {
+    pub fn bandwidth_bps(&self) -> u64 {
+        self.bandwidth_bps.unwrap_or_else(|| {
+            (self.video_bitrate_kbps as u64 + self.audio_bitrate_kbps as u64) * 1060
+        })
+    }
+}
+
+/// Per-library encoding override. Every field is optional.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct EncodingOverride {
+    pub preset: Option<String>,
+    pub crf: Option<u8>,
+    pub max_height: Option<u32>,
+    pub audio_bitrate_kbps: Option<u32>,
+    pub ladder: Option<Vec<LadderRung>>,
+}
+
+impl EncodingOverride {
+    /// Layer this override over `base`.
+    pub fn apply(&self, base: &EncodingConfig) -> EncodingConfig {
+        EncodingConfig {
+            preset: self.preset.clone().unwrap_or_else(|| base.preset.clone()),
+            crf: self.crf.unwrap_or(base.crf),
+            max_height: self.max_height.or(base.max_height),
+            audio_bitrate_kbps: self.audio_bitrate_kbps.unwrap_or(base.audio_bitrate_kbps),
+            ladder: self.ladder.clone().unwrap_or_else(|| base.ladder.clone()),
+        }
+    }
+}
+
+impl EncodingConfig {
+    /// Reject settings FFmpeg would refuse or that would produce an empty ladder.
+    pub fn validate(&self) -> Result<(), String> {
+        if !ENCODER_PRESETS.contains(&self.preset.as_str()) {
+            return Err(format!(
+                "unknown preset '{}' (expected one of {})",
+                self.preset,
+                ENCODER_PRESETS.join(", ")
+            ));
+        }
+        if self.crf > 51 {
+            return Err(format!("crf {} is out of range 0-51", self.crf));
+        }
+        if self.audio_bitrate_kbps == 0 {
+            return Err("audio_bitrate_kbps must be positive".to_string());
+        }
+        if self.ladder.is_empty() {
+            return Err("ladder must have at least one rung".to_string());
+        }
+        for (i, rung) in self.ladder.iter().enumerate() {
+            if rung.label.trim().is_empty() {
+                return Err(format!("ladder rung {} has an empty label", i + 1));
+            }
+            if rung.height == 0 || rung.video_bitrate_kbps == 0 || rung.audio_bitrate_kbps == 0 {
+                return Err(format!(
+                    "ladder rung '{}' needs a positive height and bitrates",
+                    rung.label
+                ));
+            }
+            if let Some(prev) = i.checked_sub(1).map(|p| &self.ladder[p]) {
+                if rung.height >= prev.height {
+                    return Err(format!(
+                        "ladder must be ordered from highest to lowest height ('{}' follows '{}')",
+                        rung.label, prev.label
+                    ));
+                }
+            }
+        }
+        if let Some(max) = self.max_height {
+            let lowest = self.ladder[self.ladder.len() - 1].height;
+            if max < lowest {
+                return Err(format!(
+                    "max_height {max} is below the lowest ladder rung ({lowest})"
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Default for EncodingConfig {
+    fn default() -> Self {
+        Self {
+            preset: default_encoder_preset(),
+            crf: default_encoder_crf(),
+            max_height: None,
+            audio_bitrate_kbps: default_audio_bitrate_kbps(),
+            ladder: default_ladder(),
+        }
+    }
+}
+
+fn default_encoder_preset() -> String {
+    "veryfast".to_string()
+}
+
+fn default_encoder_crf() -> u8 {
+    23
+}
+
+fn default_audio_bitrate_kbps() -> u32 {
+    192
+}
+
+fn default_ladder() -> Vec<LadderRung> {
+    let rung = |label: &str, width, height, video, audio, bandwidth| LadderRung {
+        label: label.to_string(),
+        width,
+        height,
+        video_bitrate_kbps: video,
+        audio_bitrate_kbps: audio,
+        bandwidth_bps: Some(bandwidth),
+    };
+    vec![
+        rung("2160p", 3840, 2160, 14000, 192, 15_000_000),
+        rung("1080p", 1920, 1080, 5000, 192, 5_500_000),
+        rung("720p", 1280, 720, 2800, 128, 3_100_000),
+        rung("480p", 854, 480, 1400, 128, 1_600_000),
+        rung("360p", 640, 360, 800, 96, 950_000),
+    ]
 }
 
 fn default_transcode_queue_timeout_secs() -> u64 {
@@ -130,11 +361,54 @@ fn default_hls_ffmpeg_idle_secs() -> u64 {
     30
 }
 
+fn default_segment_cache_max_gb() -> f64 {
+    50.0
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MetadataConfig {
     pub tmdb_api_key: Option<String>,
     pub image_cache_dir: PathBuf,
     pub rate_limit_per_second: u32,
+    /// Metadata languages as TMDB language tags (e.g. "en-US", "es-MX").
+    /// The first is the primary language stored on every item; the rest are
+    /// fetched as translations for users who prefer them.
+    #[serde(default = "default_metadata_languages")]
+    pub languages: Vec<String>,
+}
+
+fn default_metadata_languages() -> Vec<String> {
+    vec!["en-US".to_string()]
+}
+
+impl MetadataConfig {
+    /// The language item metadata is stored in, and the fallback for translations.
+    pub fn primary_language(&self) -> &str {
+        self.languages
+            .first()
+            .map(String::as_str)
+            .unwrap_or("en-US")
+    }
+
+    /// Match a requested language against the configured list: an exact tag
+    /// first ("es-MX"), then the same base language ("es" or "es-ES" → "es-MX").
+    pub fn match_language(&self, requested: &str) -> Option<&str> {
+        let requested = requested.trim();
+        if requested.is_empty() {
+            return None;
+        }
+        let base = |tag: &str| {
+            tag.split(['-', '_'])
+                .next()
+                .unwrap_or(tag)
+                .to_ascii_lowercase()
+        };
+        self.languages
+            .iter()
+            .find(|l| l.eq_ignore_ascii_case(requested))
+            .or_else(|| self.languages.iter().find(|l| base(l) == base(requested)))
+            .map(String::as_str)
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -221,12 +495,226 @@ fn default_update_repo() -> String {
     "ryan-stephens/ferrite".to_string()
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DownloadConfig {
+    /// Directory where completed offline downloads are stored.
+    #[serde(default = "default_download_cache_dir")]
+    pub cache_dir: PathBuf,
+    /// Maximum total size of the download cache in GiB.
+    /// Least-recently-accessed downloads are evicted when the quota is exceeded.
+    #[serde(default = "default_download_quota_gb")]
+    pub quota_gb: u64,
+    /// Hours a completed download is kept before it expires and is deleted.
+    #[serde(default = "default_download_expiry_hours")]
+    pub expiry_hours: u64,
+    /// Number of download transcodes that may run at the same time.
+    /// Kept separate from `max_concurrent_transcodes` so queued downloads never
+    /// starve live playback.
+    #[serde(default = "default_download_max_concurrent")]
+    pub max_concurrent: usize,
+}
+
+impl Default for DownloadConfig {
+    fn default() -> Self {
+        Self {
+            cache_dir: default_download_cache_dir(),
+            quota_gb: default_download_quota_gb(),
+            expiry_hours: default_download_expiry_hours(),
+            max_concurrent: default_download_max_concurrent(),
+        }
+    }
+}
+
+impl DownloadConfig {
+    /// Quota expressed in bytes.
+    pub fn quota_bytes(&self) -> u64 {
+        self.quota_gb.saturating_mul(1024 * 1024 * 1024)
+    }
+}
+
+fn default_download_cache_dir() -> PathBuf {
+    PathBuf::from("cache/downloads")
+}
+
+fn default_download_quota_gb() -> u64 {
+    50
+}
+
+fn default_download_expiry_hours() -> u64 {
+    168
+}
+
+fn default_download_max_concurrent() -> usize {
+    1
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NetworkConfig {
+    /// Subnets (CIDR notation) whose clients are treated as local.
+    /// Remote bitrate caps never apply to local clients.
+    #[serde(default = "default_local_subnets")]
+    pub local_subnets: Vec<String>,
+    /// Use the first `X-Forwarded-For` address as the client IP.
+    /// Only enable this when Ferrite sits behind a trusted reverse proxy.
+    #[serde(default)]
+    pub trust_forwarded_for: bool,
+    /// Server-wide bitrate cap (kbps) for remote clients. Variants above the
+    /// cap are left out of the HLS master playlist. `None` = unlimited.
+    #[serde(default)]
+    pub remote_max_bitrate_kbps: Option<u32>,
+    /// Default number of concurrent playback sessions per user (0 = unlimited).
+    /// Can be overridden per user.
+    #[serde(default)]
+    pub max_streams_per_user: u32,
+}
+
+impl Default for NetworkConfig {
+    fn default() -> Self {
+        Self {
+            local_subnets: default_local_subnets(),
+            trust_forwarded_for: false,
+            remote_max_bitrate_kbps: None,
+            max_streams_per_user: 0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LiveTvConfig {
+    /// Enable the live TV subsystem (channel import, guide refresh and DVR).
+    #[serde(default)]
+    pub enabled: bool,
+    /// M3U channel list: a local file path or an `http(s)://` URL
+    /// (e.g. `http://hdhomerun.local/lineup.m3u`).
+    #[serde(default)]
+    pub m3u_path: Option<String>,
+    /// XMLTV guide: a local file path or an `http(s)://` URL.
+    #[serde(default)]
+    pub xmltv_path: Option<String>,
+    /// Directory recordings are written to. Point this at a library folder
+    /// so finished recordings are picked up by the scanner.
+    #[serde(default = "default_recordings_dir")]
+    pub recordings_dir: PathBuf,
+    /// Hours between automatic channel and guide refreshes.
+    #[serde(default = "default_guide_refresh_hours")]
+    pub guide_refresh_hours: u64,
+}
+
+impl Default for LiveTvConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            m3u_path: None,
+            xmltv_path: None,
+            recordings_dir: default_recordings_dir(),
+            guide_refresh_hours: default_guide_refresh_hours(),
+        }
+    }
+}
+
+/// How much of a file an integrity check decodes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IntegrityMode {
+    /// A few short windows spread through the file, the last at its reported end.
+    #[default]
+    Sample,
+    /// Every frame of the file.
+    Full,
+}
+
+impl IntegrityMode {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Sample => "sample",
+            Self::Full => "full",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "sample" => Some(Self::Sample),
+            "full" => Some(Self::Full),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IntegrityConfig {
+    /// Check library files in the background. On-demand checks from the
+    /// admin API work either way.
+    #[serde(default)]
+    pub enabled: bool,
+    /// Decode mode for scheduled checks.
+    #[serde(default)]
+    pub mode: IntegrityMode,
+    /// Hours between scheduled passes.
+    #[serde(default = "default_integrity_interval_hours")]
+    pub interval_hours: u64,
+    /// Files checked per scheduled pass, never-checked files first.
+    #[serde(default = "default_integrity_items_per_pass")]
+    pub items_per_pass: u32,
+    /// Days before an unchanged file is checked again.
+    #[serde(default = "default_integrity_recheck_days")]
+    pub recheck_days: u64,
+}
+
+impl Default for IntegrityConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            mode: IntegrityMode::default(),
+            interval_hours: default_integrity_interval_hours(),
+            items_per_pass: default_integrity_items_per_pass(),
+            recheck_days: default_integrity_recheck_days(),
+        }
+    }
+}
+
+fn default_integrity_interval_hours() -> u64 {
+    24
+}
+
+fn default_integrity_items_per_pass() -> u32 {
+    200
+}
+
+fn default_integrity_recheck_days() -> u64 {
+    30
+}
+
+fn default_recordings_dir() -> PathBuf {
+    PathBuf::from("recordings")
+}
+
+fn default_guide_refresh_hours() -> u64 {
+    12
+}
+
+fn default_local_subnets() -> Vec<String> {
+    [
+        "127.0.0.0/8",
+        "10.0.0.0/8",
+        "172.16.0.0/12",
+        "192.168.0.0/16",
+        "169.254.0.0/16",
+        "::1/128",
+        "fc00::/7",
+        "fe80::/10",
+    ]
+    .into_iter()
+    .map(String::from)
+    .collect()
+}
+
 impl Default for MetadataConfig {
     fn default() -> Self {
         Self {
             tmdb_api_key: None,
             image_cache_dir: PathBuf::from("cache/images"),
             rate_limit_per_second: 4,
+            languages: default_metadata_languages(),
         }
     }
 }
@@ -273,11 +761,22 @@ impl AppConfig {
             self.scanner.subtitle_cache_dir = data_dir.join(&self.scanner.subtitle_cache_dir);
         }
 
+        // Resolve download cache dir
+        if self.downloads.cache_dir.is_relative() {
+            self.downloads.cache_dir = data_dir.join(&self.downloads.cache_dir);
+        }
+
+        // Resolve live TV recordings dir
+        if self.livetv.recordings_dir.is_relative() {
+            self.livetv.recordings_dir = data_dir.join(&self.livetv.recordings_dir);
+        }
+
         // Ensure cache directories exist
         for dir in [
             &self.transcode.cache_dir,
             &self.metadata.image_cache_dir,
             &self.scanner.subtitle_cache_dir,
+            &self.downloads.cache_dir,
         ] {
             if let Err(e) = std::fs::create_dir_all(dir) {
                 tracing::warn!("Failed to create cache directory {}: {}", dir.display(), e);
@@ -337,11 +836,19 @@ impl Default for AppConfig {
                 hls_segment_mime_mode: HlsSegmentMimeMode::default(),
                 hls_ffmpeg_idle_secs: default_hls_ffmpeg_idle_secs(),
                 hw_accel: None,
+                encoding: EncodingConfig::default(),
+                library_encoding: HashMap::new(),
+                segment_cache_enabled: false,
+                segment_cache_max_gb: default_segment_cache_max_gb(),
             },
             metadata: MetadataConfig::default(),
             auth: None,
             dlna: DlnaConfig::default(),
             update: UpdateConfig::default(),
+            downloads: DownloadConfig::default(),
+            network: NetworkConfig::default(),
+            livetv: LiveTvConfig::default(),
+            integrity: IntegrityConfig::default(),
         }
     }
 }
@@ -11,12 +11,17 @@ pub enum LibraryType {
     Music,
 }
 
-/// A configured media library (a directory the user points at).
+/// A configured media library (one or more directories the user points at).
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Library {
     pub id: Uuid,
     pub name: String,
+    /// Primary root folder (the first entry of `paths`).
     pub path: String,
+    /// Every root folder, primary first.
+    pub paths: Vec<String>,
+    /// Glob patterns excluded from scans, in `.ferriteignore` syntax.
+    pub ignore_patterns: Vec<String>,
     pub library_type: LibraryType,
     pub scan_interval_minutes: u32,
     pub last_scanned_at: Option<DateTime<Utc>>,
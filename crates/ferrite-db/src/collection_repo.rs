@@ -1,4 +1,6 @@
+use crate::movie_repo::{self, MediaQuery, MovieWithMediaRow};
 use anyhow::Result;
+use serde::{Deserialize, Serialize};
 use sqlx::SqlitePool;
 use uuid::Uuid;
 
@@ -9,10 +11,131 @@ pub struct CollectionRow {
     pub name: String,
     pub description: String,
     pub kind: String,
+    /// JSON-encoded [`SmartRules`]; only set for smart collections.
+    /// Serialized as the rule object rather than a string.
+    #[serde(serialize_with = "serialize_rules")]
+    pub rules: Option<String>,
     pub created_at: String,
     pub updated_at: String,
 }
 
+fn serialize_rules<S: serde::Serializer>(
+    rules: &Option<String>,
+    serializer: S,
+) -> std::result::Result<S::Ok, S::Error> {
+    let value = rules
+        .as_deref()
+        .and_then(|json| serde_json::from_str::<serde_json::Value>(json).ok());
+    value.serialize(serializer)
+}
+
+impl CollectionRow {
+    /// Decode the rule set of a smart collection. `None` for other kinds.
+    pub fn smart_rules(&self) -> Result<Option<SmartRules>> {
+        match &self.rules {
+            Some(json) if self.kind == "smart" => Ok(Some(serde_json::from_str(json)?)),
+            _ => Ok(None),
+        }
+    }
+}
+
+/// Saved filter rules of a smart collection. Every set field must match;
+/// members are evaluated at listing time through [`MediaQuery`].
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SmartRules {
+    pub library_id: Option<String>,
+    pub genre: Option<String>,
+    pub year_min: Option<i64>,
+    pub year_max: Option<i64>,
+    pub min_rating: Option<f64>,
+    /// "sd", "720p", "1080p" or "4k"
+    pub resolution: Option<String>,
+    /// Video codec as probed, e.g. "hevc"
+    pub video_codec: Option<String>,
+    /// Hide items the collection owner has finished watching
+    pub unwatched: bool,
+    pub added_within_days: Option<i64>,
+    /// Same values as the media listing `sort` / `dir` parameters
+    pub sort_by: Option<String>,
+    pub sort_dir: Option<String>,
+}
+
+impl SmartRules {
+    /// Check the rules for values the query cannot express.
+    pub fn validate(&self) -> std::result::Result<(), String> {
+        if let (Some(min), Some(max)) = (self.year_min, self.year_max) {
+            if min > max {
+                return Err(format!("year_min ({min}) is after year_max ({max})"));
+            }
+        }
+        if let Some(res) = &self.resolution {
+            if resolution_width_range(res).is_none() {
+                return Err(format!(
+                    "Unknown resolution '{res}' (expected sd, 720p, 1080p or 4k)"
+                ));
+            }
+        }
+        if self.added_within_days.is_some_and(|d| d <= 0) {
+            return Err("added_within_days must be positive".into());
+        }
+        Ok(())
+    }
+
+    /// Build the listing query for these rules, as seen by `owner_id`.
+    pub fn media_query<'a>(
+        &'a self,
+        owner_id: &'a str,
+        page: i64,
+        per_page: i64,
+    ) -> MediaQuery<'a> {
+        let (min_width, max_width) = self
+            .resolution
+            .as_deref()
+            .and_then(resolution_width_range)
+            .unwrap_or((None, None));
+        MediaQuery {
+            library_id: self.library_id.as_deref(),
+            genre: self.genre.as_deref(),
+            sort_by: self.sort_by.as_deref(),
+            sort_dir: self.sort_dir.as_deref(),
+            page,
+            per_page,
+            year_min: self.year_min,
+            year_max: self.year_max,
+            min_rating: self.min_rating,
+            min_width,
+            max_width,
+            video_codec: self.video_codec.as_deref(),
+            added_within_days: self.added_within_days,
+            unwatched_by: self.unwatched.then_some(owner_id),
+            ..Default::default()
+        }
+    }
+}
+
+/// Width range (min inclusive, max exclusive) of a resolution class. Width
+/// rather than height so letterboxed scope releases land in the right class.
+fn resolution_width_range(resolution: &str) -> Option<(Option<i64>, Option<i64>)> {
+    match resolution.to_ascii_lowercase().as_str() {
+        "sd" => Some((None, Some(1200))),
+        "720p" => Some((Some(1200), Some(1800))),
+        "1080p" => Some((Some(1800), Some(3200))),
+        "4k" | "2160p" | "uhd" => Some((Some(3200), None)),
+        _ => None,
+    }
+}
+
+/// A user a collection is shared with.
+#[derive(Debug, Clone, sqlx::FromRow, serde::Serialize)]
+pub struct ShareRow {
+    pub collection_id: String,
+    pub user_id: String,
+    pub username: String,
+    pub can_edit: i64,
+    pub created_at: String,
+}
+
 #[derive(Debug, Clone, sqlx::FromRow, serde::Serialize)]
 pub struct CollectionItemRow {
     pub id: String,
@@ -44,31 +167,113 @@ pub async fn create_collection(
     Ok(row)
 }
 
-/// List all collections for a user.
+/// Create a smart collection from a validated rule set.
+pub async fn create_smart_collection(
+    pool: &SqlitePool,
+    user_id: &str,
+    name: &str,
+    description: &str,
+    rules: &SmartRules,
+) -> Result<CollectionRow> {
+    let id = Uuid::new_v4().to_string();
+    let row = sqlx::query_as::<_, CollectionRow>(
+        "INSERT INTO collections (id, user_id, name, description, kind, rules) VALUES (?, ?, ?, ?, 'smart', ?) RETURNING *",
+    )
+    .bind(&id)
+    .bind(user_id)
+    .bind(name)
+    .bind(description)
+    .bind(serde_json::to_string(rules)?)
+    .fetch_one(pool)
+    .await?;
+    Ok(row)
+}
+
+/// List all collections a user owns or has been shared.
 pub async fn list_collections(
     pool: &SqlitePool,
     user_id: &str,
     kind: Option<&str>,
 ) -> Result<Vec<CollectionRow>> {
-    let rows = if let Some(k) = kind {
-        sqlx::query_as::<_, CollectionRow>(
-            "SELECT * FROM collections WHERE user_id = ? AND kind = ? ORDER BY updated_at DESC",
-        )
-        .bind(user_id)
-        .bind(k)
-        .fetch_all(pool)
-        .await?
-    } else {
-        sqlx::query_as::<_, CollectionRow>(
-            "SELECT * FROM collections WHERE user_id = ? ORDER BY updated_at DESC",
-        )
-        .bind(user_id)
-        .fetch_all(pool)
-        .await?
-    };
+    let rows = sqlx::query_as::<_, CollectionRow>(
+        "SELECT * FROM collections \
+         WHERE (user_id = ? \
+                OR id IN (SELECT collection_id FROM collection_shares WHERE user_id = ?)) \
+           AND (? IS NULL OR kind = ?) \
+         ORDER BY updated_at DESC",
+    )
+    .bind(user_id)
+    .bind(user_id)
+    .bind(kind)
+    .bind(kind)
+    .fetch_all(pool)
+    .await?;
     Ok(rows)
 }
 
+/// Share a collection with a user, or change an existing share's permission.
+pub async fn share_collection(
+    pool: &SqlitePool,
+    collection_id: &str,
+    user_id: &str,
+    can_edit: bool,
+) -> Result<()> {
+    sqlx::query(
+        "INSERT INTO collection_shares (collection_id, user_id, can_edit) VALUES (?, ?, ?) \
+         ON CONFLICT(collection_id, user_id) DO UPDATE SET can_edit = excluded.can_edit",
+    )
+    .bind(collection_id)
+    .bind(user_id)
+    .bind(can_edit as i64)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Stop sharing a collection with a user.
+pub async fn unshare_collection(
+    pool: &SqlitePool,
+    collection_id: &str,
+    user_id: &str,
+) -> Result<bool> {
+    let result =
+        sqlx::query("DELETE FROM collection_shares WHERE collection_id = ? AND user_id = ?")
+            .bind(collection_id)
+            .bind(user_id)
+            .execute(pool)
+            .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// List the users a collection is shared with.
+pub async fn list_shares(pool: &SqlitePool, collection_id: &str) -> Result<Vec<ShareRow>> {
+    let rows = sqlx::query_as::<_, ShareRow>(
+        "SELECT cs.collection_id, cs.user_id, u.username, cs.can_edit, cs.created_at \
+         FROM collection_shares cs JOIN users u ON u.id = cs.user_id \
+         WHERE cs.collection_id = ? ORDER BY u.username ASC",
+    )
+    .bind(collection_id)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
+
+/// Whether `user_id` was shared the collection, and if so whether they may edit it.
+pub async fn get_share_permission(
+    pool: &SqlitePool,
+    collection_id: &str,
+    user_id: &str,
+) -> Result<Option<bool>> {
+    let row: Option<(i64,)> = sqlx::query_as(
+        "SELECT can_edit FROM collection_shares WHERE collection_id = ? AND user_id = ?",
+    )
+    .bind(collection_id)
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await?;
+    Ok(row.map(|(can_edit,)| can_edit != 0))
+}
+
 /// Get a single collection by ID.
 pub async fn get_collection(pool: &SqlitePool, id: &str) -> Result<Option<CollectionRow>> {
     let row = sqlx::query_as::<_, CollectionRow>("SELECT * FROM collections WHERE id = ?")
@@ -96,6 +301,44 @@ pub async fn update_collection(
     Ok(row)
 }
 
+/// Replace the rule set of a smart collection.
+pub async fn update_rules(
+    pool: &SqlitePool,
+    id: &str,
+    rules: &SmartRules,
+) -> Result<Option<CollectionRow>> {
+    let row = sqlx::query_as::<_, CollectionRow>(
+        "UPDATE collections SET rules = ?, updated_at = datetime('now') WHERE id = ? AND kind = 'smart' RETURNING *",
+    )
+    .bind(serde_json::to_string(rules)?)
+    .bind(id)
+    .fetch_optional(pool)
+    .await?;
+    Ok(row)
+}
+
+/// Evaluate a smart collection's rules: one page of matching media items.
+pub async fn list_smart_items(
+    pool: &SqlitePool,
+    collection: &CollectionRow,
+    rules: &SmartRules,
+    page: i64,
+    per_page: i64,
+) -> Result<Vec<MovieWithMediaRow>> {
+    let query = rules.media_query(&collection.user_id, page, per_page);
+    movie_repo::list_movies_with_media(pool, &query, Some(&collection.user_id)).await
+}
+
+/// Count the media items currently matching a smart collection's rules.
+pub async fn count_smart_items(
+    pool: &SqlitePool,
+    collection: &CollectionRow,
+    rules: &SmartRules,
+) -> Result<i64> {
+    let query = rules.media_query(&collection.user_id, 1, 1);
+    movie_repo::count_movies_with_media(pool, &query).await
+}
+
 /// Delete a collection and all its items (CASCADE).
 pub async fn delete_collection(pool: &SqlitePool, id: &str) -> Result<bool> {
     let result = sqlx::query("DELETE FROM collections WHERE id = ?")
@@ -0,0 +1,259 @@
+use anyhow::Result;
+use sqlx::SqlitePool;
+use uuid::Uuid;
+
+/// A row from the downloads table.
+#[derive(Debug, Clone, sqlx::FromRow, serde::Serialize)]
+pub struct DownloadRow {
+    pub id: String,
+    pub user_id: Option<String>,
+    pub media_id: String,
+    pub variant_label: String,
+    pub audio_stream: i64,
+    /// Comma-separated external subtitle ids muxed as soft tracks.
+    pub subtitle_ids: String,
+    pub status: String,
+    pub progress_pct: i64,
+    /// Location of the finished file inside the download cache (internal only).
+    #[serde(skip_serializing)]
+    pub file_path: Option<String>,
+    pub file_size: Option<i64>,
+    pub estimated_size: i64,
+    pub error: Option<String>,
+    pub created_at: String,
+    pub completed_at: Option<String>,
+    pub expires_at: Option<String>,
+    pub last_accessed_at: Option<String>,
+}
+
+impl DownloadRow {
+    /// Parse the stored comma-separated subtitle ids.
+    pub fn subtitle_id_list(&self) -> Vec<i64> {
+        self.subtitle_ids
+            .split(',')
+            .filter_map(|s| s.trim().parse().ok())
+            .collect()
+    }
+}
+
+/// Queue a new download. Returns the inserted row.
+#[allow(clippy::too_many_arguments)]
+pub async fn create_download(
+    pool: &SqlitePool,
+    user_id: Option<&str>,
+    media_id: &str,
+    variant_label: &str,
+    audio_stream: u32,
+    subtitle_ids: &[i64],
+    estimated_size: u64,
+) -> Result<DownloadRow> {
+    let id = Uuid::new_v4().to_string();
+    let subtitle_ids = subtitle_ids
+        .iter()
+        .map(i64::to_string)
+        .collect::<Vec<_>>()
+        .join(",");
+    let row = sqlx::query_as::<_, DownloadRow>(
+        "INSERT INTO downloads (id, user_id, media_id, variant_label, audio_stream, subtitle_ids, estimated_size) \
+         VALUES (?, ?, ?, ?, ?, ?, ?) RETURNING *",
+    )
+    .bind(&id)
+    .bind(user_id)
+    .bind(media_id)
+    .bind(variant_label)
+    .bind(audio_stream as i64)
+    .bind(&subtitle_ids)
+    .bind(estimated_size as i64)
+    .fetch_one(pool)
+    .await?;
+    Ok(row)
+}
+
+/// Get a single download by ID.
+pub async fn get_download(pool: &SqlitePool, id: &str) -> Result<Option<DownloadRow>> {
+    let row = sqlx::query_as::<_, DownloadRow>("SELECT * FROM downloads WHERE id = ?")
+        .bind(id)
+        .fetch_optional(pool)
+        .await?;
+    Ok(row)
+}
+
+/// List downloads owned by a user (or the anonymous owner when `user_id` is `None`).
+pub async fn list_downloads(pool: &SqlitePool, user_id: Option<&str>) -> Result<Vec<DownloadRow>> {
+    let rows = match user_id {
+        Some(uid) => {
+            sqlx::query_as::<_, DownloadRow>(
+                "SELECT * FROM downloads WHERE user_id = ? ORDER BY created_at DESC",
+            )
+            .bind(uid)
+            .fetch_all(pool)
+            .await?
+        }
+        None => {
+            sqlx::query_as::<_, DownloadRow>(
+                "SELECT * FROM downloads WHERE user_id IS NULL ORDER BY created_at DESC",
+            )
+            .fetch_all(pool)
+            .await?
+        }
+    };
+    Ok(rows)
+}
+
+/// Queued and transcoding downloads of every user, oldest first.
+pub async fn list_active_downloads(pool: &SqlitePool) -> Result<Vec<DownloadRow>> {
+    let rows = sqlx::query_as::<_, DownloadRow>(
+        "SELECT * FROM downloads WHERE status IN ('queued', 'transcoding') ORDER BY created_at ASC",
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
+
+/// Find an existing queued, running or ready download with identical parameters
+/// so repeat requests reuse it instead of transcoding again.
+pub async fn find_matching(
+    pool: &SqlitePool,
+    user_id: Option<&str>,
+    media_id: &str,
+    variant_label: &str,
+    audio_stream: u32,
+    subtitle_ids: &[i64],
+) -> Result<Option<DownloadRow>> {
+    let subtitle_ids = subtitle_ids
+        .iter()
+        .map(i64::to_string)
+        .collect::<Vec<_>>()
+        .join(",");
+    let row = sqlx::query_as::<_, DownloadRow>(
+        "SELECT * FROM downloads \
+         WHERE user_id IS ? AND media_id = ? AND variant_label = ? AND audio_stream = ? \
+           AND subtitle_ids = ? AND status IN ('queued', 'transcoding', 'ready') \
+         ORDER BY created_at DESC LIMIT 1",
+    )
+    .bind(user_id)
+    .bind(media_id)
+    .bind(variant_label)
+    .bind(audio_stream as i64)
+    .bind(&subtitle_ids)
+    .fetch_optional(pool)
+    .await?;
+    Ok(row)
+}
+
+/// Atomically claim the oldest queued download and mark it as transcoding.
+pub async fn claim_next_queued(pool: &SqlitePool) -> Result<Option<DownloadRow>> {
+    let row = sqlx::query_as::<_, DownloadRow>(
+        "UPDATE downloads SET status = 'transcoding', progress_pct = 0, error = NULL \
+         WHERE id = (SELECT id FROM downloads WHERE status = 'queued' ORDER BY created_at ASC LIMIT 1) \
+         RETURNING *",
+    )
+    .fetch_optional(pool)
+    .await?;
+    Ok(row)
+}
+
+/// Put downloads that were interrupted mid-transcode (e.g. by a restart) back in the queue.
+pub async fn requeue_interrupted(pool: &SqlitePool) -> Result<u64> {
+    let result = sqlx::query(
+        "UPDATE downloads SET status = 'queued', progress_pct = 0 WHERE status = 'transcoding'",
+    )
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected())
+}
+
+/// Update the transcode progress of a running download.
+pub async fn update_progress(pool: &SqlitePool, id: &str, progress_pct: u8) -> Result<()> {
+    sqlx::query("UPDATE downloads SET progress_pct = ? WHERE id = ? AND status = 'transcoding'")
+        .bind(progress_pct as i64)
+        .bind(id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// Mark a download as ready to serve.
+pub async fn mark_ready(
+    pool: &SqlitePool,
+    id: &str,
+    file_path: &str,
+    file_size: u64,
+    expiry_hours: u64,
+) -> Result<()> {
+    sqlx::query(
+        "UPDATE downloads SET status = 'ready', progress_pct = 100, file_path = ?, file_size = ?, \
+         completed_at = datetime('now'), last_accessed_at = datetime('now'), \
+         expires_at = datetime('now', '+' || ? || ' hours') \
+         WHERE id = ?",
+    )
+    .bind(file_path)
+    .bind(file_size as i64)
+    .bind(expiry_hours as i64)
+    .bind(id)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Mark a download as failed with an error summary.
+pub async fn mark_failed(pool: &SqlitePool, id: &str, error: &str) -> Result<()> {
+    sqlx::query("UPDATE downloads SET status = 'failed', error = ? WHERE id = ?")
+        .bind(error)
+        .bind(id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// Record that a ready download was just fetched (drives LRU eviction).
+pub async fn touch(pool: &SqlitePool, id: &str) -> Result<()> {
+    sqlx::query("UPDATE downloads SET last_accessed_at = datetime('now') WHERE id = ?")
+        .bind(id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// Delete a download row.
+pub async fn delete_download(pool: &SqlitePool, id: &str) -> Result<bool> {
+    let result = sqlx::query("DELETE FROM downloads WHERE id = ?")
+        .bind(id)
+        .execute(pool)
+        .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// List ready downloads whose expiry time has passed.
+pub async fn list_expired(pool: &SqlitePool) -> Result<Vec<DownloadRow>> {
+    let rows = sqlx::query_as::<_, DownloadRow>(
+        "SELECT * FROM downloads WHERE status = 'ready' AND expires_at <= datetime('now')",
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
+
+/// List ready downloads from least to most recently accessed (eviction order).
+pub async fn list_ready_lru(pool: &SqlitePool) -> Result<Vec<DownloadRow>> {
+    let rows = sqlx::query_as::<_, DownloadRow>(
+        "SELECT * FROM downloads WHERE status = 'ready' \
+         ORDER BY COALESCE(last_accessed_at, completed_at) ASC",
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
+
+/// Bytes currently committed to the download cache: finished files plus the
+/// estimated size of queued and running transcodes.
+pub async fn committed_bytes(pool: &SqlitePool) -> Result<u64> {
+    let total: (i64,) = sqlx::query_as(
+        "SELECT COALESCE(SUM(CASE WHEN status = 'ready' THEN COALESCE(file_size, 0) \
+                                  ELSE estimated_size END), 0) \
+         FROM downloads WHERE status IN ('queued', 'transcoding', 'ready')",
+    )
+    .fetch_one(pool)
+    .await?;
+    Ok(total.0.max(0) as u64)
+}
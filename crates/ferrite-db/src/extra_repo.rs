@@ -0,0 +1,162 @@
+use anyhow::Result;
+use sqlx::{SqliteConnection, SqlitePool};
+
+/// A library file with what the extras pass needs to classify and link it.
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct ExtraCandidateRow {
+    pub id: String,
+    pub file_path: String,
+    pub media_type: String,
+    pub extra_type: Option<String>,
+    pub extra_parent_media_id: Option<String>,
+    pub extra_parent_show_id: Option<String>,
+    /// Primary of the file's movie group (the file itself when ungrouped)
+    pub movie_id: String,
+    /// Show of the file when it is an episode
+    pub show_id: Option<String>,
+}
+
+/// An extra as listed under its movie or show.
+#[derive(Debug, Clone, sqlx::FromRow, serde::Serialize)]
+pub struct ExtraRow {
+    pub id: String,
+    pub title: Option<String>,
+    pub extra_type: String,
+    pub duration_ms: Option<i64>,
+    pub width: Option<i64>,
+    pub height: Option<i64>,
+    pub added_at: String,
+}
+
+/// Every file in a library with its extra and parent columns.
+pub async fn list_extra_candidates(
+    pool: &SqlitePool,
+    library_id: &str,
+) -> Result<Vec<ExtraCandidateRow>> {
+    let rows = sqlx::query_as::<_, ExtraCandidateRow>(
+        r#"SELECT mi.id, mi.file_path, mi.media_type, mi.extra_type,
+                  mi.extra_parent_media_id, mi.extra_parent_show_id,
+                  COALESCE(mi.movie_group_id, mi.id) AS movie_id,
+                  s.tv_show_id AS show_id
+           FROM media_items mi
+           LEFT JOIN episodes e ON e.media_item_id = mi.id AND e.file_position = 0
+           LEFT JOIN seasons s ON s.id = e.season_id
+           WHERE mi.library_id = ?"#,
+    )
+    .bind(library_id)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
+
+/// Mark a media item as an extra. It leaves any movie group and stops being
+/// a movie or episode of its own.
+pub async fn mark_extra(
+    conn: &mut SqliteConnection,
+    media_item_id: &str,
+    extra_type: &str,
+    title: &str,
+) -> Result<()> {
+    sqlx::query(
+        r#"UPDATE media_items
+           SET extra_type = ?, title = ?, year = NULL,
+               movie_group_id = NULL, version_label = NULL,
+               part_number = NULL, stack_head_id = NULL
+           WHERE id = ?"#,
+    )
+    .bind(extra_type)
+    .bind(title)
+    .bind(media_item_id)
+    .execute(&mut *conn)
+    .await?;
+    sqlx::query("DELETE FROM movies WHERE media_item_id = ?")
+        .bind(media_item_id)
+        .execute(&mut *conn)
+        .await?;
+    sqlx::query("DELETE FROM episodes WHERE media_item_id = ?")
+        .bind(media_item_id)
+        .execute(&mut *conn)
+        .await?;
+    Ok(())
+}
+
+/// Turn an extra back into regular content (e.g. after it was moved out of
+/// an extras folder).
+pub async fn clear_extra(conn: &mut SqliteConnection, media_item_id: &str) -> Result<()> {
+    sqlx::query(
+        r#"UPDATE media_items
+           SET extra_type = NULL, extra_parent_media_id = NULL, extra_parent_show_id = NULL
+           WHERE id = ?"#,
+    )
+    .bind(media_item_id)
+    .execute(conn)
+    .await?;
+    Ok(())
+}
+
+/// Point an extra at the movie (group primary) or show it belongs to.
+pub async fn set_extra_parent(
+    pool: &SqlitePool,
+    media_item_id: &str,
+    parent_media_id: Option<&str>,
+    parent_show_id: Option<&str>,
+) -> Result<()> {
+    sqlx::query(
+        "UPDATE media_items SET extra_parent_media_id = ?, extra_parent_show_id = ? WHERE id = ?",
+    )
+    .bind(parent_media_id)
+    .bind(parent_show_id)
+    .bind(media_item_id)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Extras of the movie `media_item_id` belongs to; any version or part of the
+/// movie resolves to the same list.
+pub async fn list_extras_for_media(
+    pool: &SqlitePool,
+    media_item_id: &str,
+) -> Result<Vec<ExtraRow>> {
+    let rows = sqlx::query_as::<_, ExtraRow>(
+        r#"SELECT id, title, extra_type, duration_ms, width, height, added_at
+           FROM media_items
+           WHERE extra_type IS NOT NULL
+             AND extra_parent_media_id = (
+                 SELECT COALESCE(movie_group_id, id) FROM media_items WHERE id = ?
+             )
+           ORDER BY extra_type ASC, title ASC, file_path ASC"#,
+    )
+    .bind(media_item_id)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
+
+/// Extras of a TV show.
+pub async fn list_extras_for_show(pool: &SqlitePool, show_id: &str) -> Result<Vec<ExtraRow>> {
+    let rows = sqlx::query_as::<_, ExtraRow>(
+        r#"SELECT id, title, extra_type, duration_ms, width, height, added_at
+           FROM media_items
+           WHERE extra_type IS NOT NULL AND extra_parent_show_id = ?
+           ORDER BY extra_type ASC, title ASC, file_path ASC"#,
+    )
+    .bind(show_id)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
+
+/// The show an episode belongs to.
+pub async fn show_id_for_episode(pool: &SqlitePool, media_item_id: &str) -> Result<Option<String>> {
+    let row: Option<(String,)> = sqlx::query_as(
+        r#"SELECT s.tv_show_id
+           FROM episodes e
+           JOIN seasons s ON s.id = e.season_id
+           WHERE e.media_item_id = ?"#,
+    )
+    .bind(media_item_id)
+    .fetch_optional(pool)
+    .await?;
+    Ok(row.map(|r| r.0))
+}
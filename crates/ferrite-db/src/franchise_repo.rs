@@ -0,0 +1,104 @@
+use anyhow::Result;
+use sqlx::SqlitePool;
+use uuid::Uuid;
+
+/// A franchise with the number of library movies in it.
+#[derive(Debug, Clone, sqlx::FromRow, serde::Serialize)]
+pub struct FranchiseRow {
+    pub id: String,
+    pub tmdb_id: i64,
+    pub name: String,
+    pub poster_path: Option<String>,
+    pub backdrop_path: Option<String>,
+    pub updated_at: String,
+    pub item_count: i64,
+}
+
+/// A franchise fetched from a metadata provider, ready to be stored.
+#[derive(Debug, Clone)]
+pub struct NewFranchise {
+    pub tmdb_id: i64,
+    pub name: String,
+    /// Local filenames in the image cache
+    pub poster_path: Option<String>,
+    pub backdrop_path: Option<String>,
+}
+
+/// Member count of a franchise: movies (primary versions only) linked to it.
+const ITEM_COUNT: &str = "(SELECT COUNT(*) FROM movies m \
+     JOIN media_items mi ON mi.id = m.media_item_id \
+     WHERE m.franchise_id = f.id \
+       AND (mi.movie_group_id IS NULL OR mi.movie_group_id = mi.id))";
+
+/// Point a movie at its franchise, or clear the link with `None`.
+/// Franchises are upserted by TMDB id so every film of a series shares one row.
+pub async fn set_movie_franchise(
+    pool: &SqlitePool,
+    media_item_id: &str,
+    franchise: Option<&NewFranchise>,
+) -> Result<()> {
+    let mut tx = pool.begin().await?;
+    let franchise_id = match franchise {
+        Some(f) => {
+            let (id,): (String,) = sqlx::query_as(
+                "INSERT INTO franchises (id, tmdb_id, name, poster_path, backdrop_path) \
+                 VALUES (?, ?, ?, ?, ?) \
+                 ON CONFLICT(tmdb_id) DO UPDATE SET \
+                   name = excluded.name, \
+                   poster_path = COALESCE(excluded.poster_path, franchises.poster_path), \
+                   backdrop_path = COALESCE(excluded.backdrop_path, franchises.backdrop_path), \
+                   updated_at = datetime('now') \
+                 RETURNING id",
+            )
+            .bind(Uuid::new_v4().to_string())
+            .bind(f.tmdb_id)
+            .bind(&f.name)
+            .bind(&f.poster_path)
+            .bind(&f.backdrop_path)
+            .fetch_one(&mut *tx)
+            .await?;
+            Some(id)
+        }
+        None => None,
+    };
+    sqlx::query("UPDATE movies SET franchise_id = ? WHERE media_item_id = ?")
+        .bind(&franchise_id)
+        .bind(media_item_id)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+/// List every franchise that still has movies in the library, by name.
+pub async fn list_franchises(pool: &SqlitePool) -> Result<Vec<FranchiseRow>> {
+    let sql = format!(
+        "SELECT * FROM (SELECT f.*, {ITEM_COUNT} AS item_count FROM franchises f) \
+         WHERE item_count > 0 ORDER BY name ASC"
+    );
+    let rows = sqlx::query_as::<_, FranchiseRow>(&sql)
+        .fetch_all(pool)
+        .await?;
+    Ok(rows)
+}
+
+/// Fetch a single franchise by ID.
+pub async fn get_franchise(pool: &SqlitePool, id: &str) -> Result<Option<FranchiseRow>> {
+    let sql = format!("SELECT f.*, {ITEM_COUNT} AS item_count FROM franchises f WHERE f.id = ?");
+    let row = sqlx::query_as::<_, FranchiseRow>(&sql)
+        .bind(id)
+        .fetch_optional(pool)
+        .await?;
+    Ok(row)
+}
+
+/// Delete franchises no movie points at any more. Returns how many were removed.
+pub async fn prune_empty_franchises(pool: &SqlitePool) -> Result<u64> {
+    let result = sqlx::query(
+        "DELETE FROM franchises \
+         WHERE NOT EXISTS (SELECT 1 FROM movies m WHERE m.franchise_id = franchises.id)",
+    )
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected())
+}
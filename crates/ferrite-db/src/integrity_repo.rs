@@ -0,0 +1,158 @@
+use anyhow::Result;
+use sqlx::SqlitePool;
+
+/// Latest integrity check of a media file.
+#[derive(Debug, Clone, sqlx::FromRow, serde::Serialize)]
+pub struct IntegrityRow {
+    pub media_item_id: String,
+    /// `ok`, `damaged` or `broken`
+    pub status: String,
+    /// `sample` or `full`
+    pub mode: String,
+    pub error_count: i64,
+    /// Distinct problems found, one per line
+    pub error_summary: Option<String>,
+    pub decoded_ms: i64,
+    pub file_size: i64,
+    pub checked_at: String,
+}
+
+/// A check result to store.
+#[derive(Debug)]
+pub struct IntegrityInsert<'a> {
+    pub media_item_id: &'a str,
+    pub status: &'a str,
+    pub mode: &'a str,
+    pub error_count: u64,
+    pub errors: &'a [String],
+    pub decoded_ms: u64,
+    pub file_size: i64,
+}
+
+/// Store (or replace) the check result of a media item.
+pub async fn save_integrity(pool: &SqlitePool, check: &IntegrityInsert<'_>) -> Result<()> {
+    let summary = (!check.errors.is_empty()).then(|| check.errors.join("\n"));
+    sqlx::query(
+        "INSERT INTO media_integrity \
+         (media_item_id, status, mode, error_count, error_summary, decoded_ms, file_size) \
+         VALUES (?, ?, ?, ?, ?, ?, ?) \
+         ON CONFLICT(media_item_id) DO UPDATE SET \
+           status = excluded.status, mode = excluded.mode, \
+           error_count = excluded.error_count, error_summary = excluded.error_summary, \
+           decoded_ms = excluded.decoded_ms, file_size = excluded.file_size, \
+           checked_at = datetime('now')",
+    )
+    .bind(check.media_item_id)
+    .bind(check.status)
+    .bind(check.mode)
+    .bind(check.error_count as i64)
+    .bind(summary)
+    .bind(check.decoded_ms as i64)
+    .bind(check.file_size)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// The latest check of a media item, whatever file it was made on.
+pub async fn get_integrity(pool: &SqlitePool, media_item_id: &str) -> Result<Option<IntegrityRow>> {
+    let row =
+        sqlx::query_as::<_, IntegrityRow>("SELECT * FROM media_integrity WHERE media_item_id = ?")
+            .bind(media_item_id)
+            .fetch_optional(pool)
+            .await?;
+    Ok(row)
+}
+
+/// A media item due for a check.
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct IntegrityTarget {
+    pub id: String,
+    pub file_path: String,
+    pub file_size: i64,
+    pub duration_ms: Option<i64>,
+}
+
+/// Items never checked, replaced since their check, or checked more than
+/// `recheck_days` ago; never-checked items first, then the longest unchecked.
+/// `recheck_days = 0` makes every item due. `limit = None` returns them all.
+pub async fn list_due_for_check(
+    pool: &SqlitePool,
+    library_id: Option<&str>,
+    recheck_days: u64,
+    limit: Option<u32>,
+) -> Result<Vec<IntegrityTarget>> {
+    let rows = sqlx::query_as::<_, IntegrityTarget>(
+        "SELECT mi.id, mi.file_path, mi.file_size, mi.duration_ms \
+         FROM media_items mi \
+         LEFT JOIN media_integrity ic ON ic.media_item_id = mi.id \
+         WHERE (? IS NULL OR mi.library_id = ?) \
+           AND (ic.media_item_id IS NULL \
+                OR ic.file_size != mi.file_size \
+                OR ic.checked_at <= datetime('now', ?)) \
+         ORDER BY ic.checked_at IS NOT NULL, ic.checked_at ASC, mi.file_path ASC \
+         LIMIT ?",
+    )
+    .bind(library_id)
+    .bind(library_id)
+    .bind(format!("-{recheck_days} days"))
+    .bind(limit.map_or(-1, i64::from))
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
+
+/// A media item whose latest check found a problem.
+#[derive(Debug, Clone, sqlx::FromRow, serde::Serialize)]
+pub struct BrokenMediaRow {
+    pub media_item_id: String,
+    pub library_id: String,
+    pub media_type: String,
+    pub file_path: String,
+    pub title: Option<String>,
+    pub duration_ms: Option<i64>,
+    /// `damaged` or `broken`
+    pub status: String,
+    pub mode: String,
+    pub error_count: i64,
+    pub error_summary: Option<String>,
+    pub decoded_ms: i64,
+    pub checked_at: String,
+}
+
+/// Items whose latest check of their current file found a problem, broken
+/// before damaged, optionally limited to one library.
+pub async fn list_broken_media(
+    pool: &SqlitePool,
+    library_id: Option<&str>,
+) -> Result<Vec<BrokenMediaRow>> {
+    let rows = sqlx::query_as::<_, BrokenMediaRow>(
+        "SELECT ic.media_item_id, mi.library_id, mi.media_type, mi.file_path, mi.title, \
+                mi.duration_ms, ic.status, ic.mode, ic.error_count, ic.error_summary, \
+                ic.decoded_ms, ic.checked_at \
+         FROM media_integrity ic \
+         JOIN media_items mi ON mi.id = ic.media_item_id \
+         WHERE ic.status != 'ok' \
+           AND ic.file_size = mi.file_size \
+           AND (? IS NULL OR mi.library_id = ?) \
+         ORDER BY ic.status = 'damaged', mi.file_path ASC",
+    )
+    .bind(library_id)
+    .bind(library_id)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
+
+/// Number of items per check status, counting only checks of the current file.
+pub async fn count_by_status(pool: &SqlitePool) -> Result<Vec<(String, i64)>> {
+    let rows: Vec<(String, i64)> = sqlx::query_as(
+        "SELECT ic.status, COUNT(*) FROM media_integrity ic \
+         JOIN media_items mi ON mi.id = ic.media_item_id \
+         WHERE ic.file_size = mi.file_size \
+         GROUP BY ic.status ORDER BY ic.status",
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
@@ -1,14 +1,26 @@
 pub mod chapter_repo;
 pub mod collection_repo;
+pub mod download_repo;
+pub mod extra_repo;
+pub mod franchise_repo;
+pub mod integrity_repo;
 pub mod keyframe_repo;
 pub mod library_repo;
+pub mod livetv_repo;
+pub mod loudness_repo;
 pub mod media_repo;
 pub mod movie_repo;
+pub mod people_repo;
 pub mod preference_repo;
 pub mod progress_repo;
+pub mod queue_repo;
+pub mod stats_repo;
 pub mod stream_repo;
 pub mod subtitle_repo;
+pub mod termination_repo;
+pub mod translation_repo;
 pub mod tv_repo;
+pub mod unmatched_repo;
 pub mod user_repo;
 pub mod webhook_repo;
 
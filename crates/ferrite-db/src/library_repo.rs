@@ -1,21 +1,33 @@
 use anyhow::Result;
 use ferrite_core::media::{Library, LibraryType};
 use sqlx::SqlitePool;
+use std::collections::HashMap;
+use std::path::Path;
 use uuid::Uuid;
 
+/// Changes applied by `update_library`; `None` leaves a field as it is.
+#[derive(Debug, Default)]
+pub struct LibraryUpdate<'a> {
+    pub name: Option<&'a str>,
+    /// Root folders, primary first. Must not be empty.
+    pub paths: Option<&'a [String]>,
+    pub ignore_patterns: Option<&'a [String]>,
+    pub scan_interval_minutes: Option<u32>,
+}
+
 pub async fn create_library(
     pool: &SqlitePool,
     name: &str,
-    path: &str,
+    paths: &[String],
     library_type: LibraryType,
+    ignore_patterns: &[String],
 ) -> Result<Library> {
-    // Reject duplicate paths to prevent scan conflicts.
-    let existing: Option<(String,)> = sqlx::query_as("SELECT id FROM libraries WHERE path = ?")
-        .bind(path)
-        .fetch_optional(pool)
-        .await?;
-    if existing.is_some() {
-        anyhow::bail!("A library with this path already exists");
+    let Some((primary, extra)) = paths.split_first() else {
+        anyhow::bail!("A library needs at least one folder");
+    };
+    // Reject shared or nested folders to prevent scan conflicts.
+    if let Some(path) = find_taken_path(pool, paths, None).await? {
+        anyhow::bail!("'{path}' overlaps a folder of another library");
     }
 
     let id = Uuid::new_v4().to_string();
@@ -24,30 +36,161 @@ pub async fn create_library(
         .unwrap_or("movie")
         .to_string();
 
-    sqlx::query("INSERT INTO libraries (id, name, path, library_type) VALUES (?, ?, ?, ?)")
-        .bind(&id)
-        .bind(name)
-        .bind(path)
-        .bind(&lib_type)
-        .execute(pool)
-        .await?;
+    let mut tx = pool.begin().await?;
+    sqlx::query(
+        "INSERT INTO libraries (id, name, path, library_type, ignore_patterns) \
+         VALUES (?, ?, ?, ?, ?)",
+    )
+    .bind(&id)
+    .bind(name)
+    .bind(primary)
+    .bind(&lib_type)
+    .bind(ignore_patterns.join("\n"))
+    .execute(&mut *tx)
+    .await?;
+    insert_folders(&mut tx, &id, extra).await?;
+    tx.commit().await?;
 
     get_library(pool, &id).await
 }
 
+/// Rename a library or change its folders and scan settings.
+pub async fn update_library(
+    pool: &SqlitePool,
+    id: &str,
+    update: &LibraryUpdate<'_>,
+) -> Result<Library> {
+    let folders = match update.paths {
+        Some(paths) => {
+            let Some(split) = paths.split_first() else {
+                anyhow::bail!("A library needs at least one folder");
+            };
+            if let Some(path) = find_taken_path(pool, paths, Some(id)).await? {
+                anyhow::bail!("'{path}' overlaps a folder of another library");
+            }
+            Some(split)
+        }
+        None => None,
+    };
+
+    let mut tx = pool.begin().await?;
+    if let Some(name) = update.name {
+        sqlx::query("UPDATE libraries SET name = ? WHERE id = ?")
+            .bind(name)
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+    }
+    if let Some((primary, extra)) = folders {
+        sqlx::query("UPDATE libraries SET path = ? WHERE id = ?")
+            .bind(primary)
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("DELETE FROM library_folders WHERE library_id = ?")
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        insert_folders(&mut tx, id, extra).await?;
+    }
+    if let Some(patterns) = update.ignore_patterns {
+        sqlx::query("UPDATE libraries SET ignore_patterns = ? WHERE id = ?")
+            .bind(patterns.join("\n"))
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+    }
+    if let Some(minutes) = update.scan_interval_minutes {
+        sqlx::query("UPDATE libraries SET scan_interval_minutes = ? WHERE id = ?")
+            .bind(minutes as i64)
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+    }
+    tx.commit().await?;
+
+    get_library(pool, id).await
+}
+
+async fn insert_folders(
+    tx: &mut sqlx::Transaction<'_, sqlx::Sqlite>,
+    library_id: &str,
+    paths: &[String],
+) -> Result<()> {
+    for (position, path) in paths.iter().enumerate() {
+        sqlx::query("INSERT INTO library_folders (library_id, path, position) VALUES (?, ?, ?)")
+            .bind(library_id)
+            .bind(path)
+            .bind(position as i64)
+            .execute(&mut **tx)
+            .await?;
+    }
+    Ok(())
+}
+
+/// The first of `paths` that is, contains or sits inside a folder of another
+/// library.
+pub async fn find_taken_path(
+    pool: &SqlitePool,
+    paths: &[String],
+    exclude_library_id: Option<&str>,
+) -> Result<Option<String>> {
+    let exclude = exclude_library_id.unwrap_or("");
+    let taken: Vec<(String,)> = sqlx::query_as(
+        "SELECT path FROM libraries WHERE id != ? \
+         UNION \
+         SELECT path FROM library_folders WHERE library_id != ?",
+    )
+    .bind(exclude)
+    .bind(exclude)
+    .fetch_all(pool)
+    .await?;
+    Ok(paths
+        .iter()
+        .find(|path| {
+            let path = Path::new(path.as_str());
+            taken.iter().any(|(other,)| {
+                let other = Path::new(other.as_str());
+                path.starts_with(other) || other.starts_with(path)
+            })
+        })
+        .cloned())
+}
+
 pub async fn get_library(pool: &SqlitePool, id: &str) -> Result<Library> {
     let row = sqlx::query_as::<_, LibraryRow>("SELECT * FROM libraries WHERE id = ?")
         .bind(id)
         .fetch_one(pool)
         .await?;
-    Ok(row.into())
+    let folders: Vec<(String,)> = sqlx::query_as(
+        "SELECT path FROM library_folders WHERE library_id = ? ORDER BY position, path",
+    )
+    .bind(id)
+    .fetch_all(pool)
+    .await?;
+    Ok(row.into_library(folders.into_iter().map(|(p,)| p).collect()))
 }
 
 pub async fn list_libraries(pool: &SqlitePool) -> Result<Vec<Library>> {
     let rows = sqlx::query_as::<_, LibraryRow>("SELECT * FROM libraries ORDER BY name")
         .fetch_all(pool)
         .await?;
-    Ok(rows.into_iter().map(Into::into).collect())
+    let folders: Vec<(String, String)> = sqlx::query_as(
+        "SELECT library_id, path FROM library_folders ORDER BY library_id, position, path",
+    )
+    .fetch_all(pool)
+    .await?;
+    let mut by_library: HashMap<String, Vec<String>> = HashMap::new();
+    for (library_id, path) in folders {
+        by_library.entry(library_id).or_default().push(path);
+    }
+    Ok(rows
+        .into_iter()
+        .map(|row| {
+            let extra = by_library.remove(&row.id).unwrap_or_default();
+            row.into_library(extra)
+        })
+        .collect())
 }
 
 pub async fn delete_library(pool: &SqlitePool, id: &str) -> Result<()> {
@@ -75,29 +218,46 @@ struct LibraryRow {
     scan_interval_minutes: Option<i64>,
     last_scanned_at: Option<String>,
     created_at: String,
+    ignore_patterns: String,
 }
 
-impl From<LibraryRow> for Library {
-    fn from(row: LibraryRow) -> Self {
-        let library_type = match row.library_type.as_str() {
+impl LibraryRow {
+    /// Build the library from its row and its additional root folders.
+    fn into_library(self, extra_paths: Vec<String>) -> Library {
+        let library_type = match self.library_type.as_str() {
             "tv" => LibraryType::Tv,
             "music" => LibraryType::Music,
             _ => LibraryType::Movie,
         };
+        let paths = std::iter::once(self.path.clone())
+            .chain(extra_paths)
+            .collect();
+        let ignore_patterns = self
+            .ignore_patterns
+            .lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty())
+            .map(String::from)
+            .collect();
         Library {
-            id: Uuid::parse_str(&row.id).unwrap_or_else(|_| Uuid::new_v4()),
-            name: row.name,
-            path: row.path,
+            id: Uuid::parse_str(&self.id).unwrap_or_else(|_| Uuid::new_v4()),
+            name: self.name,
+            path: self.path,
+            paths,
+            ignore_patterns,
             library_type,
-            scan_interval_minutes: row.scan_interval_minutes.unwrap_or(60) as u32,
-            last_scanned_at: row.last_scanned_at.and_then(|s| {
+            scan_interval_minutes: self.scan_interval_minutes.unwrap_or(60) as u32,
+            last_scanned_at: self.last_scanned_at.and_then(|s| {
                 chrono::NaiveDateTime::parse_from_str(&s, "%Y-%m-%d %H:%M:%S")
                     .ok()
                     .map(|dt| dt.and_utc())
             }),
-            created_at: chrono::NaiveDateTime::parse_from_str(&row.created_at, "%Y-%m-%d %H:%M:%S")
-                .map(|dt| dt.and_utc())
-                .unwrap_or_else(|_| chrono::Utc::now()),
+            created_at: chrono::NaiveDateTime::parse_from_str(
+                &self.created_at,
+                "%Y-%m-%d %H:%M:%S",
+            )
+            .map(|dt| dt.and_utc())
+            .unwrap_or_else(|_| chrono::Utc::now()),
         }
     }
 }
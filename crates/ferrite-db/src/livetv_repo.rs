@@ -0,0 +1,363 @@
+use anyhow::Result;
+use sqlx::SqlitePool;
+use std::collections::HashSet;
+use uuid::Uuid;
+
+/// A row from the livetv_channels table.
+#[derive(Debug, Clone, sqlx::FromRow, serde::Serialize)]
+pub struct ChannelRow {
+    pub id: String,
+    #[serde(skip_serializing)]
+    pub source_key: String,
+    pub name: String,
+    pub number: Option<String>,
+    pub logo_url: Option<String>,
+    pub group_title: Option<String>,
+    /// Upstream tuner URL (internal only; clients play through the HLS relay).
+    #[serde(skip_serializing)]
+    pub stream_url: String,
+    pub xmltv_id: Option<String>,
+    pub updated_at: String,
+}
+
+/// A row from the livetv_programs table.
+#[derive(Debug, Clone, sqlx::FromRow, serde::Serialize)]
+pub struct ProgramRow {
+    pub id: i64,
+    pub xmltv_id: String,
+    pub start_time: String,
+    pub end_time: String,
+    pub title: String,
+    pub subtitle: Option<String>,
+    pub description: Option<String>,
+    pub category: Option<String>,
+    pub episode_num: Option<String>,
+}
+
+/// A row from the livetv_recordings table.
+#[derive(Debug, Clone, sqlx::FromRow, serde::Serialize)]
+pub struct RecordingRow {
+    pub id: String,
+    pub user_id: Option<String>,
+    pub channel_id: Option<String>,
+    pub channel_name: String,
+    #[serde(skip_serializing)]
+    pub stream_url: String,
+    pub title: String,
+    pub start_time: String,
+    pub end_time: String,
+    pub status: String,
+    pub file_path: Option<String>,
+    pub error: Option<String>,
+    pub created_at: String,
+}
+
+/// A channel parsed from the tuner playlist, ready to be stored.
+#[derive(Debug, Clone)]
+pub struct NewChannel {
+    pub source_key: String,
+    pub name: String,
+    pub number: Option<String>,
+    pub logo_url: Option<String>,
+    pub group_title: Option<String>,
+    pub stream_url: String,
+    pub xmltv_id: Option<String>,
+}
+
+/// A guide entry parsed from XMLTV, ready to be stored.
+#[derive(Debug, Clone)]
+pub struct NewProgram {
+    pub xmltv_id: String,
+    pub start_time: String,
+    pub end_time: String,
+    pub title: String,
+    pub subtitle: Option<String>,
+    pub description: Option<String>,
+    pub category: Option<String>,
+    pub episode_num: Option<String>,
+}
+
+// ---------------------------------------------------------------------------
+// Channels
+// ---------------------------------------------------------------------------
+
+/// Insert or update the given channels (matched on `source_key`) and delete
+/// channels no longer present in the playlist. Existing channel IDs are kept
+/// so client bookmarks and scheduled recordings stay valid.
+/// Returns `(upserted, removed)`.
+pub async fn sync_channels(pool: &SqlitePool, channels: &[NewChannel]) -> Result<(u64, u64)> {
+    let mut tx = pool.begin().await?;
+
+    let existing: Vec<(String,)> = sqlx::query_as("SELECT source_key FROM livetv_channels")
+        .fetch_all(&mut *tx)
+        .await?;
+
+    let mut seen = HashSet::new();
+    let mut upserted = 0u64;
+    for ch in channels {
+        if !seen.insert(ch.source_key.as_str()) {
+            continue;
+        }
+        sqlx::query(
+            "INSERT INTO livetv_channels \
+               (id, source_key, name, number, logo_url, group_title, stream_url, xmltv_id) \
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?) \
+             ON CONFLICT(source_key) DO UPDATE SET \
+               name = excluded.name, number = excluded.number, logo_url = excluded.logo_url, \
+               group_title = excluded.group_title, stream_url = excluded.stream_url, \
+               xmltv_id = excluded.xmltv_id, updated_at = datetime('now')",
+        )
+        .bind(Uuid::new_v4().to_string())
+        .bind(&ch.source_key)
+        .bind(&ch.name)
+        .bind(&ch.number)
+        .bind(&ch.logo_url)
+        .bind(&ch.group_title)
+        .bind(&ch.stream_url)
+        .bind(&ch.xmltv_id)
+        .execute(&mut *tx)
+        .await?;
+        upserted += 1;
+    }
+
+    let mut removed = 0u64;
+    for (key,) in existing {
+        if !seen.contains(key.as_str()) {
+            removed += sqlx::query("DELETE FROM livetv_channels WHERE source_key = ?")
+                .bind(&key)
+                .execute(&mut *tx)
+                .await?
+                .rows_affected();
+        }
+    }
+
+    tx.commit().await?;
+    Ok((upserted, removed))
+}
+
+/// List all channels ordered by channel number, then name.
+pub async fn list_channels(pool: &SqlitePool) -> Result<Vec<ChannelRow>> {
+    let rows = sqlx::query_as::<_, ChannelRow>(
+        "SELECT * FROM livetv_channels \
+         ORDER BY number IS NULL, CAST(number AS REAL), number, name COLLATE NOCASE",
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
+
+/// Get a single channel by ID.
+pub async fn get_channel(pool: &SqlitePool, id: &str) -> Result<Option<ChannelRow>> {
+    let row = sqlx::query_as::<_, ChannelRow>("SELECT * FROM livetv_channels WHERE id = ?")
+        .bind(id)
+        .fetch_optional(pool)
+        .await?;
+    Ok(row)
+}
+
+// ---------------------------------------------------------------------------
+// Guide
+// ---------------------------------------------------------------------------
+
+/// Replace the whole programme guide with `programs` in a single transaction.
+pub async fn replace_programs(pool: &SqlitePool, programs: &[NewProgram]) -> Result<u64> {
+    let mut tx = pool.begin().await?;
+    sqlx::query("DELETE FROM livetv_programs")
+        .execute(&mut *tx)
+        .await?;
+    for p in programs {
+        sqlx::query(
+            "INSERT INTO livetv_programs \
+               (xmltv_id, start_time, end_time, title, subtitle, description, category, episode_num) \
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(&p.xmltv_id)
+        .bind(&p.start_time)
+        .bind(&p.end_time)
+        .bind(&p.title)
+        .bind(&p.subtitle)
+        .bind(&p.description)
+        .bind(&p.category)
+        .bind(&p.episode_num)
+        .execute(&mut *tx)
+        .await?;
+    }
+    tx.commit().await?;
+    Ok(programs.len() as u64)
+}
+
+/// Programmes on one guide channel that overlap `[from, to)`.
+pub async fn list_channel_programs(
+    pool: &SqlitePool,
+    xmltv_id: &str,
+    from: &str,
+    to: &str,
+) -> Result<Vec<ProgramRow>> {
+    let rows = sqlx::query_as::<_, ProgramRow>(
+        "SELECT * FROM livetv_programs \
+         WHERE xmltv_id = ? AND end_time > ? AND start_time < ? \
+         ORDER BY start_time",
+    )
+    .bind(xmltv_id)
+    .bind(from)
+    .bind(to)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
+
+/// Programmes on any imported channel that overlap `[from, to)`.
+pub async fn list_programs_in_window(
+    pool: &SqlitePool,
+    from: &str,
+    to: &str,
+) -> Result<Vec<ProgramRow>> {
+    let rows = sqlx::query_as::<_, ProgramRow>(
+        "SELECT p.* FROM livetv_programs p \
+         WHERE p.end_time > ? AND p.start_time < ? \
+           AND EXISTS (SELECT 1 FROM livetv_channels c WHERE c.xmltv_id = p.xmltv_id) \
+         ORDER BY p.xmltv_id, p.start_time",
+    )
+    .bind(from)
+    .bind(to)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
+
+/// Get a single programme by ID.
+pub async fn get_program(pool: &SqlitePool, id: i64) -> Result<Option<ProgramRow>> {
+    let row = sqlx::query_as::<_, ProgramRow>("SELECT * FROM livetv_programs WHERE id = ?")
+        .bind(id)
+        .fetch_optional(pool)
+        .await?;
+    Ok(row)
+}
+
+// ---------------------------------------------------------------------------
+// Recordings
+// ---------------------------------------------------------------------------
+
+/// Schedule a recording. Returns the inserted row.
+#[allow(clippy::too_many_arguments)]
+pub async fn create_recording(
+    pool: &SqlitePool,
+    user_id: Option<&str>,
+    channel_id: &str,
+    channel_name: &str,
+    stream_url: &str,
+    title: &str,
+    start_time: &str,
+    end_time: &str,
+) -> Result<RecordingRow> {
+    let id = Uuid::new_v4().to_string();
+    let row = sqlx::query_as::<_, RecordingRow>(
+        "INSERT INTO livetv_recordings \
+           (id, user_id, channel_id, channel_name, stream_url, title, start_time, end_time) \
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?) RETURNING *",
+    )
+    .bind(&id)
+    .bind(user_id)
+    .bind(channel_id)
+    .bind(channel_name)
+    .bind(stream_url)
+    .bind(title)
+    .bind(start_time)
+    .bind(end_time)
+    .fetch_one(pool)
+    .await?;
+    Ok(row)
+}
+
+/// Get a single recording by ID.
+pub async fn get_recording(pool: &SqlitePool, id: &str) -> Result<Option<RecordingRow>> {
+    let row = sqlx::query_as::<_, RecordingRow>("SELECT * FROM livetv_recordings WHERE id = ?")
+        .bind(id)
+        .fetch_optional(pool)
+        .await?;
+    Ok(row)
+}
+
+/// List recordings owned by a user (or the anonymous owner when `user_id` is `None`).
+pub async fn list_recordings(
+    pool: &SqlitePool,
+    user_id: Option<&str>,
+) -> Result<Vec<RecordingRow>> {
+    let rows = sqlx::query_as::<_, RecordingRow>(
+        "SELECT * FROM livetv_recordings WHERE user_id IS ? ORDER BY start_time DESC",
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
+
+/// Atomically claim scheduled recordings whose window is open now and mark
+/// them as recording.
+pub async fn claim_due_recordings(pool: &SqlitePool) -> Result<Vec<RecordingRow>> {
+    let rows = sqlx::query_as::<_, RecordingRow>(
+        "UPDATE livetv_recordings SET status = 'recording', error = NULL \
+         WHERE status = 'scheduled' AND start_time <= datetime('now') AND end_time > datetime('now') \
+         RETURNING *",
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
+
+/// Fail scheduled recordings whose window passed without them starting
+/// (e.g. the server was down).
+pub async fn fail_missed_recordings(pool: &SqlitePool) -> Result<u64> {
+    let result = sqlx::query(
+        "UPDATE livetv_recordings SET status = 'failed', error = 'Missed: server was not running' \
+         WHERE status = 'scheduled' AND end_time <= datetime('now')",
+    )
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected())
+}
+
+/// Put recordings interrupted by a restart back in the schedule so the
+/// remainder of their window is captured.
+pub async fn requeue_interrupted(pool: &SqlitePool) -> Result<u64> {
+    let result =
+        sqlx::query("UPDATE livetv_recordings SET status = 'scheduled' WHERE status = 'recording'")
+            .execute(pool)
+            .await?;
+    Ok(result.rows_affected())
+}
+
+/// Record the output file of a recording that just started.
+pub async fn set_recording_file(pool: &SqlitePool, id: &str, file_path: &str) -> Result<()> {
+    sqlx::query("UPDATE livetv_recordings SET file_path = ? WHERE id = ?")
+        .bind(file_path)
+        .bind(id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// Set the final status of a recording (`completed`, `failed` or `cancelled`).
+pub async fn finish_recording(
+    pool: &SqlitePool,
+    id: &str,
+    status: &str,
+    error: Option<&str>,
+) -> Result<()> {
+    sqlx::query("UPDATE livetv_recordings SET status = ?, error = ? WHERE id = ?")
+        .bind(status)
+        .bind(error)
+        .bind(id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// Delete a recording row. The recorded file (if any) is left in place.
+pub async fn delete_recording(pool: &SqlitePool, id: &str) -> Result<bool> {
+    let result = sqlx::query("DELETE FROM livetv_recordings WHERE id = ?")
+        .bind(id)
+        .execute(pool)
+        .await?;
+    Ok(result.rows_affected() > 0)
+}
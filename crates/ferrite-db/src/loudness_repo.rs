@@ -0,0 +1,73 @@
+use anyhow::Result;
+use sqlx::SqlitePool;
+
+/// Cached loudness analysis of one audio stream.
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct LoudnessRow {
+    pub media_item_id: String,
+    pub audio_stream_index: i64,
+    pub input_i: f64,
+    pub input_tp: f64,
+    pub input_lra: f64,
+    pub input_thresh: f64,
+    pub target_offset: f64,
+    pub file_size: i64,
+    pub analyzed_at: String,
+}
+
+/// Fetch the analysis of an audio stream, if it was measured for a file of
+/// `file_size` bytes.
+pub async fn get_loudness(
+    pool: &SqlitePool,
+    media_item_id: &str,
+    audio_stream_index: i64,
+    file_size: i64,
+) -> Result<Option<LoudnessRow>> {
+    let row = sqlx::query_as::<_, LoudnessRow>(
+        "SELECT * FROM audio_loudness \
+         WHERE media_item_id = ? AND audio_stream_index = ? AND file_size = ?",
+    )
+    .bind(media_item_id)
+    .bind(audio_stream_index)
+    .bind(file_size)
+    .fetch_optional(pool)
+    .await?;
+    Ok(row)
+}
+
+/// Store (or replace) the analysis of an audio stream.
+#[allow(clippy::too_many_arguments)]
+pub async fn save_loudness(
+    pool: &SqlitePool,
+    media_item_id: &str,
+    audio_stream_index: i64,
+    input_i: f64,
+    input_tp: f64,
+    input_lra: f64,
+    input_thresh: f64,
+    target_offset: f64,
+    file_size: i64,
+) -> Result<()> {
+    sqlx::query(
+        "INSERT INTO audio_loudness \
+         (media_item_id, audio_stream_index, input_i, input_tp, input_lra, input_thresh, \
+          target_offset, file_size) \
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?) \
+         ON CONFLICT(media_item_id, audio_stream_index) DO UPDATE SET \
+           input_i = excluded.input_i, input_tp = excluded.input_tp, \
+           input_lra = excluded.input_lra, input_thresh = excluded.input_thresh, \
+           target_offset = excluded.target_offset, file_size = excluded.file_size, \
+           analyzed_at = datetime('now')",
+    )
+    .bind(media_item_id)
+    .bind(audio_stream_index)
+    .bind(input_i)
+    .bind(input_tp)
+    .bind(input_lra)
+    .bind(input_thresh)
+    .bind(target_offset)
+    .bind(file_size)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
@@ -21,6 +21,7 @@ pub async fn insert_media_item(
     media_type: &str,
     file_path: &str,
     file_size: u64,
+    file_hash: Option<&str>,
     title: Option<&str>,
     year: Option<i32>,
     probe: Option<&MediaProbeData>,
@@ -33,11 +34,12 @@ pub async fn insert_media_item(
     // On INSERT the new id is returned; on conflict the existing row's id is returned
     // because the DO UPDATE triggers RETURNING on the updated row.
     let actual_id: (String,) = sqlx::query_as(
-        r#"INSERT INTO media_items (id, library_id, media_type, file_path, file_size, title, year,
+        r#"INSERT INTO media_items (id, library_id, media_type, file_path, file_size, file_hash, title, year,
              container_format, video_codec, audio_codec, width, height, duration_ms, bitrate_kbps)
-           VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+           VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
            ON CONFLICT(file_path) DO UPDATE SET
              file_size = excluded.file_size,
+             file_hash = excluded.file_hash,
              title = excluded.title,
              year = excluded.year,
              container_format = excluded.container_format,
@@ -47,6 +49,8 @@ pub async fn insert_media_item(
              height = excluded.height,
              duration_ms = excluded.duration_ms,
              bitrate_kbps = excluded.bitrate_kbps,
+             subtitles_pending = 0,
+             needs_reprobe = 0,
              updated_at = datetime('now')
            RETURNING id"#,
     )
@@ -55,6 +59,7 @@ pub async fn insert_media_item(
     .bind(media_type)
     .bind(file_path)
     .bind(file_size as i64)
+    .bind(file_hash)
     .bind(title)
     .bind(year)
     .bind(&p.container_format)
@@ -70,18 +75,110 @@ pub async fn insert_media_item(
     Ok(actual_id.0)
 }
 
-/// Load all (file_path, file_size) pairs for a library for delta scan.
-/// Used to skip re-probing files that haven't changed since the last scan.
-pub async fn get_all_file_sizes(
+/// Path, size and content hash of an indexed file, used to detect moves.
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct MediaFileIdentity {
+    pub id: String,
+    pub file_path: String,
+    pub file_size: i64,
+    pub file_hash: Option<String>,
+    /// Indexed by a cancelled scan before its subtitles were extracted
+    pub subtitles_pending: bool,
+    /// Streams should be probed again, without re-indexing the item
+    pub needs_reprobe: bool,
+}
+
+/// Load the file identity of every item in a library.
+pub async fn list_file_identities(
     pool: &SqlitePool,
     library_id: &str,
-) -> Result<std::collections::HashMap<String, u64>> {
-    let rows: Vec<(String, i64)> =
-        sqlx::query_as("SELECT file_path, file_size FROM media_items WHERE library_id = ?")
-            .bind(library_id)
-            .fetch_all(pool)
-            .await?;
-    Ok(rows.into_iter().map(|(p, s)| (p, s as u64)).collect())
+) -> Result<Vec<MediaFileIdentity>> {
+    let rows = sqlx::query_as::<_, MediaFileIdentity>(
+        "SELECT id, file_path, file_size, file_hash, subtitles_pending, needs_reprobe FROM media_items WHERE library_id = ?",
+    )
+    .bind(library_id)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
+
+/// Load the file identity of items at `path_prefix` or anywhere beneath it.
+pub async fn list_file_identities_by_path_prefix(
+    pool: &SqlitePool,
+    path_prefix: &str,
+) -> Result<Vec<MediaFileIdentity>> {
+    let (normalized, like_backslash, like_slash) = path_prefix_patterns(path_prefix);
+    let rows = sqlx::query_as::<_, MediaFileIdentity>(
+        "SELECT id, file_path, file_size, file_hash, subtitles_pending, needs_reprobe FROM media_items \
+         WHERE file_path = ? OR file_path LIKE ? ESCAPE '\\' OR file_path LIKE ? ESCAPE '\\'",
+    )
+    .bind(normalized)
+    .bind(like_backslash)
+    .bind(like_slash)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
+
+/// Clear the re-probe flag of an item once its streams were refreshed.
+pub async fn clear_needs_reprobe(
+    executor: &mut SqliteConnection,
+    media_item_id: &str,
+) -> Result<()> {
+    sqlx::query("UPDATE media_items SET needs_reprobe = 0 WHERE id = ?")
+        .bind(media_item_id)
+        .execute(executor)
+        .await?;
+    Ok(())
+}
+
+/// Flag items whose subtitle extraction was cut short by a cancelled scan so
+/// the next scan indexes them again.
+pub async fn mark_subtitles_pending(pool: &SqlitePool, media_item_ids: &[String]) -> Result<u64> {
+    let mut tx = pool.begin().await?;
+    let mut marked = 0;
+    for id in media_item_ids {
+        marked += sqlx::query("UPDATE media_items SET subtitles_pending = 1 WHERE id = ?")
+            .bind(id)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+    }
+    tx.commit().await?;
+    Ok(marked)
+}
+
+/// Store the content hash of an already indexed file.
+pub async fn set_file_hash(
+    executor: &mut SqliteConnection,
+    file_path: &str,
+    file_hash: &str,
+) -> Result<()> {
+    sqlx::query("UPDATE media_items SET file_hash = ? WHERE file_path = ?")
+        .bind(file_hash)
+        .bind(file_path)
+        .execute(executor)
+        .await?;
+    Ok(())
+}
+
+/// Point an existing item at the new location of its (moved or renamed)
+/// file, keeping its ID and everything attached to it.
+pub async fn relocate_media_item(
+    executor: &mut SqliteConnection,
+    id: &str,
+    new_file_path: &str,
+    file_size: u64,
+) -> Result<()> {
+    sqlx::query(
+        "UPDATE media_items SET file_path = ?, file_size = ?, updated_at = datetime('now') WHERE id = ?",
+    )
+    .bind(new_file_path)
+    .bind(file_size as i64)
+    .bind(id)
+    .execute(executor)
+    .await?;
+    Ok(())
 }
 
 /// Look up a media item's ID by its file path. Used after a batched insert to
@@ -105,7 +202,7 @@ pub async fn get_media_item(pool: &SqlitePool, id: &str) -> Result<Option<MediaI
                   s.season_number,
                   ts.title AS show_title
            FROM media_items mi
-           LEFT JOIN episodes e ON e.media_item_id = mi.id
+           LEFT JOIN episodes e ON e.media_item_id = mi.id AND e.file_position = 0
            LEFT JOIN seasons s ON s.id = e.season_id
            LEFT JOIN tv_shows ts ON ts.id = s.tv_show_id
            WHERE mi.id = ?"#,
@@ -116,6 +213,46 @@ pub async fn get_media_item(pool: &SqlitePool, id: &str) -> Result<Option<MediaI
     Ok(row)
 }
 
+/// List the playable versions of a movie group — stack heads and single files,
+/// primary first. Returns an empty list for media that isn't grouped.
+pub async fn list_movie_versions(pool: &SqlitePool, group_id: &str) -> Result<Vec<MediaItemRow>> {
+    let rows = sqlx::query_as::<_, MediaItemRow>(
+        r#"SELECT mi.*,
+                  NULL AS episode_number,
+                  NULL AS episode_title,
+                  NULL AS season_number,
+                  NULL AS show_title
+           FROM media_items mi
+           WHERE mi.movie_group_id = ? AND mi.stack_head_id IS NULL
+           ORDER BY mi.id = mi.movie_group_id DESC, mi.added_at ASC, mi.id ASC"#,
+    )
+    .bind(group_id)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
+
+/// List every part of a stack in play order, starting with the head itself.
+/// A file that isn't stacked yields a single-element list.
+pub async fn list_stack_parts(pool: &SqlitePool, head_id: &str) -> Result<Vec<MediaItemRow>> {
+    let rows = sqlx::query_as::<_, MediaItemRow>(
+        r#"SELECT mi.*,
+                  NULL AS episode_number,
+                  NULL AS episode_title,
+                  NULL AS season_number,
+                  NULL AS show_title
+           FROM media_items mi
+           WHERE mi.id = ? OR mi.stack_head_id = ?
+           ORDER BY mi.stack_head_id IS NOT NULL, COALESCE(mi.part_number, 0) ASC, mi.file_path ASC"#,
+    )
+    .bind(head_id)
+    .bind(head_id)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
+
+/// List indexed media, main content only (extras are listed with their parent).
 pub async fn list_media_items(
     pool: &SqlitePool,
     library_id: Option<&str>,
@@ -132,10 +269,10 @@ pub async fn list_media_items(
                       s.season_number,
                       ts.title AS show_title
                FROM media_items mi
-               LEFT JOIN episodes e ON e.media_item_id = mi.id
+               LEFT JOIN episodes e ON e.media_item_id = mi.id AND e.file_position = 0
                LEFT JOIN seasons s ON s.id = e.season_id
                LEFT JOIN tv_shows ts ON ts.id = s.tv_show_id
-               WHERE mi.library_id = ?
+               WHERE mi.library_id = ? AND mi.extra_type IS NULL
                ORDER BY mi.title ASC, mi.file_path ASC
                LIMIT ? OFFSET ?"#,
         )
@@ -152,9 +289,10 @@ pub async fn list_media_items(
                       s.season_number,
                       ts.title AS show_title
                FROM media_items mi
-               LEFT JOIN episodes e ON e.media_item_id = mi.id
+               LEFT JOIN episodes e ON e.media_item_id = mi.id AND e.file_position = 0
                LEFT JOIN seasons s ON s.id = e.season_id
                LEFT JOIN tv_shows ts ON ts.id = s.tv_show_id
+               WHERE mi.extra_type IS NULL
                ORDER BY mi.title ASC, mi.file_path ASC
                LIMIT ? OFFSET ?"#,
         )
@@ -169,12 +307,14 @@ pub async fn list_media_items(
 
 pub async fn count_media_items(pool: &SqlitePool, library_id: Option<&str>) -> Result<i64> {
     let count: (i64,) = if let Some(lib_id) = library_id {
-        sqlx::query_as("SELECT COUNT(*) FROM media_items WHERE library_id = ?")
-            .bind(lib_id)
-            .fetch_one(pool)
-            .await?
+        sqlx::query_as(
+            "SELECT COUNT(*) FROM media_items WHERE library_id = ? AND extra_type IS NULL",
+        )
+        .bind(lib_id)
+        .fetch_one(pool)
+        .await?
     } else {
-        sqlx::query_as("SELECT COUNT(*) FROM media_items")
+        sqlx::query_as("SELECT COUNT(*) FROM media_items WHERE extra_type IS NULL")
             .fetch_one(pool)
             .await?
     };
@@ -213,13 +353,7 @@ pub async fn delete_media_items_by_path_prefix(
     pool: &SqlitePool,
     path_prefix: &str,
 ) -> Result<u64> {
-    let normalized = path_prefix.trim_end_matches(['\\', '/']);
-    let escaped = normalized
-        .replace('\\', "\\\\")
-        .replace('%', "\\%")
-        .replace('_', "\\_");
-    let like_backslash = format!("{}\\\\%", escaped);
-    let like_slash = format!("{}/%", escaped);
+    let (normalized, like_backslash, like_slash) = path_prefix_patterns(path_prefix);
 
     let result = sqlx::query(
         "DELETE FROM media_items WHERE file_path = ? OR file_path LIKE ? ESCAPE '\\' OR file_path LIKE ? ESCAPE '\\'",
@@ -233,6 +367,19 @@ pub async fn delete_media_items_by_path_prefix(
     Ok(result.rows_affected())
 }
 
+/// Exact path plus escaped LIKE patterns matching anything beneath it with
+/// either path separator.
+fn path_prefix_patterns(path_prefix: &str) -> (&str, String, String) {
+    let normalized = path_prefix.trim_end_matches(['\\', '/']);
+    let escaped = normalized
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_");
+    let like_backslash = format!("{}\\\\%", escaped);
+    let like_slash = format!("{}/%", escaped);
+    (normalized, like_backslash, like_slash)
+}
+
 #[derive(Debug, Clone, sqlx::FromRow, serde::Serialize)]
 pub struct MediaItemRow {
     pub id: String,
@@ -252,6 +399,16 @@ pub struct MediaItemRow {
     pub year: Option<i64>,
     pub added_at: String,
     pub updated_at: String,
+    /// Primary version of the movie group this file belongs to (null when ungrouped)
+    pub movie_group_id: Option<String>,
+    /// Edition / quality label parsed from the filename
+    pub version_label: Option<String>,
+    /// Part number for stacked multi-part files
+    pub part_number: Option<i64>,
+    /// First part of the stack this file continues (null for heads and single files)
+    pub stack_head_id: Option<String>,
+    /// Kind of extra (`trailer`, `featurette`, ...); null for main content
+    pub extra_type: Option<String>,
     /// Episode number (null for non-episodes)
     pub episode_number: Option<i64>,
     /// Episode title from the episodes table (null for non-episodes)
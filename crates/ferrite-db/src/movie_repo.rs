@@ -1,5 +1,7 @@
 use anyhow::Result;
-use sqlx::{SqliteConnection, SqlitePool};
+use sqlx::query::QueryAs;
+use sqlx::sqlite::SqliteArguments;
+use sqlx::{Sqlite, SqliteConnection, SqlitePool};
 use tracing::warn;
 
 /// Row for the movies table joined with media_items.
@@ -59,6 +61,81 @@ pub struct MovieNeedingMetadata {
     pub year: Option<i64>,
 }
 
+/// A movie-library file with its current version/stack assignment.
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct MovieFileRow {
+    pub id: String,
+    pub file_path: String,
+    pub added_at: String,
+    pub movie_group_id: Option<String>,
+    pub version_label: Option<String>,
+    pub part_number: Option<i64>,
+    pub stack_head_id: Option<String>,
+}
+
+/// Version/stack assignment computed by the scanner's grouping pass.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MovieGroupAssignment {
+    pub media_item_id: String,
+    pub movie_group_id: Option<String>,
+    pub version_label: Option<String>,
+    pub part_number: Option<i64>,
+    pub stack_head_id: Option<String>,
+}
+
+/// List every movie file in a library with its grouping columns. Extras are
+/// left out; they never join a version group.
+pub async fn list_movie_files(pool: &SqlitePool, library_id: &str) -> Result<Vec<MovieFileRow>> {
+    let rows = sqlx::query_as::<_, MovieFileRow>(
+        r#"
+        SELECT id, file_path, added_at, movie_group_id, version_label, part_number, stack_head_id
+        FROM media_items
+        WHERE library_id = ? AND media_type = 'movie'
+          AND extra_type IS NULL
+        "#,
+    )
+    .bind(library_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows)
+}
+
+/// Write version/stack assignments in a single transaction.
+/// Rows whose columns already match are left untouched; returns how many changed.
+pub async fn apply_movie_groups(
+    pool: &SqlitePool,
+    assignments: &[MovieGroupAssignment],
+) -> Result<u64> {
+    let mut tx = pool.begin().await?;
+    let mut changed = 0u64;
+    for a in assignments {
+        let result = sqlx::query(
+            r#"
+            UPDATE media_items
+            SET movie_group_id = ?, version_label = ?, part_number = ?, stack_head_id = ?
+            WHERE id = ?
+              AND (movie_group_id IS NOT ? OR version_label IS NOT ?
+                   OR part_number IS NOT ? OR stack_head_id IS NOT ?)
+            "#,
+        )
+        .bind(&a.movie_group_id)
+        .bind(&a.version_label)
+        .bind(a.part_number)
+        .bind(&a.stack_head_id)
+        .bind(&a.media_item_id)
+        .bind(&a.movie_group_id)
+        .bind(&a.version_label)
+        .bind(a.part_number)
+        .bind(&a.stack_head_id)
+        .execute(&mut *tx)
+        .await?;
+        changed += result.rows_affected();
+    }
+    tx.commit().await?;
+    Ok(changed)
+}
+
 /// Insert a skeleton movie row (from filename parsing).
 /// Uses INSERT OR IGNORE so it will NOT overwrite existing metadata.
 /// Accepts `&mut SqliteConnection` so it can run inside a transaction.
@@ -173,7 +250,7 @@ pub async fn get_movie_with_media(
         FROM media_items mi
         LEFT JOIN movies m ON m.media_item_id = mi.id
         LEFT JOIN playback_progress pp ON pp.media_item_id = mi.id AND pp.user_id IS ?
-        LEFT JOIN episodes ep ON ep.media_item_id = mi.id
+        LEFT JOIN episodes ep ON ep.media_item_id = mi.id AND ep.file_position = 0
         LEFT JOIN seasons s ON s.id = ep.season_id
         LEFT JOIN tv_shows ts ON ts.id = s.tv_show_id
         WHERE mi.id = ?
@@ -197,6 +274,60 @@ pub struct MediaQuery<'a> {
     pub sort_dir: Option<&'a str>,
     pub page: i64,
     pub per_page: i64,
+    /// Inclusive release year bounds
+    pub year_min: Option<i64>,
+    pub year_max: Option<i64>,
+    pub min_rating: Option<f64>,
+    /// Video width bounds: `min_width <= width < max_width`
+    pub min_width: Option<i64>,
+    pub max_width: Option<i64>,
+    /// Video codec name as probed, compared case-insensitively (e.g. "hevc")
+    pub video_codec: Option<&'a str>,
+    /// Only items added in the last N days
+    pub added_within_days: Option<i64>,
+    /// Exclude items this user has finished watching
+    pub unwatched_by: Option<&'a str>,
+    /// Only movies in this franchise
+    pub franchise_id: Option<&'a str>,
+}
+
+/// Filters shared by every listing/count query, appended after the
+/// library/search/genre checks. Bound by [`bind_extra_filters`].
+const EXTRA_FILTERS: &str = r#"
+          AND (? IS NULL OR COALESCE(m.year, mi.year) >= ?)
+          AND (? IS NULL OR COALESCE(m.year, mi.year) <= ?)
+          AND (? IS NULL OR m.rating >= ?)
+          AND (? IS NULL OR mi.width >= ?)
+          AND (? IS NULL OR mi.width < ?)
+          AND (? IS NULL OR LOWER(mi.video_codec) = LOWER(?))
+          AND (? IS NULL OR mi.added_at >= datetime('now', '-' || ? || ' days'))
+          AND (? IS NULL OR NOT EXISTS (
+                SELECT 1 FROM playback_progress w
+                WHERE w.media_item_id = mi.id AND w.user_id = ? AND w.completed = 1))
+          AND (? IS NULL OR m.franchise_id = ?)"#;
+
+fn bind_extra_filters<'q, O>(
+    q: QueryAs<'q, Sqlite, O, SqliteArguments<'q>>,
+    query: &MediaQuery<'q>,
+) -> QueryAs<'q, Sqlite, O, SqliteArguments<'q>> {
+    q.bind(query.year_min)
+        .bind(query.year_min)
+        .bind(query.year_max)
+        .bind(query.year_max)
+        .bind(query.min_rating)
+        .bind(query.min_rating)
+        .bind(query.min_width)
+        .bind(query.min_width)
+        .bind(query.max_width)
+        .bind(query.max_width)
+        .bind(query.video_codec)
+        .bind(query.video_codec)
+        .bind(query.added_within_days)
+        .bind(query.added_within_days)
+        .bind(query.unwatched_by)
+        .bind(query.unwatched_by)
+        .bind(query.franchise_id)
+        .bind(query.franchise_id)
 }
 
 /// List movies joined with media_items, with search, filter, sort, and pagination.
@@ -247,24 +378,27 @@ pub async fn count_movies_with_media(pool: &SqlitePool, query: &MediaQuery<'_>)
         }
     }
 
-    let row: (i64,) = sqlx::query_as(
+    let sql = format!(
         r#"
         SELECT COUNT(*)
         FROM media_items mi
         LEFT JOIN movies m ON m.media_item_id = mi.id
         WHERE (? IS NULL OR mi.library_id = ?)
+          AND (mi.movie_group_id IS NULL OR mi.movie_group_id = mi.id)
+          AND mi.extra_type IS NULL
           AND (? IS NULL OR COALESCE(m.title, mi.title) LIKE '%' || ? || '%')
           AND (? IS NULL OR m.genres LIKE '%' || ? || '%')
-        "#,
-    )
-    .bind(query.library_id)
-    .bind(query.library_id)
-    .bind(query.search)
-    .bind(query.search)
-    .bind(query.genre)
-    .bind(query.genre)
-    .fetch_one(pool)
-    .await?;
+          {EXTRA_FILTERS}
+        "#
+    );
+    let q = sqlx::query_as::<_, (i64,)>(&sql)
+        .bind(query.library_id)
+        .bind(query.library_id)
+        .bind(query.search)
+        .bind(query.search)
+        .bind(query.genre)
+        .bind(query.genre);
+    let row = bind_extra_filters(q, query).fetch_one(pool).await?;
     Ok(row.0)
 }
 
@@ -302,25 +436,29 @@ async fn list_movies_with_media_like(
         FROM media_items mi
         LEFT JOIN movies m ON m.media_item_id = mi.id
         LEFT JOIN playback_progress pp ON pp.media_item_id = mi.id AND pp.user_id IS ?
-        LEFT JOIN episodes ep ON ep.media_item_id = mi.id
+        LEFT JOIN episodes ep ON ep.media_item_id = mi.id AND ep.file_position = 0
         LEFT JOIN seasons s ON s.id = ep.season_id
         LEFT JOIN tv_shows ts ON ts.id = s.tv_show_id
         WHERE (? IS NULL OR mi.library_id = ?)
+          AND (mi.movie_group_id IS NULL OR mi.movie_group_id = mi.id)
+          AND mi.extra_type IS NULL
           AND (? IS NULL OR COALESCE(m.title, mi.title) LIKE '%' || ? || '%')
           AND (? IS NULL OR m.genres LIKE '%' || ? || '%')
+          {EXTRA_FILTERS}
         {order_clause}
         LIMIT ? OFFSET ?
         "#
     );
 
-    let rows = sqlx::query_as::<_, MovieWithMediaRow>(&sql)
+    let q = sqlx::query_as::<_, MovieWithMediaRow>(&sql)
         .bind(user_id)
         .bind(query.library_id)
         .bind(query.library_id)
         .bind(query.search)
         .bind(query.search)
         .bind(query.genre)
-        .bind(query.genre)
+        .bind(query.genre);
+    let rows = bind_extra_filters(q, query)
         .bind(query.per_page)
         .bind(offset)
         .fetch_all(pool)
@@ -369,24 +507,28 @@ async fn list_movies_with_media_fts(
         FROM media_items mi
         LEFT JOIN movies m ON m.media_item_id = mi.id
         LEFT JOIN playback_progress pp ON pp.media_item_id = mi.id AND pp.user_id IS ?
-        LEFT JOIN episodes ep ON ep.media_item_id = mi.id
+        LEFT JOIN episodes ep ON ep.media_item_id = mi.id AND ep.file_position = 0
         LEFT JOIN seasons s ON s.id = ep.season_id
         LEFT JOIN tv_shows ts ON ts.id = s.tv_show_id
         JOIN media_fts ON media_fts.media_item_id = mi.id
         WHERE (? IS NULL OR mi.library_id = ?)
+          AND (mi.movie_group_id IS NULL OR mi.movie_group_id = mi.id)
+          AND mi.extra_type IS NULL
           AND (? IS NULL OR m.genres LIKE '%' || ? || '%')
+          {EXTRA_FILTERS}
           AND media_fts MATCH ?
         {order_clause}
         LIMIT ? OFFSET ?
         "#
     );
 
-    sqlx::query_as::<_, MovieWithMediaRow>(&sql)
+    let q = sqlx::query_as::<_, MovieWithMediaRow>(&sql)
         .bind(user_id)
         .bind(query.library_id)
         .bind(query.library_id)
         .bind(query.genre)
-        .bind(query.genre)
+        .bind(query.genre);
+    bind_extra_filters(q, query)
         .bind(fts_query)
         .bind(query.per_page)
         .bind(offset)
@@ -399,24 +541,29 @@ async fn count_movies_with_media_fts(
     query: &MediaQuery<'_>,
     fts_query: &str,
 ) -> std::result::Result<i64, sqlx::Error> {
-    let row: (i64,) = sqlx::query_as(
+    let sql = format!(
         r#"
         SELECT COUNT(DISTINCT mi.id)
         FROM media_items mi
         LEFT JOIN movies m ON m.media_item_id = mi.id
         JOIN media_fts ON media_fts.media_item_id = mi.id
         WHERE (? IS NULL OR mi.library_id = ?)
+          AND (mi.movie_group_id IS NULL OR mi.movie_group_id = mi.id)
+          AND mi.extra_type IS NULL
           AND (? IS NULL OR m.genres LIKE '%' || ? || '%')
+          {EXTRA_FILTERS}
           AND media_fts MATCH ?
-        "#,
-    )
-    .bind(query.library_id)
-    .bind(query.library_id)
-    .bind(query.genre)
-    .bind(query.genre)
-    .bind(fts_query)
-    .fetch_one(pool)
-    .await?;
+        "#
+    );
+    let q = sqlx::query_as::<_, (i64,)>(&sql)
+        .bind(query.library_id)
+        .bind(query.library_id)
+        .bind(query.genre)
+        .bind(query.genre);
+    let row = bind_extra_filters(q, query)
+        .bind(fts_query)
+        .fetch_one(pool)
+        .await?;
 
     Ok(row.0)
 }
@@ -485,7 +632,11 @@ pub async fn get_movies_needing_metadata(
         SELECT media_item_id, title, year
         FROM movies
         WHERE fetched_at IS NULL
-          AND media_item_id IN (SELECT id FROM media_items WHERE library_id = ?)
+          AND media_item_id IN (
+              SELECT id FROM media_items
+              WHERE library_id = ?
+                AND (movie_group_id IS NULL OR movie_group_id = id)
+          )
         "#,
     )
     .bind(library_id)
@@ -494,3 +645,13 @@ pub async fn get_movies_needing_metadata(
 
     Ok(rows)
 }
+
+/// Record that a TMDB search found no match for a movie, so it is listed
+/// among the unmatched items.
+pub async fn mark_movie_match_failed(pool: &SqlitePool, media_item_id: &str) -> Result<()> {
+    sqlx::query("UPDATE movies SET match_failed_at = datetime('now') WHERE media_item_id = ?")
+        .bind(media_item_id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
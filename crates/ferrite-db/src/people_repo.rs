@@ -0,0 +1,198 @@
+use anyhow::Result;
+use sqlx::SqlitePool;
+use uuid::Uuid;
+
+/// A row from the people table.
+#[derive(Debug, Clone, sqlx::FromRow, serde::Serialize)]
+pub struct PersonRow {
+    pub id: String,
+    pub tmdb_id: Option<i64>,
+    pub name: String,
+    pub profile_path: Option<String>,
+    pub updated_at: String,
+}
+
+/// A credit on a movie or show, joined with the person it belongs to.
+#[derive(Debug, Clone, sqlx::FromRow, serde::Serialize)]
+pub struct CreditRow {
+    pub person_id: String,
+    pub name: String,
+    pub profile_path: Option<String>,
+    pub credit_type: String,
+    pub role: Option<String>,
+    pub department: Option<String>,
+    pub character: Option<String>,
+    pub sort_order: i64,
+}
+
+/// One entry in a person's filmography, limited to titles in the library.
+#[derive(Debug, Clone, sqlx::FromRow, serde::Serialize)]
+pub struct PersonCreditRow {
+    /// "movie" (id is a media item id) or "show" (id is a tv_show id)
+    pub media_kind: String,
+    pub id: String,
+    pub title: Option<String>,
+    pub year: Option<i64>,
+    pub poster_path: Option<String>,
+    pub credit_type: String,
+    pub role: Option<String>,
+    pub character: Option<String>,
+}
+
+/// A credit fetched from a metadata provider, ready to be stored.
+#[derive(Debug, Clone)]
+pub struct NewCredit {
+    pub person_tmdb_id: i64,
+    pub name: String,
+    /// Local filename in the image cache
+    pub profile_path: Option<String>,
+    /// "cast" or "crew"
+    pub credit_type: String,
+    pub role: Option<String>,
+    pub department: Option<String>,
+    pub character: Option<String>,
+    pub sort_order: i64,
+}
+
+/// The title a set of credits belongs to.
+#[derive(Debug, Clone, Copy)]
+pub enum CreditTarget<'a> {
+    /// A movie, keyed by its media item id.
+    Movie(&'a str),
+    /// A TV show, keyed by its tv_show id.
+    Show(&'a str),
+}
+
+impl CreditTarget<'_> {
+    fn media_item_id(self) -> Option<String> {
+        match self {
+            CreditTarget::Movie(id) => Some(id.to_string()),
+            CreditTarget::Show(_) => None,
+        }
+    }
+
+    fn tv_show_id(self) -> Option<String> {
+        match self {
+            CreditTarget::Movie(_) => None,
+            CreditTarget::Show(id) => Some(id.to_string()),
+        }
+    }
+}
+
+/// Replace every credit on `target`. People are upserted by TMDB id so a
+/// person keeps one row (and one filmography) across all titles.
+pub async fn replace_credits(
+    pool: &SqlitePool,
+    target: CreditTarget<'_>,
+    credits: &[NewCredit],
+) -> Result<()> {
+    let media_item_id = target.media_item_id();
+    let tv_show_id = target.tv_show_id();
+    let mut tx = pool.begin().await?;
+
+    sqlx::query("DELETE FROM media_people WHERE media_item_id IS ? AND tv_show_id IS ?")
+        .bind(&media_item_id)
+        .bind(&tv_show_id)
+        .execute(&mut *tx)
+        .await?;
+
+    for credit in credits {
+        let (person_id,): (String,) = sqlx::query_as(
+            "INSERT INTO people (id, tmdb_id, name, profile_path) VALUES (?, ?, ?, ?) \
+             ON CONFLICT(tmdb_id) DO UPDATE SET \
+               name = excluded.name, \
+               profile_path = COALESCE(excluded.profile_path, people.profile_path), \
+               updated_at = datetime('now') \
+             RETURNING id",
+        )
+        .bind(Uuid::new_v4().to_string())
+        .bind(credit.person_tmdb_id)
+        .bind(&credit.name)
+        .bind(&credit.profile_path)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        sqlx::query(
+            "INSERT INTO media_people \
+               (person_id, media_item_id, tv_show_id, credit_type, role, department, character, sort_order) \
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(&person_id)
+        .bind(&media_item_id)
+        .bind(&tv_show_id)
+        .bind(&credit.credit_type)
+        .bind(&credit.role)
+        .bind(&credit.department)
+        .bind(&credit.character)
+        .bind(credit.sort_order)
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    tx.commit().await?;
+    Ok(())
+}
+
+/// List the credits on a movie or show: cast in billing order, then crew.
+pub async fn list_credits(pool: &SqlitePool, target: CreditTarget<'_>) -> Result<Vec<CreditRow>> {
+    let rows = sqlx::query_as::<_, CreditRow>(
+        r#"
+        SELECT p.id AS person_id, p.name, p.profile_path,
+               mp.credit_type, mp.role, mp.department, mp.character, mp.sort_order
+        FROM media_people mp
+        JOIN people p ON p.id = mp.person_id
+        WHERE mp.media_item_id IS ? AND mp.tv_show_id IS ?
+        ORDER BY mp.credit_type = 'crew', mp.sort_order ASC, p.name ASC
+        "#,
+    )
+    .bind(target.media_item_id())
+    .bind(target.tv_show_id())
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
+
+/// Fetch a single person by ID.
+pub async fn get_person(pool: &SqlitePool, id: &str) -> Result<Option<PersonRow>> {
+    let row = sqlx::query_as::<_, PersonRow>("SELECT * FROM people WHERE id = ?")
+        .bind(id)
+        .fetch_optional(pool)
+        .await?;
+    Ok(row)
+}
+
+/// List the movies and shows in the library a person is credited on,
+/// newest first. Alternate movie versions are folded into their primary.
+pub async fn list_person_credits(
+    pool: &SqlitePool,
+    person_id: &str,
+) -> Result<Vec<PersonCreditRow>> {
+    let rows = sqlx::query_as::<_, PersonCreditRow>(
+        r#"
+        SELECT * FROM (
+            SELECT 'movie' AS media_kind, mi.id AS id,
+                   COALESCE(m.title, mi.title) AS title,
+                   COALESCE(m.year, mi.year) AS year,
+                   m.poster_path,
+                   mp.credit_type, mp.role, mp.character
+            FROM media_people mp
+            JOIN media_items mi ON mi.id = mp.media_item_id
+            LEFT JOIN movies m ON m.media_item_id = mi.id
+            WHERE mp.person_id = ?
+              AND (mi.movie_group_id IS NULL OR mi.movie_group_id = mi.id)
+            UNION ALL
+            SELECT 'show' AS media_kind, ts.id AS id, ts.title, ts.year, ts.poster_path,
+                   mp.credit_type, mp.role, mp.character
+            FROM media_people mp
+            JOIN tv_shows ts ON ts.id = mp.tv_show_id
+            WHERE mp.person_id = ?
+        )
+        ORDER BY year IS NULL, year DESC, title ASC
+        "#,
+    )
+    .bind(person_id)
+    .bind(person_id)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
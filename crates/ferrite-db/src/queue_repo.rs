@@ -0,0 +1,162 @@
+use anyhow::Result;
+use sqlx::{Connection, SqliteConnection, SqlitePool};
+
+/// A user's play queue settings.
+#[derive(Debug, Clone, sqlx::FromRow, serde::Serialize)]
+pub struct QueueRow {
+    pub user_id: String,
+    pub source_collection_id: Option<String>,
+    pub current_position: i64,
+    pub shuffle: i64,
+    pub repeat_mode: String,
+    pub updated_at: String,
+}
+
+/// One queued media item, in play order.
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct QueueItemRow {
+    pub position: i64,
+    pub original_position: i64,
+    pub media_id: String,
+}
+
+/// A queued item joined with what a client needs to render it.
+#[derive(Debug, Clone, sqlx::FromRow, serde::Serialize)]
+pub struct QueueEntryRow {
+    pub position: i64,
+    pub media_item_id: String,
+    pub media_type: String,
+    pub title: Option<String>,
+    pub show_title: Option<String>,
+    pub season_number: Option<i64>,
+    pub episode_number: Option<i64>,
+    pub poster_path: Option<String>,
+    pub duration_ms: Option<i64>,
+}
+
+/// A full queue to write: settings plus items in play order.
+#[derive(Debug, Clone)]
+pub struct QueueWrite<'a> {
+    pub source_collection_id: Option<&'a str>,
+    pub current_position: i64,
+    pub shuffle: bool,
+    pub repeat_mode: &'a str,
+    /// (original_position, media_id) in play order
+    pub items: Vec<(i64, &'a str)>,
+}
+
+/// Fetch a user's queue settings.
+/// Accepts `&mut SqliteConnection` so it can run inside a transaction.
+pub async fn get_queue(executor: &mut SqliteConnection, user_id: &str) -> Result<Option<QueueRow>> {
+    let row = sqlx::query_as::<_, QueueRow>("SELECT * FROM play_queues WHERE user_id = ?")
+        .bind(user_id)
+        .fetch_optional(executor)
+        .await?;
+    Ok(row)
+}
+
+/// List a user's queued items in play order.
+/// Accepts `&mut SqliteConnection` so it can run inside a transaction.
+pub async fn list_queue_items(
+    executor: &mut SqliteConnection,
+    user_id: &str,
+) -> Result<Vec<QueueItemRow>> {
+    let rows = sqlx::query_as::<_, QueueItemRow>(
+        "SELECT position, original_position, media_id FROM play_queue_items \
+         WHERE user_id = ? ORDER BY position ASC",
+    )
+    .bind(user_id)
+    .fetch_all(executor)
+    .await?;
+    Ok(rows)
+}
+
+/// List a user's queued items with display metadata, in play order.
+pub async fn list_queue_entries(pool: &SqlitePool, user_id: &str) -> Result<Vec<QueueEntryRow>> {
+    let rows = sqlx::query_as::<_, QueueEntryRow>(
+        r#"
+        SELECT q.position, mi.id AS media_item_id, mi.media_type,
+               COALESCE(ep.title, m.title, mi.title) AS title,
+               ts.title AS show_title,
+               s.season_number,
+               ep.episode_number,
+               COALESCE(ep.still_path, m.poster_path, ts.poster_path) AS poster_path,
+               mi.duration_ms
+        FROM play_queue_items q
+        JOIN media_items mi ON mi.id = q.media_id
+        LEFT JOIN movies m ON m.media_item_id = mi.id
+        LEFT JOIN episodes ep ON ep.media_item_id = mi.id AND ep.file_position = 0
+        LEFT JOIN seasons s ON s.id = ep.season_id
+        LEFT JOIN tv_shows ts ON ts.id = s.tv_show_id
+        WHERE q.user_id = ?
+        ORDER BY q.position ASC
+        "#,
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
+
+/// Replace a user's queue. Items are renumbered 0.. in the given order.
+/// Accepts `&mut SqliteConnection` so it can run inside a transaction.
+pub async fn save_queue(
+    executor: &mut SqliteConnection,
+    user_id: &str,
+    queue: &QueueWrite<'_>,
+) -> Result<()> {
+    let mut tx = executor.begin().await?;
+    sqlx::query(
+        "INSERT INTO play_queues \
+           (user_id, source_collection_id, current_position, shuffle, repeat_mode) \
+         VALUES (?, ?, ?, ?, ?) \
+         ON CONFLICT(user_id) DO UPDATE SET \
+           source_collection_id = excluded.source_collection_id, \
+           current_position = excluded.current_position, \
+           shuffle = excluded.shuffle, \
+           repeat_mode = excluded.repeat_mode, \
+           updated_at = datetime('now')",
+    )
+    .bind(user_id)
+    .bind(queue.source_collection_id)
+    .bind(queue.current_position)
+    .bind(queue.shuffle as i64)
+    .bind(queue.repeat_mode)
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query("DELETE FROM play_queue_items WHERE user_id = ?")
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+
+    // 4 bind params per row, chunks of 200 (800 < SQLite's 999 limit)
+    for (chunk_index, chunk) in queue.items.chunks(200).enumerate() {
+        let placeholders: Vec<&str> = chunk.iter().map(|_| "(?, ?, ?, ?)").collect();
+        let sql = format!(
+            "INSERT INTO play_queue_items (user_id, position, original_position, media_id) VALUES {}",
+            placeholders.join(", ")
+        );
+        let mut query = sqlx::query(&sql);
+        for (i, (original_position, media_id)) in chunk.iter().enumerate() {
+            query = query
+                .bind(user_id)
+                .bind((chunk_index * 200 + i) as i64)
+                .bind(*original_position)
+                .bind(*media_id);
+        }
+        query.execute(&mut *tx).await?;
+    }
+
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Delete a user's queue and its items.
+pub async fn delete_queue(pool: &SqlitePool, user_id: &str) -> Result<bool> {
+    let result = sqlx::query("DELETE FROM play_queues WHERE user_id = ?")
+        .bind(user_id)
+        .execute(pool)
+        .await?;
+    Ok(result.rows_affected() > 0)
+}
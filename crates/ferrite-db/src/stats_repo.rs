@@ -0,0 +1,113 @@
+use anyhow::Result;
+use sqlx::SqlitePool;
+
+/// Size and runtime of one library.
+#[derive(Debug, Clone, sqlx::FromRow, serde::Serialize)]
+pub struct LibraryTotalsRow {
+    pub library_id: String,
+    pub name: String,
+    pub library_type: String,
+    /// Files on disk: every version and stack part counts, extras don't.
+    pub file_count: i64,
+    pub total_size: i64,
+    pub total_duration_ms: i64,
+}
+
+/// File count, size and runtime of every library, including empty ones.
+/// Extras are left out, as in the library listings.
+pub async fn library_totals(pool: &SqlitePool) -> Result<Vec<LibraryTotalsRow>> {
+    let rows = sqlx::query_as::<_, LibraryTotalsRow>(
+        "SELECT l.id AS library_id, l.name, l.library_type, \
+                COUNT(mi.id) AS file_count, \
+                COALESCE(SUM(mi.file_size), 0) AS total_size, \
+                COALESCE(SUM(mi.duration_ms), 0) AS total_duration_ms \
+         FROM libraries l \
+         LEFT JOIN media_items mi ON mi.library_id = l.id AND mi.extra_type IS NULL \
+         GROUP BY l.id \
+         ORDER BY l.name COLLATE NOCASE",
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
+
+/// Format summary of one media file: the item's container and primary codecs
+/// plus the HDR signalling of its first video stream.
+#[derive(Debug, Clone, Default, sqlx::FromRow)]
+pub struct MediaFormatRow {
+    pub file_size: i64,
+    pub duration_ms: Option<i64>,
+    pub container_format: Option<String>,
+    pub video_codec: Option<String>,
+    pub audio_codec: Option<String>,
+    pub width: Option<i64>,
+    pub height: Option<i64>,
+    pub bitrate_kbps: Option<i64>,
+    pub color_transfer: Option<String>,
+    pub dv_profile: Option<i64>,
+    pub dv_bl_compat_id: Option<i64>,
+    pub hdr10_plus: bool,
+}
+
+/// Format summary of every media file except extras, optionally limited to
+/// one library.
+pub async fn list_media_formats(
+    pool: &SqlitePool,
+    library_id: Option<&str>,
+) -> Result<Vec<MediaFormatRow>> {
+    let rows = sqlx::query_as::<_, MediaFormatRow>(
+        "SELECT mi.file_size, mi.duration_ms, mi.container_format, mi.video_codec, \
+                mi.audio_codec, mi.width, mi.height, mi.bitrate_kbps, \
+                ms.color_transfer, ms.dv_profile, ms.dv_bl_compat_id, \
+                COALESCE(ms.hdr10_plus, 0) AS hdr10_plus \
+         FROM media_items mi \
+         LEFT JOIN media_streams ms ON ms.id = ( \
+             SELECT id FROM media_streams \
+             WHERE media_item_id = mi.id AND stream_type = 'video' \
+             ORDER BY stream_index LIMIT 1) \
+         WHERE (? IS NULL OR mi.library_id = ?) AND mi.extra_type IS NULL",
+    )
+    .bind(library_id)
+    .bind(library_id)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
+
+/// A media file in the largest-files list.
+#[derive(Debug, Clone, sqlx::FromRow, serde::Serialize)]
+pub struct LargeFileRow {
+    pub media_item_id: String,
+    pub library_id: String,
+    pub media_type: String,
+    pub file_path: String,
+    pub title: Option<String>,
+    pub file_size: i64,
+    pub duration_ms: Option<i64>,
+    pub bitrate_kbps: Option<i64>,
+    pub video_codec: Option<String>,
+    pub height: Option<i64>,
+}
+
+/// The `limit` largest media files except extras, optionally limited to one
+/// library.
+pub async fn largest_files(
+    pool: &SqlitePool,
+    library_id: Option<&str>,
+    limit: u32,
+) -> Result<Vec<LargeFileRow>> {
+    let rows = sqlx::query_as::<_, LargeFileRow>(
+        "SELECT id AS media_item_id, library_id, media_type, file_path, title, file_size, \
+                duration_ms, bitrate_kbps, video_codec, height \
+         FROM media_items \
+         WHERE (? IS NULL OR library_id = ?) AND extra_type IS NULL \
+         ORDER BY file_size DESC, file_path ASC \
+         LIMIT ?",
+    )
+    .bind(library_id)
+    .bind(library_id)
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
@@ -21,9 +21,18 @@ pub struct StreamInsert {
     pub color_space: Option<String>,
     pub color_transfer: Option<String>,
     pub color_primaries: Option<String>,
+    /// Dolby Vision profile, level and base layer compatibility id; `None` without Dolby Vision.
+    pub dv_profile: Option<u8>,
+    pub dv_level: Option<u8>,
+    pub dv_bl_compat_id: Option<u8>,
+    pub dv_rpu_present: bool,
+    pub dv_el_present: bool,
+    pub hdr10_plus: bool,
     pub channels: Option<u32>,
     pub channel_layout: Option<String>,
     pub sample_rate: Option<u32>,
+    /// `atmos` or `dts_x`.
+    pub spatial_audio: Option<String>,
     pub bitrate_bps: Option<u64>,
 }
 
@@ -45,9 +54,10 @@ pub async fn replace_streams(
         return Ok(());
     }
 
-    // Batch insert: 22 bind params per row, chunks of 40 (880 < SQLite's 999 limit)
-    let row_placeholder = "(?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)";
-    for chunk in streams.chunks(40) {
+    // Batch insert: 29 bind params per row, chunks of 34 (986 < SQLite's 999 limit)
+    let row_placeholder =
+        "(?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)";
+    for chunk in streams.chunks(34) {
         let placeholders: Vec<&str> = chunk.iter().map(|_| row_placeholder).collect();
         let sql = format!(
             "INSERT INTO media_streams (\
@@ -55,7 +65,8 @@ pub async fn replace_streams(
                 profile, language, title, is_default, is_forced, \
                 width, height, frame_rate, pixel_format, bit_depth, \
                 color_space, color_transfer, color_primaries, \
-                channels, channel_layout, sample_rate, bitrate_bps\
+                dv_profile, dv_level, dv_bl_compat_id, dv_rpu_present, dv_el_present, hdr10_plus, \
+                channels, channel_layout, sample_rate, spatial_audio, bitrate_bps\
             ) VALUES {}",
             placeholders.join(", ")
         );
@@ -80,9 +91,16 @@ pub async fn replace_streams(
                 .bind(&s.color_space)
                 .bind(&s.color_transfer)
                 .bind(&s.color_primaries)
+                .bind(s.dv_profile.map(|v| v as i64))
+                .bind(s.dv_level.map(|v| v as i64))
+                .bind(s.dv_bl_compat_id.map(|v| v as i64))
+                .bind(s.dv_rpu_present as i32)
+                .bind(s.dv_el_present as i32)
+                .bind(s.hdr10_plus as i32)
                 .bind(s.channels.map(|v| v as i64))
                 .bind(&s.channel_layout)
                 .bind(s.sample_rate.map(|v| v as i64))
+                .bind(&s.spatial_audio)
                 .bind(s.bitrate_bps.map(|v| v as i64));
         }
         query.execute(&mut *executor).await?;
@@ -113,9 +131,16 @@ pub struct MediaStreamRow {
     pub color_space: Option<String>,
     pub color_transfer: Option<String>,
     pub color_primaries: Option<String>,
+    pub dv_profile: Option<i64>,
+    pub dv_level: Option<i64>,
+    pub dv_bl_compat_id: Option<i64>,
+    pub dv_rpu_present: i64,
+    pub dv_el_present: i64,
+    pub hdr10_plus: i64,
     pub channels: Option<i64>,
     pub channel_layout: Option<String>,
     pub sample_rate: Option<i64>,
+    pub spatial_audio: Option<String>,
     pub bitrate_bps: Option<i64>,
 }
 
@@ -138,6 +163,8 @@ pub struct VideoMeta {
     pub color_space: Option<String>,
     pub color_transfer: Option<String>,
     pub color_primaries: Option<String>,
+    pub dv_profile: Option<i64>,
+    pub dv_bl_compat_id: Option<i64>,
 }
 
 /// Fetch all video stream metadata for a media item in a single DB round-trip.
@@ -145,7 +172,8 @@ pub struct VideoMeta {
 /// and `get_video_color_metadata` calls that were previously made sequentially.
 pub async fn get_video_meta(pool: &SqlitePool, media_item_id: &str) -> Result<Option<VideoMeta>> {
     let row: Option<VideoMeta> = sqlx::query_as(
-        "SELECT pixel_format, frame_rate, color_space, color_transfer, color_primaries \
+        "SELECT pixel_format, frame_rate, color_space, color_transfer, color_primaries, \
+                 dv_profile, dv_bl_compat_id \
              FROM media_streams \
              WHERE media_item_id = ? AND stream_type = 'video' \
              ORDER BY stream_index LIMIT 1",
@@ -211,3 +239,37 @@ pub async fn get_video_color_metadata(
         color_primaries: r.2,
     }))
 }
+
+/// Channel count of the `audio_index`-th audio stream (0-based) of a media item.
+pub async fn get_audio_channels(
+    pool: &SqlitePool,
+    media_item_id: &str,
+    audio_index: u32,
+) -> Result<Option<u32>> {
+    let row: Option<(Option<i64>,)> = sqlx::query_as(
+        "SELECT channels FROM media_streams WHERE media_item_id = ? AND stream_type = 'audio' \
+         ORDER BY stream_index LIMIT 1 OFFSET ?",
+    )
+    .bind(media_item_id)
+    .bind(audio_index as i64)
+    .fetch_optional(pool)
+    .await?;
+    Ok(row.and_then(|r| r.0).map(|c| c as u32))
+}
+
+/// Codec of the `audio_index`-th audio stream (0-based) of a media item.
+pub async fn get_audio_codec(
+    pool: &SqlitePool,
+    media_item_id: &str,
+    audio_index: u32,
+) -> Result<Option<String>> {
+    let row: Option<(Option<String>,)> = sqlx::query_as(
+        "SELECT codec_name FROM media_streams WHERE media_item_id = ? AND stream_type = 'audio' \
+         ORDER BY stream_index LIMIT 1 OFFSET ?",
+    )
+    .bind(media_item_id)
+    .bind(audio_index as i64)
+    .fetch_optional(pool)
+    .await?;
+    Ok(row.and_then(|r| r.0))
+}
@@ -0,0 +1,68 @@
+use anyhow::Result;
+use sqlx::SqlitePool;
+
+/// One audit log entry for an administrator stopping a stream.
+#[derive(Debug, Clone, sqlx::FromRow, serde::Serialize)]
+pub struct TerminationRow {
+    pub id: i64,
+    pub admin_user_id: Option<String>,
+    pub admin_username: Option<String>,
+    pub target_user_id: Option<String>,
+    pub target_username: Option<String>,
+    pub media_id: Option<String>,
+    pub playback_session_id: Option<String>,
+    pub scope: String,
+    pub reason: Option<String>,
+    pub created_at: String,
+}
+
+/// An audit log entry to write.
+#[derive(Debug, Clone, Default)]
+pub struct NewTermination<'a> {
+    pub admin_user_id: Option<&'a str>,
+    pub admin_username: Option<&'a str>,
+    pub target_user_id: Option<&'a str>,
+    pub target_username: Option<&'a str>,
+    pub media_id: Option<&'a str>,
+    pub playback_session_id: Option<&'a str>,
+    /// "session", "user" or "transcode"
+    pub scope: &'a str,
+    pub reason: Option<&'a str>,
+}
+
+/// Record a termination and return its id.
+pub async fn record_termination(pool: &SqlitePool, entry: &NewTermination<'_>) -> Result<i64> {
+    let result = sqlx::query(
+        "INSERT INTO stream_terminations \
+         (admin_user_id, admin_username, target_user_id, target_username, media_id, \
+          playback_session_id, scope, reason) \
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+    )
+    .bind(entry.admin_user_id)
+    .bind(entry.admin_username)
+    .bind(entry.target_user_id)
+    .bind(entry.target_username)
+    .bind(entry.media_id)
+    .bind(entry.playback_session_id)
+    .bind(entry.scope)
+    .bind(entry.reason)
+    .execute(pool)
+    .await?;
+    Ok(result.last_insert_rowid())
+}
+
+/// List terminations, newest first.
+pub async fn list_terminations(
+    pool: &SqlitePool,
+    limit: i64,
+    offset: i64,
+) -> Result<Vec<TerminationRow>> {
+    let rows = sqlx::query_as::<_, TerminationRow>(
+        "SELECT * FROM stream_terminations ORDER BY id DESC LIMIT ? OFFSET ?",
+    )
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
@@ -0,0 +1,141 @@
+use anyhow::Result;
+use sqlx::SqlitePool;
+use std::collections::HashMap;
+
+/// Translated metadata for one title in one language.
+/// `id` is the media item id (movies) or tv_show id (shows).
+#[derive(Debug, Clone, sqlx::FromRow, serde::Serialize)]
+pub struct TranslationRow {
+    pub id: String,
+    pub language: String,
+    pub title: Option<String>,
+    pub overview: Option<String>,
+    /// Always NULL for shows
+    pub tagline: Option<String>,
+    pub poster_path: Option<String>,
+}
+
+/// A translation fetched from a metadata provider, ready to be stored.
+#[derive(Debug, Clone)]
+pub struct NewTranslation {
+    pub language: String,
+    pub title: Option<String>,
+    pub overview: Option<String>,
+    pub tagline: Option<String>,
+    /// Local filename in the image cache
+    pub poster_path: Option<String>,
+}
+
+/// Replace every stored translation of a movie.
+pub async fn replace_movie_translations(
+    pool: &SqlitePool,
+    media_item_id: &str,
+    translations: &[NewTranslation],
+) -> Result<()> {
+    let mut tx = pool.begin().await?;
+    sqlx::query("DELETE FROM movie_translations WHERE media_item_id = ?")
+        .bind(media_item_id)
+        .execute(&mut *tx)
+        .await?;
+    for t in translations {
+        sqlx::query(
+            "INSERT INTO movie_translations \
+               (media_item_id, language, title, overview, tagline, poster_path) \
+             VALUES (?, ?, ?, ?, ?, ?)",
+        )
+        .bind(media_item_id)
+        .bind(&t.language)
+        .bind(&t.title)
+        .bind(&t.overview)
+        .bind(&t.tagline)
+        .bind(&t.poster_path)
+        .execute(&mut *tx)
+        .await?;
+    }
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Replace every stored translation of a TV show. Taglines are not kept for shows.
+pub async fn replace_show_translations(
+    pool: &SqlitePool,
+    tv_show_id: &str,
+    translations: &[NewTranslation],
+) -> Result<()> {
+    let mut tx = pool.begin().await?;
+    sqlx::query("DELETE FROM tv_show_translations WHERE tv_show_id = ?")
+        .bind(tv_show_id)
+        .execute(&mut *tx)
+        .await?;
+    for t in translations {
+        sqlx::query(
+            "INSERT INTO tv_show_translations \
+               (tv_show_id, language, title, overview, poster_path) \
+             VALUES (?, ?, ?, ?, ?)",
+        )
+        .bind(tv_show_id)
+        .bind(&t.language)
+        .bind(&t.title)
+        .bind(&t.overview)
+        .bind(&t.poster_path)
+        .execute(&mut *tx)
+        .await?;
+    }
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Look up the `language` translations for a batch of movies, keyed by media item id.
+/// Movies without a translation in that language are absent from the map.
+pub async fn get_movie_translations(
+    pool: &SqlitePool,
+    language: &str,
+    media_item_ids: &[String],
+) -> Result<HashMap<String, TranslationRow>> {
+    fetch_translations(
+        pool,
+        "SELECT media_item_id AS id, language, title, overview, tagline, poster_path \
+         FROM movie_translations WHERE language = ? AND media_item_id IN",
+        language,
+        media_item_ids,
+    )
+    .await
+}
+
+/// Look up the `language` translations for a batch of shows, keyed by tv_show id.
+pub async fn get_show_translations(
+    pool: &SqlitePool,
+    language: &str,
+    tv_show_ids: &[String],
+) -> Result<HashMap<String, TranslationRow>> {
+    fetch_translations(
+        pool,
+        "SELECT tv_show_id AS id, language, title, overview, NULL AS tagline, poster_path \
+         FROM tv_show_translations WHERE language = ? AND tv_show_id IN",
+        language,
+        tv_show_ids,
+    )
+    .await
+}
+
+async fn fetch_translations(
+    pool: &SqlitePool,
+    select: &str,
+    language: &str,
+    ids: &[String],
+) -> Result<HashMap<String, TranslationRow>> {
+    let mut out = HashMap::with_capacity(ids.len());
+    // One bind for the language plus one per id, chunked under SQLite's 999 limit
+    for chunk in ids.chunks(900) {
+        let placeholders: Vec<&str> = chunk.iter().map(|_| "?").collect();
+        let sql = format!("{} ({})", select, placeholders.join(", "));
+        let mut query = sqlx::query_as::<_, TranslationRow>(&sql).bind(language);
+        for id in chunk {
+            query = query.bind(id);
+        }
+        for row in query.fetch_all(pool).await? {
+            out.insert(row.id.clone(), row);
+        }
+    }
+    Ok(out)
+}
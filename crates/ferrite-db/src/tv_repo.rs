@@ -126,27 +126,357 @@ pub async fn upsert_season(
     Ok(id)
 }
 
-/// Insert or update an episode row linking a media_item to a season.
+/// Link a media item to the episodes it holds — one for most files, several
+/// for multi-episode files — in play order. Episodes the file no longer
+/// covers are dropped; metadata of episodes it keeps is preserved.
 /// Accepts `&mut SqliteConnection` so it can run inside a transaction.
 pub async fn upsert_episode(
     executor: &mut SqliteConnection,
     media_item_id: &str,
     season_id: &str,
-    episode_number: u32,
+    episode_numbers: &[u32],
+) -> Result<()> {
+    let current: Vec<(String, i64)> =
+        sqlx::query_as("SELECT season_id, episode_number FROM episodes WHERE media_item_id = ?")
+            .bind(media_item_id)
+            .fetch_all(&mut *executor)
+            .await?;
+    for (old_season, number) in current {
+        if old_season != season_id || !episode_numbers.contains(&(number as u32)) {
+            sqlx::query("DELETE FROM episodes WHERE season_id = ? AND episode_number = ?")
+                .bind(&old_season)
+                .bind(number)
+                .execute(&mut *executor)
+                .await?;
+        }
+    }
+
+    for (position, number) in episode_numbers.iter().enumerate() {
+        sqlx::query(
+            r#"INSERT INTO episodes (media_item_id, season_id, episode_number, file_position)
+               VALUES (?, ?, ?, ?)
+               ON CONFLICT(season_id, episode_number) DO UPDATE SET
+                 media_item_id = excluded.media_item_id,
+                 file_position = excluded.file_position,
+                 needs_match   = 0,
+                 absolute_number = NULL"#,
+        )
+        .bind(media_item_id)
+        .bind(season_id)
+        .bind(*number as i64)
+        .bind(position as i64)
+        .execute(&mut *executor)
+        .await?;
+    }
+
+    Ok(())
+}
+
+/// Flag a media item's episode as needing a provider match: a date-based
+/// episode (`air_date`) or a special known only by `title`.
+pub async fn mark_episode_unmatched(
+    executor: &mut SqliteConnection,
+    media_item_id: &str,
+    air_date: Option<&str>,
+    title: Option<&str>,
 ) -> Result<()> {
     sqlx::query(
-        r#"INSERT OR REPLACE INTO episodes (media_item_id, season_id, episode_number)
-           VALUES (?, ?, ?)"#,
+        r#"UPDATE episodes
+           SET needs_match = 1,
+               air_date    = COALESCE(?, air_date),
+               title       = COALESCE(?, title)
+           WHERE media_item_id = ?"#,
     )
+    .bind(air_date)
+    .bind(title)
     .bind(media_item_id)
-    .bind(season_id)
-    .bind(episode_number as i64)
     .execute(executor)
     .await?;
+    Ok(())
+}
 
+/// Record the absolute number (`Show - 105`) of a media item's episode and
+/// flag it for mapping onto the provider's seasons.
+pub async fn mark_episode_absolute(
+    executor: &mut SqliteConnection,
+    media_item_id: &str,
+    absolute_number: u32,
+) -> Result<()> {
+    sqlx::query("UPDATE episodes SET needs_match = 1, absolute_number = ? WHERE media_item_id = ?")
+        .bind(absolute_number as i64)
+        .bind(media_item_id)
+        .execute(executor)
+        .await?;
     Ok(())
 }
 
+/// Placeholder number for a special that has no episode number yet: the one
+/// it already holds in `season_id`, or the next free number from 1000 up.
+pub async fn provisional_episode_number(
+    executor: &mut SqliteConnection,
+    season_id: &str,
+    media_item_id: &str,
+) -> Result<u32> {
+    let held: Option<(i64,)> = sqlx::query_as(
+        "SELECT episode_number FROM episodes WHERE season_id = ? AND media_item_id = ? AND needs_match = 1",
+    )
+    .bind(season_id)
+    .bind(media_item_id)
+    .fetch_optional(&mut *executor)
+    .await?;
+    if let Some((n,)) = held {
+        return Ok(n as u32);
+    }
+    let (max,): (Option<i64>,) =
+        sqlx::query_as("SELECT MAX(episode_number) FROM episodes WHERE season_id = ?")
+            .bind(season_id)
+            .fetch_one(&mut *executor)
+            .await?;
+    Ok(max.map_or(1000, |m| (m + 1).max(1000)) as u32)
+}
+
+/// An episode whose numbering awaits a provider match.
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct UnmatchedEpisodeRow {
+    pub media_item_id: String,
+    pub season_id: String,
+    pub season_number: i64,
+    pub episode_number: i64,
+    pub title: Option<String>,
+    pub air_date: Option<String>,
+}
+
+/// Date-based episodes and title-only specials of a show not yet matched.
+pub async fn list_unmatched_episodes(
+    pool: &SqlitePool,
+    show_id: &str,
+) -> Result<Vec<UnmatchedEpisodeRow>> {
+    let rows = sqlx::query_as::<_, UnmatchedEpisodeRow>(
+        r#"SELECT e.media_item_id, e.season_id, s.season_number, e.episode_number,
+                  e.title, e.air_date
+           FROM episodes e
+           JOIN seasons s ON s.id = e.season_id
+           WHERE s.tv_show_id = ? AND e.needs_match = 1 AND e.absolute_number IS NULL
+           ORDER BY s.season_number, e.episode_number"#,
+    )
+    .bind(show_id)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
+
+/// Absolute numbers of a show's episodes still waiting to be mapped onto the
+/// provider's seasons, in ascending order.
+pub async fn list_unmapped_absolute_numbers(pool: &SqlitePool, show_id: &str) -> Result<Vec<i64>> {
+    let rows: Vec<(i64,)> = sqlx::query_as(
+        r#"SELECT e.absolute_number
+           FROM episodes e
+           JOIN seasons s ON s.id = e.season_id
+           WHERE s.tv_show_id = ? AND e.needs_match = 1 AND e.absolute_number IS NOT NULL
+           ORDER BY e.absolute_number"#,
+    )
+    .bind(show_id)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows.into_iter().map(|(n,)| n).collect())
+}
+
+/// Where an absolutely numbered episode belongs in the provider's seasons.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AbsolutePlacement {
+    pub absolute_number: i64,
+    pub season_number: u32,
+    pub episode_number: u32,
+}
+
+/// Move a show's unmapped absolute episodes to their season and episode
+/// numbers. All of them are lifted out of their slots first, so orderings
+/// that shuffle episodes between seasons don't collide with themselves.
+/// Moved episodes lose the metadata of their old slot and are refetched by
+/// the episode metadata pass. An episode whose target is held by another
+/// file stays unmapped. Returns how many episodes were placed.
+pub async fn place_absolute_episodes(
+    pool: &SqlitePool,
+    show_id: &str,
+    placements: &[AbsolutePlacement],
+) -> Result<u32> {
+    let mut tx = pool.begin().await?;
+    let pending: Vec<(String, i64, i64, i64)> = sqlx::query_as(
+        r#"SELECT e.season_id, s.season_number, e.episode_number, e.absolute_number
+           FROM episodes e
+           JOIN seasons s ON s.id = e.season_id
+           WHERE s.tv_show_id = ? AND e.needs_match = 1 AND e.absolute_number IS NOT NULL"#,
+    )
+    .bind(show_id)
+    .fetch_all(&mut *tx)
+    .await?;
+
+    let mut lifted = Vec::new();
+    let mut placed = 0;
+    for (season_id, season_number, episode_number, absolute) in pending {
+        let Some(target) = placements.iter().find(|p| p.absolute_number == absolute) else {
+            continue;
+        };
+        if (season_number, episode_number)
+            == (target.season_number as i64, target.episode_number as i64)
+        {
+            sqlx::query(
+                "UPDATE episodes SET needs_match = 0 WHERE season_id = ? AND episode_number = ?",
+            )
+            .bind(&season_id)
+            .bind(episode_number)
+            .execute(&mut *tx)
+            .await?;
+            placed += 1;
+            continue;
+        }
+        // Absolute numbers are unique within a show, so their negatives are
+        // free parking slots
+        sqlx::query(
+            "UPDATE episodes SET episode_number = ? WHERE season_id = ? AND episode_number = ?",
+        )
+        .bind(-absolute)
+        .bind(&season_id)
+        .bind(episode_number)
+        .execute(&mut *tx)
+        .await?;
+        lifted.push((season_id, episode_number, absolute, *target));
+    }
+
+    for (season_id, episode_number, absolute, target) in &lifted {
+        let target_season = upsert_season(&mut tx, show_id, target.season_number).await?;
+        let free = slot_is_free(&mut tx, &target_season, target.episode_number as i64).await?;
+        let (to_season, to_number) = if free {
+            placed += 1;
+            (target_season.as_str(), target.episode_number as i64)
+        } else if slot_is_free(&mut tx, season_id, *episode_number).await? {
+            (season_id.as_str(), *episode_number)
+        } else {
+            let (max,): (Option<i64>,) =
+                sqlx::query_as("SELECT MAX(episode_number) FROM episodes WHERE season_id = ?")
+                    .bind(season_id)
+                    .fetch_one(&mut *tx)
+                    .await?;
+            (season_id.as_str(), max.map_or(1000, |m| (m + 1).max(1000)))
+        };
+        sqlx::query(
+            r#"UPDATE episodes
+               SET season_id = ?, episode_number = ?, needs_match = ?,
+                   title = NULL, overview = NULL, air_date = NULL, still_path = NULL
+               WHERE season_id = ? AND episode_number = ?"#,
+        )
+        .bind(to_season)
+        .bind(to_number)
+        .bind(!free)
+        .bind(season_id)
+        .bind(-absolute)
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    sqlx::query(
+        r#"DELETE FROM seasons
+           WHERE tv_show_id = ?
+             AND NOT EXISTS (SELECT 1 FROM episodes WHERE season_id = seasons.id)"#,
+    )
+    .bind(show_id)
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+    Ok(placed)
+}
+
+async fn slot_is_free(
+    executor: &mut SqliteConnection,
+    season_id: &str,
+    episode_number: i64,
+) -> Result<bool> {
+    let taken: Option<(i64,)> =
+        sqlx::query_as("SELECT 1 FROM episodes WHERE season_id = ? AND episode_number = ?")
+            .bind(season_id)
+            .bind(episode_number)
+            .fetch_optional(executor)
+            .await?;
+    Ok(taken.is_none())
+}
+
+/// Set the TMDB episode group used to map a show's absolute episode numbers
+/// (`None` maps them through the show's regular seasons) and flag those
+/// episodes for remapping. Returns `false` when the show doesn't exist.
+pub async fn set_episode_group(
+    pool: &SqlitePool,
+    show_id: &str,
+    episode_group_id: Option<&str>,
+) -> Result<bool> {
+    let mut tx = pool.begin().await?;
+    let updated = sqlx::query("UPDATE tv_shows SET episode_group_id = ? WHERE id = ?")
+        .bind(episode_group_id)
+        .bind(show_id)
+        .execute(&mut *tx)
+        .await?;
+    if updated.rows_affected() == 0 {
+        return Ok(false);
+    }
+    sqlx::query(
+        r#"UPDATE episodes SET needs_match = 1
+           WHERE absolute_number IS NOT NULL
+             AND season_id IN (SELECT id FROM seasons WHERE tv_show_id = ?)"#,
+    )
+    .bind(show_id)
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+    Ok(true)
+}
+
+/// Move a matched episode from its provisional slot to the provider's season
+/// and episode number. Returns `false`, leaving it in place, when another
+/// file already holds the target episode. A provisional season left empty is
+/// removed.
+pub async fn resolve_episode(
+    pool: &SqlitePool,
+    show_id: &str,
+    from: (&str, i64),
+    season_number: u32,
+    episode_number: u32,
+) -> Result<bool> {
+    let (from_season_id, from_number) = from;
+    let mut tx = pool.begin().await?;
+    let season_id = upsert_season(&mut tx, show_id, season_number).await?;
+    let taken: Option<(i64,)> =
+        sqlx::query_as("SELECT 1 FROM episodes WHERE season_id = ? AND episode_number = ?")
+            .bind(&season_id)
+            .bind(episode_number as i64)
+            .fetch_optional(&mut *tx)
+            .await?;
+    if taken.is_some() {
+        return Ok(false);
+    }
+
+    sqlx::query(
+        r#"UPDATE episodes
+           SET season_id = ?, episode_number = ?, needs_match = 0
+           WHERE season_id = ? AND episode_number = ?"#,
+    )
+    .bind(&season_id)
+    .bind(episode_number as i64)
+    .bind(from_season_id)
+    .bind(from_number)
+    .execute(&mut *tx)
+    .await?;
+    if season_id != from_season_id {
+        sqlx::query(
+            "DELETE FROM seasons WHERE id = ? AND NOT EXISTS (SELECT 1 FROM episodes WHERE season_id = ?)",
+        )
+        .bind(from_season_id)
+        .bind(from_season_id)
+        .execute(&mut *tx)
+        .await?;
+    }
+    tx.commit().await?;
+    Ok(true)
+}
+
 // ── Query types ──────────────────────────────────────────────────────────────
 
 /// A TV show row for API responses.
@@ -165,6 +495,10 @@ pub struct TvShowRow {
     pub backdrop_path: Option<String>,
     pub genres: Option<String>,
     pub fetched_at: Option<String>,
+    /// TMDB episode group that absolute episode numbers are mapped through
+    pub episode_group_id: Option<String>,
+    /// When the last TMDB search found no match
+    pub match_failed_at: Option<String>,
     pub season_count: i64,
     pub episode_count: i64,
 }
@@ -187,6 +521,8 @@ pub struct EpisodeRow {
     pub media_item_id: String,
     pub season_id: String,
     pub episode_number: i64,
+    /// Position within a multi-episode file (0 for its first episode)
+    pub file_position: i64,
     pub episode_title: Option<String>,
     pub overview: Option<String>,
     pub air_date: Option<String>,
@@ -267,7 +603,7 @@ pub async fn list_episodes(
     user_id: Option<&str>,
 ) -> Result<Vec<EpisodeRow>> {
     let rows = sqlx::query_as::<_, EpisodeRow>(
-        r#"SELECT e.media_item_id, e.season_id, e.episode_number,
+        r#"SELECT e.media_item_id, e.season_id, e.episode_number, e.file_position,
                   e.title AS episode_title, e.overview, e.air_date, e.still_path,
                   mi.file_path, mi.file_size, mi.duration_ms,
                   mi.video_codec, mi.audio_codec, mi.width, mi.height,
@@ -311,10 +647,13 @@ pub async fn get_next_episode(
     let row = sqlx::query_as::<_, NextEpisodeRow>(
         r#"
         WITH current AS (
+            -- Multi-episode files continue after their last episode
             SELECT e.episode_number, e.season_id, s.season_number, s.tv_show_id
             FROM episodes e
             JOIN seasons s ON s.id = e.season_id
             WHERE e.media_item_id = ?
+            ORDER BY e.file_position DESC
+            LIMIT 1
         ),
         -- Next episode in the same season (lowest episode_number above current)
         same_season_next AS (
@@ -334,7 +673,7 @@ pub async fn get_next_episode(
             JOIN media_items mi ON mi.id = e.media_item_id
             JOIN tv_shows ts ON ts.id = s.tv_show_id
             JOIN current c ON e.season_id = c.season_id
-            WHERE e.episode_number > c.episode_number
+            WHERE e.episode_number > c.episode_number AND e.file_position = 0
             ORDER BY e.episode_number ASC
             LIMIT 1
         ),
@@ -356,7 +695,7 @@ pub async fn get_next_episode(
             JOIN media_items mi ON mi.id = e.media_item_id
             JOIN tv_shows ts ON ts.id = s.tv_show_id
             JOIN current c ON s.tv_show_id = c.tv_show_id
-            WHERE s.season_number > c.season_number
+            WHERE s.season_number > c.season_number AND e.file_position = 0
             ORDER BY s.season_number ASC, e.episode_number ASC
             LIMIT 1
         )
@@ -507,11 +846,22 @@ pub async fn get_shows_needing_metadata(
     Ok(rows)
 }
 
+/// Record that a TMDB search found no match for a show, so it is listed
+/// among the unmatched items.
+pub async fn mark_show_match_failed(pool: &SqlitePool, show_id: &str) -> Result<()> {
+    sqlx::query("UPDATE tv_shows SET match_failed_at = datetime('now') WHERE id = ?")
+        .bind(show_id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
 /// Get TV shows that have show-level metadata (tmdb_id set) but still have
 /// episodes that were never enriched — i.e. episodes where title AND air_date
 /// are both NULL, indicating they were inserted during scanning but never
-/// received TMDB metadata. This avoids re-processing episodes where TMDB
-/// legitimately has no still_path or overview.
+/// received TMDB metadata — or episodes still awaiting a provider match.
+/// This avoids re-processing episodes where TMDB legitimately has no
+/// still_path or overview.
 pub async fn get_shows_needing_episode_metadata(
     pool: &SqlitePool,
     library_id: &str,
@@ -523,8 +873,7 @@ pub async fn get_shows_needing_episode_metadata(
            JOIN episodes e ON e.season_id = s.id
            WHERE ts.library_id = ?
              AND ts.tmdb_id IS NOT NULL
-             AND e.title IS NULL
-             AND e.air_date IS NULL"#,
+             AND ((e.title IS NULL AND e.air_date IS NULL) OR e.needs_match = 1)"#,
     )
     .bind(library_id)
     .fetch_all(pool)
@@ -0,0 +1,34 @@
+use anyhow::Result;
+use sqlx::SqlitePool;
+
+/// An item of the `unmatched_items` view: a file or show a scan indexed but
+/// couldn't identify.
+#[derive(Debug, Clone, sqlx::FromRow, serde::Serialize)]
+pub struct UnmatchedItemRow {
+    /// `unparsed`, `no_match` or `unmatched`
+    pub reason: String,
+    pub library_id: String,
+    pub media_item_id: Option<String>,
+    pub tv_show_id: Option<String>,
+    pub file_path: Option<String>,
+    pub title: Option<String>,
+    pub since: Option<String>,
+}
+
+/// Unmatched items, optionally limited to one library, grouped by reason.
+pub async fn list_unmatched_items(
+    pool: &SqlitePool,
+    library_id: Option<&str>,
+) -> Result<Vec<UnmatchedItemRow>> {
+    let rows = sqlx::query_as::<_, UnmatchedItemRow>(
+        r#"SELECT reason, library_id, media_item_id, tv_show_id, file_path, title, since
+           FROM unmatched_items
+           WHERE ? IS NULL OR library_id = ?
+           ORDER BY reason ASC, COALESCE(file_path, title) ASC"#,
+    )
+    .bind(library_id)
+    .bind(library_id)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
@@ -14,6 +14,10 @@ pub struct UserRow {
     pub is_admin: i64,
     pub created_at: String,
     pub last_login_at: Option<String>,
+    /// Remote bitrate cap in kbps (NULL = server default, 0 = unlimited).
+    pub remote_max_bitrate_kbps: Option<i64>,
+    /// Concurrent playback limit (NULL = server default, 0 = unlimited).
+    pub max_concurrent_streams: Option<i64>,
 }
 
 /// Create a new user with a bcrypt-hashed password. Returns the new user row.
@@ -111,3 +115,21 @@ pub async fn change_password(pool: &SqlitePool, user_id: &str, new_password: &st
         .await?;
     Ok(())
 }
+
+/// Set a user's streaming limits. `None` reverts a limit to the server default.
+pub async fn update_stream_limits(
+    pool: &SqlitePool,
+    user_id: &str,
+    remote_max_bitrate_kbps: Option<i64>,
+    max_concurrent_streams: Option<i64>,
+) -> Result<bool> {
+    let result = sqlx::query(
+        "UPDATE users SET remote_max_bitrate_kbps = ?, max_concurrent_streams = ? WHERE id = ?",
+    )
+    .bind(remote_max_bitrate_kbps)
+    .bind(max_concurrent_streams)
+    .bind(user_id)
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
@@ -0,0 +1,184 @@
+use ferrite_core::media::LibraryType;
+use ferrite_db::tv_repo::AbsolutePlacement;
+use ferrite_db::{create_pools, library_repo, media_repo, tv_repo};
+use sqlx::SqlitePool;
+use uuid::Uuid;
+
+async fn insert_absolute_episode(
+    pool: &SqlitePool,
+    library_id: &Uuid,
+    show_id: &str,
+    absolute: u32,
+) -> String {
+    let mut conn = pool.acquire().await.unwrap();
+    let path = format!("/anime/Show/[Group] Show - {absolute:02}.mkv");
+    let id = media_repo::insert_media_item(
+        &mut conn,
+        library_id,
+        "episode",
+        &path,
+        1234,
+        None,
+        Some("Show"),
+        None,
+        None,
+    )
+    .await
+    .unwrap();
+    let season_id = tv_repo::upsert_season(&mut conn, show_id, 1).await.unwrap();
+    tv_repo::upsert_episode(&mut conn, &id, &season_id, &[absolute])
+        .await
+        .unwrap();
+    tv_repo::mark_episode_absolute(&mut conn, &id, absolute)
+        .await
+        .unwrap();
+    id
+}
+
+fn place(absolute_number: i64, season_number: u32, episode_number: u32) -> AbsolutePlacement {
+    AbsolutePlacement {
+        absolute_number,
+        season_number,
+        episode_number,
+    }
+}
+
+async fn layout(pool: &SqlitePool, show_id: &str) -> Vec<(i64, i64, String)> {
+    let mut out = Vec::new();
+    for (season_id, season_number) in tv_repo::get_seasons_for_show(pool, show_id).await.unwrap() {
+        for e in tv_repo::list_episodes(pool, &season_id, None)
+            .await
+            .unwrap()
+        {
+            out.push((season_number, e.episode_number, e.media_item_id));
+        }
+    }
+    out
+}
+
+#[tokio::test]
+async fn absolute_episodes_move_to_provider_seasons_and_can_be_remapped() {
+    let db_path = std::env::temp_dir().join(format!("ferrite-db-test-{}.sqlite", Uuid::new_v4()));
+    let db = create_pools(&db_path, 4)
+        .await
+        .expect("failed to create test db pool");
+    let lib = library_repo::create_library(
+        &db.write,
+        "Anime",
+        &["/anime".to_string()],
+        LibraryType::Tv,
+        &[],
+    )
+    .await
+    .unwrap();
+    let mut conn = db.write.acquire().await.unwrap();
+    let show_id = tv_repo::upsert_tv_show(&mut conn, &lib.id.to_string(), "Show")
+        .await
+        .unwrap();
+    drop(conn);
+
+    let first = insert_absolute_episode(&db.write, &lib.id, &show_id, 1).await;
+    let thirteenth = insert_absolute_episode(&db.write, &lib.id, &show_id, 13).await;
+    let fourteenth = insert_absolute_episode(&db.write, &lib.id, &show_id, 14).await;
+    assert_eq!(
+        tv_repo::list_unmapped_absolute_numbers(&db.read, &show_id)
+            .await
+            .unwrap(),
+        vec![1, 13, 14]
+    );
+    // Absolute episodes aren't matched by air date or title
+    assert!(tv_repo::list_unmatched_episodes(&db.read, &show_id)
+        .await
+        .unwrap()
+        .is_empty());
+
+    // Twelve episodes in season 1
+    let placed = tv_repo::place_absolute_episodes(
+        &db.write,
+        &show_id,
+        &[place(1, 1, 1), place(13, 2, 1), place(14, 2, 2)],
+    )
+    .await
+    .unwrap();
+    assert_eq!(placed, 3);
+    assert_eq!(
+        layout(&db.read, &show_id).await,
+        vec![
+            (1, 1, first.clone()),
+            (2, 1, thirteenth.clone()),
+            (2, 2, fourteenth.clone()),
+        ]
+    );
+    assert!(tv_repo::list_unmapped_absolute_numbers(&db.read, &show_id)
+        .await
+        .unwrap()
+        .is_empty());
+
+    // An episode group with thirteen episodes in season 1: episode 14 takes
+    // the slot episode 13 leaves
+    assert!(
+        tv_repo::set_episode_group(&db.write, &show_id, Some("group"))
+            .await
+            .unwrap()
+    );
+    let placed = tv_repo::place_absolute_episodes(
+        &db.write,
+        &show_id,
+        &[place(1, 1, 1), place(13, 1, 13), place(14, 2, 1)],
+    )
+    .await
+    .unwrap();
+    assert_eq!(placed, 3);
+    assert_eq!(
+        layout(&db.read, &show_id).await,
+        vec![
+            (1, 1, first),
+            (1, 13, thirteenth),
+            (2, 1, fourteenth.clone()),
+        ]
+    );
+    let show = tv_repo::get_show(&db.read, &show_id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(show.episode_group_id.as_deref(), Some("group"));
+
+    // A target held by a regularly numbered file leaves the episode unmapped
+    assert!(tv_repo::set_episode_group(&db.write, &show_id, None)
+        .await
+        .unwrap());
+    let mut conn = db.write.acquire().await.unwrap();
+    let regular = media_repo::insert_media_item(
+        &mut conn,
+        &lib.id,
+        "episode",
+        "/anime/Show/Show - S03E01.mkv",
+        1234,
+        None,
+        Some("Show"),
+        None,
+        None,
+    )
+    .await
+    .unwrap();
+    let season3 = tv_repo::upsert_season(&mut conn, &show_id, 3)
+        .await
+        .unwrap();
+    tv_repo::upsert_episode(&mut conn, &regular, &season3, &[1])
+        .await
+        .unwrap();
+    drop(conn);
+    let placed = tv_repo::place_absolute_episodes(&db.write, &show_id, &[place(14, 3, 1)])
+        .await
+        .unwrap();
+    assert_eq!(placed, 0);
+    assert_eq!(
+        tv_repo::list_unmapped_absolute_numbers(&db.read, &show_id)
+            .await
+            .unwrap(),
+        vec![1, 13, 14]
+    );
+    assert!(layout(&db.read, &show_id)
+        .await
+        .contains(&(2, 1, fourteenth)));
+}
@@ -0,0 +1,102 @@
+use ferrite_db::create_pools;
+use ferrite_db::{loudness_repo, stream_repo};
+use uuid::Uuid;
+
+#[tokio::test]
+async fn loudness_is_cached_per_stream_and_file_size() {
+    let db_path = std::env::temp_dir().join(format!("ferrite-db-test-{}.sqlite", Uuid::new_v4()));
+    let db = create_pools(&db_path, 4)
+        .await
+        .expect("failed to create test db pool");
+
+    let library_id = Uuid::new_v4().to_string();
+    sqlx::query(
+        "INSERT INTO libraries (id, name, path, library_type) VALUES (?, 'Movies', '/m', 'movie')",
+    )
+    .bind(&library_id)
+    .execute(&db.write)
+    .await
+    .unwrap();
+    let media_id = Uuid::new_v4().to_string();
+    sqlx::query(
+        "INSERT INTO media_items (id, library_id, media_type, file_path, file_size, title) \
+         VALUES (?, ?, 'movie', '/m/film.mkv', 1000, 'Film')",
+    )
+    .bind(&media_id)
+    .bind(&library_id)
+    .execute(&db.write)
+    .await
+    .unwrap();
+    for (index, stream_type, codec, channels) in [
+        (0, "video", "hevc", None),
+        (1, "audio", "truehd", Some(6)),
+        (2, "audio", "aac", Some(2)),
+    ] {
+        sqlx::query(
+            "INSERT INTO media_streams (media_item_id, stream_index, stream_type, codec_name, \
+                                        channels) \
+             VALUES (?, ?, ?, ?, ?)",
+        )
+        .bind(&media_id)
+        .bind(index)
+        .bind(stream_type)
+        .bind(codec)
+        .bind(channels)
+        .execute(&db.write)
+        .await
+        .unwrap();
+    }
+
+    assert_eq!(
+        stream_repo::get_audio_channels(&db.read, &media_id, 0)
+            .await
+            .unwrap(),
+        Some(6)
+    );
+    assert_eq!(
+        stream_repo::get_audio_channels(&db.read, &media_id, 1)
+            .await
+            .unwrap(),
+        Some(2)
+    );
+    assert_eq!(
+        stream_repo::get_audio_channels(&db.read, &media_id, 2)
+            .await
+            .unwrap(),
+        None
+    );
+    assert_eq!(
+        stream_repo::get_audio_codec(&db.read, &media_id, 1)
+            .await
+            .unwrap()
+            .as_deref(),
+        Some("aac")
+    );
+
+    loudness_repo::save_loudness(&db.write, &media_id, 0, -27.6, -4.5, 18.1, -38.3, 0.6, 1000)
+        .await
+        .unwrap();
+    let row = loudness_repo::get_loudness(&db.read, &media_id, 0, 1000)
+        .await
+        .unwrap()
+        .expect("cached analysis");
+    assert_eq!(row.input_i, -27.6);
+    assert!(loudness_repo::get_loudness(&db.read, &media_id, 1, 1000)
+        .await
+        .unwrap()
+        .is_none());
+    // A replaced file doesn't reuse the old measurement
+    assert!(loudness_repo::get_loudness(&db.read, &media_id, 0, 2000)
+        .await
+        .unwrap()
+        .is_none());
+
+    loudness_repo::save_loudness(&db.write, &media_id, 0, -20.0, -2.0, 9.0, -30.0, 0.1, 2000)
+        .await
+        .unwrap();
+    let row = loudness_repo::get_loudness(&db.read, &media_id, 0, 2000)
+        .await
+        .unwrap()
+        .expect("re-analysis replaces the row");
+    assert_eq!(row.input_i, -20.0);
+}
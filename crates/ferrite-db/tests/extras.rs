@@ -0,0 +1,113 @@
+use ferrite_core::media::LibraryType;
+use ferrite_db::movie_repo::{self, MediaQuery};
+use ferrite_db::{create_pools, extra_repo, library_repo, media_repo};
+use sqlx::SqlitePool;
+use uuid::Uuid;
+
+async fn insert_movie(pool: &SqlitePool, library_id: &Uuid, path: &str, title: &str) -> String {
+    let mut conn = pool.acquire().await.unwrap();
+    let id = media_repo::insert_media_item(
+        &mut conn,
+        library_id,
+        "movie",
+        path,
+        1234,
+        None,
+        Some(title),
+        Some(1995),
+        None,
+    )
+    .await
+    .unwrap();
+    movie_repo::upsert_movie_skeleton(&mut conn, &id, title, Some(1995))
+        .await
+        .unwrap();
+    id
+}
+
+#[tokio::test]
+async fn extras_are_listed_under_their_movie_only() {
+    let db_path = std::env::temp_dir().join(format!("ferrite-db-test-{}.sqlite", Uuid::new_v4()));
+    let db = create_pools(&db_path, 4)
+        .await
+        .expect("failed to create test db pool");
+    let lib = library_repo::create_library(
+        &db.write,
+        "Movies",
+        &["/m".to_string()],
+        LibraryType::Movie,
+        &[],
+    )
+    .await
+    .unwrap();
+    let lib_id = lib.id.to_string();
+
+    let movie = insert_movie(&db.write, &lib.id, "/m/Heat (1995)/Heat (1995).mkv", "Heat").await;
+    let trailer = insert_movie(
+        &db.write,
+        &lib.id,
+        "/m/Heat (1995)/Heat (1995)-trailer.mkv",
+        "Heat",
+    )
+    .await;
+
+    // Indexed as a movie before it was recognised: marking drops the movie row
+    let mut conn = db.write.acquire().await.unwrap();
+    extra_repo::mark_extra(&mut conn, &trailer, "trailer", "Heat")
+        .await
+        .unwrap();
+    drop(conn);
+    extra_repo::set_extra_parent(&db.write, &trailer, Some(&movie), None)
+        .await
+        .unwrap();
+
+    let query = MediaQuery {
+        library_id: Some(&lib_id),
+        page: 1,
+        per_page: 50,
+        ..Default::default()
+    };
+    let listed = movie_repo::list_movies_with_media(&db.read, &query, None)
+        .await
+        .unwrap();
+    assert_eq!(listed.len(), 1);
+    assert_eq!(
+        movie_repo::count_movies_with_media(&db.read, &query)
+            .await
+            .unwrap(),
+        1
+    );
+    assert_eq!(
+        media_repo::count_media_items(&db.read, Some(&lib_id))
+            .await
+            .unwrap(),
+        1
+    );
+    assert!(movie_repo::list_movie_files(&db.read, &lib_id)
+        .await
+        .unwrap()
+        .iter()
+        .all(|f| f.id != trailer));
+
+    let extras = extra_repo::list_extras_for_media(&db.read, &movie)
+        .await
+        .unwrap();
+    assert_eq!(extras.len(), 1);
+    assert_eq!(extras[0].id, trailer);
+    assert_eq!(extras[0].extra_type, "trailer");
+
+    // Removing the movie orphans the extra rather than deleting it
+    media_repo::delete_media_item_by_path(&db.write, "/m/Heat (1995)/Heat (1995).mkv")
+        .await
+        .unwrap();
+    let item = media_repo::get_media_item(&db.read, &trailer)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(item.extra_type.as_deref(), Some("trailer"));
+    let candidates = extra_repo::list_extra_candidates(&db.read, &lib_id)
+        .await
+        .unwrap();
+    assert_eq!(candidates.len(), 1);
+    assert_eq!(candidates[0].extra_parent_media_id, None);
+}
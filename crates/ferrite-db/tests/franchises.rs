@@ -0,0 +1,124 @@
+use ferrite_db::create_pools;
+use ferrite_db::franchise_repo::{self, NewFranchise};
+use ferrite_db::movie_repo::{self, MediaQuery};
+use sqlx::SqlitePool;
+use uuid::Uuid;
+
+async fn new_test_pool() -> ferrite_db::Database {
+    let db_path = std::env::temp_dir().join(format!("ferrite-db-test-{}.sqlite", Uuid::new_v4()));
+    create_pools(&db_path, 4)
+        .await
+        .expect("failed to create test db pool")
+}
+
+async fn seed_movie(pool: &SqlitePool, library_id: &str, title: &str, year: i64) -> String {
+    let id = Uuid::new_v4().to_string();
+    sqlx::query(
+        "INSERT INTO media_items (id, library_id, media_type, file_path, file_size, title, year) \
+         VALUES (?, ?, 'movie', ?, 1, ?, ?)",
+    )
+    .bind(&id)
+    .bind(library_id)
+    .bind(format!("/m/{title}.mkv"))
+    .bind(title)
+    .bind(year)
+    .execute(pool)
+    .await
+    .unwrap();
+    sqlx::query("INSERT INTO movies (media_item_id, title, year) VALUES (?, ?, ?)")
+        .bind(&id)
+        .bind(title)
+        .bind(year)
+        .execute(pool)
+        .await
+        .unwrap();
+    id
+}
+
+fn bond() -> NewFranchise {
+    NewFranchise {
+        tmdb_id: 645,
+        name: "James Bond Collection".to_string(),
+        poster_path: Some("franchise_645_poster.jpg".to_string()),
+        backdrop_path: None,
+    }
+}
+
+#[tokio::test]
+async fn franchises_follow_their_movies() {
+    let db = new_test_pool().await;
+    let library_id = Uuid::new_v4().to_string();
+    sqlx::query(
+        "INSERT INTO libraries (id, name, path, library_type) VALUES (?, 'Movies', '/m', 'movie')",
+    )
+    .bind(&library_id)
+    .execute(&db.write)
+    .await
+    .unwrap();
+
+    let skyfall = seed_movie(&db.write, &library_id, "Skyfall", 2012).await;
+    let dr_no = seed_movie(&db.write, &library_id, "Dr. No", 1962).await;
+    let heat = seed_movie(&db.write, &library_id, "Heat", 1995).await;
+
+    franchise_repo::set_movie_franchise(&db.write, &skyfall, Some(&bond()))
+        .await
+        .unwrap();
+    // A later enrichment without artwork keeps the cached poster
+    let no_art = NewFranchise {
+        poster_path: None,
+        ..bond()
+    };
+    franchise_repo::set_movie_franchise(&db.write, &dr_no, Some(&no_art))
+        .await
+        .unwrap();
+    franchise_repo::set_movie_franchise(&db.write, &heat, None)
+        .await
+        .unwrap();
+
+    let franchises = franchise_repo::list_franchises(&db.read).await.unwrap();
+    assert_eq!(franchises.len(), 1);
+    let franchise = &franchises[0];
+    assert_eq!(franchise.item_count, 2);
+    assert_eq!(
+        franchise.poster_path.as_deref(),
+        Some("franchise_645_poster.jpg")
+    );
+
+    // Members list in release order
+    let query = MediaQuery {
+        franchise_id: Some(&franchise.id),
+        sort_by: Some("year"),
+        page: 1,
+        per_page: 50,
+        ..Default::default()
+    };
+    let members = movie_repo::list_movies_with_media(&db.read, &query, None)
+        .await
+        .unwrap();
+    let ids: Vec<&str> = members.iter().map(|m| m.id.as_str()).collect();
+    assert_eq!(ids, vec![dr_no.as_str(), skyfall.as_str()]);
+
+    // Re-matching one film and deleting the other empties the franchise
+    franchise_repo::set_movie_franchise(&db.write, &dr_no, None)
+        .await
+        .unwrap();
+    sqlx::query("DELETE FROM media_items WHERE id = ?")
+        .bind(&skyfall)
+        .execute(&db.write)
+        .await
+        .unwrap();
+    assert!(franchise_repo::list_franchises(&db.read)
+        .await
+        .unwrap()
+        .is_empty());
+    assert_eq!(
+        franchise_repo::prune_empty_franchises(&db.write)
+            .await
+            .unwrap(),
+        1
+    );
+    assert!(franchise_repo::get_franchise(&db.read, &franchise.id)
+        .await
+        .unwrap()
+        .is_none());
+}
@@ -0,0 +1,86 @@
+use ferrite_core::media::LibraryType;
+use ferrite_db::create_pools;
+use ferrite_db::library_repo::{self, LibraryUpdate};
+use uuid::Uuid;
+
+#[tokio::test]
+async fn libraries_keep_every_folder_and_their_ignore_patterns() {
+    let db_path = std::env::temp_dir().join(format!("ferrite-db-test-{}.sqlite", Uuid::new_v4()));
+    let db = create_pools(&db_path, 4)
+        .await
+        .expect("failed to create test db pool");
+
+    let paths = vec!["/media/movies".to_string(), "/mnt/disk2/movies".to_string()];
+    let lib = library_repo::create_library(
+        &db.write,
+        "Movies",
+        &paths,
+        LibraryType::Movie,
+        &["*sample*".to_string()],
+    )
+    .await
+    .unwrap();
+    assert_eq!(lib.path, "/media/movies");
+    assert_eq!(lib.paths, paths);
+    assert_eq!(lib.ignore_patterns, vec!["*sample*"]);
+
+    // A folder can only belong to one library
+    let taken = library_repo::find_taken_path(&db.read, &["/mnt/disk2/movies".to_string()], None)
+        .await
+        .unwrap();
+    assert_eq!(taken.as_deref(), Some("/mnt/disk2/movies"));
+    let own = library_repo::find_taken_path(
+        &db.read,
+        &["/mnt/disk2/movies".to_string()],
+        Some(&lib.id.to_string()),
+    )
+    .await
+    .unwrap();
+    assert!(own.is_none());
+    // Nor can it contain or sit inside another library's folder
+    for nested in ["/media/movies/4k", "/media", "/mnt/disk2/movies/"] {
+        let taken = library_repo::find_taken_path(&db.read, &[nested.to_string()], None)
+            .await
+            .unwrap();
+        assert_eq!(taken.as_deref(), Some(nested));
+    }
+    let sibling = library_repo::find_taken_path(&db.read, &["/media/movies-4k".to_string()], None)
+        .await
+        .unwrap();
+    assert!(sibling.is_none());
+
+    let new_paths = vec![
+        "/mnt/disk2/movies".to_string(),
+        "/mnt/disk3/movies".to_string(),
+    ];
+    let patterns = vec!["*sample*".to_string(), "Extras/".to_string()];
+    let updated = library_repo::update_library(
+        &db.write,
+        &lib.id.to_string(),
+        &LibraryUpdate {
+            name: Some("Films"),
+            paths: Some(&new_paths),
+            ignore_patterns: Some(&patterns),
+            scan_interval_minutes: Some(30),
+        },
+    )
+    .await
+    .unwrap();
+    assert_eq!(updated.name, "Films");
+    assert_eq!(updated.path, "/mnt/disk2/movies");
+    assert_eq!(updated.paths, new_paths);
+    assert_eq!(updated.ignore_patterns, patterns);
+    assert_eq!(updated.scan_interval_minutes, 30);
+
+    // The dropped folder is free again
+    assert!(
+        library_repo::find_taken_path(&db.read, &["/media/movies".to_string()], None)
+            .await
+            .unwrap()
+            .is_none()
+    );
+
+    let listed = library_repo::list_libraries(&db.read).await.unwrap();
+    assert_eq!(listed.len(), 1);
+    assert_eq!(listed[0].paths, new_paths);
+}
@@ -0,0 +1,102 @@
+use ferrite_db::create_pools;
+use ferrite_db::stats_repo;
+use uuid::Uuid;
+
+#[tokio::test]
+async fn stats_report_library_totals_formats_and_largest_files() {
+    let db_path = std::env::temp_dir().join(format!("ferrite-db-test-{}.sqlite", Uuid::new_v4()));
+    let db = create_pools(&db_path, 4)
+        .await
+        .expect("failed to create test db pool");
+
+    let movies = Uuid::new_v4().to_string();
+    let empty = Uuid::new_v4().to_string();
+    for (id, name) in [(&movies, "Movies"), (&empty, "Anime")] {
+        sqlx::query(
+            "INSERT INTO libraries (id, name, path, library_type) VALUES (?, ?, '/m', 'movie')",
+        )
+        .bind(id)
+        .bind(name)
+        .execute(&db.write)
+        .await
+        .unwrap();
+    }
+    let mut ids = Vec::new();
+    for (name, size, duration) in [("a", 3_000, Some(60_000)), ("b", 9_000, None)] {
+        let id = Uuid::new_v4().to_string();
+        sqlx::query(
+            "INSERT INTO media_items (id, library_id, media_type, file_path, file_size, title, \
+                                      duration_ms, container_format, video_codec, audio_codec, width) \
+             VALUES (?, ?, 'movie', ?, ?, ?, ?, 'matroska', 'hevc', 'eac3', 3840)",
+        )
+        .bind(&id)
+        .bind(&movies)
+        .bind(format!("/m/{name}.mkv"))
+        .bind(size)
+        .bind(name)
+        .bind(duration)
+        .execute(&db.write)
+        .await
+        .unwrap();
+        ids.push(id);
+    }
+    // Extras are left out of every statistic
+    sqlx::query(
+        "INSERT INTO media_items (id, library_id, media_type, file_path, file_size, title, \
+                                  extra_type) \
+         VALUES (?, ?, 'movie', '/m/a-trailer.mkv', 50000, 'a', 'trailer')",
+    )
+    .bind(Uuid::new_v4().to_string())
+    .bind(&movies)
+    .execute(&db.write)
+    .await
+    .unwrap();
+    // Only the first video stream counts
+    for (index, stream_type, dv_profile) in [
+        (0, "audio", None),
+        (1, "video", Some(8)),
+        (2, "video", None),
+    ] {
+        sqlx::query(
+            "INSERT INTO media_streams (media_item_id, stream_index, stream_type, color_transfer, \
+                                        dv_profile, dv_bl_compat_id) \
+             VALUES (?, ?, ?, 'smpte2084', ?, 1)",
+        )
+        .bind(&ids[0])
+        .bind(index)
+        .bind(stream_type)
+        .bind(dv_profile)
+        .execute(&db.write)
+        .await
+        .unwrap();
+    }
+
+    let totals = stats_repo::library_totals(&db.read).await.unwrap();
+    assert_eq!(totals.len(), 2);
+    assert_eq!(totals[0].name, "Anime");
+    assert_eq!(totals[0].file_count, 0);
+    assert_eq!(totals[0].total_size, 0);
+    assert_eq!(totals[1].file_count, 2);
+    assert_eq!(totals[1].total_size, 12_000);
+    assert_eq!(totals[1].total_duration_ms, 60_000);
+
+    let formats = stats_repo::list_media_formats(&db.read, Some(&movies))
+        .await
+        .unwrap();
+    assert_eq!(formats.len(), 2);
+    let first = formats.iter().find(|f| f.file_size == 3_000).unwrap();
+    assert_eq!(first.dv_profile, Some(8));
+    assert_eq!(first.dv_bl_compat_id, Some(1));
+    assert_eq!(first.color_transfer.as_deref(), Some("smpte2084"));
+    let second = formats.iter().find(|f| f.file_size == 9_000).unwrap();
+    assert_eq!(second.dv_profile, None);
+    assert!(!second.hdr10_plus);
+    assert!(stats_repo::list_media_formats(&db.read, Some(&empty))
+        .await
+        .unwrap()
+        .is_empty());
+
+    let largest = stats_repo::largest_files(&db.read, None, 1).await.unwrap();
+    assert_eq!(largest.len(), 1);
+    assert_eq!(largest[0].media_item_id, ids[1]);
+}
@@ -0,0 +1,153 @@
+use ferrite_db::create_pools;
+use ferrite_db::livetv_repo::{self, NewChannel, NewProgram};
+use uuid::Uuid;
+
+async fn new_test_pool() -> ferrite_db::Database {
+    let db_path = std::env::temp_dir().join(format!("ferrite-db-test-{}.sqlite", Uuid::new_v4()));
+    create_pools(&db_path, 4)
+        .await
+        .expect("failed to create test db pool")
+}
+
+fn channel(key: &str, name: &str, number: &str) -> NewChannel {
+    NewChannel {
+        source_key: key.to_string(),
+        name: name.to_string(),
+        number: Some(number.to_string()),
+        logo_url: None,
+        group_title: None,
+        stream_url: format!("http://tuner.local/{key}"),
+        xmltv_id: Some(key.to_string()),
+    }
+}
+
+#[tokio::test]
+async fn sync_keeps_ids_of_existing_channels_and_removes_missing_ones() {
+    let db = new_test_pool().await;
+
+    livetv_repo::sync_channels(
+        &db.write,
+        &[channel("a", "Alpha", "10"), channel("b", "Beta", "2")],
+    )
+    .await
+    .unwrap();
+    let before = livetv_repo::list_channels(&db.read).await.unwrap();
+    assert_eq!(
+        before.iter().map(|c| c.name.as_str()).collect::<Vec<_>>(),
+        vec!["Beta", "Alpha"],
+        "channels are ordered numerically"
+    );
+    let alpha_id = before
+        .iter()
+        .find(|c| c.name == "Alpha")
+        .unwrap()
+        .id
+        .clone();
+
+    let (upserted, removed) = livetv_repo::sync_channels(
+        &db.write,
+        &[channel("a", "Alpha HD", "10"), channel("c", "Gamma", "3")],
+    )
+    .await
+    .unwrap();
+    assert_eq!((upserted, removed), (2, 1));
+
+    let after = livetv_repo::list_channels(&db.read).await.unwrap();
+    assert_eq!(after.len(), 2);
+    let alpha = after.iter().find(|c| c.source_key == "a").unwrap();
+    assert_eq!(alpha.id, alpha_id);
+    assert_eq!(alpha.name, "Alpha HD");
+    assert!(after.iter().all(|c| c.source_key != "b"));
+}
+
+#[tokio::test]
+async fn guide_window_and_recording_schedule() {
+    let db = new_test_pool().await;
+    livetv_repo::sync_channels(&db.write, &[channel("a", "Alpha", "1")])
+        .await
+        .unwrap();
+    let ch = livetv_repo::list_channels(&db.read)
+        .await
+        .unwrap()
+        .remove(0);
+
+    let program = |start: &str, end: &str, title: &str| NewProgram {
+        xmltv_id: "a".into(),
+        start_time: start.into(),
+        end_time: end.into(),
+        title: title.into(),
+        subtitle: None,
+        description: None,
+        category: None,
+        episode_num: None,
+    };
+    livetv_repo::replace_programs(
+        &db.write,
+        &[
+            program("2024-01-01 10:00:00", "2024-01-01 11:00:00", "Morning"),
+            program("2024-01-01 11:00:00", "2024-01-01 12:00:00", "Noon"),
+            program("2024-01-01 12:00:00", "2024-01-01 13:00:00", "Afternoon"),
+        ],
+    )
+    .await
+    .unwrap();
+    let window = livetv_repo::list_channel_programs(
+        &db.read,
+        "a",
+        "2024-01-01 10:30:00",
+        "2024-01-01 12:00:00",
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        window.iter().map(|p| p.title.as_str()).collect::<Vec<_>>(),
+        vec!["Morning", "Noon"]
+    );
+
+    // One recording whose window is open now, one that was missed.
+    let open = livetv_repo::create_recording(
+        &db.write,
+        None,
+        &ch.id,
+        &ch.name,
+        &ch.stream_url,
+        "Now",
+        "2000-01-01 00:00:00",
+        "2999-01-01 00:00:00",
+    )
+    .await
+    .unwrap();
+    let missed = livetv_repo::create_recording(
+        &db.write,
+        None,
+        &ch.id,
+        &ch.name,
+        &ch.stream_url,
+        "Past",
+        "2000-01-01 00:00:00",
+        "2000-01-01 01:00:00",
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(
+        livetv_repo::fail_missed_recordings(&db.write)
+            .await
+            .unwrap(),
+        1
+    );
+    let due = livetv_repo::claim_due_recordings(&db.write).await.unwrap();
+    assert_eq!(due.len(), 1);
+    assert_eq!(due[0].id, open.id);
+    assert_eq!(due[0].status, "recording");
+    assert!(livetv_repo::claim_due_recordings(&db.write)
+        .await
+        .unwrap()
+        .is_empty());
+
+    let missed = livetv_repo::get_recording(&db.read, &missed.id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(missed.status, "failed");
+}
@@ -0,0 +1,110 @@
+use ferrite_db::create_pools;
+use ferrite_db::integrity_repo::{self, IntegrityInsert};
+use uuid::Uuid;
+
+#[tokio::test]
+async fn integrity_checks_track_broken_media_and_replaced_files() {
+    let db_path = std::env::temp_dir().join(format!("ferrite-db-test-{}.sqlite", Uuid::new_v4()));
+    let db = create_pools(&db_path, 4)
+        .await
+        .expect("failed to create test db pool");
+
+    let library_id = Uuid::new_v4().to_string();
+    sqlx::query(
+        "INSERT INTO libraries (id, name, path, library_type) VALUES (?, 'Movies', '/m', 'movie')",
+    )
+    .bind(&library_id)
+    .execute(&db.write)
+    .await
+    .unwrap();
+    let mut ids = Vec::new();
+    for name in ["a", "b", "c"] {
+        let id = Uuid::new_v4().to_string();
+        sqlx::query(
+            "INSERT INTO media_items (id, library_id, media_type, file_path, file_size, title, duration_ms) \
+             VALUES (?, ?, 'movie', ?, 1000, ?, 60000)",
+        )
+        .bind(&id)
+        .bind(&library_id)
+        .bind(format!("/m/{name}.mkv"))
+        .bind(name)
+        .execute(&db.write)
+        .await
+        .unwrap();
+        ids.push(id);
+    }
+
+    let due = integrity_repo::list_due_for_check(&db.read, Some(&library_id), 30, None)
+        .await
+        .unwrap();
+    assert_eq!(due.len(), 3);
+    assert_eq!(due[0].file_path, "/m/a.mkv");
+    assert_eq!(due[0].duration_ms, Some(60000));
+
+    let errors = vec!["[h264] error while decoding MB 12 34".to_string()];
+    for (id, status) in ids.iter().zip(["ok", "damaged", "broken"]) {
+        integrity_repo::save_integrity(
+            &db.write,
+            &IntegrityInsert {
+                media_item_id: id,
+                status,
+                mode: "sample",
+                error_count: if status == "ok" { 0 } else { 3 },
+                errors: if status == "ok" { &[] } else { &errors },
+                decoded_ms: 50000,
+                file_size: 1000,
+            },
+        )
+        .await
+        .unwrap();
+    }
+
+    assert!(
+        integrity_repo::list_due_for_check(&db.read, Some(&library_id), 30, None)
+            .await
+            .unwrap()
+            .is_empty()
+    );
+    // recheck_days = 0 makes everything due again
+    assert_eq!(
+        integrity_repo::list_due_for_check(&db.read, None, 0, Some(2))
+            .await
+            .unwrap()
+            .len(),
+        2
+    );
+
+    let broken = integrity_repo::list_broken_media(&db.read, Some(&library_id))
+        .await
+        .unwrap();
+    let statuses: Vec<_> = broken.iter().map(|b| b.status.as_str()).collect();
+    assert_eq!(statuses, ["broken", "damaged"]);
+    assert_eq!(
+        broken[0].error_summary.as_deref(),
+        Some("[h264] error while decoding MB 12 34")
+    );
+    let ok = integrity_repo::get_integrity(&db.read, &ids[0])
+        .await
+        .unwrap()
+        .expect("stored check");
+    assert_eq!(ok.error_summary, None);
+
+    // Replacing the broken file hides its old result and makes it due again
+    sqlx::query("UPDATE media_items SET file_size = 2000 WHERE id = ?")
+        .bind(&ids[2])
+        .execute(&db.write)
+        .await
+        .unwrap();
+    let broken = integrity_repo::list_broken_media(&db.read, None)
+        .await
+        .unwrap();
+    assert_eq!(broken.len(), 1);
+    assert_eq!(broken[0].media_item_id, ids[1]);
+    let due = integrity_repo::list_due_for_check(&db.read, Some(&library_id), 30, None)
+        .await
+        .unwrap();
+    assert_eq!(due.len(), 1);
+    assert_eq!(due[0].id, ids[2]);
+    let counts = integrity_repo::count_by_status(&db.read).await.unwrap();
+    assert_eq!(counts, [("damaged".to_string(), 1), ("ok".to_string(), 1)]);
+}
@@ -63,6 +63,7 @@ fn query_for<'a>(library_id: &'a str, search: &'a str) -> MediaQuery<'a> {
         sort_dir: None,
         page: 1,
         per_page: 20,
+        ..Default::default()
     }
 }
 
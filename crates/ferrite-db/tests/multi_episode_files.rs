@@ -0,0 +1,197 @@
+use ferrite_core::media::LibraryType;
+use ferrite_db::{create_pools, library_repo, media_repo, tv_repo};
+use sqlx::SqlitePool;
+use uuid::Uuid;
+
+async fn insert_episode_file(pool: &SqlitePool, library_id: &Uuid, path: &str) -> String {
+    let mut conn = pool.acquire().await.unwrap();
+    media_repo::insert_media_item(
+        &mut conn,
+        library_id,
+        "episode",
+        path,
+        1234,
+        None,
+        Some("Show"),
+        None,
+        None,
+    )
+    .await
+    .unwrap()
+}
+
+#[tokio::test]
+async fn one_file_can_hold_several_episodes() {
+    let db_path = std::env::temp_dir().join(format!("ferrite-db-test-{}.sqlite", Uuid::new_v4()));
+    let db = create_pools(&db_path, 4)
+        .await
+        .expect("failed to create test db pool");
+    let lib = library_repo::create_library(
+        &db.write,
+        "Shows",
+        &["/tv".to_string()],
+        LibraryType::Tv,
+        &[],
+    )
+    .await
+    .unwrap();
+    let lib_id = lib.id.to_string();
+
+    let double =
+        insert_episode_file(&db.write, &lib.id, "/tv/Show/Season 1/Show - S01E01E02.mkv").await;
+    let third =
+        insert_episode_file(&db.write, &lib.id, "/tv/Show/Season 1/Show - S01E03.mkv").await;
+
+    let mut conn = db.write.acquire().await.unwrap();
+    let show_id = tv_repo::upsert_tv_show(&mut conn, &lib_id, "Show")
+        .await
+        .unwrap();
+    let season_id = tv_repo::upsert_season(&mut conn, &show_id, 1)
+        .await
+        .unwrap();
+    tv_repo::upsert_episode(&mut conn, &double, &season_id, &[1, 2])
+        .await
+        .unwrap();
+    tv_repo::upsert_episode(&mut conn, &third, &season_id, &[3])
+        .await
+        .unwrap();
+    drop(conn);
+
+    let episodes = tv_repo::list_episodes(&db.read, &season_id, None)
+        .await
+        .unwrap();
+    let listed: Vec<(i64, &str, i64)> = episodes
+        .iter()
+        .map(|e| (e.episode_number, e.media_item_id.as_str(), e.file_position))
+        .collect();
+    assert_eq!(
+        listed,
+        vec![
+            (1, double.as_str(), 0),
+            (2, double.as_str(), 1),
+            (3, third.as_str(), 0)
+        ]
+    );
+
+    // The file is still one media item, shown under its first episode
+    let item = media_repo::get_media_item(&db.read, &double)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(item.episode_number, Some(1));
+    assert_eq!(
+        media_repo::list_media_items(&db.read, Some(&lib_id), 1, 50)
+            .await
+            .unwrap()
+            .len(),
+        2
+    );
+
+    // Up next skips the rest of the same file
+    let next = tv_repo::get_next_episode(&db.read, &double)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(next.media_item_id, third);
+
+    // Re-split: the file now only holds episode 1
+    let mut conn = db.write.acquire().await.unwrap();
+    tv_repo::upsert_episode(&mut conn, &double, &season_id, &[1])
+        .await
+        .unwrap();
+    drop(conn);
+    assert_eq!(
+        tv_repo::get_episode_numbers_for_season(&db.read, &season_id)
+            .await
+            .unwrap(),
+        vec![1, 3]
+    );
+}
+
+#[tokio::test]
+async fn date_based_episodes_move_to_the_provider_numbering() {
+    let db_path = std::env::temp_dir().join(format!("ferrite-db-test-{}.sqlite", Uuid::new_v4()));
+    let db = create_pools(&db_path, 4)
+        .await
+        .expect("failed to create test db pool");
+    let lib = library_repo::create_library(
+        &db.write,
+        "Daily",
+        &["/daily".to_string()],
+        LibraryType::Tv,
+        &[],
+    )
+    .await
+    .unwrap();
+
+    let media = insert_episode_file(&db.write, &lib.id, "/daily/Show/Show 2024-03-15.mkv").await;
+
+    let mut conn = db.write.acquire().await.unwrap();
+    let show_id = tv_repo::upsert_tv_show(&mut conn, &lib.id.to_string(), "Show")
+        .await
+        .unwrap();
+    let provisional = tv_repo::upsert_season(&mut conn, &show_id, 2024)
+        .await
+        .unwrap();
+    tv_repo::upsert_episode(&mut conn, &media, &provisional, &[315])
+        .await
+        .unwrap();
+    tv_repo::mark_episode_unmatched(&mut conn, &media, Some("2024-03-15"), None)
+        .await
+        .unwrap();
+    drop(conn);
+
+    let unmatched = tv_repo::list_unmatched_episodes(&db.read, &show_id)
+        .await
+        .unwrap();
+    assert_eq!(unmatched.len(), 1);
+    assert_eq!(unmatched[0].air_date.as_deref(), Some("2024-03-15"));
+    assert_eq!(
+        (unmatched[0].season_number, unmatched[0].episode_number),
+        (2024, 315)
+    );
+
+    assert!(
+        tv_repo::resolve_episode(&db.write, &show_id, (&provisional, 315), 29, 71)
+            .await
+            .unwrap()
+    );
+    // A slot already held by another episode is refused
+    let mut conn = db.write.acquire().await.unwrap();
+    let other = tv_repo::upsert_season(&mut conn, &show_id, 2024)
+        .await
+        .unwrap();
+    drop(conn);
+    assert!(
+        !tv_repo::resolve_episode(&db.write, &show_id, (&other, 316), 29, 71)
+            .await
+            .unwrap()
+    );
+    let mut conn = db.write.acquire().await.unwrap();
+    sqlx::query("DELETE FROM seasons WHERE id = ?")
+        .bind(&other)
+        .execute(&mut *conn)
+        .await
+        .unwrap();
+    drop(conn);
+
+    let seasons = tv_repo::get_seasons_for_show(&db.read, &show_id)
+        .await
+        .unwrap();
+    assert_eq!(
+        seasons.iter().map(|(_, n)| *n).collect::<Vec<_>>(),
+        vec![29]
+    );
+    assert!(tv_repo::list_unmatched_episodes(&db.read, &show_id)
+        .await
+        .unwrap()
+        .is_empty());
+    let item = media_repo::get_media_item(&db.read, &media)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        (item.season_number, item.episode_number),
+        (Some(29), Some(71))
+    );
+}
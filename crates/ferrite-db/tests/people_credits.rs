@@ -0,0 +1,120 @@
+use ferrite_db::create_pools;
+use ferrite_db::people_repo::{self, CreditTarget, NewCredit};
+use uuid::Uuid;
+
+async fn new_test_pool() -> ferrite_db::Database {
+    let db_path = std::env::temp_dir().join(format!("ferrite-db-test-{}.sqlite", Uuid::new_v4()));
+    create_pools(&db_path, 4)
+        .await
+        .expect("failed to create test db pool")
+}
+
+fn cast(tmdb_id: i64, name: &str, character: &str, order: i64) -> NewCredit {
+    NewCredit {
+        person_tmdb_id: tmdb_id,
+        name: name.to_string(),
+        profile_path: None,
+        credit_type: "cast".to_string(),
+        role: None,
+        department: None,
+        character: Some(character.to_string()),
+        sort_order: order,
+    }
+}
+
+async fn seed_titles(db: &ferrite_db::Database) -> (String, String) {
+    let library_id = Uuid::new_v4().to_string();
+    sqlx::query(
+        "INSERT INTO libraries (id, name, path, library_type) VALUES (?, 'Lib', '/lib', 'movie')",
+    )
+    .bind(&library_id)
+    .execute(&db.write)
+    .await
+    .unwrap();
+    let media_id = Uuid::new_v4().to_string();
+    sqlx::query(
+        "INSERT INTO media_items (id, library_id, media_type, file_path, file_size, title, year) \
+         VALUES (?, ?, 'movie', '/lib/Heat (1995).mkv', 1, 'Heat', 1995)",
+    )
+    .bind(&media_id)
+    .bind(&library_id)
+    .execute(&db.write)
+    .await
+    .unwrap();
+    let show_id = Uuid::new_v4().to_string();
+    sqlx::query(
+        "INSERT INTO tv_shows (id, library_id, title, year) VALUES (?, ?, 'Crime Story', 1986)",
+    )
+    .bind(&show_id)
+    .bind(&library_id)
+    .execute(&db.write)
+    .await
+    .unwrap();
+    (media_id, show_id)
+}
+
+#[tokio::test]
+async fn credits_share_people_across_titles_and_build_a_filmography() {
+    let db = new_test_pool().await;
+    let (movie_id, show_id) = seed_titles(&db).await;
+
+    people_repo::replace_credits(
+        &db.write,
+        CreditTarget::Movie(&movie_id),
+        &[
+            cast(2, "Robert De Niro", "Neil McCauley", 1),
+            cast(1, "Al Pacino", "Vincent Hanna", 0),
+        ],
+    )
+    .await
+    .unwrap();
+    people_repo::replace_credits(
+        &db.write,
+        CreditTarget::Show(&show_id),
+        &[cast(1, "Al Pacino", "Guest", 0)],
+    )
+    .await
+    .unwrap();
+
+    let movie_credits = people_repo::list_credits(&db.read, CreditTarget::Movie(&movie_id))
+        .await
+        .unwrap();
+    assert_eq!(
+        movie_credits
+            .iter()
+            .map(|c| c.name.as_str())
+            .collect::<Vec<_>>(),
+        vec!["Al Pacino", "Robert De Niro"]
+    );
+
+    let pacino = &movie_credits[0];
+    let filmography = people_repo::list_person_credits(&db.read, &pacino.person_id)
+        .await
+        .unwrap();
+    assert_eq!(
+        filmography
+            .iter()
+            .map(|c| (c.media_kind.as_str(), c.title.as_deref()))
+            .collect::<Vec<_>>(),
+        vec![("movie", Some("Heat")), ("show", Some("Crime Story"))]
+    );
+
+    // Re-enriching replaces the title's credits without touching the others.
+    people_repo::replace_credits(
+        &db.write,
+        CreditTarget::Movie(&movie_id),
+        &[cast(2, "Robert De Niro", "Neil McCauley", 0)],
+    )
+    .await
+    .unwrap();
+    let filmography = people_repo::list_person_credits(&db.read, &pacino.person_id)
+        .await
+        .unwrap();
+    assert_eq!(filmography.len(), 1);
+    assert_eq!(filmography[0].media_kind, "show");
+    let person = people_repo::get_person(&db.read, &pacino.person_id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(person.tmdb_id, Some(1));
+}
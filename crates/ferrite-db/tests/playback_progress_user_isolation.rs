@@ -178,6 +178,7 @@ async fn movie_queries_join_progress_for_requesting_user_only() {
         sort_dir: None,
         page: 1,
         per_page: 20,
+        ..Default::default()
     };
 
     let listed = movie_repo::list_movies_with_media(&pools.write, &query, Some(&user_a))
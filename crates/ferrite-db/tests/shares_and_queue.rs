@@ -0,0 +1,162 @@
+use ferrite_db::collection_repo;
+use ferrite_db::create_pools;
+use ferrite_db::queue_repo::{self, QueueWrite};
+use sqlx::SqlitePool;
+use uuid::Uuid;
+
+async fn new_test_pool() -> ferrite_db::Database {
+    let db_path = std::env::temp_dir().join(format!("ferrite-db-test-{}.sqlite", Uuid::new_v4()));
+    create_pools(&db_path, 4)
+        .await
+        .expect("failed to create test db pool")
+}
+
+async fn seed_user(pool: &SqlitePool, username: &str) -> String {
+    let id = Uuid::new_v4().to_string();
+    sqlx::query(
+        "INSERT INTO users (id, username, password_hash) VALUES (?, ?, '$2b$12$placeholder')",
+    )
+    .bind(&id)
+    .bind(username)
+    .execute(pool)
+    .await
+    .unwrap();
+    id
+}
+
+async fn seed_media(pool: &SqlitePool, library_id: &str, title: &str) -> String {
+    let id = Uuid::new_v4().to_string();
+    sqlx::query(
+        "INSERT INTO media_items (id, library_id, media_type, file_path, file_size, title) \
+         VALUES (?, ?, 'movie', ?, 1, ?)",
+    )
+    .bind(&id)
+    .bind(library_id)
+    .bind(format!("/m/{title}.mkv"))
+    .bind(title)
+    .execute(pool)
+    .await
+    .unwrap();
+    id
+}
+
+#[tokio::test]
+async fn shared_collections_are_listed_for_the_sharee() {
+    let db = new_test_pool().await;
+    let alice = seed_user(&db.write, "alice").await;
+    let bob = seed_user(&db.write, "bob").await;
+
+    let playlist =
+        collection_repo::create_collection(&db.write, &alice, "Road trip", "", "playlist")
+            .await
+            .unwrap();
+    assert!(collection_repo::list_collections(&db.read, &bob, None)
+        .await
+        .unwrap()
+        .is_empty());
+
+    collection_repo::share_collection(&db.write, &playlist.id, &bob, false)
+        .await
+        .unwrap();
+    let visible = collection_repo::list_collections(&db.read, &bob, Some("playlist"))
+        .await
+        .unwrap();
+    assert_eq!(visible.len(), 1);
+    assert_eq!(
+        collection_repo::get_share_permission(&db.read, &playlist.id, &bob)
+            .await
+            .unwrap(),
+        Some(false)
+    );
+
+    // Re-sharing upgrades the permission rather than duplicating the share
+    collection_repo::share_collection(&db.write, &playlist.id, &bob, true)
+        .await
+        .unwrap();
+    let shares = collection_repo::list_shares(&db.read, &playlist.id)
+        .await
+        .unwrap();
+    assert_eq!(shares.len(), 1);
+    assert_eq!(shares[0].username, "bob");
+    assert_eq!(
+        collection_repo::get_share_permission(&db.read, &playlist.id, &bob)
+            .await
+            .unwrap(),
+        Some(true)
+    );
+
+    assert!(
+        collection_repo::unshare_collection(&db.write, &playlist.id, &bob)
+            .await
+            .unwrap()
+    );
+    assert_eq!(
+        collection_repo::get_share_permission(&db.read, &playlist.id, &bob)
+            .await
+            .unwrap(),
+        None
+    );
+}
+
+#[tokio::test]
+async fn queue_round_trips_and_drops_deleted_media() {
+    let db = new_test_pool().await;
+    let user = seed_user(&db.write, "carol").await;
+    let library_id = Uuid::new_v4().to_string();
+    sqlx::query(
+        "INSERT INTO libraries (id, name, path, library_type) VALUES (?, 'Movies', '/m', 'movie')",
+    )
+    .bind(&library_id)
+    .execute(&db.write)
+    .await
+    .unwrap();
+    let a = seed_media(&db.write, &library_id, "A").await;
+    let b = seed_media(&db.write, &library_id, "B").await;
+    let c = seed_media(&db.write, &library_id, "C").await;
+
+    let write = QueueWrite {
+        source_collection_id: None,
+        current_position: 1,
+        shuffle: true,
+        repeat_mode: "all",
+        items: vec![(2, c.as_str()), (0, a.as_str()), (1, b.as_str())],
+    };
+    queue_repo::save_queue(&mut db.write.acquire().await.unwrap(), &user, &write)
+        .await
+        .unwrap();
+
+    let mut conn = db.read.acquire().await.unwrap();
+    let queue = queue_repo::get_queue(&mut conn, &user)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(queue.current_position, 1);
+    assert_eq!(queue.shuffle, 1);
+    assert_eq!(queue.repeat_mode, "all");
+    let entries = queue_repo::list_queue_entries(&db.read, &user)
+        .await
+        .unwrap();
+    let titles: Vec<_> = entries.iter().map(|e| e.title.as_deref()).collect();
+    assert_eq!(titles, vec![Some("C"), Some("A"), Some("B")]);
+
+    // Deleting media leaves a gap in stored positions
+    sqlx::query("DELETE FROM media_items WHERE id = ?")
+        .bind(&a)
+        .execute(&db.write)
+        .await
+        .unwrap();
+    let items = queue_repo::list_queue_items(&mut conn, &user)
+        .await
+        .unwrap();
+    let positions: Vec<_> = items
+        .iter()
+        .map(|i| (i.position, i.original_position))
+        .collect();
+    assert_eq!(positions, vec![(0, 2), (2, 1)]);
+
+    assert!(queue_repo::delete_queue(&db.write, &user).await.unwrap());
+    assert!(queue_repo::list_queue_items(&mut conn, &user)
+        .await
+        .unwrap()
+        .is_empty());
+}
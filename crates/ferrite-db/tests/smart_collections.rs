@@ -0,0 +1,249 @@
+use ferrite_db::collection_repo::{self, SmartRules};
+use ferrite_db::create_pools;
+use sqlx::SqlitePool;
+use uuid::Uuid;
+
+async fn new_test_pool() -> ferrite_db::Database {
+    let db_path = std::env::temp_dir().join(format!("ferrite-db-test-{}.sqlite", Uuid::new_v4()));
+    create_pools(&db_path, 4)
+        .await
+        .expect("failed to create test db pool")
+}
+
+async fn seed_library_and_user(pool: &SqlitePool) -> (String, String) {
+    let library_id = Uuid::new_v4().to_string();
+    sqlx::query(
+        "INSERT INTO libraries (id, name, path, library_type) VALUES (?, 'Movies', '/m', 'movie')",
+    )
+    .bind(&library_id)
+    .execute(pool)
+    .await
+    .unwrap();
+    let user_id = Uuid::new_v4().to_string();
+    sqlx::query(
+        "INSERT INTO users (id, username, password_hash) VALUES (?, 'owner', '$2b$12$placeholder')",
+    )
+    .bind(&user_id)
+    .execute(pool)
+    .await
+    .unwrap();
+    (library_id, user_id)
+}
+
+struct Movie<'a> {
+    title: &'a str,
+    year: i64,
+    rating: f64,
+    genres: &'a str,
+    width: i64,
+    codec: &'a str,
+    added_days_ago: i64,
+}
+
+async fn seed_movie(pool: &SqlitePool, library_id: &str, movie: Movie<'_>) -> String {
+    let id = Uuid::new_v4().to_string();
+    sqlx::query(
+        "INSERT INTO media_items \
+           (id, library_id, media_type, file_path, file_size, title, width, video_codec, added_at) \
+         VALUES (?, ?, 'movie', ?, 1, ?, ?, ?, datetime('now', ?))",
+    )
+    .bind(&id)
+    .bind(library_id)
+    .bind(format!("/m/{}.mkv", movie.title))
+    .bind(movie.title)
+    .bind(movie.width)
+    .bind(movie.codec)
+    .bind(format!("-{} days", movie.added_days_ago))
+    .execute(pool)
+    .await
+    .unwrap();
+    sqlx::query(
+        "INSERT INTO movies (media_item_id, title, year, rating, genres) VALUES (?, ?, ?, ?, ?)",
+    )
+    .bind(&id)
+    .bind(movie.title)
+    .bind(movie.year)
+    .bind(movie.rating)
+    .bind(movie.genres)
+    .execute(pool)
+    .await
+    .unwrap();
+    id
+}
+
+#[tokio::test]
+async fn smart_collections_evaluate_rules_at_listing_time() {
+    let db = new_test_pool().await;
+    let (library_id, user_id) = seed_library_and_user(&db.write).await;
+
+    let dune = seed_movie(
+        &db.write,
+        &library_id,
+        Movie {
+            title: "Dune",
+            year: 2021,
+            rating: 8.0,
+            genres: r#"["Science Fiction","Adventure"]"#,
+            width: 3840,
+            codec: "hevc",
+            added_days_ago: 2,
+        },
+    )
+    .await;
+    let arrival = seed_movie(
+        &db.write,
+        &library_id,
+        Movie {
+            title: "Arrival",
+            year: 2016,
+            rating: 7.9,
+            genres: r#"["Science Fiction","Drama"]"#,
+            width: 3840,
+            codec: "HEVC",
+            added_days_ago: 60,
+        },
+    )
+    .await;
+    seed_movie(
+        &db.write,
+        &library_id,
+        Movie {
+            title: "Alien",
+            year: 1979,
+            rating: 8.5,
+            genres: r#"["Science Fiction","Horror"]"#,
+            width: 1920,
+            codec: "h264",
+            added_days_ago: 1,
+        },
+    )
+    .await;
+
+    let rules = SmartRules {
+        library_id: Some(library_id.clone()),
+        genre: Some("Science Fiction".into()),
+        year_min: Some(2000),
+        resolution: Some("4k".into()),
+        video_codec: Some("hevc".into()),
+        unwatched: true,
+        sort_by: Some("year".into()),
+        sort_dir: Some("desc".into()),
+        ..Default::default()
+    };
+    rules.validate().unwrap();
+    let collection =
+        collection_repo::create_smart_collection(&db.write, &user_id, "4K sci-fi", "", &rules)
+            .await
+            .unwrap();
+
+    // Rules survive the round trip through the collections table
+    let stored = collection_repo::get_collection(&db.read, &collection.id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(stored.kind, "smart");
+    assert_eq!(stored.smart_rules().unwrap(), Some(rules.clone()));
+
+    let ids = |rows: Vec<ferrite_db::movie_repo::MovieWithMediaRow>| {
+        rows.into_iter().map(|r| r.id).collect::<Vec<_>>()
+    };
+    let items = collection_repo::list_smart_items(&db.read, &stored, &rules, 1, 50)
+        .await
+        .unwrap();
+    assert_eq!(ids(items), vec![dune.clone(), arrival.clone()]);
+
+    // Finishing a movie drops it from an unwatched collection
+    sqlx::query(
+        "INSERT INTO playback_progress (id, media_item_id, user_id, position_ms, completed) \
+         VALUES (?, ?, ?, 0, 1)",
+    )
+    .bind(Uuid::new_v4().to_string())
+    .bind(&dune)
+    .bind(&user_id)
+    .execute(&db.write)
+    .await
+    .unwrap();
+    let items = collection_repo::list_smart_items(&db.read, &stored, &rules, 1, 50)
+        .await
+        .unwrap();
+    assert_eq!(ids(items), vec![arrival.clone()]);
+
+    // Updating the rules changes membership without touching items
+    let recent = SmartRules {
+        added_within_days: Some(7),
+        ..Default::default()
+    };
+    let updated = collection_repo::update_rules(&db.write, &collection.id, &recent)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        collection_repo::count_smart_items(&db.read, &updated, &recent)
+            .await
+            .unwrap(),
+        2
+    );
+}
+
+#[test]
+fn invalid_rules_are_rejected() {
+    let bad_years = SmartRules {
+        year_min: Some(2020),
+        year_max: Some(1990),
+        ..Default::default()
+    };
+    assert!(bad_years.validate().is_err());
+
+    let bad_resolution = SmartRules {
+        resolution: Some("8k".into()),
+        ..Default::default()
+    };
+    assert!(bad_resolution.validate().is_err());
+
+    assert!(serde_json::from_str::<SmartRules>(r#"{"genres": ["Drama"]}"#).is_err());
+}
+
+#[tokio::test]
+async fn manual_collections_keep_working_after_rebuild() {
+    let db = new_test_pool().await;
+    let (library_id, user_id) = seed_library_and_user(&db.write).await;
+    let movie = seed_movie(
+        &db.write,
+        &library_id,
+        Movie {
+            title: "Heat",
+            year: 1995,
+            rating: 8.3,
+            genres: r#"["Crime"]"#,
+            width: 1920,
+            codec: "h264",
+            added_days_ago: 3,
+        },
+    )
+    .await;
+
+    let playlist = collection_repo::create_collection(&db.write, &user_id, "Queue", "", "playlist")
+        .await
+        .unwrap();
+    assert_eq!(playlist.rules, None);
+    assert_eq!(playlist.smart_rules().unwrap(), None);
+    collection_repo::add_item(&db.write, &playlist.id, &movie)
+        .await
+        .unwrap();
+    assert_eq!(
+        collection_repo::count_items(&db.read, &playlist.id)
+            .await
+            .unwrap(),
+        1
+    );
+
+    // Items still cascade with their collection
+    collection_repo::delete_collection(&db.write, &playlist.id)
+        .await
+        .unwrap();
+    let (left,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM collection_items")
+        .fetch_one(&db.read)
+        .await
+        .unwrap();
+    assert_eq!(left, 0);
+}
@@ -0,0 +1,53 @@
+use ferrite_db::create_pools;
+use ferrite_db::termination_repo::{self, NewTermination};
+use uuid::Uuid;
+
+#[tokio::test]
+async fn terminations_are_listed_newest_first() {
+    let db_path = std::env::temp_dir().join(format!("ferrite-db-test-{}.sqlite", Uuid::new_v4()));
+    let db = create_pools(&db_path, 4)
+        .await
+        .expect("failed to create test db pool");
+
+    termination_repo::record_termination(
+        &db.write,
+        &NewTermination {
+            target_username: Some("dave"),
+            media_id: Some("m1"),
+            playback_session_id: Some("p1"),
+            scope: "session",
+            reason: Some("Server maintenance"),
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+    termination_repo::record_termination(
+        &db.write,
+        &NewTermination {
+            scope: "user",
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+
+    let rows = termination_repo::list_terminations(&db.read, 10, 0)
+        .await
+        .unwrap();
+    assert_eq!(rows.len(), 2);
+    assert_eq!(rows[0].scope, "user");
+    assert_eq!(rows[1].reason.as_deref(), Some("Server maintenance"));
+    assert_eq!(rows[1].playback_session_id.as_deref(), Some("p1"));
+
+    // A bad scope is rejected by the table constraint
+    assert!(termination_repo::record_termination(
+        &db.write,
+        &NewTermination {
+            scope: "everything",
+            ..Default::default()
+        },
+    )
+    .await
+    .is_err());
+}
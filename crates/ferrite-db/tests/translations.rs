@@ -0,0 +1,119 @@
+use ferrite_db::create_pools;
+use ferrite_db::translation_repo::{self, NewTranslation};
+use uuid::Uuid;
+
+async fn new_test_pool() -> ferrite_db::Database {
+    let db_path = std::env::temp_dir().join(format!("ferrite-db-test-{}.sqlite", Uuid::new_v4()));
+    create_pools(&db_path, 4)
+        .await
+        .expect("failed to create test db pool")
+}
+
+fn spanish(title: &str, overview: Option<&str>) -> NewTranslation {
+    NewTranslation {
+        language: "es-MX".to_string(),
+        title: Some(title.to_string()),
+        overview: overview.map(str::to_string),
+        tagline: Some("Eslogan".to_string()),
+        poster_path: None,
+    }
+}
+
+async fn seed_titles(db: &ferrite_db::Database) -> (String, String, String) {
+    let library_id = Uuid::new_v4().to_string();
+    sqlx::query(
+        "INSERT INTO libraries (id, name, path, library_type) VALUES (?, 'Lib', '/lib', 'movie')",
+    )
+    .bind(&library_id)
+    .execute(&db.write)
+    .await
+    .unwrap();
+    let mut movies = Vec::new();
+    for (title, path) in [("Heat", "/lib/Heat.mkv"), ("Ronin", "/lib/Ronin.mkv")] {
+        let id = Uuid::new_v4().to_string();
+        sqlx::query(
+            "INSERT INTO media_items (id, library_id, media_type, file_path, file_size, title) \
+             VALUES (?, ?, 'movie', ?, 1, ?)",
+        )
+        .bind(&id)
+        .bind(&library_id)
+        .bind(path)
+        .bind(title)
+        .execute(&db.write)
+        .await
+        .unwrap();
+        movies.push(id);
+    }
+    let show_id = Uuid::new_v4().to_string();
+    sqlx::query("INSERT INTO tv_shows (id, library_id, title) VALUES (?, ?, 'The Office')")
+        .bind(&show_id)
+        .bind(&library_id)
+        .execute(&db.write)
+        .await
+        .unwrap();
+    (movies.remove(0), movies.remove(0), show_id)
+}
+
+#[tokio::test]
+async fn translations_are_replaced_and_looked_up_per_language() {
+    let db = new_test_pool().await;
+    let (heat, ronin, show) = seed_titles(&db).await;
+
+    translation_repo::replace_movie_translations(
+        &db.write,
+        &heat,
+        &[spanish(
+            "Fuego contra fuego",
+            Some("Un ladrón y un policía."),
+        )],
+    )
+    .await
+    .unwrap();
+    // Re-enrichment replaces the previous set
+    translation_repo::replace_movie_translations(
+        &db.write,
+        &heat,
+        &[spanish("Fuego contra fuego", None)],
+    )
+    .await
+    .unwrap();
+    translation_repo::replace_show_translations(&db.write, &show, &[spanish("La oficina", None)])
+        .await
+        .unwrap();
+
+    let movies =
+        translation_repo::get_movie_translations(&db.read, "es-MX", &[heat.clone(), ronin.clone()])
+            .await
+            .unwrap();
+    assert_eq!(movies.len(), 1);
+    let heat_es = &movies[&heat];
+    assert_eq!(heat_es.title.as_deref(), Some("Fuego contra fuego"));
+    assert_eq!(heat_es.overview, None);
+    assert_eq!(heat_es.tagline.as_deref(), Some("Eslogan"));
+    assert!(!movies.contains_key(&ronin));
+
+    let other =
+        translation_repo::get_movie_translations(&db.read, "de-DE", std::slice::from_ref(&heat))
+            .await
+            .unwrap();
+    assert!(other.is_empty());
+
+    let shows =
+        translation_repo::get_show_translations(&db.read, "es-MX", std::slice::from_ref(&show))
+            .await
+            .unwrap();
+    assert_eq!(shows[&show].title.as_deref(), Some("La oficina"));
+    // Show translations never carry a tagline
+    assert_eq!(shows[&show].tagline, None);
+
+    // Translations go away with their title
+    sqlx::query("DELETE FROM media_items WHERE id = ?")
+        .bind(&heat)
+        .execute(&db.write)
+        .await
+        .unwrap();
+    let gone = translation_repo::get_movie_translations(&db.read, "es-MX", &[heat])
+        .await
+        .unwrap();
+    assert!(gone.is_empty());
+}
@@ -0,0 +1,109 @@
+use ferrite_core::media::LibraryType;
+use ferrite_db::{create_pools, library_repo, media_repo, movie_repo, tv_repo, unmatched_repo};
+use uuid::Uuid;
+
+#[tokio::test]
+async fn unmatched_items_list_unparsed_files_and_failed_matches() {
+    let db_path = std::env::temp_dir().join(format!("ferrite-db-test-{}.sqlite", Uuid::new_v4()));
+    let db = create_pools(&db_path, 4)
+        .await
+        .expect("failed to create test db pool");
+    let movies = library_repo::create_library(
+        &db.write,
+        "Movies",
+        &["/m".to_string()],
+        LibraryType::Movie,
+        &[],
+    )
+    .await
+    .unwrap();
+    let shows = library_repo::create_library(
+        &db.write,
+        "Shows",
+        &["/tv".to_string()],
+        LibraryType::Tv,
+        &[],
+    )
+    .await
+    .unwrap();
+
+    let mut conn = db.write.acquire().await.unwrap();
+    let movie = media_repo::insert_media_item(
+        &mut conn,
+        &movies.id,
+        "movie",
+        "/m/Obscure (1931).mkv",
+        1234,
+        None,
+        Some("Obscure"),
+        Some(1931),
+        None,
+    )
+    .await
+    .unwrap();
+    movie_repo::upsert_movie_skeleton(&mut conn, &movie, "Obscure", Some(1931))
+        .await
+        .unwrap();
+    let unparsed = media_repo::insert_media_item(
+        &mut conn,
+        &shows.id,
+        "episode",
+        "/tv/Show/notes.mkv",
+        1234,
+        None,
+        Some("notes"),
+        None,
+        None,
+    )
+    .await
+    .unwrap();
+    let show_id = tv_repo::upsert_tv_show(&mut conn, &shows.id.to_string(), "Show")
+        .await
+        .unwrap();
+    drop(conn);
+
+    // Nothing has failed to match yet; only the unparsed file is listed
+    let items = unmatched_repo::list_unmatched_items(&db.read, None)
+        .await
+        .unwrap();
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0].reason, "unparsed");
+    assert_eq!(items[0].media_item_id.as_deref(), Some(unparsed.as_str()));
+
+    movie_repo::mark_movie_match_failed(&db.write, &movie)
+        .await
+        .unwrap();
+    tv_repo::mark_show_match_failed(&db.write, &show_id)
+        .await
+        .unwrap();
+    let items = unmatched_repo::list_unmatched_items(&db.read, Some(&movies.id.to_string()))
+        .await
+        .unwrap();
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0].reason, "no_match");
+    assert_eq!(items[0].title.as_deref(), Some("Obscure"));
+    let items = unmatched_repo::list_unmatched_items(&db.read, Some(&shows.id.to_string()))
+        .await
+        .unwrap();
+    let reasons: Vec<(&str, Option<&str>)> = items
+        .iter()
+        .map(|i| (i.reason.as_str(), i.tv_show_id.as_deref()))
+        .collect();
+    assert_eq!(
+        reasons,
+        vec![("no_match", Some(show_id.as_str())), ("unparsed", None)]
+    );
+
+    // A later match takes the movie off the list
+    sqlx::query("UPDATE movies SET tmdb_id = 1 WHERE media_item_id = ?")
+        .bind(&movie)
+        .execute(&db.write)
+        .await
+        .unwrap();
+    assert!(
+        unmatched_repo::list_unmatched_items(&db.read, Some(&movies.id.to_string()))
+            .await
+            .unwrap()
+            .is_empty()
+    );
+}
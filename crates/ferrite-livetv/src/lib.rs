@@ -0,0 +1,25 @@
+pub mod m3u;
+pub mod recorder;
+pub mod xmltv;
+
+use anyhow::{Context, Result};
+
+/// Read a channel list or guide from a local path or an `http(s)://` URL.
+pub async fn read_source(location: &str) -> Result<String> {
+    if location.starts_with("http://") || location.starts_with("https://") {
+        let response = reqwest::Client::builder()
+            .timeout(std::time::Duration::from_secs(60))
+            .user_agent(format!("Ferrite/{}", env!("CARGO_PKG_VERSION")))
+            .build()?
+            .get(location)
+            .send()
+            .await
+            .with_context(|| format!("failed to fetch {location}"))?
+            .error_for_status()?;
+        Ok(response.text().await?)
+    } else {
+        tokio::fs::read_to_string(location)
+            .await
+            .with_context(|| format!("failed to read {location}"))
+    }
+}
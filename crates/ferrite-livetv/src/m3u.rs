@@ -0,0 +1,182 @@
+//! Parser for extended M3U channel lists (`#EXTM3U` / `#EXTINF`) as produced
+//! by IPTV tuners such as HDHomeRun, Threadfin and xTeVe.
+
+/// A single channel entry from an M3U playlist.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct M3uChannel {
+    pub name: String,
+    pub url: String,
+    /// `tvg-id` attribute, used to match the channel against the XMLTV guide.
+    pub tvg_id: Option<String>,
+    pub tvg_name: Option<String>,
+    pub logo: Option<String>,
+    pub group: Option<String>,
+    /// `tvg-chno` (or `channel-number`) attribute.
+    pub number: Option<String>,
+}
+
+impl M3uChannel {
+    /// Stable key used to match the channel across playlist refreshes:
+    /// the `tvg-id` when present, otherwise the stream URL.
+    pub fn source_key(&self) -> &str {
+        self.tvg_id.as_deref().unwrap_or(&self.url)
+    }
+}
+
+/// Parse an M3U playlist. Lines that are not `#EXTINF` entries followed by a
+/// stream URL are ignored; entries without an `#EXTINF` header are kept with
+/// the URL as the name.
+pub fn parse(content: &str) -> Vec<M3uChannel> {
+    let mut channels = Vec::new();
+    let mut pending: Option<M3uChannel> = None;
+
+    for line in content.lines() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        if let Some(info) = line.strip_prefix("#EXTINF:") {
+            pending = Some(parse_extinf(info));
+            continue;
+        }
+        if line.starts_with('#') {
+            continue;
+        }
+        let mut channel = pending.take().unwrap_or_else(|| M3uChannel {
+            name: String::new(),
+            url: String::new(),
+            tvg_id: None,
+            tvg_name: None,
+            logo: None,
+            group: None,
+            number: None,
+        });
+        channel.url = line.to_string();
+        if channel.name.is_empty() {
+            channel.name = channel
+                .tvg_name
+                .clone()
+                .unwrap_or_else(|| channel.url.clone());
+        }
+        channels.push(channel);
+    }
+
+    channels
+}
+
+/// Parse the part of an `#EXTINF` line after the colon:
+/// `-1 tvg-id="x" tvg-name="y",Display Name`.
+fn parse_extinf(info: &str) -> M3uChannel {
+    let (attrs, name) = split_name(info);
+    let mut channel = M3uChannel {
+        name: name.trim().to_string(),
+        url: String::new(),
+        tvg_id: None,
+        tvg_name: None,
+        logo: None,
+        group: None,
+        number: None,
+    };
+
+    for (key, value) in parse_attributes(attrs) {
+        let value = (!value.is_empty()).then_some(value);
+        match key.to_ascii_lowercase().as_str() {
+            "tvg-id" => channel.tvg_id = value,
+            "tvg-name" => channel.tvg_name = value,
+            "tvg-logo" => channel.logo = value,
+            "group-title" => channel.group = value,
+            "tvg-chno" | "channel-number" => channel.number = value,
+            _ => {}
+        }
+    }
+    channel
+}
+
+/// Split the attribute section from the display name at the first comma that
+/// is not inside a quoted attribute value.
+fn split_name(info: &str) -> (&str, &str) {
+    let mut in_quotes = false;
+    for (i, c) in info.char_indices() {
+        match c {
+            '"' => in_quotes = !in_quotes,
+            ',' if !in_quotes => return (&info[..i], &info[i + 1..]),
+            _ => {}
+        }
+    }
+    (info, "")
+}
+
+/// Extract `key="value"` pairs. The leading duration token has no `=` and is skipped.
+fn parse_attributes(attrs: &str) -> Vec<(String, String)> {
+    let mut out = Vec::new();
+    let mut rest = attrs;
+    while let Some(eq) = rest.find('=') {
+        let key = rest[..eq].rsplit(char::is_whitespace).next().unwrap_or("");
+        let after = &rest[eq + 1..];
+        let (value, remaining) = if let Some(quoted) = after.strip_prefix('"') {
+            match quoted.find('"') {
+                Some(end) => (&quoted[..end], &quoted[end + 1..]),
+                None => (quoted, ""),
+            }
+        } else {
+            let end = after.find(char::is_whitespace).unwrap_or(after.len());
+            (&after[..end], &after[end..])
+        };
+        if !key.is_empty() {
+            out.push((key.to_string(), value.trim().to_string()));
+        }
+        rest = remaining;
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_extinf_attributes_and_urls() {
+        let playlist = "#EXTM3U\n\
+            #EXTINF:-1 tvg-id=\"bbc1.uk\" tvg-name=\"BBC One\" tvg-logo=\"http://logo/bbc1.png\" group-title=\"UK, General\" tvg-chno=\"101\",BBC One HD\n\
+            http://tuner.local:5004/auto/v101\n\
+            \n\
+            #EXTINF:-1 channel-number=\"2.1\",News\n\
+            #EXTVLCOPT:http-user-agent=Test\n\
+            http://tuner.local:5004/auto/v2.1\n";
+        let channels = parse(playlist);
+        assert_eq!(channels.len(), 2);
+
+        let bbc = &channels[0];
+        assert_eq!(bbc.name, "BBC One HD");
+        assert_eq!(bbc.url, "http://tuner.local:5004/auto/v101");
+        assert_eq!(bbc.tvg_id.as_deref(), Some("bbc1.uk"));
+        assert_eq!(bbc.tvg_name.as_deref(), Some("BBC One"));
+        assert_eq!(bbc.logo.as_deref(), Some("http://logo/bbc1.png"));
+        assert_eq!(bbc.group.as_deref(), Some("UK, General"));
+        assert_eq!(bbc.number.as_deref(), Some("101"));
+        assert_eq!(bbc.source_key(), "bbc1.uk");
+
+        let news = &channels[1];
+        assert_eq!(news.name, "News");
+        assert_eq!(news.number.as_deref(), Some("2.1"));
+        assert_eq!(news.tvg_id, None);
+        assert_eq!(news.source_key(), "http://tuner.local:5004/auto/v2.1");
+    }
+
+    #[test]
+    fn falls_back_to_tvg_name_or_url_for_missing_names() {
+        let playlist = "#EXTINF:-1 tvg-name=\"Movies\",\nhttp://a/1\nhttp://a/2\n";
+        let channels = parse(playlist);
+        assert_eq!(channels.len(), 2);
+        assert_eq!(channels[0].name, "Movies");
+        assert_eq!(channels[1].name, "http://a/2");
+    }
+
+    #[test]
+    fn empty_attribute_values_are_treated_as_missing() {
+        let channels = parse("#EXTINF:-1 tvg-id=\"\" tvg-logo=\"\",Local\nudp://239.0.0.1:1234\n");
+        assert_eq!(channels[0].tvg_id, None);
+        assert_eq!(channels[0].logo, None);
+        assert_eq!(channels[0].url, "udp://239.0.0.1:1234");
+    }
+}
@@ -0,0 +1,163 @@
+//! DVR recording: copy a live channel stream to an MPEG-TS file for a fixed
+//! duration with ffmpeg (no re-encode).
+
+use anyhow::{Context, Result};
+use std::path::Path;
+use std::process::Stdio;
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::process::Command;
+use tokio::sync::Notify;
+use tracing::{debug, info, warn};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecordingOutcome {
+    /// ffmpeg ran until the requested duration elapsed (or the stream ended).
+    Completed,
+    /// The recording was stopped early; whatever was captured is kept.
+    Stopped,
+}
+
+/// Build ffmpeg arguments that capture `duration_secs` of `stream_url` into
+/// an MPEG-TS file at `output`.
+pub fn build_record_args(stream_url: &str, duration_secs: u64, output: &Path) -> Vec<String> {
+    let mut args: Vec<String> = vec![
+        "-hide_banner".into(),
+        "-loglevel".into(),
+        "warning".into(),
+        "-nostdin".into(),
+        "-y".into(),
+    ];
+    if stream_url.starts_with("http://") || stream_url.starts_with("https://") {
+        // Tuners occasionally drop the connection; reconnect instead of
+        // ending the recording early.
+        args.extend([
+            "-reconnect".into(),
+            "1".into(),
+            "-reconnect_streamed".into(),
+            "1".into(),
+            "-reconnect_delay_max".into(),
+            "10".into(),
+        ]);
+    }
+    args.extend([
+        "-i".into(),
+        stream_url.to_string(),
+        "-t".into(),
+        duration_secs.to_string(),
+        "-map".into(),
+        "0:v?".into(),
+        "-map".into(),
+        "0:a?".into(),
+        "-map".into(),
+        "0:s?".into(),
+        "-c".into(),
+        "copy".into(),
+        "-f".into(),
+        "mpegts".into(),
+        output.to_string_lossy().into_owned(),
+    ]);
+    args
+}
+
+/// Run a recording to completion, or until `stop` is notified.
+pub async fn run_recording(
+    ffmpeg_path: &str,
+    args: &[String],
+    stop: &Notify,
+) -> Result<RecordingOutcome> {
+    debug!("ffmpeg recording args: {:?}", args);
+
+    let mut child = Command::new(ffmpeg_path)
+        .args(args)
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .context("failed to spawn ffmpeg for recording")?;
+
+    let stderr_task = child.stderr.take().map(|stderr| {
+        tokio::spawn(async move {
+            let mut tail: Vec<String> = Vec::new();
+            let mut lines = BufReader::new(stderr).lines();
+            while let Ok(Some(line)) = lines.next_line().await {
+                warn!("ffmpeg recording: {}", line);
+                if tail.len() == 5 {
+                    tail.remove(0);
+                }
+                tail.push(line);
+            }
+            tail
+        })
+    });
+
+    let status = tokio::select! {
+        status = child.wait() => status.context("failed to wait for ffmpeg")?,
+        _ = stop.notified() => {
+            info!("Recording stopped, killing ffmpeg");
+            let _ = child.kill().await;
+            return Ok(RecordingOutcome::Stopped);
+        }
+    };
+    if status.success() {
+        return Ok(RecordingOutcome::Completed);
+    }
+
+    let tail = match stderr_task {
+        Some(task) => task.await.unwrap_or_default(),
+        None => Vec::new(),
+    };
+    if tail.is_empty() {
+        anyhow::bail!("ffmpeg exited with {status}");
+    }
+    anyhow::bail!("ffmpeg exited with {status}: {}", tail.join("; "))
+}
+
+/// Turn a programme title into a safe file name stem.
+pub fn sanitize_file_stem(title: &str) -> String {
+    let cleaned: String = title
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect();
+    let cleaned = cleaned.trim().trim_matches('.').trim();
+    if cleaned.is_empty() {
+        "Recording".to_string()
+    } else {
+        cleaned.chars().take(120).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn http_sources_reconnect_and_copy_streams() {
+        let out = PathBuf::from("/rec/News.ts");
+        let args = build_record_args("http://tuner/auto/v101", 1800, &out);
+        assert!(args.iter().any(|a| a == "-reconnect"));
+        let i = args.iter().position(|a| a == "-i").unwrap();
+        assert_eq!(args[i + 1], "http://tuner/auto/v101");
+        let t = args.iter().position(|a| a == "-t").unwrap();
+        assert_eq!(args[t + 1], "1800");
+        let c = args.iter().position(|a| a == "-c").unwrap();
+        assert_eq!(args[c + 1], "copy");
+        assert_eq!(args.last().unwrap(), "/rec/News.ts");
+    }
+
+    #[test]
+    fn non_http_sources_skip_reconnect_flags() {
+        let args = build_record_args("udp://239.0.0.1:1234", 60, Path::new("out.ts"));
+        assert!(!args.iter().any(|a| a == "-reconnect"));
+    }
+
+    #[test]
+    fn sanitizes_titles() {
+        assert_eq!(sanitize_file_stem("News: 10/11"), "News_ 10_11");
+        assert_eq!(sanitize_file_stem("  ..  "), "Recording");
+    }
+}
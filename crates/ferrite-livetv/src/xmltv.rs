@@ -0,0 +1,291 @@
+//! Minimal XMLTV guide parser: channels and programmes only.
+
+use anyhow::{Context, Result};
+use chrono::{FixedOffset, NaiveDateTime, TimeZone, Utc};
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+
+/// Timestamp format used for guide times once normalised to UTC
+/// (matches SQLite's `datetime('now')`, so times compare as strings).
+pub const TIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct XmltvChannel {
+    pub id: String,
+    pub display_name: Option<String>,
+    pub icon: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct XmltvProgramme {
+    pub channel: String,
+    /// Start time in UTC, formatted with [`TIME_FORMAT`].
+    pub start: String,
+    /// End time in UTC, formatted with [`TIME_FORMAT`].
+    pub stop: String,
+    pub title: String,
+    pub subtitle: Option<String>,
+    pub description: Option<String>,
+    pub category: Option<String>,
+    pub episode_num: Option<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct XmltvGuide {
+    pub channels: Vec<XmltvChannel>,
+    pub programmes: Vec<XmltvProgramme>,
+}
+
+/// Parse an XMLTV document. Programmes without a parseable start/stop time
+/// or title are skipped.
+pub fn parse(content: &str) -> Result<XmltvGuide> {
+    let mut reader = Reader::from_str(content);
+    reader.config_mut().trim_text(true);
+
+    let mut guide = XmltvGuide::default();
+    let mut channel: Option<XmltvChannel> = None;
+    let mut programme: Option<XmltvProgramme> = None;
+    // Element whose text content is currently being collected.
+    let mut field: Option<String> = None;
+    let mut episode_system: Option<String> = None;
+
+    loop {
+        let event = reader
+            .read_event()
+            .with_context(|| format!("invalid XMLTV at byte {}", reader.buffer_position()))?;
+        match event {
+            Event::Start(e) => {
+                let name = String::from_utf8_lossy(e.name().as_ref()).into_owned();
+                match name.as_str() {
+                    "channel" => {
+                        channel = Some(XmltvChannel {
+                            id: attr(&e, "id").unwrap_or_default(),
+                            ..Default::default()
+                        })
+                    }
+                    "programme" => programme = start_programme(&e),
+                    "episode-num" => {
+                        episode_system = attr(&e, "system");
+                        field = Some(name);
+                    }
+                    _ => field = Some(name),
+                }
+            }
+            Event::Empty(e) if e.name().as_ref() == b"icon" => {
+                if let Some(ch) = channel.as_mut() {
+                    ch.icon = attr(&e, "src");
+                }
+            }
+            Event::Text(t) => {
+                let text = t.unescape()?.trim().to_string();
+                if let Some(name) = field.as_deref() {
+                    apply_text(
+                        name,
+                        text,
+                        episode_system.as_deref(),
+                        channel.as_mut(),
+                        programme.as_mut(),
+                    );
+                }
+            }
+            Event::CData(t) => {
+                let text = String::from_utf8_lossy(&t).trim().to_string();
+                if let Some(name) = field.as_deref() {
+                    apply_text(
+                        name,
+                        text,
+                        episode_system.as_deref(),
+                        channel.as_mut(),
+                        programme.as_mut(),
+                    );
+                }
+            }
+            Event::End(e) => match e.name().as_ref() {
+                b"channel" => {
+                    if let Some(ch) = channel.take().filter(|c| !c.id.is_empty()) {
+                        guide.channels.push(ch);
+                    }
+                }
+                b"programme" => {
+                    if let Some(p) = programme.take().filter(|p| !p.title.is_empty()) {
+                        guide.programmes.push(p);
+                    }
+                }
+                _ => field = None,
+            },
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    Ok(guide)
+}
+
+fn start_programme(e: &BytesStart) -> Option<XmltvProgramme> {
+    let channel = attr(e, "channel")?;
+    let start = parse_time(&attr(e, "start")?)?;
+    let stop = parse_time(&attr(e, "stop")?)?;
+    Some(XmltvProgramme {
+        channel,
+        start,
+        stop,
+        ..Default::default()
+    })
+}
+
+fn apply_text(
+    field: &str,
+    text: String,
+    episode_system: Option<&str>,
+    channel: Option<&mut XmltvChannel>,
+    programme: Option<&mut XmltvProgramme>,
+) {
+    if text.is_empty() {
+        return;
+    }
+    if let Some(p) = programme {
+        match field {
+            "title" if p.title.is_empty() => p.title = text,
+            "sub-title" if p.subtitle.is_none() => p.subtitle = Some(text),
+            "desc" if p.description.is_none() => p.description = Some(text),
+            "category" if p.category.is_none() => p.category = Some(text),
+            // Prefer the human-readable onscreen form over xmltv_ns.
+            "episode-num" if p.episode_num.is_none() || episode_system == Some("onscreen") => {
+                p.episode_num = Some(text)
+            }
+            _ => {}
+        }
+    } else if let Some(ch) = channel {
+        if field == "display-name" && ch.display_name.is_none() {
+            ch.display_name = Some(text);
+        }
+    }
+}
+
+fn attr(e: &BytesStart, name: &str) -> Option<String> {
+    e.attributes()
+        .flatten()
+        .find(|a| a.key.as_ref() == name.as_bytes())
+        .and_then(|a| a.unescape_value().ok())
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+}
+
+/// Convert an XMLTV timestamp (`YYYYMMDDhhmmss +hhmm`, with optional
+/// trailing fields and offset) to UTC in [`TIME_FORMAT`]. Times without an
+/// offset are taken as UTC.
+pub fn parse_time(value: &str) -> Option<String> {
+    let value = value.trim();
+    let (stamp, offset) = match value.split_once(char::is_whitespace) {
+        Some((stamp, offset)) => (stamp, Some(offset.trim())),
+        None => (value, None),
+    };
+    if stamp.len() < 8 || !stamp.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    // Pad missing time fields with zeros: "20240101" → "20240101000000".
+    let mut digits: String = stamp.chars().take(14).collect();
+    while digits.len() < 14 {
+        digits.push('0');
+    }
+    let naive = NaiveDateTime::parse_from_str(&digits, "%Y%m%d%H%M%S").ok()?;
+
+    let offset_secs = match offset {
+        Some(o) => parse_offset(o)?,
+        None => 0,
+    };
+    let local = FixedOffset::east_opt(offset_secs)?
+        .from_local_datetime(&naive)
+        .single()?;
+    Some(local.with_timezone(&Utc).format(TIME_FORMAT).to_string())
+}
+
+fn parse_offset(offset: &str) -> Option<i32> {
+    let (sign, rest) = match offset.as_bytes().first()? {
+        b'+' => (1, &offset[1..]),
+        b'-' => (-1, &offset[1..]),
+        _ => return None,
+    };
+    if rest.len() != 4 || !rest.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let hours: i32 = rest[..2].parse().ok()?;
+    let minutes: i32 = rest[2..].parse().ok()?;
+    Some(sign * (hours * 3600 + minutes * 60))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GUIDE: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE tv SYSTEM "xmltv.dtd">
+<tv generator-info-name="test">
+  <channel id="bbc1.uk">
+    <display-name>BBC One</display-name>
+    <display-name>101</display-name>
+    <icon src="http://logo/bbc1.png" />
+  </channel>
+  <programme start="20240101120000 +0100" stop="20240101130000 +0100" channel="bbc1.uk">
+    <title lang="en">News &amp; Weather</title>
+    <sub-title>Lunchtime</sub-title>
+    <desc><![CDATA[The latest headlines.]]></desc>
+    <category>News</category>
+    <episode-num system="xmltv_ns">0.4.</episode-num>
+    <episode-num system="onscreen">S01E05</episode-num>
+  </programme>
+  <programme start="20240101130000 +0100" stop="20240101140000 +0100" channel="bbc1.uk">
+    <title></title>
+  </programme>
+  <programme start="garbage" stop="20240101140000" channel="bbc1.uk">
+    <title>Skipped</title>
+  </programme>
+</tv>"#;
+
+    #[test]
+    fn parses_channels_and_programmes() {
+        let guide = parse(GUIDE).unwrap();
+        assert_eq!(
+            guide.channels,
+            vec![XmltvChannel {
+                id: "bbc1.uk".into(),
+                display_name: Some("BBC One".into()),
+                icon: Some("http://logo/bbc1.png".into()),
+            }]
+        );
+
+        assert_eq!(guide.programmes.len(), 1);
+        let p = &guide.programmes[0];
+        assert_eq!(p.channel, "bbc1.uk");
+        assert_eq!(p.start, "2024-01-01 11:00:00");
+        assert_eq!(p.stop, "2024-01-01 12:00:00");
+        assert_eq!(p.title, "News & Weather");
+        assert_eq!(p.subtitle.as_deref(), Some("Lunchtime"));
+        assert_eq!(p.description.as_deref(), Some("The latest headlines."));
+        assert_eq!(p.category.as_deref(), Some("News"));
+        assert_eq!(p.episode_num.as_deref(), Some("S01E05"));
+    }
+
+    #[test]
+    fn converts_times_to_utc() {
+        assert_eq!(
+            parse_time("20240101120000 +0000").as_deref(),
+            Some("2024-01-01 12:00:00")
+        );
+        assert_eq!(
+            parse_time("20231231230000 -0130").as_deref(),
+            Some("2024-01-01 00:30:00")
+        );
+        assert_eq!(
+            parse_time("202401011200").as_deref(),
+            Some("2024-01-01 12:00:00")
+        );
+        assert_eq!(parse_time("20240101120000 EST"), None);
+        assert_eq!(parse_time("not a time"), None);
+    }
+
+    #[test]
+    fn rejects_malformed_xml() {
+        assert!(parse("<tv><channel id=\"a\"></tv>").is_err());
+    }
+}
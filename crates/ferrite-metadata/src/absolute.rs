@@ -0,0 +1,97 @@
+use crate::provider::{EpisodeRef, SeasonSummary};
+
+/// Converts absolute episode numbers (`Show - 105`, common for anime) to the
+/// provider's season and episode numbers.
+#[derive(Debug, Clone, Default)]
+pub struct AbsoluteMap {
+    order: Vec<EpisodeRef>,
+}
+
+impl AbsoluteMap {
+    /// Count through the regular seasons in order, using each season's
+    /// episode count. Specials (season 0) have no absolute numbers.
+    pub fn from_seasons(seasons: &[SeasonSummary]) -> Self {
+        let mut regular: Vec<&SeasonSummary> =
+            seasons.iter().filter(|s| s.season_number > 0).collect();
+        regular.sort_by_key(|s| s.season_number);
+        let order = regular
+            .into_iter()
+            .flat_map(|s| {
+                (1..=s.episode_count).map(move |episode_number| EpisodeRef {
+                    season_number: s.season_number,
+                    episode_number,
+                })
+            })
+            .collect();
+        Self { order }
+    }
+
+    /// Follow an episode group's order instead of the season layout. Specials
+    /// the group interleaves are skipped.
+    pub fn from_episode_group(episodes: Vec<EpisodeRef>) -> Self {
+        let order = episodes
+            .into_iter()
+            .filter(|e| e.season_number > 0)
+            .collect();
+        Self { order }
+    }
+
+    /// The episode at 1-based absolute position `absolute`.
+    pub fn get(&self, absolute: i64) -> Option<EpisodeRef> {
+        let index = usize::try_from(absolute.checked_sub(1)?).ok()?;
+        self.order.get(index).copied()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn season(season_number: i64, episode_count: i64) -> SeasonSummary {
+        SeasonSummary {
+            season_number,
+            episode_count,
+        }
+    }
+
+    fn at(map: &AbsoluteMap, absolute: i64) -> Option<(i64, i64)> {
+        map.get(absolute)
+            .map(|e| (e.season_number, e.episode_number))
+    }
+
+    #[test]
+    fn season_counts_carry_over_into_later_seasons() {
+        let map = AbsoluteMap::from_seasons(&[season(2, 24), season(0, 5), season(1, 26)]);
+        assert_eq!(at(&map, 1), Some((1, 1)));
+        assert_eq!(at(&map, 26), Some((1, 26)));
+        assert_eq!(at(&map, 27), Some((2, 1)));
+        assert_eq!(at(&map, 50), Some((2, 24)));
+        assert_eq!(at(&map, 51), None);
+        assert_eq!(at(&map, 0), None);
+    }
+
+    #[test]
+    fn episode_groups_keep_their_order_without_specials() {
+        let map = AbsoluteMap::from_episode_group(vec![
+            EpisodeRef {
+                season_number: 1,
+                episode_number: 1,
+            },
+            EpisodeRef {
+                season_number: 0,
+                episode_number: 3,
+            },
+            EpisodeRef {
+                season_number: 1,
+                episode_number: 3,
+            },
+            EpisodeRef {
+                season_number: 1,
+                episode_number: 2,
+            },
+        ]);
+        assert_eq!(at(&map, 2), Some((1, 3)));
+        assert_eq!(at(&map, 3), Some((1, 2)));
+        assert_eq!(at(&map, 4), None);
+    }
+}
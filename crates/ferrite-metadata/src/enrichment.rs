@@ -1,8 +1,17 @@
+use crate::absolute::AbsoluteMap;
 use crate::image_cache::ImageCache;
-use crate::provider::{MetadataProvider, TvSearchResult};
+use crate::provider::{
+    Credit, EpisodeMetadata, Franchise, MetadataProvider, MovieSearchResult, SeasonSummary,
+    TvSearchResult,
+};
 use crate::tmdb;
 use anyhow::Result;
-use ferrite_db::{movie_repo, tv_repo};
+use async_trait::async_trait;
+use ferrite_db::franchise_repo::{self, NewFranchise};
+use ferrite_db::movie_repo;
+use ferrite_db::people_repo::{self, CreditTarget, NewCredit};
+use ferrite_db::translation_repo::{self, NewTranslation};
+use ferrite_db::tv_repo::{self, AbsolutePlacement, UnmatchedEpisodeRow};
 use futures::stream::{self, StreamExt};
 use sqlx::SqlitePool;
 use std::sync::atomic::{AtomicU32, Ordering};
@@ -10,6 +19,156 @@ use std::sync::Arc;
 use tracing::{debug, info, warn};
 
 const EPISODE_STILL_DOWNLOAD_CONCURRENCY: usize = 8;
+const PROFILE_DOWNLOAD_CONCURRENCY: usize = 8;
+
+/// Lets the caller of a library enrichment pass pause or stop it between
+/// titles, e.g. when an administrator pauses or cancels a scan.
+#[async_trait]
+pub trait EnrichmentControl: Send + Sync {
+    /// Wait while paused; `false` once the pass should stop.
+    async fn proceed(&self) -> bool;
+}
+
+/// Checkpoint of an optional control; always proceeds without one.
+async fn proceed(control: Option<&dyn EnrichmentControl>) -> bool {
+    match control {
+        Some(control) => control.proceed().await,
+        None => true,
+    }
+}
+
+/// Cache profile images for a title's credits and convert them for storage.
+/// A failed image download only drops the picture, never the credit.
+async fn prepare_credits(credits: &[Credit], image_cache: &ImageCache) -> Vec<NewCredit> {
+    stream::iter(credits.to_vec())
+        .map(|credit| async move {
+            let profile_path = match credit.profile_path.as_deref() {
+                Some(pp) => match image_cache.ensure_profile(pp, credit.person_tmdb_id).await {
+                    Ok(f) => Some(f),
+                    Err(e) => {
+                        debug!("Profile download failed for '{}': {}", credit.name, e);
+                        None
+                    }
+                },
+                None => None,
+            };
+            NewCredit {
+                person_tmdb_id: credit.person_tmdb_id,
+                name: credit.name,
+                profile_path,
+                credit_type: credit.kind.as_str().to_string(),
+                role: credit.role,
+                department: credit.department,
+                character: credit.character,
+                sort_order: credit.order as i64,
+            }
+        })
+        .buffered(PROFILE_DOWNLOAD_CONCURRENCY)
+        .collect()
+        .await
+}
+
+/// Cache a franchise's artwork and convert it for storage. Failed downloads
+/// only drop the image.
+async fn prepare_franchise(franchise: &Franchise, image_cache: &ImageCache) -> NewFranchise {
+    let poster_path = match franchise.poster_path.as_deref() {
+        Some(pp) => match image_cache
+            .ensure_franchise_image(pp, franchise.tmdb_id, "poster")
+            .await
+        {
+            Ok(f) => Some(f),
+            Err(e) => {
+                debug!(
+                    "Franchise poster download failed for '{}': {}",
+                    franchise.name, e
+                );
+                None
+            }
+        },
+        None => None,
+    };
+    let backdrop_path = match franchise.backdrop_path.as_deref() {
+        Some(bp) => match image_cache
+            .ensure_franchise_image(bp, franchise.tmdb_id, "backdrop")
+            .await
+        {
+            Ok(f) => Some(f),
+            Err(e) => {
+                debug!(
+                    "Franchise backdrop download failed for '{}': {}",
+                    franchise.name, e
+                );
+                None
+            }
+        },
+        None => None,
+    };
+    NewFranchise {
+        tmdb_id: franchise.tmdb_id,
+        name: franchise.name.clone(),
+        poster_path,
+        backdrop_path,
+    }
+}
+
+/// Which kind of title a set of translations is fetched for.
+#[derive(Clone, Copy)]
+enum TitleKind {
+    Movie,
+    Show,
+}
+
+/// Fetch the provider's translation languages for a title and cache any
+/// language-specific posters. A poster identical to the primary one reuses
+/// the primary's cached file. Failed languages are skipped.
+async fn prepare_translations(
+    provider: &dyn MetadataProvider,
+    kind: TitleKind,
+    tmdb_id: i64,
+    primary_remote: Option<&str>,
+    primary_local: Option<&str>,
+    image_cache: &ImageCache,
+) -> Vec<NewTranslation> {
+    let mut out = Vec::new();
+    for language in provider.translation_languages().to_vec() {
+        let fetched = match kind {
+            TitleKind::Movie => provider.get_movie_localized(tmdb_id, &language).await,
+            TitleKind::Show => provider.get_tv_localized(tmdb_id, &language).await,
+        };
+        let localized = match fetched {
+            Ok(l) => l,
+            Err(e) => {
+                debug!(
+                    "Translation fetch failed for TMDB {} ({}): {}",
+                    tmdb_id, language, e
+                );
+                continue;
+            }
+        };
+        let poster_path = match localized.poster_path.as_deref() {
+            Some(pp) if Some(pp) == primary_remote => primary_local.map(str::to_string),
+            Some(pp) => match image_cache
+                .ensure_localized_poster(pp, tmdb_id, &language)
+                .await
+            {
+                Ok(f) => Some(f),
+                Err(e) => {
+                    debug!("Localized poster download failed ({}): {}", language, e);
+                    None
+                }
+            },
+            None => None,
+        };
+        out.push(NewTranslation {
+            language: localized.language,
+            title: localized.title,
+            overview: localized.overview,
+            tagline: localized.tagline,
+            poster_path,
+        });
+    }
+    out
+}
 
 /// Strip a trailing 4-digit year from a title string.
 /// Handles both bare years ("Cosmos 2014") and parenthesized years ("Cosmos (2014)").
@@ -126,14 +285,45 @@ async fn find_best_tv_match(
     None
 }
 
+/// The TMDB movie enrichment would match `title` to, without storing
+/// anything. `None` when the search fails or nothing matches.
+pub async fn find_movie_candidate(
+    provider: &dyn MetadataProvider,
+    title: &str,
+    year: Option<i32>,
+) -> Option<MovieSearchResult> {
+    let results = match provider.search_movie(title, year).await {
+        Ok(r) => r,
+        Err(e) => {
+            warn!("TMDB search failed for '{}': {}", title, e);
+            return None;
+        }
+    };
+    tmdb::pick_best_match(&results, title, year)
+}
+
+/// The TMDB show enrichment would match a show titled `title` to, without
+/// storing anything.
+pub async fn find_show_candidate(
+    provider: &dyn MetadataProvider,
+    title: &str,
+) -> Option<TvSearchResult> {
+    let (search_title, year) = strip_trailing_year(title);
+    find_best_tv_match(provider, &search_title, year)
+        .await
+        .map(|(best, _, _)| best)
+}
+
 /// Enrich all movies in a library that don't have metadata yet.
 /// Searches TMDB for each, downloads poster images, saves to DB.
-/// Returns the number of movies successfully enriched.
+/// `control` is consulted before each movie. Returns the number of movies
+/// successfully enriched.
 pub async fn enrich_library_movies(
     pool: &SqlitePool,
     library_id: &str,
     provider: Arc<dyn MetadataProvider>,
     image_cache: Arc<ImageCache>,
+    control: Option<&dyn EnrichmentControl>,
 ) -> Result<u32> {
     let pending = movie_repo::get_movies_needing_metadata(pool, library_id).await?;
 
@@ -156,6 +346,9 @@ pub async fn enrich_library_movies(
             let enriched = enriched.clone();
             let pool = pool.clone();
             async move {
+                if !proceed(control).await {
+                    return;
+                }
                 let year = item.year.map(|y| y as i32);
 
                 // Search TMDB
@@ -172,6 +365,11 @@ pub async fn enrich_library_movies(
                     Some(m) => m,
                     None => {
                         debug!("No TMDB match for '{}'", item.title);
+                        if let Err(e) =
+                            movie_repo::mark_movie_match_failed(&pool, &item.media_item_id).await
+                        {
+                            warn!("Failed to record missing match for '{}': {}", item.title, e);
+                        }
                         return;
                     }
                 };
@@ -214,6 +412,21 @@ pub async fn enrich_library_movies(
                     None
                 };
 
+                let credits = prepare_credits(&details.credits, &image_cache).await;
+                let franchise = match &details.franchise {
+                    Some(f) => Some(prepare_franchise(f, &image_cache).await),
+                    None => None,
+                };
+                let translations = prepare_translations(
+                    provider.as_ref(),
+                    TitleKind::Movie,
+                    details.tmdb_id,
+                    details.poster_path.as_deref(),
+                    poster_local.as_deref(),
+                    &image_cache,
+                )
+                .await;
+
                 // Save to DB
                 let genres_json = serde_json::to_string(&details.genres).unwrap_or_default();
                 if let Err(e) = movie_repo::update_movie_metadata(
@@ -237,6 +450,33 @@ pub async fn enrich_library_movies(
                     warn!("DB update failed for '{}': {}", item.title, e);
                     return;
                 }
+                if let Err(e) = people_repo::replace_credits(
+                    &pool,
+                    CreditTarget::Movie(&item.media_item_id),
+                    &credits,
+                )
+                .await
+                {
+                    warn!("Failed to store credits for '{}': {}", item.title, e);
+                }
+                if let Err(e) = translation_repo::replace_movie_translations(
+                    &pool,
+                    &item.media_item_id,
+                    &translations,
+                )
+                .await
+                {
+                    warn!("Failed to store translations for '{}': {}", item.title, e);
+                }
+                if let Err(e) = franchise_repo::set_movie_franchise(
+                    &pool,
+                    &item.media_item_id,
+                    franchise.as_ref(),
+                )
+                .await
+                {
+                    warn!("Failed to store franchise for '{}': {}", item.title, e);
+                }
 
                 info!(
                     "Enriched: '{}' -> TMDB {} ({})",
@@ -254,17 +494,249 @@ pub async fn enrich_library_movies(
         "Metadata enrichment complete: {}/{} movies enriched",
         enriched, pending_count
     );
+
+    // Movies that left a franchise (removed or re-matched) can leave it empty
+    match franchise_repo::prune_empty_franchises(pool).await {
+        Ok(n) if n > 0 => info!("Removed {} empty franchises", n),
+        Ok(_) => {}
+        Err(e) => warn!("Failed to prune empty franchises: {}", e),
+    }
     Ok(enriched)
 }
 
+/// Match a show's date-based episodes and title-only specials against the
+/// provider and move them to its season/episode numbering, so the regular
+/// episode metadata pass picks them up. `seasons` are the provider's
+/// seasons; they are fetched when `None` and needed. Returns how many
+/// episodes were resolved.
+async fn resolve_unmatched_episodes(
+    pool: &SqlitePool,
+    provider: &dyn MetadataProvider,
+    show_id: &str,
+    title: &str,
+    tmdb_id: i64,
+    seasons: Option<&[SeasonSummary]>,
+) -> u32 {
+    let unmatched = match tv_repo::list_unmatched_episodes(pool, show_id).await {
+        Ok(rows) => rows,
+        Err(e) => {
+            warn!("Failed to list unmatched episodes of '{}': {}", title, e);
+            return 0;
+        }
+    };
+    if unmatched.is_empty() {
+        return 0;
+    }
+
+    // Specials only need season 0; air dates can land in any season
+    let seasons: Vec<i64> = if unmatched.iter().any(|e| e.air_date.is_some()) {
+        let summaries = match seasons {
+            Some(seasons) => seasons.to_vec(),
+            None => match provider.get_tv_details(tmdb_id).await {
+                Ok(details) => details.seasons,
+                Err(e) => {
+                    warn!("TMDB TV details failed for '{}': {}", title, e);
+                    return 0;
+                }
+            },
+        };
+        summaries.iter().map(|s| s.season_number).collect()
+    } else {
+        vec![0]
+    };
+    let mut listings: Vec<(i64, Vec<EpisodeMetadata>)> = Vec::with_capacity(seasons.len());
+    for season in seasons {
+        match provider.get_season_episodes(tmdb_id, season).await {
+            Ok(eps) => listings.push((season, eps)),
+            Err(e) => debug!("TMDB season {} fetch failed for '{}': {}", season, title, e),
+        }
+    }
+
+    let mut resolved = 0;
+    for ep in &unmatched {
+        let Some((season, number)) = match_unmatched_episode(ep, &listings) else {
+            debug!(
+                "No TMDB episode of '{}' matches {}",
+                title,
+                ep.air_date
+                    .as_deref()
+                    .or(ep.title.as_deref())
+                    .unwrap_or("?")
+            );
+            continue;
+        };
+        match tv_repo::resolve_episode(
+            pool,
+            show_id,
+            (&ep.season_id, ep.episode_number),
+            season as u32,
+            number as u32,
+        )
+        .await
+        {
+            Ok(true) => resolved += 1,
+            Ok(false) => debug!(
+                "S{:02}E{:02} of '{}' is already taken by another file",
+                season, number, title
+            ),
+            Err(e) => warn!("Failed to renumber episode of '{}': {}", title, e),
+        }
+    }
+    if resolved > 0 {
+        info!(
+            "Matched {} date-based episode(s) or special(s) of '{}' to TMDB numbering",
+            resolved, title
+        );
+    }
+    resolved
+}
+
+/// Map a show's absolutely numbered episodes onto the provider's seasons,
+/// through the show's episode group when one is set and its season episode
+/// counts otherwise. `seasons` are fetched when `None` and needed. Returns
+/// how many episodes were placed.
+async fn map_absolute_episodes(
+    pool: &SqlitePool,
+    provider: &dyn MetadataProvider,
+    show_id: &str,
+    title: &str,
+    tmdb_id: i64,
+    seasons: Option<&[SeasonSummary]>,
+) -> u32 {
+    let numbers = match tv_repo::list_unmapped_absolute_numbers(pool, show_id).await {
+        Ok(numbers) => numbers,
+        Err(e) => {
+            warn!("Failed to list absolute episodes of '{}': {}", title, e);
+            return 0;
+        }
+    };
+    if numbers.is_empty() {
+        return 0;
+    }
+
+    let group_id = match tv_repo::get_show(pool, show_id).await {
+        Ok(show) => show.and_then(|s| s.episode_group_id),
+        Err(e) => {
+            warn!("Failed to load TV show '{}': {}", title, e);
+            return 0;
+        }
+    };
+    let map = match (group_id, seasons) {
+        (Some(group_id), _) => match provider.get_episode_group_episodes(&group_id).await {
+            Ok(episodes) => AbsoluteMap::from_episode_group(episodes),
+            Err(e) => {
+                warn!(
+                    "TMDB episode group {} failed for '{}': {}",
+                    group_id, title, e
+                );
+                return 0;
+            }
+        },
+        (None, Some(seasons)) => AbsoluteMap::from_seasons(seasons),
+        (None, None) => match provider.get_tv_details(tmdb_id).await {
+            Ok(details) => AbsoluteMap::from_seasons(&details.seasons),
+            Err(e) => {
+                warn!("TMDB TV details failed for '{}': {}", title, e);
+                return 0;
+            }
+        },
+    };
+
+    let placements: Vec<AbsolutePlacement> = numbers
+        .iter()
+        .filter_map(|&absolute| {
+            let target = map.get(absolute)?;
+            Some(AbsolutePlacement {
+                absolute_number: absolute,
+                season_number: target.season_number as u32,
+                episode_number: target.episode_number as u32,
+            })
+        })
+        .collect();
+    if placements.len() < numbers.len() {
+        debug!(
+            "{} absolute episode(s) of '{}' are beyond what TMDB lists",
+            numbers.len() - placements.len(),
+            title
+        );
+    }
+    if placements.is_empty() {
+        return 0;
+    }
+    match tv_repo::place_absolute_episodes(pool, show_id, &placements).await {
+        Ok(placed) => {
+            if placed > 0 {
+                info!(
+                    "Mapped {} absolute episode(s) of '{}' to TMDB seasons",
+                    placed, title
+                );
+            }
+            placed
+        }
+        Err(e) => {
+            warn!("Failed to map absolute episodes of '{}': {}", title, e);
+            0
+        }
+    }
+}
+
+/// The provider's (season, episode) for an unmatched episode: the episode
+/// aired on its date (regular seasons before specials) or, for a special,
+/// the season 0 episode with the same title.
+fn match_unmatched_episode(
+    ep: &UnmatchedEpisodeRow,
+    listings: &[(i64, Vec<EpisodeMetadata>)],
+) -> Option<(i64, i32)> {
+    let ordered = listings
+        .iter()
+        .filter(|(season, _)| *season != 0)
+        .chain(listings.iter().filter(|(season, _)| *season == 0));
+    if let Some(date) = ep.air_date.as_deref() {
+        return ordered
+            .flat_map(|(season, eps)| eps.iter().map(move |e| (*season, e)))
+            .find(|(_, e)| e.air_date.as_deref() == Some(date))
+            .map(|(season, e)| (season, e.episode_number));
+    }
+    let wanted = normalize_episode_title(ep.title.as_deref()?);
+    listings
+        .iter()
+        .filter(|(season, _)| *season == 0)
+        .flat_map(|(_, eps)| eps.iter())
+        .find(|e| {
+            e.title
+                .as_deref()
+                .is_some_and(|t| normalize_episode_title(t) == wanted)
+        })
+        .map(|e| (0, e.episode_number))
+}
+
+/// Lowercase alphanumeric words, so punctuation and spacing don't matter.
+fn normalize_episode_title(title: &str) -> String {
+    title
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() {
+                c.to_lowercase().next().unwrap_or(c)
+            } else {
+                ' '
+            }
+        })
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
 /// Enrich all TV shows in a library that don't have metadata yet.
 /// Searches TMDB for each, downloads poster/backdrop images, saves to DB.
-/// Returns the number of shows successfully enriched.
+/// `control` is consulted before each show. Returns the number of shows
+/// successfully enriched.
 pub async fn enrich_library_shows(
     pool: &SqlitePool,
     library_id: &str,
     provider: Arc<dyn MetadataProvider>,
     image_cache: Arc<ImageCache>,
+    control: Option<&dyn EnrichmentControl>,
 ) -> Result<u32> {
     let pending = tv_repo::get_shows_needing_metadata(pool, library_id).await?;
 
@@ -286,6 +758,9 @@ pub async fn enrich_library_shows(
             let enriched = enriched.clone();
             let pool = pool.clone();
             async move {
+                if !proceed(control).await {
+                    return;
+                }
                 // Strip trailing year from title if present (e.g. "Star Trek Lower Decks 2020" → "Star Trek Lower Decks")
                 let (search_title, parsed_year) = strip_trailing_year(&title);
                 let year_i32 = year.map(|y| y as i32).or(parsed_year);
@@ -298,6 +773,9 @@ pub async fn enrich_library_shows(
                                 "No TMDB match for TV show '{}' (searched: '{}')",
                                 title, search_title
                             );
+                            if let Err(e) = tv_repo::mark_show_match_failed(&pool, &show_id).await {
+                                warn!("Failed to record missing match for '{}': {}", title, e);
+                            }
                             return;
                         }
                     };
@@ -347,6 +825,17 @@ pub async fn enrich_library_shows(
                     None
                 };
 
+                let credits = prepare_credits(&details.credits, &image_cache).await;
+                let translations = prepare_translations(
+                    provider.as_ref(),
+                    TitleKind::Show,
+                    details.tmdb_id,
+                    details.poster_path.as_deref(),
+                    poster_local.as_deref(),
+                    &image_cache,
+                )
+                .await;
+
                 // Save to DB
                 let genres_json = serde_json::to_string(&details.genres).unwrap_or_default();
                 if let Err(e) = tv_repo::update_show_metadata(
@@ -365,6 +854,21 @@ pub async fn enrich_library_shows(
                     warn!("DB update failed for TV show '{}': {}", title, e);
                     return;
                 }
+                if let Err(e) =
+                    people_repo::replace_credits(&pool, CreditTarget::Show(&show_id), &credits)
+                        .await
+                {
+                    warn!("Failed to store credits for TV show '{}': {}", title, e);
+                }
+                if let Err(e) =
+                    translation_repo::replace_show_translations(&pool, &show_id, &translations)
+                        .await
+                {
+                    warn!(
+                        "Failed to store translations for TV show '{}': {}",
+                        title, e
+                    );
+                }
 
                 info!(
                     "Enriched TV: '{}' -> TMDB {} ({})",
@@ -372,6 +876,25 @@ pub async fn enrich_library_shows(
                 );
                 enriched.fetch_add(1, Ordering::Relaxed);
 
+                map_absolute_episodes(
+                    &pool,
+                    provider.as_ref(),
+                    &show_id,
+                    &title,
+                    details.tmdb_id,
+                    Some(&details.seasons),
+                )
+                .await;
+                resolve_unmatched_episodes(
+                    &pool,
+                    provider.as_ref(),
+                    &show_id,
+                    &title,
+                    details.tmdb_id,
+                    Some(&details.seasons),
+                )
+                .await;
+
                 // Fetch episode metadata for every season we have on disk
                 let seasons = match tv_repo::get_seasons_for_show(&pool, &show_id).await {
                     Ok(s) => s,
@@ -477,6 +1000,10 @@ pub async fn enrich_library_shows(
         enriched, pending_count
     );
 
+    if !proceed(control).await {
+        return Ok(enriched);
+    }
+
     // Backfill episode metadata for shows that already have show-level metadata
     // but were enriched before episode fetching was implemented.
     let backfill = tv_repo::get_shows_needing_episode_metadata(pool, library_id).await?;
@@ -496,6 +1023,27 @@ pub async fn enrich_library_shows(
                         Some(id) => id,
                         None => return,
                     };
+                    if !proceed(control).await {
+                        return;
+                    }
+                    map_absolute_episodes(
+                        &pool,
+                        provider.as_ref(),
+                        &show_id,
+                        &title,
+                        tmdb_id,
+                        None,
+                    )
+                    .await;
+                    resolve_unmatched_episodes(
+                        &pool,
+                        provider.as_ref(),
+                        &show_id,
+                        &title,
+                        tmdb_id,
+                        None,
+                    )
+                    .await;
                     let seasons = match tv_repo::get_seasons_for_show(&pool, &show_id).await {
                         Ok(s) => s,
                         Err(e) => {
@@ -612,6 +1160,8 @@ pub async fn enrich_single_show(
                     "No TMDB match for TV show '{}' (searched: '{}')",
                     title, search_title,
                 );
+                let _wp = write_sem.acquire().await.expect("semaphore closed");
+                tv_repo::mark_show_match_failed(pool, show_id).await?;
                 return Ok(false);
             }
         };
@@ -661,9 +1211,38 @@ pub async fn enrich_single_show(
     };
 
     let genres_json = serde_json::to_string(&details.genres).unwrap_or_default();
+    let credits = prepare_credits(&details.credits, image_cache).await;
+    let translations = prepare_translations(
+        provider,
+        TitleKind::Show,
+        details.tmdb_id,
+        details.poster_path.as_deref(),
+        poster_local.as_deref(),
+        image_cache,
+    )
+    .await;
 
     // ── Phase 1: fetch seasons + episode HTTP data (no DB write lock held) ──────
 
+    map_absolute_episodes(
+        pool,
+        provider,
+        show_id,
+        title,
+        details.tmdb_id,
+        Some(&details.seasons),
+    )
+    .await;
+    resolve_unmatched_episodes(
+        pool,
+        provider,
+        show_id,
+        title,
+        details.tmdb_id,
+        Some(&details.seasons),
+    )
+    .await;
+
     // Snapshot seasons now for the HTTP fetch phase. We re-fetch inside the
     // transaction to catch any seasons added between now and the write lock.
     let seasons_snapshot = tv_repo::get_seasons_for_show(pool, show_id)
@@ -831,6 +1410,17 @@ pub async fn enrich_single_show(
     }
 
     tx.commit().await?;
+    if let Err(e) = people_repo::replace_credits(pool, CreditTarget::Show(show_id), &credits).await
+    {
+        warn!("Failed to store credits for TV show '{}': {}", title, e);
+    }
+    if let Err(e) = translation_repo::replace_show_translations(pool, show_id, &translations).await
+    {
+        warn!(
+            "Failed to store translations for TV show '{}': {}",
+            title, e
+        );
+    }
     drop(_wp);
 
     info!(
@@ -867,6 +1457,8 @@ pub async fn enrich_single_movie(
                 title,
                 results.len()
             );
+            let _wp = write_sem.acquire().await.expect("semaphore closed");
+            movie_repo::mark_movie_match_failed(pool, media_item_id).await?;
             return Ok(false);
         }
     };
@@ -907,6 +1499,20 @@ pub async fn enrich_single_movie(
     };
 
     let genres_json = serde_json::to_string(&details.genres).unwrap_or_default();
+    let credits = prepare_credits(&details.credits, image_cache).await;
+    let franchise = match &details.franchise {
+        Some(f) => Some(prepare_franchise(f, image_cache).await),
+        None => None,
+    };
+    let translations = prepare_translations(
+        provider,
+        TitleKind::Movie,
+        details.tmdb_id,
+        details.poster_path.as_deref(),
+        poster_local.as_deref(),
+        image_cache,
+    )
+    .await;
     let _wp = write_sem.acquire().await.expect("semaphore closed");
     if let Err(e) = movie_repo::update_movie_metadata(
         pool,
@@ -929,6 +1535,21 @@ pub async fn enrich_single_movie(
         warn!("DB update failed for '{}': {}", title, e);
         return Ok(false);
     }
+    if let Err(e) =
+        people_repo::replace_credits(pool, CreditTarget::Movie(media_item_id), &credits).await
+    {
+        warn!("Failed to store credits for '{}': {}", title, e);
+    }
+    if let Err(e) =
+        translation_repo::replace_movie_translations(pool, media_item_id, &translations).await
+    {
+        warn!("Failed to store translations for '{}': {}", title, e);
+    }
+    if let Err(e) =
+        franchise_repo::set_movie_franchise(pool, media_item_id, franchise.as_ref()).await
+    {
+        warn!("Failed to store franchise for '{}': {}", title, e);
+    }
     drop(_wp);
 
     info!(
@@ -937,3 +1558,53 @@ pub async fn enrich_single_movie(
     );
     Ok(true)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tmdb_episode(number: i32, title: &str, air_date: &str) -> EpisodeMetadata {
+        EpisodeMetadata {
+            episode_number: number,
+            title: Some(title.into()),
+            overview: None,
+            air_date: Some(air_date.into()),
+            still_path: None,
+        }
+    }
+
+    fn unmatched(title: Option<&str>, air_date: Option<&str>) -> UnmatchedEpisodeRow {
+        UnmatchedEpisodeRow {
+            media_item_id: "m".into(),
+            season_id: "s".into(),
+            season_number: 0,
+            episode_number: 1000,
+            title: title.map(Into::into),
+            air_date: air_date.map(Into::into),
+        }
+    }
+
+    #[test]
+    fn unmatched_episodes_resolve_by_air_date_then_special_title() {
+        let listings = vec![
+            (
+                0,
+                vec![tmdb_episode(3, "The Christmas Invasion", "2024-03-15")],
+            ),
+            (29, vec![tmdb_episode(71, "Guest Night", "2024-03-15")]),
+        ];
+        // Regular seasons win over a special aired the same day
+        assert_eq!(
+            match_unmatched_episode(&unmatched(None, Some("2024-03-15")), &listings),
+            Some((29, 71))
+        );
+        assert_eq!(
+            match_unmatched_episode(&unmatched(Some("the christmas invasion!"), None), &listings),
+            Some((0, 3))
+        );
+        assert_eq!(
+            match_unmatched_episode(&unmatched(Some("Unknown Special"), None), &listings),
+            None
+        );
+    }
+}
@@ -94,4 +94,90 @@ impl ImageCache {
 
         Ok(filename)
     }
+
+    /// Download a TMDB person profile image and cache it locally.
+    /// Uses "w185" size (suitable for cast/crew headshots).
+    /// `person_tmdb_id` is used to create a deterministic filename.
+    /// Returns the local filename.
+    pub async fn ensure_profile(&self, tmdb_path: &str, person_tmdb_id: i64) -> Result<String> {
+        let filename = format!("person_{}_profile.jpg", person_tmdb_id);
+        let local_path = self.cache_dir.join(&filename);
+
+        if local_path.exists() {
+            debug!("Profile already cached: {}", filename);
+            return Ok(filename);
+        }
+
+        let url = format!("{}w185{}", TMDB_IMAGE_BASE, tmdb_path);
+        let bytes = self.client.get(&url).send().await?.bytes().await?;
+        tokio::fs::write(&local_path, &bytes).await?;
+        info!("Cached profile image: {} ({} bytes)", filename, bytes.len());
+
+        Ok(filename)
+    }
+
+    /// Download a language-specific TMDB poster and cache it locally.
+    /// Uses "w500" size, like [`ImageCache::ensure_poster`].
+    /// The language tag is part of the filename so each translation keeps its own artwork.
+    /// Returns the local filename.
+    pub async fn ensure_localized_poster(
+        &self,
+        tmdb_path: &str,
+        tmdb_id: i64,
+        language: &str,
+    ) -> Result<String> {
+        let tag: String = language
+            .chars()
+            .filter(|c| c.is_ascii_alphanumeric() || *c == '-')
+            .collect();
+        let filename = format!("{}_{}_poster.jpg", tmdb_id, tag);
+        let local_path = self.cache_dir.join(&filename);
+
+        if local_path.exists() {
+            debug!("Localized poster already cached: {}", filename);
+            return Ok(filename);
+        }
+
+        let url = format!("{}w500{}", TMDB_IMAGE_BASE, tmdb_path);
+        let bytes = self.client.get(&url).send().await?.bytes().await?;
+        tokio::fs::write(&local_path, &bytes).await?;
+        info!(
+            "Cached localized poster: {} ({} bytes)",
+            filename,
+            bytes.len()
+        );
+
+        Ok(filename)
+    }
+
+    /// Download a TMDB franchise (collection) image and cache it locally.
+    /// `kind` is "poster" (w500) or "backdrop" (w1280). TMDB collection ids can
+    /// equal movie ids, so filenames are prefixed to keep them apart from movie
+    /// artwork. Returns the local filename.
+    pub async fn ensure_franchise_image(
+        &self,
+        tmdb_path: &str,
+        franchise_tmdb_id: i64,
+        kind: &str,
+    ) -> Result<String> {
+        let filename = format!("franchise_{}_{}.jpg", franchise_tmdb_id, kind);
+        let local_path = self.cache_dir.join(&filename);
+
+        if local_path.exists() {
+            debug!("Franchise image already cached: {}", filename);
+            return Ok(filename);
+        }
+
+        let size = if kind == "backdrop" { "w1280" } else { "w500" };
+        let url = format!("{}{}{}", TMDB_IMAGE_BASE, size, tmdb_path);
+        let bytes = self.client.get(&url).send().await?.bytes().await?;
+        tokio::fs::write(&local_path, &bytes).await?;
+        info!(
+            "Cached franchise image: {} ({} bytes)",
+            filename,
+            bytes.len()
+        );
+
+        Ok(filename)
+    }
 }
@@ -1,3 +1,4 @@
+pub mod absolute;
 pub mod enrichment;
 pub mod image_cache;
 pub mod provider;
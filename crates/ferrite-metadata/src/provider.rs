@@ -27,6 +27,51 @@ pub struct MovieDetails {
     pub poster_path: Option<String>,
     pub backdrop_path: Option<String>,
     pub genres: Vec<String>,
+    pub credits: Vec<Credit>,
+    /// Franchise the movie belongs to (TMDB `belongs_to_collection`)
+    pub franchise: Option<Franchise>,
+}
+
+/// A movie franchise, e.g. "James Bond Collection".
+#[derive(Debug, Clone)]
+pub struct Franchise {
+    pub tmdb_id: i64,
+    pub name: String,
+    pub poster_path: Option<String>,
+    pub backdrop_path: Option<String>,
+}
+
+/// Whether a credit is an on-screen role or a production job.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CreditKind {
+    Cast,
+    Crew,
+}
+
+impl CreditKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            CreditKind::Cast => "cast",
+            CreditKind::Crew => "crew",
+        }
+    }
+}
+
+/// A cast or crew credit on a movie or show.
+#[derive(Debug, Clone)]
+pub struct Credit {
+    pub person_tmdb_id: i64,
+    pub name: String,
+    pub kind: CreditKind,
+    /// Crew job (e.g. "Director"); `None` for cast.
+    pub role: Option<String>,
+    /// Crew department (e.g. "Writing"); `None` for cast.
+    pub department: Option<String>,
+    /// Character played; `None` for crew.
+    pub character: Option<String>,
+    /// Billing order as reported by the provider.
+    pub order: i32,
+    pub profile_path: Option<String>,
 }
 
 #[derive(Debug, Clone)]
@@ -52,6 +97,45 @@ pub struct TvShowDetails {
     pub poster_path: Option<String>,
     pub backdrop_path: Option<String>,
     pub genres: Vec<String>,
+    pub credits: Vec<Credit>,
+    /// Seasons the provider lists, specials (0) included.
+    pub seasons: Vec<SeasonSummary>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SeasonSummary {
+    pub season_number: i64,
+    pub episode_count: i64,
+}
+
+/// An alternative episode ordering of a show (TMDB episode group), e.g. the
+/// absolute or DVD order.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct EpisodeGroupSummary {
+    pub id: String,
+    pub name: String,
+    /// TMDB group type: 1 original air date, 2 absolute, 3 DVD, 4 digital,
+    /// 5 story arc, 6 production, 7 TV.
+    pub group_type: i32,
+    pub episode_count: i64,
+    pub group_count: i64,
+}
+
+/// A regular episode referenced from an episode group.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EpisodeRef {
+    pub season_number: i64,
+    pub episode_number: i64,
+}
+
+/// Title text and artwork for a movie or show in one language.
+#[derive(Debug, Clone)]
+pub struct LocalizedMetadata {
+    pub language: String,
+    pub title: Option<String>,
+    pub overview: Option<String>,
+    pub tagline: Option<String>,
+    pub poster_path: Option<String>,
 }
 
 #[derive(Debug, Clone)]
@@ -74,4 +158,15 @@ pub trait MetadataProvider: Send + Sync {
         tmdb_id: i64,
         season_number: i64,
     ) -> Result<Vec<EpisodeMetadata>>;
+    async fn get_episode_groups(&self, tmdb_id: i64) -> Result<Vec<EpisodeGroupSummary>>;
+    /// Episodes of an episode group, in the group's order.
+    async fn get_episode_group_episodes(&self, group_id: &str) -> Result<Vec<EpisodeRef>>;
+
+    /// Languages to fetch translations in, besides the primary language the
+    /// `get_*_details` calls return.
+    fn translation_languages(&self) -> &[String] {
+        &[]
+    }
+    async fn get_movie_localized(&self, tmdb_id: i64, language: &str) -> Result<LocalizedMetadata>;
+    async fn get_tv_localized(&self, tmdb_id: i64, language: &str) -> Result<LocalizedMetadata>;
 }
@@ -1,6 +1,7 @@
 use crate::provider::{
-    EpisodeMetadata, MetadataProvider, MovieDetails, MovieSearchResult, TvSearchResult,
-    TvShowDetails,
+    Credit, CreditKind, EpisodeGroupSummary, EpisodeMetadata, EpisodeRef, Franchise,
+    LocalizedMetadata, MetadataProvider, MovieDetails, MovieSearchResult, SeasonSummary,
+    TvSearchResult, TvShowDetails,
 };
 use anyhow::{Context, Result};
 use async_trait::async_trait;
@@ -17,10 +18,31 @@ use tracing::debug;
 
 const TMDB_BASE_URL: &str = "https://api.themoviedb.org/3";
 
+/// Top-billed cast members kept per title.
+const MAX_CAST: usize = 30;
+
+/// Crew jobs worth browsing by; the full crew list runs to hundreds of entries.
+const KEY_CREW_JOBS: &[&str] = &[
+    "Director",
+    "Screenplay",
+    "Writer",
+    "Novel",
+    "Story",
+    "Creator",
+    "Producer",
+    "Executive Producer",
+    "Original Music Composer",
+    "Director of Photography",
+];
+
 pub struct TmdbProvider {
     client: Client,
     api_key: String,
     rate_limiter: Arc<RateLimiter<NotKeyed, InMemoryState, DefaultClock>>,
+    /// Primary metadata language (TMDB `language` parameter).
+    language: String,
+    /// Additional languages fetched as translations.
+    translations: Vec<String>,
 }
 
 impl TmdbProvider {
@@ -31,8 +53,80 @@ impl TmdbProvider {
             client: Client::new(),
             api_key,
             rate_limiter,
+            language: "en-US".to_string(),
+            translations: Vec::new(),
         }
     }
+
+    /// Set the metadata languages; the first is primary, the rest are translations.
+    pub fn with_languages(mut self, languages: &[String]) -> Self {
+        if let Some((primary, rest)) = languages.split_first() {
+            self.language = primary.clone();
+            self.translations = rest
+                .iter()
+                .filter(|l| !l.eq_ignore_ascii_case(primary))
+                .cloned()
+                .collect();
+        }
+        self
+    }
+
+    /// Query-string suffix selecting the primary language, plus the release
+    /// region when the language tag carries one (e.g. "es-MX" → region MX).
+    fn locale_params(&self) -> String {
+        match region_of(&self.language) {
+            Some(region) => format!("language={}&region={}", self.language, region),
+            None => format!("language={}", self.language),
+        }
+    }
+
+    async fn get_localized(
+        &self,
+        kind: &str,
+        tmdb_id: i64,
+        language: &str,
+    ) -> Result<LocalizedMetadata> {
+        self.rate_limiter.until_ready().await;
+
+        let url = format!(
+            "{}/{}/{}?api_key={}&language={}",
+            TMDB_BASE_URL, kind, tmdb_id, self.api_key, language
+        );
+
+        debug!(
+            "TMDB localized {}: id={} language={}",
+            kind, tmdb_id, language
+        );
+
+        let response = self
+            .client
+            .get(&url)
+            .send()
+            .await
+            .context("TMDB localized details request failed")?;
+
+        let detail: TmdbLocalizedDetail = response
+            .json()
+            .await
+            .context("Failed to parse TMDB localized detail")?;
+
+        // TMDB returns empty strings for fields without a translation.
+        let non_empty = |v: Option<String>| v.filter(|s| !s.trim().is_empty());
+        Ok(LocalizedMetadata {
+            language: language.to_string(),
+            title: non_empty(detail.title.or(detail.name)),
+            overview: non_empty(detail.overview),
+            tagline: non_empty(detail.tagline),
+            poster_path: non_empty(detail.poster_path),
+        })
+    }
+}
+
+/// Region subtag of a language tag: "es-MX" → "MX".
+fn region_of(tag: &str) -> Option<&str> {
+    tag.split(['-', '_'])
+        .nth(1)
+        .filter(|r| r.len() == 2 && r.chars().all(|c| c.is_ascii_alphabetic()))
 }
 
 #[async_trait]
@@ -41,8 +135,11 @@ impl MetadataProvider for TmdbProvider {
         self.rate_limiter.until_ready().await;
 
         let mut url = format!(
-            "{}/search/movie?api_key={}&query={}&language=en-US",
-            TMDB_BASE_URL, self.api_key, title
+            "{}/search/movie?api_key={}&query={}&{}",
+            TMDB_BASE_URL,
+            self.api_key,
+            title,
+            self.locale_params()
         );
         if let Some(y) = year {
             url.push_str(&format!("&year={}", y));
@@ -94,8 +191,11 @@ impl MetadataProvider for TmdbProvider {
         self.rate_limiter.until_ready().await;
 
         let url = format!(
-            "{}/movie/{}?api_key={}&language=en-US",
-            TMDB_BASE_URL, tmdb_id, self.api_key
+            "{}/movie/{}?api_key={}&{}&append_to_response=credits",
+            TMDB_BASE_URL,
+            tmdb_id,
+            self.api_key,
+            self.locale_params()
         );
 
         debug!("TMDB details: id={}", tmdb_id);
@@ -143,6 +243,13 @@ impl MetadataProvider for TmdbProvider {
             poster_path: detail.poster_path,
             backdrop_path: detail.backdrop_path,
             genres,
+            credits: convert_credits(detail.credits),
+            franchise: detail.belongs_to_collection.map(|c| Franchise {
+                tmdb_id: c.id,
+                name: c.name,
+                poster_path: c.poster_path,
+                backdrop_path: c.backdrop_path,
+            }),
         })
     }
 
@@ -150,8 +257,11 @@ impl MetadataProvider for TmdbProvider {
         self.rate_limiter.until_ready().await;
 
         let mut url = format!(
-            "{}/search/tv?api_key={}&query={}&language=en-US",
-            TMDB_BASE_URL, self.api_key, title
+            "{}/search/tv?api_key={}&query={}&{}",
+            TMDB_BASE_URL,
+            self.api_key,
+            title,
+            self.locale_params()
         );
         if let Some(y) = year {
             url.push_str(&format!("&first_air_date_year={}", y));
@@ -206,8 +316,8 @@ impl MetadataProvider for TmdbProvider {
         self.rate_limiter.until_ready().await;
 
         let url = format!(
-            "{}/tv/{}/season/{}?api_key={}&language=en-US",
-            TMDB_BASE_URL, tmdb_id, season_number, self.api_key
+            "{}/tv/{}/season/{}?api_key={}&language={}",
+            TMDB_BASE_URL, tmdb_id, season_number, self.api_key, self.language
         );
 
         debug!(
@@ -247,8 +357,11 @@ impl MetadataProvider for TmdbProvider {
         self.rate_limiter.until_ready().await;
 
         let url = format!(
-            "{}/tv/{}?api_key={}&language=en-US",
-            TMDB_BASE_URL, tmdb_id, self.api_key
+            "{}/tv/{}?api_key={}&{}&append_to_response=credits",
+            TMDB_BASE_URL,
+            tmdb_id,
+            self.api_key,
+            self.locale_params()
         );
 
         debug!("TMDB TV details: id={}", tmdb_id);
@@ -294,8 +407,160 @@ impl MetadataProvider for TmdbProvider {
             poster_path: detail.poster_path,
             backdrop_path: detail.backdrop_path,
             genres,
+            credits: convert_credits(detail.credits),
+            seasons: detail
+                .seasons
+                .unwrap_or_default()
+                .into_iter()
+                .map(|s| SeasonSummary {
+                    season_number: s.season_number,
+                    episode_count: s.episode_count.unwrap_or(0),
+                })
+                .collect(),
+        })
+    }
+
+    async fn get_episode_groups(&self, tmdb_id: i64) -> Result<Vec<EpisodeGroupSummary>> {
+        self.rate_limiter.until_ready().await;
+
+        let url = format!(
+            "{}/tv/{}/episode_groups?api_key={}&language={}",
+            TMDB_BASE_URL, tmdb_id, self.api_key, self.language
+        );
+
+        debug!("TMDB episode groups: show={}", tmdb_id);
+
+        let response = self
+            .client
+            .get(&url)
+            .send()
+            .await
+            .context("TMDB episode groups request failed")?;
+
+        let groups: TmdbEpisodeGroupsResponse = response
+            .json()
+            .await
+            .context("Failed to parse TMDB episode groups")?;
+
+        Ok(groups
+            .results
+            .into_iter()
+            .map(|g| EpisodeGroupSummary {
+                id: g.id,
+                name: g.name,
+                group_type: g.group_type,
+                episode_count: g.episode_count.unwrap_or(0),
+                group_count: g.group_count.unwrap_or(0),
+            })
+            .collect())
+    }
+
+    async fn get_episode_group_episodes(&self, group_id: &str) -> Result<Vec<EpisodeRef>> {
+        self.rate_limiter.until_ready().await;
+
+        let url = format!(
+            "{}/tv/episode_group/{}?api_key={}&language={}",
+            TMDB_BASE_URL, group_id, self.api_key, self.language
+        );
+
+        debug!("TMDB episode group: id={}", group_id);
+
+        let response = self
+            .client
+            .get(&url)
+            .send()
+            .await
+            .context("TMDB episode group request failed")?;
+
+        let detail: TmdbEpisodeGroupDetail = response
+            .json()
+            .await
+            .context("Failed to parse TMDB episode group")?;
+
+        Ok(flatten_episode_group(detail))
+    }
+
+    fn translation_languages(&self) -> &[String] {
+        &self.translations
+    }
+
+    async fn get_movie_localized(&self, tmdb_id: i64, language: &str) -> Result<LocalizedMetadata> {
+        self.get_localized("movie", tmdb_id, language).await
+    }
+
+    async fn get_tv_localized(&self, tmdb_id: i64, language: &str) -> Result<LocalizedMetadata> {
+        self.get_localized("tv", tmdb_id, language).await
+    }
+}
+
+/// Flatten a TMDB credits block: top-billed cast in billing order, then key crew jobs.
+/// Episodes of a group in play order: groups by their `order`, episodes by
+/// theirs within each group.
+fn flatten_episode_group(detail: TmdbEpisodeGroupDetail) -> Vec<EpisodeRef> {
+    let mut groups = detail.groups;
+    groups.sort_by_key(|g| g.order);
+    groups
+        .into_iter()
+        .flat_map(|g| {
+            let mut episodes = g.episodes;
+            episodes.sort_by_key(|e| e.order);
+            episodes.into_iter().map(|e| EpisodeRef {
+                season_number: e.season_number,
+                episode_number: e.episode_number,
+            })
         })
+        .collect()
+}
+
+fn convert_credits(credits: Option<TmdbCredits>) -> Vec<Credit> {
+    let Some(credits) = credits else {
+        return Vec::new();
+    };
+
+    let mut cast = credits.cast.unwrap_or_default();
+    cast.sort_by_key(|c| c.order.unwrap_or(i32::MAX));
+    let mut out: Vec<Credit> = cast
+        .into_iter()
+        .take(MAX_CAST)
+        .enumerate()
+        .map(|(idx, c)| Credit {
+            person_tmdb_id: c.id,
+            name: c.name,
+            kind: CreditKind::Cast,
+            role: None,
+            department: None,
+            character: c.character.filter(|ch| !ch.is_empty()),
+            order: c.order.unwrap_or(idx as i32),
+            profile_path: c.profile_path,
+        })
+        .collect();
+
+    for (idx, c) in credits
+        .crew
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|c| c.job.as_deref().is_some_and(|j| KEY_CREW_JOBS.contains(&j)))
+        .enumerate()
+    {
+        // The same person often holds several jobs; keep one row per job.
+        if out
+            .iter()
+            .any(|o| o.kind == CreditKind::Crew && o.person_tmdb_id == c.id && o.role == c.job)
+        {
+            continue;
+        }
+        out.push(Credit {
+            person_tmdb_id: c.id,
+            name: c.name,
+            kind: CreditKind::Crew,
+            role: c.job,
+            department: c.department,
+            character: None,
+            order: idx as i32,
+            profile_path: c.profile_path,
+        });
     }
+    out
 }
 
 /// Fold a string to ASCII-approximate form for fuzzy comparison.
@@ -429,6 +694,50 @@ struct TmdbMovieDetail {
     poster_path: Option<String>,
     backdrop_path: Option<String>,
     genres: Option<Vec<TmdbGenre>>,
+    credits: Option<TmdbCredits>,
+    belongs_to_collection: Option<TmdbCollectionRef>,
+}
+
+#[derive(Deserialize)]
+struct TmdbCollectionRef {
+    id: i64,
+    name: String,
+    poster_path: Option<String>,
+    backdrop_path: Option<String>,
+}
+
+/// Fields shared by movie and TV detail responses that TMDB translates.
+#[derive(Deserialize)]
+struct TmdbLocalizedDetail {
+    title: Option<String>,
+    name: Option<String>,
+    overview: Option<String>,
+    tagline: Option<String>,
+    poster_path: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct TmdbCredits {
+    cast: Option<Vec<TmdbCastMember>>,
+    crew: Option<Vec<TmdbCrewMember>>,
+}
+
+#[derive(Deserialize)]
+struct TmdbCastMember {
+    id: i64,
+    name: String,
+    character: Option<String>,
+    order: Option<i32>,
+    profile_path: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct TmdbCrewMember {
+    id: i64,
+    name: String,
+    job: Option<String>,
+    department: Option<String>,
+    profile_path: Option<String>,
 }
 
 #[derive(Deserialize)]
@@ -458,6 +767,47 @@ struct TmdbTvDetail {
     poster_path: Option<String>,
     backdrop_path: Option<String>,
     genres: Option<Vec<TmdbGenre>>,
+    credits: Option<TmdbCredits>,
+    seasons: Option<Vec<TmdbSeasonSummary>>,
+}
+
+#[derive(Deserialize)]
+struct TmdbSeasonSummary {
+    season_number: i64,
+    episode_count: Option<i64>,
+}
+
+#[derive(Deserialize)]
+struct TmdbEpisodeGroupsResponse {
+    results: Vec<TmdbEpisodeGroupSummary>,
+}
+
+#[derive(Deserialize)]
+struct TmdbEpisodeGroupSummary {
+    id: String,
+    name: String,
+    #[serde(rename = "type")]
+    group_type: i32,
+    episode_count: Option<i64>,
+    group_count: Option<i64>,
+}
+
+#[derive(Deserialize)]
+struct TmdbEpisodeGroupDetail {
+    groups: Vec<TmdbEpisodeGroup>,
+}
+
+#[derive(Deserialize)]
+struct TmdbEpisodeGroup {
+    order: i64,
+    episodes: Vec<TmdbEpisodeGroupEntry>,
+}
+
+#[derive(Deserialize)]
+struct TmdbEpisodeGroupEntry {
+    order: i64,
+    season_number: i64,
+    episode_number: i64,
 }
 
 #[derive(Deserialize)]
@@ -480,3 +830,82 @@ struct TmdbGenre {
     id: i64,
     name: String,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn languages_split_into_primary_and_translations() {
+        let provider = TmdbProvider::new("key".into(), 4).with_languages(&[
+            "es-MX".to_string(),
+            "en-US".to_string(),
+            "es-MX".to_string(),
+        ]);
+        assert_eq!(provider.locale_params(), "language=es-MX&region=MX");
+        assert_eq!(provider.translation_languages(), &["en-US".to_string()]);
+
+        let plain = TmdbProvider::new("key".into(), 4).with_languages(&["de".to_string()]);
+        assert_eq!(plain.locale_params(), "language=de");
+        assert!(plain.translation_languages().is_empty());
+    }
+
+    #[test]
+    fn credits_keep_billing_order_and_key_crew_only() {
+        let credits: TmdbCredits = serde_json::from_str(
+            r#"{
+                "cast": [
+                    {"id": 2, "name": "Val Kilmer", "character": "Chris", "order": 2, "profile_path": null},
+                    {"id": 1, "name": "Al Pacino", "character": "Vincent Hanna", "order": 0, "profile_path": "/al.jpg"}
+                ],
+                "crew": [
+                    {"id": 9, "name": "Michael Mann", "job": "Director", "department": "Directing", "profile_path": "/mm.jpg"},
+                    {"id": 9, "name": "Michael Mann", "job": "Writer", "department": "Writing", "profile_path": "/mm.jpg"},
+                    {"id": 9, "name": "Michael Mann", "job": "Director", "department": "Directing", "profile_path": "/mm.jpg"},
+                    {"id": 7, "name": "Someone", "job": "Grip", "department": "Crew", "profile_path": null}
+                ]
+            }"#,
+        )
+        .unwrap();
+
+        let out = convert_credits(Some(credits));
+        let summary: Vec<(&str, CreditKind, Option<&str>)> = out
+            .iter()
+            .map(|c| (c.name.as_str(), c.kind, c.role.as_deref()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("Al Pacino", CreditKind::Cast, None),
+                ("Val Kilmer", CreditKind::Cast, None),
+                ("Michael Mann", CreditKind::Crew, Some("Director")),
+                ("Michael Mann", CreditKind::Crew, Some("Writer")),
+            ]
+        );
+        assert_eq!(out[0].character.as_deref(), Some("Vincent Hanna"));
+        assert!(convert_credits(None).is_empty());
+    }
+
+    #[test]
+    fn episode_groups_flatten_in_group_order() {
+        let detail: TmdbEpisodeGroupDetail = serde_json::from_str(
+            r#"{
+                "groups": [
+                    {"order": 2, "episodes": [
+                        {"order": 1, "season_number": 2, "episode_number": 2},
+                        {"order": 0, "season_number": 2, "episode_number": 1}
+                    ]},
+                    {"order": 1, "episodes": [
+                        {"order": 0, "season_number": 1, "episode_number": 1}
+                    ]}
+                ]
+            }"#,
+        )
+        .unwrap();
+        let order: Vec<(i64, i64)> = flatten_episode_group(detail)
+            .iter()
+            .map(|e| (e.season_number, e.episode_number))
+            .collect();
+        assert_eq!(order, vec![(1, 1), (2, 1), (2, 2)]);
+    }
+}
@@ -0,0 +1,266 @@
+//! Dry-run scans: walk and parse a library the way [`crate::scan_library`]
+//! would and report what it would index, without writing to the database.
+
+use crate::filename::{self, ExtraScope, ParsedFilename};
+use crate::{index_title, library_ignore_rules, library_roots, probe, walker};
+use anyhow::Result;
+use ferrite_core::media::{LibraryType, AUDIO_EXTENSIONS, VIDEO_EXTENSIONS};
+use ferrite_db::{library_repo, media_repo};
+use ferrite_metadata::enrichment;
+use ferrite_metadata::provider::MetadataProvider;
+use futures::stream::{self, StreamExt};
+use serde::Serialize;
+use sqlx::SqlitePool;
+use std::collections::{HashMap, HashSet};
+
+/// How a file on disk compares with the index.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FileStatus {
+    New,
+    /// Indexed with a different size; a scan re-probes it.
+    Changed,
+    Unchanged,
+}
+
+/// The TMDB title enrichment would pick for a file.
+#[derive(Debug, Clone, Serialize)]
+pub struct MetadataCandidate {
+    pub tmdb_id: i64,
+    pub title: String,
+    pub year: Option<i32>,
+}
+
+/// What a scan would make of one file.
+#[derive(Debug, Clone, Serialize)]
+pub struct DryRunFile {
+    pub path: String,
+    pub size: u64,
+    pub status: FileStatus,
+    pub parsed: ParsedFilename,
+    /// Set when the file is recognised as an extra of a movie or show.
+    pub extra_type: Option<&'static str>,
+    /// Title (movie) or show name the file would be indexed and matched by.
+    pub title: String,
+    pub year: Option<i32>,
+    /// Whether ffprobe was run on the file.
+    pub probed: bool,
+    pub probe_error: Option<String>,
+    /// `None` when no provider is configured, for extras, or when nothing
+    /// matched.
+    pub candidate: Option<MetadataCandidate>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DryRunReport {
+    pub library_id: String,
+    pub files: Vec<DryRunFile>,
+    /// Indexed files that weren't found, which a scan would relocate (when
+    /// the same content turns up elsewhere) or remove.
+    pub missing: Vec<String>,
+    /// Files that would have been probed past [`DryRunLimits::max_probes`].
+    pub probes_skipped: usize,
+    /// Distinct titles left unsearched past [`DryRunLimits::max_searches`].
+    pub searches_skipped: usize,
+    /// Set when either limit was hit, so the report only covers part of the
+    /// library's probes or searches.
+    pub truncated: bool,
+}
+
+/// Caps on the slow parts of a dry run, which answers within one request.
+#[derive(Debug, Clone, Copy)]
+pub struct DryRunLimits {
+    /// Files probed, in path order; the rest are reported unprobed.
+    pub max_probes: usize,
+    /// Distinct movies or shows searched with the provider.
+    pub max_searches: usize,
+}
+
+impl Default for DryRunLimits {
+    fn default() -> Self {
+        Self {
+            max_probes: 200,
+            max_searches: 50,
+        }
+    }
+}
+
+/// Dry-run a scan of `library_id`. New and changed files are probed, as a
+/// scan would; `probe_unchanged` probes every file to surface unreadable
+/// ones that are already indexed. With a `provider` each distinct movie or
+/// show is searched once to report the candidate enrichment would pick.
+/// Probes and searches stop at `limits`; every file is still walked, parsed
+/// and reported.
+pub async fn dry_run_library(
+    pool: &SqlitePool,
+    library_id: &str,
+    ffprobe_path: &str,
+    concurrent_probes: usize,
+    probe_unchanged: bool,
+    provider: Option<&dyn MetadataProvider>,
+    limits: DryRunLimits,
+) -> Result<DryRunReport> {
+    let library = library_repo::get_library(pool, library_id).await?;
+    let roots = library_roots(&library);
+    let extensions: &[&str] = match library.library_type {
+        LibraryType::Movie | LibraryType::Tv => VIDEO_EXTENSIONS,
+        LibraryType::Music => AUDIO_EXTENSIONS,
+    };
+    let is_movie_library = matches!(library.library_type, LibraryType::Movie);
+    let is_tv_library = matches!(library.library_type, LibraryType::Tv);
+
+    let mut files = walker::walk_roots(&roots, extensions, &library_ignore_rules(&library)).await?;
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+    let indexed: HashMap<String, u64> = media_repo::list_file_identities(pool, library_id)
+        .await?
+        .into_iter()
+        .map(|r| (r.file_path, r.file_size.max(0) as u64))
+        .collect();
+    let walked: HashSet<String> = files
+        .iter()
+        .map(|f| f.path.to_string_lossy().to_string())
+        .collect();
+    let mut missing: Vec<String> = indexed
+        .keys()
+        .filter(|path| !walked.contains(*path))
+        .cloned()
+        .collect();
+    missing.sort();
+    let extra_scope = ExtraScope::new(&roots, files.iter().map(|f| f.path.as_path()));
+
+    // Decide up front which files get probed so the cap applies in path
+    // order rather than in completion order.
+    let mut probes_left = limits.max_probes;
+    let mut probes_skipped = 0;
+    let planned: Vec<_> = files
+        .into_iter()
+        .map(|file| {
+            let path = file.path.to_string_lossy().to_string();
+            let status = match indexed.get(&path) {
+                None => FileStatus::New,
+                Some(&size) if size != file.size => FileStatus::Changed,
+                Some(_) => FileStatus::Unchanged,
+            };
+            let mut probed = probe_unchanged || status != FileStatus::Unchanged;
+            if probed && probes_left == 0 {
+                probed = false;
+                probes_skipped += 1;
+            } else if probed {
+                probes_left -= 1;
+            }
+            (file, path, status, probed)
+        })
+        .collect();
+
+    let mut report: Vec<DryRunFile> = stream::iter(planned)
+        .map(|(file, path, status, probed)| {
+            let extra_scope = &extra_scope;
+            async move {
+                let parsed = filename::parse_path(&file.path);
+                let extra = filename::detect_extra(&file.path, extra_scope);
+                let (title, year) =
+                    index_title(&file.path, &parsed, extra.as_ref(), is_movie_library);
+
+                let probe_error = if probed {
+                    probe::probe_file(ffprobe_path, &file.path)
+                        .await
+                        .err()
+                        .map(|e| e.to_string())
+                } else {
+                    None
+                };
+
+                DryRunFile {
+                    path,
+                    size: file.size,
+                    status,
+                    parsed,
+                    extra_type: extra.map(|e| e.extra_type.as_str()),
+                    title,
+                    year,
+                    probed,
+                    probe_error,
+                    candidate: None,
+                }
+            }
+        })
+        .buffer_unordered(concurrent_probes.max(1))
+        .collect()
+        .await;
+    report.sort_by(|a, b| a.path.cmp(&b.path));
+
+    let mut unsearched: HashSet<(String, Option<i32>)> = HashSet::new();
+    if let Some(provider) = provider {
+        let mut candidates: HashMap<(String, Option<i32>), Option<MetadataCandidate>> =
+            HashMap::new();
+        for file in report.iter_mut() {
+            let Some(key) = search_key(file, is_movie_library, is_tv_library) else {
+                continue;
+            };
+            if !candidates.contains_key(&key) {
+                if candidates.len() >= limits.max_searches {
+                    unsearched.insert(key);
+                    continue;
+                }
+                let found = search(provider, &key.0, key.1, is_movie_library).await;
+                candidates.insert(key.clone(), found);
+            }
+            file.candidate = candidates[&key].clone();
+        }
+    }
+
+    Ok(DryRunReport {
+        library_id: library_id.to_string(),
+        files: report,
+        missing,
+        probes_skipped,
+        searches_skipped: unsearched.len(),
+        truncated: probes_skipped > 0 || !unsearched.is_empty(),
+    })
+}
+
+/// Title and year a file's metadata is searched by, or `None` when
+/// enrichment wouldn't search for it.
+fn search_key(
+    file: &DryRunFile,
+    is_movie_library: bool,
+    is_tv_library: bool,
+) -> Option<(String, Option<i32>)> {
+    if file.extra_type.is_some() || file.title.is_empty() {
+        return None;
+    }
+    if is_movie_library {
+        return Some((file.title.clone(), file.year));
+    }
+    match &file.parsed {
+        ParsedFilename::Episode(episode) if is_tv_library => {
+            Some((episode.show_name.clone(), None))
+        }
+        _ => None,
+    }
+}
+
+async fn search(
+    provider: &dyn MetadataProvider,
+    title: &str,
+    year: Option<i32>,
+    is_movie_library: bool,
+) -> Option<MetadataCandidate> {
+    if is_movie_library {
+        enrichment::find_movie_candidate(provider, title, year)
+            .await
+            .map(|m| MetadataCandidate {
+                tmdb_id: m.tmdb_id,
+                title: m.title,
+                year: m.year,
+            })
+    } else {
+        enrichment::find_show_candidate(provider, title)
+            .await
+            .map(|s| MetadataCandidate {
+                tmdb_id: s.tmdb_id,
+                title: s.title,
+                year: s.year,
+            })
+    }
+}
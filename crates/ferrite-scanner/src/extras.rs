@@ -0,0 +1,283 @@
+use crate::filename::{self, ExtraScope, ParsedFilename};
+use anyhow::Result;
+use ferrite_db::extra_repo::{self, ExtraCandidateRow};
+use ferrite_db::movie_repo;
+use sqlx::SqlitePool;
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+
+/// Extras scope of a library from its indexed files.
+fn extra_scope(roots: &[PathBuf], rows: &[ExtraCandidateRow]) -> ExtraScope {
+    ExtraScope::new(roots, rows.iter().map(|r| Path::new(&r.file_path)))
+}
+
+/// Bring the extra classification of already indexed files in line with
+/// their paths: files that now sit in an extras folder (or were indexed
+/// before extras were recognised) become extras, and extras moved out of one
+/// become regular movies or episodes again.
+///
+/// Runs after every scan, before movie grouping and the empty-season cleanup.
+/// Returns the number of reclassified files.
+pub async fn classify_extras(
+    pool: &SqlitePool,
+    library_id: &str,
+    roots: &[PathBuf],
+    is_movie_library: bool,
+    is_tv_library: bool,
+) -> Result<u64> {
+    let rows = extra_repo::list_extra_candidates(pool, library_id).await?;
+    let scope = extra_scope(roots, &rows);
+    let changes: Vec<(&ExtraCandidateRow, Option<filename::DetectedExtra>)> = rows
+        .iter()
+        .filter_map(|row| {
+            let detected = filename::detect_extra(Path::new(&row.file_path), &scope);
+            let wanted = detected.as_ref().map(|d| d.extra_type.as_str());
+            (wanted != row.extra_type.as_deref()).then_some((row, detected))
+        })
+        .collect();
+    if changes.is_empty() {
+        return Ok(0);
+    }
+
+    let mut tx = pool.begin().await?;
+    for (row, detected) in &changes {
+        match detected {
+            Some(extra) => {
+                extra_repo::mark_extra(&mut tx, &row.id, extra.extra_type.as_str(), &extra.title)
+                    .await?;
+            }
+            None => {
+                extra_repo::clear_extra(&mut tx, &row.id).await?;
+                let stem = Path::new(&row.file_path)
+                    .file_stem()
+                    .map(|s| s.to_string_lossy().to_string())
+                    .unwrap_or_default();
+                if is_movie_library {
+                    let variant = filename::parse_movie_variant(&stem);
+                    movie_repo::upsert_movie_skeleton(
+                        &mut tx,
+                        &row.id,
+                        &variant.title,
+                        variant.year.map(|y| y as i64),
+                    )
+                    .await?;
+                } else if is_tv_library {
+                    if let ParsedFilename::Episode(episode) =
+                        filename::parse_path(Path::new(&row.file_path))
+                    {
+                        crate::index_episode(&mut tx, library_id, &row.id, &episode).await;
+                    }
+                }
+            }
+        }
+    }
+    tx.commit().await?;
+
+    info!(
+        "Reclassified {} file(s) as extras or main content in library {}",
+        changes.len(),
+        library_id
+    );
+    Ok(changes.len() as u64)
+}
+
+/// Link every extra in a library to its movie or show. Runs after movie
+/// grouping so movie extras point at the group primary. Returns the number
+/// of extras whose parent changed.
+pub async fn link_extras(pool: &SqlitePool, library_id: &str, roots: &[PathBuf]) -> Result<u64> {
+    let rows = extra_repo::list_extra_candidates(pool, library_id).await?;
+    let links = plan_extra_links(&rows, &extra_scope(roots, &rows));
+    for link in &links {
+        if let Err(e) = extra_repo::set_extra_parent(
+            pool,
+            &link.media_item_id,
+            link.parent_media_id.as_deref(),
+            link.parent_show_id.as_deref(),
+        )
+        .await
+        {
+            warn!("Failed to link extra {}: {}", link.media_item_id, e);
+        }
+    }
+    Ok(links.len() as u64)
+}
+
+/// Parent assignment for one extra.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExtraLink {
+    pub media_item_id: String,
+    pub parent_media_id: Option<String>,
+    pub parent_show_id: Option<String>,
+}
+
+/// Work out which movie or show each extra belongs to. Only extras whose
+/// parent changes are returned.
+///
+/// A suffix-named extra (`Movie (2010)-trailer.mkv`) belongs to the file with
+/// the matching name beside it. Otherwise the candidates are the regular files
+/// directly in the owning folder or, failing that, anywhere below it (episodes
+/// in season folders). The extra is linked when they all belong to one show or
+/// one movie, and left unlinked when the folder is ambiguous.
+pub fn plan_extra_links(rows: &[ExtraCandidateRow], scope: &ExtraScope) -> Vec<ExtraLink> {
+    let mains: Vec<(&Path, String, &ExtraCandidateRow)> = rows
+        .iter()
+        .filter(|r| r.extra_type.is_none())
+        .filter_map(|r| {
+            let path = Path::new(&r.file_path);
+            let stem = path.file_stem()?.to_string_lossy().to_lowercase();
+            Some((path.parent()?, stem, r))
+        })
+        .collect();
+
+    let mut links = Vec::new();
+    for row in rows.iter().filter(|r| r.extra_type.is_some()) {
+        let Some(extra) = filename::detect_extra(Path::new(&row.file_path), scope) else {
+            continue;
+        };
+        let owner = extra.owner_dir.as_path();
+
+        let by_stem = extra.owner_stem.as_deref().and_then(|stem| {
+            let stem = stem.to_lowercase();
+            mains
+                .iter()
+                .find(|(dir, s, _)| *dir == owner && *s == stem)
+                .map(|(_, _, r)| *r)
+        });
+        let candidates: Vec<&ExtraCandidateRow> = match by_stem {
+            Some(r) => vec![r],
+            None => {
+                let direct: Vec<_> = mains
+                    .iter()
+                    .filter(|(dir, _, _)| *dir == owner)
+                    .map(|(_, _, r)| *r)
+                    .collect();
+                if direct.is_empty() {
+                    mains
+                        .iter()
+                        .filter(|(dir, _, _)| dir.starts_with(owner))
+                        .map(|(_, _, r)| *r)
+                        .collect()
+                } else {
+                    direct
+                }
+            }
+        };
+
+        let shows: BTreeSet<&str> = candidates
+            .iter()
+            .filter_map(|r| r.show_id.as_deref())
+            .collect();
+        let movies: BTreeSet<&str> = candidates
+            .iter()
+            .filter(|r| r.show_id.is_none() && r.media_type == "movie")
+            .map(|r| r.movie_id.as_str())
+            .collect();
+        let (parent_media_id, parent_show_id) = if shows.len() == 1 {
+            (None, shows.first().map(|s| s.to_string()))
+        } else if shows.is_empty() && movies.len() == 1 {
+            (movies.first().map(|m| m.to_string()), None)
+        } else {
+            (None, None)
+        };
+
+        if parent_media_id != row.extra_parent_media_id
+            || parent_show_id != row.extra_parent_show_id
+        {
+            links.push(ExtraLink {
+                media_item_id: row.id.clone(),
+                parent_media_id,
+                parent_show_id,
+            });
+        }
+    }
+    links
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(id: &str, path: &str, extra: Option<&str>) -> ExtraCandidateRow {
+        ExtraCandidateRow {
+            id: id.into(),
+            file_path: path.into(),
+            media_type: "movie".into(),
+            extra_type: extra.map(Into::into),
+            extra_parent_media_id: None,
+            extra_parent_show_id: None,
+            movie_id: id.into(),
+            show_id: None,
+        }
+    }
+
+    fn episode(id: &str, path: &str, show: &str) -> ExtraCandidateRow {
+        ExtraCandidateRow {
+            media_type: "episode".into(),
+            show_id: Some(show.into()),
+            ..row(id, path, None)
+        }
+    }
+
+    fn parent_of(links: &[ExtraLink], id: &str) -> (Option<String>, Option<String>) {
+        let link = links.iter().find(|l| l.media_item_id == id).unwrap();
+        (link.parent_media_id.clone(), link.parent_show_id.clone())
+    }
+
+    #[test]
+    fn folder_extras_link_to_the_movie_in_the_parent_folder() {
+        let mut uhd = row("uhd", "/m/Heat (1995)/Heat (1995) - 2160p.mkv", None);
+        uhd.movie_id = "hd".into();
+        let rows = vec![
+            row("hd", "/m/Heat (1995)/Heat (1995) - 1080p.mkv", None),
+            uhd,
+            row(
+                "x",
+                "/m/Heat (1995)/Featurettes/Making Of.mkv",
+                Some("featurette"),
+            ),
+        ];
+        let links = plan_extra_links(&rows, &ExtraScope::default());
+        assert_eq!(parent_of(&links, "x"), (Some("hd".into()), None));
+    }
+
+    #[test]
+    fn suffix_extras_pick_the_matching_sibling() {
+        let rows = vec![
+            row("heat", "/m/Heat (1995).mkv", None),
+            row("ronin", "/m/Ronin (1998).mkv", None),
+            row("t", "/m/Ronin (1998)-trailer.mkv", Some("trailer")),
+            row("loose", "/m/trailer.mkv", Some("trailer")),
+        ];
+        let links = plan_extra_links(&rows, &ExtraScope::default());
+        assert_eq!(parent_of(&links, "t"), (Some("ronin".into()), None));
+        // A bare trailer in a shared folder can't be attributed
+        assert!(links.iter().all(|l| l.media_item_id != "loose"));
+    }
+
+    #[test]
+    fn show_extras_link_to_the_show_of_episodes_below() {
+        let mut linked = row(
+            "old",
+            "/tv/Show/Behind The Scenes/b.mkv",
+            Some("behind_the_scenes"),
+        );
+        linked.extra_parent_show_id = Some("show".into());
+        let rows = vec![
+            episode("e1", "/tv/Show/Season 1/Show - S01E01.mkv", "show"),
+            episode("e2", "/tv/Show/Season 2/Show - S02E01.mkv", "show"),
+            row(
+                "d",
+                "/tv/Show/Season 1/Deleted Scenes/d.mkv",
+                Some("deleted_scene"),
+            ),
+            row("t", "/tv/Show/Trailers/t.mkv", Some("trailer")),
+            linked,
+        ];
+        let links = plan_extra_links(&rows, &ExtraScope::default());
+        assert_eq!(parent_of(&links, "d"), (None, Some("show".into())));
+        assert_eq!(parent_of(&links, "t"), (None, Some("show".into())));
+        // Unchanged links aren't rewritten
+        assert!(links.iter().all(|l| l.media_item_id != "old"));
+    }
+}
@@ -1,20 +1,73 @@
 use regex::Regex;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
 use std::sync::LazyLock;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct ParsedMovie {
     pub title: String,
     pub year: Option<i32>,
 }
 
-#[derive(Debug, Clone)]
+/// A movie file's title plus the edition and part markers that distinguish it
+/// from other files of the same movie.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MovieVariant {
+    pub title: String,
+    pub year: Option<i32>,
+    /// Edition / quality label, e.g. `2160p` or `Director's Cut`.
+    pub version: Option<String>,
+    /// Part number for stacked files (`cd1`, `part2`, `disc3`, ...).
+    pub part: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize)]
 pub struct ParsedEpisode {
     pub show_name: String,
     pub season: u32,
     pub episode: u32,
+    /// Last episode of a multi-episode file (`S01E05E06`, `S01E05-E07`).
+    pub last_episode: Option<u32>,
+    /// Air date (`YYYY-MM-DD`) of a date-based episode. `season` and `episode`
+    /// are then provisional (the year and `MMDD`) until enrichment finds the
+    /// provider's numbering.
+    pub air_date: Option<String>,
+    /// Title of a special named without an episode number. `episode` is then
+    /// a placeholder until enrichment matches the title.
+    pub special_title: Option<String>,
+    /// Absolute episode number (`Show - 105`). `season` and `episode` are
+    /// then provisional (season 1, the absolute number) until enrichment maps
+    /// it onto the provider's seasons.
+    pub absolute: Option<u32>,
+}
+
+impl ParsedEpisode {
+    fn numbered(show_name: String, season: u32, episode: u32) -> Self {
+        Self {
+            show_name,
+            season,
+            episode,
+            last_episode: None,
+            air_date: None,
+            special_title: None,
+            absolute: None,
+        }
+    }
+
+    /// Every episode number the file covers, in order.
+    pub fn episode_numbers(&self) -> Vec<u32> {
+        (self.episode..=self.last_episode.unwrap_or(self.episode).max(self.episode)).collect()
+    }
+
+    /// Whether the numbering is provisional and needs a provider match.
+    pub fn needs_match(&self) -> bool {
+        self.air_date.is_some() || self.special_title.is_some() || self.absolute.is_some()
+    }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", content = "value", rename_all = "snake_case")]
 pub enum ParsedFilename {
     Movie(ParsedMovie),
     Episode(ParsedEpisode),
@@ -30,6 +83,21 @@ pub enum ParsedFilename {
 static RE_EPISODE_SXXEXX: LazyLock<Regex> =
     LazyLock::new(|| Regex::new(r"(?i)^(.+?)[.\s_-]+s(\d{1,4})e(\d{1,4})").unwrap());
 
+/// Continuation of a multi-episode marker right after `S01E05` / `1x05`:
+/// `E06`, `-E06`, `-06`, `x06`. Captures: (1) episode number.
+static RE_EPISODE_CONTINUATION: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?i)^(?:-?e|-|x)(\d{1,4})").unwrap());
+
+/// Matches date-based episodes of daily shows: `Show 2024-03-15`,
+/// `Show.2024.03.15.Guest`. Captures: (1) show name, (2) year, (3) month, (4) day.
+static RE_EPISODE_DATE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^(.+?)[.\s_-]+((?:19|20)\d{2})[.\s_-](\d{2})[.\s_-](\d{2})(?:[.\s_-]|$)").unwrap()
+});
+
+/// Folder names holding a show's specials (season 0).
+static RE_SPECIALS_FOLDER: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?i)^(?:specials|season[\s._-]*0+)$").unwrap());
+
 /// Matches `Show Name 1x05`.
 static RE_EPISODE_NX_NN: LazyLock<Regex> =
     LazyLock::new(|| Regex::new(r"(?i)^(.+?)[.\s_-]+(\d{1,2})x(\d{2,3})").unwrap());
@@ -56,6 +124,17 @@ static RE_MOVIE_BRACKET_YEAR: LazyLock<Regex> =
 static RE_MOVIE_DOT_YEAR: LazyLock<Regex> =
     LazyLock::new(|| Regex::new(r"^(.+?)[.\s_-]+((?:19|20)\d{2})(?:[.\s_-]|$)").unwrap());
 
+/// Matches a trailing disc marker: `cd1`, `disc 2`, `.disk3`.
+/// Captures: (1) part number.
+static RE_DISC_MARKER: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?i)[\s._-]+(?:cd|disc|disk)[\s._-]*(\d{1,2})$").unwrap());
+
+/// Matches a trailing `- part3` / `.pt1` marker. Only a stack marker after a
+/// year or ` - ` delimiter, since titles end in `Part 1` too.
+/// Captures: (1) leading separator, (2) part number.
+static RE_PART_MARKER: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?i)([\s._-]+)(?:part|pt)[\s._-]*(\d{1,2})$").unwrap());
+
 /// Replace dots and underscores with spaces, collapse runs of whitespace,
 /// strip trailing hyphens/dashes (left by Sonarr-style " - " delimiters), and trim.
 pub fn clean_title(raw: &str) -> String {
@@ -97,28 +176,30 @@ pub fn strip_trailing_year(name: &str) -> &str {
 pub fn parse_filename(file_stem: &str) -> ParsedFilename {
     // --- TV episodes (checked first) ---
 
-    if let Some(caps) = RE_EPISODE_SXXEXX.captures(file_stem) {
-        let raw = clean_title(&caps[1]);
-        let show_name = strip_trailing_year(&raw).to_string();
-        let season: u32 = caps[2].parse().unwrap_or(0);
-        let episode: u32 = caps[3].parse().unwrap_or(0);
-        return ParsedFilename::Episode(ParsedEpisode {
-            show_name,
-            season,
-            episode,
-        });
+    for re in [&*RE_EPISODE_SXXEXX, &*RE_EPISODE_NX_NN] {
+        if let Some(caps) = re.captures(file_stem) {
+            let raw = clean_title(&caps[1]);
+            let show_name = strip_trailing_year(&raw).to_string();
+            let season: u32 = caps[2].parse().unwrap_or(0);
+            let episode: u32 = caps[3].parse().unwrap_or(0);
+            let end = caps.get(0).map(|m| m.end()).unwrap_or(file_stem.len());
+            let mut parsed = ParsedEpisode::numbered(show_name, season, episode);
+            parsed.last_episode = last_episode_of_range(&file_stem[end..], episode);
+            return ParsedFilename::Episode(parsed);
+        }
     }
 
-    if let Some(caps) = RE_EPISODE_NX_NN.captures(file_stem) {
-        let raw = clean_title(&caps[1]);
-        let show_name = strip_trailing_year(&raw).to_string();
-        let season: u32 = caps[2].parse().unwrap_or(0);
-        let episode: u32 = caps[3].parse().unwrap_or(0);
-        return ParsedFilename::Episode(ParsedEpisode {
-            show_name,
-            season,
-            episode,
-        });
+    // Daily shows: "Show 2024-03-15"
+    if let Some(caps) = RE_EPISODE_DATE.captures(file_stem) {
+        let year: u32 = caps[2].parse().unwrap_or(0);
+        let month: u32 = caps[3].parse().unwrap_or(0);
+        let day: u32 = caps[4].parse().unwrap_or(0);
+        if (1..=12).contains(&month) && (1..=31).contains(&day) {
+            let raw = clean_title(&caps[1]);
+            let mut parsed = ParsedEpisode::numbered(raw, year, month * 100 + day);
+            parsed.air_date = Some(format!("{year:04}-{month:02}-{day:02}"));
+            return ParsedFilename::Episode(parsed);
+        }
     }
 
     // Absolute episode numbering (anime): "Show Name - 05"
@@ -126,12 +207,10 @@ pub fn parse_filename(file_stem: &str) -> ParsedFilename {
         let raw = clean_title(&caps[1]);
         let show_name = strip_trailing_year(&raw).to_string();
         let episode: u32 = caps[2].parse().unwrap_or(0);
-        // Absolute episodes go into season 1 by convention
-        return ParsedFilename::Episode(ParsedEpisode {
-            show_name,
-            season: 1,
-            episode,
-        });
+        // Absolute episodes go into season 1 until enrichment maps them
+        let mut parsed = ParsedEpisode::numbered(show_name, 1, episode);
+        parsed.absolute = Some(episode);
+        return ParsedFilename::Episode(parsed);
     }
 
     // --- Movies ---
@@ -159,6 +238,328 @@ pub fn parse_filename(file_stem: &str) -> ParsedFilename {
     ParsedFilename::Unknown(clean_title(file_stem))
 }
 
+/// The last episode of a multi-episode marker following the first episode
+/// number, e.g. `E06` after `S01E05`. Files list either every episode
+/// (`E05E06E07`) or the bounds (`E05-E07`); both cover the whole range.
+fn last_episode_of_range(rest: &str, first: u32) -> Option<u32> {
+    let mut rest = rest;
+    let mut last = None;
+    while let Some(caps) = RE_EPISODE_CONTINUATION.captures(rest) {
+        let end = caps.get(0).map(|m| m.end()).unwrap_or(rest.len());
+        // `-720p` or `-2nd` is a tag, not an episode
+        if rest[end..]
+            .chars()
+            .next()
+            .is_some_and(|c| c.is_alphanumeric() && !matches!(c, 'e' | 'E' | 'x' | 'X'))
+        {
+            break;
+        }
+        let n: u32 = caps[1].parse().unwrap_or(0);
+        if n <= last.unwrap_or(first) {
+            break;
+        }
+        last = Some(n);
+        rest = &rest[end..];
+    }
+    // Guard against reading a year or resolution as an episode range
+    last.filter(|&n| n - first <= 50)
+}
+
+/// Parse a file by its full path. Like [`parse_filename`], but a file inside
+/// a `Specials` / `Season 0` folder that has no episode number is read as a
+/// special titled by its filename, belonging to the show folder above.
+pub fn parse_path(path: &Path) -> ParsedFilename {
+    let stem = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let parsed = parse_filename(&stem);
+    if matches!(parsed, ParsedFilename::Episode(_)) {
+        return parsed;
+    }
+    let show_dir = path.parent().filter(|dir| {
+        dir.file_name()
+            .and_then(|n| n.to_str())
+            .is_some_and(|n| RE_SPECIALS_FOLDER.is_match(n))
+    });
+    match show_dir
+        .and_then(Path::parent)
+        .and_then(|d| d.file_name())
+        .and_then(|n| n.to_str())
+    {
+        Some(show) => {
+            let show_name = clean_title(show);
+            let mut special = ParsedEpisode::numbered(show_name, 0, 0);
+            special.special_title = Some(clean_title(&stem));
+            ParsedFilename::Episode(special)
+        }
+        None => parsed,
+    }
+}
+
+/// Split a trailing stack marker (`cd1`, `part2`, ...) off a file stem.
+/// Returns the remaining stem and the part number, if any.
+///
+/// `cd`, `disc` and `disk` always mark a part. `part` and `pt` only do after
+/// a year or a ` - ` delimiter: `Movie (2010) - Part 2` and
+/// `Movie - Director's Cut - pt1` are stacks, while
+/// `Harry.Potter.and.the.Deathly.Hallows.Part.1` is a title.
+pub fn split_part_marker(file_stem: &str) -> (&str, Option<u32>) {
+    if let Some(caps) = RE_DISC_MARKER.captures(file_stem) {
+        let start = caps.get(0).map(|m| m.start()).unwrap_or(file_stem.len());
+        return (&file_stem[..start], caps[1].parse().ok());
+    }
+    if let Some(caps) = RE_PART_MARKER.captures(file_stem) {
+        let start = caps.get(0).map(|m| m.start()).unwrap_or(file_stem.len());
+        let before = caps.get(1).map(|m| &file_stem[..m.end()]).unwrap_or("");
+        let delimited = before.contains(" - ")
+            || [
+                &*RE_MOVIE_PAREN_YEAR,
+                &*RE_MOVIE_BRACKET_YEAR,
+                &*RE_MOVIE_DOT_YEAR,
+            ]
+            .iter()
+            .any(|re| re.is_match(before));
+        if delimited {
+            return (&file_stem[..start], caps[2].parse().ok());
+        }
+    }
+    (file_stem, None)
+}
+
+/// Parse a movie file stem into its title, year, version label and part number.
+///
+/// `Movie (2010) - 2160p - cd2` → title `Movie`, year 2010, version `2160p`, part 2.
+/// Without a year, a ` - ` delimiter separates the title from the version.
+pub fn parse_movie_variant(file_stem: &str) -> MovieVariant {
+    let (base, part) = split_part_marker(file_stem);
+
+    let with_year = [
+        &*RE_MOVIE_PAREN_YEAR,
+        &*RE_MOVIE_BRACKET_YEAR,
+        &*RE_MOVIE_DOT_YEAR,
+    ]
+    .into_iter()
+    .find_map(|re| re.captures(base));
+
+    let (title, year, rest) = match with_year {
+        Some(caps) => {
+            let end = caps.get(2).map(|m| m.end() + 1).unwrap_or(base.len());
+            (
+                clean_title(&caps[1]),
+                caps[2].parse().ok(),
+                base.get(end..).unwrap_or(""),
+            )
+        }
+        None => match base.split_once(" - ") {
+            Some((title, rest)) => (clean_title(title), None, rest),
+            None => (clean_title(base), None, ""),
+        },
+    };
+
+    let version = clean_title(rest)
+        .trim_start_matches(['-', '–', '—'])
+        .trim()
+        .to_string();
+
+    MovieVariant {
+        title,
+        year,
+        version: (!version.is_empty()).then_some(version),
+        part,
+    }
+}
+
+/// Kind of bonus material, following the Plex/Jellyfin folder and suffix names.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExtraType {
+    Trailer,
+    Featurette,
+    BehindTheScenes,
+    DeletedScene,
+    Interview,
+    Scene,
+    Short,
+    Other,
+}
+
+impl ExtraType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Trailer => "trailer",
+            Self::Featurette => "featurette",
+            Self::BehindTheScenes => "behind_the_scenes",
+            Self::DeletedScene => "deleted_scene",
+            Self::Interview => "interview",
+            Self::Scene => "scene",
+            Self::Short => "short",
+            Self::Other => "other",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "trailer" => Some(Self::Trailer),
+            "featurette" => Some(Self::Featurette),
+            "behind_the_scenes" => Some(Self::BehindTheScenes),
+            "deleted_scene" => Some(Self::DeletedScene),
+            "interview" => Some(Self::Interview),
+            "scene" => Some(Self::Scene),
+            "short" => Some(Self::Short),
+            "other" => Some(Self::Other),
+            _ => None,
+        }
+    }
+
+    /// The type for an extras folder name (`Trailers`, `Behind The Scenes`, ...).
+    fn from_folder(name: &str) -> Option<Self> {
+        let key: String = name
+            .chars()
+            .filter(|c| c.is_alphanumeric())
+            .collect::<String>()
+            .to_lowercase();
+        match key.as_str() {
+            "trailers" => Some(Self::Trailer),
+            "featurettes" => Some(Self::Featurette),
+            "behindthescenes" => Some(Self::BehindTheScenes),
+            "deletedscenes" => Some(Self::DeletedScene),
+            "interviews" => Some(Self::Interview),
+            "scenes" => Some(Self::Scene),
+            "shorts" => Some(Self::Short),
+            "other" | "extras" => Some(Self::Other),
+            _ => None,
+        }
+    }
+
+    /// The type for a filename suffix (`-trailer`, `-deleted`, ...).
+    fn from_suffix(suffix: &str) -> Option<Self> {
+        match suffix.to_lowercase().as_str() {
+            "trailer" => Some(Self::Trailer),
+            "featurette" => Some(Self::Featurette),
+            "behindthescenes" => Some(Self::BehindTheScenes),
+            "deleted" | "deletedscene" => Some(Self::DeletedScene),
+            "interview" => Some(Self::Interview),
+            "scene" => Some(Self::Scene),
+            "short" => Some(Self::Short),
+            "other" | "extra" => Some(Self::Other),
+            _ => None,
+        }
+    }
+}
+
+/// An extra recognised from its location or name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DetectedExtra {
+    pub extra_type: ExtraType,
+    /// Folder of the movie or show the extra belongs to.
+    pub owner_dir: PathBuf,
+    /// For suffix-named extras, the stem of the file it accompanies
+    /// (`Movie (2010)` for `Movie (2010)-trailer.mkv`).
+    pub owner_stem: Option<String>,
+    pub title: String,
+}
+
+/// Matches a trailing extras suffix: `Movie (2010)-trailer`, `Clip-deleted`.
+/// Captures: (1) owner stem, (2) suffix.
+static RE_EXTRA_SUFFIX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"(?i)^(.*?)-(trailer|featurette|behindthescenes|deleted|deletedscene|interview|scene|short|other|extra)$",
+    )
+    .unwrap()
+});
+
+/// Whether a file stem carries an `S01E05` / `1x05` episode number.
+fn is_numbered_episode(file_stem: &str) -> bool {
+    RE_EPISODE_SXXEXX.is_match(file_stem) || RE_EPISODE_NX_NN.is_match(file_stem)
+}
+
+/// Folders of a library that are never extras folders, whatever their
+/// name: the library roots (a library at `/media/Shorts`) and folders that
+/// directly hold numbered episodes (a show called `Extras` or `Other`).
+#[derive(Debug, Clone, Default)]
+pub struct ExtraScope {
+    roots: Vec<PathBuf>,
+    episode_dirs: HashSet<PathBuf>,
+}
+
+impl ExtraScope {
+    /// Scope for a library with `roots` whose files include `files`.
+    pub fn new<'a>(roots: &[PathBuf], files: impl IntoIterator<Item = &'a Path>) -> Self {
+        let episode_dirs = files
+            .into_iter()
+            .filter(|path| {
+                path.file_stem()
+                    .and_then(|s| s.to_str())
+                    .is_some_and(is_numbered_episode)
+            })
+            .filter_map(|path| path.parent().map(Path::to_path_buf))
+            .collect();
+        Self {
+            roots: roots.to_vec(),
+            episode_dirs,
+        }
+    }
+
+    fn allows_extras_folder(&self, dir: &Path) -> bool {
+        !self.roots.iter().any(|root| root == dir) && !self.episode_dirs.contains(dir)
+    }
+}
+
+/// Recognise extras by the Plex/Jellyfin conventions: files inside a
+/// `Trailers/`, `Featurettes/`, `Behind The Scenes/`, ... folder next to the
+/// movie or show, files named with a `-trailer`, `-featurette`, ... suffix, and
+/// a bare `trailer.mkv` beside a movie.
+///
+/// A file with an episode number is always an episode, and folders `scope`
+/// rules out are never extras folders.
+pub fn detect_extra(path: &Path, scope: &ExtraScope) -> Option<DetectedExtra> {
+    let stem = path.file_stem()?.to_str()?;
+    let dir = path.parent()?;
+    if is_numbered_episode(stem) {
+        return None;
+    }
+
+    if let Some(extra_type) = dir
+        .file_name()
+        .and_then(|n| n.to_str())
+        .and_then(ExtraType::from_folder)
+        .filter(|_| scope.allows_extras_folder(dir))
+    {
+        return Some(DetectedExtra {
+            extra_type,
+            owner_dir: dir.parent()?.to_path_buf(),
+            owner_stem: None,
+            title: clean_title(stem),
+        });
+    }
+
+    if let Some(caps) = RE_EXTRA_SUFFIX.captures(stem) {
+        let extra_type = ExtraType::from_suffix(&caps[2])?;
+        let owner = caps[1].trim();
+        return Some(DetectedExtra {
+            extra_type,
+            owner_dir: dir.to_path_buf(),
+            owner_stem: (!owner.is_empty()).then(|| owner.to_string()),
+            title: if owner.is_empty() {
+                clean_title(&caps[2])
+            } else {
+                clean_title(owner)
+            },
+        });
+    }
+
+    if stem.eq_ignore_ascii_case("trailer") {
+        return Some(DetectedExtra {
+            extra_type: ExtraType::Trailer,
+            owner_dir: dir.to_path_buf(),
+            owner_stem: None,
+            title: "Trailer".to_string(),
+        });
+    }
+
+    None
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -201,6 +602,159 @@ mod tests {
         }
     }
 
+    #[test]
+    fn movie_variant_version_suffix() {
+        let v = parse_movie_variant("Inception (2010) - 2160p");
+        assert_eq!(v.title, "Inception");
+        assert_eq!(v.year, Some(2010));
+        assert_eq!(v.version.as_deref(), Some("2160p"));
+        assert_eq!(v.part, None);
+
+        let plain = parse_movie_variant("Inception (2010)");
+        assert_eq!(plain.title, "Inception");
+        assert_eq!(plain.version, None);
+    }
+
+    #[test]
+    fn movie_variant_part_markers() {
+        for stem in [
+            "Heat (1995) cd2",
+            "Heat (1995) - Part 2",
+            "Heat.1995.DVDRip.pt2",
+            "Heat (1995) Disc 2",
+        ] {
+            let v = parse_movie_variant(stem);
+            assert_eq!(v.title, "Heat", "{stem}");
+            assert_eq!(v.year, Some(1995), "{stem}");
+            assert_eq!(v.part, Some(2), "{stem}");
+        }
+        assert_eq!(
+            parse_movie_variant("Heat.1995.DVDRip.pt2")
+                .version
+                .as_deref(),
+            Some("DVDRip")
+        );
+    }
+
+    #[test]
+    fn movie_variant_without_year() {
+        let v = parse_movie_variant("Some Movie - Director's Cut - cd1");
+        assert_eq!(v.title, "Some Movie");
+        assert_eq!(v.year, None);
+        assert_eq!(v.version.as_deref(), Some("Director's Cut"));
+        assert_eq!(v.part, Some(1));
+
+        // Titles that merely contain a number are not stack markers.
+        let v = parse_movie_variant("Apollo 13 (1995)");
+        assert_eq!(v.title, "Apollo 13");
+        assert_eq!(v.part, None);
+    }
+
+    #[test]
+    fn titles_ending_in_part_or_dvd_are_not_stacks() {
+        for (stem, title) in [
+            (
+                "Harry.Potter.and.the.Deathly.Hallows.Part.1",
+                "Harry Potter and the Deathly Hallows Part 1",
+            ),
+            (
+                "Harry.Potter.and.the.Deathly.Hallows.Part.2",
+                "Harry Potter and the Deathly Hallows Part 2",
+            ),
+            ("Kill Bill Pt 2", "Kill Bill Pt 2"),
+        ] {
+            let v = parse_movie_variant(stem);
+            assert_eq!(v.title, title, "{stem}");
+            assert_eq!(v.part, None, "{stem}");
+        }
+
+        let v = parse_movie_variant("Movie.2010.DVD9");
+        assert_eq!(v.title, "Movie");
+        assert_eq!(v.part, None);
+        assert_eq!(v.version.as_deref(), Some("DVD9"));
+    }
+
+    // ---- Extras tests ----
+
+    fn extra(path: &str) -> Option<DetectedExtra> {
+        detect_extra(Path::new(path), &ExtraScope::default())
+    }
+
+    #[test]
+    fn extras_in_named_folders() {
+        let e = extra("/m/Heat (1995)/Behind The Scenes/Making Of.mkv").unwrap();
+        assert_eq!(e.extra_type, ExtraType::BehindTheScenes);
+        assert_eq!(e.owner_dir, Path::new("/m/Heat (1995)"));
+        assert_eq!(e.title, "Making Of");
+
+        let e = extra("/tv/Show/trailers/teaser.mp4").unwrap();
+        assert_eq!(e.extra_type, ExtraType::Trailer);
+        assert_eq!(e.owner_dir, Path::new("/tv/Show"));
+    }
+
+    #[test]
+    fn extras_with_filename_suffix() {
+        let e = extra("/m/Heat (1995)/Heat (1995)-trailer.mkv").unwrap();
+        assert_eq!(e.extra_type, ExtraType::Trailer);
+        assert_eq!(e.owner_dir, Path::new("/m/Heat (1995)"));
+        assert_eq!(e.owner_stem.as_deref(), Some("Heat (1995)"));
+
+        let e = extra("/m/Heat/Bank Scene-deleted.mkv").unwrap();
+        assert_eq!(e.extra_type, ExtraType::DeletedScene);
+        assert_eq!(e.title, "Bank Scene");
+
+        let e = extra("/m/Heat/trailer.mkv").unwrap();
+        assert_eq!(e.extra_type, ExtraType::Trailer);
+        assert_eq!(e.owner_stem, None);
+    }
+
+    #[test]
+    fn roots_and_episode_folders_are_not_extras_folders() {
+        let files = [
+            "/media/Shorts/Clip.mkv",
+            "/tv/Other/Other - S01E01.mkv",
+            "/tv/Other/Other - S01E02.mkv",
+            "/tv/Other/Other - Pilot Special.mkv",
+            "/tv/Extras/Extras 1x01.mkv",
+        ];
+        let scope = ExtraScope::new(
+            &[PathBuf::from("/media/Shorts"), PathBuf::from("/tv")],
+            files.iter().map(Path::new),
+        );
+        for p in files {
+            assert_eq!(detect_extra(Path::new(p), &scope), None, "{p}");
+        }
+        // An episode number wins over the folder even without the scope
+        assert_eq!(extra("/tv/Show/Featurettes/Show - S01E01.mkv"), None);
+        // Extras folders below the root still count
+        let e = detect_extra(
+            Path::new("/media/Shorts/Heat (1995)/Trailers/teaser.mkv"),
+            &scope,
+        )
+        .unwrap();
+        assert_eq!(e.extra_type, ExtraType::Trailer);
+    }
+
+    #[test]
+    fn regular_files_are_not_extras() {
+        for p in [
+            "/m/Heat (1995)/Heat (1995).mkv",
+            "/m/Spider-Man (2002)/Spider-Man (2002).mkv",
+            "/tv/Show/Season 1/Show - S01E01 - The Trailer.mkv",
+            "/m/The Other Guys (2010)/The Other Guys (2010).mkv",
+        ] {
+            assert_eq!(extra(p), None, "{p}");
+        }
+        assert_eq!(
+            ExtraType::parse("deleted_scene"),
+            Some(ExtraType::DeletedScene)
+        );
+        assert_eq!(
+            ExtraType::parse(ExtraType::Short.as_str()),
+            Some(ExtraType::Short)
+        );
+    }
+
     // ---- Episode tests ----
 
     #[test]
@@ -314,16 +868,79 @@ mod tests {
 
     #[test]
     fn sonarr_multi_episode() {
-        // Multi-episode: first episode number is captured
         let result = parse_filename("Show Name - S01E05E06 - Double Feature");
         match result {
             ParsedFilename::Episode(e) => {
                 assert_eq!(e.show_name, "Show Name");
                 assert_eq!(e.season, 1);
                 assert_eq!(e.episode, 5);
+                assert_eq!(e.episode_numbers(), vec![5, 6]);
+            }
+            other => panic!("Expected Episode, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn multi_episode_ranges() {
+        let numbers = |stem: &str| match parse_filename(stem) {
+            ParsedFilename::Episode(e) => e.episode_numbers(),
+            other => panic!("Expected Episode, got {:?}", other),
+        };
+        assert_eq!(numbers("Show - S02E01-E03 - Pilot"), vec![1, 2, 3]);
+        assert_eq!(numbers("show.s02e01-02.720p"), vec![1, 2]);
+        assert_eq!(numbers("Show 1x05x06"), vec![5, 6]);
+        // Tags after a single episode aren't ranges
+        assert_eq!(numbers("Show.S01E05-720p"), vec![5]);
+        assert_eq!(numbers("Show.S01E05.1080p"), vec![5]);
+        assert_eq!(numbers("Show - S01E05 - 2nd Chance"), vec![5]);
+    }
+
+    #[test]
+    fn date_based_episodes() {
+        for stem in [
+            "The Daily Show 2024-03-15",
+            "The.Daily.Show.2024.03.15.Guest.720p",
+        ] {
+            match parse_filename(stem) {
+                ParsedFilename::Episode(e) => {
+                    assert_eq!(e.show_name, "The Daily Show", "{stem}");
+                    assert_eq!(e.air_date.as_deref(), Some("2024-03-15"), "{stem}");
+                    assert_eq!((e.season, e.episode), (2024, 315), "{stem}");
+                    assert!(e.needs_match());
+                }
+                other => panic!("Expected Episode for {stem}, got {:?}", other),
+            }
+        }
+        // Not a date: stays a movie
+        assert!(matches!(
+            parse_filename("Movie.2024.13.45"),
+            ParsedFilename::Movie(_)
+        ));
+    }
+
+    #[test]
+    fn specials_named_by_title() {
+        match parse_path(Path::new(
+            "/tv/Doctor Who (2005)/Specials/The Christmas Invasion.mkv",
+        )) {
+            ParsedFilename::Episode(e) => {
+                assert_eq!(e.show_name, "Doctor Who (2005)");
+                assert_eq!(e.season, 0);
+                assert_eq!(e.special_title.as_deref(), Some("The Christmas Invasion"));
+            }
+            other => panic!("Expected Episode, got {:?}", other),
+        }
+        match parse_path(Path::new("/tv/Show/Season 00/Show - S00E02.mkv")) {
+            ParsedFilename::Episode(e) => {
+                assert_eq!((e.season, e.episode), (0, 2));
+                assert!(!e.needs_match());
             }
             other => panic!("Expected Episode, got {:?}", other),
         }
+        assert!(matches!(
+            parse_path(Path::new("/tv/Show/Season 1/Commentary.mkv")),
+            ParsedFilename::Unknown(_)
+        ));
     }
 
     #[test]
@@ -334,6 +951,8 @@ mod tests {
                 assert_eq!(e.show_name, "Naruto Shippuden");
                 assert_eq!(e.season, 1);
                 assert_eq!(e.episode, 5);
+                assert_eq!(e.absolute, Some(5));
+                assert!(e.needs_match());
             }
             other => panic!("Expected Episode, got {:?}", other),
         }
@@ -0,0 +1,138 @@
+//! Fast partial content hash used to recognise media files that were moved
+//! or renamed, so their existing rows (progress, collections, metadata) can
+//! be relocated instead of deleted and re-created.
+
+use anyhow::Result;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::io::SeekFrom;
+use std::path::Path;
+use tokio::fs::File;
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+
+/// Bytes read from each end of the file.
+const CHUNK_SIZE: u64 = 64 * 1024;
+
+/// SHA-256 over the file size plus the first and last [`CHUNK_SIZE`] bytes
+/// (the whole file when it is smaller than two chunks), as lowercase hex.
+///
+/// Reads at most 128 KiB regardless of file size, so it is cheap enough to
+/// run for every new file during a scan.
+pub async fn partial_hash(path: &Path) -> Result<String> {
+    let mut file = File::open(path).await?;
+    let size = file.metadata().await?.len();
+
+    let mut hasher = Sha256::new();
+    hasher.update(size.to_le_bytes());
+
+    if size <= CHUNK_SIZE * 2 {
+        let mut buf = Vec::with_capacity(size as usize);
+        file.read_to_end(&mut buf).await?;
+        hasher.update(&buf);
+    } else {
+        let mut buf = vec![0u8; CHUNK_SIZE as usize];
+        file.read_exact(&mut buf).await?;
+        hasher.update(&buf);
+        file.seek(SeekFrom::Start(size - CHUNK_SIZE)).await?;
+        file.read_exact(&mut buf).await?;
+        hasher.update(&buf);
+    }
+
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Media rows whose files have gone missing, indexed by (size, hash) so a
+/// file appearing elsewhere can claim the row it was moved from.
+#[derive(Debug, Default)]
+pub struct MovedFileTracker {
+    missing: HashMap<(u64, String), Vec<String>>,
+}
+
+impl MovedFileTracker {
+    /// Register a missing media row. Rows without a hash can't be matched and are ignored.
+    pub fn add_missing(&mut self, media_id: String, file_size: u64, file_hash: Option<String>) {
+        if let Some(hash) = file_hash {
+            self.missing
+                .entry((file_size, hash))
+                .or_default()
+                .push(media_id);
+        }
+    }
+
+    /// Take the ID of a missing row with identical content, if any. Each row
+    /// can be claimed once.
+    pub fn claim(&mut self, file_size: u64, file_hash: &str) -> Option<String> {
+        let key = (file_size, file_hash.to_string());
+        let ids = self.missing.get_mut(&key)?;
+        let id = ids.pop();
+        if ids.is_empty() {
+            self.missing.remove(&key);
+        }
+        id
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.missing.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    async fn write_temp(bytes: &[u8]) -> std::path::PathBuf {
+        let path = std::env::temp_dir().join(format!("ferrite-hash-{}.bin", Uuid::new_v4()));
+        tokio::fs::write(&path, bytes).await.unwrap();
+        path
+    }
+
+    #[tokio::test]
+    async fn identical_content_hashes_equal_across_paths() {
+        let data: Vec<u8> = (0..300_000u32).map(|i| (i % 251) as u8).collect();
+        let a = write_temp(&data).await;
+        let b = write_temp(&data).await;
+        assert_eq!(
+            partial_hash(&a).await.unwrap(),
+            partial_hash(&b).await.unwrap()
+        );
+
+        // A change in the tail chunk changes the hash.
+        let mut changed = data.clone();
+        *changed.last_mut().unwrap() ^= 0xff;
+        let c = write_temp(&changed).await;
+        assert_ne!(
+            partial_hash(&a).await.unwrap(),
+            partial_hash(&c).await.unwrap()
+        );
+
+        for p in [a, b, c] {
+            let _ = tokio::fs::remove_file(p).await;
+        }
+    }
+
+    #[tokio::test]
+    async fn small_files_hash_whole_content_and_size() {
+        let a = write_temp(b"abc").await;
+        let b = write_temp(b"abd").await;
+        let c = write_temp(b"").await;
+        let ha = partial_hash(&a).await.unwrap();
+        assert_ne!(ha, partial_hash(&b).await.unwrap());
+        assert_ne!(ha, partial_hash(&c).await.unwrap());
+        assert_eq!(ha.len(), 64);
+        for p in [a, b, c] {
+            let _ = tokio::fs::remove_file(p).await;
+        }
+    }
+
+    #[test]
+    fn tracker_claims_each_row_once_and_requires_matching_size() {
+        let mut tracker = MovedFileTracker::default();
+        tracker.add_missing("m1".into(), 100, Some("h".into()));
+        tracker.add_missing("m2".into(), 100, None);
+        assert_eq!(tracker.claim(200, "h"), None);
+        assert_eq!(tracker.claim(100, "h").as_deref(), Some("m1"));
+        assert_eq!(tracker.claim(100, "h"), None);
+        assert!(tracker.is_empty());
+    }
+}
@@ -0,0 +1,239 @@
+//! Exclusion rules for library scans.
+//!
+//! Rules come from a library's `ignore_patterns` setting (relative to each
+//! root folder) and from `.ferriteignore` files, which apply to the folder
+//! they sit in and everything below it. The syntax is a small gitignore
+//! subset, matched case-insensitively:
+//!
+//! - blank lines and lines starting with `#` are skipped
+//! - `*` and `?` match within one path component, `**` across components
+//! - a trailing `/` matches directories only (`Extras/`)
+//! - a pattern without a `/` matches a file or folder name at any depth
+//!   (`*sample*`); one with a `/` matches the path from the base folder
+//!   (`Featurettes/*.mkv`, `/Unsorted`)
+
+use std::path::{Component, Path, PathBuf};
+
+/// Name of the per-folder ignore file.
+pub const IGNORE_FILE: &str = ".ferriteignore";
+
+#[derive(Debug, Clone)]
+struct Pattern {
+    glob: Vec<char>,
+    dir_only: bool,
+    anchored: bool,
+}
+
+/// A compiled set of exclusion patterns.
+#[derive(Debug, Clone, Default)]
+pub struct IgnoreRules {
+    patterns: Vec<Pattern>,
+}
+
+impl IgnoreRules {
+    pub fn parse<'a>(lines: impl IntoIterator<Item = &'a str>) -> Self {
+        let patterns = lines
+            .into_iter()
+            .filter_map(|line| {
+                let line = line.trim();
+                if line.is_empty() || line.starts_with('#') {
+                    return None;
+                }
+                let dir_only = line.ends_with('/');
+                let line = line.trim_end_matches('/');
+                let anchored = line.contains('/');
+                let line = line.trim_start_matches('/');
+                (!line.is_empty()).then(|| Pattern {
+                    glob: line.to_lowercase().chars().collect(),
+                    dir_only,
+                    anchored,
+                })
+            })
+            .collect();
+        Self { patterns }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.patterns.is_empty()
+    }
+
+    /// Whether `relative` (a path below the rules' base folder) is excluded,
+    /// either itself or through one of its parent folders.
+    pub fn is_ignored(&self, relative: &Path, is_dir: bool) -> bool {
+        if self.patterns.is_empty() {
+            return false;
+        }
+        let components: Vec<String> = relative
+            .components()
+            .filter_map(|c| match c {
+                Component::Normal(name) => Some(name.to_string_lossy().to_lowercase()),
+                _ => None,
+            })
+            .collect();
+        let mut joined = String::new();
+        for (i, name) in components.iter().enumerate() {
+            if i > 0 {
+                joined.push('/');
+            }
+            joined.push_str(name);
+            let entry_is_dir = is_dir || i + 1 < components.len();
+            let name: Vec<char> = name.chars().collect();
+            let full: Vec<char> = joined.chars().collect();
+            let hit = self.patterns.iter().any(|p| {
+                if p.dir_only && !entry_is_dir {
+                    return false;
+                }
+                glob_match(&p.glob, if p.anchored { &full } else { &name })
+            });
+            if hit {
+                return true;
+            }
+        }
+        false
+    }
+}
+
+/// Read the `.ferriteignore` file in `dir`, if there is one.
+pub async fn load_ignore_file(dir: &Path) -> Option<IgnoreRules> {
+    let contents = tokio::fs::read_to_string(dir.join(IGNORE_FILE))
+        .await
+        .ok()?;
+    let rules = IgnoreRules::parse(contents.lines());
+    (!rules.is_empty()).then_some(rules)
+}
+
+/// Whether `path` is excluded from a library with `roots` and library-level
+/// `rules`, including by any `.ferriteignore` between its root and itself.
+/// Paths outside every root count as excluded.
+pub async fn is_path_ignored(
+    roots: &[PathBuf],
+    rules: &IgnoreRules,
+    path: &Path,
+    is_dir: bool,
+) -> bool {
+    let Some(root) = roots
+        .iter()
+        .filter(|root| path.starts_with(root))
+        .max_by_key(|root| root.components().count())
+    else {
+        return true;
+    };
+    let Ok(relative) = path.strip_prefix(root) else {
+        return true;
+    };
+    if rules.is_ignored(relative, is_dir) {
+        return true;
+    }
+
+    // Folder-level ignore files from the root down to the path's parent
+    let mut dir = root.clone();
+    let mut components = relative.components().peekable();
+    while let Some(component) = components.next() {
+        if let Some(local) = load_ignore_file(&dir).await {
+            let Ok(below) = path.strip_prefix(&dir) else {
+                break;
+            };
+            if local.is_ignored(below, is_dir) {
+                return true;
+            }
+        }
+        if components.peek().is_none() {
+            break;
+        }
+        dir.push(component);
+    }
+    false
+}
+
+fn glob_match(pattern: &[char], text: &[char]) -> bool {
+    match pattern.first() {
+        None => text.is_empty(),
+        Some('*') if pattern.get(1) == Some(&'*') => {
+            let rest = &pattern[2..];
+            // `**/` may also match no folders at all
+            if rest.first() == Some(&'/') && glob_match(&rest[1..], text) {
+                return true;
+            }
+            (0..=text.len()).any(|i| glob_match(rest, &text[i..]))
+        }
+        Some('*') => {
+            let rest = &pattern[1..];
+            for i in 0..=text.len() {
+                if glob_match(rest, &text[i..]) {
+                    return true;
+                }
+                if text.get(i) == Some(&'/') {
+                    break;
+                }
+            }
+            false
+        }
+        Some('?') => {
+            matches!(text.first(), Some(c) if *c != '/') && glob_match(&pattern[1..], &text[1..])
+        }
+        Some(c) => text.first() == Some(c) && glob_match(&pattern[1..], &text[1..]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules(lines: &[&str]) -> IgnoreRules {
+        IgnoreRules::parse(lines.iter().copied())
+    }
+
+    #[test]
+    fn unanchored_patterns_match_names_at_any_depth() {
+        let r = rules(&["*sample*", "# comment", ""]);
+        assert!(r.is_ignored(Path::new("Movie (2020)/movie-SAMPLE.mkv"), false));
+        assert!(r.is_ignored(Path::new("Samples/clip.mkv"), false));
+        assert!(!r.is_ignored(Path::new("Movie (2020)/movie.mkv"), false));
+    }
+
+    #[test]
+    fn trailing_slash_only_matches_directories() {
+        let r = rules(&["Extras/"]);
+        assert!(r.is_ignored(Path::new("Movie/Extras/behind.mkv"), false));
+        assert!(r.is_ignored(Path::new("Movie/extras"), true));
+        assert!(!r.is_ignored(Path::new("Movie/extras"), false));
+    }
+
+    #[test]
+    fn patterns_with_a_slash_are_anchored_to_the_base() {
+        let r = rules(&["/Unsorted", "Show/**/*.nfo.mkv"]);
+        assert!(r.is_ignored(Path::new("Unsorted/a.mkv"), false));
+        assert!(!r.is_ignored(Path::new("Movies/Unsorted/a.mkv"), false));
+        assert!(r.is_ignored(Path::new("Show/x.nfo.mkv"), false));
+        assert!(r.is_ignored(Path::new("Show/S01/x.nfo.mkv"), false));
+        assert!(!r.is_ignored(Path::new("Other/S01/x.nfo.mkv"), false));
+    }
+
+    #[test]
+    fn single_star_does_not_cross_folders() {
+        let r = rules(&["a/*.mkv"]);
+        assert!(r.is_ignored(Path::new("a/b.mkv"), false));
+        assert!(!r.is_ignored(Path::new("a/b/c.mkv"), false));
+    }
+
+    #[tokio::test]
+    async fn ignore_files_apply_below_their_folder() {
+        let root = std::env::temp_dir().join(format!("ferrite-ignore-{}", uuid::Uuid::new_v4()));
+        let show = root.join("Show");
+        tokio::fs::create_dir_all(show.join("Behind"))
+            .await
+            .unwrap();
+        tokio::fs::write(show.join(IGNORE_FILE), "Behind/\n")
+            .await
+            .unwrap();
+        let roots = vec![root.clone()];
+        let none = IgnoreRules::default();
+
+        assert!(is_path_ignored(&roots, &none, &show.join("Behind/b.mkv"), false).await);
+        assert!(!is_path_ignored(&roots, &none, &show.join("e01.mkv"), false).await);
+        assert!(!is_path_ignored(&roots, &none, &root.join("Behind/b.mkv"), false).await);
+        assert!(is_path_ignored(&roots, &none, Path::new("/elsewhere/x.mkv"), false).await);
+
+        tokio::fs::remove_dir_all(&root).await.unwrap();
+    }
+}
@@ -1,23 +1,31 @@
+pub mod dry_run;
 pub mod extract;
+pub mod extras;
 pub mod filename;
+pub mod hash;
+pub mod ignore;
 pub mod probe;
 pub mod progress;
 pub mod subtitle;
+pub mod versions;
 pub mod walker;
 pub mod watcher;
 
 use anyhow::Result;
-use ferrite_core::media::{LibraryType, AUDIO_EXTENSIONS, VIDEO_EXTENSIONS};
+use ferrite_core::media::{Library, LibraryType, AUDIO_EXTENSIONS, VIDEO_EXTENSIONS};
 use ferrite_db::chapter_repo::ChapterInsert;
+use ferrite_db::extra_repo;
 use ferrite_db::library_repo;
 use ferrite_db::media_repo::{self, MediaProbeData};
 use ferrite_db::movie_repo;
 use ferrite_db::stream_repo::StreamInsert;
 use ferrite_db::tv_repo;
-use filename::{ParsedEpisode, ParsedFilename, ParsedMovie};
+use ferrite_metadata::enrichment::EnrichmentControl;
+use filename::{DetectedExtra, ExtraType, ParsedEpisode, ParsedFilename, ParsedMovie};
 use futures::stream::{self, StreamExt};
+use ignore::IgnoreRules;
 use progress::{ScanState, ScanStatus};
-use sqlx::SqlitePool;
+use sqlx::{SqliteConnection, SqlitePool};
 use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
@@ -25,7 +33,81 @@ use tokio::sync::Semaphore;
 use tracing::{debug, info, warn};
 
 pub use progress::{ScanProgress, ScanRegistry};
-pub use watcher::WatcherHandle;
+pub use watcher::{LibraryChange, WatcherHandle};
+
+/// Root folders of a library, primary first.
+pub fn library_roots(library: &Library) -> Vec<PathBuf> {
+    library.paths.iter().map(PathBuf::from).collect()
+}
+
+/// The library-level exclusion rules of `library`.
+pub fn library_ignore_rules(library: &Library) -> IgnoreRules {
+    IgnoreRules::parse(library.ignore_patterns.iter().map(String::as_str))
+}
+
+/// Title and year a file is indexed under: the extra's own title, the movie
+/// title without version/part suffixes (so every file of a movie gets the
+/// same skeleton title), or the show name of an episode.
+pub(crate) fn index_title(
+    path: &Path,
+    parsed: &ParsedFilename,
+    extra: Option<&DetectedExtra>,
+    is_movie_library: bool,
+) -> (String, Option<i32>) {
+    if let Some(extra) = extra {
+        return (extra.title.clone(), None);
+    }
+    if is_movie_library {
+        let file_stem = path
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let variant = filename::parse_movie_variant(&file_stem);
+        return (variant.title, variant.year);
+    }
+    match parsed {
+        ParsedFilename::Movie(ParsedMovie { title, year }) => (title.clone(), *year),
+        ParsedFilename::Episode(ParsedEpisode { show_name, .. }) => (show_name.clone(), None),
+        ParsedFilename::Unknown(name) => (name.clone(), None),
+    }
+}
+
+/// Rows to store for the streams of a probed file.
+fn stream_inserts(streams: &[probe::StreamInfo]) -> Vec<StreamInsert> {
+    streams
+        .iter()
+        .map(|s| StreamInsert {
+            stream_index: s.index,
+            stream_type: s.stream_type.clone(),
+            codec_name: s.codec_name.clone(),
+            codec_long_name: s.codec_long_name.clone(),
+            profile: s.profile.clone(),
+            language: s.language.clone(),
+            title: s.title.clone(),
+            is_default: s.is_default,
+            is_forced: s.is_forced,
+            width: s.width,
+            height: s.height,
+            frame_rate: s.frame_rate.clone(),
+            pixel_format: s.pixel_format.clone(),
+            bit_depth: s.bit_depth,
+            color_space: s.color_space.clone(),
+            color_transfer: s.color_transfer.clone(),
+            color_primaries: s.color_primaries.clone(),
+            dv_profile: s.dolby_vision.map(|dv| dv.profile),
+            dv_level: s.dolby_vision.map(|dv| dv.level),
+            dv_bl_compat_id: s.dolby_vision.map(|dv| dv.bl_compat_id),
+            dv_rpu_present: s.dolby_vision.is_some_and(|dv| dv.rpu_present),
+            dv_el_present: s.dolby_vision.is_some_and(|dv| dv.el_present),
+            hdr10_plus: s.hdr10_plus,
+            channels: s.channels,
+            channel_layout: s.channel_layout.clone(),
+            sample_rate: s.sample_rate,
+            spatial_audio: s.spatial_audio.clone(),
+            bitrate_bps: s.bitrate_bps,
+        })
+        .collect()
+}
 
 /// Scan a single library using a per-item concurrent pipeline.
 ///
@@ -49,22 +131,29 @@ pub async fn scan_library(
     image_cache: Option<Arc<ferrite_metadata::image_cache::ImageCache>>,
 ) -> Result<u32> {
     let library = library_repo::get_library(pool, library_id).await?;
-    let lib_path = Path::new(&library.path);
+    let roots = library_roots(&library);
 
-    if !lib_path.exists() {
-        warn!("Library path does not exist: {}", library.path);
+    for root in roots.iter().filter(|r| !r.exists()) {
+        warn!("Library path does not exist: {}", root.display());
+    }
+    if !roots.iter().any(|r| r.exists()) {
         scan_state.set_status(ScanStatus::Failed).await;
         return Ok(0);
     }
 
-    info!("Scanning library '{}' at {}", library.name, library.path);
+    info!(
+        "Scanning library '{}' at {}",
+        library.name,
+        library.paths.join(", ")
+    );
 
     let extensions: &[&str] = match library.library_type {
         LibraryType::Movie | LibraryType::Tv => VIDEO_EXTENSIONS,
         LibraryType::Music => AUDIO_EXTENSIONS,
     };
 
-    let files = walker::walk_directory(lib_path, extensions).await?;
+    let rules = library_ignore_rules(&library);
+    let files = walker::walk_roots(&roots, extensions, &rules).await?;
     let total = files.len() as u32;
     info!("Found {} media files in '{}'", total, library.name);
     scan_state
@@ -80,12 +169,68 @@ pub async fn scan_library(
     let is_movie_library = matches!(library.library_type, LibraryType::Movie);
     let is_tv_library = matches!(library.library_type, LibraryType::Tv);
 
-    // Delta scan: load existing (file_path -> file_size) to skip unchanged files.
+    // Delta scan: load existing (file_path -> (file_size, has_hash)) to skip
+    // unchanged files; files a cancelled scan left without subtitles are
+    // indexed again. Items whose file is no longer on disk are tracked by
+    // content hash so a file that was moved or renamed within the library
+    // reclaims its row instead of being indexed as new.
     // Wrapped in Arc so it is shared across all per-item futures without cloning.
-    let existing: Arc<std::collections::HashMap<String, u64>> = Arc::new(
-        media_repo::get_all_file_sizes(pool, library_id)
-            .await
-            .unwrap_or_default(),
+    let identities = media_repo::list_file_identities(pool, library_id)
+        .await
+        .unwrap_or_default();
+    let mut moved_files = hash::MovedFileTracker::default();
+    let mut pruned = 0u32;
+    {
+        let walked: HashSet<&Path> = files.iter().map(|f| f.path.as_path()).collect();
+        for row in &identities {
+            let path = Path::new(&row.file_path);
+            if walked.contains(path) {
+                continue;
+            }
+            // Only rows the library no longer covers are removed: outside
+            // every root, or matched by an ignore rule. Anything else that
+            // wasn't walked (gone, or a stat that failed on a network share)
+            // is kept and treated as missing.
+            if ignore::is_path_ignored(&roots, &rules, path, false).await {
+                match media_repo::delete_media_item_by_path(pool, &row.file_path).await {
+                    Ok(rows) => pruned += rows as u32,
+                    Err(e) => warn!("Failed to remove excluded media {}: {}", row.file_path, e),
+                }
+                continue;
+            }
+            moved_files.add_missing(
+                row.id.clone(),
+                row.file_size.max(0) as u64,
+                row.file_hash.clone(),
+            );
+        }
+    }
+    if pruned > 0 {
+        info!(
+            "Removed {} item(s) excluded from '{}' by its folders or ignore rules",
+            pruned, library.name
+        );
+    }
+    let moved_files = Arc::new(std::sync::Mutex::new(moved_files));
+    // Unchanged files flagged for a re-probe only get their streams refreshed
+    let reprobe: Arc<HashMap<String, String>> = Arc::new(
+        identities
+            .iter()
+            .filter(|r| r.needs_reprobe && !r.subtitles_pending)
+            .map(|r| (r.file_path.clone(), r.id.clone()))
+            .collect(),
+    );
+    let existing: Arc<HashMap<String, (u64, bool)>> = Arc::new(
+        identities
+            .into_iter()
+            .filter(|r| !r.subtitles_pending)
+            .map(|r| {
+                (
+                    r.file_path,
+                    (r.file_size.max(0) as u64, r.file_hash.is_some()),
+                )
+            })
+            .collect(),
     );
 
     let probe_sem = Arc::new(Semaphore::new(concurrent_probes));
@@ -111,6 +256,9 @@ pub async fn scan_library(
                 let scan_state = scan_state.clone();
                 tokio::spawn(async move {
                     while let Some((media_item_id, title, year)) = rx.recv().await {
+                        if !scan_state.checkpoint().await {
+                            break;
+                        }
                         scan_state
                             .set_current(&format!("Enriching: {}", title))
                             .await;
@@ -140,52 +288,118 @@ pub async fn scan_library(
         };
 
     let mut count = 0u32;
+    let mut relocated = 0u32;
 
     struct ProbedItem {
         file_path_str: String,
         file_size: u64,
+        file_hash: Option<String>,
         title: String,
         year: Option<i32>,
         parsed: ParsedFilename,
+        extra: Option<ExtraType>,
         probe_data: Option<MediaProbeData>,
         streams: Vec<StreamInsert>,
         chapters: Vec<ChapterInsert>,
     }
 
+    enum ScanItem {
+        /// New or changed file that was probed and needs indexing.
+        Probed(Box<ProbedItem>),
+        /// A missing item's file reappeared at a new path.
+        Relocated {
+            media_id: String,
+            file_path_str: String,
+            file_size: u64,
+        },
+        /// Unchanged file indexed before content hashes were recorded.
+        HashBackfill {
+            file_path_str: String,
+            file_hash: String,
+        },
+        /// Unchanged file flagged for a re-probe; `None` when ffprobe failed.
+        Reprobed {
+            media_id: String,
+            streams: Option<Vec<StreamInsert>>,
+        },
+    }
+
+    let extra_scope = Arc::new(filename::ExtraScope::new(
+        &roots,
+        files.iter().map(|f| f.path.as_path()),
+    ));
     let probe_stream = stream::iter(files)
         .map(|file| {
             let probe_sem = probe_sem.clone();
+            let extra_scope = extra_scope.clone();
             let ffprobe = ffprobe_path.to_string();
             let scan_state = scan_state.clone();
             let existing = existing.clone();
+            let reprobe = reprobe.clone();
+            let moved_files = moved_files.clone();
 
             async move {
+                if !scan_state.checkpoint().await {
+                    return Ok(None);
+                }
                 let file_path_str = file.path.to_string_lossy().to_string();
-                let file_stem = file
-                    .path
-                    .file_stem()
-                    .map(|s| s.to_string_lossy().to_string())
-                    .unwrap_or_default();
-
-                let parsed = filename::parse_filename(&file_stem);
-                let (title, year) = match &parsed {
-                    ParsedFilename::Movie(ParsedMovie { title, year }) => (title.clone(), *year),
-                    ParsedFilename::Episode(ParsedEpisode { show_name, .. }) => {
-                        (show_name.clone(), None)
-                    }
-                    ParsedFilename::Unknown(name) => (name.clone(), None),
-                };
+                let parsed = filename::parse_path(&file.path);
+                let extra = filename::detect_extra(&file.path, &extra_scope);
+                let (title, year) =
+                    index_title(&file.path, &parsed, extra.as_ref(), is_movie_library);
 
                 // Delta scan: skip unchanged files
-                let already_indexed = existing
-                    .get(&file_path_str)
-                    .map(|&sz| sz == file.size)
-                    .unwrap_or(false);
-
-                if already_indexed {
-                    debug!("Skipping unchanged file: {}", file_path_str);
-                    scan_state.inc_probed();
-                    return Ok::<Option<ProbedItem>, anyhow::Error>(None);
+                if let Some(&(size, has_hash)) = existing.get(&file_path_str) {
+                    if size == file.size {
+                        if let Some(media_id) = reprobe.get(&file_path_str) {
+                            let _permit = probe_sem.acquire().await.expect("semaphore closed");
+                            let streams = match probe::probe_file(&ffprobe, &file.path).await {
+                                Ok(pr) => Some(stream_inserts(&pr.streams)),
+                                Err(e) => {
+                                    warn!("ffprobe failed for {}: {}", file.path.display(), e);
+                                    None
+                                }
+                            };
+                            scan_state.inc_probed();
+                            return Ok(Some(ScanItem::Reprobed {
+                                media_id: media_id.clone(),
+                                streams,
+                            }));
+                        }
+                        debug!("Skipping unchanged file: {}", file_path_str);
+                        scan_state.inc_probed();
+                        if !has_hash {
+                            if let Ok(file_hash) = hash::partial_hash(&file.path).await {
+                                return Ok(Some(ScanItem::HashBackfill {
+                                    file_path_str,
+                                    file_hash,
+                                }));
+                            }
+                        }
+                        return Ok::<Option<ScanItem>, anyhow::Error>(None);
+                    }
+                }
+
+                let file_hash = match hash::partial_hash(&file.path).await {
+                    Ok(h) => Some(h),
+                    Err(e) => {
+                        warn!("Failed to hash {}: {}", file.path.display(), e);
+                        None
+                    }
+                };
+                if let Some(h) = file_hash.as_deref() {
+                    let claimed = moved_files
+                        .lock()
+                        .expect("moved file tracker poisoned")
+                        .claim(file.size, h);
+                    if let Some(media_id) = claimed {
+                        scan_state.inc_probed();
+                        return Ok(Some(ScanItem::Relocated {
+                            media_id,
+                            file_path_str,
+                            file_size: file.size,
+                        }));
+                    }
                 }
 
                 scan_state.set_current(&format!("Probing: {}", title)).await;
@@ -194,33 +408,7 @@ pub async fn scan_library(
                     let _permit = probe_sem.acquire().await.expect("semaphore closed");
                     match probe::probe_file(&ffprobe, &file.path).await {
                         Ok(pr) => {
-                            let stream_inserts = pr
-                                .streams
-                                .iter()
-                                .map(|s| StreamInsert {
-                                    stream_index: s.index,
-                                    stream_type: s.stream_type.clone(),
-                                    codec_name: s.codec_name.clone(),
-                                    codec_long_name: s.codec_long_name.clone(),
-                                    profile: s.profile.clone(),
-                                    language: s.language.clone(),
-                                    title: s.title.clone(),
-                                    is_default: s.is_default,
-                                    is_forced: s.is_forced,
-                                    width: s.width,
-                                    height: s.height,
-                                    frame_rate: s.frame_rate.clone(),
-                                    pixel_format: s.pixel_format.clone(),
-                                    bit_depth: s.bit_depth,
-                                    color_space: s.color_space.clone(),
-                                    color_transfer: s.color_transfer.clone(),
-                                    color_primaries: s.color_primaries.clone(),
-                                    channels: s.channels,
-                                    channel_layout: s.channel_layout.clone(),
-                                    sample_rate: s.sample_rate,
-                                    bitrate_bps: s.bitrate_bps,
-                                })
-                                .collect();
+                            let stream_inserts = stream_inserts(&pr.streams);
                             let chapter_inserts = pr
                                 .chapters
                                 .iter()
@@ -252,16 +440,18 @@ pub async fn scan_library(
 
                 scan_state.inc_probed();
 
-                Ok(Some(ProbedItem {
+                Ok(Some(ScanItem::Probed(Box::new(ProbedItem {
                     file_path_str,
                     file_size: file.size,
+                    file_hash,
                     title,
                     year,
                     parsed,
+                    extra: extra.map(|e| e.extra_type),
                     probe_data,
                     streams,
                     chapters,
-                }))
+                }))))
             }
         })
         .buffer_unordered(concurrent_probes * 2);
@@ -279,10 +469,40 @@ pub async fn scan_library(
 
         let mut inserted_in_chunk = 0u32;
         let mut enrichment_items = Vec::new();
+        let mut relocated_in_chunk: Vec<(String, String)> = Vec::new();
 
         for r in chunk {
             match r {
-                Ok(Some(item)) => {
+                Ok(Some(ScanItem::HashBackfill {
+                    file_path_str,
+                    file_hash,
+                })) => {
+                    if let Err(e) =
+                        media_repo::set_file_hash(&mut tx, &file_path_str, &file_hash).await
+                    {
+                        warn!("Failed to store hash for '{}': {}", file_path_str, e);
+                    }
+                }
+                Ok(Some(ScanItem::Reprobed { media_id, streams })) => {
+                    if let Some(streams) = streams {
+                        ferrite_db::stream_repo::replace_streams(&mut tx, &media_id, &streams)
+                            .await?;
+                    }
+                    // A file ffprobe can't read isn't retried on every scan
+                    media_repo::clear_needs_reprobe(&mut tx, &media_id).await?;
+                }
+                Ok(Some(ScanItem::Relocated {
+                    media_id,
+                    file_path_str,
+                    file_size,
+                })) => {
+                    media_repo::relocate_media_item(&mut tx, &media_id, &file_path_str, file_size)
+                        .await?;
+                    info!("Relocated moved file to {}", file_path_str);
+                    relocated_in_chunk.push((media_id, file_path_str));
+                }
+                Ok(Some(ScanItem::Probed(item))) => {
+                    let item = *item;
                     scan_state
                         .set_current(&format!("Indexing: {}", item.title))
                         .await;
@@ -293,6 +513,7 @@ pub async fn scan_library(
                         media_type,
                         &item.file_path_str,
                         item.file_size,
+                        item.file_hash.as_deref(),
                         Some(&item.title),
                         item.year,
                         item.probe_data.as_ref(),
@@ -319,7 +540,14 @@ pub async fn scan_library(
                         }
                     }
                     // Keyframe indexing is deferred to on-demand (lazy) probing at seek time.
-                    if is_movie_library {
+                    if let Some(extra_type) = item.extra {
+                        if let Err(e) =
+                            extra_repo::mark_extra(&mut tx, &mid, extra_type.as_str(), &item.title)
+                                .await
+                        {
+                            warn!("Failed to mark '{}' as an extra: {}", item.title, e);
+                        }
+                    } else if is_movie_library {
                         if let Err(e) = movie_repo::upsert_movie_skeleton(
                             &mut tx,
                             &mid,
@@ -335,43 +563,11 @@ pub async fn scan_library(
                         }
                     }
 
-                    let _show_id: Option<String> = if is_tv_library {
-                        if let ParsedFilename::Episode(ParsedEpisode {
-                            show_name,
-                            season,
-                            episode,
-                        }) = &item.parsed
-                        {
-                            match tv_repo::upsert_tv_show(&mut tx, library_id, show_name).await {
-                                Ok(show_id) => {
-                                    match tv_repo::upsert_season(&mut tx, &show_id, *season).await {
-                                        Ok(season_id) => {
-                                            if let Err(e) = tv_repo::upsert_episode(
-                                                &mut tx, &mid, &season_id, *episode,
-                                            )
-                                            .await
-                                            {
-                                                warn!("Failed to create episode for '{}' S{:02}E{:02}: {}", show_name, season, episode, e);
-                                            }
-                                        }
-                                        Err(e) => warn!(
-                                            "Failed to create season for '{}' S{:02}: {}",
-                                            show_name, season, e
-                                        ),
-                                    }
-                                    Some(show_id)
-                                }
-                                Err(e) => {
-                                    warn!("Failed to create TV show '{}': {}", show_name, e);
-                                    None
-                                }
-                            }
-                        } else {
-                            None
+                    if is_tv_library && item.extra.is_none() {
+                        if let ParsedFilename::Episode(episode) = &item.parsed {
+                            index_episode(&mut tx, library_id, &mid, episode).await;
                         }
-                    } else {
-                        None
-                    };
+                    }
 
                     inserted_in_chunk += 1;
 
@@ -402,7 +598,9 @@ pub async fn scan_library(
                         item.title.clone(),
                         embedded_streams,
                     ))));
-                    enrichment_items.push((mid, item.title, item.year));
+                    if item.extra.is_none() {
+                        enrichment_items.push((mid, item.title, item.year));
+                    }
                 }
                 Ok(None) => {}
                 Err(e) => {
@@ -415,6 +613,11 @@ pub async fn scan_library(
         tx.commit().await?;
         drop(_write_permit);
 
+        relocated += relocated_in_chunk.len() as u32;
+        for (media_id, file_path_str) in relocated_in_chunk {
+            refresh_sidecar_subtitles(pool, &media_id, Path::new(&file_path_str)).await;
+        }
+
         if inserted_in_chunk > 0 {
             scan_state
                 .files_inserted
@@ -429,10 +632,25 @@ pub async fn scan_library(
             }
         }
 
+        // Everything up to here is committed; stop before the next batch.
+        if scan_state.is_cancelled() {
+            break;
+        }
+
         tokio::task::yield_now().await;
     }
+    drop(chunk_stream);
 
-    library_repo::update_last_scanned(pool, library_id).await?;
+    // A cancelled scan keeps what it committed but isn't a completed scan.
+    if !scan_state.is_cancelled() {
+        library_repo::update_last_scanned(pool, library_id).await?;
+    }
+
+    if let Err(e) =
+        extras::classify_extras(pool, library_id, &roots, is_movie_library, is_tv_library).await
+    {
+        warn!("Extras classification failed for '{}': {}", library.name, e);
+    }
 
     if is_tv_library {
         let empty_seasons = tv_repo::delete_empty_seasons(pool).await.unwrap_or(0);
@@ -445,14 +663,40 @@ pub async fn scan_library(
         }
     }
 
+    if is_movie_library {
+        if let Err(e) = versions::refresh_movie_groups(pool, library_id).await {
+            warn!(
+                "Movie version grouping failed for '{}': {}",
+                library.name, e
+            );
+        }
+    }
+
+    if let Err(e) = extras::link_extras(pool, library_id, &roots).await {
+        warn!("Linking extras failed for '{}': {}", library.name, e);
+    }
+
     // Drop the pipeline sender so the enrichment worker knows Phase 1 is done.
     drop(movie_enrichment_tx);
 
     info!(
-        "Phase 1 complete for '{}': {} new items indexed",
-        library.name, count
+        "Phase 1 complete for '{}': {} new items indexed, {} moved item(s) relocated",
+        library.name, count, relocated
     );
 
+    // Collect items that need subtitle work (new/changed files only).
+    let subtitle_items: Vec<(String, String, String, Vec<extract::EmbeddedSubtitleStream>)> =
+        phase1_results
+            .into_iter()
+            .filter_map(|r| r.ok().flatten())
+            .collect();
+
+    if scan_state.is_cancelled() {
+        let pending: Vec<String> = subtitle_items.into_iter().map(|i| i.0).collect();
+        finish_cancelled_scan(pool, &library.name, &scan_state, &pending).await;
+        return Ok(count);
+    }
+
     // ── Phase 2: metadata enrichment ─────────────────────────────────────────
     // TV libraries: enrichment runs AFTER all files are committed so that
     // enrich_library_shows sees every season and episode for each show.
@@ -462,6 +706,7 @@ pub async fn scan_library(
     // at scan start but is now available).
     if let (Some(provider), Some(img_cache)) = (tmdb_provider.as_ref(), image_cache.as_ref()) {
         scan_state.set_status(ScanStatus::Enriching).await;
+        let control: &dyn EnrichmentControl = scan_state.as_ref();
 
         if is_tv_library {
             scan_state
@@ -472,6 +717,7 @@ pub async fn scan_library(
                 library_id,
                 provider.clone(),
                 img_cache.clone(),
+                Some(control),
             )
             .await
             {
@@ -491,6 +737,7 @@ pub async fn scan_library(
                 library_id,
                 provider.clone(),
                 img_cache.clone(),
+                Some(control),
             )
             .await
             {
@@ -504,6 +751,12 @@ pub async fn scan_library(
         }
     }
 
+    if scan_state.is_cancelled() {
+        let pending: Vec<String> = subtitle_items.into_iter().map(|i| i.0).collect();
+        finish_cancelled_scan(pool, &library.name, &scan_state, &pending).await;
+        return Ok(count);
+    }
+
     // Mark library as fully indexed — items are now visible in the UI.
     scan_state.set_status(ScanStatus::Complete).await;
     info!(
@@ -512,13 +765,6 @@ pub async fn scan_library(
     );
 
     // ── Phase 3: subtitle extraction (runs after library is fully visible) ────
-    // Collect items that need subtitle work (new/changed files only).
-    let subtitle_items: Vec<(String, String, String, Vec<extract::EmbeddedSubtitleStream>)> =
-        phase1_results
-            .into_iter()
-            .filter_map(|r| r.ok().flatten())
-            .collect();
-
     if !subtitle_items.is_empty() {
         info!(
             "Starting subtitle extraction for {} item(s) in '{}'",
@@ -527,7 +773,8 @@ pub async fn scan_library(
         );
         scan_state.set_status(ScanStatus::Subtitles).await;
 
-        let subtitle_results: Vec<()> = stream::iter(subtitle_items)
+        // Items whose subtitles weren't stored because the scan was cancelled.
+        let skipped: Vec<String> = stream::iter(subtitle_items)
             .map(|item| {
                 let (media_item_id, file_path_str, title, embedded_streams) = item;
                 let pool = pool.clone();
@@ -538,6 +785,9 @@ pub async fn scan_library(
                 let scan_state = scan_state.clone();
 
                 async move {
+                    if !scan_state.checkpoint().await {
+                        return Some(media_item_id);
+                    }
                     let mut all_subs =
                         subtitle::find_external_subtitles(Path::new(&file_path_str)).await;
 
@@ -570,13 +820,18 @@ pub async fn scan_library(
                             warn!("Failed to store subtitles for '{}': {}", title, e);
                         }
                     }
+                    None
                 }
             })
             .buffer_unordered(std::cmp::min(2, concurrent_probes))
+            .filter_map(|skipped| async move { skipped })
             .collect()
             .await;
 
-        let _ = subtitle_results;
+        if scan_state.is_cancelled() {
+            finish_cancelled_scan(pool, &library.name, &scan_state, &skipped).await;
+            return Ok(count);
+        }
         info!("Subtitle extraction complete for '{}'", library.name);
         scan_state.set_status(ScanStatus::Complete).await;
     }
@@ -584,6 +839,50 @@ pub async fn scan_library(
     Ok(count)
 }
 
+/// Wrap up a scan stopped by an administrator: flag the items still waiting
+/// for subtitle extraction so the next scan picks them up again.
+async fn finish_cancelled_scan(
+    pool: &SqlitePool,
+    library_name: &str,
+    scan_state: &ScanState,
+    pending_subtitles: &[String],
+) {
+    if !pending_subtitles.is_empty() {
+        if let Err(e) = media_repo::mark_subtitles_pending(pool, pending_subtitles).await {
+            warn!(
+                "Failed to flag {} item(s) in '{}' for subtitle extraction: {}",
+                pending_subtitles.len(),
+                library_name,
+                e
+            );
+        }
+    }
+    scan_state.set_current("").await;
+    scan_state.set_status(ScanStatus::Cancelled).await;
+    info!(
+        "Scan of '{}' cancelled; {} item(s) left for the next scan's subtitle pass",
+        library_name,
+        pending_subtitles.len()
+    );
+}
+
+/// What an incremental scan changed in the library.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct IncrementalScanResult {
+    pub indexed: u32,
+    pub relocated: u32,
+    pub removed: u32,
+}
+
+impl IncrementalScanResult {
+    /// Number of media items added, moved or removed.
+    pub fn total(&self) -> u32 {
+        self.indexed
+            .saturating_add(self.relocated)
+            .saturating_add(self.removed)
+    }
+}
+
 /// Incremental, path-scoped scan for watcher change bursts.
 ///
 /// Unlike `scan_library`, this routine avoids walking the whole library tree and
@@ -597,14 +896,15 @@ pub async fn scan_library_incremental(
     _concurrent_probes: usize,
     subtitle_cache_dir: &Path,
     changed_paths: &[PathBuf],
-) -> Result<u32> {
+) -> Result<IncrementalScanResult> {
     let library = library_repo::get_library(pool, library_id).await?;
-    let lib_path = Path::new(&library.path);
+    let roots = library_roots(&library);
 
-    if !lib_path.exists() {
-        warn!("Library path does not exist: {}", library.path);
-        return Ok(0);
+    if !roots.iter().any(|r| r.exists()) {
+        warn!("Library path does not exist: {}", library.paths.join(", "));
+        return Ok(IncrementalScanResult::default());
     }
+    let rules = library_ignore_rules(&library);
 
     let extensions: &[&str] = match library.library_type {
         LibraryType::Movie | LibraryType::Tv => VIDEO_EXTENSIONS,
@@ -617,16 +917,25 @@ pub async fn scan_library_incremental(
         LibraryType::Music => "track",
     };
 
-    let existing: HashMap<String, u64> = media_repo::get_all_file_sizes(pool, library_id)
+    let existing: HashMap<String, (u64, bool)> = media_repo::list_file_identities(pool, library_id)
         .await
-        .unwrap_or_default();
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|r| !r.subtitles_pending)
+        .map(|r| {
+            (
+                r.file_path,
+                (r.file_size.max(0) as u64, r.file_hash.is_some()),
+            )
+        })
+        .collect();
 
     let is_movie_library = matches!(library.library_type, LibraryType::Movie);
     let is_tv_library = matches!(library.library_type, LibraryType::Tv);
 
     let unique_paths: HashSet<PathBuf> = changed_paths
         .iter()
-        .filter(|p| p.starts_with(lib_path))
+        .filter(|p| roots.iter().any(|root| p.starts_with(root)))
         .cloned()
         .collect();
 
@@ -635,6 +944,13 @@ pub async fn scan_library_incremental(
 
     let mut indexed_count = 0u32;
     let mut removed_count = 0u32;
+    let mut relocated_count = 0u32;
+
+    // Items under paths that disappeared in this batch. They are only deleted
+    // after the batch's new files were checked, so a file that was moved or
+    // renamed reclaims its existing row instead.
+    let mut missing_items: Vec<media_repo::MediaFileIdentity> = Vec::new();
+    let mut present_files: Vec<(PathBuf, std::fs::Metadata)> = Vec::new();
 
     while let Some(path) = pending_paths.pop() {
         if !visited_paths.insert(path.clone()) {
@@ -644,27 +960,10 @@ pub async fn scan_library_incremental(
         let file_path_str = path.to_string_lossy().to_string();
 
         if !path.exists() {
-            let ext = path
-                .extension()
-                .and_then(|e| e.to_str())
-                .map(|e| e.to_ascii_lowercase());
-            let removed_rows = if ext.as_deref().is_some_and(|e| extensions.contains(&e)) {
-                media_repo::delete_media_item_by_path(pool, &file_path_str).await
-            } else {
-                media_repo::delete_media_items_by_path_prefix(pool, &file_path_str).await
-            };
-
-            match removed_rows {
-                Ok(rows) if rows > 0 => {
-                    removed_count = removed_count.saturating_add(rows as u32);
-                    info!(
-                        "Incremental scan removed {} row(s) for missing path: {}",
-                        rows, file_path_str
-                    );
-                }
-                Ok(_) => {}
+            match media_repo::list_file_identities_by_path_prefix(pool, &file_path_str).await {
+                Ok(rows) => missing_items.extend(rows),
                 Err(e) => warn!(
-                    "Failed to remove media for missing path '{}': {}",
+                    "Failed to look up media for missing path '{}': {}",
                     file_path_str, e
                 ),
             }
@@ -674,7 +973,7 @@ pub async fn scan_library_incremental(
         let metadata = match tokio::fs::metadata(&path).await {
             Ok(m) if m.is_file() => m,
             Ok(m) if m.is_dir() => {
-                if path.as_path() == lib_path {
+                if roots.contains(&path) {
                     debug!(
                         "Skipping library-root directory change during incremental scan: {}",
                         path.display()
@@ -687,7 +986,11 @@ pub async fn scan_library_incremental(
                     path.display()
                 );
 
-                match walker::walk_directory(&path, extensions).await {
+                if ignore::is_path_ignored(&roots, &rules, &path, true).await {
+                    debug!("Skipping ignored directory: {}", path.display());
+                    continue;
+                }
+                match walker::walk_directory(&path, extensions, &IgnoreRules::default()).await {
                     Ok(files) => {
                         pending_paths.extend(files.into_iter().map(|f| f.path));
                     }
@@ -706,7 +1009,41 @@ pub async fn scan_library_incremental(
                 continue;
             }
         };
+        if ignore::is_path_ignored(&roots, &rules, &path, false).await {
+            // An indexed file that is now excluded goes the way of a missing one
+            if existing.contains_key(&file_path_str) {
+                match media_repo::list_file_identities_by_path_prefix(pool, &file_path_str).await {
+                    Ok(rows) => missing_items.extend(rows),
+                    Err(e) => warn!(
+                        "Failed to look up media for ignored path '{}': {}",
+                        file_path_str, e
+                    ),
+                }
+            }
+            continue;
+        }
+        present_files.push((path, metadata));
+    }
+
+    let mut moved_files = hash::MovedFileTracker::default();
+    for row in &missing_items {
+        moved_files.add_missing(
+            row.id.clone(),
+            row.file_size.max(0) as u64,
+            row.file_hash.clone(),
+        );
+    }
+    let mut claimed_ids: HashSet<String> = HashSet::new();
+    let extra_scope = filename::ExtraScope::new(
+        &roots,
+        existing
+            .keys()
+            .map(Path::new)
+            .chain(present_files.iter().map(|(path, _)| path.as_path())),
+    );
 
+    for (path, metadata) in present_files {
+        let file_path_str = path.to_string_lossy().to_string();
         let ext = path
             .extension()
             .and_then(|e| e.to_str())
@@ -716,59 +1053,51 @@ pub async fn scan_library_incremental(
             continue;
         }
 
-        let already_indexed = existing
-            .get(&file_path_str)
-            .map(|&sz| sz == metadata.len())
-            .unwrap_or(false);
+        if let Some(&(size, has_hash)) = existing.get(&file_path_str) {
+            if size == metadata.len() {
+                debug!(
+                    "Skipping unchanged media path in incremental scan: {}",
+                    file_path_str
+                );
+                if !has_hash {
+                    if let Ok(file_hash) = hash::partial_hash(&path).await {
+                        let mut conn = pool.acquire().await?;
+                        media_repo::set_file_hash(&mut conn, &file_path_str, &file_hash).await?;
+                    }
+                }
+                continue;
+            }
+        }
 
-        if already_indexed {
-            debug!(
-                "Skipping unchanged media path in incremental scan: {}",
-                file_path_str
-            );
+        let file_hash = match hash::partial_hash(&path).await {
+            Ok(h) => Some(h),
+            Err(e) => {
+                warn!("Failed to hash {}: {}", path.display(), e);
+                None
+            }
+        };
+        if let Some(media_id) = file_hash
+            .as_deref()
+            .and_then(|h| moved_files.claim(metadata.len(), h))
+        {
+            let mut conn = pool.acquire().await?;
+            media_repo::relocate_media_item(&mut conn, &media_id, &file_path_str, metadata.len())
+                .await?;
+            drop(conn);
+            info!("Relocated moved file to {}", file_path_str);
+            refresh_sidecar_subtitles(pool, &media_id, &path).await;
+            claimed_ids.insert(media_id);
+            relocated_count = relocated_count.saturating_add(1);
             continue;
         }
 
-        let file_stem = path
-            .file_stem()
-            .map(|s| s.to_string_lossy().to_string())
-            .unwrap_or_default();
-        let parsed = filename::parse_filename(&file_stem);
-        let (title, year) = match &parsed {
-            ParsedFilename::Movie(ParsedMovie { title, year }) => (title.clone(), *year),
-            ParsedFilename::Episode(ParsedEpisode { show_name, .. }) => (show_name.clone(), None),
-            ParsedFilename::Unknown(name) => (name.clone(), None),
-        };
+        let parsed = filename::parse_path(&path);
+        let extra = filename::detect_extra(&path, &extra_scope);
+        let (title, year) = index_title(&path, &parsed, extra.as_ref(), is_movie_library);
 
         let (probe_data, streams, chapters) = match probe::probe_file(ffprobe_path, &path).await {
             Ok(pr) => {
-                let stream_inserts: Vec<StreamInsert> = pr
-                    .streams
-                    .iter()
-                    .map(|s| StreamInsert {
-                        stream_index: s.index,
-                        stream_type: s.stream_type.clone(),
-                        codec_name: s.codec_name.clone(),
-                        codec_long_name: s.codec_long_name.clone(),
-                        profile: s.profile.clone(),
-                        language: s.language.clone(),
-                        title: s.title.clone(),
-                        is_default: s.is_default,
-                        is_forced: s.is_forced,
-                        width: s.width,
-                        height: s.height,
-                        frame_rate: s.frame_rate.clone(),
-                        pixel_format: s.pixel_format.clone(),
-                        bit_depth: s.bit_depth,
-                        color_space: s.color_space.clone(),
-                        color_transfer: s.color_transfer.clone(),
-                        color_primaries: s.color_primaries.clone(),
-                        channels: s.channels,
-                        channel_layout: s.channel_layout.clone(),
-                        sample_rate: s.sample_rate,
-                        bitrate_bps: s.bitrate_bps,
-                    })
-                    .collect();
+                let stream_inserts: Vec<StreamInsert> = stream_inserts(&pr.streams);
                 let chapter_inserts: Vec<ChapterInsert> = pr
                     .chapters
                     .iter()
@@ -805,6 +1134,7 @@ pub async fn scan_library_incremental(
             media_type,
             &file_path_str,
             metadata.len(),
+            file_hash.as_deref(),
             Some(&title),
             year,
             probe_data.as_ref(),
@@ -826,7 +1156,13 @@ pub async fn scan_library_incremental(
         }
         // Keyframe indexing is deferred to on-demand (lazy) probing at seek time.
 
-        if is_movie_library {
+        if let Some(extra) = &extra {
+            if let Err(e) =
+                extra_repo::mark_extra(&mut tx, &mid, extra.extra_type.as_str(), &title).await
+            {
+                warn!("Failed to mark '{}' as an extra: {}", title, e);
+            }
+        } else if is_movie_library {
             if let Err(e) =
                 movie_repo::upsert_movie_skeleton(&mut tx, &mid, &title, year.map(|y| y as i64))
                     .await
@@ -835,32 +1171,9 @@ pub async fn scan_library_incremental(
             }
         }
 
-        if is_tv_library {
-            if let ParsedFilename::Episode(ParsedEpisode {
-                show_name,
-                season,
-                episode,
-            }) = &parsed
-            {
-                match tv_repo::upsert_tv_show(&mut tx, library_id, show_name).await {
-                    Ok(show_id) => match tv_repo::upsert_season(&mut tx, &show_id, *season).await {
-                        Ok(season_id) => {
-                            if let Err(e) =
-                                tv_repo::upsert_episode(&mut tx, &mid, &season_id, *episode).await
-                            {
-                                warn!(
-                                    "Failed to create episode for '{}' S{:02}E{:02}: {}",
-                                    show_name, season, episode, e
-                                );
-                            }
-                        }
-                        Err(e) => warn!(
-                            "Failed to create season for '{}' S{:02}: {}",
-                            show_name, season, e
-                        ),
-                    },
-                    Err(e) => warn!("Failed to create TV show '{}': {}", show_name, e),
-                }
+        if is_tv_library && extra.is_none() {
+            if let ParsedFilename::Episode(episode) = &parsed {
+                index_episode(&mut tx, library_id, &mid, episode).await;
             }
         }
 
@@ -908,6 +1221,35 @@ pub async fn scan_library_incremental(
         }
     }
 
+    for row in missing_items {
+        if claimed_ids.contains(&row.id) {
+            continue;
+        }
+        match media_repo::delete_media_item_by_path(pool, &row.file_path).await {
+            Ok(rows) if rows > 0 => {
+                removed_count = removed_count.saturating_add(rows as u32);
+                info!(
+                    "Incremental scan removed media for missing path: {}",
+                    row.file_path
+                );
+            }
+            Ok(_) => {}
+            Err(e) => warn!(
+                "Failed to remove media for missing path '{}': {}",
+                row.file_path, e
+            ),
+        }
+    }
+
+    let changed = indexed_count > 0 || removed_count > 0 || relocated_count > 0;
+    if changed {
+        if let Err(e) =
+            extras::classify_extras(pool, library_id, &roots, is_movie_library, is_tv_library).await
+        {
+            warn!("Extras classification failed for '{}': {}", library.name, e);
+        }
+    }
+
     if is_tv_library {
         let empty_seasons = tv_repo::delete_empty_seasons(pool).await.unwrap_or(0);
         let empty_shows = tv_repo::delete_empty_shows(pool).await.unwrap_or(0);
@@ -919,14 +1261,135 @@ pub async fn scan_library_incremental(
         }
     }
 
-    if indexed_count > 0 || removed_count > 0 {
+    if is_movie_library && changed {
+        if let Err(e) = versions::refresh_movie_groups(pool, library_id).await {
+            warn!(
+                "Movie version grouping failed for '{}': {}",
+                library.name, e
+            );
+        }
+    }
+
+    if changed {
+        if let Err(e) = extras::link_extras(pool, library_id, &roots).await {
+            warn!("Linking extras failed for '{}': {}", library.name, e);
+        }
         library_repo::update_last_scanned(pool, library_id).await?;
     }
 
     info!(
-        "Incremental scan complete for '{}': indexed={} removed={}",
-        library.name, indexed_count, removed_count
+        "Incremental scan complete for '{}': indexed={} relocated={} removed={}",
+        library.name, indexed_count, relocated_count, removed_count
     );
 
-    Ok(indexed_count.saturating_add(removed_count))
+    Ok(IncrementalScanResult {
+        indexed: indexed_count,
+        relocated: relocated_count,
+        removed: removed_count,
+    })
+}
+
+/// Attach an episode file to its show and season, creating both as needed.
+/// Specials named only by title get a placeholder number above any real one.
+pub(crate) async fn index_episode(
+    conn: &mut SqliteConnection,
+    library_id: &str,
+    media_item_id: &str,
+    episode: &ParsedEpisode,
+) {
+    let show_name = &episode.show_name;
+    let show_id = match tv_repo::upsert_tv_show(conn, library_id, show_name).await {
+        Ok(id) => id,
+        Err(e) => {
+            warn!("Failed to create TV show '{}': {}", show_name, e);
+            return;
+        }
+    };
+    let season_id = match tv_repo::upsert_season(conn, &show_id, episode.season).await {
+        Ok(id) => id,
+        Err(e) => {
+            warn!(
+                "Failed to create season for '{}' S{:02}: {}",
+                show_name, episode.season, e
+            );
+            return;
+        }
+    };
+    let numbers = if episode.special_title.is_some() {
+        match tv_repo::provisional_episode_number(conn, &season_id, media_item_id).await {
+            Ok(n) => vec![n],
+            Err(e) => {
+                warn!("Failed to number special of '{}': {}", show_name, e);
+                return;
+            }
+        }
+    } else {
+        episode.episode_numbers()
+    };
+    if let Err(e) = tv_repo::upsert_episode(conn, media_item_id, &season_id, &numbers).await {
+        warn!(
+            "Failed to create episode for '{}' S{:02}E{:02}: {}",
+            show_name, episode.season, episode.episode, e
+        );
+        return;
+    }
+    if let Some(absolute) = episode.absolute {
+        if let Err(e) = tv_repo::mark_episode_absolute(conn, media_item_id, absolute).await {
+            warn!(
+                "Failed to flag episode {} of '{}' for mapping: {}",
+                absolute, show_name, e
+            );
+        }
+    } else if episode.needs_match() {
+        if let Err(e) = tv_repo::mark_episode_unmatched(
+            conn,
+            media_item_id,
+            episode.air_date.as_deref(),
+            episode.special_title.as_deref(),
+        )
+        .await
+        {
+            warn!(
+                "Failed to flag episode of '{}' for matching: {}",
+                show_name, e
+            );
+        }
+    }
+}
+
+/// Re-discover sidecar subtitles next to a relocated file. Subtitles
+/// extracted into the cache are keyed by media ID and stay valid.
+async fn refresh_sidecar_subtitles(pool: &SqlitePool, media_item_id: &str, file_path: &Path) {
+    let existing = match ferrite_db::subtitle_repo::get_subtitles(pool, media_item_id).await {
+        Ok(rows) => rows,
+        Err(e) => {
+            warn!("Failed to load subtitles for {}: {}", media_item_id, e);
+            return;
+        }
+    };
+    let mut subtitles: Vec<ferrite_db::subtitle_repo::SubtitleInsert> = existing
+        .into_iter()
+        .filter(|s| {
+            // Extracted subtitles live in `{subtitle_cache_dir}/{media_item_id}/`.
+            Path::new(&s.file_path)
+                .parent()
+                .and_then(|dir| dir.file_name())
+                .is_some_and(|name| name == media_item_id)
+        })
+        .map(|s| ferrite_db::subtitle_repo::SubtitleInsert {
+            file_path: s.file_path,
+            format: s.format,
+            language: s.language,
+            title: s.title,
+            is_forced: s.is_forced != 0,
+            is_sdh: s.is_sdh != 0,
+            file_size: s.file_size.max(0) as u64,
+        })
+        .collect();
+    subtitles.extend(subtitle::find_external_subtitles(file_path).await);
+    if let Err(e) =
+        ferrite_db::subtitle_repo::replace_subtitles(pool, media_item_id, &subtitles).await
+    {
+        warn!("Failed to refresh subtitles for {}: {}", media_item_id, e);
+    }
 }
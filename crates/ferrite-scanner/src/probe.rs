@@ -27,14 +27,31 @@ pub struct StreamInfo {
     pub color_space: Option<String>,
     pub color_transfer: Option<String>,
     pub color_primaries: Option<String>,
+    pub dolby_vision: Option<DolbyVisionConfig>,
+    /// SMPTE ST 2094-40 dynamic metadata on the first frame.
+    pub hdr10_plus: bool,
     // Audio-specific
     pub channels: Option<u32>,
     pub channel_layout: Option<String>,
     pub sample_rate: Option<u32>,
+    /// `atmos` or `dts_x` when the profile reports object-based audio.
+    pub spatial_audio: Option<String>,
     // Common
     pub bitrate_bps: Option<u64>,
 }
 
+/// Dolby Vision configuration record of a video stream.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DolbyVisionConfig {
+    pub profile: u8,
+    pub level: u8,
+    pub rpu_present: bool,
+    pub el_present: bool,
+    /// Base layer signal compatibility: 0 none (profile 5), 1 HDR10,
+    /// 2 SDR, 4 HLG, 6 HDR10 (Blu-ray).
+    pub bl_compat_id: u8,
+}
+
 /// A single chapter extracted from ffprobe.
 #[derive(Debug, Clone)]
 pub struct ChapterInfo {
@@ -136,47 +153,16 @@ pub async fn probe_file(ffprobe_path: &str, file_path: &Path) -> Result<ProbeRes
     let audio_codec = audio_stream.and_then(|s| s.codec_name.clone());
 
     // Build detailed StreamInfo for every stream
-    let streams: Vec<StreamInfo> = json
-        .streams
-        .iter()
-        .filter_map(|s| {
-            let stream_type = s.codec_type.as_deref()?;
-            // Only capture video, audio, and subtitle streams
-            if !matches!(stream_type, "video" | "audio" | "subtitle") {
-                return None;
-            }
-
-            let disposition = s.disposition.as_ref();
-            let tags = s.tags.as_ref();
-
-            Some(StreamInfo {
-                index: s.index.unwrap_or(0),
-                stream_type: stream_type.to_string(),
-                codec_name: s.codec_name.clone(),
-                codec_long_name: s.codec_long_name.clone(),
-                profile: s.profile.clone(),
-                language: tags.and_then(|t| t.language.clone()),
-                title: tags.and_then(|t| t.title.clone()),
-                is_default: disposition.and_then(|d| d.default).unwrap_or(0) != 0,
-                is_forced: disposition.and_then(|d| d.forced).unwrap_or(0) != 0,
-                width: s.width,
-                height: s.height,
-                frame_rate: s.r_frame_rate.clone(),
-                pixel_format: s.pix_fmt.clone(),
-                bit_depth: s
-                    .bits_per_raw_sample
-                    .as_deref()
-                    .and_then(|b| b.parse().ok()),
-                color_space: s.color_space.clone(),
-                color_transfer: s.color_transfer.clone(),
-                color_primaries: s.color_primaries.clone(),
-                channels: s.channels,
-                channel_layout: s.channel_layout.clone(),
-                sample_rate: s.sample_rate.as_deref().and_then(|r| r.parse().ok()),
-                bitrate_bps: s.bit_rate.as_deref().and_then(|b| b.parse().ok()),
-            })
-        })
-        .collect();
+    let mut streams: Vec<StreamInfo> = json.streams.iter().filter_map(stream_info).collect();
+
+    // HDR10+ metadata is carried per frame, so it only shows up when a frame
+    // is read. PQ is the only transfer it is defined for.
+    for stream in streams
+        .iter_mut()
+        .filter(|s| s.stream_type == "video" && s.color_transfer.as_deref() == Some("smpte2084"))
+    {
+        stream.hdr10_plus = probe_hdr10_plus(ffprobe_path, file_path, stream.index).await;
+    }
 
     // Keyframe indexing is deferred to on-demand (lazy) probing at seek time.
     // This avoids the expensive full-file read during scanning.
@@ -236,6 +222,116 @@ pub async fn probe_file(ffprobe_path: &str, file_path: &Path) -> Result<ProbeRes
     })
 }
 
+/// Map one ffprobe stream to a [`StreamInfo`], skipping data and attachment streams.
+fn stream_info(s: &FfprobeStream) -> Option<StreamInfo> {
+    let stream_type = s.codec_type.as_deref()?;
+    // Only capture video, audio, and subtitle streams
+    if !matches!(stream_type, "video" | "audio" | "subtitle") {
+        return None;
+    }
+
+    let disposition = s.disposition.as_ref();
+    let tags = s.tags.as_ref();
+
+    Some(StreamInfo {
+        index: s.index.unwrap_or(0),
+        stream_type: stream_type.to_string(),
+        codec_name: s.codec_name.clone(),
+        codec_long_name: s.codec_long_name.clone(),
+        profile: s.profile.clone(),
+        language: tags.and_then(|t| t.language.clone()),
+        title: tags.and_then(|t| t.title.clone()),
+        is_default: disposition.and_then(|d| d.default).unwrap_or(0) != 0,
+        is_forced: disposition.and_then(|d| d.forced).unwrap_or(0) != 0,
+        width: s.width,
+        height: s.height,
+        frame_rate: s.r_frame_rate.clone(),
+        pixel_format: s.pix_fmt.clone(),
+        bit_depth: s
+            .bits_per_raw_sample
+            .as_deref()
+            .and_then(|b| b.parse().ok()),
+        color_space: s.color_space.clone(),
+        color_transfer: s.color_transfer.clone(),
+        color_primaries: s.color_primaries.clone(),
+        dolby_vision: s.side_data_list.iter().find_map(dolby_vision_config),
+        hdr10_plus: s.side_data_list.iter().any(is_hdr10_plus),
+        channels: s.channels,
+        channel_layout: s.channel_layout.clone(),
+        sample_rate: s.sample_rate.as_deref().and_then(|r| r.parse().ok()),
+        spatial_audio: spatial_audio(s.profile.as_deref()).map(str::to_string),
+        bitrate_bps: s.bit_rate.as_deref().and_then(|b| b.parse().ok()),
+    })
+}
+
+fn dolby_vision_config(side_data: &FfprobeSideData) -> Option<DolbyVisionConfig> {
+    if side_data.side_data_type.as_deref() != Some("DOVI configuration record") {
+        return None;
+    }
+    Some(DolbyVisionConfig {
+        profile: side_data.dv_profile? as u8,
+        level: side_data.dv_level.unwrap_or(0) as u8,
+        rpu_present: side_data.rpu_present_flag.unwrap_or(0) != 0,
+        el_present: side_data.el_present_flag.unwrap_or(0) != 0,
+        bl_compat_id: side_data.dv_bl_signal_compatibility_id.unwrap_or(0) as u8,
+    })
+}
+
+fn is_hdr10_plus(side_data: &FfprobeSideData) -> bool {
+    side_data
+        .side_data_type
+        .as_deref()
+        .is_some_and(|t| t.contains("SMPTE2094-40"))
+}
+
+/// Object-based audio reported in the stream profile, e.g.
+/// "Dolby TrueHD + Dolby Atmos" or "DTS-HD MA + DTS:X".
+fn spatial_audio(profile: Option<&str>) -> Option<&'static str> {
+    let profile = profile?.to_ascii_lowercase();
+    if profile.contains("atmos") {
+        Some("atmos")
+    } else if profile.contains("dts:x") {
+        Some("dts_x")
+    } else {
+        None
+    }
+}
+
+/// Check the first frame of stream `index` for HDR10+ dynamic metadata.
+async fn probe_hdr10_plus(ffprobe_path: &str, file_path: &Path, index: u32) -> bool {
+    let output = Command::new(ffprobe_path)
+        .args([
+            "-v",
+            "quiet",
+            "-print_format",
+            "json",
+            "-select_streams",
+            &index.to_string(),
+            "-read_intervals",
+            "%+#1",
+            "-show_entries",
+            "frame=side_data_list",
+            "-show_frames",
+        ])
+        .arg(file_path)
+        .output()
+        .await;
+    let output = match output {
+        Ok(output) if output.status.success() => output,
+        _ => {
+            debug!("HDR10+ probe failed for {}", file_path.display());
+            return false;
+        }
+    };
+    serde_json::from_slice::<FfprobeFrames>(&output.stdout)
+        .map(|json| {
+            json.frames
+                .iter()
+                .any(|f| f.side_data_list.iter().any(is_hdr10_plus))
+        })
+        .unwrap_or(false)
+}
+
 /// Probe keyframe positions from a media file using ffprobe.
 ///
 /// This runs `ffprobe -skip_frame nokey` which reads the entire video file
@@ -346,6 +442,30 @@ struct FfprobeStream {
     bit_rate: Option<String>,
     disposition: Option<FfprobeDisposition>,
     tags: Option<FfprobeTags>,
+    #[serde(default)]
+    side_data_list: Vec<FfprobeSideData>,
+}
+
+#[derive(Deserialize)]
+struct FfprobeSideData {
+    side_data_type: Option<String>,
+    dv_profile: Option<u32>,
+    dv_level: Option<u32>,
+    rpu_present_flag: Option<u32>,
+    el_present_flag: Option<u32>,
+    dv_bl_signal_compatibility_id: Option<u32>,
+}
+
+#[derive(Deserialize)]
+struct FfprobeFrames {
+    #[serde(default)]
+    frames: Vec<FfprobeFrame>,
+}
+
+#[derive(Deserialize)]
+struct FfprobeFrame {
+    #[serde(default)]
+    side_data_list: Vec<FfprobeSideData>,
 }
 
 #[derive(Deserialize)]
@@ -366,3 +486,108 @@ struct FfprobeFormat {
     duration: Option<String>,
     bit_rate: Option<String>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_stream(json: &str) -> StreamInfo {
+        let stream: FfprobeStream = serde_json::from_str(json).unwrap();
+        stream_info(&stream).unwrap()
+    }
+
+    #[test]
+    fn dolby_vision_configuration_record_is_read() {
+        let stream = parse_stream(
+            r#"{
+                "index": 0,
+                "codec_name": "hevc",
+                "codec_type": "video",
+                "pix_fmt": "yuv420p10le",
+                "color_transfer": "smpte2084",
+                "color_primaries": "bt2020",
+                "side_data_list": [
+                    {
+                        "side_data_type": "DOVI configuration record",
+                        "dv_version_major": 1,
+                        "dv_version_minor": 0,
+                        "dv_profile": 8,
+                        "dv_level": 6,
+                        "rpu_present_flag": 1,
+                        "el_present_flag": 0,
+                        "bl_present_flag": 1,
+                        "dv_bl_signal_compatibility_id": 1
+                    }
+                ]
+            }"#,
+        );
+        assert_eq!(
+            stream.dolby_vision,
+            Some(DolbyVisionConfig {
+                profile: 8,
+                level: 6,
+                rpu_present: true,
+                el_present: false,
+                bl_compat_id: 1,
+            })
+        );
+        assert!(!stream.hdr10_plus);
+        assert_eq!(stream.spatial_audio, None);
+    }
+
+    #[test]
+    fn plain_hdr10_has_no_dolby_vision() {
+        let stream = parse_stream(
+            r#"{
+                "index": 0,
+                "codec_name": "hevc",
+                "codec_type": "video",
+                "color_transfer": "smpte2084",
+                "side_data_list": [
+                    {"side_data_type": "Mastering display metadata"},
+                    {"side_data_type": "Content light level metadata"}
+                ]
+            }"#,
+        );
+        assert_eq!(stream.dolby_vision, None);
+        assert!(!stream.hdr10_plus);
+    }
+
+    #[test]
+    fn hdr10_plus_frame_side_data_is_detected() {
+        let frames: FfprobeFrames = serde_json::from_str(
+            r#"{"frames": [{"side_data_list": [
+                {"side_data_type": "Mastering display metadata"},
+                {"side_data_type": "HDR Dynamic Metadata SMPTE2094-40 (HDR10+)"}
+            ]}]}"#,
+        )
+        .unwrap();
+        assert!(frames.frames[0].side_data_list.iter().any(is_hdr10_plus));
+    }
+
+    #[test]
+    fn object_audio_is_read_from_the_profile() {
+        let truehd = parse_stream(
+            r#"{"index": 1, "codec_name": "truehd", "codec_type": "audio",
+                "profile": "Dolby TrueHD + Dolby Atmos", "channels": 8}"#,
+        );
+        assert_eq!(truehd.spatial_audio.as_deref(), Some("atmos"));
+
+        let eac3 = parse_stream(
+            r#"{"index": 1, "codec_name": "eac3", "codec_type": "audio",
+                "profile": "Dolby Digital Plus + Dolby Atmos"}"#,
+        );
+        assert_eq!(eac3.spatial_audio.as_deref(), Some("atmos"));
+
+        let dts = parse_stream(
+            r#"{"index": 2, "codec_name": "dts", "codec_type": "audio",
+                "profile": "DTS-HD MA + DTS:X"}"#,
+        );
+        assert_eq!(dts.spatial_audio.as_deref(), Some("dts_x"));
+
+        let plain = parse_stream(
+            r#"{"index": 3, "codec_name": "dts", "codec_type": "audio", "profile": "DTS-HD MA"}"#,
+        );
+        assert_eq!(plain.spatial_audio, None);
+    }
+}
@@ -1,10 +1,12 @@
+use async_trait::async_trait;
 use dashmap::DashMap;
+use ferrite_metadata::enrichment::EnrichmentControl;
 use serde::Serialize;
 use std::sync::{
     atomic::{AtomicU32, AtomicU64, Ordering},
     Arc,
 };
-use tokio::sync::RwLock;
+use tokio::sync::{watch, RwLock};
 
 #[derive(Debug, Clone, PartialEq, Serialize)]
 #[serde(rename_all = "snake_case")]
@@ -14,6 +16,15 @@ pub enum ScanStatus {
     Subtitles,
     Complete,
     Failed,
+    Cancelled,
+}
+
+/// What an administrator asked a running scan to do.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ScanControl {
+    Run,
+    Pause,
+    Cancel,
 }
 
 /// Per-library scan progress, updated atomically as work proceeds.
@@ -30,6 +41,7 @@ pub struct ScanState {
     pub started_at_unix: AtomicU64,
     /// Timestamp (unix secs) when the current phase started. Reset at each phase transition.
     pub phase_started_at_unix: AtomicU64,
+    control: watch::Sender<ScanControl>,
 }
 
 impl ScanState {
@@ -50,9 +62,67 @@ impl ScanState {
             current_item: RwLock::new(String::new()),
             started_at_unix: AtomicU64::new(started),
             phase_started_at_unix: AtomicU64::new(started),
+            control: watch::Sender::new(ScanControl::Run),
         })
     }
 
+    /// Whether the scan is still working (possibly paused).
+    pub async fn is_running(&self) -> bool {
+        matches!(
+            *self.status.read().await,
+            ScanStatus::Scanning | ScanStatus::Enriching | ScanStatus::Subtitles
+        )
+    }
+
+    /// Hold the scan at its next checkpoint. Returns `false` if it is being cancelled.
+    pub fn pause(&self) -> bool {
+        self.control.send_if_modified(|c| {
+            let paused = *c == ScanControl::Run;
+            if paused {
+                *c = ScanControl::Pause;
+            }
+            paused
+        });
+        !self.is_cancelled()
+    }
+
+    /// Let a paused scan continue. Returns `false` if it is being cancelled.
+    pub fn resume(&self) -> bool {
+        self.control.send_if_modified(|c| {
+            let resumed = *c == ScanControl::Pause;
+            if resumed {
+                *c = ScanControl::Run;
+            }
+            resumed
+        });
+        !self.is_cancelled()
+    }
+
+    /// Stop the scan at its next checkpoint; a paused scan stops right away.
+    pub fn cancel(&self) {
+        self.control.send_replace(ScanControl::Cancel);
+    }
+
+    pub fn is_paused(&self) -> bool {
+        *self.control.borrow() == ScanControl::Pause
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        *self.control.borrow() == ScanControl::Cancel
+    }
+
+    /// Cooperative cancellation point: waits while the scan is paused and
+    /// returns `false` once it has been cancelled.
+    pub async fn checkpoint(&self) -> bool {
+        let mut control = self.control.subscribe();
+        let state = match control.wait_for(|c| *c != ScanControl::Pause).await {
+            Ok(c) => *c,
+            // The sender lives in `self`, so this can't happen.
+            Err(_) => ScanControl::Cancel,
+        };
+        state == ScanControl::Run
+    }
+
     pub async fn set_status(&self, status: ScanStatus) {
         let now = std::time::SystemTime::now()
             .duration_since(std::time::UNIX_EPOCH)
@@ -87,7 +157,14 @@ impl ScanState {
     }
 }
 
-#[derive(Serialize)]
+#[async_trait]
+impl EnrichmentControl for ScanState {
+    async fn proceed(&self) -> bool {
+        self.checkpoint().await
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
 pub struct ScanProgress {
     pub scanning: bool,
     pub status: ScanStatus,
@@ -98,6 +175,10 @@ pub struct ScanProgress {
     pub items_enriched: u32,
     pub errors: u32,
     pub current_item: String,
+    /// Held at a checkpoint until resumed.
+    pub paused: bool,
+    /// Cancel requested; the scan is finishing its current batch.
+    pub cancelling: bool,
     pub elapsed_seconds: u64,
     pub phase_elapsed_seconds: u64,
     pub estimated_remaining_seconds: Option<u64>,
@@ -127,7 +208,9 @@ impl ScanState {
 
         // ETA: extrapolate from Phase 1 probe rate (most predictable phase).
         // Only compute when actively scanning and we have meaningful progress.
+        let paused = self.is_paused();
         let estimated_remaining_seconds = if matches!(status, ScanStatus::Scanning)
+            && !paused
             && probed > 0
             && total > probed
             && phase_elapsed > 0
@@ -149,6 +232,8 @@ impl ScanState {
             items_enriched: self.items_enriched.load(Ordering::Relaxed),
             errors: self.errors.load(Ordering::Relaxed),
             current_item: self.current_item.read().await.clone(),
+            paused,
+            cancelling: scanning && self.is_cancelled(),
             elapsed_seconds: elapsed,
             phase_elapsed_seconds: phase_elapsed,
             estimated_remaining_seconds,
@@ -198,6 +283,11 @@ impl ScanRegistry {
         self.0.get(library_id).map(|r| r.clone())
     }
 
+    /// Scan states of every library scanned since startup, running or not.
+    pub fn list(&self) -> Vec<Arc<ScanState>> {
+        self.0.iter().map(|r| r.value().clone()).collect()
+    }
+
     /// Remove a library's scan state from the registry (e.g. on library deletion).
     pub fn remove(&self, library_id: &str) {
         self.0.remove(library_id);
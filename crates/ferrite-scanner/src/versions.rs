@@ -0,0 +1,177 @@
+use crate::filename::{self, MovieVariant};
+use anyhow::Result;
+use ferrite_db::movie_repo::{self, MovieFileRow, MovieGroupAssignment};
+use sqlx::SqlitePool;
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use tracing::info;
+
+/// Recompute movie version groups and multi-part stacks for a movie library.
+///
+/// Runs after every scan so groups follow files as they are added, moved or
+/// removed. Returns the number of media items whose assignment changed.
+pub async fn refresh_movie_groups(pool: &SqlitePool, library_id: &str) -> Result<u64> {
+    let files = movie_repo::list_movie_files(pool, library_id).await?;
+    let assignments = plan_movie_groups(&files);
+    let changed = movie_repo::apply_movie_groups(pool, &assignments).await?;
+    if changed > 0 {
+        info!(
+            "Updated version/stack grouping for {} movie files in library {}",
+            changed, library_id
+        );
+    }
+    Ok(changed)
+}
+
+/// Files of one logical movie, keyed by [`group_key`].
+type MovieGroups<'a> = HashMap<(String, Option<i32>), Vec<(&'a MovieFileRow, MovieVariant)>>;
+
+/// Group key: title folded to lowercase alphanumerics plus the year, so
+/// `The.Matrix.1999` and `The Matrix (1999)` land together.
+fn group_key(variant: &MovieVariant) -> (String, Option<i32>) {
+    let title = variant
+        .title
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() {
+                c.to_ascii_lowercase()
+            } else {
+                ' '
+            }
+        })
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ");
+    (title, variant.year)
+}
+
+/// Assign every movie file to its logical movie.
+///
+/// Files with a part marker and the same version label stack behind the lowest
+/// part. Every other file (and each stack head) is a version; the earliest-added
+/// version is the group's primary, which keeps the primary — and the metadata
+/// attached to it — stable as more versions show up.
+pub fn plan_movie_groups(files: &[MovieFileRow]) -> Vec<MovieGroupAssignment> {
+    let mut groups: MovieGroups<'_> = HashMap::new();
+    for file in files {
+        let stem = Path::new(&file.file_path)
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let variant = filename::parse_movie_variant(&stem);
+        groups
+            .entry(group_key(&variant))
+            .or_default()
+            .push((file, variant));
+    }
+
+    let mut assignments = Vec::with_capacity(files.len());
+    for members in groups.into_values() {
+        // Stacks keyed by lowercase version label; heads are the lowest part.
+        let mut stacks: BTreeMap<Option<String>, Vec<(&MovieFileRow, &MovieVariant)>> =
+            BTreeMap::new();
+        let mut versions: Vec<&MovieFileRow> = Vec::new();
+        for (file, variant) in &members {
+            match variant.part {
+                Some(_) => stacks
+                    .entry(variant.version.as_ref().map(|v| v.to_lowercase()))
+                    .or_default()
+                    .push((file, variant)),
+                None => versions.push(file),
+            }
+        }
+
+        let mut stack_heads: HashMap<&str, &str> = HashMap::new();
+        for parts in stacks.values_mut() {
+            parts.sort_by(|a, b| (a.1.part, &a.0.file_path).cmp(&(b.1.part, &b.0.file_path)));
+            let head = parts[0].0;
+            versions.push(head);
+            for (file, _) in parts.iter().skip(1) {
+                stack_heads.insert(file.id.as_str(), head.id.as_str());
+            }
+        }
+
+        let primary = if members.len() > 1 {
+            versions
+                .iter()
+                .min_by(|a, b| (&a.added_at, &a.id).cmp(&(&b.added_at, &b.id)))
+                .map(|f| f.id.clone())
+        } else {
+            None
+        };
+
+        for (file, variant) in &members {
+            assignments.push(MovieGroupAssignment {
+                media_item_id: file.id.clone(),
+                movie_group_id: primary.clone(),
+                version_label: variant.version.clone(),
+                part_number: variant.part.map(i64::from),
+                stack_head_id: stack_heads.get(file.id.as_str()).map(|s| s.to_string()),
+            });
+        }
+    }
+    assignments
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(id: &str, path: &str, added_at: &str) -> MovieFileRow {
+        MovieFileRow {
+            id: id.into(),
+            file_path: path.into(),
+            added_at: added_at.into(),
+            movie_group_id: None,
+            version_label: None,
+            part_number: None,
+            stack_head_id: None,
+        }
+    }
+
+    fn find<'a>(plan: &'a [MovieGroupAssignment], id: &str) -> &'a MovieGroupAssignment {
+        plan.iter().find(|a| a.media_item_id == id).unwrap()
+    }
+
+    #[test]
+    fn versions_group_under_earliest_added_primary() {
+        let files = vec![
+            file(
+                "b",
+                "/m/Inception (2010) - 2160p.mkv",
+                "2024-02-01 00:00:00",
+            ),
+            file(
+                "a",
+                "/m/Inception (2010) - 1080p.mkv",
+                "2024-01-01 00:00:00",
+            ),
+            file("c", "/m/Heat (1995).mkv", "2024-01-01 00:00:00"),
+        ];
+        let plan = plan_movie_groups(&files);
+
+        assert_eq!(find(&plan, "a").movie_group_id.as_deref(), Some("a"));
+        assert_eq!(find(&plan, "b").movie_group_id.as_deref(), Some("a"));
+        assert_eq!(find(&plan, "b").version_label.as_deref(), Some("2160p"));
+        assert_eq!(find(&plan, "c").movie_group_id, None);
+    }
+
+    #[test]
+    fn parts_stack_behind_the_first_part() {
+        let files = vec![
+            file("p2", "/m/Heat (1995) cd2.avi", "2024-01-01 00:00:00"),
+            file("p1", "/m/Heat (1995) cd1.avi", "2024-01-02 00:00:00"),
+            file("v", "/m/Heat.1995.2160p.mkv", "2024-03-01 00:00:00"),
+        ];
+        let plan = plan_movie_groups(&files);
+
+        // The cd2 file was added first but can never be the primary.
+        assert_eq!(find(&plan, "p1").movie_group_id.as_deref(), Some("p1"));
+        assert_eq!(find(&plan, "p1").stack_head_id, None);
+        assert_eq!(find(&plan, "p2").stack_head_id.as_deref(), Some("p1"));
+        assert_eq!(find(&plan, "p2").part_number, Some(2));
+        assert_eq!(find(&plan, "v").movie_group_id.as_deref(), Some("p1"));
+        assert_eq!(find(&plan, "v").stack_head_id, None);
+    }
+}
@@ -1,8 +1,13 @@
+use crate::ignore::{self, IgnoreRules};
 use anyhow::Result;
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use tokio::fs;
 use tracing::debug;
 
+/// Ignore files in effect for a folder, each with the folder it applies from.
+type ScopedRules = Vec<(PathBuf, Arc<IgnoreRules>)>;
+
 /// Info about a discovered media file.
 #[derive(Debug)]
 pub struct DiscoveredFile {
@@ -11,11 +16,31 @@ pub struct DiscoveredFile {
 }
 
 /// Recursively walk a directory and return all files with matching extensions.
-pub async fn walk_directory(root: &Path, extensions: &[&str]) -> Result<Vec<DiscoveredFile>> {
+///
+/// `rules` are matched against paths relative to `root`; any `.ferriteignore`
+/// found on the way applies to its own folder and below. Excluded folders are
+/// not descended into.
+pub async fn walk_directory(
+    root: &Path,
+    extensions: &[&str],
+    rules: &IgnoreRules,
+) -> Result<Vec<DiscoveredFile>> {
     let mut files = Vec::new();
-    let mut stack = vec![root.to_path_buf()];
+    let mut stack: Vec<(PathBuf, ScopedRules)> = vec![(root.to_path_buf(), Vec::new())];
+
+    while let Some((dir, mut scoped)) = stack.pop() {
+        if let Some(local) = ignore::load_ignore_file(&dir).await {
+            scoped.push((dir.clone(), Arc::new(local)));
+        }
+        let excluded = |path: &Path, is_dir: bool| {
+            path.strip_prefix(root)
+                .is_ok_and(|rel| rules.is_ignored(rel, is_dir))
+                || scoped.iter().any(|(base, local)| {
+                    path.strip_prefix(base)
+                        .is_ok_and(|rel| local.is_ignored(rel, is_dir))
+                })
+        };
 
-    while let Some(dir) = stack.pop() {
         let mut entries = fs::read_dir(&dir).await?;
         while let Some(entry) = entries.next_entry().await? {
             let path = entry.path();
@@ -26,11 +51,19 @@ pub async fn walk_directory(root: &Path, extensions: &[&str]) -> Result<Vec<Disc
             };
 
             if metadata.is_dir() {
-                stack.push(path);
+                if excluded(&path, true) {
+                    debug!("Skipping ignored folder: {}", path.display());
+                    continue;
+                }
+                stack.push((path, scoped.clone()));
             } else if metadata.is_file() {
                 if let Some(ext) = path.extension().and_then(|e| e.to_str()) {
                     let ext_lower = ext.to_lowercase();
                     if extensions.contains(&ext_lower.as_str()) {
+                        if excluded(&path, false) {
+                            debug!("Skipping ignored file: {}", path.display());
+                            continue;
+                        }
                         debug!("Found media file: {}", path.display());
                         files.push(DiscoveredFile {
                             path,
@@ -45,3 +78,18 @@ pub async fn walk_directory(root: &Path, extensions: &[&str]) -> Result<Vec<Disc
     files.sort_by(|a, b| a.path.cmp(&b.path));
     Ok(files)
 }
+
+/// Walk every root folder of a library. Roots that don't exist are skipped.
+pub async fn walk_roots(
+    roots: &[PathBuf],
+    extensions: &[&str],
+    rules: &IgnoreRules,
+) -> Result<Vec<DiscoveredFile>> {
+    let mut files = Vec::new();
+    for root in roots.iter().filter(|r| r.exists()) {
+        files.extend(walk_directory(root, extensions, rules).await?);
+    }
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+    files.dedup_by(|a, b| a.path == b.path);
+    Ok(files)
+}
@@ -2,21 +2,25 @@ use anyhow::Result;
 use ferrite_core::media::LibraryType;
 use ferrite_db::library_repo;
 use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use serde::Serialize;
 use sqlx::SqlitePool;
 use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::time::Duration;
-use tokio::sync::mpsc;
+use tokio::sync::{broadcast, mpsc};
 use tracing::{debug, info, warn};
 
 const MAX_INCREMENTAL_BATCH_PATHS: usize = 256;
 
 /// Commands sent to the running watcher task for dynamic library management.
 pub enum WatcherCmd {
-    /// Start watching a new library directory.
-    Watch { library_id: String, path: PathBuf },
-    /// Stop watching a library directory (e.g. on library deletion).
+    /// Start watching the root folders of a library.
+    Watch {
+        library_id: String,
+        paths: Vec<PathBuf>,
+    },
+    /// Stop watching a library's folders (e.g. on library deletion).
     Unwatch { library_id: String },
 }
 
@@ -28,19 +32,19 @@ pub struct WatcherHandle {
 }
 
 impl WatcherHandle {
-    /// Register a new library directory for filesystem watching.
+    /// Register a library's root folders for filesystem watching.
     /// This is safe to call from any async context (e.g. an API handler).
-    pub async fn watch_library(&self, library_id: String, path: PathBuf) {
+    pub async fn watch_library(&self, library_id: String, paths: Vec<PathBuf>) {
         if let Err(e) = self
             .cmd_tx
-            .send(WatcherCmd::Watch { library_id, path })
+            .send(WatcherCmd::Watch { library_id, paths })
             .await
         {
             warn!("Failed to send watch command to watcher task: {}", e);
         }
     }
 
-    /// Unregister a library directory so it is no longer watched.
+    /// Unregister a library's folders so they are no longer watched.
     /// Also drains any pending filesystem events for this library.
     pub async fn unwatch_library(&self, library_id: String) {
         if let Err(e) = self.cmd_tx.send(WatcherCmd::Unwatch { library_id }).await {
@@ -49,6 +53,18 @@ impl WatcherHandle {
     }
 }
 
+/// Media items added, moved or removed by one watcher-triggered scan.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct LibraryChange {
+    pub library_id: String,
+    pub indexed: u32,
+    pub relocated: u32,
+    pub removed: u32,
+    /// Whether the incremental scan failed and a full rescan ran instead;
+    /// the counts are incomplete then and clients should reload the library.
+    pub full_rescan: bool,
+}
+
 pub struct LibraryWatcher {
     pool: SqlitePool,
     ffprobe_path: String,
@@ -58,6 +74,7 @@ pub struct LibraryWatcher {
     subtitle_cache_dir: PathBuf,
     tmdb_provider: Option<Arc<dyn ferrite_metadata::provider::MetadataProvider>>,
     image_cache: Option<Arc<ferrite_metadata::image_cache::ImageCache>>,
+    changes: Option<broadcast::Sender<LibraryChange>>,
 }
 
 impl LibraryWatcher {
@@ -81,9 +98,17 @@ impl LibraryWatcher {
             subtitle_cache_dir,
             tmdb_provider,
             image_cache,
+            changes: None,
         }
     }
 
+    /// Publish a `LibraryChange` after every watcher-triggered scan that
+    /// touched the library.
+    pub fn with_change_events(mut self, changes: broadcast::Sender<LibraryChange>) -> Self {
+        self.changes = Some(changes);
+        self
+    }
+
     pub async fn start(self) -> Result<WatcherHandle> {
         let libraries = library_repo::list_libraries(&self.pool).await?;
 
@@ -101,18 +126,24 @@ impl LibraryWatcher {
         let mut lib_paths: Vec<(PathBuf, String)> = Vec::new();
 
         for lib in &libraries {
-            let path = PathBuf::from(&lib.path);
-            if path.exists() {
-                if let Err(e) = watcher.watch(&path, RecursiveMode::Recursive) {
+            for path in crate::library_roots(lib) {
+                if path.exists() {
+                    if let Err(e) = watcher.watch(&path, RecursiveMode::Recursive) {
+                        warn!(
+                            "Failed to watch library '{}' at {}: {}",
+                            lib.name,
+                            path.display(),
+                            e
+                        );
+                    } else {
+                        lib_paths.push((path, lib.id.to_string()));
+                    }
+                } else {
                     warn!(
-                        "Failed to watch library '{}' at {}: {}",
-                        lib.name, lib.path, e
+                        "Library path does not exist, skipping watch: {}",
+                        path.display()
                     );
-                } else {
-                    lib_paths.push((path, lib.id.to_string()));
                 }
-            } else {
-                warn!("Library path does not exist, skipping watch: {}", lib.path);
             }
         }
 
@@ -157,6 +188,7 @@ impl LibraryWatcher {
         let subtitle_cache_dir = self.subtitle_cache_dir;
         let tmdb_provider = self.tmdb_provider;
         let image_cache = self.image_cache;
+        let changes = self.changes;
 
         tokio::spawn(async move {
             // Keep the watcher alive for the lifetime of this task.
@@ -184,29 +216,34 @@ impl LibraryWatcher {
                     }
                     Some(cmd) = cmd_rx.recv() => {
                         match cmd {
-                            WatcherCmd::Watch { library_id, path } => {
-                                if path.exists() {
+                            WatcherCmd::Watch { library_id, paths } => {
+                                for path in paths {
+                                    if !path.exists() {
+                                        warn!("Library path does not exist, skipping watch: {:?}", path);
+                                        continue;
+                                    }
                                     match watcher.watch(&path, RecursiveMode::Recursive) {
                                         Ok(()) => {
-                                            info!("Now watching new library '{}' at {:?}", library_id, path);
-                                            lib_paths.push((path, library_id));
+                                            info!("Now watching library '{}' at {:?}", library_id, path);
+                                            lib_paths.push((path, library_id.clone()));
                                         }
                                         Err(e) => {
-                                            warn!("Failed to watch new library '{}' at {:?}: {}", library_id, path, e);
+                                            warn!("Failed to watch library '{}' at {:?}: {}", library_id, path, e);
                                         }
                                     }
-                                } else {
-                                    warn!("New library path does not exist, skipping watch: {:?}", path);
                                 }
                             }
                             WatcherCmd::Unwatch { library_id } => {
-                                // Remove from lib_paths and unwatch the directory.
-                                if let Some(pos) = lib_paths.iter().position(|(_, id)| id == &library_id) {
-                                    let (path, _) = lib_paths.remove(pos);
+                                // Remove from lib_paths and unwatch each of its folders.
+                                let (removed, kept): (Vec<_>, Vec<_>) = lib_paths
+                                    .drain(..)
+                                    .partition(|(_, id)| id == &library_id);
+                                lib_paths = kept;
+                                for (path, _) in removed {
                                     if let Err(e) = watcher.unwatch(&path) {
                                         warn!("Failed to unwatch library '{}' at {:?}: {}", library_id, path, e);
                                     } else {
-                                        info!("Stopped watching deleted library '{}' at {:?}", library_id, path);
+                                        info!("Stopped watching library '{}' at {:?}", library_id, path);
                                     }
                                 }
                                 // Drain any pending events for this library.
@@ -228,7 +265,7 @@ impl LibraryWatcher {
                                 paths.len()
                             );
                             let mut incremental_failed = false;
-                            let mut indexed_total = 0u32;
+                            let mut result = crate::IncrementalScanResult::default();
                             for chunk in paths.chunks(MAX_INCREMENTAL_BATCH_PATHS) {
                                 match crate::scan_library_incremental(
                                     &pool,
@@ -241,7 +278,11 @@ impl LibraryWatcher {
                                 )
                                 .await
                                 {
-                                    Ok(n) => indexed_total += n,
+                                    Ok(r) => {
+                                        result.indexed += r.indexed;
+                                        result.relocated += r.relocated;
+                                        result.removed += r.removed;
+                                    }
                                     Err(e) => {
                                         warn!(
                                             "Incremental scan failed for '{}': {}. Falling back to full rescan.",
@@ -275,7 +316,7 @@ impl LibraryWatcher {
                                         full_err
                                     );
                                 }
-                            } else if indexed_total > 0 {
+                            } else if result.total() > 0 {
                                 // Enrich newly added items after incremental scan
                                 enrich_library_after_scan(
                                     &pool,
@@ -285,6 +326,19 @@ impl LibraryWatcher {
                                 )
                                 .await;
                             }
+
+                            if let Some(changes) = &changes {
+                                if incremental_failed || result.total() > 0 {
+                                    // No receivers is fine: nobody is listening.
+                                    let _ = changes.send(LibraryChange {
+                                        library_id: lib_id,
+                                        indexed: result.indexed,
+                                        relocated: result.relocated,
+                                        removed: result.removed,
+                                        full_rescan: incremental_failed,
+                                    });
+                                }
+                            }
                         }
                     }
                     else => {
@@ -332,7 +386,7 @@ async fn enrich_library_after_scan(
                 library.name
             );
             match ferrite_metadata::enrichment::enrich_library_shows(
-                pool, library_id, provider, cache,
+                pool, library_id, provider, cache, None,
             )
             .await
             {
@@ -347,7 +401,7 @@ async fn enrich_library_after_scan(
                 library.name
             );
             match ferrite_metadata::enrichment::enrich_library_movies(
-                pool, library_id, provider, cache,
+                pool, library_id, provider, cache, None,
             )
             .await
             {
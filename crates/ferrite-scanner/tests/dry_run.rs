@@ -0,0 +1,143 @@
+use ferrite_db::create_pools;
+use ferrite_scanner::dry_run::{dry_run_library, DryRunFile, DryRunLimits, FileStatus};
+use ferrite_scanner::filename::ParsedFilename;
+use sqlx::SqlitePool;
+use std::path::Path;
+use tokio::fs;
+use uuid::Uuid;
+
+async fn new_test_pool() -> SqlitePool {
+    let db_path =
+        std::env::temp_dir().join(format!("ferrite-scanner-test-{}.sqlite", Uuid::new_v4()));
+    let pools = create_pools(&db_path, 4)
+        .await
+        .expect("failed to create test db pool");
+    pools.read
+}
+
+async fn write_file(path: &Path, len: usize) {
+    fs::create_dir_all(path.parent().unwrap())
+        .await
+        .expect("failed to create folder");
+    fs::write(path, vec![0u8; len])
+        .await
+        .expect("failed to write file");
+}
+
+fn file<'a>(files: &'a [DryRunFile], suffix: &str) -> &'a DryRunFile {
+    files
+        .iter()
+        .find(|f| f.path.ends_with(suffix))
+        .unwrap_or_else(|| panic!("{suffix} not reported"))
+}
+
+#[tokio::test]
+async fn dry_run_reports_files_without_indexing_them() {
+    let pool = new_test_pool().await;
+    let root = std::env::temp_dir().join(format!("ferrite-lib-{}", Uuid::new_v4()));
+    let indexed = root.join("Show/Season 1/Show - S01E02.mkv");
+    write_file(&root.join("Show/Season 1/Show - S01E01E02.mkv"), 10).await;
+    write_file(&indexed, 20).await;
+    write_file(&root.join("Show/Trailers/Teaser.mkv"), 10).await;
+    write_file(&root.join("Show/notes.mkv"), 10).await;
+
+    let library_id = Uuid::new_v4().to_string();
+    sqlx::query(
+        "INSERT INTO libraries (id, name, path, library_type) VALUES (?, 'Dry Run', ?, 'tv')",
+    )
+    .bind(&library_id)
+    .bind(root.to_string_lossy().to_string())
+    .execute(&pool)
+    .await
+    .unwrap();
+    for (path, size) in [
+        (indexed.to_string_lossy().to_string(), 20),
+        (root.join("Show/gone.mkv").to_string_lossy().to_string(), 5),
+    ] {
+        sqlx::query(
+            "INSERT INTO media_items (id, library_id, media_type, file_path, file_size, title) \
+             VALUES (?, ?, 'episode', ?, ?, 'Show')",
+        )
+        .bind(Uuid::new_v4().to_string())
+        .bind(&library_id)
+        .bind(path)
+        .bind(size)
+        .execute(&pool)
+        .await
+        .unwrap();
+    }
+
+    let report = dry_run_library(
+        &pool,
+        &library_id,
+        "/nonexistent/ffprobe",
+        2,
+        false,
+        None,
+        DryRunLimits::default(),
+    )
+    .await
+    .unwrap();
+    assert_eq!(report.files.len(), 4);
+    assert!(!report.truncated);
+
+    let double = file(&report.files, "Show - S01E01E02.mkv");
+    assert_eq!(double.status, FileStatus::New);
+    assert_eq!(double.title, "Show");
+    match &double.parsed {
+        ParsedFilename::Episode(e) => assert_eq!(e.episode_numbers(), vec![1, 2]),
+        other => panic!("Expected Episode, got {:?}", other),
+    }
+    assert!(double.probed);
+    assert!(double.probe_error.is_some());
+
+    let unchanged = file(&report.files, "Show - S01E02.mkv");
+    assert_eq!(unchanged.status, FileStatus::Unchanged);
+    assert!(!unchanged.probed && unchanged.probe_error.is_none());
+
+    assert_eq!(
+        file(&report.files, "Teaser.mkv").extra_type,
+        Some("trailer")
+    );
+    assert!(matches!(
+        file(&report.files, "notes.mkv").parsed,
+        ParsedFilename::Unknown(_)
+    ));
+    assert_eq!(report.missing.len(), 1);
+    assert!(report.missing[0].ends_with("gone.mkv"));
+
+    // Past the probe cap files are still reported, just unprobed
+    let capped = dry_run_library(
+        &pool,
+        &library_id,
+        "/nonexistent/ffprobe",
+        2,
+        false,
+        None,
+        DryRunLimits {
+            max_probes: 1,
+            max_searches: 0,
+        },
+    )
+    .await
+    .unwrap();
+    assert_eq!(capped.files.len(), 4);
+    assert_eq!(capped.probes_skipped, 2);
+    assert!(capped.truncated);
+    assert!(file(&capped.files, "Show - S01E01E02.mkv").probed);
+    assert!(!file(&capped.files, "notes.mkv").probed);
+
+    // Nothing was written
+    let (count,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM media_items")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(count, 2);
+    let (shows,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM tv_shows")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(shows, 0);
+
+    let _ = fs::remove_dir_all(&root).await;
+}
@@ -100,7 +100,8 @@ async fn incremental_scan_removes_media_for_deleted_directory_prefix() {
     .await
     .expect("incremental scan failed");
 
-    assert_eq!(touched, 2);
+    assert_eq!(touched.removed, 2);
+    assert_eq!(touched.total(), 2);
 
     let remaining: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM media_items WHERE library_id = ?")
         .bind(&library_id)
@@ -140,7 +141,8 @@ async fn incremental_scan_indexes_media_for_changed_subdirectory_events() {
     .await
     .expect("incremental scan failed");
 
-    assert_eq!(touched, 1);
+    assert_eq!(touched.indexed, 1);
+    assert_eq!(touched.total(), 1);
 
     let count: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM media_items WHERE library_id = ?")
         .bind(&library_id)
@@ -152,3 +154,224 @@ async fn incremental_scan_indexes_media_for_changed_subdirectory_events() {
 
     let _ = fs::remove_dir_all(&library_root).await;
 }
+
+async fn media_rows(pool: &SqlitePool, library_id: &str) -> Vec<(String, String, Option<String>)> {
+    sqlx::query_as("SELECT id, file_path, file_hash FROM media_items WHERE library_id = ?")
+        .bind(library_id)
+        .fetch_all(pool)
+        .await
+        .expect("failed to load media rows")
+}
+
+#[tokio::test]
+async fn incremental_scan_relocates_moved_file_instead_of_recreating_it() {
+    let pool = new_test_pool().await;
+
+    let library_root = std::env::temp_dir().join(format!("ferrite-lib-{}", Uuid::new_v4()));
+    let old_dir = library_root.join("Unsorted");
+    let new_dir = library_root.join("Movies").join("Heat (1995)");
+    fs::create_dir_all(&old_dir)
+        .await
+        .expect("failed to create old dir");
+    fs::create_dir_all(&new_dir)
+        .await
+        .expect("failed to create new dir");
+
+    let library_id = seed_library(&pool, &library_root).await;
+
+    let old_path = old_dir.join("heat.mkv");
+    fs::write(&old_path, b"heat movie bytes")
+        .await
+        .expect("failed to create media file");
+    scan_library_incremental(
+        &pool,
+        &library_id,
+        "missing-ffprobe",
+        "missing-ffmpeg",
+        2,
+        &library_root.join("subtitle-cache"),
+        std::slice::from_ref(&old_path),
+    )
+    .await
+    .expect("initial incremental scan failed");
+    let before = media_rows(&pool, &library_id).await;
+    assert_eq!(before.len(), 1);
+    assert!(before[0].2.is_some(), "content hash is recorded on insert");
+
+    // Move (and rename) the file, then deliver both paths in one watcher batch.
+    let new_path = new_dir.join("Heat (1995).mkv");
+    fs::rename(&old_path, &new_path)
+        .await
+        .expect("failed to move media file");
+    scan_library_incremental(
+        &pool,
+        &library_id,
+        "missing-ffprobe",
+        "missing-ffmpeg",
+        2,
+        &library_root.join("subtitle-cache"),
+        &[old_path.clone(), new_dir.clone()],
+    )
+    .await
+    .expect("incremental scan after move failed");
+
+    let after = media_rows(&pool, &library_id).await;
+    assert_eq!(after.len(), 1);
+    assert_eq!(after[0].0, before[0].0, "media id survives the move");
+    assert_eq!(after[0].1, new_path.to_string_lossy());
+
+    let _ = fs::remove_dir_all(&library_root).await;
+}
+
+#[tokio::test]
+async fn full_scan_relocates_moved_file_and_backfills_missing_hashes() {
+    let pool = new_test_pool().await;
+
+    let library_root = std::env::temp_dir().join(format!("ferrite-lib-{}", Uuid::new_v4()));
+    fs::create_dir_all(library_root.join("b"))
+        .await
+        .expect("failed to create library dir");
+    let library_id = seed_library(&pool, &library_root).await;
+
+    // Indexed before hashes existed: no file_hash on the row.
+    let legacy = library_root.join("legacy.mkv");
+    fs::write(&legacy, b"legacy").await.unwrap();
+    insert_media_item(&pool, &library_id, &legacy, "Legacy").await;
+    sqlx::query("UPDATE media_items SET file_size = 6 WHERE file_path = ?")
+        .bind(legacy.to_string_lossy().to_string())
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let original = library_root.join("a.mkv");
+    fs::write(&original, b"movie a").await.unwrap();
+    let scan = |pool: SqlitePool, library_id: String, root: std::path::PathBuf| async move {
+        ferrite_scanner::scan_library(
+            &pool,
+            &library_id,
+            "missing-ffprobe",
+            "missing-ffmpeg",
+            2,
+            &root.join("subtitle-cache"),
+            ferrite_scanner::progress::ScanState::new(library_id.clone()),
+            None,
+            None,
+        )
+        .await
+        .expect("full scan failed")
+    };
+    scan(pool.clone(), library_id.clone(), library_root.clone()).await;
+
+    let rows = media_rows(&pool, &library_id).await;
+    assert_eq!(rows.len(), 2);
+    assert!(rows.iter().all(|r| r.2.is_some()), "legacy hash backfilled");
+    let original_id = rows
+        .iter()
+        .find(|r| r.1 == original.to_string_lossy())
+        .unwrap()
+        .0
+        .clone();
+
+    let moved = library_root.join("b").join("a renamed.mkv");
+    fs::rename(&original, &moved).await.unwrap();
+    let indexed = scan(pool.clone(), library_id.clone(), library_root.clone()).await;
+    assert_eq!(indexed, 0, "a moved file is not indexed as new");
+
+    let rows = media_rows(&pool, &library_id).await;
+    assert_eq!(rows.len(), 2);
+    let relocated = rows.iter().find(|r| r.0 == original_id).unwrap();
+    assert_eq!(relocated.1, moved.to_string_lossy());
+
+    let _ = fs::remove_dir_all(&library_root).await;
+}
+
+#[tokio::test]
+async fn incremental_scan_groups_versions_and_stacks_parts() {
+    let pool = new_test_pool().await;
+
+    let library_root = std::env::temp_dir().join(format!("ferrite-lib-{}", Uuid::new_v4()));
+    let movie_dir = library_root.join("Heat (1995)");
+    fs::create_dir_all(&movie_dir)
+        .await
+        .expect("failed to create movie dir");
+    for name in [
+        "Heat (1995) cd1.avi",
+        "Heat (1995) cd2.avi",
+        "Heat (1995) - 2160p.mkv",
+    ] {
+        fs::write(movie_dir.join(name), name.as_bytes())
+            .await
+            .expect("failed to create sample media file");
+    }
+
+    let library_id = seed_library(&pool, &library_root).await;
+    scan_library_incremental(
+        &pool,
+        &library_id,
+        "missing-ffprobe",
+        "missing-ffmpeg",
+        2,
+        &library_root.join("subtitle-cache"),
+        std::slice::from_ref(&movie_dir),
+    )
+    .await
+    .expect("incremental scan failed");
+
+    let id_of = |name: &str| {
+        let path = movie_dir.join(name).to_string_lossy().to_string();
+        let pool = pool.clone();
+        async move {
+            ferrite_db::media_repo::get_media_item_id_by_path(&pool, &path)
+                .await
+                .expect("lookup failed")
+                .expect("media item missing")
+        }
+    };
+    let cd1 = id_of("Heat (1995) cd1.avi").await;
+    let cd2 = id_of("Heat (1995) cd2.avi").await;
+    let uhd = id_of("Heat (1995) - 2160p.mkv").await;
+
+    let cd2_row = ferrite_db::media_repo::get_media_item(&pool, &cd2)
+        .await
+        .expect("query failed")
+        .expect("cd2 missing");
+    assert_eq!(cd2_row.stack_head_id.as_deref(), Some(cd1.as_str()));
+    assert_eq!(cd2_row.part_number, Some(2));
+
+    let group = cd2_row.movie_group_id.expect("cd2 should be grouped");
+    let versions = ferrite_db::media_repo::list_movie_versions(&pool, &group)
+        .await
+        .expect("versions query failed");
+    let mut version_ids: Vec<&str> = versions.iter().map(|v| v.id.as_str()).collect();
+    version_ids.sort();
+    let mut expected = vec![cd1.as_str(), uhd.as_str()];
+    expected.sort();
+    assert_eq!(version_ids, expected);
+
+    let parts = ferrite_db::media_repo::list_stack_parts(&pool, &cd1)
+        .await
+        .expect("parts query failed");
+    let part_ids: Vec<&str> = parts.iter().map(|p| p.id.as_str()).collect();
+    assert_eq!(part_ids, vec![cd1.as_str(), cd2.as_str()]);
+
+    // Listings show one logical movie.
+    let query = ferrite_db::movie_repo::MediaQuery {
+        library_id: Some(&library_id),
+        page: 1,
+        per_page: 50,
+        ..Default::default()
+    };
+    let listed = ferrite_db::movie_repo::list_movies_with_media(&pool, &query, None)
+        .await
+        .expect("list failed");
+    assert_eq!(listed.len(), 1);
+    assert_eq!(listed[0].id, group);
+    assert_eq!(
+        ferrite_db::movie_repo::count_movies_with_media(&pool, &query)
+            .await
+            .expect("count failed"),
+        1
+    );
+
+    let _ = fs::remove_dir_all(&library_root).await;
+}
@@ -0,0 +1,87 @@
+use ferrite_db::{create_pools, media_repo};
+use ferrite_scanner::progress::ScanState;
+use sqlx::SqlitePool;
+use std::path::Path;
+use tokio::fs;
+use uuid::Uuid;
+
+async fn new_test_pool() -> SqlitePool {
+    let db_path =
+        std::env::temp_dir().join(format!("ferrite-scanner-test-{}.sqlite", Uuid::new_v4()));
+    let pools = create_pools(&db_path, 4)
+        .await
+        .expect("failed to create test db pool");
+    pools.read
+}
+
+async fn seed_library(pool: &SqlitePool, library_path: &Path, ignore_patterns: &str) -> String {
+    let library_id = Uuid::new_v4().to_string();
+    sqlx::query(
+        "INSERT INTO libraries (id, name, path, library_type, ignore_patterns) \
+         VALUES (?, 'Prune Test', ?, 'movie', ?)",
+    )
+    .bind(&library_id)
+    .bind(library_path.to_string_lossy().to_string())
+    .bind(ignore_patterns)
+    .execute(pool)
+    .await
+    .expect("failed to insert library");
+    library_id
+}
+
+async fn insert_media_item(pool: &SqlitePool, library_id: &str, file_path: &Path) {
+    sqlx::query(
+        "INSERT INTO media_items (id, library_id, media_type, file_path, file_size, title) \
+         VALUES (?, ?, 'movie', ?, 1234, 'Indexed')",
+    )
+    .bind(Uuid::new_v4().to_string())
+    .bind(library_id)
+    .bind(file_path.to_string_lossy().to_string())
+    .execute(pool)
+    .await
+    .expect("failed to insert media item");
+}
+
+#[tokio::test]
+async fn scan_prunes_only_ignored_or_outside_rows() {
+    let pool = new_test_pool().await;
+    let library_root = std::env::temp_dir().join(format!("ferrite-lib-{}", Uuid::new_v4()));
+    fs::create_dir_all(library_root.join("Samples"))
+        .await
+        .unwrap();
+    fs::write(library_root.join("Samples/clip.mkv"), b"sample")
+        .await
+        .unwrap();
+    // On disk but never walked, as after a failed stat on a network share
+    let unwalked = library_root.join("Film (2001).mkv.partial");
+    fs::write(&unwalked, b"film").await.unwrap();
+    let library_id = seed_library(&pool, &library_root, "Samples/").await;
+
+    insert_media_item(&pool, &library_id, &library_root.join("Samples/clip.mkv")).await;
+    insert_media_item(&pool, &library_id, &unwalked).await;
+    insert_media_item(&pool, &library_id, Path::new("/elsewhere/Other (1999).mkv")).await;
+
+    ferrite_scanner::scan_library(
+        &pool,
+        &library_id,
+        "missing-ffprobe",
+        "missing-ffmpeg",
+        2,
+        &library_root.join("subtitle-cache"),
+        ScanState::new(library_id.clone()),
+        None,
+        None,
+    )
+    .await
+    .expect("full scan failed");
+
+    let remaining: Vec<String> = media_repo::list_file_identities(&pool, &library_id)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|row| row.file_path)
+        .collect();
+    assert_eq!(remaining, [unwalked.to_string_lossy().to_string()]);
+
+    let _ = fs::remove_dir_all(&library_root).await;
+}
@@ -0,0 +1,216 @@
+use ferrite_db::{create_pools, media_repo};
+use ferrite_scanner::progress::{ScanState, ScanStatus};
+use sqlx::SqlitePool;
+use std::path::Path;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+use tokio::fs;
+use uuid::Uuid;
+
+async fn new_test_pool() -> SqlitePool {
+    let db_path =
+        std::env::temp_dir().join(format!("ferrite-scanner-test-{}.sqlite", Uuid::new_v4()));
+    let pools = create_pools(&db_path, 4)
+        .await
+        .expect("failed to create test db pool");
+    pools.read
+}
+
+async fn seed_library(pool: &SqlitePool, library_path: &Path) -> String {
+    let library_id = Uuid::new_v4().to_string();
+    sqlx::query(
+        "INSERT INTO libraries (id, name, path, library_type) VALUES (?, 'Scan Control Test', ?, 'movie')",
+    )
+    .bind(&library_id)
+    .bind(library_path.to_string_lossy().to_string())
+    .execute(pool)
+    .await
+    .expect("failed to insert library");
+    library_id
+}
+
+async fn scan(
+    pool: &SqlitePool,
+    library_id: &str,
+    root: &Path,
+    scan_state: std::sync::Arc<ScanState>,
+) {
+    ferrite_scanner::scan_library(
+        pool,
+        library_id,
+        "missing-ffprobe",
+        "missing-ffmpeg",
+        2,
+        &root.join("subtitle-cache"),
+        scan_state,
+        None,
+        None,
+    )
+    .await
+    .expect("full scan failed");
+}
+
+#[tokio::test]
+async fn pause_holds_checkpoints_until_resumed_or_cancelled() {
+    let scan_state = ScanState::new("lib".into());
+    assert!(scan_state.checkpoint().await);
+
+    assert!(scan_state.pause());
+    assert!(scan_state.to_progress().await.paused);
+    let held = tokio::time::timeout(Duration::from_millis(50), scan_state.checkpoint()).await;
+    assert!(held.is_err(), "checkpoint passed while paused");
+
+    assert!(scan_state.resume());
+    assert!(scan_state.checkpoint().await);
+
+    scan_state.pause();
+    let waiter = {
+        let scan_state = scan_state.clone();
+        tokio::spawn(async move { scan_state.checkpoint().await })
+    };
+    scan_state.cancel();
+    assert!(
+        !waiter.await.unwrap(),
+        "cancel releases a paused checkpoint"
+    );
+    assert!(!scan_state.resume(), "a cancelled scan can't be resumed");
+    assert!(scan_state.to_progress().await.cancelling);
+}
+
+#[tokio::test]
+async fn cancelled_scan_stops_without_indexing_or_marking_the_library_scanned() {
+    let pool = new_test_pool().await;
+    let library_root = std::env::temp_dir().join(format!("ferrite-lib-{}", Uuid::new_v4()));
+    fs::create_dir_all(&library_root).await.unwrap();
+    for name in ["a.mkv", "b.mkv", "c.mkv"] {
+        fs::write(library_root.join(name), name.as_bytes())
+            .await
+            .unwrap();
+    }
+    let library_id = seed_library(&pool, &library_root).await;
+
+    // Paused before it starts, the scan walks the library and then waits.
+    let scan_state = ScanState::new(library_id.clone());
+    scan_state.pause();
+    let task = {
+        let (pool, library_id, root, scan_state) = (
+            pool.clone(),
+            library_id.clone(),
+            library_root.clone(),
+            scan_state.clone(),
+        );
+        tokio::spawn(async move { scan(&pool, &library_id, &root, scan_state).await })
+    };
+    while scan_state.total_files.load(Ordering::Relaxed) == 0 {
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    assert!(scan_state.is_running().await);
+
+    scan_state.cancel();
+    tokio::time::timeout(Duration::from_secs(10), task)
+        .await
+        .expect("cancelled scan did not stop")
+        .unwrap();
+
+    assert_eq!(*scan_state.status.read().await, ScanStatus::Cancelled);
+    assert!(!scan_state.to_progress().await.scanning);
+    let items: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM media_items WHERE library_id = ?")
+        .bind(&library_id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(items.0, 0);
+    let last_scanned: (Option<String>,) =
+        sqlx::query_as("SELECT last_scanned_at FROM libraries WHERE id = ?")
+            .bind(&library_id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    assert_eq!(last_scanned.0, None);
+
+    let _ = fs::remove_dir_all(&library_root).await;
+}
+
+#[tokio::test]
+async fn items_left_without_subtitles_are_indexed_again() {
+    let pool = new_test_pool().await;
+    let library_root = std::env::temp_dir().join(format!("ferrite-lib-{}", Uuid::new_v4()));
+    fs::create_dir_all(&library_root).await.unwrap();
+    fs::write(library_root.join("a.mkv"), b"movie a")
+        .await
+        .unwrap();
+    let library_id = seed_library(&pool, &library_root).await;
+
+    scan(
+        &pool,
+        &library_id,
+        &library_root,
+        ScanState::new(library_id.clone()),
+    )
+    .await;
+    let identity = media_repo::list_file_identities(&pool, &library_id)
+        .await
+        .unwrap()
+        .remove(0);
+    assert!(!identity.subtitles_pending);
+
+    // As left behind by a scan cancelled before its subtitle pass
+    media_repo::mark_subtitles_pending(&pool, std::slice::from_ref(&identity.id))
+        .await
+        .unwrap();
+    let scan_state = ScanState::new(library_id.clone());
+    scan(&pool, &library_id, &library_root, scan_state.clone()).await;
+
+    assert_eq!(scan_state.files_inserted.load(Ordering::Relaxed), 1);
+    let after = media_repo::list_file_identities(&pool, &library_id)
+        .await
+        .unwrap();
+    assert_eq!(after.len(), 1);
+    assert_eq!(after[0].id, identity.id, "re-indexed in place");
+    assert!(!after[0].subtitles_pending);
+
+    let _ = fs::remove_dir_all(&library_root).await;
+}
+
+#[tokio::test]
+async fn items_flagged_for_reprobe_keep_their_row_and_are_not_reindexed() {
+    let pool = new_test_pool().await;
+    let library_root = std::env::temp_dir().join(format!("ferrite-lib-{}", Uuid::new_v4()));
+    fs::create_dir_all(&library_root).await.unwrap();
+    fs::write(library_root.join("a.mkv"), b"movie a")
+        .await
+        .unwrap();
+    let library_id = seed_library(&pool, &library_root).await;
+
+    scan(
+        &pool,
+        &library_id,
+        &library_root,
+        ScanState::new(library_id.clone()),
+    )
+    .await;
+    let identity = media_repo::list_file_identities(&pool, &library_id)
+        .await
+        .unwrap()
+        .remove(0);
+
+    // As set by a migration adding new stream columns
+    sqlx::query("UPDATE media_items SET needs_reprobe = 1 WHERE id = ?")
+        .bind(&identity.id)
+        .execute(&pool)
+        .await
+        .unwrap();
+    let scan_state = ScanState::new(library_id.clone());
+    scan(&pool, &library_id, &library_root, scan_state.clone()).await;
+
+    assert_eq!(scan_state.files_inserted.load(Ordering::Relaxed), 0);
+    let after = media_repo::list_file_identities(&pool, &library_id)
+        .await
+        .unwrap();
+    assert_eq!(after.len(), 1);
+    assert_eq!(after[0].id, identity.id);
+    // Cleared even though ffprobe is missing, so it isn't retried every scan
+    assert!(!after[0].needs_reprobe);
+
+    let _ = fs::remove_dir_all(&library_root).await;
+}
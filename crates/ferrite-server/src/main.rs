@@ -138,6 +138,10 @@ async fn main() -> Result<()> {
     // or from ~/ferrite/ (seedbox deployment).
     // Note: FERRITE_DATA_DIR is checked inside resolve_paths().
     config.resolve_paths();
+    config
+        .transcode
+        .validate_encoding()
+        .map_err(|e| anyhow::anyhow!("Invalid encoding config {e}"))?;
 
     // Detect hardware-accelerated encoders
     let hw_pref = config.transcode.hw_accel.as_deref().and_then(|s| match s {
@@ -156,7 +160,10 @@ async fn main() -> Result<()> {
         "Video encoder: {} (backend={})",
         hw_caps.selected_profile.encoder_name, hw_caps.selected_profile.backend,
     );
-    let encoder_profile = Arc::new(hw_caps.selected_profile.clone());
+    let encoder_profile = Arc::new(hw_caps.selected_profile.with_quality(
+        &config.transcode.encoding.preset,
+        config.transcode.encoding.crf,
+    ));
 
     // Initialize database (before background tasks that may need it)
     let db =
@@ -166,7 +173,7 @@ async fn main() -> Result<()> {
     let hls_cache_dir = config.transcode.cache_dir.join("hls");
     tokio::fs::create_dir_all(&hls_cache_dir).await?;
 
-    let hls_manager = Arc::new(ferrite_stream::hls::HlsSessionManager::new(
+    let mut hls_manager = ferrite_stream::hls::HlsSessionManager::new(
         hls_cache_dir,
         config.transcode.ffmpeg_path.clone(),
         config.transcode.hls_segment_duration,
@@ -174,7 +181,18 @@ async fn main() -> Result<()> {
         config.transcode.hls_session_timeout_secs,
         config.transcode.hls_ffmpeg_idle_secs,
         hw_caps.selected_profile,
-    ));
+    )
+    .with_encoding(config.transcode.encoding.clone());
+    if config.transcode.segment_cache_enabled {
+        let root = config.transcode.cache_dir.join("hls-cache");
+        let max_bytes = (config.transcode.segment_cache_max_gb * 1024.0 * 1024.0 * 1024.0) as u64;
+        let segment_cache = tokio::task::spawn_blocking(move || {
+            ferrite_stream::segment_cache::SegmentCache::open(root, max_bytes)
+        })
+        .await??;
+        hls_manager = hls_manager.with_segment_cache(Arc::new(segment_cache));
+    }
+    let hls_manager = Arc::new(hls_manager);
 
     // Spawn HLS cleanup background task (supervised — logs panics)
     let cleanup_manager = hls_manager.clone();
@@ -183,8 +201,9 @@ async fn main() -> Result<()> {
     }));
 
     // Build app state and router
-    let transcode_semaphore = Arc::new(tokio::sync::Semaphore::new(
+    let transcodes = Arc::new(ferrite_api::scheduler::TranscodeScheduler::new(
         config.transcode.max_concurrent_transcodes,
+        std::time::Duration::from_secs(config.transcode.transcode_queue_timeout_secs),
     ));
 
     // Initialize webhook dispatcher
@@ -200,10 +219,13 @@ async fn main() -> Result<()> {
     let (watcher_tmdb, watcher_img_cache) = if let Some(ref api_key) = config.metadata.tmdb_api_key
     {
         let provider: std::sync::Arc<dyn ferrite_metadata::provider::MetadataProvider> =
-            std::sync::Arc::new(ferrite_metadata::tmdb::TmdbProvider::new(
-                api_key.clone(),
-                config.metadata.rate_limit_per_second,
-            ));
+            std::sync::Arc::new(
+                ferrite_metadata::tmdb::TmdbProvider::new(
+                    api_key.clone(),
+                    config.metadata.rate_limit_per_second,
+                )
+                .with_languages(&config.metadata.languages),
+            );
         let cache = std::sync::Arc::new(ferrite_metadata::image_cache::ImageCache::new(
             config.metadata.image_cache_dir.clone(),
         ));
@@ -212,6 +234,9 @@ async fn main() -> Result<()> {
         (None, None)
     };
 
+    // Live admin event stream; the watcher publishes library changes into it.
+    let event_hub = Arc::new(ferrite_api::events::EventHub::new());
+
     // Start filesystem watcher for auto-rescan (before AppState so the handle
     // can be stored for dynamic library registration from API handlers).
     let watcher = ferrite_scanner::watcher::LibraryWatcher::new(
@@ -223,7 +248,8 @@ async fn main() -> Result<()> {
         config.scanner.subtitle_cache_dir.clone(),
         watcher_tmdb,
         watcher_img_cache,
-    );
+    )
+    .with_change_events(event_hub.library_changes());
     let watcher_handle = match watcher.start().await {
         Ok(handle) => {
             info!("Filesystem watcher started");
@@ -243,11 +269,48 @@ async fn main() -> Result<()> {
         }
     }
 
+    let app_config = Arc::new(config.clone());
+
+    // Offline download worker (supervised — logs panics)
+    let download_manager = Arc::new(ferrite_api::downloads::DownloadManager::new(
+        db.clone(),
+        app_config.clone(),
+        encoder_profile.clone(),
+    ));
+    let worker_downloads = download_manager.clone();
+    tokio::spawn(supervised_task("download worker", async move {
+        worker_downloads.run().await;
+    }));
+
+    // Live TV guide refresh and DVR scheduler (supervised — logs panics)
+    let livetv_manager = Arc::new(ferrite_api::livetv::LiveTvManager::new(
+        db.clone(),
+        app_config.clone(),
+    ));
+    if config.livetv.enabled {
+        let scheduler_livetv = livetv_manager.clone();
+        tokio::spawn(supervised_task("live TV scheduler", async move {
+            scheduler_livetv.run().await;
+        }));
+    }
+
+    // Media file integrity checks (supervised — logs panics)
+    let integrity_checker = Arc::new(ferrite_api::integrity::IntegrityChecker::new(
+        db.clone(),
+        app_config.clone(),
+    ));
+    if config.integrity.enabled {
+        let scheduled_checker = integrity_checker.clone();
+        tokio::spawn(supervised_task("integrity scheduler", async move {
+            scheduled_checker.run().await;
+        }));
+    }
+
     let state = AppState {
         db: db.clone(),
-        config: Arc::new(config.clone()),
+        config: app_config,
         hls_sessions: hls_manager,
-        transcode_semaphore,
+        transcodes,
         login_limiter: AppState::new_login_limiter(),
         encoder_profile,
         webhook_dispatcher,
@@ -256,8 +319,21 @@ async fn main() -> Result<()> {
         playback_metrics: Arc::new(ferrite_api::metrics::PlaybackMetrics::default()),
         update_state: Arc::new(ferrite_api::state::UpdateState::new()),
         user_cache,
+        downloads: download_manager,
+        network: Arc::new(ferrite_api::network::NetworkPolicy::from_config(
+            &config.network,
+        )),
+        livetv: livetv_manager,
+        events: event_hub,
+        integrity: integrity_checker,
     };
 
+    // Sample scans, streams and jobs for connected event stream clients
+    let sampler_state = state.clone();
+    tokio::spawn(supervised_task("event stream", async move {
+        ferrite_api::events::EventHub::run(sampler_state).await;
+    }));
+
     // Spawn background update check (every 6 hours, log-only, never auto-applies)
     if !config.update.disabled {
         let bg_update_config = config.update.clone();
@@ -340,14 +416,17 @@ async fn main() -> Result<()> {
     let listener = tokio::net::TcpListener::bind(addr).await?;
 
     // Graceful shutdown: wait for Ctrl+C, then clean up FFmpeg processes
-    axum::serve(listener, router)
-        .with_graceful_shutdown(async move {
-            let _ = tokio::signal::ctrl_c().await;
-            info!("Shutdown signal received — cleaning up...");
-            shutdown_hls_manager.destroy_all_sessions().await;
-            info!("Graceful shutdown complete");
-        })
-        .await?;
+    axum::serve(
+        listener,
+        router.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .with_graceful_shutdown(async move {
+        let _ = tokio::signal::ctrl_c().await;
+        info!("Shutdown signal received — cleaning up...");
+        shutdown_hls_manager.destroy_all_sessions().await;
+        info!("Graceful shutdown complete");
+    })
+    .await?;
 
     Ok(())
 }
@@ -413,11 +492,74 @@ hls_session_timeout_secs = 30
 hls_segment_mime_mode = "video-mp4"
 # Hardware acceleration: "nvenc", "qsv", "vaapi", "software", or omit for auto-detect
 # hw_accel = "software"
+# keep transcoded HLS segments so rewatching reuses them (stored in <cache_dir>/hls-cache)
+segment_cache_enabled = false
+segment_cache_max_gb = 50
+
+[transcode.encoding]
+# x264 preset (ultrafast..veryslow) and quality factor (0-51, lower is better)
+preset = "veryfast"
+crf = 23
+# never transcode above this height
+# max_height = 1080
+# AAC bitrate (kbps) when audio is re-encoded outside the ABR ladder
+audio_bitrate_kbps = 192
+# ABR ladder, highest first; omit to use the built-in 2160p..360p ladder
+# [[transcode.encoding.ladder]]
+# label = "1080p"
+# width = 1920
+# height = 1080
+# video_bitrate_kbps = 5000
+# audio_bitrate_kbps = 192
+
+# Per-library overrides, keyed by library name or ID; unset fields inherit
+# [transcode.library_encoding."Anime"]
+# crf = 20
+# max_height = 1080
+
+[downloads]
+# Offline downloads: complete MP4 transcodes stored for clients to fetch
+cache_dir = "cache/downloads"
+quota_gb = 50
+# finished downloads are deleted this many hours after completion
+expiry_hours = 168
+max_concurrent = 1
+
+[network]
+# Clients in these subnets are local; remote bitrate caps never apply to them
+local_subnets = ["127.0.0.0/8", "10.0.0.0/8", "172.16.0.0/12", "192.168.0.0/16", "169.254.0.0/16", "::1/128", "fc00::/7", "fe80::/10"]
+# Use X-Forwarded-For as the client address (only behind a trusted reverse proxy)
+trust_forwarded_for = false
+# Server-wide bitrate cap (kbps) for remote clients; variants above it are hidden
+# remote_max_bitrate_kbps = 8000
+# Default concurrent playback sessions per user (0 = unlimited)
+max_streams_per_user = 0
+
+[livetv]
+# Import channels from an M3U tuner playlist and an XMLTV guide (paths or URLs)
+enabled = false
+# m3u_path = "http://hdhomerun.local/lineup.m3u"
+# xmltv_path = "/path/to/guide.xml"
+# Recordings are saved here; point it at a library folder to have them scanned
+recordings_dir = "recordings"
+guide_refresh_hours = 12
+
+[integrity]
+# Decode library files in the background to find corrupt or truncated ones
+enabled = false
+# "sample" decodes a few windows per file (ending at its reported end), "full" every frame
+mode = "sample"
+interval_hours = 24
+items_per_pass = 200
+# unchanged files are checked again after this many days
+recheck_days = 30
 
 [metadata]
 image_cache_dir = "cache/images"
 rate_limit_per_second = 4
 # tmdb_api_key = "your-tmdb-api-key"
+# first language is the primary; others are fetched as translations
+languages = ["en-US"]
 
 [auth]
 jwt_secret = "{jwt_secret}"
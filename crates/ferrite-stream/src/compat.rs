@@ -1,3 +1,5 @@
+use ferrite_transcode::tonemap::DolbyVision;
+
 /// Client-specific playback capability profile.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ClientProfile {
@@ -9,6 +11,14 @@ pub enum ClientProfile {
 }
 
 impl ClientProfile {
+    pub const ALL: [Self; 5] = [
+        Self::WebChrome,
+        Self::SafariIos,
+        Self::Android,
+        Self::Tvos,
+        Self::Roku,
+    ];
+
     pub fn as_str(self) -> &'static str {
         match self {
             Self::WebChrome => "web-chrome",
@@ -198,6 +208,84 @@ pub fn determine_strategy_for_profile(
     }
 }
 
+/// Whether the video can reach the client as-is given its Dolby Vision
+/// signalling. No client profile decodes Dolby Vision itself: profiles 7 and
+/// 8.x play as their HDR10, SDR or HLG base layer (tvOS shows 8.1 as HDR10),
+/// while profile 5 has no base layer other decoders can show and must be
+/// tone-mapped.
+pub fn is_dolby_vision_compatible(dolby_vision: Option<DolbyVision>) -> bool {
+    dolby_vision.is_none_or(|dv| dv.has_compatible_base_layer())
+}
+
+/// [`determine_strategy_for_profile`] for a video stream that may carry Dolby
+/// Vision, forcing a full transcode when the client can't show it.
+pub fn determine_strategy_for_stream(
+    profile: ClientProfile,
+    container_format: Option<&str>,
+    video_codec: Option<&str>,
+    audio_codec: Option<&str>,
+    dolby_vision: Option<DolbyVision>,
+) -> StreamStrategy {
+    if !is_dolby_vision_compatible(dolby_vision) {
+        return StreamStrategy::FullTranscode;
+    }
+    determine_strategy_for_profile(profile, container_format, video_codec, audio_codec)
+}
+
+impl StreamStrategy {
+    /// Relative server cost of the strategy (lower is cheaper).
+    fn cost(&self) -> u8 {
+        match self {
+            StreamStrategy::DirectPlay => 0,
+            StreamStrategy::Remux => 1,
+            StreamStrategy::AudioTranscode => 2,
+            StreamStrategy::FullTranscode => 3,
+        }
+    }
+}
+
+/// Codec summary of one version of a title, used by [`select_version`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct VersionCandidate<'a> {
+    pub container_format: Option<&'a str>,
+    pub video_codec: Option<&'a str>,
+    pub audio_codec: Option<&'a str>,
+    pub height: Option<i64>,
+    pub dolby_vision: Option<DolbyVision>,
+    /// Multi-part stacks are always played through a remux or transcode.
+    pub stacked: bool,
+}
+
+/// Pick the version that is cheapest to stream for the client profile,
+/// preferring the higher resolution among equally cheap versions.
+/// Ties keep the earlier candidate, so callers should list the primary first.
+pub fn select_version(
+    profile: ClientProfile,
+    candidates: &[VersionCandidate<'_>],
+) -> Option<usize> {
+    candidates
+        .iter()
+        .enumerate()
+        .min_by_key(|(idx, c)| {
+            let mut strategy = determine_strategy_for_stream(
+                profile,
+                c.container_format,
+                c.video_codec,
+                c.audio_codec,
+                c.dolby_vision,
+            );
+            if c.stacked && strategy == StreamStrategy::DirectPlay {
+                strategy = StreamStrategy::Remux;
+            }
+            (
+                strategy.cost(),
+                std::cmp::Reverse(c.height.unwrap_or(0)),
+                *idx,
+            )
+        })
+        .map(|(idx, _)| idx)
+}
+
 /// Analyze a media item's codecs and determine the best streaming strategy.
 /// Defaults to the baseline web profile for compatibility with existing callsites.
 pub fn determine_strategy(
@@ -217,6 +305,46 @@ pub fn determine_strategy(
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_select_version_prefers_cheapest_then_highest_resolution() {
+        let hevc_4k = VersionCandidate {
+            container_format: Some("matroska"),
+            video_codec: Some("hevc"),
+            audio_codec: Some("eac3"),
+            height: Some(2160),
+            dolby_vision: None,
+            stacked: false,
+        };
+        let h264_720 = VersionCandidate {
+            container_format: Some("mp4"),
+            video_codec: Some("h264"),
+            audio_codec: Some("aac"),
+            height: Some(720),
+            dolby_vision: None,
+            stacked: false,
+        };
+        let h264_1080 = VersionCandidate {
+            height: Some(1080),
+            ..h264_720
+        };
+        let candidates = [hevc_4k, h264_720, h264_1080];
+        assert_eq!(
+            select_version(ClientProfile::WebChrome, &candidates),
+            Some(2)
+        );
+
+        // A stacked copy loses direct play and falls behind the single file.
+        let stacked_1080 = VersionCandidate {
+            stacked: true,
+            ..h264_1080
+        };
+        assert_eq!(
+            select_version(ClientProfile::WebChrome, &[stacked_1080, h264_720]),
+            Some(1)
+        );
+        assert_eq!(select_version(ClientProfile::WebChrome, &[]), None);
+    }
+
     #[test]
     fn test_mp4_h264_aac_is_direct() {
         assert_eq!(
@@ -382,4 +510,55 @@ mod tests {
             StreamStrategy::FullTranscode,
         );
     }
+
+    #[test]
+    fn test_tvos_plays_dolby_vision_8_1_as_hdr10() {
+        let dv = DolbyVision::from_columns(Some(8), Some(1));
+        assert_eq!(
+            determine_strategy_for_stream(
+                ClientProfile::Tvos,
+                Some("mp4"),
+                Some("hevc"),
+                Some("eac3"),
+                dv,
+            ),
+            StreamStrategy::DirectPlay,
+        );
+    }
+
+    #[test]
+    fn test_dolby_vision_profile_5_always_transcodes() {
+        let dv = DolbyVision::from_columns(Some(5), Some(0));
+        for profile in [
+            ClientProfile::Tvos,
+            ClientProfile::SafariIos,
+            ClientProfile::Android,
+            ClientProfile::Roku,
+        ] {
+            assert_eq!(
+                determine_strategy_for_stream(profile, Some("mp4"), Some("hevc"), Some("aac"), dv),
+                StreamStrategy::FullTranscode,
+                "{}",
+                profile.as_str()
+            );
+        }
+    }
+
+    #[test]
+    fn test_select_version_avoids_dolby_vision_profile_5() {
+        let dv5 = VersionCandidate {
+            container_format: Some("mp4"),
+            video_codec: Some("hevc"),
+            audio_codec: Some("aac"),
+            height: Some(2160),
+            dolby_vision: DolbyVision::from_columns(Some(5), Some(0)),
+            stacked: false,
+        };
+        let hdr10 = VersionCandidate {
+            dolby_vision: None,
+            height: Some(1080),
+            ..dv5
+        };
+        assert_eq!(select_version(ClientProfile::Tvos, &[dv5, hdr10]), Some(1));
+    }
 }
@@ -0,0 +1,455 @@
+//! Offline downloads: complete single-file MP4 transcodes.
+//!
+//! Unlike HLS sessions (whose segments are deleted as the playlist window
+//! slides), a download produces one self-contained, `faststart` MP4 at a chosen
+//! quality variant that a client can fetch with range requests and keep.
+
+use anyhow::{Context, Result};
+use ferrite_transcode::hwaccel::EncoderProfile;
+use ferrite_transcode::variants::QualityVariant;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::process::Command;
+use tokio::sync::Notify;
+use tracing::{debug, info, warn};
+
+/// Text subtitle formats that can be muxed into MP4 as `mov_text` soft tracks.
+/// Bitmap formats (PGS `sup`, VobSub `sub`/`idx`) cannot be converted.
+const SOFT_SUBTITLE_FORMATS: &[&str] = &["srt", "vtt", "ass", "ssa"];
+
+/// Whether an external subtitle in `format` can be included as a soft track.
+pub fn is_soft_subtitle_format(format: &str) -> bool {
+    SOFT_SUBTITLE_FORMATS.contains(&format.to_lowercase().as_str())
+}
+
+/// Properties of the source file that drive the transcode decisions.
+#[derive(Debug, Clone, Default)]
+pub struct DownloadSource {
+    pub file_path: PathBuf,
+    pub source_height: Option<u32>,
+    pub video_codec: Option<String>,
+    /// Codec of the selected audio stream, not the item's primary one.
+    pub audio_codec: Option<String>,
+    pub pixel_format: Option<String>,
+    pub color_transfer: Option<String>,
+    pub color_primaries: Option<String>,
+    /// Audio stream index (0-based within audio streams).
+    pub audio_stream_index: u32,
+}
+
+/// An external text subtitle to mux as a soft track.
+#[derive(Debug, Clone)]
+pub struct DownloadSubtitle {
+    pub path: PathBuf,
+    pub language: Option<String>,
+    pub title: Option<String>,
+}
+
+/// Estimate the output size in bytes for a download at `variant`.
+/// Used for quota admission before any transcoding starts.
+pub fn estimate_output_bytes(variant: &QualityVariant, duration_secs: f64) -> u64 {
+    let kbps = (variant.video_bitrate_kbps + variant.audio_bitrate_kbps) as f64;
+    (kbps * 1000.0 / 8.0 * duration_secs.max(0.0)) as u64
+}
+
+/// Build the FFmpeg args that transcode `source` into a single MP4 at `output_path`.
+///
+/// - Video is copied when the variant is the source's native height, the source
+///   is already H.264 and no tone-mapping/bit-depth filter is needed; otherwise it
+///   is encoded to H.264 (scaled and bitrate-constrained when below native).
+/// - Audio is copied when MP4/browser compatible, otherwise AAC stereo.
+/// - Subtitles are added as extra inputs and converted to `mov_text`.
+/// - `-progress pipe:1` reports progress on stdout for [`run_download`].
+pub fn build_download_args(
+    source: &DownloadSource,
+    variant: &QualityVariant,
+    subtitles: &[DownloadSubtitle],
+    encoder: &EncoderProfile,
+    output_path: &Path,
+) -> Vec<String> {
+    let needs_scaling = variant.height != source.source_height.unwrap_or(1080);
+
+    let mut vf_parts: Vec<String> = Vec::new();
+    let pixel_format = source.pixel_format.as_deref();
+    let is_high_bit = pixel_format
+        .map(ferrite_transcode::tonemap::is_high_bit_depth)
+        .unwrap_or(false);
+    let needs_tonemap = is_high_bit
+        && ferrite_transcode::tonemap::is_true_hdr(
+            source.color_transfer.as_deref(),
+            source.color_primaries.as_deref(),
+        );
+    if needs_tonemap {
+        vf_parts.push(ferrite_transcode::tonemap::tonemap_filter_for(
+            source.color_transfer.as_deref(),
+            source.color_primaries.as_deref(),
+        ));
+    } else if is_high_bit {
+        vf_parts.push(ferrite_transcode::tonemap::bit_depth_filter());
+    }
+    if needs_scaling {
+        vf_parts.push(format!("scale=-2:{}", variant.height));
+    }
+
+    let video_is_h264 = source
+        .video_codec
+        .as_deref()
+        .map(|c| c.eq_ignore_ascii_case("h264"))
+        .unwrap_or(false);
+    let can_copy_video = video_is_h264 && vf_parts.is_empty();
+
+    // Software filters (scale/tonemap) need CPU-side frames, so fall back to the
+    // software encoder like the HLS path does for scaled variants.
+    let needs_software = !vf_parts.is_empty() && encoder.is_hardware();
+    let effective_encoder = if needs_software {
+        encoder.software_fallback()
+    } else {
+        encoder.clone()
+    };
+
+    let mut args: Vec<String> = vec![
+        "-hide_banner".into(),
+        "-loglevel".into(),
+        "error".into(),
+        "-nostdin".into(),
+        "-y".into(),
+    ];
+
+    if !can_copy_video && !needs_software {
+        args.extend(effective_encoder.hw_input_args(!vf_parts.is_empty()));
+    }
+
+    args.extend(crate::stack::input_args(&source.file_path));
+    for sub in subtitles {
+        args.extend(["-i".into(), sub.path.to_string_lossy().to_string()]);
+    }
+
+    args.extend([
+        "-map".into(),
+        "0:v:0".into(),
+        "-map".into(),
+        format!("0:a:{}", source.audio_stream_index),
+    ]);
+    for i in 0..subtitles.len() {
+        args.extend(["-map".into(), format!("{}:s:0", i + 1)]);
+    }
+
+    if !vf_parts.is_empty() {
+        args.extend(["-vf".into(), vf_parts.join(",")]);
+    }
+
+    if can_copy_video {
+        args.extend(["-c:v".into(), "copy".into()]);
+    } else {
+        if is_high_bit {
+            args.extend(effective_encoder.video_encode_args_no_pix_fmt());
+        } else {
+            args.extend(effective_encoder.video_encode_args());
+        }
+        if needs_scaling {
+            args.extend([
+                "-b:v".into(),
+                format!("{}k", variant.video_bitrate_kbps),
+                "-maxrate".into(),
+                format!("{}k", (variant.video_bitrate_kbps as f64 * 1.5) as u32),
+                "-bufsize".into(),
+                format!("{}k", variant.video_bitrate_kbps * 2),
+            ]);
+        }
+    }
+
+    let can_passthrough = source
+        .audio_codec
+        .as_deref()
+        .map(ferrite_transcode::audio::can_passthrough)
+        .unwrap_or(false);
+    if can_passthrough {
+        args.extend(["-c:a".into(), "copy".into()]);
+    } else {
+        args.extend([
+            "-c:a".into(),
+            "aac".into(),
+            "-b:a".into(),
+            format!("{}k", variant.audio_bitrate_kbps),
+            "-ac".into(),
+            "2".into(),
+        ]);
+    }
+
+    if !subtitles.is_empty() {
+        args.extend(["-c:s".into(), "mov_text".into()]);
+        for (i, sub) in subtitles.iter().enumerate() {
+            if let Some(lang) = sub.language.as_deref() {
+                args.extend([format!("-metadata:s:s:{i}"), format!("language={lang}")]);
+            }
+            if let Some(title) = sub.title.as_deref() {
+                args.extend([format!("-metadata:s:s:{i}"), format!("title={title}")]);
+            }
+        }
+    }
+
+    args.extend([
+        "-movflags".into(),
+        "+faststart".into(),
+        "-progress".into(),
+        "pipe:1".into(),
+        "-nostats".into(),
+        "-f".into(),
+        "mp4".into(),
+        output_path.to_string_lossy().to_string(),
+    ]);
+
+    args
+}
+
+/// Parse an FFmpeg `-progress` line into a completion percentage.
+/// Both `out_time_us` and (despite its name) `out_time_ms` are in microseconds.
+pub fn parse_progress_line(line: &str, duration_secs: f64) -> Option<u8> {
+    let value = line
+        .strip_prefix("out_time_us=")
+        .or_else(|| line.strip_prefix("out_time_ms="))?;
+    let micros: i64 = value.trim().parse().ok()?;
+    if duration_secs <= 0.0 || micros < 0 {
+        return None;
+    }
+    let pct = (micros as f64 / 1_000_000.0 / duration_secs * 100.0).clamp(0.0, 99.0);
+    Some(pct as u8)
+}
+
+/// How a download transcode ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DownloadOutcome {
+    Completed,
+    Cancelled,
+}
+
+/// Run FFmpeg with `args` to completion, reporting progress through `on_progress`.
+///
+/// The process is killed if `cancel` is notified. Returns an error containing
+/// the last FFmpeg error lines if the transcode fails.
+pub async fn run_download<F>(
+    ffmpeg_path: &str,
+    args: &[String],
+    duration_secs: f64,
+    cancel: &Notify,
+    mut on_progress: F,
+) -> Result<DownloadOutcome>
+where
+    F: FnMut(u8),
+{
+    debug!("ffmpeg download args: {:?}", args);
+
+    let mut child = Command::new(ffmpeg_path)
+        .args(args)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .context("failed to spawn ffmpeg for download")?;
+
+    let stderr_task = child.stderr.take().map(|stderr| {
+        tokio::spawn(async move {
+            let mut tail: Vec<String> = Vec::new();
+            let mut lines = BufReader::new(stderr).lines();
+            while let Ok(Some(line)) = lines.next_line().await {
+                warn!("ffmpeg download: {}", line);
+                if tail.len() == 5 {
+                    tail.remove(0);
+                }
+                tail.push(line);
+            }
+            tail
+        })
+    });
+
+    let stdout = child
+        .stdout
+        .take()
+        .context("failed to capture ffmpeg stdout")?;
+    let mut lines = BufReader::new(stdout).lines();
+
+    loop {
+        tokio::select! {
+            line = lines.next_line() => match line {
+                Ok(Some(line)) => {
+                    if let Some(pct) = parse_progress_line(&line, duration_secs) {
+                        on_progress(pct);
+                    }
+                }
+                _ => break,
+            },
+            _ = cancel.notified() => {
+                info!("Download transcode cancelled, killing ffmpeg");
+                let _ = child.kill().await;
+                return Ok(DownloadOutcome::Cancelled);
+            }
+        }
+    }
+
+    let status = child.wait().await.context("failed to wait for ffmpeg")?;
+    if status.success() {
+        return Ok(DownloadOutcome::Completed);
+    }
+
+    let tail = match stderr_task {
+        Some(task) => task.await.unwrap_or_default(),
+        None => Vec::new(),
+    };
+    if tail.is_empty() {
+        anyhow::bail!("ffmpeg exited with {status}");
+    }
+    anyhow::bail!("ffmpeg exited with {status}: {}", tail.join("; "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ferrite_transcode::variants::select_variants;
+
+    fn variant(label: &str) -> QualityVariant {
+        select_variants(Some(1920), Some(1080))
+            .into_iter()
+            .find(|v| v.label == label)
+            .unwrap()
+    }
+
+    fn h264_source() -> DownloadSource {
+        DownloadSource {
+            file_path: PathBuf::from("/media/movie.mkv"),
+            source_height: Some(1080),
+            video_codec: Some("h264".into()),
+            audio_codec: Some("aac".into()),
+            ..Default::default()
+        }
+    }
+
+    fn has_pair(args: &[String], flag: &str, value: &str) -> bool {
+        args.windows(2).any(|w| w[0] == flag && w[1] == value)
+    }
+
+    #[test]
+    fn native_h264_is_copied() {
+        let args = build_download_args(
+            &h264_source(),
+            &variant("1080p"),
+            &[],
+            &EncoderProfile::software(),
+            Path::new("/cache/out.part"),
+        );
+        assert!(has_pair(&args, "-c:v", "copy"));
+        assert!(has_pair(&args, "-c:a", "copy"));
+        assert!(!args.iter().any(|a| a == "-vf"));
+        assert!(has_pair(&args, "-movflags", "+faststart"));
+        assert_eq!(args.last().unwrap(), "/cache/out.part");
+    }
+
+    #[test]
+    fn lower_variant_scales_and_constrains_bitrate() {
+        let args = build_download_args(
+            &h264_source(),
+            &variant("720p"),
+            &[],
+            &EncoderProfile::software(),
+            Path::new("/cache/out.part"),
+        );
+        assert!(has_pair(&args, "-vf", "scale=-2:720"));
+        assert!(has_pair(&args, "-b:v", "2800k"));
+        assert!(!has_pair(&args, "-c:v", "copy"));
+    }
+
+    #[test]
+    fn incompatible_audio_is_encoded_at_variant_bitrate() {
+        let mut source = h264_source();
+        source.audio_codec = Some("dts".into());
+        source.audio_stream_index = 2;
+        let args = build_download_args(
+            &source,
+            &variant("720p"),
+            &[],
+            &EncoderProfile::software(),
+            Path::new("/cache/out.part"),
+        );
+        assert!(has_pair(&args, "-map", "0:a:2"));
+        assert!(has_pair(&args, "-c:a", "aac"));
+        assert!(has_pair(&args, "-b:a", "128k"));
+    }
+
+    #[test]
+    fn hdr_source_is_tonemapped_not_copied() {
+        let mut source = h264_source();
+        source.pixel_format = Some("yuv420p10le".into());
+        source.color_transfer = Some("smpte2084".into());
+        source.color_primaries = Some("bt2020".into());
+        let args = build_download_args(
+            &source,
+            &variant("1080p"),
+            &[],
+            &EncoderProfile::software(),
+            Path::new("/cache/out.part"),
+        );
+        let vf = args
+            .windows(2)
+            .find(|w| w[0] == "-vf")
+            .map(|w| w[1].clone())
+            .unwrap();
+        assert!(vf.contains("tonemap"));
+        assert!(!has_pair(&args, "-c:v", "copy"));
+    }
+
+    #[test]
+    fn soft_subtitles_are_mapped_as_mov_text() {
+        let subs = vec![
+            DownloadSubtitle {
+                path: PathBuf::from("/media/movie.en.srt"),
+                language: Some("eng".into()),
+                title: None,
+            },
+            DownloadSubtitle {
+                path: PathBuf::from("/media/movie.fr.ass"),
+                language: Some("fre".into()),
+                title: Some("Forced".into()),
+            },
+        ];
+        let args = build_download_args(
+            &h264_source(),
+            &variant("1080p"),
+            &subs,
+            &EncoderProfile::software(),
+            Path::new("/cache/out.part"),
+        );
+        assert!(has_pair(&args, "-i", "/media/movie.en.srt"));
+        assert!(has_pair(&args, "-map", "1:s:0"));
+        assert!(has_pair(&args, "-map", "2:s:0"));
+        assert!(has_pair(&args, "-c:s", "mov_text"));
+        assert!(has_pair(&args, "-metadata:s:s:1", "language=fre"));
+        assert!(has_pair(&args, "-metadata:s:s:1", "title=Forced"));
+    }
+
+    #[test]
+    fn bitmap_subtitles_are_not_soft_muxable() {
+        assert!(is_soft_subtitle_format("SRT"));
+        assert!(is_soft_subtitle_format("ass"));
+        assert!(!is_soft_subtitle_format("sup"));
+        assert!(!is_soft_subtitle_format("idx"));
+    }
+
+    #[test]
+    fn progress_line_parsing() {
+        assert_eq!(parse_progress_line("out_time_us=30000000", 120.0), Some(25));
+        assert_eq!(parse_progress_line("out_time_ms=60000000", 120.0), Some(50));
+        assert_eq!(
+            parse_progress_line("out_time_us=999000000", 120.0),
+            Some(99)
+        );
+        assert_eq!(parse_progress_line("out_time_us=N/A", 120.0), None);
+        assert_eq!(parse_progress_line("frame=100", 120.0), None);
+        assert_eq!(parse_progress_line("out_time_us=1000", 0.0), None);
+    }
+
+    #[test]
+    fn estimate_uses_variant_bitrates() {
+        // 720p: 2800 + 128 kbps for 100s = 36.6 MB
+        assert_eq!(estimate_output_bytes(&variant("720p"), 100.0), 36_600_000);
+    }
+}
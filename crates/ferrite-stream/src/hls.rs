@@ -1,5 +1,7 @@
+use crate::segment_cache::{CachedRun, SegmentCache, SegmentCacheKey};
 use anyhow::{anyhow, Result};
 use dashmap::DashMap;
+use ferrite_core::config::EncodingConfig;
 use ferrite_transcode::hwaccel::EncoderProfile;
 use ferrite_transcode::variants::QualityVariant;
 use std::path::{Path, PathBuf};
@@ -55,6 +57,13 @@ pub struct HlsSession {
     /// and should be promoted to the full ABR ladder on the next master playlist poll.
     /// Set to false for seek-created sessions (which should stay single-variant).
     pub awaiting_promotion: std::sync::atomic::AtomicBool,
+    /// Grid index of this session's first segment. Segment files are numbered
+    /// from here; always 0 for sessions outside the segment cache.
+    pub first_segment: u64,
+    /// Segments checked out of the segment cache, served ahead of FFmpeg's output.
+    cached_prefix: Option<CachedRun>,
+    /// Segment cache entry that this session's FFmpeg output is stored into.
+    cache_key: Option<SegmentCacheKey>,
 }
 
 /// RFC6381 video codec emitted by the HLS pipeline.
@@ -92,6 +101,39 @@ fn output_audio_codec_rfc6381(source_audio_codec: Option<&str>) -> String {
     }
 }
 
+/// Whether a variant has to be scaled from the source height.
+fn variant_needs_scaling(variant: Option<&QualityVariant>, source_height: Option<u32>) -> bool {
+    variant.is_some_and(|v| v.height != source_height.unwrap_or(1080))
+}
+
+/// Whether a source-resolution session would copy the video stream rather
+/// than re-encode it.
+pub fn can_copy_source_video(
+    video_codec: Option<&str>,
+    pixel_format: Option<&str>,
+    burns_subtitles: bool,
+) -> bool {
+    let high_bit = pixel_format
+        .map(ferrite_transcode::tonemap::is_high_bit_depth)
+        .unwrap_or(false);
+    copies_video(video_codec, high_bit, burns_subtitles, false)
+}
+
+/// Whether the HLS pipeline copies the video stream: only H.264 sources that
+/// need no filters (tone-mapping or bit-depth conversion, subtitle burn-in,
+/// scaling).
+fn copies_video(
+    video_codec: Option<&str>,
+    high_bit: bool,
+    burns_subtitles: bool,
+    scaling: bool,
+) -> bool {
+    let video_is_h264 = video_codec
+        .map(|c| c.to_lowercase() == "h264")
+        .unwrap_or(false);
+    video_is_h264 && !high_bit && !burns_subtitles && !scaling
+}
+
 /// Current wall-clock time as milliseconds since UNIX epoch.
 fn epoch_ms_now() -> u64 {
     std::time::SystemTime::now()
@@ -158,6 +200,21 @@ impl HlsSession {
             .store(count, std::sync::atomic::Ordering::Release);
     }
 
+    /// True when segment timestamps are absolute media time rather than
+    /// relative to `start_secs`: with `-c:v copy`, and for segment-cached
+    /// sessions (which use `-output_ts_offset` so runs from different
+    /// sessions line up). Players must not add a start offset.
+    pub fn absolute_timestamps(&self) -> bool {
+        self.video_copied || self.cache_key.is_some()
+    }
+
+    /// Number of segments served from the segment cache.
+    pub fn cached_segment_count(&self) -> usize {
+        self.cached_prefix
+            .as_ref()
+            .map_or(0, |run| run.segments.len())
+    }
+
     /// Parse the playlist from disk to determine the actual available segment range.
     /// Returns `(available_start_secs, available_end_secs)` relative to the media timeline.
     /// Uses segment filenames (seg_NNN.m4s) rather than EXTINF count because the playlist
@@ -165,10 +222,16 @@ impl HlsSession {
     /// segment numbers keep incrementing.
     pub async fn playlist_available_range(&self) -> Option<(f64, f64)> {
         let playlist_path = self.output_dir.join("playlist.m3u8");
-        let content = tokio::fs::read_to_string(&playlist_path).await.ok()?;
-
-        let mut min_seg: Option<u64> = None;
-        let mut max_seg: Option<u64> = None;
+        let cached = self.cached_prefix.as_ref().map(|run| &run.segments);
+        let mut min_seg: Option<u64> = cached.and_then(|s| s.first()).map(|s| s.0);
+        let mut max_seg: Option<u64> = cached.and_then(|s| s.last()).map(|s| s.0);
+
+        let content = match tokio::fs::read_to_string(&playlist_path).await {
+            Ok(content) => content,
+            // A fully cached session has no FFmpeg playlist
+            Err(_) if min_seg.is_some() => String::new(),
+            Err(_) => return None,
+        };
 
         for line in content.lines() {
             let line = line.trim();
@@ -185,8 +248,8 @@ impl HlsSession {
 
         let seg_dur = self.segment_duration as f64;
         Some((
-            self.start_secs + min_seg? as f64 * seg_dur,
-            self.start_secs + (max_seg? + 1) as f64 * seg_dur,
+            self.start_secs + min_seg?.saturating_sub(self.first_segment) as f64 * seg_dur,
+            self.start_secs + (max_seg? + 1).saturating_sub(self.first_segment) as f64 * seg_dur,
         ))
     }
 
@@ -292,6 +355,17 @@ pub struct ActiveSessionInfo {
     pub bitrate_kbps: Option<u32>,
     pub idle_secs: u64,
     pub age_secs: u64,
+    /// Playback the session belongs to (see [`HlsSessionManager::owner_key`]).
+    pub owner_key: Option<String>,
+    pub user_id: Option<String>,
+}
+
+/// Why an administrator stopped a playback. Kept for the session timeout so
+/// the player learns about it on its next heartbeat or segment request.
+#[derive(Debug, Clone)]
+pub struct TerminationNotice {
+    pub reason: Option<String>,
+    pub terminated_at: Instant,
 }
 
 pub struct HlsSessionManager {
@@ -303,6 +377,10 @@ pub struct HlsSessionManager {
     /// Per-owner-key semaphore (capacity 1) to prevent concurrent session creation
     /// races for the same owner key, which could orphan FFmpeg processes.
     creation_locks: DashMap<String, Arc<Semaphore>>,
+    /// Map from owner key → (user_id, registered_at) for per-user stream limits.
+    owner_users: DashMap<String, (String, Instant)>,
+    /// Termination notices keyed by owner key and by each terminated session id
+    terminations: DashMap<String, Arc<TerminationNotice>>,
     cache_dir: PathBuf,
     ffmpeg_path: String,
     segment_duration: u64,
@@ -311,6 +389,10 @@ pub struct HlsSessionManager {
     /// Seconds of no segment requests before FFmpeg is killed (client paused).
     ffmpeg_idle_secs: u64,
     encoder: EncoderProfile,
+    /// Default ladder and encoder quality when a caller doesn't supply one.
+    encoding: EncodingConfig,
+    /// Persistent cache of re-encoded segments, when enabled.
+    segment_cache: Option<Arc<SegmentCache>>,
 }
 
 impl HlsSessionManager {
@@ -338,6 +420,8 @@ impl HlsSessionManager {
             media_sessions: DashMap::new(),
             media_variant_sessions: DashMap::new(),
             creation_locks: DashMap::new(),
+            owner_users: DashMap::new(),
+            terminations: DashMap::new(),
             cache_dir,
             ffmpeg_path,
             segment_duration,
@@ -345,9 +429,27 @@ impl HlsSessionManager {
             session_timeout_secs,
             ffmpeg_idle_secs,
             encoder,
+            encoding: EncodingConfig::default(),
+            segment_cache: None,
         }
     }
 
+    /// Use `encoding` as the default ladder and encoder quality.
+    pub fn with_encoding(mut self, encoding: EncodingConfig) -> Self {
+        self.encoding = encoding;
+        self
+    }
+
+    /// Reuse re-encoded segments across sessions through `cache`.
+    pub fn with_segment_cache(mut self, cache: Arc<SegmentCache>) -> Self {
+        self.segment_cache = Some(cache);
+        self
+    }
+
+    pub fn segment_cache(&self) -> Option<&Arc<SegmentCache>> {
+        self.segment_cache.as_ref()
+    }
+
     /// Get or create an HLS session for a media item.
     /// Returns the session. Creates FFmpeg process if new.
     /// `start_secs` is the time offset to start transcoding from (0.0 = beginning).
@@ -420,6 +522,8 @@ impl HlsSessionManager {
             video_codec,
             color_transfer,
             color_primaries,
+            None,
+            &self.encoding,
         )
         .await
     }
@@ -447,41 +551,21 @@ impl HlsSessionManager {
         video_codec: Option<&str>,
         color_transfer: Option<&str>,
         color_primaries: Option<&str>,
+        audio_filter: Option<&str>,
+        encoding: &EncodingConfig,
     ) -> Result<Arc<HlsSession>> {
-        // Destroy any existing session for this media (single-variant path)
-        if variant.is_none() {
-            if let Some(sid) = self.media_sessions.get(media_id) {
-                let old_sid = sid.clone();
-                drop(sid);
-                self.destroy_session(&old_sid).await;
-            }
-        }
-
-        // Create new session
-        let session_id = uuid::Uuid::new_v4().to_string();
-        let output_dir = self.cache_dir.join(&session_id);
-        tokio::fs::create_dir_all(&output_dir).await?;
-
-        let variant_label = variant.map(|v| v.label.clone());
-        info!(
-            "Creating HLS session {} for media {} at {:.1}s variant={} ({})",
-            session_id,
-            media_id,
-            start_secs,
-            variant_label.as_deref().unwrap_or("native"),
-            file_path.display()
-        );
-
-        // Spawn FFmpeg (with -ss if starting from a non-zero position)
-        let (child, stderr, video_copied) = self
-            .spawn_ffmpeg(
+        let session = self
+            .create_session_no_wait(
+                media_id,
                 file_path,
-                &output_dir,
+                duration_secs,
+                width,
+                height,
+                bitrate_kbps,
                 start_secs,
                 requested_secs,
                 subtitle_path,
                 variant,
-                height,
                 pixel_format,
                 audio_stream_index,
                 frame_rate,
@@ -489,86 +573,11 @@ impl HlsSessionManager {
                 video_codec,
                 color_transfer,
                 color_primaries,
+                audio_filter,
+                encoding,
             )
             .await?;
 
-        let (session_w, session_h, session_bw) = match variant {
-            Some(v) => (Some(v.width), Some(v.height), v.bandwidth_bps),
-            None => (
-                width,
-                height,
-                bitrate_kbps.map(|k| (k as u64) * 1000).unwrap_or(5_000_000),
-            ),
-        };
-
-        // When video is copied (-c:v copy), there is no post-input -ss trim so
-        // the stream actually starts from the keyframe position, not the precise
-        // requested time. The frontend uses start_secs as hlsStartOffset to
-        // compute actualTime(), so it must match where the video truly begins.
-        let effective_start = if video_copied {
-            start_secs
-        } else {
-            requested_secs
-        };
-
-        let video_codec_rfc6381 = output_video_codec_rfc6381(video_codec, video_copied);
-        let audio_codec_rfc6381 = output_audio_codec_rfc6381(audio_codec);
-
-        let now_epoch = epoch_ms_now();
-        let session = Arc::new(HlsSession {
-            session_id: session_id.clone(),
-            media_id: media_id.to_string(),
-            output_dir: output_dir.clone(),
-            segment_duration: self.segment_duration,
-            ffmpeg_handle: Mutex::new(Some(child)),
-            created_at: Instant::now(),
-            last_accessed_epoch_ms: std::sync::atomic::AtomicU64::new(now_epoch),
-            last_segment_request_epoch_ms: std::sync::atomic::AtomicU64::new(now_epoch),
-            ffmpeg_failed: std::sync::atomic::AtomicBool::new(false),
-            segment_count: std::sync::atomic::AtomicU64::new(0),
-            duration_secs,
-            width: session_w,
-            height: session_h,
-            bitrate_kbps: variant.map(|v| v.video_bitrate_kbps).or(bitrate_kbps),
-            start_secs: effective_start,
-            variant_label,
-            bandwidth_bps: session_bw,
-            video_codec_rfc6381,
-            audio_codec_rfc6381,
-            video_copied,
-            awaiting_promotion: std::sync::atomic::AtomicBool::new(false),
-        });
-
-        // Wire the stderr reader to the session's ffmpeg_failed flag.
-        // This must happen after session construction so we can clone the Arc.
-        if let Some(stderr) = stderr {
-            let session_id_log = session_id.clone();
-            let session_arc = session.clone();
-            tokio::spawn(async move {
-                use tokio::io::AsyncBufReadExt;
-                let reader = tokio::io::BufReader::new(stderr);
-                let mut lines = reader.lines();
-                while let Ok(Some(line)) = lines.next_line().await {
-                    warn!("ffmpeg HLS [{}]: {}", session_id_log, line);
-                    if is_ffmpeg_fatal_error(&line) {
-                        warn!(
-                            "ffmpeg HLS [{}]: fatal error detected, marking session failed",
-                            session_id_log
-                        );
-                        session_arc
-                            .ffmpeg_failed
-                            .store(true, std::sync::atomic::Ordering::Release);
-                    }
-                }
-            });
-        }
-
-        self.sessions.insert(session_id.clone(), session.clone());
-        if variant.is_none() {
-            self.media_sessions
-                .insert(media_id.to_string(), session_id.clone());
-        }
-
         Self::wait_for_first_segment(&session).await;
 
         Ok(session)
@@ -579,6 +588,9 @@ impl HlsSessionManager {
     /// The aggressive initial tier (20ms) minimizes latency for video-copy sessions
     /// that typically produce the first segment in <300ms.
     async fn wait_for_first_segment(session: &HlsSession) {
+        if session.cached_segment_count() > 0 {
+            return;
+        }
         let playlist_path = session.output_dir.join("playlist.m3u8");
         let mut ready = false;
         let poll_schedule: &[(u64, u32)] = &[(20, 25), (50, 40), (250, 40)];
@@ -633,6 +645,8 @@ impl HlsSessionManager {
         video_codec: Option<&str>,
         color_transfer: Option<&str>,
         color_primaries: Option<&str>,
+        audio_filter: Option<&str>,
+        encoding: &EncodingConfig,
     ) -> Result<Arc<HlsSession>> {
         // Destroy any existing session for this media (single-variant path)
         if variant.is_none() {
@@ -658,25 +672,84 @@ impl HlsSessionManager {
             file_path.display()
         );
 
-        // Spawn FFmpeg (with -ss if starting from a non-zero position)
-        let (child, stderr, video_copied) = self
-            .spawn_ffmpeg(
+        // Re-encoded variants can use the segment cache. Their segments sit on
+        // a fixed grid, so snap the start down to a segment boundary and check
+        // out whatever is already cached from there.
+        let cache_key = self
+            .segment_cache_key(
+                media_id,
                 file_path,
-                &output_dir,
-                start_secs,
-                requested_secs,
-                subtitle_path,
                 variant,
+                subtitle_path,
                 height,
                 pixel_format,
                 audio_stream_index,
-                frame_rate,
-                audio_codec,
                 video_codec,
-                color_transfer,
-                color_primaries,
+                audio_filter,
+                encoding,
             )
-            .await?;
+            .await;
+        let seg_dur = self.segment_duration as f64;
+        let first_segment = match cache_key {
+            Some(_) => (requested_secs / seg_dur).floor() as u64,
+            None => 0,
+        };
+        let cached_prefix = match (&self.segment_cache, &cache_key) {
+            (Some(cache), Some(key)) => {
+                let (cache, key, dest) = (cache.clone(), key.clone(), output_dir.clone());
+                tokio::task::spawn_blocking(move || cache.checkout(&key, first_segment, &dest))
+                    .await
+                    .unwrap_or(None)
+            }
+            _ => None,
+        };
+        let cached_count = cached_prefix.as_ref().map_or(0, |run| run.segments.len()) as u64;
+        let fully_cached = cached_prefix.as_ref().is_some_and(|run| run.complete);
+        if cached_count > 0 {
+            info!(
+                "HLS session {} reuses {} cached segments from segment {}{}",
+                session_id,
+                cached_count,
+                first_segment,
+                if fully_cached { " (complete)" } else { "" }
+            );
+        }
+
+        // FFmpeg picks up at the first segment the cache doesn't have.
+        let output_first_segment = cache_key.as_ref().map(|_| first_segment + cached_count);
+        let (ffmpeg_start, ffmpeg_requested) = match output_first_segment {
+            Some(n) if cached_count > 0 => (n as f64 * seg_dur, n as f64 * seg_dur),
+            Some(n) => (start_secs.min(n as f64 * seg_dur), n as f64 * seg_dur),
+            None => (start_secs, requested_secs),
+        };
+
+        // Spawn FFmpeg (with -ss if starting from a non-zero position)
+        let (child, stderr, video_copied) = if fully_cached {
+            (None, None, false)
+        } else {
+            let (child, stderr, video_copied) = self
+                .spawn_ffmpeg(
+                    file_path,
+                    &output_dir,
+                    ffmpeg_start,
+                    ffmpeg_requested,
+                    subtitle_path,
+                    variant,
+                    height,
+                    pixel_format,
+                    audio_stream_index,
+                    frame_rate,
+                    audio_codec,
+                    video_codec,
+                    color_transfer,
+                    color_primaries,
+                    audio_filter,
+                    encoding,
+                    output_first_segment,
+                )
+                .await?;
+            (Some(child), stderr, video_copied)
+        };
 
         let (session_w, session_h, session_bw) = match variant {
             Some(v) => (Some(v.width), Some(v.height), v.bandwidth_bps),
@@ -687,14 +760,23 @@ impl HlsSessionManager {
             ),
         };
 
-        let effective_start = if video_copied {
+        // When video is copied (-c:v copy), there is no post-input -ss trim so
+        // the stream actually starts from the keyframe position, not the precise
+        // requested time. The frontend uses start_secs as hlsStartOffset to
+        // compute actualTime(), so it must match where the video truly begins.
+        // Segment-cached sessions start on the grid boundary.
+        let effective_start = if cache_key.is_some() {
+            first_segment as f64 * seg_dur
+        } else if video_copied {
             start_secs
         } else {
             requested_secs
         };
 
         let video_codec_rfc6381 = output_video_codec_rfc6381(video_codec, video_copied);
-        let audio_codec_rfc6381 = output_audio_codec_rfc6381(audio_codec);
+        // Filtered audio is always re-encoded to AAC
+        let audio_codec_rfc6381 =
+            output_audio_codec_rfc6381(audio_codec.filter(|_| audio_filter.is_none()));
 
         let now_epoch = epoch_ms_now();
         let session = Arc::new(HlsSession {
@@ -702,7 +784,7 @@ impl HlsSessionManager {
             media_id: media_id.to_string(),
             output_dir: output_dir.clone(),
             segment_duration: self.segment_duration,
-            ffmpeg_handle: Mutex::new(Some(child)),
+            ffmpeg_handle: Mutex::new(child),
             created_at: Instant::now(),
             last_accessed_epoch_ms: std::sync::atomic::AtomicU64::new(now_epoch),
             last_segment_request_epoch_ms: std::sync::atomic::AtomicU64::new(now_epoch),
@@ -719,9 +801,13 @@ impl HlsSessionManager {
             audio_codec_rfc6381,
             video_copied,
             awaiting_promotion: std::sync::atomic::AtomicBool::new(false),
+            first_segment,
+            cached_prefix,
+            cache_key,
         });
 
         // Wire the stderr reader to the session's ffmpeg_failed flag.
+        // This must happen after session construction so we can clone the Arc.
         if let Some(stderr) = stderr {
             let session_id_log = session_id.clone();
             let session_arc = session.clone();
@@ -793,6 +879,10 @@ impl HlsSessionManager {
             video_codec,
             color_transfer,
             color_primaries,
+            None,
+            None,
+            None,
+            &self.encoding,
         )
         .await
     }
@@ -819,6 +909,10 @@ impl HlsSessionManager {
         video_codec: Option<&str>,
         color_transfer: Option<&str>,
         color_primaries: Option<&str>,
+        max_bitrate_kbps: Option<u32>,
+        max_variants: Option<usize>,
+        audio_filter: Option<&str>,
+        encoding: &EncodingConfig,
     ) -> Result<Vec<Arc<HlsSession>>> {
         // Serialize creates for this ownership key so concurrent calls don't
         // destroy each other's session mapping and orphan FFmpeg processes.
@@ -835,7 +929,14 @@ impl HlsSessionManager {
         // Destroy any existing variant sessions for this ownership key
         self.destroy_owner_sessions(owner_key).await;
 
-        let variants = ferrite_transcode::variants::select_variants(source_width, source_height);
+        let mut variants = ferrite_transcode::variants::cap_variants(
+            ferrite_transcode::variants::select_variants_for(encoding, source_width, source_height),
+            max_bitrate_kbps,
+        );
+        // Keep the top of the ladder when the transcode scheduler grants fewer encodes
+        if let Some(max) = max_variants {
+            variants.truncate(max.max(1));
+        }
         info!(
             "Creating {} ABR variant sessions for media {} (source={}x{})",
             variants.len(),
@@ -868,6 +969,8 @@ impl HlsSessionManager {
                     video_codec,
                     color_transfer,
                     color_primaries,
+                    audio_filter,
+                    encoding,
                 )
                 .await?;
             session_ids.push(session.session_id.clone());
@@ -932,6 +1035,9 @@ impl HlsSessionManager {
             video_codec,
             color_transfer,
             color_primaries,
+            None,
+            None,
+            &self.encoding,
             awaiting_promotion,
         )
         .await
@@ -959,6 +1065,9 @@ impl HlsSessionManager {
         video_codec: Option<&str>,
         color_transfer: Option<&str>,
         color_primaries: Option<&str>,
+        max_bitrate_kbps: Option<u32>,
+        audio_filter: Option<&str>,
+        encoding: &EncodingConfig,
         awaiting_promotion: bool,
     ) -> Result<Vec<Arc<HlsSession>>> {
         // Serialize creates for this ownership key so concurrent calls don't
@@ -976,12 +1085,141 @@ impl HlsSessionManager {
         // Destroy any existing sessions for this media
         self.destroy_owner_sessions(owner_key).await;
 
-        let variants = ferrite_transcode::variants::select_variants(source_width, source_height);
+        let variants = ferrite_transcode::variants::cap_variants(
+            ferrite_transcode::variants::select_variants_for(encoding, source_width, source_height),
+            max_bitrate_kbps,
+        );
         // Use only the highest quality variant (first in the list)
         let variant = variants
             .first()
             .ok_or_else(|| anyhow!("No quality variants available"))?;
 
+        self.create_owned_session(
+            owner_key,
+            media_id,
+            file_path,
+            duration_secs,
+            source_width,
+            source_height,
+            source_bitrate_kbps,
+            start_secs,
+            requested_secs,
+            subtitle_path,
+            variant,
+            pixel_format,
+            audio_stream_index,
+            frame_rate,
+            audio_codec,
+            video_codec,
+            color_transfer,
+            color_primaries,
+            audio_filter,
+            encoding,
+            awaiting_promotion,
+        )
+        .await
+    }
+
+    /// Create a single session at the source resolution, which copies the
+    /// video stream when the source allows it (see [`can_copy_source_video`]).
+    /// The transcode scheduler uses this as the cheap path when it's saturated.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn create_passthrough_session_owned(
+        &self,
+        owner_key: &str,
+        media_id: &str,
+        file_path: &Path,
+        duration_secs: Option<f64>,
+        source_width: Option<u32>,
+        source_height: Option<u32>,
+        source_bitrate_kbps: Option<u32>,
+        start_secs: f64,
+        requested_secs: f64,
+        pixel_format: Option<&str>,
+        audio_stream_index: Option<u32>,
+        frame_rate: Option<&str>,
+        audio_codec: Option<&str>,
+        video_codec: Option<&str>,
+        color_transfer: Option<&str>,
+        color_primaries: Option<&str>,
+        audio_filter: Option<&str>,
+        encoding: &EncodingConfig,
+    ) -> Result<Vec<Arc<HlsSession>>> {
+        let lock = self
+            .creation_locks
+            .entry(owner_key.to_string())
+            .or_insert_with(|| Arc::new(Semaphore::new(1)))
+            .clone();
+        let _guard = lock
+            .acquire()
+            .await
+            .map_err(|e| anyhow!("Lock error: {}", e))?;
+
+        self.destroy_owner_sessions(owner_key).await;
+
+        let video_bitrate_kbps = source_bitrate_kbps.unwrap_or(5000);
+        let variant = QualityVariant {
+            label: "source".to_string(),
+            height: source_height.unwrap_or(1080),
+            width: source_width.unwrap_or(0),
+            video_bitrate_kbps,
+            audio_bitrate_kbps: encoding.audio_bitrate_kbps,
+            bandwidth_bps: (video_bitrate_kbps + encoding.audio_bitrate_kbps) as u64 * 1000,
+        };
+
+        self.create_owned_session(
+            owner_key,
+            media_id,
+            file_path,
+            duration_secs,
+            source_width,
+            source_height,
+            source_bitrate_kbps,
+            start_secs,
+            requested_secs,
+            None,
+            &variant,
+            pixel_format,
+            audio_stream_index,
+            frame_rate,
+            audio_codec,
+            video_codec,
+            color_transfer,
+            color_primaries,
+            audio_filter,
+            encoding,
+            false,
+        )
+        .await
+    }
+
+    /// Create one variant session and register it as the owner's only session.
+    /// Callers hold the owner's creation lock.
+    #[allow(clippy::too_many_arguments)]
+    async fn create_owned_session(
+        &self,
+        owner_key: &str,
+        media_id: &str,
+        file_path: &Path,
+        duration_secs: Option<f64>,
+        source_width: Option<u32>,
+        source_height: Option<u32>,
+        source_bitrate_kbps: Option<u32>,
+        start_secs: f64,
+        requested_secs: f64,
+        subtitle_path: Option<&Path>,
+        variant: &QualityVariant,
+        pixel_format: Option<&str>,
+        audio_stream_index: Option<u32>,
+        frame_rate: Option<&str>,
+        audio_codec: Option<&str>,
+        video_codec: Option<&str>,
+        color_transfer: Option<&str>,
+        color_primaries: Option<&str>,
+        audio_filter: Option<&str>,
+        encoding: &EncodingConfig,
+        awaiting_promotion: bool,
+    ) -> Result<Vec<Arc<HlsSession>>> {
         info!(
             "Creating single HLS session for media {} at {:.1}s variant={} awaiting_promotion={}",
             media_id, start_secs, variant.label, awaiting_promotion,
@@ -1006,6 +1244,8 @@ impl HlsSessionManager {
                 video_codec,
                 color_transfer,
                 color_primaries,
+                audio_filter,
+                encoding,
             )
             .await?;
 
@@ -1052,11 +1292,113 @@ impl HlsSessionManager {
         futures::future::join_all(futs).await;
     }
 
+    /// Record which user a playback owner key belongs to, so concurrent
+    /// playbacks can be counted per user.
+    pub fn register_owner_user(&self, owner_key: &str, user_id: &str) {
+        self.owner_users
+            .insert(owner_key.to_string(), (user_id.to_string(), Instant::now()));
+    }
+
+    /// Forget a playback owner (explicit playback stop).
+    pub fn unregister_owner(&self, owner_key: &str) {
+        self.owner_users.remove(owner_key);
+    }
+
+    /// User a playback owner key was registered to.
+    pub fn owner_user(&self, owner_key: &str) -> Option<String> {
+        self.owner_users.get(owner_key).map(|e| e.value().0.clone())
+    }
+
+    /// Owner key of the playback a session belongs to.
+    pub fn owner_of_session(&self, session_id: &str) -> Option<String> {
+        self.media_variant_sessions
+            .iter()
+            .find(|e| e.value().iter().any(|sid| sid == session_id))
+            .map(|e| e.key().clone())
+            .or_else(|| {
+                self.media_sessions
+                    .iter()
+                    .find(|e| e.value() == session_id)
+                    .map(|e| e.key().clone())
+            })
+    }
+
+    /// Split an owner key into its media id and playback session id.
+    pub fn split_owner_key(owner_key: &str) -> (&str, Option<&str>) {
+        match owner_key.split_once("::") {
+            Some((media_id, pid)) => (media_id, Some(pid)),
+            None => (owner_key, None),
+        }
+    }
+
+    /// Stop a playback on an administrator's behalf: destroy its sessions and
+    /// leave a notice with `reason` for the player to pick up.
+    pub async fn terminate_owner(&self, owner_key: &str, reason: Option<String>) {
+        let notice = Arc::new(TerminationNotice {
+            reason,
+            terminated_at: Instant::now(),
+        });
+        let mut keys: Vec<String> = self
+            .get_variant_sessions_owned(owner_key)
+            .iter()
+            .map(|s| s.session_id.clone())
+            .collect();
+        if let Some(sid) = self.media_sessions.get(owner_key) {
+            keys.push(sid.clone());
+        }
+        keys.push(owner_key.to_string());
+        for key in keys {
+            self.terminations.insert(key, Arc::clone(&notice));
+        }
+        info!("Terminating playback {}", owner_key);
+        self.destroy_owner_sessions(owner_key).await;
+        self.unregister_owner(owner_key);
+    }
+
+    /// Termination notice for an owner key or session id, if an administrator
+    /// stopped it recently.
+    pub fn termination_notice(&self, key: &str) -> Option<Arc<TerminationNotice>> {
+        self.terminations.get(key).map(|e| Arc::clone(e.value()))
+    }
+
+    fn owner_has_sessions(&self, owner_key: &str) -> bool {
+        self.media_variant_sessions.contains_key(owner_key)
+            || self.media_sessions.contains_key(owner_key)
+    }
+
+    /// Owner keys of a user's active playbacks: owners with live sessions, plus
+    /// owners registered within the session timeout that have not created
+    /// sessions yet (between session start and the first master playlist).
+    pub fn active_owners_for_user(&self, user_id: &str) -> Vec<String> {
+        self.owner_users
+            .iter()
+            .filter(|entry| {
+                let (uid, registered_at) = entry.value();
+                uid == user_id
+                    && (self.owner_has_sessions(entry.key())
+                        || registered_at.elapsed().as_secs() <= self.session_timeout_secs)
+            })
+            .map(|entry| entry.key().clone())
+            .collect()
+    }
+
     /// Return a snapshot of all currently active HLS sessions.
     pub fn list_active_sessions(&self) -> Vec<ActiveSessionInfo> {
+        let mut owners: std::collections::HashMap<String, String> =
+            std::collections::HashMap::new();
+        for entry in self.media_variant_sessions.iter() {
+            for sid in entry.value() {
+                owners.insert(sid.clone(), entry.key().clone());
+            }
+        }
+        for entry in self.media_sessions.iter() {
+            owners.insert(entry.value().clone(), entry.key().clone());
+        }
         let mut result = Vec::new();
         for entry in self.sessions.iter() {
             let s = entry.value();
+            let owner_key = owners.get(&s.session_id).cloned();
+            let user_id = owner_key.as_deref().and_then(|o| self.owner_user(o));
             result.push(ActiveSessionInfo {
                 session_id: s.session_id.clone(),
                 media_id: s.media_id.clone(),
@@ -1067,6 +1409,8 @@ impl HlsSessionManager {
                 bitrate_kbps: s.bitrate_kbps,
                 idle_secs: s.idle_secs(),
                 age_secs: s.created_at.elapsed().as_secs(),
+                owner_key,
+                user_id,
             });
         }
         result
@@ -1088,11 +1432,114 @@ impl HlsSessionManager {
         }
     }
 
+    /// Number of video encodes an owner's sessions are running (live FFmpeg
+    /// processes that re-encode video), or `None` if the owner has no sessions.
+    pub async fn owner_encode_load(&self, owner_key: &str) -> Option<usize> {
+        let sessions = self.get_variant_sessions_owned(owner_key);
+        if sessions.is_empty() {
+            return None;
+        }
+        let mut load = 0;
+        for session in sessions {
+            if !session.video_copied && session.is_ffmpeg_alive().await {
+                load += 1;
+            }
+        }
+        Some(load)
+    }
+
+    /// Drop an owner's higher-bitrate variants so only the `keep` lowest
+    /// remain. Players fall back to the remaining levels. Returns how many
+    /// variants were dropped.
+    pub async fn trim_owner_variants(&self, owner_key: &str, keep: usize) -> usize {
+        let mut sessions = self.get_variant_sessions_owned(owner_key);
+        sessions.sort_by_key(|s| s.bandwidth_bps);
+        let mut dropped = 0;
+        for session in sessions.iter().skip(keep.max(1)) {
+            info!(
+                "Dropping variant {} of {} to free transcode capacity",
+                session.variant_label.as_deref().unwrap_or("native"),
+                owner_key
+            );
+            self.destroy_session(&session.session_id).await;
+            dropped += 1;
+        }
+        dropped
+    }
+
+    /// Segment cache entry for a new session, or `None` when the session can't
+    /// use the cache: the cache is off, it's the native (non-variant) stream,
+    /// subtitles are burned in, or video is copied rather than re-encoded.
+    #[allow(clippy::too_many_arguments)]
+    async fn segment_cache_key(
+        &self,
+        media_id: &str,
+        file_path: &Path,
+        variant: Option<&QualityVariant>,
+        subtitle_path: Option<&Path>,
+        source_height: Option<u32>,
+        pixel_format: Option<&str>,
+        audio_stream_index: Option<u32>,
+        video_codec: Option<&str>,
+        audio_filter: Option<&str>,
+        encoding: &EncodingConfig,
+    ) -> Option<SegmentCacheKey> {
+        self.segment_cache.as_ref()?;
+        let variant = variant?;
+        if subtitle_path.is_some() {
+            return None;
+        }
+        let scaling = variant_needs_scaling(Some(variant), source_height);
+        let high_bit = pixel_format
+            .map(ferrite_transcode::tonemap::is_high_bit_depth)
+            .unwrap_or(false);
+        if copies_video(video_codec, high_bit, false, scaling) {
+            return None;
+        }
+
+        // A replaced file keeps its media id, so its size and mtime are part
+        // of the key along with everything that shapes the encoder output.
+        let meta = tokio::fs::metadata(file_path).await.ok()?;
+        let mtime = meta
+            .modified()
+            .ok()?
+            .duration_since(std::time::UNIX_EPOCH)
+            .ok()?
+            .as_secs();
+        let encoder_name = if scaling && self.encoder.is_hardware() {
+            self.encoder.software_fallback().encoder_name
+        } else {
+            self.encoder.encoder_name.clone()
+        };
+        let params = format!(
+            "{}|{}|{}|{}x{}|{}k|{}k|{}s|{}|{}|{}",
+            encoder_name,
+            encoding.preset,
+            encoding.crf,
+            variant.width,
+            variant.height,
+            variant.video_bitrate_kbps,
+            variant.audio_bitrate_kbps,
+            self.segment_duration,
+            meta.len(),
+            mtime,
+            audio_filter.unwrap_or("")
+        );
+        Some(SegmentCacheKey::new(
+            media_id,
+            &variant.label,
+            audio_stream_index.unwrap_or(0),
+            &params,
+        ))
+    }
+
     /// Spawn FFmpeg with HLS output.
     /// If `start_secs > 0`, uses `-ss` before `-i` for fast input seeking.
     /// If `subtitle_path` is provided, burns subtitles into the video via `-vf subtitles=`.
     /// If `variant` is provided, scales video and constrains bitrate to that quality level.
     /// `source_height` is used to determine if the variant actually needs scaling.
+    /// With `first_segment` (segment-cached sessions), segments are numbered
+    /// from that grid index, carry absolute timestamps and are all kept on disk.
     #[allow(clippy::too_many_arguments)]
     async fn spawn_ffmpeg(
         &self,
@@ -1110,17 +1557,17 @@ impl HlsSessionManager {
         video_codec: Option<&str>,
         color_transfer: Option<&str>,
         color_primaries: Option<&str>,
+        audio_filter: Option<&str>,
+        encoding: &EncodingConfig,
+        first_segment: Option<u64>,
     ) -> Result<(Child, Option<tokio::process::ChildStderr>, bool)> {
         // Only fall back to software encoding when we actually need CPU-side
         // frame access (subtitle burn-in or resolution scaling).
         // If the variant matches the source resolution, no scale filter is needed
         // and we can keep using the hardware encoder.
-        let needs_scaling = variant.is_some_and(|v| {
-            let src_h = source_height.unwrap_or(1080);
-            v.height != src_h
-        });
-        let needs_software =
-            (subtitle_path.is_some() || needs_scaling) && self.encoder.is_hardware();
+        let needs_scaling = variant_needs_scaling(variant, source_height);
+        let encoder = self.encoder.with_quality(&encoding.preset, encoding.crf);
+        let needs_software = (subtitle_path.is_some() || needs_scaling) && encoder.is_hardware();
         let effective_encoder = if needs_software {
             if subtitle_path.is_some() {
                 info!("HLS subtitle burn-in active — falling back to software encoder");
@@ -1128,9 +1575,9 @@ impl HlsSessionManager {
             if needs_scaling {
                 info!("HLS variant scaling active — falling back to software encoder");
             }
-            EncoderProfile::software()
+            encoder.software_fallback()
         } else {
-            self.encoder.clone()
+            encoder
         };
 
         // ---------------------------------------------------------------
@@ -1151,7 +1598,10 @@ impl HlsSessionManager {
                 color_transfer,
                 color_primaries
             );
-            vf_parts.push(ferrite_transcode::tonemap::tonemap_filter());
+            vf_parts.push(ferrite_transcode::tonemap::tonemap_filter_for(
+                color_transfer,
+                color_primaries,
+            ));
         } else if is_high_bit {
             info!(
                 "10-bit SDR detected (pix={}, transfer={:?}), applying bit-depth conversion only",
@@ -1184,12 +1634,14 @@ impl HlsSessionManager {
             }
         }
 
-        let video_is_h264 = video_codec
-            .map(|c| c.to_lowercase() == "h264")
-            .unwrap_or(false);
         // Video copy is now permitted on seek because we align the seek target
         // to a keyframe in the API layer, removing the need for a precise post-input trim.
-        let can_copy_video = video_is_h264 && vf_parts.is_empty();
+        let can_copy_video = copies_video(
+            video_codec,
+            is_high_bit,
+            subtitle_path.is_some(),
+            needs_scaling,
+        );
 
         let has_software_filters = !vf_parts.is_empty();
 
@@ -1226,7 +1678,7 @@ impl HlsSessionManager {
         }
 
         let audio_map = format!("0:a:{}", audio_stream_index.unwrap_or(0));
-        args.extend(["-i".into(), file_path.to_string_lossy().to_string()]);
+        args.extend(crate::stack::input_args(file_path));
 
         // Precise seek after input (only when re-encoding): decode from the
         // keyframe but trim output to the exact requested time.
@@ -1277,7 +1729,7 @@ impl HlsSessionManager {
 
         let audio_bitrate = variant
             .map(|v| format!("{}k", v.audio_bitrate_kbps))
-            .unwrap_or_else(|| "192k".into());
+            .unwrap_or_else(|| format!("{}k", encoding.audio_bitrate_kbps));
 
         // Force keyframes at segment boundaries (only when re-encoding video).
         // -force_key_frames "expr:..." only works with libx264, NOT with hardware
@@ -1295,13 +1747,18 @@ impl HlsSessionManager {
             ]);
         }
 
-        // Audio: passthrough if the source codec is browser-compatible, otherwise re-encode to AAC stereo
-        let can_passthrough = audio_codec
-            .map(ferrite_transcode::audio::can_passthrough)
-            .unwrap_or(false);
+        // Audio: passthrough if the source codec is browser-compatible and no
+        // audio filter applies, otherwise re-encode to AAC stereo
+        let can_passthrough = audio_filter.is_none()
+            && audio_codec
+                .map(ferrite_transcode::audio::can_passthrough)
+                .unwrap_or(false);
         if can_passthrough {
             args.extend(["-c:a".into(), "copy".into()]);
         } else {
+            if let Some(filter) = audio_filter {
+                args.extend(["-af".into(), filter.to_string()]);
+            }
             args.extend([
                 "-c:a".into(),
                 "aac".into(),
@@ -1312,6 +1769,20 @@ impl HlsSessionManager {
             ]);
         }
 
+        if let Some(first) = first_segment {
+            // Absolute timestamps and grid numbering let segments from this
+            // session be mixed with cached ones from earlier sessions.
+            args.extend([
+                "-output_ts_offset".into(),
+                format!("{:.3}", requested_secs),
+                "-start_number".into(),
+                first.to_string(),
+            ]);
+        }
+
+        // Copy mode and cached sessions keep every segment; the segment cache
+        // stitches cached runs and FFmpeg's output into one gapless playlist.
+        let keep_all_segments = can_copy_video || first_segment.is_some();
         args.extend([
             // HLS output
             "-f".into(),
@@ -1319,8 +1790,8 @@ impl HlsSessionManager {
             "-hls_time".into(),
             self.segment_duration.to_string(),
             "-hls_list_size".into(),
-            if can_copy_video {
-                "0".into() // Unlimited: keep all segments in playlist for copy mode
+            if keep_all_segments {
+                "0".into() // Unlimited: keep all segments in the playlist
             } else {
                 self.playlist_window_segments.to_string()
             },
@@ -1331,7 +1802,7 @@ impl HlsSessionManager {
             "-hls_segment_filename".into(),
             "seg_%03d.m4s".into(),
             "-hls_flags".into(),
-            if can_copy_video {
+            if keep_all_segments {
                 // Video-copy remuxes at disk speed (much faster than real-time).
                 // Keep all segments so the player can fetch from the start.
                 "independent_segments+temp_file".into()
@@ -1411,9 +1882,17 @@ impl HlsSessionManager {
         session.touch();
 
         let playlist_path = session.output_dir.join("playlist.m3u8");
-        let raw = tokio::fs::read_to_string(&playlist_path)
-            .await
-            .map_err(|e| anyhow!("Failed to read playlist: {}", e))?;
+        let raw = match &session.cached_prefix {
+            Some(run) => {
+                // FFmpeg may not have written its playlist yet, or may not be
+                // running at all when the cache covers the rest of the title.
+                let live = tokio::fs::read_to_string(&playlist_path).await.ok();
+                merge_cached_playlist(run, session.first_segment, live.as_deref())
+            }
+            None => tokio::fs::read_to_string(&playlist_path)
+                .await
+                .map_err(|e| anyhow!("Failed to read playlist: {}", e))?,
+        };
 
         let base_url = format!("/api/stream/{}/hls/{}", media_id, session.session_id);
         let token_suffix = token
@@ -1447,6 +1926,18 @@ impl HlsSessionManager {
 
         let path = session.output_dir.join(filename);
 
+        // Segments checked out of the cache are already on disk.
+        if let Some(run) = &session.cached_prefix {
+            let cached = filename == "cache_init.mp4"
+                || crate::segment_cache::parse_segment_index(filename).is_some_and(|index| {
+                    index >= session.first_segment
+                        && index < session.first_segment + run.segments.len() as u64
+                });
+            if cached {
+                return Ok(Some(path));
+            }
+        }
+
         // init.mp4 is written once before any segments — serve as soon as it exists.
         // Poll at 100ms (not 500ms) because FFmpeg writes init.mp4 almost immediately
         // and this is on the critical path for time-to-first-frame.
@@ -1497,6 +1988,7 @@ impl HlsSessionManager {
                             if let Ok(playlist) = tokio::fs::read_to_string(&playlist_path).await {
                                 if segment_listed_in_playlist(&playlist, filename) {
                                     session.refresh_segment_count();
+                                    self.store_in_segment_cache(session, playlist);
                                     return Ok(Some(path));
                                 }
                             }
@@ -1544,6 +2036,16 @@ impl HlsSessionManager {
         Ok(None)
     }
 
+    /// Hard-link the finished segments listed in `playlist` into the segment
+    /// cache in the background, if this session feeds one.
+    fn store_in_segment_cache(&self, session: &HlsSession, playlist: String) {
+        let (Some(cache), Some(key)) = (&self.segment_cache, &session.cache_key) else {
+            return;
+        };
+        let (cache, key, dir) = (cache.clone(), key.clone(), session.output_dir.clone());
+        tokio::task::spawn_blocking(move || cache.store(&key, &dir, &playlist));
+    }
+
     /// Get a session by ID.
     pub fn get_session(&self, session_id: &str) -> Option<Arc<HlsSession>> {
         self.sessions.get(session_id).map(|s| s.clone())
@@ -1584,6 +2086,7 @@ impl HlsSessionManager {
             info!("Initiated teardown for HLS session {}", session_id);
 
             let session_id_owned = session_id.to_string();
+            let segment_cache = self.segment_cache.clone();
             tokio::spawn(async move {
                 // Kill FFmpeg (SIGTERM → 2s → SIGKILL on Unix, immediate kill on Windows)
                 session.kill_ffmpeg().await;
@@ -1600,6 +2103,18 @@ impl HlsSessionManager {
                     tokio::time::sleep(std::time::Duration::from_millis(50)).await;
                 }
 
+                // Keep everything FFmpeg finished, including segments encoded
+                // ahead of the player, before the directory goes away.
+                if let (Some(cache), Some(key)) = (segment_cache, session.cache_key.clone()) {
+                    let playlist_path = session.output_dir.join("playlist.m3u8");
+                    if let Ok(playlist) = tokio::fs::read_to_string(&playlist_path).await {
+                        let dir = session.output_dir.clone();
+                        let _ =
+                            tokio::task::spawn_blocking(move || cache.store(&key, &dir, &playlist))
+                                .await;
+                    }
+                }
+
                 // Remove output directory
                 if session.output_dir.exists() {
                     if let Err(e) = tokio::fs::remove_dir_all(&session.output_dir).await {
@@ -1674,6 +2189,24 @@ impl HlsSessionManager {
                 info!("Cleaning up expired HLS session: {}", session_id);
                 self.destroy_session(&session_id).await;
             }
+
+            // Drop owner registrations whose sessions are gone.
+            let stale_owners: Vec<String> = self
+                .owner_users
+                .iter()
+                .filter(|entry| {
+                    !self.owner_has_sessions(entry.key())
+                        && entry.value().1.elapsed().as_secs() > self.session_timeout_secs
+                })
+                .map(|entry| entry.key().clone())
+                .collect();
+            for owner_key in stale_owners {
+                self.owner_users.remove(&owner_key);
+            }
+
+            self.terminations.retain(|_, notice| {
+                notice.terminated_at.elapsed().as_secs() <= self.session_timeout_secs
+            });
         }
     }
 }
@@ -1723,6 +2256,50 @@ fn rewrite_playlist(raw: &str, base_url: &str, token_suffix: &str) -> String {
     result
 }
 
+/// Build a session playlist from its cached run followed by FFmpeg's own
+/// playlist (`live`) for the segments after it. The run uses the cached init
+/// segment; FFmpeg's `#EXT-X-MAP` switches to its own for the rest.
+fn merge_cached_playlist(run: &CachedRun, first_segment: u64, live: Option<&str>) -> String {
+    let mut target = run
+        .segments
+        .iter()
+        .map(|s| s.1.ceil() as u64)
+        .max()
+        .unwrap_or(1);
+    let mut live_body = String::new();
+    for line in live.unwrap_or_default().lines() {
+        if let Some(t) = line.strip_prefix("#EXT-X-TARGETDURATION:") {
+            target = target.max(t.trim().parse().unwrap_or(0));
+        } else if line == "#EXTM3U"
+            || line.starts_with("#EXT-X-VERSION:")
+            || line.starts_with("#EXT-X-MEDIA-SEQUENCE:")
+            || line == "#EXT-X-INDEPENDENT-SEGMENTS"
+        {
+            continue;
+        } else {
+            live_body.push_str(line);
+            live_body.push('\n');
+        }
+    }
+
+    let mut playlist = format!(
+        "#EXTM3U\n#EXT-X-VERSION:7\n#EXT-X-TARGETDURATION:{}\n#EXT-X-MEDIA-SEQUENCE:{}\n#EXT-X-INDEPENDENT-SEGMENTS\n#EXT-X-MAP:URI=\"cache_init.mp4\"\n",
+        target, first_segment
+    );
+    for (index, duration) in &run.segments {
+        playlist.push_str(&format!(
+            "#EXTINF:{:.6},\n{}\n",
+            duration,
+            crate::segment_cache::segment_filename(*index)
+        ));
+    }
+    playlist.push_str(&live_body);
+    if run.complete {
+        playlist.push_str("#EXT-X-ENDLIST\n");
+    }
+    playlist
+}
+
 /// Check if a segment filename appears in the playlist's #EXTINF entries.
 /// A line sequence like:
 ///   #EXTINF:6.006,
@@ -1866,6 +2443,9 @@ mod tests {
             audio_codec_rfc6381: "mp4a.40.2".to_string(),
             video_copied: false,
             awaiting_promotion: AtomicBool::new(false),
+            first_segment: 0,
+            cached_prefix: None,
+            cache_key: None,
         })
     }
 
@@ -1908,6 +2488,35 @@ mod tests {
         let _ = std::fs::remove_dir_all(root);
     }
 
+    #[test]
+    fn cached_run_is_stitched_ahead_of_ffmpeg_output() {
+        let run = CachedRun {
+            segments: vec![(50, 2.002), (51, 2.002)],
+            complete: false,
+        };
+        let live = "#EXTM3U\n#EXT-X-VERSION:7\n#EXT-X-TARGETDURATION:3\n#EXT-X-MEDIA-SEQUENCE:52\n#EXT-X-INDEPENDENT-SEGMENTS\n#EXT-X-MAP:URI=\"init.mp4\"\n#EXTINF:2.002000,\nseg_052.m4s\n";
+        let merged = merge_cached_playlist(&run, 50, Some(live));
+        assert_eq!(merged.matches("#EXTM3U").count(), 1);
+        assert!(merged.contains("#EXT-X-TARGETDURATION:3\n"));
+        assert!(merged.contains("#EXT-X-MEDIA-SEQUENCE:50\n"));
+        let uris: Vec<_> = merged.lines().filter(|l| !l.starts_with('#')).collect();
+        assert_eq!(uris, vec!["seg_050.m4s", "seg_051.m4s", "seg_052.m4s"]);
+        let cache_map = merged.find("cache_init.mp4").unwrap();
+        let live_map = merged.find("URI=\"init.mp4\"").unwrap();
+        assert!(cache_map < merged.find("seg_050").unwrap());
+        assert!(live_map > merged.find("seg_051").unwrap());
+        assert!(!merged.contains("#EXT-X-ENDLIST"));
+
+        // A run reaching the end of the title needs no FFmpeg output
+        let complete = CachedRun {
+            complete: true,
+            ..run
+        };
+        assert!(
+            merge_cached_playlist(&complete, 50, None).ends_with("seg_051.m4s\n#EXT-X-ENDLIST\n")
+        );
+    }
+
     #[test]
     fn master_playlist_includes_codecs_metadata() {
         let root = test_temp_dir("master-codecs");
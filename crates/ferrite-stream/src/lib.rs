@@ -1,4 +1,7 @@
 pub mod compat;
 pub mod direct;
+pub mod download;
 pub mod hls;
+pub mod segment_cache;
+pub mod stack;
 pub mod transcode;
@@ -0,0 +1,674 @@
+//! Persistent cache of transcoded HLS segments.
+//!
+//! Session output under `cache_dir/hls` is deleted with its session. When the
+//! cache is enabled, finished segments of re-encoded variant sessions are also
+//! hard-linked into `{root}/{media_id}/{variant}-a{audio}-{fingerprint}/`, so a
+//! later session for the same media, variant and audio track can serve them
+//! again and only transcode the gaps. Segments are numbered on a fixed grid
+//! (`index * segment_duration`), which is what lets runs produced by different
+//! sessions line up.
+//!
+//! Each entry directory holds `init.mp4`, the `seg_NNN.m4s` files and an
+//! append-only `segments.txt` index (`<index> <duration>` lines plus an
+//! `end <index>` line once the last segment of the title has been seen).
+//! Whole entries are evicted least-recently-used first once the cache grows
+//! past its quota.
+
+use anyhow::Result;
+use std::collections::{BTreeMap, HashMap};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::SystemTime;
+use tracing::{debug, info, warn};
+
+const INDEX_FILE: &str = "segments.txt";
+const INIT_FILE: &str = "init.mp4";
+
+/// Identifies one cache entry: a media item encoded as one variant with one
+/// audio track and one set of encoder parameters.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SegmentCacheKey {
+    media_id: String,
+    entry: String,
+}
+
+impl SegmentCacheKey {
+    /// `params` describes everything else that changes the encoded output
+    /// (encoder, quality, bitrates, segment length, source file identity);
+    /// it is hashed into the entry name so a config change starts a new entry.
+    pub fn new(media_id: &str, variant_label: &str, audio_stream: u32, params: &str) -> Self {
+        Self {
+            media_id: sanitize(media_id),
+            entry: format!(
+                "{}-a{}-{:016x}",
+                sanitize(variant_label),
+                audio_stream,
+                fnv1a(params.as_bytes())
+            ),
+        }
+    }
+
+    fn rel_dir(&self) -> PathBuf {
+        Path::new(&self.media_id).join(&self.entry)
+    }
+}
+
+/// A contiguous run of cached segments checked out into a session directory.
+#[derive(Debug, Clone, Default)]
+pub struct CachedRun {
+    /// `(index, duration_secs)` of each segment, in order.
+    pub segments: Vec<(u64, f64)>,
+    /// True when the run reaches the last segment of the title.
+    pub complete: bool,
+}
+
+/// Cache usage for the admin API.
+#[derive(Debug, Clone, Copy)]
+pub struct SegmentCacheStats {
+    pub entries: usize,
+    pub segments: usize,
+    pub used_bytes: u64,
+    pub max_bytes: u64,
+}
+
+#[derive(Debug)]
+struct Entry {
+    segments: BTreeMap<u64, f64>,
+    end: Option<u64>,
+    init_hash: Option<u64>,
+    bytes: u64,
+    last_used: SystemTime,
+    /// Claimed by a store or an eviction working on the entry's files
+    /// outside the lock. Other stores skip a busy entry and eviction passes
+    /// it over.
+    busy: bool,
+}
+
+impl Entry {
+    fn new() -> Self {
+        Self {
+            segments: BTreeMap::new(),
+            end: None,
+            init_hash: None,
+            bytes: 0,
+            last_used: SystemTime::now(),
+            busy: false,
+        }
+    }
+
+    /// Whether a playlist lists segments or an end this entry doesn't have.
+    fn is_behind(entry: Option<&Self>, listed: &[(u64, f64)], end: Option<u64>) -> bool {
+        let has_new = listed
+            .iter()
+            .any(|(index, _)| entry.is_none_or(|e| !e.segments.contains_key(index)));
+        has_new || entry.is_some_and(|e| end.is_some() && e.end != end)
+    }
+}
+
+/// File work a store claimed under the lock.
+struct StorePlan {
+    /// Encoder output changed: wipe the entry's directory first.
+    reset: bool,
+    link_init: bool,
+    segments: Vec<(u64, f64)>,
+    end: Option<u64>,
+}
+
+/// What a store's file work added to its entry.
+#[derive(Default)]
+struct StoreOutcome {
+    init_linked: bool,
+    segments: Vec<(u64, f64)>,
+    bytes: u64,
+}
+
+pub struct SegmentCache {
+    root: PathBuf,
+    max_bytes: u64,
+    /// Entries keyed by their directory relative to `root`. Checkouts link
+    /// files under this lock; stores and evictions claim their entry with
+    /// [`Entry::busy`] and do their file work outside it.
+    entries: Mutex<HashMap<PathBuf, Entry>>,
+}
+
+impl SegmentCache {
+    /// Open (or create) the cache at `root`, rebuilding the index from disk and
+    /// evicting down to `max_bytes`. Blocking.
+    pub fn open(root: PathBuf, max_bytes: u64) -> Result<Self> {
+        std::fs::create_dir_all(&root)?;
+        let mut entries = HashMap::new();
+        for media_dir in std::fs::read_dir(&root)?.flatten() {
+            if !media_dir.path().is_dir() {
+                continue;
+            }
+            for entry_dir in std::fs::read_dir(media_dir.path())?.flatten() {
+                let path = entry_dir.path();
+                let rel = path.strip_prefix(&root)?.to_path_buf();
+                match load_entry(&path) {
+                    Some(entry) => {
+                        entries.insert(rel, entry);
+                    }
+                    None => {
+                        debug!(
+                            "Discarding incomplete segment cache entry {}",
+                            path.display()
+                        );
+                        let _ = std::fs::remove_dir_all(&path);
+                    }
+                }
+            }
+        }
+
+        let cache = Self {
+            root,
+            max_bytes,
+            entries: Mutex::new(entries),
+        };
+        let evicted = cache.evict_locked(&mut cache.entries.lock().unwrap(), None);
+        cache.remove_evicted(evicted);
+        let stats = cache.stats();
+        info!(
+            "Segment cache at {}: {} entries, {} MiB of {} MiB",
+            cache.root.display(),
+            stats.entries,
+            stats.used_bytes / (1024 * 1024),
+            cache.max_bytes / (1024 * 1024)
+        );
+        Ok(cache)
+    }
+
+    /// Hard-link the contiguous run of cached segments starting at `first`
+    /// into `dest` (as `cache_init.mp4` and `seg_NNN.m4s`). Returns `None`
+    /// when segment `first` isn't cached. Blocking.
+    pub fn checkout(&self, key: &SegmentCacheKey, first: u64, dest: &Path) -> Option<CachedRun> {
+        let rel = key.rel_dir();
+        let mut entries = self.entries.lock().unwrap();
+        let entry = entries.get_mut(&rel)?;
+        entry.init_hash?;
+        if !entry.segments.contains_key(&first) {
+            return None;
+        }
+
+        let dir = self.root.join(&rel);
+        if let Err(e) = link_or_copy(&dir.join(INIT_FILE), &dest.join("cache_init.mp4")) {
+            warn!("Failed to check out cached init segment: {}", e);
+            return None;
+        }
+        let mut run = CachedRun::default();
+        let mut next = first;
+        while let Some(&duration) = entry.segments.get(&next) {
+            let name = segment_filename(next);
+            if let Err(e) = link_or_copy(&dir.join(&name), &dest.join(&name)) {
+                warn!("Failed to check out cached segment {}: {}", name, e);
+                break;
+            }
+            run.segments.push((next, duration));
+            next += 1;
+        }
+        run.complete = entry.end.is_some_and(|end| next > end);
+        entry.last_used = SystemTime::now();
+        if let Ok(file) = std::fs::File::options()
+            .append(true)
+            .open(dir.join(INDEX_FILE))
+        {
+            let _ = file.set_modified(entry.last_used);
+        }
+        Some(run).filter(|r| !r.segments.is_empty())
+    }
+
+    /// Copy the finished segments listed in an FFmpeg playlist from
+    /// `session_dir` into the cache. Segments already cached are skipped, so
+    /// this is cheap to call every time a segment is served. The entry is
+    /// claimed under the lock and the files are linked outside it. Blocking.
+    pub fn store(&self, key: &SegmentCacheKey, session_dir: &Path, playlist: &str) {
+        let (listed, ended) = listed_segments(playlist);
+        let new_end = ended.then(|| listed.last().map(|s| s.0)).flatten();
+        let rel = key.rel_dir();
+        if !Entry::is_behind(self.entries.lock().unwrap().get(&rel), &listed, new_end) {
+            return;
+        }
+
+        let init_src = session_dir.join(INIT_FILE);
+        let init_hash = match std::fs::read(&init_src) {
+            Ok(bytes) => fnv1a(&bytes),
+            Err(_) => return,
+        };
+
+        let plan = {
+            let mut entries = self.entries.lock().unwrap();
+            let entry = entries.entry(rel.clone()).or_insert_with(Entry::new);
+            // Another store is already catching this entry up
+            if entry.busy {
+                return;
+            }
+            // Every cached segment must decode with the one cached init
+            // segment. If the encoder's output changed, start the entry over.
+            let reset = entry.init_hash.is_some_and(|h| h != init_hash);
+            if reset {
+                info!(
+                    "Encoder output changed for {}; resetting segment cache entry",
+                    rel.display()
+                );
+                *entry = Entry::new();
+            }
+            entry.busy = true;
+            StorePlan {
+                reset,
+                link_init: entry.init_hash.is_none(),
+                segments: listed
+                    .into_iter()
+                    .filter(|(index, _)| !entry.segments.contains_key(index))
+                    .collect(),
+                end: new_end.filter(|end| entry.end != Some(*end)),
+            }
+        };
+
+        let dir = self.root.join(&rel);
+        let outcome = store_files(&dir, session_dir, &plan);
+
+        let evicted = {
+            let mut entries = self.entries.lock().unwrap();
+            // Busy entries are never evicted, so it is still there
+            let entry = entries.get_mut(&rel).expect("claimed entry is present");
+            entry.busy = false;
+            if let Some(outcome) = outcome {
+                if outcome.init_linked {
+                    entry.init_hash = Some(init_hash);
+                }
+                entry.segments.extend(outcome.segments);
+                entry.bytes += outcome.bytes;
+                if plan.end.is_some() {
+                    entry.end = plan.end;
+                }
+                entry.last_used = SystemTime::now();
+            }
+            self.evict_locked(&mut entries, Some(&rel))
+        };
+        self.remove_evicted(evicted);
+    }
+
+    /// Remove every entry. Blocking.
+    pub fn clear(&self) -> Result<()> {
+        let mut entries = self.entries.lock().unwrap();
+        for rel in entries.keys() {
+            let _ = std::fs::remove_dir_all(self.root.join(rel));
+        }
+        entries.clear();
+        for media_dir in std::fs::read_dir(&self.root)?.flatten() {
+            let _ = std::fs::remove_dir_all(media_dir.path());
+        }
+        Ok(())
+    }
+
+    pub fn stats(&self) -> SegmentCacheStats {
+        let entries = self.entries.lock().unwrap();
+        SegmentCacheStats {
+            entries: entries.len(),
+            segments: entries.values().map(|e| e.segments.len()).sum(),
+            used_bytes: entries.values().map(|e| e.bytes).sum(),
+            max_bytes: self.max_bytes,
+        }
+    }
+
+    /// Pick least recently used entries to evict until the cache fits its
+    /// quota. `keep` (the entry just written) and busy entries are never
+    /// picked. Victims stay in the map as empty busy placeholders until
+    /// [`Self::remove_evicted`] has deleted their files.
+    fn evict_locked(
+        &self,
+        entries: &mut HashMap<PathBuf, Entry>,
+        keep: Option<&Path>,
+    ) -> Vec<PathBuf> {
+        let mut used: u64 = entries.values().map(|e| e.bytes).sum();
+        let mut victims = Vec::new();
+        while used > self.max_bytes {
+            let Some(victim) = entries
+                .iter()
+                .filter(|(rel, e)| Some(rel.as_path()) != keep && !e.busy)
+                .min_by_key(|(_, e)| e.last_used)
+                .map(|(rel, _)| rel.clone())
+            else {
+                break;
+            };
+            let entry = entries.get_mut(&victim).expect("victim is present");
+            used -= entry.bytes;
+            *entry = Entry {
+                busy: true,
+                ..Entry::new()
+            };
+            victims.push(victim);
+        }
+        victims
+    }
+
+    /// Delete the files of entries picked by [`Self::evict_locked`], then
+    /// drop their placeholders. Call without holding the lock.
+    fn remove_evicted(&self, victims: Vec<PathBuf>) {
+        if victims.is_empty() {
+            return;
+        }
+        for rel in &victims {
+            let dir = self.root.join(rel);
+            if let Err(e) = std::fs::remove_dir_all(&dir) {
+                warn!(
+                    "Failed to evict segment cache entry {}: {}",
+                    dir.display(),
+                    e
+                );
+            }
+            if let Some(media_dir) = dir.parent() {
+                // Only succeeds once the media directory is empty
+                let _ = std::fs::remove_dir(media_dir);
+            }
+            debug!("Evicted segment cache entry {}", rel.display());
+        }
+        let mut entries = self.entries.lock().unwrap();
+        for rel in victims {
+            entries.remove(&rel);
+        }
+    }
+}
+
+/// Link the files a [`StorePlan`] claimed from `session_dir` into the entry
+/// directory `dir` and append them to its index. `None` when the directory or
+/// the init segment couldn't be written, in which case nothing is added.
+fn store_files(dir: &Path, session_dir: &Path, plan: &StorePlan) -> Option<StoreOutcome> {
+    if plan.reset {
+        let _ = std::fs::remove_dir_all(dir);
+    }
+    if let Err(e) = std::fs::create_dir_all(dir) {
+        warn!(
+            "Failed to create segment cache dir {}: {}",
+            dir.display(),
+            e
+        );
+        return None;
+    }
+    let mut outcome = StoreOutcome::default();
+    if plan.link_init {
+        match link_or_copy(&session_dir.join(INIT_FILE), &dir.join(INIT_FILE)) {
+            Ok(size) => {
+                outcome.init_linked = true;
+                outcome.bytes += size;
+            }
+            Err(e) => {
+                warn!("Failed to cache init segment: {}", e);
+                return None;
+            }
+        }
+    }
+
+    let mut index_lines = String::new();
+    for &(index, duration) in &plan.segments {
+        let name = segment_filename(index);
+        match link_or_copy(&session_dir.join(&name), &dir.join(&name)) {
+            Ok(size) => {
+                outcome.segments.push((index, duration));
+                outcome.bytes += size;
+                index_lines.push_str(&format!("{index} {duration:.6}\n"));
+            }
+            // Deleted by FFmpeg's playlist window or not flushed yet
+            Err(e) => debug!("Skipping uncached segment {}: {}", name, e),
+        }
+    }
+    if let Some(end) = plan.end {
+        index_lines.push_str(&format!("end {end}\n"));
+    }
+    if !index_lines.is_empty() {
+        let written = std::fs::File::options()
+            .create(true)
+            .append(true)
+            .open(dir.join(INDEX_FILE))
+            .and_then(|mut f| f.write_all(index_lines.as_bytes()));
+        if let Err(e) = written {
+            warn!(
+                "Failed to update segment cache index in {}: {}",
+                dir.display(),
+                e
+            );
+        }
+    }
+    Some(outcome)
+}
+
+/// Segment file name for a grid index, matching FFmpeg's `seg_%03d.m4s`.
+pub fn segment_filename(index: u64) -> String {
+    format!("seg_{index:03}.m4s")
+}
+
+/// Parse a segment index out of a `seg_NNN.m4s` file name.
+pub fn parse_segment_index(filename: &str) -> Option<u64> {
+    filename
+        .strip_prefix("seg_")
+        .and_then(|s| s.strip_suffix(".m4s"))
+        .and_then(|n| n.parse().ok())
+}
+
+/// `(index, duration)` of every finished segment in an HLS playlist, and
+/// whether the playlist is ended.
+pub fn listed_segments(playlist: &str) -> (Vec<(u64, f64)>, bool) {
+    let mut segments = Vec::new();
+    let mut duration = None;
+    let mut ended = false;
+    for line in playlist.lines() {
+        let line = line.trim();
+        if let Some(rest) = line.strip_prefix("#EXTINF:") {
+            duration = rest.split(',').next().and_then(|d| d.parse::<f64>().ok());
+        } else if line == "#EXT-X-ENDLIST" {
+            ended = true;
+        } else if !line.starts_with('#') && !line.is_empty() {
+            if let (Some(d), Some(index)) = (duration.take(), parse_segment_index(line)) {
+                segments.push((index, d));
+            }
+        }
+    }
+    (segments, ended)
+}
+
+fn load_entry(dir: &Path) -> Option<Entry> {
+    let index = std::fs::read_to_string(dir.join(INDEX_FILE)).ok()?;
+    let init = std::fs::read(dir.join(INIT_FILE)).ok()?;
+    let mut entry = Entry::new();
+    entry.init_hash = Some(fnv1a(&init));
+    entry.bytes = init.len() as u64;
+    entry.last_used = std::fs::metadata(dir.join(INDEX_FILE))
+        .and_then(|m| m.modified())
+        .unwrap_or_else(|_| SystemTime::now());
+
+    for line in index.lines() {
+        let mut parts = line.split_whitespace();
+        match (parts.next(), parts.next()) {
+            (Some("end"), Some(end)) => entry.end = end.parse().ok(),
+            (Some(index), Some(duration)) => {
+                let (Ok(index), Ok(duration)) = (index.parse::<u64>(), duration.parse::<f64>())
+                else {
+                    continue;
+                };
+                // Trust the file on disk over the index
+                if let Ok(meta) = std::fs::metadata(dir.join(segment_filename(index))) {
+                    if entry.segments.insert(index, duration).is_none() {
+                        entry.bytes += meta.len();
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    (!entry.segments.is_empty()).then_some(entry)
+}
+
+/// Hard-link `src` to `dst` (copying across filesystems), replacing any
+/// existing `dst`. Returns the file size.
+fn link_or_copy(src: &Path, dst: &Path) -> std::io::Result<u64> {
+    let size = std::fs::metadata(src)?.len();
+    let _ = std::fs::remove_file(dst);
+    if std::fs::hard_link(src, dst).is_err() {
+        std::fs::copy(src, dst)?;
+    }
+    Ok(size)
+}
+
+fn sanitize(component: &str) -> String {
+    component
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// FNV-1a: stable across builds, unlike `DefaultHasher`.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, b| {
+        (hash ^ u64::from(*b)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "ferrite-segcache-{}-{}",
+            name,
+            uuid::Uuid::new_v4()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// Write an FFmpeg-style session directory with segments `first..first+count`.
+    fn fake_session(dir: &Path, first: u64, count: u64, ended: bool) -> String {
+        std::fs::write(dir.join("init.mp4"), b"init").unwrap();
+        let mut playlist = format!(
+            "#EXTM3U\n#EXT-X-VERSION:7\n#EXT-X-TARGETDURATION:2\n#EXT-X-MEDIA-SEQUENCE:{first}\n#EXT-X-MAP:URI=\"init.mp4\"\n"
+        );
+        for index in first..first + count {
+            let name = segment_filename(index);
+            std::fs::write(dir.join(&name), vec![0u8; 100]).unwrap();
+            playlist.push_str(&format!("#EXTINF:2.002000,\n{name}\n"));
+        }
+        if ended {
+            playlist.push_str("#EXT-X-ENDLIST\n");
+        }
+        playlist
+    }
+
+    #[test]
+    fn stored_runs_are_checked_out_contiguously_and_survive_reopen() {
+        let root = temp_dir("root");
+        let session = temp_dir("session");
+        let key = SegmentCacheKey::new("media-1", "720p", 0, "libx264|veryfast|23");
+
+        let cache = SegmentCache::open(root.clone(), 1 << 30).unwrap();
+        let playlist = fake_session(&session, 10, 3, false);
+        cache.store(&key, &session, &playlist);
+        assert_eq!(cache.stats().segments, 3);
+
+        // Nothing cached at the requested start
+        let dest = temp_dir("dest");
+        assert!(cache.checkout(&key, 0, &dest).is_none());
+
+        let run = cache.checkout(&key, 11, &dest).unwrap();
+        assert_eq!(
+            run.segments.iter().map(|s| s.0).collect::<Vec<_>>(),
+            vec![11, 12]
+        );
+        assert!(!run.complete);
+        assert!(dest.join("cache_init.mp4").exists());
+        assert!(dest.join("seg_012.m4s").exists());
+
+        // The end of the title makes a run complete
+        let tail = temp_dir("tail");
+        let playlist = fake_session(&tail, 13, 2, true);
+        cache.store(&key, &tail, &playlist);
+        drop(cache);
+
+        let reopened = SegmentCache::open(root, 1 << 30).unwrap();
+        let run = reopened.checkout(&key, 10, &temp_dir("dest2")).unwrap();
+        assert_eq!(run.segments.len(), 5);
+        assert!(run.complete);
+        assert!((run.segments[0].1 - 2.002).abs() < 1e-9);
+    }
+
+    #[test]
+    fn least_recently_used_entries_are_evicted_over_quota() {
+        let root = temp_dir("quota");
+        // Room for one entry (init + 3 segments = 304 bytes) but not two
+        let cache = SegmentCache::open(root, 500).unwrap();
+        let old = SegmentCacheKey::new("media-1", "720p", 0, "a");
+        let new = SegmentCacheKey::new("media-2", "720p", 0, "a");
+
+        let s1 = temp_dir("s1");
+        let playlist = fake_session(&s1, 0, 3, false);
+        cache.store(&old, &s1, &playlist);
+        let s2 = temp_dir("s2");
+        let playlist = fake_session(&s2, 0, 3, false);
+        cache.store(&new, &s2, &playlist);
+
+        let stats = cache.stats();
+        assert_eq!(stats.entries, 1);
+        assert!(stats.used_bytes <= stats.max_bytes);
+        assert!(cache.checkout(&old, 0, &temp_dir("d1")).is_none());
+        assert!(cache.checkout(&new, 0, &temp_dir("d2")).is_some());
+    }
+
+    #[test]
+    fn entries_being_stored_are_not_evicted() {
+        let root = temp_dir("busy");
+        let cache = SegmentCache::open(root, 500).unwrap();
+        let old = SegmentCacheKey::new("media-1", "720p", 0, "a");
+        let new = SegmentCacheKey::new("media-2", "720p", 0, "a");
+
+        let s1 = temp_dir("b1");
+        let playlist = fake_session(&s1, 0, 3, false);
+        cache.store(&old, &s1, &playlist);
+        // As if another store were still linking files into it
+        cache
+            .entries
+            .lock()
+            .unwrap()
+            .get_mut(&old.rel_dir())
+            .unwrap()
+            .busy = true;
+        let s2 = temp_dir("b2");
+        let playlist = fake_session(&s2, 0, 3, false);
+        cache.store(&new, &s2, &playlist);
+
+        assert_eq!(cache.stats().entries, 2);
+        assert!(cache.root.join(old.rel_dir()).join(INIT_FILE).exists());
+    }
+
+    #[test]
+    fn changed_encoder_output_resets_the_entry() {
+        let root = temp_dir("reset");
+        let cache = SegmentCache::open(root, 1 << 30).unwrap();
+        let key = SegmentCacheKey::new("media-1", "480p", 1, "a");
+
+        let s1 = temp_dir("r1");
+        let playlist = fake_session(&s1, 0, 4, false);
+        cache.store(&key, &s1, &playlist);
+
+        let s2 = temp_dir("r2");
+        let playlist = fake_session(&s2, 20, 1, false);
+        std::fs::write(s2.join("init.mp4"), b"different").unwrap();
+        cache.store(&key, &s2, &playlist);
+
+        assert_eq!(cache.stats().segments, 1);
+        assert!(cache.checkout(&key, 0, &temp_dir("r3")).is_none());
+    }
+
+    #[test]
+    fn keys_hash_encoder_params_and_sanitize_labels() {
+        let a = SegmentCacheKey::new("m", "1080p/hi", 0, "crf=23");
+        let b = SegmentCacheKey::new("m", "1080p/hi", 0, "crf=20");
+        assert_ne!(a, b);
+        assert!(a.entry.starts_with("1080p_hi-a0-"));
+    }
+}
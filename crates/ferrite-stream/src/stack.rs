@@ -0,0 +1,91 @@
+//! Multi-part ("stacked") playback.
+//!
+//! Movies split across several files (`cd1`, `cd2`, ...) are played as one
+//! item by writing an ffconcat list and feeding it to FFmpeg's concat demuxer.
+//! Every FFmpeg invocation builds its input with [`input_args`], which switches
+//! to the concat demuxer when handed a list instead of a media file.
+
+use std::path::{Path, PathBuf};
+
+/// Extension used for generated concat lists.
+pub const CONCAT_LIST_EXTENSION: &str = "ffconcat";
+
+/// Whether `path` points at a generated concat list rather than a media file.
+pub fn is_concat_list(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case(CONCAT_LIST_EXTENSION))
+}
+
+/// FFmpeg arguments that open `path` as the primary input.
+pub fn input_args(path: &Path) -> Vec<String> {
+    let mut args = Vec::with_capacity(6);
+    if is_concat_list(path) {
+        args.extend(["-f".into(), "concat".into(), "-safe".into(), "0".into()]);
+    }
+    args.extend(["-i".into(), path.to_string_lossy().to_string()]);
+    args
+}
+
+/// Render an ffconcat list for the given parts, in play order.
+pub fn render_concat_list(parts: &[PathBuf]) -> String {
+    let mut out = String::from("ffconcat version 1.0\n");
+    for part in parts {
+        // Single quotes are escaped by closing the quote, emitting an escaped
+        // quote, and reopening: ' -> '\''
+        let escaped = part.to_string_lossy().replace('\'', r"'\''");
+        out.push_str(&format!("file '{escaped}'\n"));
+    }
+    out
+}
+
+/// Write (or refresh) the concat list for a stacked item under `cache_dir`
+/// and return its path.
+pub async fn write_concat_list(
+    cache_dir: &Path,
+    media_id: &str,
+    parts: &[PathBuf],
+) -> std::io::Result<PathBuf> {
+    let dir = cache_dir.join("stacks");
+    tokio::fs::create_dir_all(&dir).await?;
+    let path = dir.join(format!("{media_id}.{CONCAT_LIST_EXTENSION}"));
+    tokio::fs::write(&path, render_concat_list(parts)).await?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn input_args_switch_to_concat_demuxer_for_lists() {
+        assert_eq!(
+            input_args(Path::new("/media/movie.mkv")),
+            vec!["-i", "/media/movie.mkv"]
+        );
+        assert_eq!(
+            input_args(Path::new("/cache/stacks/abc.ffconcat")),
+            vec![
+                "-f",
+                "concat",
+                "-safe",
+                "0",
+                "-i",
+                "/cache/stacks/abc.ffconcat"
+            ]
+        );
+    }
+
+    #[test]
+    fn concat_list_quotes_paths() {
+        let list = render_concat_list(&[
+            PathBuf::from("/media/Heat (1995) cd1.avi"),
+            PathBuf::from("/media/It's Heat cd2.avi"),
+        ]);
+        assert_eq!(
+            list,
+            "ffconcat version 1.0\n\
+             file '/media/Heat (1995) cd1.avi'\n\
+             file '/media/It'\\''s Heat cd2.avi'\n"
+        );
+    }
+}
@@ -1,6 +1,7 @@
 use axum::body::Body;
 use axum::http::{header, StatusCode};
 use axum::response::Response;
+use ferrite_core::config::EncodingConfig;
 use ferrite_transcode::hwaccel::EncoderProfile;
 use std::path::Path;
 use std::pin::Pin;
@@ -87,6 +88,11 @@ pub async fn find_keyframe_before(
     file_path: &Path,
     target_secs: f64,
 ) -> Option<f64> {
+    // ffprobe can't read a concat list directly; let FFmpeg's own seek handle it.
+    if crate::stack::is_concat_list(file_path) {
+        return None;
+    }
+
     // Use ffprobe to find keyframes near the target. We read packets around the
     // target time and look for the last keyframe at or before the target.
     let output = Command::new(ffprobe_path)
@@ -147,6 +153,7 @@ pub async fn serve_remux(
     pre_resolved_start_secs: Option<f64>,
     subtitle_path: Option<&Path>,
     encoder: &EncoderProfile,
+    audio_bitrate_kbps: u32,
     audio_stream_index: Option<u32>,
     video_codec: Option<&str>,
 ) -> Result<Response, StatusCode> {
@@ -161,7 +168,9 @@ pub async fn serve_remux(
             pre_resolved_start_secs,
             subtitle_path,
             encoder,
+            audio_bitrate_kbps,
             audio_stream_index,
+            None,
         )
         .await;
     }
@@ -207,8 +216,7 @@ pub async fn serve_remux(
         args.push("-noaccurate_seek".into());
     }
 
-    args.push("-i".into());
-    args.push(file_path.to_string_lossy().to_string());
+    args.extend(crate::stack::input_args(file_path));
 
     if actual_start > 0.1 {
         args.push("-avoid_negative_ts".into());
@@ -312,8 +320,10 @@ pub async fn serve_audio_transcode(
     start_secs: Option<f64>,
     pre_resolved_start_secs: Option<f64>,
     subtitle_path: Option<&Path>,
-    _encoder: &EncoderProfile,
+    encoder: &EncoderProfile,
+    audio_bitrate_kbps: u32,
     audio_stream_index: Option<u32>,
+    audio_filter: Option<&str>,
 ) -> Result<Response, StatusCode> {
     if !file_path.exists() {
         return Err(StatusCode::NOT_FOUND);
@@ -373,8 +383,7 @@ pub async fn serve_audio_transcode(
         args.push("-noaccurate_seek".into());
     }
 
-    args.push("-i".into());
-    args.push(file_path.to_string_lossy().to_string());
+    args.extend(crate::stack::input_args(file_path));
 
     // Ensure clean timestamps from the seek point
     if actual_start > 0.1 {
@@ -393,7 +402,7 @@ pub async fn serve_audio_transcode(
         // Note: subtitle filter requires CPU-side frames, so always use software encoder here.
         let sub_path_escaped = escape_ffmpeg_filter_path(&sub_path.to_string_lossy());
         args.extend(["-vf".into(), format!("subtitles={}", sub_path_escaped)]);
-        args.extend(EncoderProfile::software().video_encode_args());
+        args.extend(encoder.software_fallback().video_encode_args());
         info!(
             "Subtitle burn-in enabled (software encode): {}",
             sub_path.display()
@@ -403,11 +412,14 @@ pub async fn serve_audio_transcode(
     }
 
     // Audio: transcode to AAC stereo
+    if let Some(filter) = audio_filter {
+        args.extend(["-af".into(), filter.to_string()]);
+    }
     args.extend([
         "-c:a".into(),
         "aac".into(),
         "-b:a".into(),
-        "192k".into(),
+        format!("{audio_bitrate_kbps}k"),
         "-ac".into(),
         "2".into(),
     ]);
@@ -481,7 +493,8 @@ pub async fn serve_audio_transcode(
 /// Audio is transcoded to AAC stereo. Output is streamed as fragmented MP4.
 ///
 /// This is significantly more CPU-intensive than audio-only transcode since
-/// every video frame must be decoded and re-encoded.
+/// every video frame must be decoded and re-encoded. Sources taller than the
+/// library's `max_height` are scaled down to it.
 #[allow(clippy::too_many_arguments)]
 pub async fn serve_full_transcode(
     ffmpeg_path: &str,
@@ -492,10 +505,13 @@ pub async fn serve_full_transcode(
     pre_resolved_start_secs: Option<f64>,
     subtitle_path: Option<&Path>,
     encoder: &EncoderProfile,
+    encoding: &EncodingConfig,
+    source_height: Option<u32>,
     pixel_format: Option<&str>,
     audio_stream_index: Option<u32>,
     color_transfer: Option<&str>,
     color_primaries: Option<&str>,
+    audio_filter: Option<&str>,
 ) -> Result<Response, StatusCode> {
     if !file_path.exists() {
         return Err(StatusCode::NOT_FOUND);
@@ -533,7 +549,7 @@ pub async fn serve_full_transcode(
     let needs_full_software = subtitle_path.is_some() && encoder.is_hardware();
     let effective_encoder = if needs_full_software {
         info!("Subtitle burn-in active — falling back to software encoder");
-        EncoderProfile::software()
+        encoder.software_fallback()
     } else {
         encoder.clone()
     };
@@ -546,7 +562,9 @@ pub async fn serve_full_transcode(
     let needs_tonemap =
         is_high_bit && ferrite_transcode::tonemap::is_true_hdr(color_transfer, color_primaries);
 
-    let has_software_filters = subtitle_path.is_some() || is_high_bit || needs_tonemap;
+    let scale_height = max_height_scale(encoding.max_height, source_height);
+    let has_software_filters =
+        subtitle_path.is_some() || is_high_bit || needs_tonemap || scale_height.is_some();
 
     // HW-accelerated decoding args (before -i)
     if !needs_full_software {
@@ -559,8 +577,7 @@ pub async fn serve_full_transcode(
         args.push(format!("{:.3}", actual_start));
     }
 
-    args.push("-i".into());
-    args.push(file_path.to_string_lossy().to_string());
+    args.extend(crate::stack::input_args(file_path));
 
     if actual_start > 0.1 {
         args.push("-avoid_negative_ts".into());
@@ -582,7 +599,10 @@ pub async fn serve_full_transcode(
     if needs_tonemap {
         info!("True HDR detected (pix={}, transfer={:?}, primaries={:?}), applying tone-mapping (full transcode)",
             pixel_format.unwrap_or("unknown"), color_transfer, color_primaries);
-        vf_parts.push(ferrite_transcode::tonemap::tonemap_filter());
+        vf_parts.push(ferrite_transcode::tonemap::tonemap_filter_for(
+            color_transfer,
+            color_primaries,
+        ));
     } else if is_high_bit {
         info!("10-bit SDR detected (pix={}, transfer={:?}), applying bit-depth conversion only (full transcode)",
             pixel_format.unwrap_or("unknown"), color_transfer);
@@ -598,6 +618,14 @@ pub async fn serve_full_transcode(
         );
     }
 
+    if let Some(height) = scale_height {
+        info!(
+            "Scaling full transcode down to max_height {}p (source {:?}p)",
+            height, source_height
+        );
+        vf_parts.push(format!("scale=-2:{height}"));
+    }
+
     if !vf_parts.is_empty() {
         args.extend(["-vf".into(), vf_parts.join(",")]);
     }
@@ -611,11 +639,14 @@ pub async fn serve_full_transcode(
     }
 
     // Audio: transcode to AAC stereo
+    if let Some(filter) = audio_filter {
+        args.extend(["-af".into(), filter.to_string()]);
+    }
     args.extend([
         "-c:a".into(),
         "aac".into(),
         "-b:a".into(),
-        "192k".into(),
+        format!("{}k", encoding.audio_bitrate_kbps),
         "-ac".into(),
         "2".into(),
     ]);
@@ -676,6 +707,12 @@ pub async fn serve_full_transcode(
     Ok(builder.body(body).unwrap())
 }
 
+/// Height to scale a full transcode down to: `max_height` when the source is
+/// known to be taller, otherwise `None` (never upscale).
+fn max_height_scale(max_height: Option<u32>, source_height: Option<u32>) -> Option<u32> {
+    max_height.filter(|&max| source_height.is_some_and(|h| h > max))
+}
+
 /// Escape a file path for use in FFmpeg's `-vf subtitles=` filter.
 /// FFmpeg filter syntax uses `:`, `\`, `'`, and `[` as special characters.
 /// On Windows, paths contain `\` and `:` which must be escaped.
@@ -716,4 +753,13 @@ mod tests {
         let result = escape_ffmpeg_filter_path("/media/[Special]/file's.srt");
         assert_eq!(result, "'/media/\\[Special\\]/file\\'s.srt'");
     }
+
+    #[test]
+    fn test_max_height_scale_only_shrinks_taller_sources() {
+        assert_eq!(max_height_scale(Some(1080), Some(2160)), Some(1080));
+        assert_eq!(max_height_scale(Some(1080), Some(1080)), None);
+        assert_eq!(max_height_scale(Some(1080), Some(720)), None);
+        assert_eq!(max_height_scale(Some(1080), None), None);
+        assert_eq!(max_height_scale(None, Some(2160)), None);
+    }
 }
@@ -1,3 +1,4 @@
+use ferrite_core::config::EncodingConfig;
 use ferrite_stream::hls::HlsSessionManager;
 use ferrite_transcode::hwaccel::EncoderProfile;
 use std::collections::HashMap;
@@ -260,6 +261,9 @@ async fn same_media_different_owner_keys_do_not_interfere() {
             Some("h264"),
             None,
             None,
+            None,
+            None,
+            &EncodingConfig::default(),
             false,
         )
         .await
@@ -284,6 +288,9 @@ async fn same_media_different_owner_keys_do_not_interfere() {
             Some("h264"),
             None,
             None,
+            None,
+            None,
+            &EncodingConfig::default(),
             false,
         )
         .await
@@ -337,6 +344,10 @@ async fn startup_owner_key_creates_multiple_abr_variants() {
             Some("h264"),
             None,
             None,
+            None,
+            None,
+            None,
+            &EncodingConfig::default(),
         )
         .await
         .expect("create owner-keyed ABR sessions");
@@ -383,6 +394,10 @@ async fn abr_master_playlist_supports_variant_downgrade_under_throttle() {
             Some("h264"),
             None,
             None,
+            None,
+            None,
+            None,
+            &EncodingConfig::default(),
         )
         .await
         .expect("create ABR sessions");
@@ -442,6 +457,110 @@ async fn abr_master_playlist_supports_variant_downgrade_under_throttle() {
     manager.destroy_owner_sessions(&owner).await;
 }
 
+#[tokio::test]
+async fn scheduler_caps_and_trims_owner_variants() {
+    let env = TestEnv::new("abr-trim");
+    let manager = env.manager();
+
+    let media = env.media_file("abr-trim.mkv");
+    let owner = HlsSessionManager::owner_key("media-abr-trim", Some("playback-abr-trim"));
+
+    let sessions = manager
+        .create_variant_sessions_owned(
+            &owner,
+            "media-abr-trim",
+            &media,
+            Some(2400.0),
+            Some(1920),
+            Some(1080),
+            Some(8000),
+            0.0,
+            0.0,
+            None,
+            None,
+            None,
+            None,
+            Some("aac"),
+            Some("h264"),
+            None,
+            None,
+            None,
+            Some(2),
+            None,
+            &EncodingConfig::default(),
+        )
+        .await
+        .expect("create capped ABR sessions");
+    assert_eq!(sessions.len(), 2);
+    let lowest = sessions
+        .iter()
+        .min_by_key(|s| s.bandwidth_bps)
+        .unwrap()
+        .session_id
+        .clone();
+
+    manager.trim_owner_variants(&owner, 1).await;
+    let remaining = manager.get_variant_sessions_owned(&owner);
+    assert_eq!(remaining.len(), 1);
+    assert_eq!(remaining[0].session_id, lowest);
+
+    manager.destroy_owner_sessions(&owner).await;
+}
+
+#[tokio::test]
+async fn terminated_owner_leaves_a_notice_for_its_sessions() {
+    let env = TestEnv::new("terminate-owner");
+    let manager = env.manager();
+
+    let media = env.media_file("terminate.mkv");
+    let owner = HlsSessionManager::owner_key("media-term", Some("playback-term"));
+    manager.register_owner_user(&owner, "user-1");
+    let sessions = manager
+        .create_single_variant_session_owned(
+            &owner,
+            "media-term",
+            &media,
+            Some(600.0),
+            Some(1280),
+            Some(720),
+            Some(3000),
+            0.0,
+            0.0,
+            None,
+            None,
+            None,
+            None,
+            Some("aac"),
+            Some("h264"),
+            None,
+            None,
+            None,
+            None,
+            &EncodingConfig::default(),
+            false,
+        )
+        .await
+        .expect("create session");
+    let sid = sessions[0].session_id.clone();
+    assert_eq!(
+        manager.owner_of_session(&sid).as_deref(),
+        Some(owner.as_str())
+    );
+    let listed = manager.list_active_sessions();
+    assert_eq!(listed[0].user_id.as_deref(), Some("user-1"));
+
+    manager
+        .terminate_owner(&owner, Some("Maintenance".to_string()))
+        .await;
+
+    assert!(manager.get_session(&sid).is_none());
+    assert!(manager.active_owners_for_user("user-1").is_empty());
+    for key in [owner.as_str(), sid.as_str()] {
+        let notice = manager.termination_notice(key).expect("termination notice");
+        assert_eq!(notice.reason.as_deref(), Some("Maintenance"));
+    }
+}
+
 #[tokio::test]
 async fn get_or_create_session_reuses_nearby_start_and_recreates_far_start() {
     let env = TestEnv::new("seek-reuse-recreate");
@@ -1,3 +1,10 @@
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::process::Stdio;
+use std::time::Duration;
+use tracing::{debug, info};
+
 /// Audio codecs that browsers can play natively in HLS/fMP4 containers.
 /// When the source uses one of these, we can copy the audio stream instead of
 /// re-encoding to stereo AAC, preserving surround sound (5.1/7.1).
@@ -15,10 +22,210 @@ pub fn can_passthrough(audio_codec: &str) -> bool {
     PASSTHROUGH_CODECS.contains(&audio_codec.to_lowercase().as_str())
 }
 
+/// Integrated loudness target for normalization (LUFS). -16 is the usual
+/// target for streamed stereo programme audio.
+pub const TARGET_LUFS: f64 = -16.0;
+/// True-peak ceiling for normalization (dBTP).
+pub const TARGET_TRUE_PEAK: f64 = -1.5;
+/// Loudness range target for normalization (LU).
+pub const TARGET_LRA: f64 = 11.0;
+
+/// Optional loudness processing applied when audio is re-encoded to stereo AAC.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AudioProcessing {
+    /// EBU R128 loudness normalization (`loudnorm`).
+    pub normalize: bool,
+    /// Downmix surround with the centre (dialogue) channel weighted up.
+    pub dialogue_boost: bool,
+    /// Dynamic range compression so quiet dialogue and loud effects end up
+    /// closer together.
+    pub night_mode: bool,
+}
+
+impl AudioProcessing {
+    pub fn is_active(&self) -> bool {
+        self.normalize || self.dialogue_boost || self.night_mode
+    }
+}
+
+/// Loudness of an audio stream as measured by a `loudnorm` analysis pass,
+/// fed back into `loudnorm` for accurate linear normalization.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct LoudnessStats {
+    /// Integrated loudness (LUFS)
+    pub input_i: f64,
+    /// True peak (dBTP)
+    pub input_tp: f64,
+    /// Loudness range (LU)
+    pub input_lra: f64,
+    /// Gating threshold (LUFS)
+    pub input_thresh: f64,
+    pub target_offset: f64,
+}
+
+/// Stereo conversion at the head of every processing chain. With
+/// `dialogue_boost` and a 5.1 or 7.1 source, the centre channel is mixed in
+/// louder than the standard downmix; anything else gets the standard one.
+fn stereo_downmix(dialogue_boost: bool, channels: Option<u32>) -> &'static str {
+    // FFmpeg channel order: FL FR FC LFE then the surrounds (BL BR, then SL SR
+    // for 7.1). The LFE is dropped, as in the standard downmix.
+    match (dialogue_boost, channels) {
+        (true, Some(6)) => "pan=stereo|c0=0.35*c0+0.45*c2+0.2*c4|c1=0.35*c1+0.45*c2+0.2*c5",
+        (true, Some(8)) => {
+            "pan=stereo|c0=0.3*c0+0.45*c2+0.125*c4+0.125*c6|c1=0.3*c1+0.45*c2+0.125*c5+0.125*c7"
+        }
+        _ => "aformat=channel_layouts=stereo",
+    }
+}
+
+fn loudnorm_target() -> String {
+    format!("loudnorm=I={TARGET_LUFS}:TP={TARGET_TRUE_PEAK}:LRA={TARGET_LRA}")
+}
+
+/// Build the `-af` filter chain for `processing`, or `None` when nothing is
+/// enabled. `channels` is the source channel count; `measured` is a cached
+/// analysis of the standard stereo downmix.
+///
+/// Measured values only describe the plain downmix, so they're used (for
+/// linear, two-pass normalization) only when no boost or compression runs
+/// first; otherwise `loudnorm` normalizes dynamically in a single pass.
+pub fn filter_chain(
+    processing: &AudioProcessing,
+    channels: Option<u32>,
+    measured: Option<&LoudnessStats>,
+) -> Option<String> {
+    if !processing.is_active() {
+        return None;
+    }
+    let mut filters = vec![stereo_downmix(processing.dialogue_boost, channels).to_string()];
+    if processing.night_mode {
+        // Threshold 0.1 ≈ -20 dBFS; makeup gain recovers the quiet parts
+        filters.push("acompressor=threshold=0.1:ratio=4:attack=10:release=250:makeup=2".into());
+    }
+    if processing.normalize {
+        let plain_downmix = filters.len() == 1 && filters[0] == stereo_downmix(false, None);
+        match measured {
+            Some(m) if plain_downmix => filters.push(format!(
+                "{}:measured_I={:.2}:measured_TP={:.2}:measured_LRA={:.2}:measured_thresh={:.2}:offset={:.2}:linear=true",
+                loudnorm_target(),
+                m.input_i,
+                m.input_tp,
+                m.input_lra,
+                m.input_thresh,
+                m.target_offset,
+            )),
+            _ => filters.push(loudnorm_target()),
+        }
+        // loudnorm resamples to 192 kHz internally
+        filters.push("aresample=48000".into());
+    }
+    Some(filters.join(","))
+}
+
+/// FFmpeg arguments for a loudness analysis pass over one audio stream.
+pub fn loudnorm_analysis_args(input: &Path, audio_stream_index: Option<u32>) -> Vec<String> {
+    vec![
+        "-hide_banner".into(),
+        "-nostdin".into(),
+        "-i".into(),
+        input.to_string_lossy().into_owned(),
+        "-map".into(),
+        format!("0:a:{}", audio_stream_index.unwrap_or(0)),
+        "-vn".into(),
+        "-af".into(),
+        format!(
+            "{},{}:print_format=json",
+            stereo_downmix(false, None),
+            loudnorm_target()
+        ),
+        "-f".into(),
+        "null".into(),
+        "-".into(),
+    ]
+}
+
+/// Parse the JSON block `loudnorm` prints at the end of an analysis pass.
+pub fn parse_loudnorm_output(stderr: &str) -> Option<LoudnessStats> {
+    let start = stderr.rfind('{')?;
+    let end = start + stderr[start..].find('}')? + 1;
+    let json: serde_json::Value = serde_json::from_str(&stderr[start..end]).ok()?;
+    // Values are printed as strings; silence measures as "-inf"
+    let field = |name: &str| -> Option<f64> {
+        let value: f64 = json.get(name)?.as_str()?.trim().parse().ok()?;
+        value.is_finite().then_some(value)
+    };
+    Some(LoudnessStats {
+        input_i: field("input_i")?,
+        input_tp: field("input_tp")?,
+        input_lra: field("input_lra")?,
+        input_thresh: field("input_thresh")?,
+        target_offset: field("target_offset")?,
+    })
+}
+
+/// Shortest time an analysis pass is given before it is abandoned.
+const ANALYSIS_MIN_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Time allowed for analyzing a file of `duration_ms`: a quarter of its
+/// runtime, at least [`ANALYSIS_MIN_TIMEOUT`]. Audio decodes far faster than
+/// realtime, so a pass that takes longer is stuck on the file or its storage.
+pub fn analysis_timeout(duration_ms: Option<i64>) -> Duration {
+    let runtime = Duration::from_millis(duration_ms.unwrap_or(0).max(0) as u64);
+    (runtime / 4).max(ANALYSIS_MIN_TIMEOUT)
+}
+
+/// Run a loudness analysis pass. Decodes the whole stream, so callers run it
+/// in the background and cache the result. FFmpeg is killed if the pass
+/// takes longer than `timeout`.
+pub async fn analyze_loudness(
+    ffmpeg_path: &str,
+    input: &Path,
+    audio_stream_index: Option<u32>,
+    timeout: Duration,
+) -> Result<LoudnessStats> {
+    let args = loudnorm_analysis_args(input, audio_stream_index);
+    debug!("loudness analysis args: {:?}", args);
+    let child = tokio::process::Command::new(ffmpeg_path)
+        .args(&args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()?;
+    let output = tokio::time::timeout(timeout, child.wait_with_output())
+        .await
+        .map_err(|_| anyhow!("loudness analysis timed out after {}s", timeout.as_secs()))??;
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    if !output.status.success() {
+        let tail: String = stderr.lines().rev().take(3).collect::<Vec<_>>().join(" | ");
+        return Err(anyhow!("loudness analysis failed: {}", tail));
+    }
+    let stats = parse_loudnorm_output(&stderr)
+        .ok_or_else(|| anyhow!("loudness analysis produced no measurement"))?;
+    info!(
+        "Measured {}: {:.1} LUFS, {:.1} dBTP, LRA {:.1}",
+        input.display(),
+        stats.input_i,
+        stats.input_tp,
+        stats.input_lra
+    );
+    Ok(stats)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn analysis_timeout_scales_with_runtime() {
+        assert_eq!(analysis_timeout(None), ANALYSIS_MIN_TIMEOUT);
+        assert_eq!(analysis_timeout(Some(60_000)), ANALYSIS_MIN_TIMEOUT);
+        assert_eq!(
+            analysis_timeout(Some(4 * 3_600_000)),
+            Duration::from_secs(3_600)
+        );
+    }
+
     #[test]
     fn aac_passthrough() {
         assert!(can_passthrough("aac"));
@@ -59,4 +266,77 @@ mod tests {
     fn truehd_no_passthrough() {
         assert!(!can_passthrough("truehd"));
     }
+
+    #[test]
+    fn no_processing_means_no_filter() {
+        assert_eq!(
+            filter_chain(&AudioProcessing::default(), Some(6), None),
+            None
+        );
+    }
+
+    #[test]
+    fn dialogue_boost_weights_centre_for_surround_only() {
+        let boost = AudioProcessing {
+            dialogue_boost: true,
+            ..Default::default()
+        };
+        let surround = filter_chain(&boost, Some(6), None).unwrap();
+        assert!(surround.starts_with("pan=stereo|c0=0.35*c0+0.45*c2"));
+        let stereo = filter_chain(&boost, Some(2), None).unwrap();
+        assert_eq!(stereo, "aformat=channel_layouts=stereo");
+    }
+
+    #[test]
+    fn normalization_uses_measurements_only_for_the_plain_downmix() {
+        let stats = LoudnessStats {
+            input_i: -27.5,
+            input_tp: -4.0,
+            input_lra: 18.0,
+            input_thresh: -38.0,
+            target_offset: 0.3,
+        };
+        let normalize = AudioProcessing {
+            normalize: true,
+            ..Default::default()
+        };
+        let linear = filter_chain(&normalize, Some(6), Some(&stats)).unwrap();
+        assert!(linear.contains("measured_I=-27.50"));
+        assert!(linear.contains("linear=true"));
+        assert!(linear.ends_with("aresample=48000"));
+
+        let night = AudioProcessing {
+            normalize: true,
+            night_mode: true,
+            ..Default::default()
+        };
+        let dynamic = filter_chain(&night, Some(6), Some(&stats)).unwrap();
+        assert!(dynamic.contains("acompressor="));
+        assert!(!dynamic.contains("measured_I"));
+    }
+
+    #[test]
+    fn parses_loudnorm_json() {
+        let stderr = r#"size=N/A time=01:45:12.00 bitrate=N/A speed= 412x
+[Parsed_loudnorm_1 @ 0x55d5c8a0] 
+{
+	"input_i" : "-27.61",
+	"input_tp" : "-4.47",
+	"input_lra" : "18.06",
+	"input_thresh" : "-38.33",
+	"output_i" : "-16.58",
+	"output_tp" : "-1.50",
+	"output_lra" : "14.78",
+	"output_thresh" : "-27.71",
+	"normalization_type" : "dynamic",
+	"target_offset" : "0.58"
+}
+"#;
+        let stats = parse_loudnorm_output(stderr).unwrap();
+        assert_eq!(stats.input_i, -27.61);
+        assert_eq!(stats.target_offset, 0.58);
+
+        let silent = stderr.replace("\"-27.61\"", "\"-inf\"");
+        assert_eq!(parse_loudnorm_output(&silent), None);
+    }
 }
@@ -39,6 +39,10 @@ pub struct EncoderProfile {
     pub encoder_args: Vec<String>,
     /// Whether hardware decoding should be used (hwaccel input flag)
     pub hw_decode_args: Vec<String>,
+    /// Configured x264-style preset. Also used by the software fallback.
+    pub preset: String,
+    /// Configured constant quality (CRF or the encoder's equivalent).
+    pub crf: u8,
 }
 
 impl EncoderProfile {
@@ -60,6 +64,8 @@ impl EncoderProfile {
                 "yuv420p".into(),
             ],
             hw_decode_args: vec![],
+            preset: "veryfast".into(),
+            crf: 23,
         }
     }
 
@@ -90,6 +96,8 @@ impl EncoderProfile {
                 "-hwaccel_output_format".into(),
                 "cuda".into(),
             ],
+            preset: "veryfast".into(),
+            crf: 23,
         }
     }
 
@@ -114,6 +122,8 @@ impl EncoderProfile {
                 "-hwaccel_output_format".into(),
                 "qsv".into(),
             ],
+            preset: "veryfast".into(),
+            crf: 23,
         }
     }
 
@@ -138,9 +148,40 @@ impl EncoderProfile {
                 "-vaapi_device".into(),
                 "/dev/dri/renderD128".into(),
             ],
+            preset: "veryfast".into(),
+            crf: 23,
         }
     }
 
+    /// Apply a configured preset and quality factor. The quality factor
+    /// replaces each backend's own knob (`-crf`, `-cq`, `-global_quality`,
+    /// `-qp`); the preset only applies to libx264 and QSV, which share
+    /// x264's preset names.
+    pub fn with_quality(&self, preset: &str, crf: u8) -> Self {
+        let mut profile = self.clone();
+        profile.preset = preset.to_string();
+        profile.crf = crf;
+        let takes_preset = matches!(self.backend, HwAccelBackend::Software | HwAccelBackend::Qsv);
+        let mut args = profile.encoder_args.iter_mut();
+        while let Some(arg) = args.next() {
+            let replacement = match arg.as_str() {
+                "-crf" | "-cq" | "-global_quality" | "-qp" => crf.to_string(),
+                "-preset" if takes_preset => preset.to_string(),
+                _ => continue,
+            };
+            if let Some(value) = args.next() {
+                *value = replacement;
+            }
+        }
+        profile
+    }
+
+    /// The software encoder with this profile's configured quality, for when
+    /// filters need CPU-side frames.
+    pub fn software_fallback(&self) -> Self {
+        Self::software().with_quality(&self.preset, self.crf)
+    }
+
     /// Get the FFmpeg args to set the video encoder (placed after -map).
     /// Returns: ["-c:v", "<encoder>", ...encoder_args]
     /// Builds the vec by iterating encoder_args by reference to avoid a full clone.
@@ -328,6 +369,30 @@ mod tests {
         assert!(!profile.is_hardware());
     }
 
+    #[test]
+    fn test_with_quality_rewrites_encoder_args() {
+        let profile = EncoderProfile::software().with_quality("slow", 18);
+        let args = profile.video_encode_args();
+        let value_after = |flag: &str| {
+            let i = args.iter().position(|a| a == flag).unwrap();
+            args[i + 1].clone()
+        };
+        assert_eq!(value_after("-preset"), "slow");
+        assert_eq!(value_after("-crf"), "18");
+
+        // NVENC keeps its own preset scale but takes the quality factor,
+        // and its software fallback inherits both settings
+        let nvenc = EncoderProfile::nvenc().with_quality("slow", 20);
+        let args = nvenc.video_encode_args();
+        let i = args.iter().position(|a| a == "-preset").unwrap();
+        assert_eq!(args[i + 1], "p4");
+        let i = args.iter().position(|a| a == "-cq").unwrap();
+        assert_eq!(args[i + 1], "20");
+        let fallback = nvenc.software_fallback();
+        assert_eq!(fallback.encoder_name, "libx264");
+        assert_eq!((fallback.preset.as_str(), fallback.crf), ("slow", 20));
+    }
+
     #[test]
     fn test_nvenc_profile_args() {
         let profile = EncoderProfile::nvenc();
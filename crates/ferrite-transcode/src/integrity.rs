@@ -0,0 +1,462 @@
+//! Decode-based integrity checks for media files.
+//!
+//! Corrupt or truncated files otherwise only show up when FFmpeg dies in the
+//! middle of a playback. A check decodes the file (or a few windows of it)
+//! with FFmpeg's error detection turned up, counts the errors it reports and
+//! compares how much decoded against the duration the probe recorded. A
+//! decode that runs far slower than real time is stopped and the file is
+//! reported broken.
+
+use anyhow::{Context, Result};
+use ferrite_core::config::IntegrityMode;
+use serde::Serialize;
+use std::path::Path;
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, BufReader};
+use tracing::debug;
+
+/// Seconds decoded per window in sample mode.
+const SAMPLE_WINDOW_SECS: f64 = 10.0;
+
+/// Start of each sample window as a fraction of the duration. A final window
+/// always ends at the reported end of the file.
+const SAMPLE_POINTS: &[f64] = &[0.0, 0.25, 0.5, 0.75];
+
+/// Files shorter than this are decoded completely even in sample mode.
+const SAMPLE_MIN_DURATION_SECS: f64 = 90.0;
+
+/// A decode may take this many times the media time it covers before it is
+/// stopped, allowing for slow software decodes of 4K sources.
+const DECODE_TIMEOUT_FACTOR: f64 = 2.0;
+
+/// Shortest decode timeout, covering FFmpeg start-up and seeking.
+const DECODE_TIMEOUT_MIN: Duration = Duration::from_secs(60);
+
+/// Distinct error messages kept in a report.
+const MAX_SUMMARY_ERRORS: usize = 10;
+
+/// A file whose decode falls short of its reported duration by more than
+/// this fraction (and at least `TRUNCATION_MIN_MS`) is truncated.
+const TRUNCATION_TOLERANCE: f64 = 0.02;
+const TRUNCATION_MIN_MS: u64 = 5_000;
+
+/// Outcome of an integrity check.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HealthStatus {
+    /// Decoded without errors.
+    Ok,
+    /// Decodes, but FFmpeg reported errors (glitches, dropped frames).
+    Damaged,
+    /// Can't be played through: no duration, fails to decode or ends early.
+    Broken,
+}
+
+impl HealthStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Ok => "ok",
+            Self::Damaged => "damaged",
+            Self::Broken => "broken",
+        }
+    }
+}
+
+/// Result of checking one file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct IntegrityReport {
+    pub status: HealthStatus,
+    pub mode: IntegrityMode,
+    /// Error lines FFmpeg printed, including repeats.
+    pub error_count: u64,
+    /// Distinct errors in the order they were first seen, at most ten.
+    pub errors: Vec<String>,
+    /// Media time that decoded, summed over all windows.
+    pub decoded_ms: u64,
+}
+
+/// FFmpeg arguments that decode `input` (from `start_secs`, for
+/// `duration_secs` when given) to the null muxer with error detection on.
+/// Errors go to stderr and decode progress to stdout.
+pub fn decode_args(
+    input: &Path,
+    start_secs: Option<f64>,
+    duration_secs: Option<f64>,
+) -> Vec<String> {
+    let mut args: Vec<String> = vec![
+        "-hide_banner".into(),
+        "-nostdin".into(),
+        "-v".into(),
+        "error".into(),
+        "-err_detect".into(),
+        "crccheck+bitstream+buffer".into(),
+    ];
+    if let Some(start) = start_secs.filter(|s| *s > 0.0) {
+        args.extend(["-ss".into(), format!("{start:.3}")]);
+    }
+    args.extend(["-i".into(), input.to_string_lossy().into_owned()]);
+    if let Some(duration) = duration_secs {
+        args.extend(["-t".into(), format!("{duration:.3}")]);
+    }
+    args.extend([
+        "-map".into(),
+        "0:v:0?".into(),
+        "-map".into(),
+        "0:a?".into(),
+        "-progress".into(),
+        "pipe:1".into(),
+        "-nostats".into(),
+        "-f".into(),
+        "null".into(),
+        "-".into(),
+    ]);
+    args
+}
+
+/// Media time decoded so far, from FFmpeg `-progress` output. Both
+/// `out_time_us` and (despite its name) `out_time_ms` are in microseconds.
+pub fn parse_decoded_ms(progress: &str) -> u64 {
+    progress
+        .lines()
+        .filter_map(|line| {
+            line.strip_prefix("out_time_us=")
+                .or_else(|| line.strip_prefix("out_time_ms="))
+        })
+        .filter_map(|value| value.trim().parse::<i64>().ok())
+        .filter(|us| *us > 0)
+        .max()
+        .map_or(0, |us| us as u64 / 1000)
+}
+
+/// Error messages collected from FFmpeg's stderr.
+#[derive(Debug, Default)]
+struct ErrorLog {
+    count: u64,
+    distinct: Vec<String>,
+}
+
+impl ErrorLog {
+    fn push(&mut self, line: &str) {
+        let line = line.trim();
+        if line.is_empty() {
+            return;
+        }
+        self.count += 1;
+        let message = strip_context_address(line);
+        if self.distinct.len() < MAX_SUMMARY_ERRORS && !self.distinct.contains(&message) {
+            self.distinct.push(message);
+        }
+    }
+}
+
+/// `[h264 @ 0x55d0c3a1e2c0] error while decoding MB 12 34` →
+/// `[h264] error while decoding MB 12 34`, so repeats of one error from
+/// different decoder instances collapse into one.
+fn strip_context_address(line: &str) -> String {
+    match (line.find(" @ 0x"), line.find(']')) {
+        (Some(at), Some(close)) if at < close => format!("{}{}", &line[..at], &line[close..]),
+        _ => line.to_string(),
+    }
+}
+
+/// One FFmpeg decode run.
+struct DecodeRun {
+    success: bool,
+    decoded_ms: u64,
+    /// Stopped after the timeout; `decoded_ms` is then 0.
+    timed_out: bool,
+}
+
+/// How long decoding `length_secs` of media may take.
+pub fn decode_timeout(length_secs: f64) -> Duration {
+    Duration::from_secs_f64(length_secs.max(0.0) * DECODE_TIMEOUT_FACTOR).max(DECODE_TIMEOUT_MIN)
+}
+
+async fn decode(
+    ffmpeg_path: &str,
+    args: &[String],
+    timeout: Duration,
+    errors: &mut ErrorLog,
+) -> Result<DecodeRun> {
+    debug!("integrity decode args: {:?}", args);
+    let mut child = tokio::process::Command::new(ffmpeg_path)
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .with_context(|| format!("failed to run {ffmpeg_path}"))?;
+    let mut stdout = child.stdout.take().context("ffmpeg stdout not captured")?;
+    let stderr = child.stderr.take().context("ffmpeg stderr not captured")?;
+
+    // Badly damaged files can print an error per frame, so stderr is read
+    // line by line instead of being buffered whole.
+    let read_errors = async {
+        let mut lines = BufReader::new(stderr).lines();
+        while let Ok(Some(line)) = lines.next_line().await {
+            errors.push(&line);
+        }
+    };
+    let read_progress = async {
+        let mut progress = String::new();
+        let _ = stdout.read_to_string(&mut progress).await;
+        progress
+    };
+    let run = async {
+        let ((), progress) = tokio::join!(read_errors, read_progress);
+        let status = child.wait().await?;
+        anyhow::Ok((status, progress))
+    };
+    // On timeout the child is killed when it is dropped
+    match tokio::time::timeout(timeout, run).await {
+        Ok(result) => {
+            let (status, progress) = result?;
+            Ok(DecodeRun {
+                success: status.success(),
+                decoded_ms: parse_decoded_ms(&progress),
+                timed_out: false,
+            })
+        }
+        Err(_) => Ok(DecodeRun {
+            success: false,
+            decoded_ms: 0,
+            timed_out: true,
+        }),
+    }
+}
+
+/// Windows decoded for a file of `duration_secs`: `(start, length)` pairs.
+/// `None` length decodes to the end of the file.
+pub fn decode_windows(mode: IntegrityMode, duration_secs: f64) -> Vec<(f64, Option<f64>)> {
+    if mode == IntegrityMode::Full || duration_secs < SAMPLE_MIN_DURATION_SECS {
+        return vec![(0.0, None)];
+    }
+    let mut windows: Vec<(f64, Option<f64>)> = SAMPLE_POINTS
+        .iter()
+        .map(|p| (duration_secs * p, Some(SAMPLE_WINDOW_SECS)))
+        .collect();
+    windows.push((
+        (duration_secs - SAMPLE_WINDOW_SECS * 1.5).max(0.0),
+        Some(SAMPLE_WINDOW_SECS),
+    ));
+    windows
+}
+
+/// Whether `decoded_ms` falls short of what the windows should have covered.
+fn is_truncated(expected_ms: u64, decoded_ms: u64) -> bool {
+    let tolerance = ((expected_ms as f64 * TRUNCATION_TOLERANCE) as u64).max(TRUNCATION_MIN_MS);
+    decoded_ms + tolerance < expected_ms
+}
+
+/// Check `input`, whose probe reported `duration_ms`.
+///
+/// Errors are only returned when FFmpeg itself can't be run; problems with
+/// the file are reported as [`HealthStatus::Broken`] or
+/// [`HealthStatus::Damaged`].
+pub async fn check_file(
+    ffmpeg_path: &str,
+    input: &Path,
+    duration_ms: Option<i64>,
+    mode: IntegrityMode,
+) -> Result<IntegrityReport> {
+    let duration_ms = match duration_ms {
+        Some(ms) if ms > 0 => ms as u64,
+        // Nothing to compare a decode against, and players can't seek it.
+        _ => {
+            return Ok(IntegrityReport {
+                status: HealthStatus::Broken,
+                mode,
+                error_count: 0,
+                errors: vec!["probe reported no duration".into()],
+                decoded_ms: 0,
+            })
+        }
+    };
+    if !input.exists() {
+        return Ok(IntegrityReport {
+            status: HealthStatus::Broken,
+            mode,
+            error_count: 0,
+            errors: vec!["file is missing".into()],
+            decoded_ms: 0,
+        });
+    }
+
+    let duration_secs = duration_ms as f64 / 1000.0;
+    let windows = decode_windows(mode, duration_secs);
+    let tail = windows.len() - 1;
+    let mut errors = ErrorLog::default();
+    let mut decoded_ms = 0;
+    let mut failed = false;
+    let mut truncated_at = None;
+    let mut timed_out = None;
+
+    for (i, (start, length)) in windows.into_iter().enumerate() {
+        let args = decode_args(input, Some(start), length);
+        let timeout = decode_timeout(length.unwrap_or(duration_secs));
+        let run = decode(ffmpeg_path, &args, timeout, &mut errors).await?;
+        if run.timed_out {
+            timed_out = Some(timeout);
+            break;
+        }
+        decoded_ms += run.decoded_ms;
+        failed |= !run.success && run.decoded_ms == 0;
+
+        // The last window ends at the reported end of the file; a truncated
+        // file runs out of frames before it does.
+        if i == tail {
+            let expected_ms = match length {
+                Some(secs) => (secs * 1000.0) as u64,
+                None => duration_ms,
+            };
+            if is_truncated(expected_ms, run.decoded_ms) {
+                truncated_at = Some((start * 1000.0) as u64 + run.decoded_ms);
+            }
+        }
+    }
+
+    let mut summary = Vec::new();
+    if let Some(timeout) = timed_out {
+        summary.push(format!(
+            "decoding took longer than {}",
+            format_ms(timeout.as_millis() as u64)
+        ));
+    }
+    if failed {
+        summary.push("ffmpeg could not decode the file".to_string());
+    }
+    if let Some(end_ms) = truncated_at {
+        summary.push(format!(
+            "decoding stops at {} of a reported {}",
+            format_ms(end_ms),
+            format_ms(duration_ms)
+        ));
+    }
+    let status = if !summary.is_empty() {
+        HealthStatus::Broken
+    } else if errors.count > 0 {
+        HealthStatus::Damaged
+    } else {
+        HealthStatus::Ok
+    };
+    summary.extend(errors.distinct);
+    summary.truncate(MAX_SUMMARY_ERRORS);
+
+    Ok(IntegrityReport {
+        status,
+        mode,
+        error_count: errors.count,
+        errors: summary,
+        decoded_ms,
+    })
+}
+
+fn format_ms(ms: u64) -> String {
+    let secs = ms / 1000;
+    format!("{}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_args_seek_before_input_and_limit_after() {
+        let args = decode_args(Path::new("/m/a.mkv"), Some(120.0), Some(10.0));
+        let ss = args.iter().position(|a| a == "-ss").unwrap();
+        let input = args.iter().position(|a| a == "-i").unwrap();
+        let t = args.iter().position(|a| a == "-t").unwrap();
+        assert!(ss < input && input < t);
+        assert_eq!(args[ss + 1], "120.000");
+        assert!(args.ends_with(&["-f".into(), "null".into(), "-".into()]));
+
+        let full = decode_args(Path::new("/m/a.mkv"), Some(0.0), None);
+        assert!(!full.contains(&"-ss".to_string()));
+        assert!(!full.contains(&"-t".to_string()));
+    }
+
+    #[test]
+    fn decoded_time_is_the_last_progress_report() {
+        let progress = "frame=10\nout_time_us=400000\nprogress=continue\n\
+                        frame=240\nout_time_us=10010000\nout_time_ms=10010000\nprogress=end\n";
+        assert_eq!(parse_decoded_ms(progress), 10_010);
+        assert_eq!(parse_decoded_ms("out_time_us=N/A\n"), 0);
+        assert_eq!(parse_decoded_ms(""), 0);
+    }
+
+    #[test]
+    fn repeated_errors_collapse_across_decoder_instances() {
+        let mut log = ErrorLog::default();
+        log.push("[h264 @ 0x55d0c3a1e2c0] error while decoding MB 12 34, bytestream -5");
+        log.push("[h264 @ 0x55d0c3b00000] error while decoding MB 12 34, bytestream -5");
+        log.push("");
+        log.push("[aac @ 0x1] Input buffer exhausted before END element found");
+        assert_eq!(log.count, 3);
+        assert_eq!(
+            log.distinct,
+            vec![
+                "[h264] error while decoding MB 12 34, bytestream -5",
+                "[aac] Input buffer exhausted before END element found",
+            ]
+        );
+    }
+
+    #[test]
+    fn sample_windows_end_at_the_reported_end() {
+        let windows = decode_windows(IntegrityMode::Sample, 3600.0);
+        assert_eq!(windows.len(), SAMPLE_POINTS.len() + 1);
+        assert_eq!(windows[0], (0.0, Some(SAMPLE_WINDOW_SECS)));
+        assert_eq!(windows[4], (3585.0, Some(SAMPLE_WINDOW_SECS)));
+
+        assert_eq!(
+            decode_windows(IntegrityMode::Full, 3600.0),
+            vec![(0.0, None)]
+        );
+        assert_eq!(
+            decode_windows(IntegrityMode::Sample, 30.0),
+            vec![(0.0, None)]
+        );
+    }
+
+    #[test]
+    fn decode_timeouts_scale_with_the_decoded_length() {
+        assert_eq!(decode_timeout(SAMPLE_WINDOW_SECS), DECODE_TIMEOUT_MIN);
+        assert_eq!(decode_timeout(7200.0), Duration::from_secs(14_400));
+        assert_eq!(decode_timeout(-1.0), DECODE_TIMEOUT_MIN);
+    }
+
+    #[test]
+    fn truncation_allows_for_rounding_at_the_end() {
+        assert!(!is_truncated(10_000, 9_960));
+        assert!(!is_truncated(7_200_000, 7_100_000));
+        assert!(is_truncated(7_200_000, 3_000_000));
+        assert!(is_truncated(10_000, 0));
+    }
+
+    #[tokio::test]
+    async fn missing_duration_or_file_is_broken_without_decoding() {
+        let no_duration = check_file(
+            "missing-ffmpeg",
+            Path::new("/nonexistent/a.mkv"),
+            Some(0),
+            IntegrityMode::Sample,
+        )
+        .await
+        .unwrap();
+        assert_eq!(no_duration.status, HealthStatus::Broken);
+        assert_eq!(no_duration.errors, vec!["probe reported no duration"]);
+
+        let missing = check_file(
+            "missing-ffmpeg",
+            Path::new("/nonexistent/a.mkv"),
+            Some(60_000),
+            IntegrityMode::Full,
+        )
+        .await
+        .unwrap();
+        assert_eq!(missing.status, HealthStatus::Broken);
+        assert_eq!(missing.errors, vec!["file is missing"]);
+    }
+}
@@ -1,5 +1,6 @@
 pub mod audio;
 pub mod hwaccel;
+pub mod integrity;
 pub mod thumbnails;
 pub mod tonemap;
 pub mod variants;
@@ -50,6 +50,92 @@ pub fn is_true_hdr(color_transfer: Option<&str>, color_primaries: Option<&str>)
     has_hdr_transfer || has_hdr_primaries
 }
 
+/// Dolby Vision signalling of a video stream, as stored from its DOVI
+/// configuration record.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DolbyVision {
+    pub profile: u8,
+    /// Base layer signal compatibility: 0 none, 1 HDR10, 2 SDR, 4 HLG, 6 HDR10 (Blu-ray).
+    pub bl_compat_id: u8,
+}
+
+impl DolbyVision {
+    /// Build from the stored columns; `None` for streams without Dolby Vision.
+    pub fn from_columns(profile: Option<i64>, bl_compat_id: Option<i64>) -> Option<Self> {
+        Some(Self {
+            profile: u8::try_from(profile?).ok()?,
+            bl_compat_id: bl_compat_id
+                .and_then(|id| u8::try_from(id).ok())
+                .unwrap_or(0),
+        })
+    }
+
+    /// Whether a decoder without Dolby Vision can show the base layer on its
+    /// own (HDR10, SDR or HLG), as with profiles 7 and 8.x. Profile 5 is
+    /// IPTPQc2 and only looks right after its RPU is applied or it is tone-mapped.
+    pub fn has_compatible_base_layer(&self) -> bool {
+        self.profile != 5 && matches!(self.bl_compat_id, 1 | 2 | 4 | 6)
+    }
+}
+
+/// The HDR flavour of a video stream, most specific first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HdrFormat {
+    DolbyVision,
+    Hdr10Plus,
+    Hdr10,
+    Hlg,
+}
+
+impl HdrFormat {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::DolbyVision => "dolby_vision",
+            Self::Hdr10Plus => "hdr10_plus",
+            Self::Hdr10 => "hdr10",
+            Self::Hlg => "hlg",
+        }
+    }
+}
+
+/// Classify a video stream. `None` for SDR.
+pub fn hdr_format(
+    color_transfer: Option<&str>,
+    dolby_vision: Option<DolbyVision>,
+    hdr10_plus: bool,
+) -> Option<HdrFormat> {
+    if dolby_vision.is_some() {
+        return Some(HdrFormat::DolbyVision);
+    }
+    if hdr10_plus {
+        return Some(HdrFormat::Hdr10Plus);
+    }
+    match color_transfer.map(str::to_lowercase).as_deref() {
+        Some("smpte2084") => Some(HdrFormat::Hdr10),
+        Some("arib-std-b67") => Some(HdrFormat::Hlg),
+        _ => None,
+    }
+}
+
+/// Transfer and primaries to tone-map from.
+///
+/// Dolby Vision without a compatible base layer is usually left untagged,
+/// which would otherwise skip tone-mapping altogether; it is treated as PQ in
+/// a BT.2020 container. That is only an approximation: profile 5 is IPTPQc2,
+/// and without its RPU reshaping (which `zscale` and `tonemap` don't apply)
+/// the result still has a green or purple cast, just a less severe one.
+/// Everything else keeps its own tags.
+pub fn effective_color<'a>(
+    color_transfer: Option<&'a str>,
+    color_primaries: Option<&'a str>,
+    dolby_vision: Option<DolbyVision>,
+) -> (Option<&'a str>, Option<&'a str>) {
+    match dolby_vision {
+        Some(dv) if !dv.has_compatible_base_layer() => (Some("smpte2084"), Some("bt2020")),
+        _ => (color_transfer, color_primaries),
+    }
+}
+
 /// Build the FFmpeg `-vf` filter string for tone-mapping HDR → SDR.
 ///
 /// Uses the `zscale` filter (from zimg library) for high-quality colorspace
@@ -69,6 +155,32 @@ pub fn tonemap_filter() -> String {
     "zscale=t=linear:npl=100,format=gbrpf32le,zscale=p=bt709,tonemap=hable:desat=0,zscale=t=bt709:m=bt709:r=tv,format=yuv420p".to_string()
 }
 
+/// [`tonemap_filter`] for a source with the given color metadata (see
+/// [`effective_color`]). The first `zscale` is told the input transfer,
+/// primaries and matrix instead of relying on the frame tags, which untagged
+/// sources such as Dolby Vision profile 5 don't carry. This doesn't apply the
+/// Dolby Vision RPU, so profile 5 colors stay off (see [`effective_color`]).
+pub fn tonemap_filter_for(color_transfer: Option<&str>, color_primaries: Option<&str>) -> String {
+    let mut input = Vec::new();
+    if let Some(transfer) = color_transfer
+        .map(str::to_lowercase)
+        .filter(|t| HDR_TRANSFERS.contains(&t.as_str()))
+    {
+        input.push(format!("tin={transfer}"));
+    }
+    if color_primaries.is_some_and(|p| p.eq_ignore_ascii_case("bt2020")) {
+        input.push("pin=bt2020".to_string());
+        input.push("min=bt2020nc".to_string());
+    }
+    if input.is_empty() {
+        return tonemap_filter();
+    }
+    format!(
+        "zscale={}:t=linear:npl=100,format=gbrpf32le,zscale=p=bt709,tonemap=hable:desat=0,zscale=t=bt709:m=bt709:r=tv,format=yuv420p",
+        input.join(":")
+    )
+}
+
 /// Build a simple fallback filter for when zscale is not available.
 /// This just converts pixel format without proper tone-mapping.
 pub fn simple_format_filter() -> String {
@@ -95,7 +207,7 @@ pub fn video_format_filter(
     match pixel_format {
         Some(fmt) if is_high_bit_depth(fmt) => {
             if is_true_hdr(color_transfer, color_primaries) {
-                Some(tonemap_filter())
+                Some(tonemap_filter_for(color_transfer, color_primaries))
             } else {
                 // 10-bit with standard BT.709 colors — just convert bit depth
                 Some(bit_depth_filter())
@@ -192,4 +304,67 @@ mod tests {
         assert!(video_format_filter(Some("yuv420p"), None, None).is_none());
         assert!(video_format_filter(None, None, None).is_none());
     }
+
+    #[test]
+    fn test_dolby_vision_base_layer_compatibility() {
+        let p5 = DolbyVision::from_columns(Some(5), Some(0)).unwrap();
+        let p81 = DolbyVision::from_columns(Some(8), Some(1)).unwrap();
+        let p84 = DolbyVision::from_columns(Some(8), Some(4)).unwrap();
+        let p7 = DolbyVision::from_columns(Some(7), Some(6)).unwrap();
+        assert!(!p5.has_compatible_base_layer());
+        assert!(p81.has_compatible_base_layer());
+        assert!(p84.has_compatible_base_layer());
+        assert!(p7.has_compatible_base_layer());
+        assert_eq!(DolbyVision::from_columns(None, Some(1)), None);
+    }
+
+    #[test]
+    fn test_dolby_vision_profile_5_always_tonemaps() {
+        let p5 = DolbyVision::from_columns(Some(5), Some(0));
+        // Profile 5 streams usually carry no color tags at all.
+        let (transfer, primaries) = effective_color(None, None, p5);
+        assert!(is_true_hdr(transfer, primaries));
+        let filter = video_format_filter(Some("yuv420p10le"), transfer, primaries).unwrap();
+        assert!(filter.starts_with("zscale=tin=smpte2084:pin=bt2020:min=bt2020nc:t=linear"));
+    }
+
+    #[test]
+    fn test_dolby_vision_8_1_keeps_its_hdr10_tags() {
+        let p81 = DolbyVision::from_columns(Some(8), Some(1));
+        assert_eq!(
+            effective_color(Some("smpte2084"), Some("bt2020"), p81),
+            (Some("smpte2084"), Some("bt2020"))
+        );
+        assert_eq!(
+            effective_color(Some("bt709"), Some("bt709"), None),
+            (Some("bt709"), Some("bt709"))
+        );
+    }
+
+    #[test]
+    fn test_hdr_format_prefers_dynamic_metadata() {
+        let p81 = DolbyVision::from_columns(Some(8), Some(1));
+        assert_eq!(
+            hdr_format(Some("smpte2084"), p81, true),
+            Some(HdrFormat::DolbyVision)
+        );
+        assert_eq!(
+            hdr_format(Some("smpte2084"), None, true),
+            Some(HdrFormat::Hdr10Plus)
+        );
+        assert_eq!(
+            hdr_format(Some("smpte2084"), None, false),
+            Some(HdrFormat::Hdr10)
+        );
+        assert_eq!(
+            hdr_format(Some("arib-std-b67"), None, false),
+            Some(HdrFormat::Hlg)
+        );
+        assert_eq!(hdr_format(Some("bt709"), None, false), None);
+    }
+
+    #[test]
+    fn test_tonemap_filter_for_untagged_source_is_plain() {
+        assert_eq!(tonemap_filter_for(None, None), tonemap_filter());
+    }
 }
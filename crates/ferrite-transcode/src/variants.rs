@@ -1,3 +1,4 @@
+use ferrite_core::config::{EncodingConfig, LadderRung};
 use serde::Serialize;
 
 /// A quality variant for adaptive bitrate HLS streaming.
@@ -18,80 +19,76 @@ pub struct QualityVariant {
     pub bandwidth_bps: u64,
 }
 
-/// Standard quality ladder for adaptive bitrate streaming.
+impl From<&LadderRung> for QualityVariant {
+    fn from(rung: &LadderRung) -> Self {
+        Self {
+            label: rung.label.clone(),
+            height: rung.height,
+            width: rung.width,
+            video_bitrate_kbps: rung.video_bitrate_kbps,
+            audio_bitrate_kbps: rung.audio_bitrate_kbps,
+            bandwidth_bps: rung.bandwidth_bps(),
+        }
+    }
+}
+
+/// Standard quality ladder for adaptive bitrate streaming (the default
+/// `[transcode.encoding]` ladder).
 /// Returns variants sorted from highest to lowest quality.
 pub fn standard_variants() -> Vec<QualityVariant> {
-    vec![
-        QualityVariant {
-            label: "2160p".into(),
-            height: 2160,
-            width: 3840,
-            video_bitrate_kbps: 14000,
-            audio_bitrate_kbps: 192,
-            bandwidth_bps: 15_000_000,
-        },
-        QualityVariant {
-            label: "1080p".into(),
-            height: 1080,
-            width: 1920,
-            video_bitrate_kbps: 5000,
-            audio_bitrate_kbps: 192,
-            bandwidth_bps: 5_500_000,
-        },
-        QualityVariant {
-            label: "720p".into(),
-            height: 720,
-            width: 1280,
-            video_bitrate_kbps: 2800,
-            audio_bitrate_kbps: 128,
-            bandwidth_bps: 3_100_000,
-        },
-        QualityVariant {
-            label: "480p".into(),
-            height: 480,
-            width: 854,
-            video_bitrate_kbps: 1400,
-            audio_bitrate_kbps: 128,
-            bandwidth_bps: 1_600_000,
-        },
-        QualityVariant {
-            label: "360p".into(),
-            height: 360,
-            width: 640,
-            video_bitrate_kbps: 800,
-            audio_bitrate_kbps: 96,
-            bandwidth_bps: 950_000,
-        },
-    ]
+    EncodingConfig::default()
+        .ladder
+        .iter()
+        .map(QualityVariant::from)
+        .collect()
+}
+
+/// Select variants from the default ladder. See [`select_variants_for`].
+pub fn select_variants(
+    source_width: Option<u32>,
+    source_height: Option<u32>,
+) -> Vec<QualityVariant> {
+    select_variants_for(&EncodingConfig::default(), source_width, source_height)
 }
 
 /// Select which quality variants to offer based on the source media dimensions.
 /// Rules:
+/// - Sources taller than `encoding.max_height` are treated as that height.
 /// - Never upscale: only include variants whose height ≤ source height.
 /// - Always include at least one variant (the closest to source resolution).
 /// - The highest variant uses the source's native resolution (no scaling).
-pub fn select_variants(
+pub fn select_variants_for(
+    encoding: &EncodingConfig,
     source_width: Option<u32>,
     source_height: Option<u32>,
 ) -> Vec<QualityVariant> {
-    let src_h = source_height.unwrap_or(1080);
-    let src_w = source_width.unwrap_or(1920);
+    let mut src_h = source_height.unwrap_or(1080);
+    let mut src_w = source_width.unwrap_or(1920);
+    if let Some(max_h) = encoding.max_height.filter(|&m| src_h > m) {
+        // Keep the aspect ratio, rounded to an even width for the encoder
+        src_w = ((src_w as u64 * max_h as u64 / src_h as u64) as u32 + 1) & !1;
+        src_h = max_h;
+    }
 
-    let all = standard_variants();
+    let all: Vec<QualityVariant> = encoding.ladder.iter().map(QualityVariant::from).collect();
+    let lowest = all.last().cloned();
 
     // Filter to variants that don't upscale
     let mut selected: Vec<QualityVariant> = all.into_iter().filter(|v| v.height <= src_h).collect();
 
-    // If source is smaller than all standard variants, use the smallest one
-    // but adjust its resolution to match the source
+    // If source is smaller than every rung, offer it at its own resolution
+    // with the lowest rung's bitrates
     if selected.is_empty() {
+        let (video_bitrate_kbps, audio_bitrate_kbps, bandwidth_bps) = lowest
+            .map(|v| (v.video_bitrate_kbps, v.audio_bitrate_kbps, v.bandwidth_bps))
+            .unwrap_or((800, 96, 950_000));
         selected.push(QualityVariant {
             label: format!("{}p", src_h),
             height: src_h,
             width: src_w,
-            video_bitrate_kbps: 800,
-            audio_bitrate_kbps: 96,
-            bandwidth_bps: 950_000,
+            video_bitrate_kbps,
+            audio_bitrate_kbps,
+            bandwidth_bps,
         });
         return selected;
     }
@@ -121,6 +118,28 @@ pub fn select_variants(
     selected
 }
 
+/// Drop variants whose total bitrate exceeds `max_bitrate_kbps` (video + audio).
+/// The lowest variant is always kept so a capped client can still play.
+/// `None` leaves the ladder unchanged.
+pub fn cap_variants(
+    variants: Vec<QualityVariant>,
+    max_bitrate_kbps: Option<u32>,
+) -> Vec<QualityVariant> {
+    let Some(cap) = max_bitrate_kbps else {
+        return variants;
+    };
+    let lowest = variants.last().cloned();
+    let capped: Vec<QualityVariant> = variants
+        .into_iter()
+        .filter(|v| v.video_bitrate_kbps + v.audio_bitrate_kbps <= cap)
+        .collect();
+    if capped.is_empty() {
+        lowest.into_iter().collect()
+    } else {
+        capped
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -184,4 +203,75 @@ mod tests {
         assert_eq!(variants.len(), 4);
         assert_eq!(variants[0].label, "1080p");
     }
+
+    #[test]
+    fn test_max_height_scales_down_tall_sources() {
+        let encoding = EncodingConfig {
+            max_height: Some(1080),
+            ..EncodingConfig::default()
+        };
+        let variants = select_variants_for(&encoding, Some(3840), Some(2160));
+        assert_eq!(variants.len(), 4);
+        assert_eq!(variants[0].label, "1080p");
+        assert_eq!(variants[0].width, 1920);
+
+        // 2.39:1 scope source keeps its aspect ratio
+        let variants = select_variants_for(&encoding, Some(3840), Some(1606));
+        assert_eq!(variants[0].height, 1080);
+        assert_eq!(variants[0].width, 2582);
+    }
+
+    #[test]
+    fn test_custom_ladder_is_used() {
+        let encoding = EncodingConfig {
+            ladder: vec![
+                LadderRung {
+                    label: "1080p-anime".into(),
+                    width: 1920,
+                    height: 1080,
+                    video_bitrate_kbps: 3000,
+                    audio_bitrate_kbps: 160,
+                    bandwidth_bps: None,
+                },
+                LadderRung {
+                    label: "540p".into(),
+                    width: 960,
+                    height: 540,
+                    video_bitrate_kbps: 900,
+                    audio_bitrate_kbps: 128,
+                    bandwidth_bps: None,
+                },
+            ],
+            ..EncodingConfig::default()
+        };
+        let variants = select_variants_for(&encoding, Some(1920), Some(1080));
+        let labels: Vec<&str> = variants.iter().map(|v| v.label.as_str()).collect();
+        assert_eq!(labels, vec!["1080p-anime", "540p"]);
+        assert_eq!(variants[0].bandwidth_bps, 3_349_600);
+
+        // Smaller than every rung: lowest rung's bitrates at native size
+        let variants = select_variants_for(&encoding, Some(640), Some(360));
+        assert_eq!(variants[0].label, "360p");
+        assert_eq!(variants[0].video_bitrate_kbps, 900);
+    }
+
+    #[test]
+    fn test_cap_removes_variants_above_bitrate() {
+        let variants = cap_variants(select_variants(Some(3840), Some(2160)), Some(3000));
+        let labels: Vec<&str> = variants.iter().map(|v| v.label.as_str()).collect();
+        assert_eq!(labels, vec!["720p", "480p", "360p"]);
+    }
+
+    #[test]
+    fn test_cap_keeps_lowest_variant_when_all_exceed() {
+        let variants = cap_variants(select_variants(Some(1920), Some(1080)), Some(100));
+        assert_eq!(variants.len(), 1);
+        assert_eq!(variants[0].label, "360p");
+    }
+
+    #[test]
+    fn test_no_cap_keeps_full_ladder() {
+        let variants = cap_variants(select_variants(Some(1920), Some(1080)), None);
+        assert_eq!(variants.len(), 4);
+    }
 }
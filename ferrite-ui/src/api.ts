@@ -60,7 +60,17 @@ export interface Library {
   id: string;
   name: string;
   path: string;
+  paths: string[];
+  ignore_patterns: string[];
   library_type: string;
+  scan_interval_minutes: number;
+}
+
+export interface LibraryUpdate {
+  name?: string;
+  paths?: string[];
+  ignore_patterns?: string[];
+  scan_interval_minutes?: number;
 }
 
 export interface MediaItem {
@@ -120,9 +130,20 @@ export interface MediaStream {
   frame_rate: string | null;
   pixel_format: string | null;
   bit_depth: number | null;
+  color_space: string | null;
+  color_transfer: string | null;
+  color_primaries: string | null;
+  /** Dolby Vision profile, level and base layer compatibility id; null without Dolby Vision. */
+  dv_profile: number | null;
+  dv_level: number | null;
+  dv_bl_compat_id: number | null;
+  dv_rpu_present: number;
+  dv_el_present: number;
+  hdr10_plus: number;
   channels: number | null;
   channel_layout: string | null;
   sample_rate: number | null;
+  spatial_audio: 'atmos' | 'dts_x' | null;
   bitrate_bps: number | null;
 }
 
@@ -134,6 +155,8 @@ export interface UserPreferences {
 
 export interface ActiveStream {
   session_id: string;
+  playback_session_id: string | null;
+  user_id: string | null;
   media_id: string;
   variant_label: string | null;
   start_secs: number;
@@ -145,6 +168,51 @@ export interface ActiveStream {
   age_secs: number;
 }
 
+export interface TerminationNotice {
+  reason: string | null;
+}
+
+export interface StreamTermination {
+  id: number;
+  admin_user_id: string | null;
+  admin_username: string | null;
+  target_user_id: string | null;
+  target_username: string | null;
+  media_id: string | null;
+  playback_session_id: string | null;
+  scope: 'session' | 'user' | 'transcode';
+  reason: string | null;
+  created_at: string;
+}
+
+export interface TranscodeSlot {
+  id: string;
+  user_id: string | null;
+  username: string | null;
+  media_id: string;
+  operation: string;
+  priority: 'normal' | 'high';
+  encodes: number;
+  hls: boolean;
+  age_secs: number;
+}
+
+export interface QueuedTranscode {
+  username: string | null;
+  media_id: string;
+  operation: string;
+  priority: 'normal' | 'high';
+  encodes: number;
+  waiting_secs: number;
+}
+
+export interface TranscodeSchedulerState {
+  capacity: number;
+  used: number;
+  slots: TranscodeSlot[];
+  queue: QueuedTranscode[];
+}
+
 export interface Chapter {
   id: number;
   media_item_id: string;
@@ -154,6 +222,26 @@ export interface Chapter {
   end_time_ms: number;
 }
 
+export type ExtraType =
+  | 'trailer'
+  | 'featurette'
+  | 'behind_the_scenes'
+  | 'deleted_scene'
+  | 'interview'
+  | 'scene'
+  | 'short'
+  | 'other';
+
+export interface Extra {
+  id: string;
+  title: string | null;
+  extra_type: ExtraType;
+  duration_ms: number | null;
+  width: number | null;
+  height: number | null;
+  added_at: string;
+}
+
 export interface ExternalSubtitle {
   id: number;
   media_item_id: string;
@@ -193,10 +281,23 @@ export interface TvShow {
   backdrop_path: string | null;
   genres: string | null;
   fetched_at: string | null;
+  /** TMDB episode group absolute episode numbers are mapped through */
+  episode_group_id: string | null;
+  /** When the last TMDB search found no match */
+  match_failed_at: string | null;
   season_count: number;
   episode_count: number;
 }
 
+export interface EpisodeGroup {
+  id: string;
+  name: string;
+  /** TMDB group type: 1 air date, 2 absolute, 3 DVD, 4 digital, 5 story arc, 6 production, 7 TV */
+  group_type: number;
+  episode_count: number;
+  group_count: number;
+}
+
 export interface Season {
   id: string;
   tv_show_id: string;
@@ -211,6 +312,8 @@ export interface Episode {
   media_item_id: string;
   season_id: string;
   episode_number: number;
+  /** Position within a multi-episode file (0 for its first episode) */
+  file_position: number;
   episode_title: string | null;
   overview: string | null;
   air_date: string | null;
@@ -229,7 +332,7 @@ export interface Episode {
 
 export interface ScanProgress {
   scanning: boolean;
-  status: 'scanning' | 'enriching' | 'subtitles' | 'complete' | 'failed';
+  status: 'scanning' | 'enriching' | 'subtitles' | 'complete' | 'failed' | 'cancelled';
   total_files: number;
   files_probed: number;
   files_inserted: number;
@@ -237,12 +340,226 @@ export interface ScanProgress {
   items_enriched: number;
   errors: number;
   current_item: string;
+  paused: boolean;
+  cancelling: boolean;
   elapsed_seconds: number;
   phase_elapsed_seconds: number;
   estimated_remaining_seconds: number | null;
   percent: number;
 }
 
+export interface LibraryChange {
+  library_id: string;
+  indexed: number;
+  relocated: number;
+  removed: number;
+  full_rescan: boolean;
+}
+
+export interface JobProgress {
+  kind: 'download';
+  id: string;
+  user_id: string | null;
+  media_id: string;
+  status: 'queued' | 'transcoding' | 'ready' | 'failed' | 'removed';
+  progress_pct: number;
+  error: string | null;
+}
+
+/** Live admin event pushed on /api/events */
+export type ServerEvent =
+  | { type: 'scan_progress'; library_id: string; progress: ScanProgress }
+  | ({ type: 'library_changed' } & LibraryChange)
+  | { type: 'sessions'; sessions: ActiveStream[]; count: number; transcodes: TranscodeSchedulerState }
+  | ({ type: 'job' } & JobProgress);
+
+/**
+ * Connect to the admin event stream, reconnecting with backoff until the
+ * returned function is called. `onStatus` reports whether the socket is open.
+ */
+export function subscribeEvents(
+  onEvent: (event: ServerEvent) => void,
+  onStatus?: (connected: boolean) => void,
+): () => void {
+  let socket: WebSocket | null = null;
+  let retryTimer: ReturnType<typeof setTimeout> | null = null;
+  let retryDelay = 1000;
+  let closed = false;
+
+  const connect = () => {
+    const proto = location.protocol === 'https:' ? 'wss:' : 'ws:';
+    socket = new WebSocket(authUrl(`${proto}//${location.host}/api/events`));
+    socket.onopen = () => {
+      retryDelay = 1000;
+      onStatus?.(true);
+    };
+    socket.onmessage = (msg) => {
+      try {
+        onEvent(JSON.parse(msg.data) as ServerEvent);
+      } catch { /* ignore malformed */ }
+    };
+    socket.onclose = () => {
+      onStatus?.(false);
+      if (closed) return;
+      retryTimer = setTimeout(connect, retryDelay);
+      retryDelay = Math.min(retryDelay * 2, 30000);
+    };
+  };
+
+  connect();
+  return () => {
+    closed = true;
+    if (retryTimer) clearTimeout(retryTimer);
+    socket?.close();
+  };
+}
+
+export type ParsedFilename =
+  | { kind: 'movie'; value: { title: string; year: number | null } }
+  | {
+      kind: 'episode';
+      value: {
+        show_name: string;
+        season: number;
+        episode: number;
+        last_episode: number | null;
+        air_date: string | null;
+        special_title: string | null;
+        absolute: number | null;
+      };
+    }
+  | { kind: 'unknown'; value: string };
+
+export interface DryRunFile {
+  path: string;
+  size: number;
+  status: 'new' | 'changed' | 'unchanged';
+  parsed: ParsedFilename;
+  extra_type: ExtraType | null;
+  title: string;
+  year: number | null;
+  probed: boolean;
+  probe_error: string | null;
+  candidate: { tmdb_id: number; title: string; year: number | null } | null;
+}
+
+export interface DryRunReport {
+  library_id: string;
+  files: DryRunFile[];
+  /** Indexed files not found on disk */
+  missing: string[];
+}
+
+export interface UnmatchedItem {
+  reason: 'unparsed' | 'no_match' | 'unmatched';
+  library_id: string;
+  media_item_id: string | null;
+  tv_show_id: string | null;
+  file_path: string | null;
+  title: string | null;
+  since: string | null;
+}
+
+export type HealthStatus = 'ok' | 'damaged' | 'broken';
+export type IntegrityMode = 'sample' | 'full';
+
+export interface MediaIntegrity {
+  media_item_id: string;
+  status: HealthStatus;
+  mode: IntegrityMode;
+  error_count: number;
+  /** Distinct problems found, one per line */
+  error_summary: string | null;
+  decoded_ms: number;
+  file_size: number;
+  checked_at: string;
+}
+
+export interface BrokenMedia {
+  media_item_id: string;
+  library_id: string;
+  media_type: string;
+  file_path: string;
+  title: string | null;
+  duration_ms: number | null;
+  status: Exclude<HealthStatus, 'ok'>;
+  mode: IntegrityMode;
+  error_count: number;
+  error_summary: string | null;
+  decoded_ms: number;
+  checked_at: string;
+}
+
+export interface IntegrityStatus {
+  counts: Partial<Record<HealthStatus, number>>;
+  scheduled: boolean;
+  mode: IntegrityMode;
+  interval_hours: number;
+  pass: {
+    library_id: string | null;
+    mode: IntegrityMode;
+    total: number;
+    checked: number;
+    damaged: number;
+    broken: number;
+  } | null;
+}
+
+export interface FormatTally {
+  count: number;
+  size: number;
+}
+
+export interface StrategyCounts {
+  direct_play: number;
+  remux: number;
+  audio_transcode: number;
+  full_transcode: number;
+  full_transcode_size: number;
+}
+
+export interface LibraryTotals {
+  library_id: string;
+  name: string;
+  library_type: string;
+  /** Files, counting every version and stack part; extras are excluded */
+  file_count: number;
+  total_size: number;
+  total_duration_ms: number;
+}
+
+export interface LargeFile {
+  media_item_id: string;
+  library_id: string;
+  media_type: string;
+  file_path: string;
+  title: string | null;
+  file_size: number;
+  duration_ms: number | null;
+  bitrate_kbps: number | null;
+  video_codec: string | null;
+  height: number | null;
+}
+
+export interface LibraryStats {
+  libraries: LibraryTotals[];
+  formats: {
+    file_count: number;
+    total_size: number;
+    total_duration_ms: number;
+    resolutions: Record<string, FormatTally>;
+    video_codecs: Record<string, FormatTally>;
+    audio_codecs: Record<string, FormatTally>;
+    containers: Record<string, FormatTally>;
+    hdr: Record<string, FormatTally>;
+    bitrates: { min_kbps: number; max_kbps: number | null; count: number; size: number }[];
+    bitrate_unknown: FormatTally;
+    /** Keyed by client profile, e.g. `web-chrome` or `tvos` */
+    strategies: Record<string, StrategyCounts>;
+  };
+  largest_files: LargeFile[];
+}
+
 export interface AuthStatus {
   auth_required: boolean;
   has_users: boolean;
@@ -324,9 +641,37 @@ export const api = {
   listLibraries: () => apiFetch<Library[]>('GET', '/api/libraries'),
   createLibrary: (name: string, path: string, library_type: string) =>
     apiFetch<Library>('POST', '/api/libraries', { name, path, library_type }),
+  updateLibrary: (id: string, update: LibraryUpdate) =>
+    apiFetch<Library>('PUT', `/api/libraries/${id}`, update),
   deleteLibrary: (id: string) => apiFetch<void>('DELETE', `/api/libraries/${id}`),
   scanLibrary: (id: string) => apiFetch<void>('POST', `/api/libraries/${id}/scan`),
   scanStatus: (id: string) => apiFetch<ScanProgress>('GET', `/api/libraries/${id}/scan/status`),
+  cancelScan: (id: string) => apiFetch<ScanProgress>('POST', `/api/libraries/${id}/scan/cancel`),
+  pauseScan: (id: string) => apiFetch<ScanProgress>('POST', `/api/libraries/${id}/scan/pause`),
+  resumeScan: (id: string) => apiFetch<ScanProgress>('POST', `/api/libraries/${id}/scan/resume`),
+  dryRunScan: (id: string, probeAll = false) =>
+    apiFetch<DryRunReport>('POST', `/api/admin/libraries/${id}/scan/dry-run${probeAll ? '?probe=all' : ''}`),
+  listUnmatched: (libraryId?: string) =>
+    apiFetch<UnmatchedItem[]>('GET', `/api/admin/unmatched${libraryId ? `?library_id=${libraryId}` : ''}`),
+  libraryStats: (libraryId?: string, largest?: number) => {
+    const qs = new URLSearchParams();
+    if (libraryId) qs.set('library_id', libraryId);
+    if (largest) qs.set('largest', String(largest));
+    return apiFetch<LibraryStats>('GET', `/api/admin/stats?${qs}`);
+  },
+  integrityStatus: () => apiFetch<IntegrityStatus>('GET', '/api/admin/integrity'),
+  listBrokenMedia: (libraryId?: string) =>
+    apiFetch<BrokenMedia[]>('GET', `/api/admin/integrity/broken${libraryId ? `?library_id=${libraryId}` : ''}`),
+  startIntegrityCheck: (opts: { libraryId?: string; mode?: IntegrityMode; all?: boolean } = {}) => {
+    const qs = new URLSearchParams();
+    if (opts.libraryId) qs.set('library_id', opts.libraryId);
+    if (opts.mode) qs.set('mode', opts.mode);
+    if (opts.all) qs.set('all', 'true');
+    return apiFetch<{ queued: number; mode: IntegrityMode }>('POST', `/api/admin/integrity/check?${qs}`);
+  },
+  getMediaIntegrity: (id: string) => apiFetch<MediaIntegrity>('GET', `/api/media/${id}/integrity`),
+  checkMediaIntegrity: (id: string, mode?: IntegrityMode) =>
+    apiFetch<MediaIntegrity>('POST', `/api/media/${id}/integrity${mode ? `?mode=${mode}` : ''}`),
 
   // Media
   listMedia: (params?: Record<string, string>) => {
@@ -337,7 +682,30 @@ export const api = {
   getStreams: (id: string) => apiFetch<MediaStream[]>('GET', `/api/media/${id}/streams`),
   listSubtitles: (id: string) => apiFetch<ExternalSubtitle[]>('GET', `/api/media/${id}/subtitles`),
   listChapters: (id: string) => apiFetch<Chapter[]>('GET', `/api/media/${id}/chapters`),
-  listActiveStreams: () => apiFetch<{ sessions: ActiveStream[]; count: number }>('GET', '/api/admin/streams'),
+  listExtras: (id: string) => apiFetch<Extra[]>('GET', `/api/media/${id}/extras`),
+  listActiveStreams: () =>
+    apiFetch<{ sessions: ActiveStream[]; count: number; transcodes: TranscodeSchedulerState }>(
+      'GET',
+      '/api/admin/streams',
+    ),
+  terminateTranscode: (id: string, reason?: string) =>
+    apiFetch<{ status: string }>(
+      'DELETE',
+      `/api/admin/transcodes/${id}${reason ? `?reason=${encodeURIComponent(reason)}` : ''}`,
+    ),
+  terminateStream: (sessionId: string, reason?: string) =>
+    apiFetch<{ status: string }>('POST', `/api/admin/streams/${sessionId}/terminate`, { reason }),
+  terminateUserStreams: (userId: string, reason?: string) =>
+    apiFetch<{ status: string; stopped: number }>(
+      'POST',
+      `/api/admin/users/${userId}/streams/terminate`,
+      { reason },
+    ),
+  listStreamTerminations: (limit = 50, offset = 0) =>
+    apiFetch<{ terminations: StreamTermination[] }>(
+      'GET',
+      `/api/admin/streams/terminations?limit=${limit}&offset=${offset}`,
+    ),
   getPreferences: () => apiFetch<UserPreferences>('GET', '/api/preferences'),
   setPreferences: (prefs: Partial<UserPreferences>) =>
     apiFetch<void>('PUT', '/api/preferences', { preferences: prefs }),
@@ -364,11 +732,20 @@ export const api = {
       `/api/stream/${id}/hls/session/start${suffix ? `?${suffix}` : ''}`,
     );
   },
-  hlsSessionHeartbeat: (id: string, playbackSessionId: string) =>
-    apiQuiet(
-      'POST',
-      `/api/stream/${id}/hls/session/heartbeat?playback_session_id=${encodeURIComponent(playbackSessionId)}`,
-    ),
+  /** Resolves to a termination notice if an admin stopped the playback. */
+  hlsSessionHeartbeat: async (id: string, playbackSessionId: string): Promise<TerminationNotice | null> => {
+    try {
+      const res = await fetch(
+        `/api/stream/${id}/hls/session/heartbeat?playback_session_id=${encodeURIComponent(playbackSessionId)}`,
+        { method: 'POST', headers: authHeaders(), keepalive: true },
+      );
+      if (res.status !== 410) return null;
+      const body = await res.json();
+      return { reason: body.reason ?? null };
+    } catch {
+      return null;
+    }
+  },
   hlsSessionStop: (id: string, playbackSessionId: string) =>
     apiQuiet(
       'DELETE',
@@ -387,6 +764,7 @@ export const api = {
       variant_count: number;
       reused: boolean;
       video_copied?: boolean;
+      absolute_pts?: boolean;
       timing_ms?: Record<string, number>;
     }>(
       'POST', `/api/stream/${id}/hls/seek?${params}`
@@ -405,6 +783,9 @@ export const api = {
   listShows: (libraryId: string) => apiFetch<TvShow[]>('GET', `/api/shows?library_id=${libraryId}`),
   getShow: (id: string) => apiFetch<TvShow>('GET', `/api/shows/${id}`),
   listSeasons: (showId: string) => apiFetch<Season[]>('GET', `/api/shows/${showId}/seasons`),
+  listEpisodeGroups: (showId: string) => apiFetch<EpisodeGroup[]>('GET', `/api/shows/${showId}/episode-groups`),
+  setEpisodeGroup: (showId: string, episodeGroupId: string | null) =>
+    apiFetch<TvShow>('PUT', `/api/shows/${showId}/episode-group`, { episode_group_id: episodeGroupId }),
   listEpisodes: (seasonId: string) => apiFetch<Episode[]>('GET', `/api/seasons/${seasonId}/episodes`),
 
   setupStatus: () => apiFetch<{ has_users: boolean }>('GET', '/api/users/setup'),
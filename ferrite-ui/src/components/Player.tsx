@@ -119,6 +119,7 @@ export default function Player(props: PlayerProps) {
   const [nextEpisode, setNextEpisode] = createSignal<NextEpisode | null>(null);
   const [upNextVisible, setUpNextVisible] = createSignal(false);
   const [upNextCountdown, setUpNextCountdown] = createSignal(15);
+  const [stoppedMessage, setStoppedMessage] = createSignal<string | null>(null);
   let upNextTimer: ReturnType<typeof setInterval> | null = null;
   let upNextStarted = false; // plain flag — safe to read in non-reactive contexts
   let upNextNavigating = false; // set when navigation to next episode is in flight
@@ -275,11 +276,25 @@ export default function Player(props: PlayerProps) {
     stopHlsHeartbeat();
     if (!playbackSessionId) return;
 
-    api.hlsSessionHeartbeat(mediaId, playbackSessionId);
-    hlsHeartbeatTimer = setInterval(() => {
+    const beat = async () => {
       if (!playbackSessionId) return;
-      api.hlsSessionHeartbeat(mediaId, playbackSessionId);
-    }, 15000);
+      const notice = await api.hlsSessionHeartbeat(mediaId, playbackSessionId);
+      if (notice) onPlaybackTerminated(notice.reason);
+    };
+    beat();
+    hlsHeartbeatTimer = setInterval(beat, 15000);
+  }
+
+  /** An admin stopped this playback: tear down locally and show their reason. */
+  function onPlaybackTerminated(reason: string | null) {
+    stopHlsHeartbeat();
+    // The server already dropped the sessions; don't send a stop for them
+    playbackSessionId = null;
+    hlsSessionId = null;
+    destroyHlsLocal();
+    videoRef.pause();
+    setBuffering(false);
+    setStoppedMessage(reason ?? 'Playback was stopped by the server administrator.');
   }
 
   async function initializeHlsLifecycle(mediaId: string, startAt: number): Promise<string> {
@@ -470,10 +485,12 @@ export default function Player(props: PlayerProps) {
         if (xhr) {
           const ids = xhr.getResponseHeader('x-hls-session-ids');
           if (ids) hlsSessionId = ids.split(',')[0];
-          // In video copy mode, fMP4 segments retain original PTS so
-          // videoRef.currentTime is already absolute — hlsStartOffset must be 0.
-          const videoCopied = xhr.getResponseHeader('x-hls-video-copied') === '1';
-          if (videoCopied) {
+          // In video copy mode (and for segment-cached sessions), fMP4 segments
+          // carry absolute PTS so videoRef.currentTime is already absolute —
+          // hlsStartOffset must be 0.
+          const absolutePts = xhr.getResponseHeader('x-hls-video-copied') === '1'
+            || xhr.getResponseHeader('x-hls-absolute-pts') === '1';
+          if (absolutePts) {
             hlsStartOffset = 0;
           } else {
             const startHdr = xhr.getResponseHeader('x-hls-requested-start') ?? xhr.getResponseHeader('x-hls-start-secs');
@@ -983,7 +1000,7 @@ export default function Player(props: PlayerProps) {
       // However, if the video is copied, fmp4 segments retain their original PTS
       // and videoRef.currentTime is ALREADY absolute. In this case, we set
       // hlsStartOffset to 0 so we don't double-count the time offset.
-      hlsStartOffset = seekRes.video_copied ? 0 : (seekRes.requested_start ?? seekRes.start_secs ?? targetTime);
+      hlsStartOffset = seekRes.video_copied || seekRes.absolute_pts ? 0 : (seekRes.requested_start ?? seekRes.start_secs ?? targetTime);
       hlsSessionId = seekRes.session_id;
       isHls = true;
 
@@ -1015,10 +1032,12 @@ export default function Player(props: PlayerProps) {
           const ids = xhr.getResponseHeader('x-hls-session-ids');
           if (ids) hlsSessionId = ids.split(',')[0];
 
-          // In video copy mode, fMP4 segments retain original PTS so
-          // videoRef.currentTime is already absolute — hlsStartOffset must be 0.
-          const videoCopied = xhr.getResponseHeader('x-hls-video-copied') === '1';
-          if (videoCopied) {
+          // In video copy mode (and for segment-cached sessions), fMP4 segments
+          // carry absolute PTS so videoRef.currentTime is already absolute —
+          // hlsStartOffset must be 0.
+          const absolutePts = xhr.getResponseHeader('x-hls-video-copied') === '1'
+            || xhr.getResponseHeader('x-hls-absolute-pts') === '1';
+          if (absolutePts) {
             hlsStartOffset = 0;
           } else {
             const startHdr = xhr.getResponseHeader('x-hls-requested-start') ?? xhr.getResponseHeader('x-hls-start-secs');
@@ -1451,6 +1470,15 @@ export default function Player(props: PlayerProps) {
       </Show>
 
       {/* Seek indicator (e.g. "+10s") */}
+      <Show when={stoppedMessage()}>
+        <div class="absolute inset-0 flex items-center justify-center z-30 bg-black/70">
+          <div class="max-w-md px-6 py-4 rounded-2xl bg-black/80 text-white text-center">
+            <p class="text-lg font-semibold mb-1">Playback stopped</p>
+            <p class="text-sm text-white/80">{stoppedMessage()}</p>
+          </div>
+        </div>
+      </Show>
+
       <Show when={seekIndicator()}>
         <div class="absolute inset-0 flex items-center justify-center pointer-events-none z-10">
           <div class="px-6 py-3 rounded-2xl bg-black/60 backdrop-blur-sm text-white text-2xl font-bold animate-fade-in">
@@ -1769,6 +1797,8 @@ export default function Player(props: PlayerProps) {
                               if (track.language && track.title) parts.push(`(${track.language.toUpperCase()})`);
                               if (track.channels) parts.push(`${track.channels}ch`);
                               if (track.codec_name) parts.push(track.codec_name.toUpperCase());
+                              if (track.spatial_audio === 'atmos') parts.push('Atmos');
+                              else if (track.spatial_audio === 'dts_x') parts.push('DTS:X');
                               return parts.join(' · ');
                             };
                             return (
@@ -1,6 +1,6 @@
 import { createSignal, onMount, onCleanup, For, Show } from 'solid-js';
 import { Activity, Monitor, Clock, Wifi, WifiOff, RefreshCw } from 'lucide-solid';
-import { api } from '../api';
+import { api, subscribeEvents } from '../api';
 import type { ActiveStream } from '../api';
 import { fmtTime } from '../utils';
 
@@ -24,7 +24,8 @@ export default function AdminPage() {
   const [loading, setLoading] = createSignal(true);
   const [error, setError] = createSignal<string | null>(null);
   const [lastRefresh, setLastRefresh] = createSignal<Date | null>(null);
-  let intervalId: ReturnType<typeof setInterval> | null = null;
+  const [live, setLive] = createSignal(false);
+  let unsubscribe: (() => void) | null = null;
 
   async function refresh() {
     try {
@@ -41,11 +42,17 @@ export default function AdminPage() {
 
   onMount(() => {
     refresh();
-    intervalId = setInterval(refresh, 5000);
+    unsubscribe = subscribeEvents((event) => {
+      if (event.type !== 'sessions') return;
+      setStreams(event.sessions);
+      setLastRefresh(new Date());
+      setError(null);
+      setLoading(false);
+    }, setLive);
   });
 
   onCleanup(() => {
-    if (intervalId) clearInterval(intervalId);
+    unsubscribe?.();
   });
 
   return (
@@ -58,7 +65,7 @@ export default function AdminPage() {
           </div>
           <div>
             <h1 class="text-xl font-bold text-white">Activity</h1>
-            <p class="text-xs text-surface-700 mt-0.5">Active transcode sessions · refreshes every 5s</p>
+            <p class="text-xs text-surface-700 mt-0.5">Active transcode sessions · {live() ? 'live' : 'reconnecting…'}</p>
           </div>
         </div>
         <button
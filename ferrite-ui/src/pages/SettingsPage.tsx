@@ -1,8 +1,8 @@
 import { createSignal, For, Show, onMount, onCleanup, createEffect } from 'solid-js';
 import type { ScanProgress, UpdateCheckResult, UpdateProgress, UpdateHistoryEntry } from '../api';
-import { Settings, FolderPlus, Trash2, RefreshCw, Server, HardDrive, Users, UserPlus, KeyRound, ShieldCheck, Shield, Sliders, Download, ExternalLink, RotateCcw, AlertTriangle, History } from 'lucide-solid';
+import { Settings, FolderPlus, Trash2, RefreshCw, Server, HardDrive, Users, UserPlus, KeyRound, ShieldCheck, Shield, Sliders, Download, ExternalLink, RotateCcw, AlertTriangle, History, Pause, Play, X } from 'lucide-solid';
 import { libraries, loadLibraries, addLibrary, deleteLibrary, refreshAll, scanning, statusMessage } from '../stores/media';
-import { api } from '../api';
+import { api, subscribeEvents } from '../api';
 import type { User, UserPreferences } from '../api';
 
 export default function SettingsPage() {
@@ -25,6 +25,8 @@ export default function SettingsPage() {
   const [updateHistoryList, setUpdateHistoryList] = createSignal<UpdateHistoryEntry[]>([]);
   let pollInterval: ReturnType<typeof setInterval> | null = null;
   let updatePollInterval: ReturnType<typeof setInterval> | null = null;
+  // Admins get scan progress pushed; everyone else polls.
+  let unsubscribeEvents: (() => void) | null = null;
 
   async function pollScanStatus() {
     const libs = libraries();
@@ -45,7 +47,7 @@ export default function SettingsPage() {
   }
 
   function startPolling() {
-    if (pollInterval) return;
+    if (pollInterval || unsubscribeEvents) return;
     pollInterval = setInterval(pollScanStatus, 2000);
   }
 
@@ -62,6 +64,19 @@ export default function SettingsPage() {
     }
   }
 
+  async function controlScan(libId: string, action: 'pause' | 'resume' | 'cancel') {
+    try {
+      const p = action === 'pause'
+        ? await api.pauseScan(libId)
+        : action === 'resume'
+          ? await api.resumeScan(libId)
+          : await api.cancelScan(libId);
+      setScanProgress(prev => ({ ...prev, [libId]: p }));
+    } catch (err: any) {
+      alert(err.message || `Failed to ${action} scan`);
+    }
+  }
+
   onMount(async () => {
     if (libraries().length === 0) await loadLibraries();
     try {
@@ -92,11 +107,19 @@ export default function SettingsPage() {
     }
     // Check if any scans are already running
     await pollScanStatus();
-    const anyActive = Object.values(scanProgress()).some(p => p.scanning);
-    if (anyActive) startPolling();
+    if (currentUser()?.is_admin === 1) {
+      unsubscribeEvents = subscribeEvents((event) => {
+        if (event.type !== 'scan_progress') return;
+        setScanProgress(prev => ({ ...prev, [event.library_id]: event.progress }));
+      });
+    } else {
+      const anyActive = Object.values(scanProgress()).some(p => p.scanning);
+      if (anyActive) startPolling();
+    }
   });
 
   onCleanup(() => {
+    unsubscribeEvents?.();
     if (pollInterval) clearInterval(pollInterval);
     if (updatePollInterval) clearInterval(updatePollInterval);
   });
@@ -497,6 +520,22 @@ export default function SettingsPage() {
                         <RefreshCw class={`w-3.5 h-3.5 ${isActive() || isTriggeringThis() ? 'animate-spin' : ''}`} />
                         {isActive() ? 'Scanning…' : 'Scan'}
                       </button>
+                      <Show when={isActive() && currentUser()?.is_admin === 1 && !progress()?.cancelling}>
+                        <button
+                          class="btn-icon text-surface-600 hover:text-white"
+                          onClick={() => controlScan(lib.id, progress()?.paused ? 'resume' : 'pause')}
+                          title={progress()?.paused ? 'Resume scan' : 'Pause scan'}
+                        >
+                          {progress()?.paused ? <Play class="w-4 h-4" /> : <Pause class="w-4 h-4" />}
+                        </button>
+                        <button
+                          class="btn-icon text-surface-600 hover:text-red-400"
+                          onClick={() => controlScan(lib.id, 'cancel')}
+                          title="Cancel scan"
+                        >
+                          <X class="w-4 h-4" />
+                        </button>
+                      </Show>
                       <button
                         class="btn-icon text-surface-600 hover:text-red-400 opacity-0 group-hover:opacity-100 transition-all"
                         onClick={async () => {
@@ -511,7 +550,9 @@ export default function SettingsPage() {
                   <Show when={isActive()}>
                     <div class="mt-3 space-y-1.5">
                       <div class="flex items-center justify-between text-xs text-surface-600">
-                        <span class="truncate max-w-xs">{progress()?.current_item || 'Scanning…'}</span>
+                        <span class="truncate max-w-xs">
+                          {progress()?.cancelling ? 'Cancelling…' : progress()?.paused ? 'Paused' : progress()?.current_item || 'Scanning…'}
+                        </span>
                         <span class="ml-2 flex-shrink-0">
                           {progress()?.files_probed ?? 0} / {progress()?.total_files ?? 0}
                           {' '}({progress()?.percent ?? 0}%)
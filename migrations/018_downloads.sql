@@ -0,0 +1,33 @@
+-- Offline downloads: complete single-file MP4 transcodes for mobile sync.
+-- Rows are queued by the API, picked up by the download worker, and the
+-- finished file lives in the managed download cache until it expires or is
+-- evicted by the quota.
+
+CREATE TABLE IF NOT EXISTS downloads (
+    id               TEXT PRIMARY KEY,
+    user_id          TEXT REFERENCES users(id) ON DELETE CASCADE,
+    media_id         TEXT NOT NULL REFERENCES media_items(id) ON DELETE CASCADE,
+    -- Quality variant label from the ABR ladder (e.g. '720p')
+    variant_label    TEXT NOT NULL,
+    -- Audio stream index (0-based within audio streams)
+    audio_stream     INTEGER NOT NULL DEFAULT 0,
+    -- Comma-separated external_subtitles ids muxed as soft (mov_text) tracks
+    subtitle_ids     TEXT NOT NULL DEFAULT '',
+    -- 'queued', 'transcoding', 'ready', 'failed'
+    status           TEXT NOT NULL DEFAULT 'queued'
+                     CHECK(status IN ('queued', 'transcoding', 'ready', 'failed')),
+    progress_pct     INTEGER NOT NULL DEFAULT 0,
+    file_path        TEXT,
+    file_size        INTEGER,
+    -- Estimated output size, used for quota admission before transcoding
+    estimated_size   INTEGER NOT NULL DEFAULT 0,
+    error            TEXT,
+    created_at       TEXT NOT NULL DEFAULT (datetime('now')),
+    completed_at     TEXT,
+    expires_at       TEXT,
+    last_accessed_at TEXT
+);
+
+CREATE INDEX IF NOT EXISTS idx_downloads_user ON downloads(user_id, created_at DESC);
+CREATE INDEX IF NOT EXISTS idx_downloads_status ON downloads(status, created_at);
+CREATE INDEX IF NOT EXISTS idx_downloads_media ON downloads(media_id);
@@ -0,0 +1,9 @@
+-- Per-user streaming limits.
+-- NULL means "use the server default" from the [network] config section;
+-- 0 means unlimited for that user.
+
+-- Maximum total bitrate (kbps) offered to this user when streaming remotely
+ALTER TABLE users ADD COLUMN remote_max_bitrate_kbps INTEGER;
+
+-- Maximum number of concurrent playback sessions for this user
+ALTER TABLE users ADD COLUMN max_concurrent_streams INTEGER;
@@ -0,0 +1,56 @@
+-- Live TV: channels imported from an M3U tuner playlist, programme guide
+-- imported from XMLTV, and DVR recordings scheduled against them.
+
+CREATE TABLE IF NOT EXISTS livetv_channels (
+    id          TEXT PRIMARY KEY,
+    -- Stable key across playlist refreshes: tvg-id when present, else the stream URL
+    source_key  TEXT NOT NULL UNIQUE,
+    name        TEXT NOT NULL,
+    number      TEXT,
+    logo_url    TEXT,
+    group_title TEXT,
+    stream_url  TEXT NOT NULL,
+    -- XMLTV channel id used to join programmes (the M3U tvg-id)
+    xmltv_id    TEXT,
+    updated_at  TEXT NOT NULL DEFAULT (datetime('now'))
+);
+
+CREATE INDEX IF NOT EXISTS idx_livetv_channels_xmltv ON livetv_channels(xmltv_id);
+
+CREATE TABLE IF NOT EXISTS livetv_programs (
+    id          INTEGER PRIMARY KEY AUTOINCREMENT,
+    xmltv_id    TEXT NOT NULL,
+    -- UTC, 'YYYY-MM-DD HH:MM:SS'
+    start_time  TEXT NOT NULL,
+    end_time    TEXT NOT NULL,
+    title       TEXT NOT NULL,
+    subtitle    TEXT,
+    description TEXT,
+    category    TEXT,
+    episode_num TEXT
+);
+
+CREATE INDEX IF NOT EXISTS idx_livetv_programs_channel_time
+    ON livetv_programs(xmltv_id, start_time);
+CREATE INDEX IF NOT EXISTS idx_livetv_programs_time ON livetv_programs(start_time, end_time);
+
+CREATE TABLE IF NOT EXISTS livetv_recordings (
+    id           TEXT PRIMARY KEY,
+    user_id      TEXT REFERENCES users(id) ON DELETE CASCADE,
+    channel_id   TEXT REFERENCES livetv_channels(id) ON DELETE SET NULL,
+    -- Copied from the channel so recordings survive channel removal
+    channel_name TEXT NOT NULL,
+    stream_url   TEXT NOT NULL,
+    title        TEXT NOT NULL,
+    -- UTC, 'YYYY-MM-DD HH:MM:SS'
+    start_time   TEXT NOT NULL,
+    end_time     TEXT NOT NULL,
+    status       TEXT NOT NULL DEFAULT 'scheduled'
+                 CHECK(status IN ('scheduled', 'recording', 'completed', 'failed', 'cancelled')),
+    file_path    TEXT,
+    error        TEXT,
+    created_at   TEXT NOT NULL DEFAULT (datetime('now'))
+);
+
+CREATE INDEX IF NOT EXISTS idx_livetv_recordings_status ON livetv_recordings(status, start_time);
+CREATE INDEX IF NOT EXISTS idx_livetv_recordings_user ON livetv_recordings(user_id, start_time DESC);
@@ -0,0 +1,20 @@
+-- Multi-version and multi-part movies.
+-- Files that parse to the same title + year in a movie library are grouped
+-- under one logical movie. The scanner recomputes these columns after each
+-- scan; NULL everywhere means the file stands on its own.
+
+-- media_item id of the group's primary version (the one shown in listings
+-- and enriched with metadata). Equal to id for the primary itself.
+ALTER TABLE media_items ADD COLUMN movie_group_id TEXT;
+
+-- Edition / quality label parsed from the filename, e.g. "2160p" or "Director's Cut"
+ALTER TABLE media_items ADD COLUMN version_label TEXT;
+
+-- Part number for stacked files (cd1, part2, disc3, ...)
+ALTER TABLE media_items ADD COLUMN part_number INTEGER;
+
+-- media_item id of the first part of the stack this file continues
+ALTER TABLE media_items ADD COLUMN stack_head_id TEXT;
+
+CREATE INDEX IF NOT EXISTS idx_media_items_movie_group ON media_items(movie_group_id);
+CREATE INDEX IF NOT EXISTS idx_media_items_stack_head ON media_items(stack_head_id);
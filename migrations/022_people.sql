@@ -0,0 +1,34 @@
+-- Cast and crew from metadata providers.
+-- `people` holds one row per provider person; `media_people` links a person
+-- to a movie (by media item) or a TV show with the role they had on it.
+
+CREATE TABLE IF NOT EXISTS people (
+    id           TEXT PRIMARY KEY,
+    tmdb_id      INTEGER UNIQUE,
+    name         TEXT NOT NULL,
+    -- Local filename in the image cache
+    profile_path TEXT,
+    updated_at   TEXT NOT NULL DEFAULT (datetime('now'))
+);
+
+CREATE INDEX IF NOT EXISTS idx_people_name ON people(name);
+
+CREATE TABLE IF NOT EXISTS media_people (
+    id            INTEGER PRIMARY KEY AUTOINCREMENT,
+    person_id     TEXT NOT NULL REFERENCES people(id) ON DELETE CASCADE,
+    media_item_id TEXT REFERENCES media_items(id) ON DELETE CASCADE,
+    tv_show_id    TEXT REFERENCES tv_shows(id) ON DELETE CASCADE,
+    -- 'cast' or 'crew'
+    credit_type   TEXT NOT NULL,
+    -- Crew job, e.g. 'Director'
+    role          TEXT,
+    department    TEXT,
+    -- Character played (cast only)
+    character     TEXT,
+    sort_order    INTEGER NOT NULL DEFAULT 0,
+    CHECK ((media_item_id IS NULL) <> (tv_show_id IS NULL))
+);
+
+CREATE INDEX IF NOT EXISTS idx_media_people_person ON media_people(person_id);
+CREATE INDEX IF NOT EXISTS idx_media_people_media ON media_people(media_item_id);
+CREATE INDEX IF NOT EXISTS idx_media_people_show ON media_people(tv_show_id);
@@ -0,0 +1,26 @@
+-- Translated metadata for the extra languages in [metadata].languages.
+-- The primary language stays in movies / tv_shows; these tables hold one row
+-- per title and additional language. NULL fields fall back to the primary.
+
+CREATE TABLE IF NOT EXISTS movie_translations (
+    media_item_id TEXT NOT NULL REFERENCES media_items(id) ON DELETE CASCADE,
+    -- BCP 47 tag as configured, e.g. 'es-MX'
+    language      TEXT NOT NULL,
+    title         TEXT,
+    overview      TEXT,
+    tagline       TEXT,
+    -- Local filename in the image cache
+    poster_path   TEXT,
+    fetched_at    TEXT NOT NULL DEFAULT (datetime('now')),
+    PRIMARY KEY (media_item_id, language)
+);
+
+CREATE TABLE IF NOT EXISTS tv_show_translations (
+    tv_show_id    TEXT NOT NULL REFERENCES tv_shows(id) ON DELETE CASCADE,
+    language      TEXT NOT NULL,
+    title         TEXT,
+    overview      TEXT,
+    poster_path   TEXT,
+    fetched_at    TEXT NOT NULL DEFAULT (datetime('now')),
+    PRIMARY KEY (tv_show_id, language)
+);
@@ -0,0 +1,47 @@
+-- Smart collections: a 'smart' kind whose members are computed from a saved
+-- rule set (JSON in `rules`) every time the collection is listed.
+-- SQLite cannot alter a CHECK constraint, so both tables are rebuilt.
+-- collection_items is moved aside first so dropping `collections` does not
+-- cascade-delete its rows.
+
+CREATE TABLE collection_items_backup AS SELECT * FROM collection_items;
+DROP TABLE collection_items;
+
+CREATE TABLE collections_v2 (
+    id          TEXT PRIMARY KEY,
+    user_id     TEXT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    name        TEXT NOT NULL,
+    description TEXT NOT NULL DEFAULT '',
+    -- 'collection' = unordered set, 'playlist' = ordered list,
+    -- 'smart' = rule-driven, no stored items
+    kind        TEXT NOT NULL DEFAULT 'collection'
+                CHECK(kind IN ('collection', 'playlist', 'smart')),
+    -- JSON rule set, only for kind = 'smart'
+    rules       TEXT,
+    created_at  TEXT NOT NULL DEFAULT (datetime('now')),
+    updated_at  TEXT NOT NULL DEFAULT (datetime('now'))
+);
+
+INSERT INTO collections_v2 (id, user_id, name, description, kind, created_at, updated_at)
+SELECT id, user_id, name, description, kind, created_at, updated_at FROM collections;
+
+DROP TABLE collections;
+ALTER TABLE collections_v2 RENAME TO collections;
+
+CREATE TABLE collection_items (
+    id            TEXT PRIMARY KEY,
+    collection_id TEXT NOT NULL REFERENCES collections(id) ON DELETE CASCADE,
+    media_id      TEXT NOT NULL REFERENCES media_items(id) ON DELETE CASCADE,
+    -- Position for ordered playlists (0-based). NULL for unordered collections.
+    position      INTEGER,
+    added_at      TEXT NOT NULL DEFAULT (datetime('now')),
+    UNIQUE(collection_id, media_id)
+);
+
+INSERT INTO collection_items (id, collection_id, media_id, position, added_at)
+SELECT id, collection_id, media_id, position, added_at FROM collection_items_backup;
+DROP TABLE collection_items_backup;
+
+CREATE INDEX IF NOT EXISTS idx_collections_user ON collections(user_id);
+CREATE INDEX IF NOT EXISTS idx_collection_items_collection ON collection_items(collection_id, position);
+CREATE INDEX IF NOT EXISTS idx_collection_items_media ON collection_items(media_id);
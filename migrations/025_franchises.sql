@@ -0,0 +1,18 @@
+-- Server-wide movie franchises from TMDB `belongs_to_collection`.
+-- Membership lives on the movie row, so a franchise follows its movies as
+-- they are enriched, re-matched or removed; franchises without any movie
+-- left are pruned after enrichment.
+
+CREATE TABLE IF NOT EXISTS franchises (
+    id            TEXT PRIMARY KEY,
+    tmdb_id       INTEGER NOT NULL UNIQUE,
+    name          TEXT NOT NULL,
+    -- Local filenames in the image cache
+    poster_path   TEXT,
+    backdrop_path TEXT,
+    updated_at    TEXT NOT NULL DEFAULT (datetime('now'))
+);
+
+ALTER TABLE movies ADD COLUMN franchise_id TEXT REFERENCES franchises(id) ON DELETE SET NULL;
+
+CREATE INDEX IF NOT EXISTS idx_movies_franchise ON movies(franchise_id);
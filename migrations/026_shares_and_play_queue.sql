@@ -0,0 +1,39 @@
+-- Sharing collections/playlists with other users, and a per-user server-side
+-- play queue that follows the user across clients.
+
+CREATE TABLE IF NOT EXISTS collection_shares (
+    collection_id TEXT NOT NULL REFERENCES collections(id) ON DELETE CASCADE,
+    user_id       TEXT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    -- 1 = may add, remove and reorder items; 0 = read-only
+    can_edit      INTEGER NOT NULL DEFAULT 0,
+    created_at    TEXT NOT NULL DEFAULT (datetime('now')),
+    PRIMARY KEY (collection_id, user_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_collection_shares_user ON collection_shares(user_id);
+
+CREATE TABLE IF NOT EXISTS play_queues (
+    user_id       TEXT PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+    -- Collection the queue was started from, if any
+    source_collection_id TEXT REFERENCES collections(id) ON DELETE SET NULL,
+    -- Position (in play_queue_items.position) of the item now playing
+    current_position INTEGER NOT NULL DEFAULT 0,
+    shuffle       INTEGER NOT NULL DEFAULT 0,
+    -- 'off', 'all' (wrap around) or 'one' (repeat the current item)
+    repeat_mode   TEXT NOT NULL DEFAULT 'off' CHECK(repeat_mode IN ('off', 'all', 'one')),
+    updated_at    TEXT NOT NULL DEFAULT (datetime('now'))
+);
+
+-- Rewritten as a whole on every queue change. Positions are contiguous when
+-- written; removing media can leave gaps, which readers tolerate.
+CREATE TABLE IF NOT EXISTS play_queue_items (
+    id                INTEGER PRIMARY KEY AUTOINCREMENT,
+    user_id           TEXT NOT NULL REFERENCES play_queues(user_id) ON DELETE CASCADE,
+    -- Play order (0-based)
+    position          INTEGER NOT NULL,
+    -- Order before shuffling, restored when shuffle is turned off
+    original_position INTEGER NOT NULL,
+    media_id          TEXT NOT NULL REFERENCES media_items(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_play_queue_items_user ON play_queue_items(user_id, position);
@@ -0,0 +1,20 @@
+-- Audit log of playback sessions stopped by an administrator.
+
+CREATE TABLE IF NOT EXISTS stream_terminations (
+    id                  INTEGER PRIMARY KEY AUTOINCREMENT,
+    -- NULL when auth is disabled; the username survives the user's deletion
+    admin_user_id       TEXT REFERENCES users(id) ON DELETE SET NULL,
+    admin_username      TEXT,
+    target_user_id      TEXT REFERENCES users(id) ON DELETE SET NULL,
+    target_username     TEXT,
+    -- No foreign key: live TV playback uses synthetic media ids
+    media_id            TEXT,
+    playback_session_id TEXT,
+    -- 'session' (one playback), 'user' (all of a user's playbacks) or
+    -- 'transcode' (a progressive transcode)
+    scope               TEXT NOT NULL CHECK(scope IN ('session', 'user', 'transcode')),
+    reason              TEXT,
+    created_at          TEXT NOT NULL DEFAULT (datetime('now'))
+);
+
+CREATE INDEX IF NOT EXISTS idx_stream_terminations_created ON stream_terminations(created_at);
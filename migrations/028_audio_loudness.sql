@@ -0,0 +1,17 @@
+-- Cached EBU R128 loudness analysis per audio stream, used for two-pass
+-- loudness normalization in transcodes.
+
+CREATE TABLE IF NOT EXISTS audio_loudness (
+    media_item_id      TEXT NOT NULL REFERENCES media_items(id) ON DELETE CASCADE,
+    -- 0-based index among the item's audio streams
+    audio_stream_index INTEGER NOT NULL,
+    input_i            REAL NOT NULL,
+    input_tp           REAL NOT NULL,
+    input_lra          REAL NOT NULL,
+    input_thresh       REAL NOT NULL,
+    target_offset      REAL NOT NULL,
+    -- File size at analysis time; a replaced file is re-analyzed
+    file_size          INTEGER NOT NULL,
+    analyzed_at        TEXT NOT NULL DEFAULT (datetime('now')),
+    PRIMARY KEY (media_item_id, audio_stream_index)
+);
@@ -0,0 +1,12 @@
+-- Libraries spanning several root folders, and per-library exclusion rules.
+-- `libraries.path` stays the primary root; additional roots live here.
+
+CREATE TABLE IF NOT EXISTS library_folders (
+    library_id TEXT NOT NULL REFERENCES libraries(id) ON DELETE CASCADE,
+    path       TEXT NOT NULL UNIQUE,
+    position   INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY (library_id, path)
+);
+
+-- Newline-separated glob patterns, same syntax as a `.ferriteignore` file
+ALTER TABLE libraries ADD COLUMN ignore_patterns TEXT NOT NULL DEFAULT '';
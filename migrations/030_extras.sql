@@ -0,0 +1,11 @@
+-- Extras (trailers, featurettes, deleted scenes, ...) are indexed media items
+-- that belong to a movie or show instead of standing on their own. The parent
+-- links are resolved by the scanner after each scan and cleared when the
+-- parent goes away.
+
+ALTER TABLE media_items ADD COLUMN extra_type TEXT;
+ALTER TABLE media_items ADD COLUMN extra_parent_media_id TEXT REFERENCES media_items(id) ON DELETE SET NULL;
+ALTER TABLE media_items ADD COLUMN extra_parent_show_id TEXT REFERENCES tv_shows(id) ON DELETE SET NULL;
+
+CREATE INDEX IF NOT EXISTS idx_media_items_extra_parent_media ON media_items(extra_parent_media_id);
+CREATE INDEX IF NOT EXISTS idx_media_items_extra_parent_show ON media_items(extra_parent_show_id);
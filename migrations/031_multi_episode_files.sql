@@ -0,0 +1,98 @@
+-- One file can hold several episodes (`S01E05E06`), so episodes are keyed by
+-- season and number instead of by media item. `file_position` orders the
+-- episodes within their file; position 0 is the one shown for the file.
+-- `needs_match` flags date-based episodes and title-only specials whose
+-- season/episode numbers are provisional until enrichment resolves them.
+
+DROP TRIGGER IF EXISTS trg_episodes_fts_ai;
+DROP TRIGGER IF EXISTS trg_episodes_fts_au;
+DROP TRIGGER IF EXISTS trg_episodes_fts_ad;
+DROP TRIGGER IF EXISTS trg_tv_shows_fts_au;
+
+CREATE TABLE episodes_new (
+    media_item_id  TEXT NOT NULL REFERENCES media_items(id) ON DELETE CASCADE,
+    season_id      TEXT NOT NULL REFERENCES seasons(id) ON DELETE CASCADE,
+    episode_number INTEGER NOT NULL,
+    file_position  INTEGER NOT NULL DEFAULT 0,
+    needs_match    INTEGER NOT NULL DEFAULT 0,
+    title          TEXT,
+    overview       TEXT,
+    air_date       TEXT,
+    still_path     TEXT,
+    PRIMARY KEY (season_id, episode_number)
+);
+
+INSERT INTO episodes_new (media_item_id, season_id, episode_number, title, overview, air_date, still_path)
+SELECT media_item_id, season_id, episode_number, title, overview, air_date, still_path
+FROM episodes;
+
+DROP TABLE episodes;
+ALTER TABLE episodes_new RENAME TO episodes;
+
+CREATE INDEX IF NOT EXISTS idx_episodes_season ON episodes(season_id);
+CREATE INDEX IF NOT EXISTS idx_episodes_media_item ON episodes(media_item_id, file_position);
+
+-- Search indexes a file under its first episode
+CREATE TRIGGER trg_episodes_fts_ai
+AFTER INSERT ON episodes
+WHEN NEW.file_position = 0
+BEGIN
+    DELETE FROM media_fts WHERE media_item_id = NEW.media_item_id;
+    INSERT INTO media_fts (media_item_id, title, overview, genres)
+    SELECT
+        NEW.media_item_id,
+        COALESCE(ts.title, mi.title, '') || ' ' || COALESCE(NEW.title, ''),
+        COALESCE(NEW.overview, ts.overview, ''),
+        COALESCE(ts.genres, '')
+    FROM media_items mi
+    JOIN seasons s ON s.id = NEW.season_id
+    JOIN tv_shows ts ON ts.id = s.tv_show_id
+    WHERE mi.id = NEW.media_item_id;
+END;
+
+CREATE TRIGGER trg_episodes_fts_au
+AFTER UPDATE ON episodes
+WHEN NEW.file_position = 0
+BEGIN
+    DELETE FROM media_fts WHERE media_item_id = NEW.media_item_id;
+    INSERT INTO media_fts (media_item_id, title, overview, genres)
+    SELECT
+        NEW.media_item_id,
+        COALESCE(ts.title, mi.title, '') || ' ' || COALESCE(NEW.title, ''),
+        COALESCE(NEW.overview, ts.overview, ''),
+        COALESCE(ts.genres, '')
+    FROM media_items mi
+    JOIN seasons s ON s.id = NEW.season_id
+    JOIN tv_shows ts ON ts.id = s.tv_show_id
+    WHERE mi.id = NEW.media_item_id;
+END;
+
+CREATE TRIGGER trg_episodes_fts_ad
+AFTER DELETE ON episodes
+WHEN OLD.file_position = 0
+BEGIN
+    DELETE FROM media_fts WHERE media_item_id = OLD.media_item_id;
+END;
+
+CREATE TRIGGER trg_tv_shows_fts_au
+AFTER UPDATE OF title, overview, genres ON tv_shows
+BEGIN
+    DELETE FROM media_fts
+    WHERE media_item_id IN (
+        SELECT e.media_item_id
+        FROM episodes e
+        JOIN seasons s ON s.id = e.season_id
+        WHERE s.tv_show_id = NEW.id
+    );
+
+    INSERT INTO media_fts (media_item_id, title, overview, genres)
+    SELECT
+        e.media_item_id,
+        COALESCE(NEW.title, mi.title, '') || ' ' || COALESCE(e.title, ''),
+        COALESCE(e.overview, NEW.overview, ''),
+        COALESCE(NEW.genres, '')
+    FROM episodes e
+    JOIN seasons s ON s.id = e.season_id
+    JOIN media_items mi ON mi.id = e.media_item_id
+    WHERE s.tv_show_id = NEW.id AND e.file_position = 0;
+END;
@@ -0,0 +1,8 @@
+-- Anime releases are often numbered absolutely (`Show - 105`). The scanner
+-- keeps that number on the episode and files it provisionally as season 1;
+-- enrichment then maps it onto the provider's seasons, either through the
+-- show's season episode counts or through a TMDB episode group picked per
+-- show. The absolute number is kept so a changed ordering can be re-applied.
+
+ALTER TABLE episodes ADD COLUMN absolute_number INTEGER;
+ALTER TABLE tv_shows ADD COLUMN episode_group_id TEXT;
@@ -0,0 +1,36 @@
+-- Persist failed TMDB searches so titles that never match can be listed
+-- instead of only showing up in the logs. A later successful match sets
+-- tmdb_id and takes the title off the list.
+
+ALTER TABLE movies ADD COLUMN match_failed_at TEXT;
+ALTER TABLE tv_shows ADD COLUMN match_failed_at TEXT;
+
+-- Everything a scan indexed but couldn't identify:
+--   unparsed    TV library files not recognised as an episode
+--   no_match    movies and shows TMDB had no match for
+--   unmatched   date-based episodes, specials and absolute episodes still
+--               waiting for the provider's numbering
+CREATE VIEW IF NOT EXISTS unmatched_items AS
+SELECT 'unparsed' AS reason, mi.library_id, mi.id AS media_item_id,
+       NULL AS tv_show_id, mi.file_path, mi.title, mi.added_at AS since
+FROM media_items mi
+JOIN libraries l ON l.id = mi.library_id
+WHERE l.library_type = 'tv'
+  AND mi.extra_type IS NULL
+  AND NOT EXISTS (SELECT 1 FROM episodes e WHERE e.media_item_id = mi.id)
+UNION ALL
+SELECT 'no_match', mi.library_id, mi.id, NULL, mi.file_path, m.title, m.match_failed_at
+FROM movies m
+JOIN media_items mi ON mi.id = m.media_item_id
+WHERE m.tmdb_id IS NULL AND m.match_failed_at IS NOT NULL
+UNION ALL
+SELECT 'no_match', ts.library_id, NULL, ts.id, NULL, ts.title, ts.match_failed_at
+FROM tv_shows ts
+WHERE ts.tmdb_id IS NULL AND ts.match_failed_at IS NOT NULL
+UNION ALL
+SELECT 'unmatched', mi.library_id, mi.id, s.tv_show_id, mi.file_path,
+       COALESCE(e.title, mi.title), mi.added_at
+FROM episodes e
+JOIN seasons s ON s.id = e.season_id
+JOIN media_items mi ON mi.id = e.media_item_id
+WHERE e.needs_match = 1 AND e.file_position = 0;
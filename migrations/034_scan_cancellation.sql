@@ -0,0 +1,3 @@
+-- Files indexed by a scan that was cancelled before their subtitles were
+-- extracted. The next scan probes them again as if they had changed.
+ALTER TABLE media_items ADD COLUMN subtitles_pending INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,28 @@
+-- Dolby Vision, HDR10+ and object-based audio, read from ffprobe side data
+-- and stream profiles. dv_* columns come from the DOVI configuration record
+-- and are NULL for streams without Dolby Vision.
+ALTER TABLE media_streams ADD COLUMN dv_profile INTEGER;
+ALTER TABLE media_streams ADD COLUMN dv_level INTEGER;
+ALTER TABLE media_streams ADD COLUMN dv_bl_compat_id INTEGER;
+ALTER TABLE media_streams ADD COLUMN dv_rpu_present INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE media_streams ADD COLUMN dv_el_present INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE media_streams ADD COLUMN hdr10_plus INTEGER NOT NULL DEFAULT 0;
+-- 'atmos' or 'dts_x'
+ALTER TABLE media_streams ADD COLUMN spatial_audio TEXT;
+
+-- Set on files whose streams should be probed again by the next full scan
+-- without re-indexing them (the item, its subtitles and its metadata are
+-- kept; only media_streams is refreshed).
+ALTER TABLE media_items ADD COLUMN needs_reprobe INTEGER NOT NULL DEFAULT 0;
+
+-- Files probed before these columns existed could carry any of them when
+-- they have PQ or 10-bit HEVC/AV1 video, or TrueHD, E-AC-3 or DTS audio.
+UPDATE media_items SET needs_reprobe = 1
+WHERE id IN (
+    SELECT media_item_id FROM media_streams
+    WHERE (stream_type = 'video'
+           AND (color_transfer = 'smpte2084'
+                OR (codec_name IN ('hevc', 'av1') AND bit_depth >= 10)
+                OR (codec_name IN ('hevc', 'av1') AND pixel_format LIKE '%10%')))
+       OR (stream_type = 'audio' AND codec_name IN ('truehd', 'eac3', 'dts'))
+);
@@ -0,0 +1,20 @@
+-- Latest integrity check of each media file: a decode with FFmpeg's error
+-- detection turned up, compared against the probed duration.
+
+CREATE TABLE IF NOT EXISTS media_integrity (
+    media_item_id TEXT PRIMARY KEY REFERENCES media_items(id) ON DELETE CASCADE,
+    -- ok, damaged (decodes with errors) or broken (no duration, fails to
+    -- decode or ends before its reported duration)
+    status        TEXT NOT NULL,
+    -- sample or full
+    mode          TEXT NOT NULL,
+    error_count   INTEGER NOT NULL DEFAULT 0,
+    -- Distinct problems found, one per line
+    error_summary TEXT,
+    decoded_ms    INTEGER NOT NULL DEFAULT 0,
+    -- File size at check time; a replaced file is checked again
+    file_size     INTEGER NOT NULL,
+    checked_at    TEXT NOT NULL DEFAULT (datetime('now'))
+);
+
+CREATE INDEX IF NOT EXISTS idx_media_integrity_status ON media_integrity(status);